@@ -0,0 +1,96 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    benches/decode.rs
+
+    Benchmark decoding of flux streams into bitstreams, and of bitstreams
+    into data.
+*/
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use fluxfox::{io::Cursor, prelude::*, test_corpus, types::DecodePolicy};
+use std::path::{Path, PathBuf};
+
+/// Flux images from the test suite, with multiple revolutions per track.
+const FLUX_IMAGES: &[&str] = &["sector_test_360k.scp", "sector_test_360k.pfi", "sector_test_360k.mfi"];
+
+/// The length of an encoded MFM byte, in bitcells.
+const MFM_BYTE_LEN: usize = 16;
+
+fn image_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("images")
+        .join("sector_test")
+        .join(name)
+}
+
+/// Decode every track of a flux image. The image is loaded lazily outside the measurement, so
+/// that only the decoding of flux into bitstreams is measured, without parsing the file.
+fn bench_decode_flux(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_flux");
+    group.sample_size(10);
+    for name in FLUX_IMAGES {
+        let path = image_path(name);
+        let data = std::fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+        let options = ParserReadOptions::default().with_decode_policy(DecodePolicy::Lazy);
+
+        group.bench_with_input(BenchmarkId::from_parameter(name), &data, |b, data| {
+            b.iter_batched(
+                || DiskImage::load_with_options(&mut Cursor::new(data), Some(&path), None, &options, None).unwrap(),
+                |mut disk| {
+                    disk.decode_all_tracks();
+                    disk
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Decode the whole bitstream of every track of an MFM disk into bytes.
+fn bench_decode_bitstream(c: &mut Criterion) {
+    let disk = test_corpus::source_image().unwrap();
+    let streams: Vec<_> = disk.iter_tracks().filter_map(|(_, track)| track.stream()).collect();
+    // Stop a byte short of the end of each track, so that no read wraps past the index.
+    let byte_cts: Vec<usize> = streams.iter().map(|s| s.len() / MFM_BYTE_LEN - 1).collect();
+
+    let mut group = c.benchmark_group("decode_bitstream");
+    group.throughput(Throughput::Bytes(byte_cts.iter().sum::<usize>() as u64));
+    group.bench_function("mfm", |b| {
+        b.iter(|| {
+            for (stream, &byte_ct) in streams.iter().zip(&byte_cts) {
+                let mut buf = vec![0; byte_ct];
+                stream.read_decoded_buf(&mut buf, 0);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode_flux, bench_decode_bitstream);
+criterion_main!(benches);
@@ -0,0 +1,86 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    benches/load.rs
+
+    Benchmark the time taken to load a disk image in each file format.
+*/
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use fluxfox::{io::Cursor, prelude::*, test_corpus::TestCorpus, types::DecodePolicy};
+use std::path::{Path, PathBuf};
+
+/// Flux images from the test suite. The test corpus writes flux formats with a single synthetic
+/// revolution, so these measure decoding of real, multi-revolution flux.
+const FLUX_IMAGES: &[&str] = &["sector_test_360k.scp", "sector_test_360k.pfi", "sector_test_360k.mfi"];
+
+fn image_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("images")
+        .join("sector_test")
+        .join(name)
+}
+
+/// Load the test corpus image of every writable format from memory.
+fn bench_load_corpus(c: &mut Criterion) {
+    let corpus = TestCorpus::generate().expect("Failed to generate test corpus");
+
+    let mut group = c.benchmark_group("load_corpus");
+    group.sample_size(20);
+    for image in &corpus.images {
+        let path = PathBuf::from(&image.file_name);
+        group.throughput(Throughput::Bytes(image.data.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(image.format), &image.data, |b, data| {
+            b.iter(|| DiskImage::load(&mut Cursor::new(data), Some(&path), None, None).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Load flux images, both decoding every track up front and deferring decoding.
+fn bench_load_flux(c: &mut Criterion) {
+    let mut group = c.benchmark_group("load_flux");
+    group.sample_size(10);
+    for name in FLUX_IMAGES {
+        let path = image_path(name);
+        let data = std::fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+        group.throughput(Throughput::Bytes(data.len() as u64));
+
+        for policy in [DecodePolicy::Eager, DecodePolicy::Lazy] {
+            let options = ParserReadOptions::default().with_decode_policy(policy);
+            group.bench_with_input(BenchmarkId::new(format!("{:?}", policy), name), &data, |b, data| {
+                b.iter(|| {
+                    DiskImage::load_with_options(&mut Cursor::new(data), Some(&path), None, &options, None).unwrap()
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_load_corpus, bench_load_flux);
+criterion_main!(benches);
@@ -0,0 +1,108 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    benches/read.rs
+
+    Benchmark sector read throughput and bitstream metadata parsing.
+*/
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use fluxfox::{
+    io::Cursor,
+    prelude::*,
+    test_corpus::{self, CORPUS_FORMAT},
+};
+use std::path::Path;
+
+/// Load the IMD image from the test suite, which produces a `MetaSector` resolution disk.
+fn metasector_image() -> DiskImage {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/images/sector_test/sector_test_360k.imd");
+    let data = std::fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+    DiskImage::load(&mut Cursor::new(data), Some(&path), None, None).unwrap()
+}
+
+/// Read every sector of a disk image, with each of the sector read methods.
+fn bench_read_sector(c: &mut Criterion) {
+    let disks = [
+        ("BitStream", test_corpus::source_image().unwrap()),
+        ("MetaSector", metasector_image()),
+    ];
+
+    let mut group = c.benchmark_group("read_sector");
+    for (resolution, disk) in &disks {
+        let ids: Vec<DiskChsn> = disk
+            .sector_map()
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|s| s.chsn)
+            .collect();
+        let bytes = ids.iter().map(|id| id.n_size() as u64).sum();
+        group.throughput(Throughput::Bytes(bytes));
+
+        group.bench_with_input(BenchmarkId::new("read_sector", resolution), &ids, |b, ids| {
+            b.iter(|| {
+                for id in ids {
+                    disk.read_sector(id.ch(), DiskChsnQuery::from(*id), None, None, RwScope::DataOnly, false)
+                        .unwrap();
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("read_sector_basic", resolution), &ids, |b, ids| {
+            b.iter(|| {
+                for id in ids {
+                    disk.read_sector_basic(id.ch(), DiskChsnQuery::from(*id), None).unwrap();
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("read_sector_ref", resolution), &ids, |b, ids| {
+            b.iter(|| {
+                for id in ids {
+                    disk.read_sector_ref(id.ch(), DiskChsnQuery::from(*id), None).unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Rescan the markers and metadata of a single bitstream track.
+fn bench_parse_metadata(c: &mut Criterion) {
+    let mut disk = test_corpus::source_image().unwrap();
+    let ch = DiskCh::new(0, 0);
+    let track = disk
+        .track_mut(ch)
+        .and_then(|track| track.as_bitstream_track_mut())
+        .expect("Track 0 should be a bitstream track");
+
+    let mut group = c.benchmark_group("parse_metadata");
+    group.throughput(Throughput::Elements(CORPUS_FORMAT.layout().s() as u64));
+    group.bench_function("rescan", |b| b.iter(|| track.rescan(None).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_read_sector, bench_parse_metadata);
+criterion_main!(benches);
@@ -0,0 +1,71 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    benches/visualization.rs
+
+    Benchmark the vectorization of disk data and metadata for visualization.
+*/
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fluxfox::{test_corpus, visualization::prelude::*};
+
+fn bench_vectorize(c: &mut Criterion) {
+    let disk = test_corpus::source_image().unwrap();
+    let common_params = CommonVizParams::default();
+
+    let mut group = c.benchmark_group("vectorize");
+    group.sample_size(20);
+
+    for decode in [false, true] {
+        let data_params = RenderTrackDataParams {
+            decode,
+            ..Default::default()
+        };
+        let vector_params = RenderVectorizationParams {
+            view_box: VizRect::from_tuple((0.0, 0.0), (1.0, 1.0)),
+            image_bg_color: None,
+            disk_bg_color: None,
+            mask_color: None,
+            pos_offset: None,
+        };
+        let name = if decode { "decoded" } else { "encoded" };
+        group.bench_with_input(BenchmarkId::new("data", name), &data_params, |b, data_params| {
+            b.iter(|| vectorize_disk_data(&disk, &common_params, data_params, &vector_params).unwrap())
+        });
+    }
+
+    let metadata_params = RenderTrackMetadataParams {
+        geometry: RenderGeometry::Sector,
+        ..Default::default()
+    };
+    group.bench_function("metadata", |b| {
+        b.iter(|| vectorize_disk_elements_by_quadrants(&disk, &common_params, &metadata_params).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_vectorize);
+criterion_main!(benches);
@@ -27,7 +27,7 @@
 
 use egui::Layout;
 use fluxfox::{
-    file_system::{fat::fat_fs::FatFileSystem, FileSystemArchive},
+    file_system::{fat::fat_fs::FatFileSystem, FileSystem, FileSystemArchive},
     DiskImage,
     DiskImageError,
     LoadingStatus,
@@ -66,19 +66,30 @@ use crate::wasm::worker;
 pub const APP_NAME: &str = "fluxfox-web";
 
 use crate::{
+    view_state::{ViewState, ViewTool},
     widgets::{filename::FilenameWidget, hello::HelloWidget},
     windows::{
         disk_visualization::VisualizationViewer,
         element_map::ElementMapViewer,
         file_viewer::FileViewer,
+        image_analysis::ImageAnalysisViewer,
+        image_metadata::ImageMetadataViewer,
         new_viz::NewVizViewer,
+        save_as::SaveAsDialog,
         sector_viewer::SectorViewer,
         source_map::SourceMapViewer,
+        track_metadata::TrackMetadataViewer,
         track_timing_viewer::TrackTimingViewer,
         track_viewer::TrackViewer,
     },
 };
-use fluxfox_egui::{controls::track_list::TrackListWidget, tracking_lock::TrackingLock};
+use fluxfox_egui::{
+    controls::{
+        track_grid::{TrackGridWidget, TrackMetric},
+        track_list::TrackListWidget,
+    },
+    tracking_lock::TrackingLock,
+};
 
 pub const DEMO_IMAGE: &[u8] = include_bytes!("../../../resources/demo.imz");
 /// The number of selection slots available for disk images.
@@ -107,7 +118,7 @@ pub enum Tool {
     SectorViewer,
     TrackViewer,
     TrackListViewer,
-    /// The filesystem viewer is currently the only tool that requires a write lock, due to
+    /// The filesystem viewer requires a write lock, due to
     /// the use of a StandardSectorView, which requires a mutable reference to the disk image.
     /// StandardSectorView is used as an interface for reading and writing sectors in a standard
     /// raw-sector based order, such as what is expected by rust-fatfs.
@@ -117,6 +128,8 @@ pub enum Tool {
     SourceMap,
     TrackElementMap,
     TrackTimingViewer,
+    TrackMetadataViewer,
+    ImageMetadata,
 }
 
 impl Display for Tool {
@@ -132,6 +145,11 @@ pub enum ThreadLoadStatus {
     Loading(f64),
     Success(DiskImage, usize),
     Error(DiskImageError),
+    /// Download progress of an image requested by URL.
+    Downloading(f64),
+    /// A downloaded or picked image file name and contents, ready to be loaded.
+    Downloaded(String, Vec<u8>),
+    DownloadError(String),
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -170,6 +188,10 @@ pub struct AppWidgets {
     disk_info: DiskInfoWidget,
     boot_sector: BootSectorWidget,
     track_list: TrackListWidget,
+    track_grid: TrackGridWidget,
+    track_metric: TrackMetric,
+    // Whether the track grid shows the differences from the disk in the other slot.
+    track_diff: bool,
     file_system: FileSystemWidget,
     filename: FilenameWidget,
 }
@@ -181,6 +203,9 @@ impl AppWidgets {
             disk_info: DiskInfoWidget::default(),
             boot_sector: BootSectorWidget::default(),
             track_list: TrackListWidget::default(),
+            track_grid: TrackGridWidget::default(),
+            track_metric: TrackMetric::default(),
+            track_diff: false,
             file_system: FileSystemWidget::default(),
             filename: FilenameWidget::default(),
         }
@@ -198,6 +223,10 @@ impl AppWidgets {
         self.disk_info.update(&disk, None);
         self.boot_sector.update(&disk);
         self.track_list.update(&disk);
+        // A new disk invalidates any diff, so go back to showing the metric.
+        self.track_diff = false;
+        let metric = self.track_metric;
+        self.track_grid.update(&disk, metric.name(), |t| metric.value(t));
     }
 
     pub fn update_mut(&mut self, disk_lock: TrackingLock<DiskImage>) {
@@ -224,6 +253,7 @@ impl AppWidgets {
         self.disk_info = DiskInfoWidget::default();
         self.boot_sector = BootSectorWidget::default();
         self.track_list = TrackListWidget::default();
+        self.track_grid = TrackGridWidget::default();
         self.file_system = FileSystemWidget::default();
     }
 }
@@ -237,6 +267,10 @@ pub struct AppWindows {
     source_map: SourceMapViewer,
     element_map: ElementMapViewer,
     track_timing_viewer: TrackTimingViewer,
+    track_metadata: TrackMetadataViewer,
+    image_metadata: ImageMetadataViewer,
+    save_as: SaveAsDialog,
+    image_analysis: ImageAnalysisViewer,
 }
 
 impl AppWindows {
@@ -250,6 +284,10 @@ impl AppWindows {
             source_map: SourceMapViewer::default(),
             element_map: ElementMapViewer::default(),
             track_timing_viewer: TrackTimingViewer::default(),
+            track_metadata: TrackMetadataViewer::default(),
+            image_metadata: ImageMetadataViewer::default(),
+            save_as: SaveAsDialog::default(),
+            image_analysis: ImageAnalysisViewer::default(),
         }
     }
 
@@ -262,10 +300,14 @@ impl AppWindows {
         self.source_map = SourceMapViewer::default();
         self.element_map = ElementMapViewer::default();
         self.track_timing_viewer = TrackTimingViewer::default();
+        self.track_metadata = TrackMetadataViewer::default();
+        self.image_metadata = ImageMetadataViewer::default();
+        self.save_as = SaveAsDialog::default();
+        self.image_analysis = ImageAnalysisViewer::default();
     }
 
     /// Update windows that hold a disk image lock with a new lock.
-    pub fn update_disk(&mut self, disk_lock: TrackingLock<DiskImage>, _name: Option<String>) {
+    pub fn update_disk(&mut self, disk_lock: TrackingLock<DiskImage>, name: Option<String>) {
         // The visualization viewer can hold a read lock in the background for rendering, so it
         // should be updated last.
         match disk_lock.read(UiLockContext::App) {
@@ -279,6 +321,13 @@ impl AppWindows {
         log::debug!("Updating track data viewer...");
         self.track_viewer.update_disk(disk_lock.clone());
 
+        log::debug!("Updating image metadata...");
+        self.image_metadata.update_disk(disk_lock.clone());
+        self.save_as.update_disk(disk_lock.clone(), name);
+
+        log::debug!("Updating image analysis...");
+        self.image_analysis.update_disk(disk_lock.clone());
+
         log::debug!("Updating sector viewer...");
         self.sector_viewer.update(disk_lock.clone(), SectorSelection::default());
 
@@ -298,6 +347,7 @@ pub enum AppEvent {
     TrackSelected(TrackSelection),
     TrackElementsSelected(TrackSelection),
     TrackTimingsSelected(TrackSelection),
+    TrackMetadataSelected(TrackSelection),
 }
 
 /// A [DiskSlot] represents data about a specific disk image slot.
@@ -341,7 +391,7 @@ pub struct App {
     ctx_init: bool,
     pub(crate) dropped_files: Vec<egui::DroppedFile>,
     load_status: ThreadLoadStatus,
-    load_sender: Option<mpsc::SyncSender<ThreadLoadStatus>>,
+    load_sender: Option<mpsc::Sender<ThreadLoadStatus>>,
     load_receiver: Option<mpsc::Receiver<ThreadLoadStatus>>,
 
     tool_sender:   mpsc::SyncSender<UiEvent>,
@@ -363,12 +413,25 @@ pub struct App {
     sector_selection: Option<SectorSelection>,
     track_selection: Option<TrackSelection>,
 
+    /// The current selection and visualization state, mirrored to the URL fragment on the web.
+    view_state: ViewState,
+    /// A view state to restore once the next disk image has loaded.
+    pending_view_state: Option<ViewState>,
+    /// The last URL fragment written, so that we only update it when the view state changes.
+    #[cfg(target_arch = "wasm32")]
+    view_fragment: String,
+    /// The URL entered in the Image menu's "Open URL" field.
+    #[cfg(target_arch = "wasm32")]
+    url_input: String,
+
     error_msg: Option<String>,
 }
 
 impl Default for App {
     fn default() -> Self {
-        let (load_sender, load_receiver) = mpsc::sync_channel(128);
+        // The load channel is unbounded so that a burst of progress updates can never cause the
+        // final result of a download or load to be dropped.
+        let (load_sender, load_receiver) = mpsc::channel();
         let (tool_sender, tool_receiver) = mpsc::sync_channel(16);
         Self {
             // Example stuff:
@@ -399,6 +462,13 @@ impl Default for App {
             sector_selection: None,
             track_selection: None,
 
+            view_state: ViewState::default(),
+            pending_view_state: None,
+            #[cfg(target_arch = "wasm32")]
+            view_fragment: String::new(),
+            #[cfg(target_arch = "wasm32")]
+            url_input: String::new(),
+
             error_msg: None,
         }
     }
@@ -510,6 +580,19 @@ impl App {
 
         app_state.supported_extensions.sort();
 
+        // Restore a view state shared via the URL fragment once an image is loaded.
+        #[cfg(target_arch = "wasm32")]
+        if let Some(fragment) = crate::wasm::fragment::read_fragment() {
+            app_state.view_fragment = fragment.clone();
+            app_state.pending_view_state = Some(ViewState::from_fragment(&fragment));
+        }
+
+        // Open an image given by URL via the `?image=` query parameter.
+        #[cfg(target_arch = "wasm32")]
+        if let Some(url) = crate::wasm::fetch::image_url_param() {
+            app_state.load_url(&cc.egui_ctx, &url);
+        }
+
         app_state
     }
 
@@ -559,6 +642,12 @@ impl eframe::App for App {
         self.windows.file_viewer.show(&ctx);
         self.windows.element_map.show(&ctx);
         self.windows.track_timing_viewer.show(&ctx);
+        self.windows.image_metadata.show(&ctx);
+        self.windows.save_as.show(&ctx);
+        if let Some(event) = self.windows.image_analysis.show(&ctx) {
+            self.events.push_back(event);
+        }
+        self.sync_element_selection(&ctx);
 
         egui::Panel::top("top_panel").show_inside(ui, |ui| {
             // The top panel is often a good place for a menu bar:
@@ -611,6 +700,9 @@ impl eframe::App for App {
 
         self.handle_ui_events();
         self.handle_app_events();
+
+        #[cfg(target_arch = "wasm32")]
+        self.sync_view_fragment();
     }
 
     /// Called by the framework to save persistent state before shutdown.
@@ -631,6 +723,7 @@ impl App {
         log::debug!("Resetting application state for new disk...");
         //self.disk_image_name = None;
         self.error_msg = None;
+        self.view_state = ViewState::default();
         self.widgets.reset();
         self.windows.reset();
     }
@@ -648,6 +741,40 @@ impl App {
         self.windows.reset();
     }
 
+    /// Write the current view state to the URL fragment if it has changed. The fragment is left
+    /// alone while a shared view state is waiting for its image to load.
+    #[cfg(target_arch = "wasm32")]
+    fn sync_view_fragment(&mut self) {
+        if self.pending_view_state.is_some() || !self.have_disk_in_selected_slot() {
+            return;
+        }
+        self.view_state.viz = self.windows.viz_viewer.view_state();
+        let fragment = self.view_state.to_fragment();
+        if fragment != self.view_fragment {
+            crate::wasm::fragment::write_fragment(&fragment);
+            self.view_fragment = fragment;
+        }
+    }
+
+    /// Restore a view state by applying its visualization parameters and queueing the events
+    /// that open the selected tool.
+    fn apply_view_state(&mut self, state: ViewState) {
+        log::debug!("apply_view_state(): Restoring view state: {:?}", state);
+        self.windows.viz_viewer.apply_view_state(&state.viz);
+        if let Some(selection) = state.sector_selection() {
+            self.events.push_back(AppEvent::SectorSelected(selection));
+        }
+        if let Some(selection) = state.track_selection() {
+            self.events.push_back(match state.tool {
+                Some(ViewTool::Elements) => AppEvent::TrackElementsSelected(selection),
+                Some(ViewTool::Timings) => AppEvent::TrackTimingsSelected(selection),
+                Some(ViewTool::Metadata) => AppEvent::TrackMetadataSelected(selection),
+                _ => AppEvent::TrackSelected(selection),
+            });
+        }
+        self.view_state = state;
+    }
+
     // Optional: clear dropped files when done
     fn clear_dropped_files(&mut self) {
         self.dropped_files.clear();
@@ -679,6 +806,14 @@ impl App {
             let is_web = cfg!(target_arch = "wasm32");
             if !is_web {
                 ui.menu_button("File", |ui| {
+                    if ui
+                        .add_enabled(self.have_disk_in_selected_slot(), egui::Button::new("Save As..."))
+                        .clicked()
+                    {
+                        self.windows.save_as.set_open(true);
+                        ui.close();
+                    }
+                    ui.separator();
                     if ui.button("Quit").clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
@@ -688,6 +823,29 @@ impl App {
             else {
                 //log::debug!("Running on web platform, showing Image menu");
                 ui.menu_button("Image", |ui| {
+                    #[cfg(target_arch = "wasm32")]
+                    {
+                        if ui.button("Open image...").clicked() {
+                            self.open_file_picker(ctx);
+                            ui.close();
+                        }
+                        ui.menu_button("Open URL", |ui| {
+                            let response = ui.add(
+                                egui::TextEdit::singleline(&mut self.url_input)
+                                    .hint_text("https://example.com/disk.imz")
+                                    .desired_width(300.0),
+                            );
+                            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                            let url = self.url_input.trim().to_string();
+                            if (ui.add_enabled(!url.is_empty(), egui::Button::new("Open")).clicked() || submitted)
+                                && !url.is_empty()
+                            {
+                                self.load_url(ctx, &url);
+                                ui.close();
+                            }
+                        });
+                        ui.separator();
+                    }
                     if ui.button("Load demo image...").clicked() {
                         let mut cursor = std::io::Cursor::new(DEMO_IMAGE);
                         DiskImage::load(&mut cursor, None, None, None)
@@ -711,6 +869,13 @@ impl App {
 
                         ui.close();
                     }
+                    if ui
+                        .add_enabled(self.have_disk_in_selected_slot(), egui::Button::new("Download As..."))
+                        .clicked()
+                    {
+                        self.windows.save_as.set_open(true);
+                        ui.close();
+                    }
                 });
             }
 
@@ -721,6 +886,8 @@ impl App {
                     ui.checkbox(self.windows.new_viz_viewer.open_mut(), "Visualization (New)");
                 }
                 ui.checkbox(self.windows.source_map.open_mut(), "Image Source Map");
+                ui.checkbox(self.windows.image_metadata.open_mut(), "Image Metadata");
+                ui.checkbox(self.windows.image_analysis.open_mut(), "Image Analysis");
             });
 
             ui.menu_button("Options", |ui| {
@@ -781,11 +948,16 @@ impl App {
 
                         self.sector_selection = Some(SectorSelection::default());
                         self.widgets.hello.set_small(true);
+
+                        if let Some(state) = self.pending_view_state.take() {
+                            self.apply_view_state(state);
+                        }
                     }
                 }
                 AppEvent::SectorSelected(selection) => {
                     if let Some(disk) = self.selected_disk() {
                         self.windows.sector_viewer.update(disk.clone(), selection.clone());
+                        self.view_state.set_sector(&selection);
                         self.sector_selection = Some(selection);
 
                         self.windows.sector_viewer.set_open(true);
@@ -794,6 +966,7 @@ impl App {
                 AppEvent::TrackSelected(selection) => {
                     if let Some(_disk) = self.selected_disk() {
                         self.windows.track_viewer.update_selection(selection.clone());
+                        self.view_state.set_track(ViewTool::Track, &selection);
                         self.track_selection = Some(selection);
                         self.windows.track_viewer.set_open(true);
                     }
@@ -802,6 +975,14 @@ impl App {
                     if let Some(disk) = self.selected_disk() {
                         self.windows.element_map.update(disk.clone(), selection.clone());
                         self.windows.element_map.set_open(true);
+                        self.view_state.set_track(ViewTool::Elements, &selection);
+                    }
+                }
+                AppEvent::TrackMetadataSelected(selection) => {
+                    if let Some(disk) = self.selected_disk() {
+                        self.windows.track_metadata.update(disk.clone(), selection.clone());
+                        self.windows.track_metadata.set_open(true);
+                        self.view_state.set_track(ViewTool::Metadata, &selection);
                     }
                 }
                 AppEvent::TrackTimingsSelected(selection) => {
@@ -816,6 +997,7 @@ impl App {
                                             Some(track.pll_markers()),
                                         );
                                         self.windows.track_timing_viewer.set_open(true);
+                                        self.view_state.set_track(ViewTool::Timings, &selection);
                                     }
                                 }
                             }
@@ -853,6 +1035,31 @@ impl App {
         }
     }
 
+    /// Show the track metadata window, and keep its selected element in sync with the element
+    /// selected on the disk visualization.
+    fn sync_element_selection(&mut self, ctx: &egui::Context) {
+        if let Some(element_idx) = self.windows.track_metadata.show(ctx) {
+            let ch = self.windows.track_metadata.phys_ch();
+            self.windows.viz_viewer.select_element(ch, element_idx);
+        }
+
+        if let Some((ch, element_idx)) = self.windows.viz_viewer.take_selected_element() {
+            if self.windows.track_metadata.is_open() {
+                if ch != self.windows.track_metadata.phys_ch() {
+                    if let Some(disk) = self.selected_disk() {
+                        let selection = TrackSelection {
+                            sel_scope: TrackSelectionScope::Metadata,
+                            phys_ch:   ch,
+                        };
+                        self.windows.track_metadata.update(disk.clone(), selection.clone());
+                        self.view_state.set_track(ViewTool::Metadata, &selection);
+                    }
+                }
+                self.windows.track_metadata.select_element(element_idx);
+            }
+        }
+    }
+
     /// Handle UI events - events sent from tools to the application.
     fn handle_ui_events(&mut self) {
         let mut keep_polling = true;
@@ -881,28 +1088,105 @@ impl App {
 
     fn handle_track_info(&mut self, ui: &mut egui::Ui) {
         if self.have_disk_in_selected_slot() {
+            let mut selection = None;
             ui.group(|ui| {
-                if let Some(selection) = self.widgets.track_list.show(ui) {
-                    log::debug!("TrackList selection: {:?}", selection);
-                    match selection {
-                        TrackListSelection::Track(track) => match track.sel_scope {
-                            TrackSelectionScope::DecodedDataStream => {
-                                self.events.push_back(AppEvent::TrackSelected(track));
-                            }
-                            TrackSelectionScope::Elements => {
-                                self.events.push_back(AppEvent::TrackElementsSelected(track));
-                            }
-                            TrackSelectionScope::Timings => {
-                                self.events.push_back(AppEvent::TrackTimingsSelected(track));
-                            }
-                            _ => log::warn!("Unsupported TrackSelectionScope: {:?}", track.sel_scope),
-                        },
-                        TrackListSelection::Sector(sector) => {
-                            self.events.push_back(AppEvent::SectorSelected(sector));
+                selection = self.show_track_grid(ui);
+            });
+            ui.group(|ui| {
+                if let Some(list_selection) = self.widgets.track_list.show(ui) {
+                    selection = Some(list_selection);
+                }
+            });
+
+            if let Some(selection) = selection {
+                log::debug!("TrackList selection: {:?}", selection);
+                match selection {
+                    TrackListSelection::Track(track) => match track.sel_scope {
+                        TrackSelectionScope::DecodedDataStream => {
+                            self.events.push_back(AppEvent::TrackSelected(track));
+                        }
+                        TrackSelectionScope::Elements => {
+                            self.events.push_back(AppEvent::TrackElementsSelected(track));
+                        }
+                        TrackSelectionScope::Timings => {
+                            self.events.push_back(AppEvent::TrackTimingsSelected(track));
+                        }
+                        TrackSelectionScope::Metadata => {
+                            self.events.push_back(AppEvent::TrackMetadataSelected(track));
                         }
+                        _ => log::warn!("Unsupported TrackSelectionScope: {:?}", track.sel_scope),
+                    },
+                    TrackListSelection::Sector(sector) => {
+                        self.events.push_back(AppEvent::SectorSelected(sector));
                     }
                 }
+            }
+        }
+    }
+
+    /// Show the track overview grid, with a selector for the metric it displays. The grid can
+    /// instead show the differences between the selected disk and the disk in the other slot.
+    fn show_track_grid(&mut self, ui: &mut egui::Ui) -> Option<TrackListSelection> {
+        let mut metric = self.widgets.track_metric;
+        let mut track_diff = self.widgets.track_diff;
+        let other_slot = (self.selected_slot + 1) % DISK_SLOTS;
+        let have_other = self.have_disk_in_slot(other_slot);
+        ui.horizontal(|ui| {
+            ui.heading(egui::RichText::new("Track Overview").strong());
+            ui.add_enabled_ui(!track_diff, |ui| {
+                egui::ComboBox::from_id_salt("track_grid_metric")
+                    .selected_text(metric.name())
+                    .show_ui(ui, |ui| {
+                        for option in TrackMetric::ALL {
+                            ui.selectable_value(&mut metric, option, option.name());
+                        }
+                    });
             });
+            let diff_label = format!("Diff with {}:", (b'A' + other_slot as u8) as char);
+            ui.add_enabled(have_other, egui::Checkbox::new(&mut track_diff, diff_label));
+        });
+        // The other disk may have been ejected since the diff was shown.
+        track_diff &= have_other;
+
+        if metric != self.widgets.track_metric || track_diff != self.widgets.track_diff {
+            self.widgets.track_metric = metric;
+            self.widgets.track_diff = track_diff;
+            self.update_track_grid(other_slot);
+        }
+        self.widgets.track_grid.show(ui)
+    }
+
+    /// Recompute the track overview grid for the selected disk, diffing it against the disk in
+    /// `other_slot` if the grid is showing differences.
+    fn update_track_grid(&mut self, other_slot: usize) {
+        let Some(disk_lock) = self.selected_disk()
+        else {
+            return;
+        };
+        let Ok(disk) = disk_lock.read(UiLockContext::App)
+        else {
+            log::error!("Failed to lock disk image for reading. Cannot update track grid.");
+            return;
+        };
+
+        if !self.widgets.track_diff {
+            let metric = self.widgets.track_metric;
+            self.widgets
+                .track_grid
+                .update(&disk, metric.name(), |t| metric.value(t));
+            return;
+        }
+
+        let Some(other_lock) = self.slot(other_slot).image.clone()
+        else {
+            return;
+        };
+        match other_lock.read(UiLockContext::App) {
+            Ok(other) => {
+                let diff = disk.diff(&other);
+                self.widgets.track_grid.update_diff(&disk, &diff);
+            }
+            Err(_) => log::error!("Failed to lock disk image for reading. Cannot diff track grid."),
         }
     }
 
@@ -955,6 +1239,40 @@ impl App {
                             }
                         }
                     }
+                    UiEvent::ExportFiles(paths) => {
+                        log::debug!("Exporting {} files", paths.len());
+
+                        let mut fs = match FatFileSystem::mount(disk.clone(), UiLockContext::FileSystemOperation, None)
+                        {
+                            Ok(fs) => fs,
+                            Err(e) => {
+                                log::error!("Error mounting FAT filesystem: {:?}", e);
+                                return;
+                            }
+                        };
+
+                        // Read every file before saving, so the file system isn't held mounted while
+                        // save dialogs are open.
+                        let mut files = Vec::with_capacity(paths.len());
+                        for path in paths {
+                            match fs.read_file(&path) {
+                                Ok(data) => files.push((path, data)),
+                                Err(e) => log::error!("Error reading file {}: {:?}", path, e),
+                            }
+                        }
+                        fs.unmount();
+
+                        for (path, file_data) in files {
+                            match App::save_file_as(&path, &file_data) {
+                                Ok(_) => {
+                                    log::info!("File {} saved successfully!", path);
+                                }
+                                Err(e) => {
+                                    log::error!("Error saving file {}: {:?}", path, e);
+                                }
+                            }
+                        }
+                    }
                     UiEvent::SelectFile(file) => {
                         let selected_file = file.path().to_string();
                         log::debug!("Selected file: {:?}", selected_file);
@@ -1011,6 +1329,7 @@ impl App {
 
     fn handle_load_messages(&mut self, ctx: &egui::Context) {
         let mut new_disk = false;
+        let mut downloaded = None;
         // Read messages from the load thread
         if let Some(receiver) = &self.load_receiver {
             // We should keep draining the receiver until it's empty, otherwise messages arriving
@@ -1056,6 +1375,22 @@ impl App {
                                 // Return to reactive mode
                                 self.run_mode = RunMode::Reactive;
                             }
+                            ThreadLoadStatus::Downloading(progress) => {
+                                self.load_status = ThreadLoadStatus::Downloading(progress);
+                                ctx.request_repaint();
+                            }
+                            ThreadLoadStatus::Downloaded(name, bytes) => {
+                                log::info!("Downloaded file: {} ({} bytes)", name, bytes.len());
+                                self.load_status = ThreadLoadStatus::Inactive;
+                                downloaded = Some((name, bytes));
+                            }
+                            ThreadLoadStatus::DownloadError(msg) => {
+                                log::error!("Error downloading disk image: {}", msg);
+                                self.load_status = ThreadLoadStatus::Inactive;
+                                self.error_msg = Some(msg);
+                                ctx.request_repaint();
+                                self.run_mode = RunMode::Reactive;
+                            }
                             _ => {}
                         }
                     }
@@ -1073,11 +1408,56 @@ impl App {
             log::debug!("Resetting disk due to new disk without loading notification...");
             self.new_disk();
         }
+
+        if let Some((name, bytes)) = downloaded {
+            self.load_image_bytes(ctx, name, bytes);
+        }
     }
 
     fn handle_loading_progress(&mut self, ui: &mut egui::Ui) {
-        if let ThreadLoadStatus::Loading(progress) = &self.load_status {
-            ui.add(egui::ProgressBar::new(*progress as f32).text(format!("{:.1}%", *progress * 100.0)));
+        match &self.load_status {
+            ThreadLoadStatus::Loading(progress) => {
+                ui.add(egui::ProgressBar::new(*progress as f32).text(format!("{:.1}%", *progress * 100.0)));
+            }
+            ThreadLoadStatus::Downloading(progress) => {
+                ui.add(
+                    egui::ProgressBar::new(*progress as f32).text(format!("Downloading: {:.1}%", *progress * 100.0)),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Start downloading the disk image at `url`. The image is loaded into the selected slot once
+    /// the download completes.
+    #[cfg(target_arch = "wasm32")]
+    fn load_url(&mut self, ctx: &egui::Context, url: &str) {
+        log::info!("Fetching disk image from URL: {}", url);
+        let sender = self.load_sender.as_ref().unwrap().clone();
+        match crate::wasm::fetch::fetch_image(url, ctx.clone(), sender) {
+            Ok(()) => {
+                self.load_status = ThreadLoadStatus::Downloading(0.0);
+                self.run_mode = RunMode::Continuous;
+            }
+            Err(e) => {
+                log::error!("Error fetching disk image: {:?}", e);
+                self.error_msg = Some(format!("Failed to fetch {}", url));
+            }
+        }
+    }
+
+    /// Show the browser's file picker. The picked image is loaded into the selected slot once it
+    /// has been read into memory.
+    #[cfg(target_arch = "wasm32")]
+    fn open_file_picker(&mut self, ctx: &egui::Context) {
+        let sender = self.load_sender.as_ref().unwrap().clone();
+        // Images may also be picked from inside a compressed archive.
+        let mut extensions = fluxfox::supported_extensions();
+        extensions.extend(["zip", "gz", "tar"]);
+        let accept = crate::wasm::picker::accept_filter(&extensions);
+        if let Err(e) = crate::wasm::picker::open_file_picker(&accept, ctx.clone(), sender) {
+            log::error!("Error opening file picker: {:?}", e);
+            self.error_msg = Some("Failed to open the file picker".to_string());
         }
     }
 
@@ -1161,51 +1541,7 @@ impl App {
                 // Only process if bytes are now available
                 log::info!("Processing file: {} ({} bytes)", file.name, bytes.len());
 
-                let bytes = bytes.clone();
-                let bytes_vec = bytes.to_vec();
-                let mut cursor = std::io::Cursor::new(bytes_vec);
-
-                let sender1 = self.load_sender.as_mut().unwrap().clone();
-                let sender2 = self.load_sender.as_mut().unwrap().clone();
-
-                // Remove the old disk image
-                self.eject_slot(self.selected_slot);
-                // Set the name of the new disk image
-                self.selected_slot_mut().image_name = Some(dropped_filename(&file));
-
-                log::debug!("Spawning thread to load disk image");
-                let loading_slot = self.selected_slot;
-                match worker::spawn_closure_worker(move || {
-                    log::debug!("Hello from worker thread!");
-
-                    // callback is of type Arc<dyn Fn(LoadingStatus) + Send + Sync>
-                    let callback = Arc::new(move |status: LoadingStatus| match status {
-                        LoadingStatus::Progress(progress) => {
-                            log::debug!("Sending Loading progress: {:.1}%", progress * 100.0);
-                            sender2.send(ThreadLoadStatus::Loading(progress)).unwrap();
-                        }
-                        _ => {}
-                    });
-                    DiskImage::load(&mut cursor, None, None, Some(callback))
-                        .map(|disk| {
-                            log::debug!("Disk image loaded successfully!");
-                            sender1.send(ThreadLoadStatus::Success(disk, loading_slot)).unwrap();
-                        })
-                        .unwrap_or_else(|e| {
-                            log::error!("Error loading disk image: {:?}", e);
-                            sender1.send(ThreadLoadStatus::Error(e)).unwrap();
-                        });
-                }) {
-                    Ok(_) => {
-                        log::debug!("Worker thread spawned successfully");
-                        // Enter continuous mode.
-                        self.run_mode = RunMode::Continuous;
-                        ctx.request_repaint();
-                    }
-                    Err(e) => {
-                        log::error!("Error spawning worker thread: {:?}", e);
-                    }
-                }
+                self.load_image_bytes(ctx, dropped_filename(&file), bytes.to_vec());
 
                 // Clear the dropped file after processing
                 self.clear_dropped_files();
@@ -1216,4 +1552,51 @@ impl App {
             }
         }
     }
+
+    /// Load a disk image from `bytes` into the selected slot on a worker thread.
+    fn load_image_bytes(&mut self, ctx: &egui::Context, name: String, bytes: Vec<u8>) {
+        let mut cursor = std::io::Cursor::new(bytes);
+
+        let sender1 = self.load_sender.as_mut().unwrap().clone();
+        let sender2 = self.load_sender.as_mut().unwrap().clone();
+
+        // Remove the old disk image
+        self.eject_slot(self.selected_slot);
+        // Set the name of the new disk image
+        self.selected_slot_mut().image_name = Some(name);
+
+        log::debug!("Spawning thread to load disk image");
+        let loading_slot = self.selected_slot;
+        match worker::spawn_closure_worker(move || {
+            log::debug!("Hello from worker thread!");
+
+            // callback is of type Arc<dyn Fn(LoadingStatus) + Send + Sync>
+            let callback = Arc::new(move |status: LoadingStatus| match status {
+                LoadingStatus::Progress(progress) => {
+                    log::debug!("Sending Loading progress: {:.1}%", progress * 100.0);
+                    sender2.send(ThreadLoadStatus::Loading(progress)).unwrap();
+                }
+                _ => {}
+            });
+            DiskImage::load(&mut cursor, None, None, Some(callback))
+                .map(|disk| {
+                    log::debug!("Disk image loaded successfully!");
+                    sender1.send(ThreadLoadStatus::Success(disk, loading_slot)).unwrap();
+                })
+                .unwrap_or_else(|e| {
+                    log::error!("Error loading disk image: {:?}", e);
+                    sender1.send(ThreadLoadStatus::Error(e)).unwrap();
+                });
+        }) {
+            Ok(_) => {
+                log::debug!("Worker thread spawned successfully");
+                // Enter continuous mode.
+                self.run_mode = RunMode::Continuous;
+                ctx.request_repaint();
+            }
+            Err(e) => {
+                log::error!("Error spawning worker thread: {:?}", e);
+            }
+        }
+    }
 }
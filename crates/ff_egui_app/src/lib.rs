@@ -34,6 +34,8 @@ pub(crate) mod widgets;
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) mod native;
 pub(crate) mod ui;
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub(crate) mod view_state;
 #[cfg(target_arch = "wasm32")]
 pub(crate) mod wasm;
 pub(crate) mod windows;
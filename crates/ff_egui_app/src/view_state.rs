@@ -0,0 +1,280 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    src/view_state.rs
+
+    Serialize the current selection and visualization parameters to and from
+    a URL fragment, so that a link can point at a specific location in a
+    disk image, e.g.:
+
+        #tool=sector&c=39&h=0&s=5&n=2&layers=data,metadata&angle=1.571&zoom0=1
+*/
+
+use fluxfox::prelude::*;
+use fluxfox_egui::{SectorSelection, TrackSelection, TrackSelectionScope};
+
+/// The tool a [ViewState] points at.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ViewTool {
+    Sector,
+    Track,
+    Elements,
+    Timings,
+    Metadata,
+}
+
+impl ViewTool {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ViewTool::Sector => "sector",
+            ViewTool::Track => "track",
+            ViewTool::Elements => "elements",
+            ViewTool::Timings => "timings",
+            ViewTool::Metadata => "metadata",
+        }
+    }
+
+    fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "sector" => Some(ViewTool::Sector),
+            "track" => Some(ViewTool::Track),
+            "elements" => Some(ViewTool::Elements),
+            "timings" => Some(ViewTool::Timings),
+            "metadata" => Some(ViewTool::Metadata),
+            _ => None,
+        }
+    }
+}
+
+/// Parameters of the disk visualization window.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VizViewState {
+    pub data_layer: bool,
+    pub metadata_layer: bool,
+    pub angle: f32,
+    pub quadrants: [Option<usize>; 2],
+}
+
+impl Default for VizViewState {
+    fn default() -> Self {
+        Self {
+            data_layer: true,
+            metadata_layer: true,
+            angle: 0.0,
+            quadrants: [None, None],
+        }
+    }
+}
+
+/// The selection and visualization state of the application, as stored in a URL fragment.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ViewState {
+    pub tool: Option<ViewTool>,
+    pub phys_ch: DiskCh,
+    /// The ID of the selected sector, if the tool is [ViewTool::Sector].
+    pub sector_id: Option<DiskChsn>,
+    pub viz: VizViewState,
+}
+
+impl ViewState {
+    pub fn set_sector(&mut self, selection: &SectorSelection) {
+        self.tool = Some(ViewTool::Sector);
+        self.phys_ch = selection.phys_ch;
+        self.sector_id = Some(selection.sector_id);
+    }
+
+    pub fn set_track(&mut self, tool: ViewTool, selection: &TrackSelection) {
+        self.tool = Some(tool);
+        self.phys_ch = selection.phys_ch;
+        self.sector_id = None;
+    }
+
+    /// Return the selection described by this state, if any.
+    pub fn sector_selection(&self) -> Option<SectorSelection> {
+        match (self.tool, self.sector_id) {
+            (Some(ViewTool::Sector), Some(sector_id)) => Some(SectorSelection {
+                phys_ch: self.phys_ch,
+                sector_id,
+                bit_offset: None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Return the track selection described by this state, if the tool is a track tool.
+    pub fn track_selection(&self) -> Option<TrackSelection> {
+        let sel_scope = match self.tool? {
+            ViewTool::Track => TrackSelectionScope::DecodedDataStream,
+            ViewTool::Elements => TrackSelectionScope::Elements,
+            ViewTool::Timings => TrackSelectionScope::Timings,
+            ViewTool::Metadata => TrackSelectionScope::Metadata,
+            ViewTool::Sector => return None,
+        };
+        Some(TrackSelection {
+            sel_scope,
+            phys_ch: self.phys_ch,
+        })
+    }
+
+    /// Serialize the state to a URL fragment, without the leading `#`.
+    pub fn to_fragment(&self) -> String {
+        let mut params = Vec::new();
+
+        if let Some(tool) = self.tool {
+            params.push(format!("tool={}", tool.as_str()));
+            params.push(format!("c={}", self.phys_ch.c()));
+            params.push(format!("h={}", self.phys_ch.h()));
+        }
+        if let Some(id) = self.sector_id {
+            // Sector IDs usually match the physical track, so only write the ID's c and h if not.
+            if id.c() != self.phys_ch.c() {
+                params.push(format!("id_c={}", id.c()));
+            }
+            if id.h() != self.phys_ch.h() {
+                params.push(format!("id_h={}", id.h()));
+            }
+            params.push(format!("s={}", id.s()));
+            params.push(format!("n={}", id.n()));
+        }
+
+        let layers: Vec<&str> = [(self.viz.data_layer, "data"), (self.viz.metadata_layer, "metadata")]
+            .iter()
+            .filter_map(|&(set, name)| set.then_some(name))
+            .collect();
+        params.push(format!("layers={}", layers.join(",")));
+        if self.viz.angle != 0.0 {
+            params.push(format!("angle={:.3}", self.viz.angle));
+        }
+        for (side, quadrant) in self.viz.quadrants.iter().enumerate() {
+            if let Some(quadrant) = quadrant {
+                params.push(format!("zoom{}={}", side, quadrant));
+            }
+        }
+
+        params.join("&")
+    }
+
+    /// Parse a URL fragment, with or without the leading `#`. Unknown or invalid parameters are
+    /// ignored, so that a hand-edited link still restores as much state as possible.
+    pub fn from_fragment(fragment: &str) -> Self {
+        let mut state = ViewState::default();
+        let (mut c, mut h) = (0u16, 0u8);
+        let (mut id_c, mut id_h, mut s, mut n) = (None, None, None, None);
+
+        for (key, value) in fragment
+            .trim_start_matches('#')
+            .split('&')
+            .filter_map(|param| param.split_once('='))
+        {
+            match key {
+                "tool" => state.tool = ViewTool::from_name(value),
+                "c" => c = value.parse().unwrap_or(c),
+                "h" => h = value.parse().unwrap_or(h),
+                "id_c" => id_c = value.parse().ok(),
+                "id_h" => id_h = value.parse().ok(),
+                "s" => s = value.parse().ok(),
+                "n" => n = value.parse().ok(),
+                "layers" => {
+                    state.viz.data_layer = value.split(',').any(|layer| layer == "data");
+                    state.viz.metadata_layer = value.split(',').any(|layer| layer == "metadata");
+                }
+                "angle" => state.viz.angle = value.parse().unwrap_or(0.0),
+                "zoom0" => state.viz.quadrants[0] = value.parse().ok().filter(|&q| q < 4),
+                "zoom1" => state.viz.quadrants[1] = value.parse().ok().filter(|&q| q < 4),
+                _ => log::warn!("ViewState::from_fragment(): Ignoring unknown parameter: {}", key),
+            }
+        }
+
+        state.phys_ch = DiskCh::new(c, h);
+        if let Some(s) = s {
+            // A sector link without a tool opens the sector viewer.
+            state.tool.get_or_insert(ViewTool::Sector);
+            // Assume the standard 512 byte sector size if not specified.
+            state.sector_id = Some(DiskChsn::new(id_c.unwrap_or(c), id_h.unwrap_or(h), s, n.unwrap_or(2)));
+        }
+        if state.tool == Some(ViewTool::Sector) && state.sector_id.is_none() {
+            state.tool = None;
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragment_round_trip() {
+        let sector = ViewState {
+            tool: Some(ViewTool::Sector),
+            phys_ch: DiskCh::new(39, 1),
+            // A sector whose ID does not match its physical track.
+            sector_id: Some(DiskChsn::new(40, 0, 5, 3)),
+            viz: VizViewState {
+                data_layer: false,
+                metadata_layer: true,
+                angle: 1.5,
+                quadrants: [Some(2), None],
+            },
+        };
+        let fragment = sector.to_fragment();
+        assert_eq!(
+            fragment,
+            "tool=sector&c=39&h=1&id_c=40&id_h=0&s=5&n=3&layers=metadata&angle=1.500&zoom0=2"
+        );
+        assert_eq!(ViewState::from_fragment(&format!("#{}", fragment)), sector);
+
+        for tool in [
+            ViewTool::Track,
+            ViewTool::Elements,
+            ViewTool::Timings,
+            ViewTool::Metadata,
+        ] {
+            let track = ViewState {
+                tool: Some(tool),
+                phys_ch: DiskCh::new(12, 0),
+                ..Default::default()
+            };
+            assert_eq!(ViewState::from_fragment(&track.to_fragment()), track);
+        }
+
+        let default = ViewState::default();
+        assert_eq!(default.to_fragment(), "layers=data,metadata");
+        assert_eq!(ViewState::from_fragment(&default.to_fragment()), default);
+    }
+
+    #[test]
+    fn test_fragment_parse_invalid() {
+        // Unknown and invalid parameters are ignored, and a sector defaults to 512 bytes.
+        let state = ViewState::from_fragment("#c=2&h=1&s=3&zoom1=7&tool=bogus&extra=1&angle=x");
+        assert_eq!(state.tool, Some(ViewTool::Sector));
+        assert_eq!(state.sector_id, Some(DiskChsn::new(2, 1, 3, 2)));
+        assert_eq!(state.viz.quadrants, [None, None]);
+        assert_eq!(state.viz.angle, 0.0);
+
+        // A sector tool without a sector selects nothing.
+        assert_eq!(ViewState::from_fragment("tool=sector&c=1").tool, None);
+    }
+}
@@ -0,0 +1,124 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    src/wasm/fetch.rs
+
+    Fetch a disk image from a URL given in the page's `?image=` query
+    parameter, so that a link can open a specific image in the web build.
+*/
+
+use crate::app::ThreadLoadStatus;
+use eframe::wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use std::sync::mpsc;
+use web_sys::{js_sys, window, ProgressEvent, UrlSearchParams, XmlHttpRequest, XmlHttpRequestResponseType};
+
+/// Return the value of the `image` query parameter of the page URL, if present.
+pub(crate) fn image_url_param() -> Option<String> {
+    let search = window()?.location().search().ok()?;
+    let params = UrlSearchParams::new_with_str(&search).ok()?;
+    params.get("image").filter(|url| !url.is_empty())
+}
+
+/// Return the last path component of `url`, ignoring any query string or fragment.
+pub(crate) fn url_filename(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(url)
+        .to_string()
+}
+
+/// Start fetching the file at `url` in the background.
+///
+/// Download progress is reported as [ThreadLoadStatus::Downloading], followed by either
+/// [ThreadLoadStatus::Downloaded] with the file contents or [ThreadLoadStatus::DownloadError].
+/// The server must allow cross-origin requests if the image is not hosted alongside the app.
+pub(crate) fn fetch_image(
+    url: &str,
+    ctx: egui::Context,
+    sender: mpsc::Sender<ThreadLoadStatus>,
+) -> Result<(), JsValue> {
+    let xhr = XmlHttpRequest::new()?;
+    xhr.open_with_async("GET", url, true)?;
+    xhr.set_response_type(XmlHttpRequestResponseType::Arraybuffer);
+
+    // We're running on the browser's main thread. The channel is unbounded, so sending never
+    // blocks, and the final result can't be lost behind a backlog of progress updates.
+    let progress_sender = sender.clone();
+    let progress_ctx = ctx.clone();
+    let on_progress = Closure::<dyn FnMut(ProgressEvent)>::new(move |event: ProgressEvent| {
+        if event.length_computable() && event.total() > 0.0 {
+            _ = progress_sender.send(ThreadLoadStatus::Downloading(event.loaded() / event.total()));
+            progress_ctx.request_repaint();
+        }
+    });
+
+    let load_xhr = xhr.clone();
+    let load_sender = sender.clone();
+    let load_ctx = ctx.clone();
+    let filename = url_filename(url);
+    let on_load = Closure::<dyn FnMut()>::new(move || {
+        let status = load_xhr.status().unwrap_or(0);
+        let result = if (200..300).contains(&status) {
+            match load_xhr.response() {
+                Ok(buffer) => {
+                    let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                    log::debug!("fetch_image(): Downloaded {} bytes", bytes.len());
+                    ThreadLoadStatus::Downloaded(filename.clone(), bytes)
+                }
+                Err(e) => ThreadLoadStatus::DownloadError(format!("Failed to read response: {:?}", e)),
+            }
+        }
+        else {
+            ThreadLoadStatus::DownloadError(format!("Server returned HTTP status {}", status))
+        };
+        if load_sender.send(result).is_err() {
+            log::error!("fetch_image(): Failed to send download result");
+        }
+        load_ctx.request_repaint();
+    });
+
+    let error_url = url.to_string();
+    let on_error = Closure::<dyn FnMut()>::new(move || {
+        _ = sender.send(ThreadLoadStatus::DownloadError(format!(
+            "Failed to fetch {} (the server may not allow cross-origin requests)",
+            error_url
+        )));
+        ctx.request_repaint();
+    });
+
+    xhr.set_onprogress(Some(on_progress.as_ref().unchecked_ref()));
+    xhr.set_onload(Some(on_load.as_ref().unchecked_ref()));
+    xhr.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+    xhr.send()?;
+
+    // The request holds references to the callbacks until it completes, so let them live on.
+    on_progress.forget();
+    on_load.forget();
+    on_error.forget();
+    Ok(())
+}
@@ -0,0 +1,51 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+
+    src/wasm/fragment.rs
+
+    Read and write the page's URL fragment, used to store a shareable
+    ViewState.
+*/
+
+use eframe::wasm_bindgen::JsValue;
+use web_sys::window;
+
+/// Return the URL fragment of the page without the leading `#`, if present.
+pub(crate) fn read_fragment() -> Option<String> {
+    let hash = window()?.location().hash().ok()?;
+    let fragment = hash.trim_start_matches('#');
+    (!fragment.is_empty()).then(|| fragment.to_string())
+}
+
+/// Replace the URL fragment of the page. This does not reload the page or add a history entry.
+pub(crate) fn write_fragment(fragment: &str) {
+    let Some(history) = window().and_then(|w| w.history().ok())
+    else {
+        return;
+    };
+    if let Err(e) = history.replace_state_with_url(&JsValue::NULL, "", Some(&format!("#{}", fragment))) {
+        log::warn!("write_fragment(): Failed to update URL fragment: {:?}", e);
+    }
+}
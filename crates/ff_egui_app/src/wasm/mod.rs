@@ -26,5 +26,8 @@
 */
 
 pub(crate) mod app;
+pub(crate) mod fetch;
+pub(crate) mod fragment;
+pub(crate) mod picker;
 pub(crate) mod util;
 pub(crate) mod worker;
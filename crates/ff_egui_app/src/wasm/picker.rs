@@ -0,0 +1,96 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/wasm/picker.rs
+
+    Open a disk image with the browser's file picker. The file is read into
+    memory asynchronously, so no filesystem access is required.
+*/
+
+use crate::app::ThreadLoadStatus;
+use eframe::wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use std::sync::mpsc;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{js_sys, window, HtmlInputElement};
+
+/// Build a file picker `accept` filter from a list of file extensions.
+pub(crate) fn accept_filter(extensions: &[&str]) -> String {
+    extensions
+        .iter()
+        .map(|ext| format!(".{}", ext))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Show the browser's file picker, restricted to files matching `accept`.
+///
+/// Once the user picks a file, its contents are read asynchronously and sent as
+/// [ThreadLoadStatus::Downloaded], or [ThreadLoadStatus::DownloadError] if the file could not be
+/// read. Nothing is sent if the user cancels the picker.
+pub(crate) fn open_file_picker(
+    accept: &str,
+    ctx: egui::Context,
+    sender: mpsc::Sender<ThreadLoadStatus>,
+) -> Result<(), JsValue> {
+    let document = window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| JsValue::from_str("No document available"))?;
+    let input: HtmlInputElement = document.create_element("input")?.dyn_into()?;
+    input.set_type("file");
+    input.set_accept(accept);
+
+    let change_input = input.clone();
+    let on_change = Closure::<dyn FnMut()>::new(move || {
+        let Some(file) = change_input.files().and_then(|files| files.get(0))
+        else {
+            return;
+        };
+        let name = file.name();
+        let sender = sender.clone();
+        let ctx = ctx.clone();
+        // Reading the file is asynchronous; we can't block the browser's main thread on it.
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = match JsFuture::from(file.array_buffer()).await {
+                Ok(buffer) => {
+                    let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                    log::debug!("open_file_picker(): Read {} bytes from {}", bytes.len(), name);
+                    ThreadLoadStatus::Downloaded(name, bytes)
+                }
+                Err(e) => ThreadLoadStatus::DownloadError(format!("Failed to read {}: {:?}", name, e)),
+            };
+            if sender.send(result).is_err() {
+                log::error!("open_file_picker(): Failed to send file contents");
+            }
+            ctx.request_repaint();
+        });
+    });
+
+    input.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+    // The input element holds a reference to the callback until a file is picked, so let it live on.
+    on_change.forget();
+    input.click();
+    Ok(())
+}
@@ -27,7 +27,7 @@
 
 use std::sync::{mpsc, Arc};
 
-use fluxfox::DiskImage;
+use fluxfox::{prelude::DiskCh, DiskImage};
 use fluxfox_egui::{
     controls::{
         disk_visualization::{DiskVisualization, VizEvent},
@@ -37,7 +37,7 @@ use fluxfox_egui::{
     UiEvent,
 };
 
-use crate::App;
+use crate::{view_state::VizViewState, App};
 use anyhow::Result;
 
 #[derive(Default)]
@@ -49,6 +49,8 @@ pub struct VisualizationViewer {
     show_error_layer: bool,
     show_weak_layer: bool,
     open: bool,
+    /// The track element most recently clicked by the user, as a physical track and element index.
+    selected_element: Option<(DiskCh, usize)>,
 }
 
 impl VisualizationViewer {
@@ -66,6 +68,7 @@ impl VisualizationViewer {
             show_metadata_layer: true,
             show_error_layer: false,
             show_weak_layer: false,
+            selected_element: None,
         }
     }
 
@@ -94,6 +97,43 @@ impl VisualizationViewer {
         _ = self.render()
     }
 
+    /// Return the current visualization parameters, for storing in a [ViewState].
+    ///
+    /// [ViewState]: crate::view_state::ViewState
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn view_state(&self) -> VizViewState {
+        VizViewState {
+            data_layer: self.show_data_layer,
+            metadata_layer: self.show_metadata_layer,
+            angle: self.viz.angle(),
+            quadrants: [self.viz.quadrant(0), self.viz.quadrant(1)],
+        }
+    }
+
+    /// Restore visualization parameters from a [ViewState].
+    ///
+    /// [ViewState]: crate::view_state::ViewState
+    pub fn apply_view_state(&mut self, state: &VizViewState) {
+        self.show_data_layer = state.data_layer;
+        self.viz.enable_data_layer(state.data_layer);
+        self.show_metadata_layer = state.metadata_layer;
+        self.viz.enable_metadata_layer(state.metadata_layer);
+        self.viz.set_angle(state.angle);
+        for (side, quadrant) in state.quadrants.iter().enumerate() {
+            self.viz.set_quadrant(side, *quadrant);
+        }
+    }
+
+    /// Highlight the track element at `element_idx` in the metadata of the track at `ch`.
+    pub fn select_element(&mut self, ch: DiskCh, element_idx: usize) {
+        self.viz.select_element(ch, element_idx);
+    }
+
+    /// Return the track element clicked by the user since the last call, if any.
+    pub fn take_selected_element(&mut self) -> Option<(DiskCh, usize)> {
+        self.selected_element.take()
+    }
+
     pub fn render(&mut self) -> Result<()> {
         self.viz.render_visualization(0)?;
         self.viz.render_visualization(1)?;
@@ -190,6 +230,9 @@ impl VisualizationViewer {
 
                                     self.viz.update_selection(c, h, s_idx);
                                 }
+                                VizEvent::ElementSelected { ch, element_idx } => {
+                                    self.selected_element = Some((ch, element_idx));
+                                }
                                 _ => {}
                             }
                         }
@@ -25,7 +25,7 @@
     --------------------------------------------------------------------------
 */
 use egui::Grid;
-use fluxfox::file_system::fat::fat_fs::FatFileSystem;
+use fluxfox::file_system::FileSystem;
 use fluxfox_egui::controls::data_table::DataTableWidget;
 
 #[derive(Default)]
@@ -47,7 +47,7 @@ impl FileViewer {
         }
     }
 
-    pub fn update(&mut self, fs: &FatFileSystem, path: String) {
+    pub fn update(&mut self, fs: &dyn FileSystem, path: String) {
         self.path = path;
 
         let data = match fs.read_file(&self.path) {
@@ -0,0 +1,291 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+//! A dashboard summarizing the [ImageAnalysis] of a disk image: error counts, a heatmap of bad
+//! sectors per track, detected copy protection, content hashes, and consistency warnings.
+//! Clicking a track or sector opens it in the track or sector viewer. The analysis is computed
+//! when the window is first shown for a disk, rather than on load.
+
+use crate::app::AppEvent;
+use fluxfox::{
+    hash_manifest::{ContentDigest, Fingerprint, HashManifestOptions},
+    image_analysis::ImageAnalysis,
+    prelude::*,
+};
+use fluxfox_egui::{
+    controls::{
+        error_banner::ErrorBanner,
+        track_grid::{TrackGridWidget, TrackMetric},
+    },
+    tracking_lock::TrackingLock,
+    SectorSelection,
+    TrackListSelection,
+    TrackSelection,
+    TrackSelectionScope,
+    UiLockContext,
+};
+
+pub struct ImageAnalysisViewer {
+    open: bool,
+    disk: Option<TrackingLock<DiskImage>>,
+    analysis: Option<ImageAnalysis>,
+    protection: Option<String>,
+    image_digest: ContentDigest,
+    fingerprint: Fingerprint,
+    heatmap: TrackGridWidget,
+    error_string: Option<String>,
+}
+
+impl Default for ImageAnalysisViewer {
+    fn default() -> Self {
+        Self {
+            open: false,
+            disk: None,
+            analysis: None,
+            protection: None,
+            image_digest: ContentDigest::default(),
+            fingerprint: Fingerprint::default(),
+            heatmap: TrackGridWidget::new().with_colors(egui::Color32::from_rgb(0x80, 0x40, 0x30), egui::Color32::RED),
+            error_string: None,
+        }
+    }
+}
+
+impl ImageAnalysisViewer {
+    /// Set the disk to analyze. Any previous analysis is discarded, and the new disk is analyzed
+    /// the next time the window is shown.
+    pub fn update_disk(&mut self, disk_lock: TrackingLock<DiskImage>) {
+        self.disk = Some(disk_lock);
+        self.analysis = None;
+        self.error_string = None;
+    }
+
+    fn analyze(&mut self) {
+        let Some(disk_lock) = &self.disk
+        else {
+            return;
+        };
+        let disk = match disk_lock.read(UiLockContext::ImageAnalysis) {
+            Ok(disk) => disk,
+            Err(tool) => {
+                log::warn!("Failed to acquire read lock, locked by tool: {:?}", tool);
+                self.error_string = Some("Failed to acquire disk read lock.".to_string());
+                return;
+            }
+        };
+
+        let analysis = disk.analyze();
+        self.protection = disk.detect_copy_protection().map(|scheme| scheme.to_string());
+        self.image_digest = disk.hash_manifest(&HashManifestOptions::default()).image;
+        self.fingerprint = disk.fingerprint();
+
+        let metric = TrackMetric::ErrorCount;
+        self.heatmap.update(&disk, metric.name(), |track| metric.value(track));
+
+        self.analysis = Some(analysis);
+        self.error_string = None;
+    }
+
+    pub fn open_mut(&mut self) -> &mut bool {
+        &mut self.open
+    }
+
+    /// Show the dashboard. Returns an [AppEvent] selecting a track or sector if one was clicked.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<AppEvent> {
+        // Analyzing and hashing the image is expensive, so only do it once the window is open.
+        if self.open && self.analysis.is_none() && self.error_string.is_none() {
+            self.analyze();
+        }
+
+        let mut new_event = None;
+        let mut open = self.open;
+        egui::Window::new("Image Analysis")
+            .open(&mut open)
+            .resizable(egui::Vec2b::new(true, true))
+            .show(ctx, |ui| {
+                if let Some(error_string) = &self.error_string {
+                    ErrorBanner::new(error_string).small().show(ui);
+                }
+                let Some(analysis) = &self.analysis
+                else {
+                    ui.label("No disk image loaded.");
+                    return;
+                };
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    Self::show_summary(ui, analysis, self.protection.as_deref());
+
+                    ui.separator();
+                    ui.label(egui::RichText::new("Bad Sectors").strong());
+                    if let Some(event) = Self::show_heatmap(ui, &mut self.heatmap, analysis) {
+                        new_event = Some(event);
+                    }
+
+                    ui.separator();
+                    egui::Grid::new("analysis_hashes").num_columns(2).show(ui, |ui| {
+                        ui.label("CRC32:");
+                        ui.monospace(self.image_digest.crc32_hex());
+                        ui.end_row();
+                        ui.label("SHA1:");
+                        ui.monospace(self.image_digest.sha1_hex());
+                        ui.end_row();
+                        ui.label("Fingerprint:");
+                        ui.monospace(self.fingerprint.to_string());
+                        ui.end_row();
+                    });
+
+                    ui.separator();
+                    if let Some(event) = Self::show_warnings(ui, analysis) {
+                        new_event = Some(event);
+                    }
+                });
+            });
+        self.open = open;
+        new_event
+    }
+
+    fn show_summary(ui: &mut egui::Ui, analysis: &ImageAnalysis, protection: Option<&str>) {
+        egui::Grid::new("analysis_summary").num_columns(2).show(ui, |ui| {
+            ui.label("Standard format:");
+            match analysis.standard_format {
+                Some(format) => ui.label(format.to_string()),
+                None => ui.label("Unknown"),
+            };
+            ui.end_row();
+
+            ui.label("Tracks:");
+            ui.label(analysis.track_ct.to_string());
+            ui.end_row();
+            ui.label("Sectors:");
+            ui.label(analysis.sector_ct.to_string());
+            ui.end_row();
+
+            let counts = [
+                ("Bad data CRCs:", analysis.data_crc_errors),
+                ("Bad address CRCs:", analysis.address_crc_errors),
+                ("No DAM:", analysis.no_dam),
+                ("Deleted:", analysis.deleted),
+                ("Missing sectors:", analysis.missing_sectors.len()),
+            ];
+            for (label, count) in counts {
+                ui.label(label);
+                let text = egui::RichText::new(count.to_string());
+                ui.label(match count {
+                    0 => text,
+                    _ => text.color(ui.visuals().warn_fg_color),
+                });
+                ui.end_row();
+            }
+
+            ui.label("Copy protection:");
+            match protection {
+                Some(scheme) => ui.colored_label(ui.visuals().warn_fg_color, scheme),
+                None => ui.label("None detected"),
+            };
+            ui.end_row();
+        });
+    }
+
+    /// Show the bad sector heatmap. Clicking a track with a single bad sector selects the sector,
+    /// otherwise the track.
+    fn show_heatmap(ui: &mut egui::Ui, heatmap: &mut TrackGridWidget, analysis: &ImageAnalysis) -> Option<AppEvent> {
+        let Some(TrackListSelection::Track(selection)) = heatmap.show(ui)
+        else {
+            return None;
+        };
+        let track = analysis.tracks.iter().find(|t| t.ch == selection.phys_ch)?;
+
+        match track.bad_sectors.as_slice() {
+            [sector_id] => Some(sector_event(track.ch, *sector_id)),
+            _ => Some(AppEvent::TrackSelected(selection)),
+        }
+    }
+
+    fn show_warnings(ui: &mut egui::Ui, analysis: &ImageAnalysis) -> Option<AppEvent> {
+        let mut new_event = None;
+        if analysis.is_clean() {
+            ui.label("✔ No consistency problems found.");
+            return None;
+        }
+
+        ui.label(egui::RichText::new("Warnings").strong());
+        let warn_color = ui.visuals().warn_fg_color;
+        if analysis.mixed_data_rates() {
+            let rates: Vec<String> = analysis.data_rates.iter().map(|r| r.to_string()).collect();
+            ui.colored_label(warn_color, format!("⚠ Mixed data rates: {}", rates.join(", ")));
+        }
+
+        for track in analysis.tracks.iter().filter(|t| !t.bad_sectors.is_empty()) {
+            for sector_id in &track.bad_sectors {
+                if ui
+                    .link(format!("⚠ Bad sector {} on track {}", sector_id, track.ch))
+                    .clicked()
+                {
+                    new_event = Some(sector_event(track.ch, *sector_id));
+                }
+            }
+        }
+        for missing in &analysis.missing_sectors {
+            if ui
+                .link(format!("⚠ Missing sector {} on track {}", missing.id, missing.ch))
+                .clicked()
+            {
+                new_event = Some(track_event(missing.ch));
+            }
+        }
+        for dup in &analysis.duplicate_ids {
+            let text = format!("⚠ Duplicate sector ID {} (x{}) on track {}", dup.id, dup.count, dup.ch);
+            if ui.link(text).clicked() {
+                new_event = Some(sector_event(dup.ch, dup.id));
+            }
+        }
+        for track in &analysis.nonstandard_tracks {
+            let text = format!(
+                "⚠ Nonstandard track {}: {} sectors, {} bitcells",
+                track.ch, track.sector_ct, track.bit_length
+            );
+            if ui.link(text).clicked() {
+                new_event = Some(track_event(track.ch));
+            }
+        }
+        new_event
+    }
+}
+
+fn sector_event(phys_ch: DiskCh, sector_id: DiskChsn) -> AppEvent {
+    AppEvent::SectorSelected(SectorSelection {
+        phys_ch,
+        sector_id,
+        bit_offset: None,
+    })
+}
+
+fn track_event(phys_ch: DiskCh) -> AppEvent {
+    AppEvent::TrackSelected(TrackSelection {
+        sel_scope: TrackSelectionScope::DecodedDataStream,
+        phys_ch,
+    })
+}
@@ -0,0 +1,158 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+
+//! A window to view and edit the descriptive metadata of a disk image, such as its title and
+//! comment. Image formats that can store a comment carry this metadata when the image is saved.
+
+use fluxfox::{
+    prelude::*,
+    types::{METADATA_KEY_DUMPER, METADATA_KEY_DUMP_DATE, METADATA_KEY_SOURCE_HARDWARE, METADATA_KEY_TITLE},
+};
+use fluxfox_egui::{controls::error_banner::ErrorBanner, tracking_lock::TrackingLock, UiLockContext};
+
+#[derive(Default)]
+pub struct ImageMetadataViewer {
+    open: bool,
+    disk: Option<TrackingLock<DiskImage>>,
+    // Field values being edited, in the order of `DiskImageMetadata::keys()`.
+    fields: Vec<(&'static str, String)>,
+    // The metadata as last read from or applied to the disk image.
+    applied: DiskImageMetadata,
+    error_string: Option<String>,
+}
+
+impl ImageMetadataViewer {
+    pub fn update_disk(&mut self, disk_lock: TrackingLock<DiskImage>) {
+        match disk_lock.read(UiLockContext::ImageMetadata) {
+            Ok(disk) => {
+                self.applied = disk.image_metadata();
+                self.revert();
+                self.error_string = None;
+            }
+            Err(tool) => {
+                log::warn!("Failed to acquire read lock, locked by tool: {:?}", tool);
+                self.error_string = Some("Failed to acquire disk read lock.".to_string());
+            }
+        }
+        self.disk = Some(disk_lock);
+    }
+
+    #[allow(dead_code)]
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    pub fn open_mut(&mut self) -> &mut bool {
+        &mut self.open
+    }
+
+    /// Return the metadata as currently edited.
+    fn edited(&self) -> DiskImageMetadata {
+        let mut metadata = DiskImageMetadata::default();
+        for (key, value) in &self.fields {
+            metadata.set(key, Some(value.trim()));
+        }
+        metadata
+    }
+
+    /// Discard any edits, restoring the metadata last read from or applied to the disk image.
+    fn revert(&mut self) {
+        self.fields = DiskImageMetadata::keys()
+            .into_iter()
+            .map(|key| (key, self.applied.get(key).unwrap_or_default().to_string()))
+            .collect();
+    }
+
+    fn apply(&mut self) {
+        let Some(disk_lock) = &self.disk
+        else {
+            return;
+        };
+        let metadata = self.edited();
+        match disk_lock.write(UiLockContext::ImageMetadata) {
+            Ok(mut disk) => disk.set_image_metadata(&metadata),
+            Err(tools) => {
+                log::warn!("Failed to acquire write lock, locked by tools: {:?}", tools);
+                self.error_string = Some("Failed to acquire disk write lock.".to_string());
+                return;
+            }
+        }
+        self.applied = metadata;
+        self.revert();
+        self.error_string = None;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new("Image Metadata")
+            .open(&mut open)
+            .resizable(egui::Vec2b::new(true, true))
+            .show(ctx, |ui| {
+                if self.disk.is_none() {
+                    ui.label("No disk image loaded.");
+                    return;
+                }
+                if let Some(error_string) = &self.error_string {
+                    ErrorBanner::new(error_string).small().show(ui);
+                }
+
+                egui::Grid::new("image_metadata_grid").num_columns(2).show(ui, |ui| {
+                    for (key, value) in self.fields.iter_mut() {
+                        ui.label(format!("{}:", field_label(key)));
+                        if *key == fluxfox::types::METADATA_KEY_COMMENT {
+                            ui.add(egui::TextEdit::multiline(value).desired_rows(4));
+                        }
+                        else {
+                            ui.add(egui::TextEdit::singleline(value));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                let modified = self.edited() != self.applied;
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(modified, egui::Button::new("Apply")).clicked() {
+                        self.apply();
+                    }
+                    if ui.add_enabled(modified, egui::Button::new("Revert")).clicked() {
+                        self.revert();
+                    }
+                });
+            });
+        self.open = open;
+    }
+}
+
+fn field_label(key: &str) -> &'static str {
+    match key {
+        METADATA_KEY_TITLE => "Title",
+        METADATA_KEY_DUMP_DATE => "Dump Date",
+        METADATA_KEY_DUMPER => "Dumper",
+        METADATA_KEY_SOURCE_HARDWARE => "Source Hardware",
+        _ => "Comment",
+    }
+}
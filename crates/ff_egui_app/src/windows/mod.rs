@@ -28,8 +28,12 @@
 pub mod disk_visualization;
 pub mod element_map;
 pub mod file_viewer;
+pub mod image_analysis;
+pub mod image_metadata;
 pub mod new_viz;
+pub mod save_as;
 pub mod sector_viewer;
 pub mod source_map;
+pub mod track_metadata;
 pub mod track_timing_viewer;
 pub mod track_viewer;
@@ -0,0 +1,302 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+
+//! A dialog to save the loaded disk image in a chosen format. Format-specific write options are
+//! shown for the selected format, along with a warning listing any features of the image the
+//! format cannot represent, before anything is written.
+
+use crate::App;
+use fluxfox::{io::Cursor, prelude::*};
+use fluxfox_egui::{controls::error_banner::ErrorBanner, tracking_lock::TrackingLock, UiLockContext};
+use strum::IntoEnumIterator;
+
+/// A writable image format and its write compatibility with the loaded disk image.
+struct FormatEntry {
+    format: DiskImageFileFormat,
+    compatibility: ParserWriteCompatibility,
+    // Descriptions of the image features the format cannot represent.
+    lost_caps: Vec<&'static str>,
+    // The resolution of the image's track data, if the format stores a lower resolution.
+    lost_resolution: Option<TrackDataResolution>,
+}
+
+pub struct SaveAsDialog {
+    open: bool,
+    disk: Option<TrackingLock<DiskImage>>,
+    file_stem: String,
+    formats: Vec<FormatEntry>,
+    selected: usize,
+    raw_fill: RawFillPolicy,
+    flux_revolutions: usize,
+    error_string: Option<String>,
+}
+
+impl Default for SaveAsDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            disk: None,
+            file_stem: String::new(),
+            formats: Vec::new(),
+            selected: 0,
+            raw_fill: RawFillPolicy::default(),
+            flux_revolutions: FluxSynthesisOptions::default().revolutions,
+            error_string: None,
+        }
+    }
+}
+
+impl SaveAsDialog {
+    pub fn update_disk(&mut self, disk_lock: TrackingLock<DiskImage>, name: Option<String>) {
+        self.file_stem = name
+            .as_deref()
+            .and_then(|name| std::path::Path::new(name).file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "disk".to_string());
+
+        match disk_lock.read(UiLockContext::ImageExport) {
+            Ok(disk) => {
+                self.formats = Self::writable_formats(&disk);
+                // Select the format the image was loaded from if it can be written, otherwise the
+                // first format that can hold the image without loss.
+                self.selected = disk
+                    .source_format()
+                    .and_then(|source| self.formats.iter().position(|entry| entry.format == source))
+                    .or_else(|| {
+                        self.formats
+                            .iter()
+                            .position(|entry| entry.compatibility == ParserWriteCompatibility::Ok)
+                    })
+                    .unwrap_or(0);
+                self.error_string = None;
+            }
+            Err(tool) => {
+                log::warn!("Failed to acquire read lock, locked by tool: {:?}", tool);
+                self.error_string = Some("Failed to acquire disk read lock.".to_string());
+            }
+        }
+        self.disk = Some(disk_lock);
+    }
+
+    /// Build the list of formats fluxfox can write, with their compatibility with `disk`. Formats
+    /// are sorted by compatibility, then by priority.
+    fn writable_formats(disk: &DiskImage) -> Vec<FormatEntry> {
+        let required_caps = disk.required_caps();
+        let best_resolution = disk.resolution().into_iter().max().unwrap_or_default();
+
+        let mut formats: Vec<FormatEntry> = DiskImageFileFormat::iter()
+            .filter(|format| format.can_write(None) != ParserWriteCompatibility::UnsupportedFormat)
+            .map(|format| FormatEntry {
+                format,
+                compatibility: format.can_write(Some(disk)),
+                lost_caps: required_caps.difference(format.capabilities()).descriptions(),
+                lost_resolution: (format.resolution() < best_resolution).then_some(best_resolution),
+            })
+            .collect();
+
+        formats.sort_by_key(|entry| {
+            let rank = match entry.compatibility {
+                ParserWriteCompatibility::Ok => 0,
+                ParserWriteCompatibility::DataLoss => 1,
+                _ => 2,
+            };
+            (rank, std::cmp::Reverse(entry.format.priority()))
+        });
+        formats
+    }
+
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    fn file_name(&self, format: DiskImageFileFormat) -> String {
+        match format.extensions().first() {
+            Some(ext) => format!("{}.{}", self.file_stem, ext),
+            None => self.file_stem.clone(),
+        }
+    }
+
+    fn write_options(&self, format: DiskImageFileFormat) -> ParserWriteOptions {
+        let options = ParserWriteOptions::default().with_flux_revolutions(self.flux_revolutions);
+        match format {
+            DiskImageFileFormat::RawSectorImage => options.with_raw_fill(self.raw_fill),
+            _ => options,
+        }
+    }
+
+    fn save(&mut self) {
+        let (Some(disk_lock), Some(entry)) = (&self.disk, self.formats.get(self.selected))
+        else {
+            return;
+        };
+        let format = entry.format;
+        let options = self.write_options(format);
+
+        let mut buf = Cursor::new(Vec::new());
+        let result = match disk_lock.write(UiLockContext::ImageExport) {
+            Ok(mut disk) => format.save_image(&mut disk, &options, &mut buf),
+            Err(tools) => {
+                log::warn!("Failed to acquire write lock, locked by tools: {:?}", tools);
+                self.error_string = Some("Failed to acquire disk write lock.".to_string());
+                return;
+            }
+        };
+        if let Err(e) = result {
+            log::error!("Error encoding {} image: {}", format, e);
+            self.error_string = Some(format!("Error encoding {} image: {}", format, e));
+            return;
+        }
+
+        let file_name = self.file_name(format);
+        match App::save_file_as(&file_name, buf.get_ref()) {
+            Ok(_) => {
+                log::info!("Image {} saved successfully!", file_name);
+                self.error_string = None;
+                self.open = false;
+            }
+            Err(e) => {
+                log::error!("Error saving image: {:?}", e);
+                self.error_string = Some(format!("Error saving image: {}", e));
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new("Save As")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if self.disk.is_none() {
+                    ui.label("No disk image loaded.");
+                    return;
+                }
+                if let Some(error_string) = &self.error_string {
+                    ErrorBanner::new(error_string).small().show(ui);
+                }
+                if self.formats.is_empty() {
+                    ui.label("No writable image formats are available.");
+                    return;
+                }
+
+                egui::Grid::new("save_as_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("File name:");
+                    ui.add(egui::TextEdit::singleline(&mut self.file_stem));
+                    ui.end_row();
+
+                    ui.label("Format:");
+                    egui::ComboBox::from_id_salt("save_as_format")
+                        .selected_text(self.formats[self.selected].format.to_string())
+                        .show_ui(ui, |ui| {
+                            for (fi, entry) in self.formats.iter().enumerate() {
+                                let label = match entry.compatibility {
+                                    ParserWriteCompatibility::Ok => entry.format.to_string(),
+                                    ParserWriteCompatibility::DataLoss => format!("{} ⚠", entry.format),
+                                    _ => format!("{} 🗙", entry.format),
+                                };
+                                ui.selectable_value(&mut self.selected, fi, label);
+                            }
+                        });
+                    ui.end_row();
+
+                    self.show_format_options(ui);
+                });
+
+                ui.separator();
+                let compatible = self.show_lossiness(ui);
+
+                ui.horizontal(|ui| {
+                    let format = self.formats[self.selected].format;
+                    let button_text = format!("Save {}", self.file_name(format));
+                    if ui.add_enabled(compatible, egui::Button::new(button_text)).clicked() {
+                        self.save();
+                    }
+                });
+            });
+        self.open &= open;
+    }
+
+    /// Show the options specific to the selected format as rows of the options grid.
+    fn show_format_options(&mut self, ui: &mut egui::Ui) {
+        let format = self.formats[self.selected].format;
+        if format == DiskImageFileFormat::RawSectorImage {
+            ui.label("Fill byte:");
+            ui.add(egui::DragValue::new(&mut self.raw_fill.fill_byte).hexadecimal(2, false, true))
+                .on_hover_text("Written for sectors on unformatted tracks and to pad short sectors.");
+            ui.end_row();
+
+            ui.label("");
+            ui.checkbox(&mut self.raw_fill.fill_unreadable, "Fill unreadable sectors")
+                .on_hover_text("Write the fill byte for sectors that cannot be read instead of failing.");
+            ui.end_row();
+        }
+        if format.resolution() == TrackDataResolution::FluxStream {
+            ui.label("Revolutions:");
+            ui.add(egui::DragValue::new(&mut self.flux_revolutions).range(1..=10))
+                .on_hover_text("The number of revolutions of flux to synthesize per track.");
+            ui.end_row();
+        }
+    }
+
+    /// Show a warning describing what would be lost writing the image in the selected format.
+    /// Returns false if the image cannot be written in the selected format at all.
+    fn show_lossiness(&self, ui: &mut egui::Ui) -> bool {
+        let entry = &self.formats[self.selected];
+        let warn_color = ui.visuals().warn_fg_color;
+
+        if entry.compatibility == ParserWriteCompatibility::Incompatible {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("🗙 This image cannot be written as {}.", entry.format),
+            );
+            return false;
+        }
+        if entry.lost_caps.is_empty() && entry.lost_resolution.is_none() {
+            ui.label(format!("✔ {} can represent this image without loss.", entry.format));
+            return true;
+        }
+
+        ui.colored_label(
+            warn_color,
+            format!("⚠ Saving as {} will lose the following from this image:", entry.format),
+        );
+        if let Some(resolution) = entry.lost_resolution {
+            ui.colored_label(
+                warn_color,
+                format!(
+                    "  • {:?} track data (saved as {:?})",
+                    resolution,
+                    entry.format.resolution()
+                ),
+            );
+        }
+        for cap in &entry.lost_caps {
+            ui.colored_label(warn_color, format!("  • {}", cap));
+        }
+        true
+    }
+}
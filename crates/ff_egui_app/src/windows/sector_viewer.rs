@@ -65,8 +65,8 @@ impl SectorViewer {
     }
 
     pub fn update(&mut self, disk_lock: TrackingLock<DiskImage>, selection: SectorSelection) {
-        match disk_lock.write(UiLockContext::SectorViewer) {
-            Ok(mut disk) => {
+        match disk_lock.read(UiLockContext::SectorViewer) {
+            Ok(disk) => {
                 self.phys_ch = selection.phys_ch;
                 let query = SectorIdQuery::new(
                     selection.sector_id.c(),
@@ -108,11 +108,9 @@ impl SectorViewer {
                     self.valid = false;
                 }
             }
-            Err(e) => {
-                for tool in e {
-                    log::warn!("Failed to acquire write lock, locked by tool: {:?}", tool);
-                }
-                self.error_string = Some("Failed to acquire disk write lock.".to_string());
+            Err(tool) => {
+                log::warn!("Failed to acquire read lock, locked by tool: {:?}", tool);
+                self.error_string = Some("Failed to acquire disk read lock.".to_string());
                 self.valid = false;
             }
         }
@@ -0,0 +1,217 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+//! A window listing the elements of a track's [TrackMetadata], as found by the track schema
+//! parser. Selecting an element highlights it on the disk visualization, and clicking an element
+//! on the disk visualization selects it here.
+
+use fluxfox::{prelude::*, track_schema::GenericTrackElement};
+use fluxfox_egui::{controls::error_banner::ErrorBanner, tracking_lock::TrackingLock, TrackSelection, UiLockContext};
+use std::ops::Range;
+
+// A row of the element table.
+struct ElementRow {
+    element_type: GenericTrackElement,
+    chsn: Option<DiskChsn>,
+    range: Range<usize>,
+}
+
+impl ElementRow {
+    /// Return whether the CRC of the element is valid, or `None` if the element has no CRC.
+    fn crc_valid(&self) -> Option<bool> {
+        match self.element_type {
+            GenericTrackElement::SectorHeader
+            | GenericTrackElement::SectorData
+            | GenericTrackElement::SectorDeletedData => Some(true),
+            GenericTrackElement::SectorBadHeader
+            | GenericTrackElement::SectorBadData
+            | GenericTrackElement::SectorBadDeletedData => Some(false),
+            GenericTrackElement::NullElement | GenericTrackElement::Marker => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TrackMetadataViewer {
+    open: bool,
+    phys_ch: DiskCh,
+    rows: Vec<ElementRow>,
+    selected: Option<usize>,
+    scroll_to_selected: bool,
+    error_string: Option<String>,
+}
+
+impl TrackMetadataViewer {
+    pub fn update(&mut self, disk_lock: TrackingLock<DiskImage>, selection: TrackSelection) {
+        self.phys_ch = selection.phys_ch;
+        self.rows.clear();
+        self.selected = None;
+
+        match disk_lock.read(UiLockContext::TrackMetadataViewer) {
+            Ok(disk) => match disk.track(selection.phys_ch).and_then(|track| track.metadata()) {
+                Some(metadata) => {
+                    self.rows = metadata
+                        .elements()
+                        .iter()
+                        .map(|instance| ElementRow {
+                            element_type: GenericTrackElement::from(instance.element()),
+                            chsn: instance.element().chsn(),
+                            range: instance.range(),
+                        })
+                        .collect();
+                    self.error_string = None;
+                }
+                None => {
+                    self.error_string = Some(format!("No metadata for track {}.", selection.phys_ch));
+                }
+            },
+            Err(tool) => {
+                log::warn!("Failed to acquire read lock, locked by tool: {:?}", tool);
+                self.error_string = Some("Failed to acquire disk read lock.".to_string());
+            }
+        }
+    }
+
+    /// Return the physical track whose metadata is displayed.
+    pub fn phys_ch(&self) -> DiskCh {
+        self.phys_ch
+    }
+
+    /// Select the element at `element_idx` and scroll it into view.
+    pub fn select_element(&mut self, element_idx: usize) {
+        if element_idx < self.rows.len() {
+            self.selected = Some(element_idx);
+            self.scroll_to_selected = true;
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    /// Show the window. Returns the index of the element the user clicked on, if any.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<usize> {
+        let mut clicked = None;
+        let mut open = self.open;
+        egui::Window::new(format!("Track Metadata: {}", self.phys_ch))
+            .id(egui::Id::new("track_metadata_viewer"))
+            .open(&mut open)
+            .resizable(egui::Vec2b::new(true, true))
+            .show(ctx, |ui| {
+                if let Some(error_string) = &self.error_string {
+                    ErrorBanner::new(error_string).small().show(ui);
+                    return;
+                }
+                clicked = self.show_table(ui);
+            });
+        self.open = open;
+
+        if clicked.is_some() {
+            self.selected = clicked;
+        }
+        clicked
+    }
+
+    fn show_table(&mut self, ui: &mut egui::Ui) -> Option<usize> {
+        use egui_extras::{Column, TableBuilder};
+
+        let mut clicked = None;
+        let text_height = egui::TextStyle::Body
+            .resolve(ui.style())
+            .size
+            .max(ui.spacing().interact_size.y);
+
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .sense(egui::Sense::click())
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::auto())
+            .column(Column::auto().at_least(140.0))
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::remainder())
+            .min_scrolled_height(0.0);
+
+        if self.scroll_to_selected {
+            if let Some(selected) = self.selected {
+                table = table.scroll_to_row(selected, Some(egui::Align::Center));
+            }
+            self.scroll_to_selected = false;
+        }
+
+        table
+            .header(20.0, |mut header| {
+                for title in ["#", "Type", "Sector ID", "Start", "End", "CRC"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(text_height, self.rows.len(), |mut row| {
+                    let index = row.index();
+                    let element = &self.rows[index];
+                    row.set_selected(self.selected == Some(index));
+
+                    row.col(|ui| {
+                        ui.label(index.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(element.element_type.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(element.chsn.map(|chsn| chsn.to_string()).unwrap_or_default());
+                    });
+                    row.col(|ui| {
+                        ui.monospace(element.range.start.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.monospace(element.range.end.to_string());
+                    });
+                    row.col(|ui| match element.crc_valid() {
+                        Some(true) => {
+                            ui.label("Valid");
+                        }
+                        Some(false) => {
+                            ui.colored_label(ui.visuals().error_fg_color, "Bad");
+                        }
+                        None => {}
+                    });
+
+                    if row.response().clicked() {
+                        clicked = Some(index);
+                    }
+                });
+            });
+        clicked
+    }
+}
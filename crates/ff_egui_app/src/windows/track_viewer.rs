@@ -26,6 +26,7 @@
 */
 use crate::app::Tool;
 use fluxfox::{
+    bitstream_codec::TrackCodec,
     prelude::*,
     track::{DiskTrack, TrackInfo},
     track_schema::{TrackElementInstance, TrackSchema},
@@ -138,29 +139,51 @@ impl TrackViewer {
             self.table.set_data(&rtr.read_buf);
             self.valid = true;
 
+            // Track element ranges are in bitcells. Each decoded byte is 16 bitcells for FM and MFM.
             if let Some(metadata) = track.metadata() {
                 for item in metadata.header_ranges() {
-                    //let offset_range = (item.start + offset as usize)..(item.end + offset as usize);
-
-                    let range = DataRange {
-                        name: "Sector Header".to_string(),
-                        range: (item.start / 16)..(item.end / 16).saturating_sub(1),
-                        fg_color: egui::Color32::from_rgb(0xff, 0x53, 0x53),
-                    };
-                    self.table.add_range(range);
+                    self.table.add_range(DataRange::new(
+                        "Sector Header",
+                        (item.start / 16)..(item.end / 16),
+                        egui::Color32::from_rgb(0xff, 0x53, 0x53),
+                    ));
                 }
 
                 for item in metadata.marker_ranges() {
-                    //let offset_range = (item.start + offset as usize)..(item.end + offset as usize);
-
-                    let range = DataRange {
-                        name: "Marker".to_string(),
-                        range: (item.start / 16)..(item.end / 16).saturating_sub(1),
-                        fg_color: egui::Color32::from_rgb(0x53, 0xdd, 0xff),
-                    };
-                    self.table.add_range(range);
+                    self.table.add_range(DataRange::new(
+                        "Address Mark",
+                        (item.start / 16)..(item.end / 16),
+                        egui::Color32::from_rgb(0x53, 0xdd, 0xff),
+                    ));
+                }
+
+                for instance in metadata.elements() {
+                    let element = instance.element();
+                    if !element.is_sector_header() && !element.is_sector_data() {
+                        continue;
+                    }
+                    if let Some(crc) = element.range(RwScope::CrcOnly) {
+                        let start = instance.range().start / 16;
+                        self.table.add_range(
+                            DataRange::new(
+                                "CRC",
+                                (start + crc.start)..(start + crc.end),
+                                egui::Color32::from_rgb(0xff, 0xd7, 0x53),
+                            )
+                            .with_bg_color(egui::Color32::from_rgb(0x40, 0x38, 0x10)),
+                        );
+                    }
                 }
             };
+
+            if let Some(stream) = track.stream() {
+                for weak in weak_byte_ranges(stream.weak_mask().iter()) {
+                    self.table.add_range(
+                        DataRange::new("Weak Bytes", weak, egui::Color32::WHITE)
+                            .with_bg_color(egui::Color32::from_rgb(0x80, 0x30, 0x80)),
+                    );
+                }
+            }
         }
     }
 
@@ -242,3 +265,20 @@ impl TrackViewer {
         });
     }
 }
+
+/// Convert a weak bit mask into ranges of decoded bytes containing weak bits, assuming 16
+/// bitcells per byte.
+fn weak_byte_ranges(mask: impl Iterator<Item = bool>) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (bit_idx, weak) in mask.enumerate() {
+        if !weak {
+            continue;
+        }
+        let byte_idx = bit_idx / 16;
+        match ranges.last_mut() {
+            Some(last) if last.end >= byte_idx => last.end = byte_idx + 1,
+            _ => ranges.push(byte_idx..byte_idx + 1),
+        }
+    }
+    ranges
+}
@@ -38,13 +38,37 @@ use crate::{
 use egui_extras::{Column, TableBuilder};
 use strum::IntoEnumIterator;
 
+/// The choices of bytes per row offered by the [DataTableWidget] hex view.
+pub const BYTES_PER_ROW_OPTIONS: [usize; 4] = [8, 16, 32, 64];
+
+/// A named range of bytes to highlight in a [DataTableWidget], such as a CRC field, an address
+/// mark or a run of weak bytes. Hovering a highlighted byte shows the range name.
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct DataRange {
     pub name: String,
     pub fg_color: egui::Color32,
+    /// An optional color to fill the background of the range with.
+    pub bg_color: Option<egui::Color32>,
+    /// The byte offsets of the range. The end is exclusive.
     pub range: Range<usize>,
 }
 
+impl DataRange {
+    pub fn new(name: &str, range: Range<usize>, fg_color: egui::Color32) -> Self {
+        Self {
+            name: name.to_string(),
+            fg_color,
+            bg_color: None,
+            range,
+        }
+    }
+
+    pub fn with_bg_color(mut self, bg_color: egui::Color32) -> Self {
+        self.bg_color = Some(bg_color);
+        self
+    }
+}
+
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct DataTableWidget {
     encoding: CharacterEncoding,
@@ -87,8 +111,28 @@ impl Default for DataTableWidget {
 }
 
 impl DataTableWidget {
+    /// Highlight a range of bytes. Ranges are cleared by [DataTableWidget::set_data]. Empty ranges
+    /// are ignored.
     pub fn add_range(&mut self, range: DataRange) {
-        self.ranges.push(range);
+        if !range.range.is_empty() {
+            self.ranges.push(range);
+        }
+    }
+
+    pub fn clear_ranges(&mut self) {
+        self.ranges.clear();
+    }
+
+    pub fn with_bytes_per_row(mut self, bytes_per_row: usize) -> Self {
+        self.set_bytes_per_row(bytes_per_row);
+        self
+    }
+
+    /// Set the number of bytes shown on each row of the hex view.
+    pub fn set_bytes_per_row(&mut self, bytes_per_row: usize) {
+        self.num_columns = bytes_per_row.max(1);
+        self.selection.clear();
+        self.calc_layout();
     }
 
     pub fn show(&mut self, ui: &mut egui::Ui) {
@@ -100,7 +144,20 @@ impl DataTableWidget {
                         ui.selectable_value(&mut self.encoding, encoding, encoding.to_string());
                     }
                 });
+
+            let mut bytes_per_row = self.num_columns;
+            egui::ComboBox::from_id_salt("BytesPerRow")
+                .selected_text(format!("{} bytes/row", bytes_per_row))
+                .show_ui(ui, |ui| {
+                    for option in BYTES_PER_ROW_OPTIONS {
+                        ui.selectable_value(&mut bytes_per_row, option, format!("{} bytes/row", option));
+                    }
+                });
+            if bytes_per_row != self.num_columns {
+                self.set_bytes_per_row(bytes_per_row);
+            }
         });
+        self.legend_ui(ui);
         self.tabs.show(ui);
         ui.separator();
 
@@ -130,6 +187,28 @@ impl DataTableWidget {
         }
     }
 
+    /// Show a swatch and name for each distinct range name.
+    fn legend_ui(&mut self, ui: &mut egui::Ui) {
+        if self.ranges.is_empty() {
+            return;
+        }
+        let mut names: Vec<&str> = Vec::new();
+        ui.horizontal_wrapped(|ui| {
+            for range in &self.ranges {
+                if names.contains(&range.name.as_str()) {
+                    continue;
+                }
+                names.push(&range.name);
+                let mut swatch = egui::RichText::new("■").color(range.fg_color);
+                if let Some(bg_color) = range.bg_color {
+                    swatch = swatch.background_color(bg_color);
+                }
+                ui.label(swatch);
+                ui.label(&range.name);
+            }
+        });
+    }
+
     fn viz_ui(&mut self, ui: &mut egui::Ui) {
         if let Some(viz_widget) = &mut self.viz_widget {
             let (_, start) = viz_widget.get_address();
@@ -226,12 +305,12 @@ impl DataTableWidget {
                 table.reset();
             }
 
-            // Create range checker
+            // Create range checker. The range checker takes inclusive ranges.
             let range_checker = RangeChecker::new(
                 &self
                     .ranges
                     .iter()
-                    .map(|r| (r.range.start, r.range.end))
+                    .map(|r| (r.range.start, r.range.end - 1))
                     .collect::<Vec<_>>(),
             );
 
@@ -264,29 +343,24 @@ impl DataTableWidget {
                             ui.label(egui::RichText::new(formatted).monospace());
                         });
                         row.col(|ui| {
-                            for (ei, element) in self.row_elements_hex(row_index).into_iter().enumerate() {
+                            for (ei, element) in self
+                                .row_elements_hex(row_index, &range_checker)
+                                .into_iter()
+                                .enumerate()
+                            {
                                 let element_address = row_index * self.num_columns + ei;
 
-                                let mut hit_range = false;
-                                let mut range_text = String::new();
-                                let mut fg_color = ui.visuals().text_color();
-                                if let Some(idx) = range_checker.contains(element_address) {
-                                    if let Some(range) = self.ranges.get(idx) {
-                                        hit_range = true;
-                                        range_text = range.name.clone();
-                                        fg_color = range.fg_color;
-                                    }
-                                }
-
-                                ui.visuals_mut().override_text_color = Some(fg_color);
                                 let response = ui.add(element);
                                 if response.hovered() {
                                     self.hover_address = Some(element_address);
 
-                                    if hit_range {
+                                    if let Some(range) = range_checker
+                                        .contains(element_address)
+                                        .and_then(|idx| self.ranges.get(idx))
+                                    {
                                         response.show_tooltip_ui(|ui| {
                                             ui.horizontal(|ui| {
-                                                ui.label(range_text);
+                                                ui.label(&range.name);
                                             });
                                         });
                                     }
@@ -294,13 +368,12 @@ impl DataTableWidget {
                                     row_hovered_idx = Some(ei);
                                     any_row_hovered_idx = Some(ei);
                                 }
-                                ui.visuals_mut().override_text_color = None;
                             }
                             //ui.label(self.row_string_hex(row_index));
                         });
                         row.col(|ui| {
                             for (ei, element) in self
-                                .row_elements_ascii(row_index, row_hovered_idx)
+                                .row_elements_ascii(row_index, row_hovered_idx, &range_checker)
                                 .into_iter()
                                 .enumerate()
                             {
@@ -334,7 +407,20 @@ impl DataTableWidget {
         self.data.len()
     }
 
-    fn row_elements_hex(&mut self, row_index: usize) -> Vec<egui::Label> {
+    /// Apply the colors of the range containing `address`, if any, to `text`.
+    fn highlight(&self, text: egui::RichText, address: usize, range_checker: &RangeChecker) -> egui::RichText {
+        match range_checker.contains(address).and_then(|idx| self.ranges.get(idx)) {
+            Some(DataRange {
+                fg_color,
+                bg_color: Some(bg_color),
+                ..
+            }) => text.color(*fg_color).background_color(*bg_color),
+            Some(range) => text.color(range.fg_color),
+            None => text,
+        }
+    }
+
+    fn row_elements_hex(&mut self, row_index: usize, range_checker: &RangeChecker) -> Vec<egui::Label> {
         let data_index = row_index * self.num_columns;
         if data_index >= self.data.len() {
             return vec![];
@@ -342,16 +428,24 @@ impl DataTableWidget {
         let data_slice = &self.data[data_index..std::cmp::min(data_index + self.num_columns, self.data.len())];
 
         let mut row_elements = Vec::new();
-        for byte in data_slice {
-            row_elements.push(egui::Label::new(
-                egui::RichText::new(format!("{:02X}", byte)).monospace(),
-            ));
+        for (bi, byte) in data_slice.iter().enumerate() {
+            let label_text = egui::RichText::new(format!("{:02X}", byte)).monospace();
+            row_elements.push(egui::Label::new(self.highlight(
+                label_text,
+                data_index + bi,
+                range_checker,
+            )));
         }
 
         row_elements
     }
 
-    fn row_elements_ascii(&mut self, row_index: usize, hovered: Option<usize>) -> Vec<egui::Label> {
+    fn row_elements_ascii(
+        &mut self,
+        row_index: usize,
+        hovered: Option<usize>,
+        range_checker: &RangeChecker,
+    ) -> Vec<egui::Label> {
         let data_index = row_index * self.num_columns;
         if data_index >= self.data.len() {
             return vec![];
@@ -361,7 +455,7 @@ impl DataTableWidget {
         let mut row_elements = Vec::new();
         for (bi, byte) in data_slice.iter().enumerate() {
             let char = self.encoding.display_byte(*byte);
-            let mut label_text = egui::RichText::new(char).monospace();
+            let mut label_text = self.highlight(egui::RichText::new(char).monospace(), data_index + bi, range_checker);
             if Some(bi) == hovered {
                 label_text = label_text.strong();
             }
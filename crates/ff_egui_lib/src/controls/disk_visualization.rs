@@ -85,8 +85,18 @@ pub enum RenderMessage {
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum VizEvent {
-    NewSectorSelected { c: u8, h: u8, s_idx: u8 },
+    NewSectorSelected {
+        c: u8,
+        h: u8,
+        s_idx: u8,
+    },
     SectorDeselected,
+    /// The user clicked on a track element. `element_idx` is the index of the element within the
+    /// metadata of the track at `ch`.
+    ElementSelected {
+        ch: DiskCh,
+        element_idx: usize,
+    },
 }
 
 struct VisualizationContext<'a> {
@@ -101,6 +111,8 @@ struct VisualizationContext<'a> {
     common_viz_params: &'a mut CommonVizParams,
     selection: &'a mut Option<SelectionContext>,
     hover_selection: Option<SelectionContext>,
+    /// The track and bit offset under the mouse cursor, whether or not it is over an element.
+    hover_position: Option<DiskSurfacePosition>,
 }
 
 #[derive(Clone)]
@@ -484,6 +496,69 @@ impl DiskVisualization {
         self.zoom_quadrant[side] = quadrant;
     }
 
+    /// Return the zoomed quadrant of the specified side, or `None` if the side is not zoomed.
+    pub fn quadrant(&self, side: usize) -> Option<usize> {
+        self.zoom_quadrant.get(side).copied().flatten()
+    }
+
+    /// Return the rotation angle of the visualization, in radians.
+    pub fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    /// Set the rotation angle of the visualization, in radians.
+    pub fn set_angle(&mut self, angle: f32) {
+        self.angle = angle.rem_euclid(TAU);
+    }
+
+    /// Select the track element at `element_idx` in the metadata of the track at `ch`, as if the
+    /// user had clicked on it. This allows other tools to highlight an element on the disk.
+    pub fn select_element(&mut self, ch: DiskCh, element_idx: usize) {
+        let Some(disk) = self
+            .disk
+            .as_ref()
+            .and_then(|d| d.read(UiLockContext::DiskVisualization).ok())
+        else {
+            log::warn!("select_element(): Couldn't lock disk for reading.");
+            return;
+        };
+
+        let mut params = self.common_viz_params.clone();
+        params.direction = TurningDirection::from(ch.h());
+        params.radius = Some(VIZ_RESOLUTION as f32 / 2.0);
+
+        match vectorize_disk_element(
+            &disk,
+            &params,
+            ch,
+            element_idx,
+            RenderGeometry::Arc,
+            VizElementFlags::HIGHLIGHT,
+        ) {
+            Ok(Some(display_list)) => {
+                if let Some(item) = display_list.items(0).and_then(|items| items.first()) {
+                    let element_range = item.info.bit_range.clone().unwrap_or(0..0);
+                    self.selection = Some(SelectionContext {
+                        mouse_pos: Pos2::ZERO,
+                        side: ch.h(),
+                        c: ch.c(),
+                        bitcell_idx: element_range.start,
+                        angle: 0.0,
+                        element_type: item.info.element_type,
+                        element_range,
+                        element_idx,
+                        element_chsn: item.info.chsn,
+                    });
+                }
+                self.selection_display_list = Some(display_list);
+            }
+            Ok(None) => {
+                log::warn!("select_element(): No element {} on track {}", element_idx, ch);
+            }
+            Err(e) => log::error!("Error vectorizing element: {}", e),
+        }
+    }
+
     pub fn clear_selection(&mut self, side: usize) {
         if let Ok(mut pixmap) = self.selection_img[side].try_lock() {
             pixmap.fill(Color::TRANSPARENT);
@@ -758,6 +833,7 @@ impl DiskVisualization {
             common_viz_params: &mut self.common_viz_params,
             selection: &mut self.selection,
             hover_selection: None,
+            hover_position: None,
         };
 
         ui.horizontal(|ui| {
@@ -804,11 +880,9 @@ impl DiskVisualization {
                                 }
 
                                 if !*context.context_menu_open {
-                                    if let Some(selection) = &context.hover_selection {
+                                    if let Some(position) = &context.hover_position {
                                         response.show_tooltip_ui(|ui| {
-                                            ui.horizontal(|ui| {
-                                                ui.label(format!("{}", selection.element_type));
-                                            });
+                                            Self::show_hover_tooltip(ui, position, context.hover_selection.as_ref());
                                         });
                                     }
                                 }
@@ -838,6 +912,10 @@ impl DiskVisualization {
                                         log::warn!("No UI sender available!");
                                     }
 
+                                    new_event = Some(VizEvent::ElementSelected {
+                                        ch: DiskCh::new(selection.c, selection.side),
+                                        element_idx: selection.element_idx,
+                                    });
                                     *context.selection = Some(selection.clone());
                                     *context.display_list_opt = context.hover_display_list_opt.clone();
                                 }
@@ -1054,6 +1132,35 @@ impl DiskVisualization {
             });
     }
 
+    /// Show the track and bit offset under the cursor, and the element there, if any. Clicking a
+    /// sector element opens it in the sector viewer.
+    fn show_hover_tooltip(ui: &mut egui::Ui, position: &DiskSurfacePosition, selection: Option<&SelectionContext>) {
+        egui::Grid::new("viz_hover_tooltip_grid").show(ui, |ui| {
+            ui.label("Track:");
+            ui.add(ChsWidget::from_ch(position.ch));
+            ui.end_row();
+
+            ui.label("Bit offset:");
+            ui.label(format!("{}", position.bit_index));
+            ui.end_row();
+
+            if let Some(selection) = selection {
+                ui.label("Element:");
+                ui.label(format!("{}", selection.element_type));
+                ui.end_row();
+
+                if let Some(chsn) = selection.element_chsn {
+                    ui.label("Sector ID:");
+                    ui.add(ChsWidget::from_chs(chsn.into()));
+                    ui.end_row();
+                }
+            }
+        });
+        if selection.is_some_and(|selection| selection.element_chsn.is_some()) {
+            ui.weak("Click to view sector");
+        }
+    }
+
     fn combine_transforms(transform_a: &RectTransform, transform_b: &RectTransform) -> RectTransform {
         // Combine transformations by chaining their mappings
         RectTransform::from_to(*transform_a.from(), transform_b.transform_rect(*transform_a.to()))
@@ -1070,6 +1177,7 @@ impl DiskVisualization {
 
         match vectorize_disk_hit_test(disk, context.common_viz_params, params, VizElementFlags::HIGHLIGHT) {
             Ok(hit) => {
+                context.hover_position = hit.position;
                 // ui.label(format!("angle: {:.3}", hit.angle));
                 // ui.label(format!("Cylinder: {}", hit.track));
                 // ui.label(format!("bit index: {}", hit.bit_index));
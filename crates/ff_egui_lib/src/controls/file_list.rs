@@ -27,13 +27,26 @@
 use crate::UiEvent;
 use egui::{Label, Sense};
 use egui_extras::{Column, TableBuilder};
-use fluxfox::file_system::FileEntry;
+use fluxfox::{
+    file_system::{FileEntry, FileExtent},
+    FoxHashMap,
+};
 
 pub const GENERIC_FILE_ICON: &str = "🗋";
 
+/// A table of the files in a directory, with the extents backing the selected file.
+///
+/// Files are exported by checking them and using the toolbar's export button. Dragging files out
+/// of the list is not supported, as egui has no way to start a drag to the desktop.
 pub struct FileListWidget {
     is_web:    bool,
     file_list: Vec<FileEntry>,
+    // The extents of each file in the list, by index. Directories have no extents.
+    extents:   Vec<Vec<FileExtent>>,
+    // Whether each file in the list is checked for export, by index.
+    checked:   Vec<bool>,
+    // The index of the file whose extents are shown in the details section.
+    detail:    Option<usize>,
     icon_map:  fluxfox::FoxHashMap<&'static str, &'static str>,
 }
 
@@ -57,6 +70,9 @@ impl FileListWidget {
                 }
             },
             file_list: Vec::new(),
+            extents:   Vec::new(),
+            checked:   Vec::new(),
+            detail:    None,
             icon_map:  FileListWidget::icon_map(),
         }
     }
@@ -95,17 +111,120 @@ impl FileListWidget {
 
     pub fn reset(&mut self) {
         self.file_list.clear();
+        self.extents.clear();
+        self.checked.clear();
+        self.detail = None;
     }
 
-    pub fn update(&mut self, files: &[FileEntry]) {
+    /// Update the list with the specified files. `extents` maps file paths to the extents
+    /// holding each file, as returned by [fluxfox::file_system::FileSystem::file_extents].
+    pub fn update(&mut self, files: &[FileEntry], extents: &FoxHashMap<String, Vec<FileExtent>>) {
         self.file_list = files.to_vec();
+        self.extents = files
+            .iter()
+            .map(|entry| extents.get(entry.path()).cloned().unwrap_or_default())
+            .collect();
+        self.checked = vec![false; files.len()];
+        self.detail = None;
     }
 
-    pub fn show(&self, ui: &mut egui::Ui) -> Option<UiEvent> {
-        self.show_dir_table(ui)
+    /// Return the paths of the files checked for export.
+    pub fn checked_paths(&self) -> Vec<String> {
+        self.file_list
+            .iter()
+            .zip(self.checked.iter())
+            .filter(|(entry, checked)| **checked && entry.is_file())
+            .map(|(entry, _)| entry.path().to_string())
+            .collect()
     }
 
-    fn show_dir_table(&self, ui: &mut egui::Ui) -> Option<UiEvent> {
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<UiEvent> {
+        let mut new_event = None;
+        ui.vertical(|ui| {
+            new_event = self.show_toolbar(ui);
+            self.show_details(ui);
+            if let Some(event) = self.show_dir_table(ui) {
+                new_event = Some(event);
+            }
+        });
+        new_event
+    }
+
+    fn show_toolbar(&mut self, ui: &mut egui::Ui) -> Option<UiEvent> {
+        let mut new_event = None;
+        let checked_paths = self.checked_paths();
+
+        ui.horizontal(|ui| {
+            let export_label_text = match self.is_web {
+                true => format!("Download Selected ({})", checked_paths.len()),
+                false => format!("Export Selected ({})", checked_paths.len()),
+            };
+            if ui
+                .add_enabled(!checked_paths.is_empty(), egui::Button::new(export_label_text))
+                .clicked()
+            {
+                new_event = Some(UiEvent::ExportFiles(checked_paths.clone()));
+            }
+            if ui.button("Select All").clicked() {
+                for (checked, entry) in self.checked.iter_mut().zip(self.file_list.iter()) {
+                    *checked = entry.is_file();
+                }
+            }
+            if ui.button("Select None").clicked() {
+                self.checked.iter_mut().for_each(|checked| *checked = false);
+            }
+        });
+        new_event
+    }
+
+    /// Show the extents and sectors backing the file selected for details, if any.
+    fn show_details(&self, ui: &mut egui::Ui) {
+        let Some(index) = self.detail
+        else {
+            return;
+        };
+        let (Some(entry), Some(extents)) = (self.file_list.get(index), self.extents.get(index))
+        else {
+            return;
+        };
+
+        ui.group(|ui| {
+            ui.label(
+                egui::RichText::new(format!(
+                    "{}: {} fragment{}",
+                    entry.short_name(),
+                    extents.len(),
+                    if extents.len() == 1 { "" } else { "s" }
+                ))
+                .strong(),
+            );
+            egui::ScrollArea::vertical()
+                .id_salt("file_extents")
+                .max_height(100.0)
+                .show(ui, |ui| {
+                    for (ei, extent) in extents.iter().enumerate() {
+                        let (Some(first), Some(last)) = (extent.sectors.first(), extent.sectors.last())
+                        else {
+                            continue;
+                        };
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "#{:<3} offset: {:08X} len: {:6} sectors: {} - {} ({})",
+                                ei,
+                                extent.offset,
+                                extent.len,
+                                first,
+                                last,
+                                extent.sectors.len()
+                            ))
+                            .monospace(),
+                        );
+                    }
+                });
+        });
+    }
+
+    fn show_dir_table(&mut self, ui: &mut egui::Ui) -> Option<UiEvent> {
         // if self.dir_list.is_empty() {
         //     return None;
         // }
@@ -125,8 +244,10 @@ impl FileListWidget {
                 .striped(true)
                 .resizable(true)
                 .cell_layout(egui::Layout::left_to_right(egui::Align::LEFT))
+                .column(Column::exact(20.0))
                 .column(Column::exact(120.0))
                 .column(Column::auto())
+                .column(Column::auto())
                 //.column(Column::auto())
                 .column(Column::auto())
                 .column(Column::auto())
@@ -135,12 +256,16 @@ impl FileListWidget {
 
             table
                 .header(20.0, |mut header| {
+                    header.col(|_| {});
                     header.col(|ui| {
                         ui.strong("Filename");
                     });
                     header.col(|ui| {
                         ui.strong("Size");
                     });
+                    header.col(|ui| {
+                        ui.strong("Fragments");
+                    });
                     // header.col(|ui| {
                     //     ui.strong("Created Date");
                     // });
@@ -157,6 +282,14 @@ impl FileListWidget {
                         //row.set_selected(self.selection.contains(&row_index));
 
                         let icon = self.get_icon(&self.file_list[row_index]);
+                        row.set_selected(self.detail == Some(row_index));
+
+                        // Checkbox column - files may be checked for export.
+                        row.col(|ui| {
+                            if self.file_list[row_index].is_file() {
+                                ui.checkbox(&mut self.checked[row_index], "");
+                            }
+                        });
 
                         // First column - file icon and filename.
                         // Filename is clickable to select the file, right clickable to open context menu.
@@ -169,6 +302,7 @@ impl FileListWidget {
                                     .add(Label::new(egui::RichText::new(file_name).monospace()).sense(Sense::click()));
 
                                 if item_response.clicked() {
+                                    self.detail = Some(row_index);
                                     log::debug!(
                                         "show_dir_table(): Clicked on {:?}",
                                         self.file_list[row_index].path().to_string()
@@ -195,6 +329,17 @@ impl FileListWidget {
                                 ui.label(self.file_list[row_index].size().to_string());
                             });
                         });
+                        // Fragments column. Highlight files stored in more than one extent.
+                        row.col(|ui| {
+                            if self.file_list[row_index].is_file() {
+                                let fragments = self.extents[row_index].len();
+                                let text = egui::RichText::new(fragments.to_string());
+                                ui.label(match fragments > 1 {
+                                    true => text.color(ui.visuals().warn_fg_color),
+                                    false => text,
+                                });
+                            }
+                        });
                         // // Created date column - Not implemented in DOS FAT12 filesystems
                         // row.col(|ui| {
                         //     if let Some(created) = self.file_list[row_index].created() {
@@ -28,7 +28,10 @@ use crate::{
     controls::{dir_tree::DirTreeWidget, file_list::FileListWidget, path_selection::PathSelectionWidget},
     UiEvent,
 };
-use fluxfox::file_system::{fat::fat_fs::FatFileSystem, FileTreeNode};
+use fluxfox::{
+    file_system::{FileExtent, FileNameType, FileSystem, FileTreeNode},
+    FoxHashMap,
+};
 use std::cell::Cell;
 
 pub struct FileSystemWidget {
@@ -39,7 +42,9 @@ pub struct FileSystemWidget {
     file_selection: Option<String>,
     new_file_selection: Cell<bool>,
 
-    tree: FileTreeNode,
+    tree:    FileTreeNode,
+    // The extents of every file in the tree, keyed by path.
+    extents: FoxHashMap<String, Vec<FileExtent>>,
 }
 
 impl Default for FileSystemWidget {
@@ -58,6 +63,7 @@ impl FileSystemWidget {
             file_selection: None,
             new_file_selection: Cell::new(false),
             tree: FileTreeNode::default(),
+            extents: FoxHashMap::new(),
         }
     }
 
@@ -157,18 +163,32 @@ impl FileSystemWidget {
                 Vec::new()
             });
 
-            self.list_widget.update(&files);
+            self.list_widget.update(&files, &self.extents);
             self.tree_widget.set_selection(new_selection.clone());
         }
         self.path_selection = new_selection;
     }
 
-    pub fn update(&mut self, fs: &FatFileSystem) {
+    pub fn update(&mut self, fs: &dyn FileSystem) {
         self.tree = fs.build_file_tree_from_root().unwrap_or_else(|| {
             log::error!("Failed to build filesystem tree!");
             FileTreeNode::default()
         });
 
+        // Resolve the extents of every file up front, so that the file list can show fragmentation
+        // without holding the file system mounted.
+        self.extents.clear();
+        for path in self.tree.file_paths(true, FileNameType::Short) {
+            match fs.file_extents(&path) {
+                Ok(extents) => {
+                    self.extents.insert(path, extents);
+                }
+                Err(e) => {
+                    log::warn!("Failed to read extents for {}: {}", path, e);
+                }
+            }
+        }
+
         self.tree_widget.update(self.tree.clone());
         self.update_selection(Some("/".to_string()));
     }
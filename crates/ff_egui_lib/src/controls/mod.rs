@@ -40,6 +40,7 @@ pub mod path_selection;
 pub mod sector_status;
 pub mod source_map;
 pub mod tab_group;
+pub mod track_grid;
 pub mod track_list;
 #[cfg(feature = "egui_plot")]
 pub mod track_timing_chart;
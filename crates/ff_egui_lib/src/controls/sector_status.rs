@@ -124,6 +124,19 @@ pub fn sector_status(ui: &mut Ui, entry: &SectorMapEntry, open: bool) -> Respons
                         false => ui.label("Normal data"),
                     };
                     ui.end_row();
+
+                    if entry.is_duplicate() {
+                        ui.label("Duplicate ID");
+                        ui.label(format!("{} of {}", entry.duplicate_idx + 1, entry.duplicate_ct));
+                        ui.end_row();
+
+                        ui.label("Returned by read");
+                        match entry.is_read_target() {
+                            true => ui.add(PillWidget::new("Yes").with_fill(good_color)),
+                            false => ui.add(PillWidget::new("No").with_fill(bad_color)),
+                        };
+                        ui.end_row();
+                    }
                 });
             });
         });
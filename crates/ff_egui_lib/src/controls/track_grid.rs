@@ -0,0 +1,245 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+//! A grid of cells, one per track, with cylinders across and heads down. Each cell is shaded by a
+//! metric computed for its track by a caller-supplied closure, such as the number of sectors or
+//! bad sectors on the track, or by the differences found on it by [DiskImage::diff]. Clicking a
+//! cell selects the track.
+
+use crate::{TrackListSelection, TrackSelection, TrackSelectionScope};
+use egui::{Color32, Rect, Sense, Vec2};
+use fluxfox::{diff::ImageDiff, prelude::*, track::Track, FoxHashMap};
+
+pub const DEFAULT_CELL_SIZE: Vec2 = Vec2::new(8.0, 14.0);
+const COLOR_LOW: Color32 = Color32::from_rgb(0x25, 0x71, 0x79);
+const COLOR_HIGH: Color32 = Color32::from_rgb(0xef, 0x7d, 0x57);
+
+/// Common per-track metrics for use with [TrackGridWidget::update].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TrackMetric {
+    #[default]
+    SectorCount,
+    ErrorCount,
+    WeakBits,
+}
+
+impl TrackMetric {
+    pub const ALL: [TrackMetric; 3] = [TrackMetric::SectorCount, TrackMetric::ErrorCount, TrackMetric::WeakBits];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrackMetric::SectorCount => "Sectors",
+            TrackMetric::ErrorCount => "Bad sectors",
+            TrackMetric::WeakBits => "Weak bits",
+        }
+    }
+
+    /// Compute the metric for `track`. Weak bits are reported as 1 if the track has any.
+    pub fn value(&self, track: &dyn Track) -> f32 {
+        match self {
+            TrackMetric::SectorCount => track.sector_ct() as f32,
+            TrackMetric::ErrorCount => track
+                .sector_list()
+                .iter()
+                .filter(|entry| entry.attributes.data_error || entry.attributes.address_error)
+                .count() as f32,
+            TrackMetric::WeakBits => track.has_weak_bits() as u8 as f32,
+        }
+    }
+}
+
+pub struct TrackGridWidget {
+    cylinders: usize,
+    heads: usize,
+    // The metric of each track, indexed by `c * heads + h`, or None if the image has no such track.
+    values: Vec<Option<f32>>,
+    max_value: f32,
+    metric_name: String,
+    sel_scope: TrackSelectionScope,
+    cell_size: Vec2,
+    low_color: Color32,
+    high_color: Color32,
+    selected: Option<DiskCh>,
+}
+
+impl Default for TrackGridWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrackGridWidget {
+    pub fn new() -> Self {
+        Self {
+            cylinders: 0,
+            heads: 0,
+            values: Vec::new(),
+            max_value: 0.0,
+            metric_name: String::new(),
+            sel_scope: TrackSelectionScope::default(),
+            cell_size: DEFAULT_CELL_SIZE,
+            low_color: COLOR_LOW,
+            high_color: COLOR_HIGH,
+            selected: None,
+        }
+    }
+
+    pub fn with_cell_size(mut self, cell_size: Vec2) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Set the colors of cells with the lowest and highest nonzero metric. Cells with a metric of
+    /// zero are drawn in the background color.
+    pub fn with_colors(mut self, low: Color32, high: Color32) -> Self {
+        self.low_color = low;
+        self.high_color = high;
+        self
+    }
+
+    /// Set the [TrackSelectionScope] of the selections emitted when a cell is clicked.
+    pub fn with_scope(mut self, sel_scope: TrackSelectionScope) -> Self {
+        self.sel_scope = sel_scope;
+        self
+    }
+
+    /// Compute `metric` for each track of `disk`. `metric_name` is shown when hovering a cell.
+    pub fn update(&mut self, disk: &DiskImage, metric_name: &str, mut metric: impl FnMut(&dyn Track) -> f32) {
+        let tracks: Vec<(DiskCh, f32)> = disk.iter_tracks().map(|(ch, track)| (ch, metric(track))).collect();
+        self.set_values(&tracks, metric_name);
+    }
+
+    /// Shade each track of `disk` by the number of differences `diff` found on it, counting each
+    /// differing sector once. `diff` is the result of comparing `disk` with [DiskImage::diff], so
+    /// tracks found only in the other image are shown as well.
+    pub fn update_diff(&mut self, disk: &DiskImage, diff: &ImageDiff) {
+        let mut counts: FoxHashMap<DiskCh, f32> = disk.iter_tracks().map(|(ch, _)| (ch, 0.0)).collect();
+        for track in &diff.tracks {
+            *counts.entry(track.ch).or_default() += track.differences.len() as f32;
+        }
+        for sector in &diff.sectors {
+            *counts.entry(sector.ch).or_default() += 1.0;
+        }
+        let tracks: Vec<(DiskCh, f32)> = counts.into_iter().collect();
+        self.set_values(&tracks, "differences");
+    }
+
+    fn set_values(&mut self, tracks: &[(DiskCh, f32)], metric_name: &str) {
+        self.cylinders = tracks.iter().map(|(ch, _)| ch.c() as usize + 1).max().unwrap_or(0);
+        self.heads = tracks.iter().map(|(ch, _)| ch.h() as usize + 1).max().unwrap_or(0);
+        self.values = vec![None; self.cylinders * self.heads];
+        for (ch, value) in tracks {
+            self.values[ch.c() as usize * self.heads + ch.h() as usize] = Some(*value);
+        }
+        self.max_value = tracks.iter().map(|(_, value)| *value).fold(0.0, f32::max);
+        self.metric_name = metric_name.to_string();
+    }
+
+    pub fn reset(&mut self) {
+        self.values.clear();
+        self.cylinders = 0;
+        self.heads = 0;
+        self.max_value = 0.0;
+        self.selected = None;
+    }
+
+    /// Outline the cell of the specified track, or clear the outline if `None`.
+    pub fn set_selection(&mut self, ch: Option<DiskCh>) {
+        self.selected = ch;
+    }
+
+    /// Return the metric of the specified track, if it exists.
+    pub fn value(&self, ch: DiskCh) -> Option<f32> {
+        let (c, h) = (ch.c() as usize, ch.h() as usize);
+        if c >= self.cylinders || h >= self.heads {
+            return None;
+        }
+        self.values[c * self.heads + h]
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<TrackListSelection> {
+        if self.values.is_empty() {
+            ui.label("No tracks.");
+            return None;
+        }
+
+        let size = Vec2::new(
+            self.cell_size.x * self.cylinders as f32,
+            self.cell_size.y * self.heads as f32,
+        );
+        let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter_at(rect);
+            let empty_color = ui.visuals().faint_bg_color;
+            for (ti, value) in self.values.iter().enumerate() {
+                let Some(value) = value
+                else {
+                    continue;
+                };
+                let (c, h) = (ti / self.heads, ti % self.heads);
+                let color = match *value > 0.0 {
+                    true => self.low_color.lerp_to_gamma(self.high_color, value / self.max_value),
+                    false => empty_color,
+                };
+                painter.rect_filled(self.cell_rect(rect, c, h).shrink(0.5), 0.0, color);
+            }
+
+            if let Some(ch) = self.selected {
+                let cell = self.cell_rect(rect, ch.c() as usize, ch.h() as usize);
+                painter.rect_stroke(
+                    cell,
+                    0.0,
+                    (1.5, ui.visuals().selection.stroke.color),
+                    egui::StrokeKind::Inside,
+                );
+            }
+        }
+
+        let hover_ch = response.hover_pos().and_then(|pos| {
+            let offset = pos - rect.min;
+            let c = (offset.x / self.cell_size.x) as usize;
+            let h = (offset.y / self.cell_size.y) as usize;
+            (c < self.cylinders && h < self.heads).then(|| DiskCh::new(c as u16, h as u8))
+        })?;
+        let value = self.value(hover_ch)?;
+
+        let response = response.on_hover_text(format!("Track {}: {} {}", hover_ch, value, self.metric_name));
+        if response.clicked() {
+            self.selected = Some(hover_ch);
+            return Some(TrackListSelection::Track(TrackSelection {
+                sel_scope: self.sel_scope.clone(),
+                phys_ch:   hover_ch,
+            }));
+        }
+        None
+    }
+
+    fn cell_rect(&self, rect: Rect, c: usize, h: usize) -> Rect {
+        let min = rect.min + Vec2::new(c as f32 * self.cell_size.x, h as f32 * self.cell_size.y);
+        Rect::from_min_size(min, self.cell_size)
+    }
+}
@@ -298,6 +298,14 @@ impl TrackListWidget {
                                                     ui.close();
                                                 }
 
+                                                if ui.button("View Track Metadata").clicked() {
+                                                    new_selection2 = Some(TrackListSelection::Track(TrackSelection {
+                                                        sel_scope: TrackSelectionScope::Metadata,
+                                                        phys_ch:   track.ch,
+                                                    }));
+                                                    ui.close();
+                                                }
+
                                                 if ui.button("View Track Flux Timings").clicked() {
                                                     new_selection2 = Some(TrackListSelection::Track(TrackSelection {
                                                         sel_scope: TrackSelectionScope::Timings,
@@ -320,6 +328,13 @@ impl TrackListWidget {
                                                         phys_ch:   track.ch,
                                                     }));
                                                 }
+
+                                                if ui.button("View Track Metadata").clicked() {
+                                                    new_selection2 = Some(TrackListSelection::Track(TrackSelection {
+                                                        sel_scope: TrackSelectionScope::Metadata,
+                                                        phys_ch:   track.ch,
+                                                    }));
+                                                }
                                             }
                                             TrackDataResolution::MetaSector => {}
                                         });
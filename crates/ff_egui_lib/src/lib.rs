@@ -81,6 +81,8 @@ pub enum TrackSelectionScope {
     DecodedDataStream,
     Elements,
     Timings,
+    /// The track's metadata, as a list of the elements found by the track schema parser.
+    Metadata,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -99,6 +101,7 @@ pub enum TrackListSelection {
 pub enum UiEvent {
     SelectionChange(TrackListSelection),
     ExportFile(String),
+    ExportFiles(Vec<String>),
     SelectPath(String),
     SelectFile(FileEntry),
     ExportDir(String),
@@ -111,6 +114,7 @@ impl Debug for UiEvent {
         let variant_name = match self {
             UiEvent::SelectionChange(_) => "SelectionChange",
             UiEvent::ExportFile(_) => "ExportFile",
+            UiEvent::ExportFiles(_) => "ExportFiles",
             UiEvent::SelectPath(_) => "SelectPath",
             UiEvent::SelectFile(_) => "SelectFile",
             UiEvent::ExportDir(_) => "ExportDir",
@@ -139,7 +143,7 @@ pub enum UiLockContext {
     SectorViewer,
     TrackViewer,
     TrackListViewer,
-    /// The filesystem viewer is currently the only tool that requires a write lock, due to
+    /// The filesystem viewer requires a write lock, due to
     /// the use of a StandardSectorView, which requires a mutable reference to the disk image.
     /// StandardSectorView is used as an interface for reading and writing sectors in a standard
     /// raw-sector based order, such as what is expected by rust-fatfs.
@@ -149,6 +153,13 @@ pub enum UiLockContext {
     SourceMap,
     TrackElementMap,
     TrackTimingViewer,
+    TrackMetadataViewer,
+    /// The image metadata editor takes a write lock only while applying edits.
+    ImageMetadata,
+    /// The Save As dialog takes a write lock only while encoding the image.
+    ImageExport,
+    /// The image analysis dashboard takes a read lock only while analyzing a new image.
+    ImageAnalysis,
 }
 
 impl Display for UiLockContext {
@@ -28,7 +28,7 @@
 use std::{
     cell::RefCell,
     io,
-    path::PathBuf,
+    path::{Path, PathBuf},
     rc::Rc,
     time::{Duration, Instant},
 };
@@ -36,11 +36,17 @@ use std::{
 pub use crate::app_context::AppContext;
 use crate::{
     cmd_interpreter::{CommandInterpreter, CommandResult},
-    components::{data_block::DataBlock, history::HistoryWidget},
-    disk_selection::DiskSelection,
+    components::{
+        data_block::DataBlock,
+        history::{HistoryEntry, HistoryWidget},
+        track_map::TrackMap,
+    },
+    disk_selection::{DiskSelection, SelectionLevel},
     logger::{init_logger, LogEntry},
     modal::ModalState,
+    script::{read_script, ScriptLine, ScriptQueue},
     widget::{FoxWidget, TabSelectableWidget},
+    worker::JobResult,
     CmdParams,
 };
 use crossbeam_channel::Receiver;
@@ -48,7 +54,7 @@ use crossterm::{
     event,
     event::{Event, KeyCode, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind},
 };
-use fluxfox::DiskImage;
+use fluxfox::{types::DiskImageFlags, DiskImage};
 use ratatui::{
     prelude::*,
     widgets::{Gauge, Paragraph},
@@ -65,12 +71,18 @@ pub(crate) enum ApplicationState {
 }
 
 pub(crate) enum AppEvent {
-    LoadingStatus(f64),
     DiskImageLoaded(DiskImage, PathBuf),
+    SecondaryImageLoaded(DiskImage, PathBuf),
     DiskImageLoadingFailed(String),
+    DiskImageSaved(PathBuf),
     DiskSelectionChanged,
+    DiskImageModified,
     Log(LogEntry),
     OpenFileRequest(PathBuf),
+    OpenSecondaryRequest(PathBuf),
+    JobProgress(f64),
+    JobFinished(DiskImage, JobResult),
+    RunScript(Vec<ScriptLine>),
 }
 
 pub(crate) struct UiContext {
@@ -88,6 +100,7 @@ pub(crate) struct App {
     pub(crate) ctx: AppContext,
     pub(crate) ui_ctx: UiContext,
     pub(crate) selected_widget: usize,
+    pub(crate) script: ScriptQueue,
 }
 
 impl App {
@@ -99,12 +112,14 @@ impl App {
 
         let db = Rc::new(RefCell::new(DataBlock::default()));
         let history = Rc::new(RefCell::new(HistoryWidget::new(None)));
+        let track_map = Rc::new(RefCell::new(TrackMap::default()));
 
         // history gets selected by default.
         history.borrow_mut().select();
 
         let widgets = vec![
             history.clone() as Rc<RefCell<dyn FoxWidget>>,
+            track_map.clone() as Rc<RefCell<dyn FoxWidget>>,
             db.clone() as Rc<RefCell<dyn FoxWidget>>,
         ];
 
@@ -119,8 +134,16 @@ impl App {
                 state: ApplicationState::Normal,
                 di: None,
                 di_name: None,
+                di_path: None,
+                secondary: None,
+                secondary_name: None,
+                load: None,
                 sender,
                 db,
+                track_map,
+                job: None,
+                undo: Default::default(),
+                search: Default::default(),
             },
             ui_ctx: UiContext {
                 dragging: false,
@@ -128,6 +151,7 @@ impl App {
             },
             widgets,
             selected_widget: 0,
+            script: ScriptQueue::default(),
         };
 
         if let Some(ref in_file) = app.params.in_filename {
@@ -189,6 +213,23 @@ impl App {
             Span::styled(image_name, Style::default()),
         ]);
 
+        if self
+            .ctx
+            .di
+            .as_ref()
+            .is_some_and(|di| di.has_flag(DiskImageFlags::DIRTY))
+        {
+            title_line.push_span(Span::styled(" [modified]", Style::yellow(Style::default())));
+        }
+
+        if let Some(secondary_name) = &self.ctx.secondary_name {
+            title_line.push_span(Span::styled(" vs ", Style::default()));
+            title_line.push_span(Span::styled(
+                secondary_name.to_string_lossy().to_string(),
+                Style::default(),
+            ));
+        }
+
         if !image_resolution.is_empty() {
             title_line.push_span(Span::styled(" [", Style::default()));
             title_line.push_span(Span::styled(image_resolution, Style::light_blue(Style::default())));
@@ -234,9 +275,9 @@ impl App {
             ApplicationState::Normal => {}
             ApplicationState::Modal(modal_state) => {
                 match modal_state {
-                    ModalState::ProgressBar(title, progress) => {
+                    ModalState::Progress(modal) => {
                         // Display a progress bar
-                        let gauge = Gauge::default().ratio(*progress);
+                        let gauge = Gauge::default().ratio(modal.progress.clamp(0.0, 1.0));
                         let sized = SizedWrapper {
                             inner:  gauge,
                             width:  (f.area().width / 2) as usize,
@@ -244,7 +285,7 @@ impl App {
                         };
 
                         let popup = Popup::new(sized)
-                            .title(title.clone())
+                            .title(modal.display_title())
                             .style(Style::new().white().on_black());
                         f.render_widget(&popup, f.area());
                     }
@@ -264,6 +305,11 @@ impl App {
             // Receive AppEvents
             self.handle_app_events();
 
+            // Run any queued script commands
+            if let Some(CommandResult::UserExit) = self.run_script_step() {
+                break Ok(());
+            }
+
             // Handle input
             if event::poll(tick_rate.saturating_sub(last_tick.elapsed()))? {
                 match event::read()? {
@@ -294,6 +340,72 @@ impl App {
         }
     }
 
+    /// Run the commands in a script file without the TUI, echoing commands and their results to
+    /// stdout. Returns an error if the script could not be read or a command failed.
+    pub fn run_script(&mut self, path: &Path) -> Result<(), String> {
+        self.history.borrow_mut().echo = true;
+        self.script.push(read_script(path)?)?;
+
+        loop {
+            self.handle_app_events();
+            if let Some(CommandResult::UserExit) = self.run_script_step() {
+                break;
+            }
+            if !self.script.is_active() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        if self.script.failed() {
+            Err(format!("Script {} failed", path.display()))
+        }
+        else {
+            Ok(())
+        }
+    }
+
+    // Run queued script commands until the queue is empty, or a command starts a load or job
+    // that must finish before the next command.
+    fn run_script_step(&mut self) -> Option<CommandResult> {
+        while !self.ctx.is_busy() {
+            let Some(line) = self.script.next()
+            else {
+                break;
+            };
+
+            match self.execute_command(&line.command) {
+                CommandResult::UserExit => return Some(CommandResult::UserExit),
+                CommandResult::Error(_) => {
+                    if let Some(msg) = self.script.abort() {
+                        self.history.borrow_mut().push(HistoryEntry::Error(msg));
+                    }
+                }
+                CommandResult::Success(_) => {}
+            }
+
+            // Handle events raised by the command, such as a request to open a file, so that
+            // the next command waits on them.
+            self.handle_app_events();
+        }
+        None
+    }
+
+    // Process a command and add it and its response to the history.
+    fn execute_command(&mut self, command: &str) -> CommandResult {
+        let mut history = self.history.borrow_mut();
+        history.push_user_cmd(command);
+
+        let result = self.ci.process_command(&mut self.ctx, command);
+        match &result {
+            CommandResult::Success(response) | CommandResult::Error(response) => {
+                history.push_cmd_response(response);
+            }
+            CommandResult::UserExit => {}
+        }
+        result
+    }
+
     fn on_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Option<CommandResult> {
         match &self.ctx.state {
             ApplicationState::Normal => self.on_key_normal(code, modifiers),
@@ -302,6 +414,9 @@ impl App {
                     self.on_key_normal(code, modifiers)
                 }
                 else {
+                    if code == KeyCode::Esc {
+                        self.ctx.cancel_job();
+                    }
                     None
                 }
             }
@@ -319,22 +434,9 @@ impl App {
             }
             KeyCode::Enter => {
                 if !self.input.is_empty() {
-                    let mut history = self.history.borrow_mut();
                     let command = self.input.clone();
-                    history.push_user_cmd(&command);
-
-                    // Process the command and get the result
-                    let result = self.ci.process_command(&mut self.ctx, &command);
-                    match result {
-                        CommandResult::Success(response) => {
-                            history.push_cmd_response(&response);
-                        }
-                        CommandResult::Error(response) => {
-                            history.push_cmd_response(&response);
-                        }
-                        CommandResult::UserExit => {
-                            return Some(CommandResult::UserExit);
-                        }
+                    if let CommandResult::UserExit = self.execute_command(&command) {
+                        return Some(CommandResult::UserExit);
                     }
 
                     // Clear input after processing
@@ -344,6 +446,11 @@ impl App {
             KeyCode::BackTab => {
                 self.select_next_widget();
             }
+            KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down
+                if self.ctx.track_map.borrow().tab_selected =>
+            {
+                self.move_track_cursor(code);
+            }
             KeyCode::PageUp => {
                 self.widgets[self.selected_widget].borrow_mut().page_up();
             }
@@ -355,6 +462,27 @@ impl App {
         None
     }
 
+    // Move the track map cursor with the arrow keys, selecting the track under the cursor.
+    fn move_track_cursor(&mut self, code: KeyCode) {
+        let (dc, dh) = match code {
+            KeyCode::Left => (-1, 0),
+            KeyCode::Right => (1, 0),
+            KeyCode::Up => (0, -1),
+            KeyCode::Down => (0, 1),
+            _ => return,
+        };
+
+        if let Some(ch) = self.ctx.track_map.borrow_mut().move_cursor(dc, dh) {
+            self.ctx.selection = DiskSelection {
+                level: SelectionLevel::Cylinder,
+                head: Some(ch.h()),
+                cylinder: Some(ch.c()),
+                sector: None,
+            };
+            _ = self.ctx.sender.send(AppEvent::DiskSelectionChanged);
+        }
+    }
+
     fn on_mouse(&mut self, event: MouseEvent, size: Size) {
         match event.kind {
             MouseEventKind::Down(_) => {
@@ -391,6 +519,18 @@ impl App {
     fn draw_data_pane(&self, f: &mut Frame, area: Rect) {
         // Display data pane content here
         //let block = Block::default().borders(Borders::ALL).title("Data Pane");
-        f.render_widget_ref(&*self.ctx.db.borrow(), area);
+        let track_map = self.ctx.track_map.borrow();
+        let map_height = if self.ctx.di.is_some() {
+            track_map.required_height(area.width).min(area.height / 2)
+        }
+        else {
+            0
+        };
+        let [map_area, data_area] = Layout::vertical([Constraint::Length(map_height), Constraint::Min(1)]).areas(area);
+
+        if map_height > 0 {
+            f.render_widget_ref(&*track_map, map_area);
+        }
+        f.render_widget_ref(&*self.ctx.db.borrow(), data_area);
     }
 }
@@ -26,12 +26,16 @@
 */
 use crate::{
     app::{AppEvent, ApplicationState},
-    components::data_block::DataBlock,
+    components::{data_block::DataBlock, track_map::TrackMap},
     disk_selection::DiskSelection,
+    modal::ModalState,
+    search::SearchResults,
+    undo::UndoStack,
+    worker::{spawn_job, JobContext, JobHandle, JobResult},
 };
 use crossbeam_channel::Sender;
-use fluxfox::DiskImage;
-use std::{cell::RefCell, path::PathBuf, rc::Rc, sync::Arc};
+use fluxfox::{load_handle::LoadHandle, DiskImage, DiskImageError, LoadingStage};
+use std::{cell::RefCell, fs::File, io::BufReader, path::PathBuf, rc::Rc};
 
 // Contain mutable data for App
 // This avoids borrowing issues when passing the mutable context to the command processor
@@ -40,46 +44,153 @@ pub struct AppContext {
     pub state: ApplicationState,
     pub di: Option<DiskImage>,
     pub di_name: Option<PathBuf>,
+    pub di_path: Option<PathBuf>,
+    /// A second image opened with `open --secondary`, to compare against and copy sectors from.
+    pub secondary: Option<DiskImage>,
+    pub secondary_name: Option<PathBuf>,
+    pub load: Option<PendingLoad>,
     pub sender: Sender<AppEvent>,
     pub db: Rc<RefCell<DataBlock>>,
+    pub track_map: Rc<RefCell<TrackMap>>,
+    pub job: Option<JobHandle>,
+    pub undo: UndoStack,
+    pub search: SearchResults,
+}
+
+// A disk image being loaded on a background thread
+pub(crate) struct PendingLoad {
+    handle: LoadHandle,
+    path: PathBuf,
+    secondary: bool,
 }
 
 impl AppContext {
     pub(crate) fn load_disk_image(&mut self, filename: PathBuf) {
-        let outer_sender = self.sender.clone();
-        let inner_filename = filename.clone();
-        std::thread::spawn(move || {
-            let inner_sender = outer_sender.clone();
-
-            match DiskImage::load_from_file(
-                &inner_filename,
-                None,
-                Some(Arc::new(move |status| match status {
-                    fluxfox::LoadingStatus::Progress(progress) => {
-                        inner_sender.send(AppEvent::LoadingStatus(progress)).unwrap();
-                    }
-                    fluxfox::LoadingStatus::Error => {
-                        log::error!("load_disk_image()... Error loading disk image");
-                        inner_sender
-                            .send(AppEvent::DiskImageLoadingFailed("Unknown error".to_string()))
-                            .unwrap();
-                    }
-                    _ => {}
-                })),
-            ) {
-                Ok(di) => {
-                    log::debug!("load_disk_image()... Successfully loaded disk image");
-                    outer_sender
-                        .send(AppEvent::DiskImageLoaded(di, filename.clone()))
-                        .unwrap();
+        self.load_image(filename, false);
+    }
+
+    pub(crate) fn load_secondary_image(&mut self, filename: PathBuf) {
+        self.load_image(filename, true);
+    }
+
+    fn load_image(&mut self, filename: PathBuf, secondary: bool) {
+        if self.is_busy() {
+            _ = self.sender.send(AppEvent::DiskImageLoadingFailed(
+                "Error: Another operation is already in progress".to_string(),
+            ));
+            return;
+        }
+
+        let file = match File::open(&filename) {
+            Ok(file) => file,
+            Err(e) => {
+                log::error!("load_disk_image()... Error opening disk image");
+                _ = self
+                    .sender
+                    .send(AppEvent::DiskImageLoadingFailed(format!("Error: {}", e)));
+                return;
+            }
+        };
+
+        let handle = DiskImage::load_in_background(BufReader::new(file), Some(filename.clone()), None, None);
+        self.load = Some(PendingLoad {
+            handle,
+            path: filename,
+            secondary,
+        });
+        self.state = ApplicationState::Modal(ModalState::new_progress_bar("Loading Disk Image"));
+    }
+
+    // Update the progress of a pending load, and send the loaded image or error once it has
+    // finished or been cancelled.
+    pub(crate) fn poll_load(&mut self) {
+        let Some(load) = &mut self.load
+        else {
+            return;
+        };
+
+        let Some(result) = load.handle.poll()
+        else {
+            if let ApplicationState::Modal(modal_state) = &mut self.state {
+                if let Some(stage) = load.handle.stage() {
+                    modal_state.update_title(match stage {
+                        LoadingStage::Detecting => "Detecting Disk Image Format",
+                        LoadingStage::Reading => "Reading Disk Image",
+                        LoadingStage::Decoding => "Decoding Tracks",
+                        LoadingStage::Analyzing => "Analyzing Disk Image",
+                    });
                 }
-                Err(e) => {
-                    log::error!("load_disk_image()... Error loading disk image");
-                    outer_sender
-                        .send(AppEvent::DiskImageLoadingFailed(format!("Error: {}", e)))
-                        .unwrap();
+                modal_state.update_progress(load.handle.progress());
+            }
+            return;
+        };
+
+        let Some(load) = self.load.take()
+        else {
+            return;
+        };
+        let event = match result {
+            Ok(di) => {
+                log::debug!("poll_load()... Successfully loaded disk image");
+                if load.secondary {
+                    AppEvent::SecondaryImageLoaded(di, load.path)
+                }
+                else {
+                    AppEvent::DiskImageLoaded(di, load.path)
                 }
             }
-        });
+            Err(DiskImageError::Cancelled) => AppEvent::DiskImageLoadingFailed("Loading cancelled".to_string()),
+            Err(e) => {
+                log::error!("poll_load()... Error loading disk image");
+                AppEvent::DiskImageLoadingFailed(format!("Error: {}", e))
+            }
+        };
+        _ = self.sender.send(event);
+    }
+
+    // Run a long-running operation on the loaded disk image in a worker thread, displaying a
+    // progress bar until it finishes. The disk image is unavailable to other commands until the
+    // job returns it.
+    pub(crate) fn start_job<F>(&mut self, title: &str, job: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut DiskImage, &JobContext) -> JobResult + Send + 'static,
+    {
+        if self.job.is_some() {
+            return Err("Another operation is already in progress".into());
+        }
+        let di = self.di.take().ok_or_else(|| "No disk image loaded".to_string())?;
+
+        self.job = Some(spawn_job(self.sender.clone(), di, job));
+        self.state = ApplicationState::Modal(ModalState::new_progress_bar(title));
+        Ok(())
+    }
+
+    // Return whether a disk image is being loaded or a job is running. Script commands wait
+    // until the app is no longer busy.
+    pub(crate) fn is_busy(&self) -> bool {
+        self.load.is_some() || self.job.is_some()
+    }
+
+    // Request cancellation of the pending load or running job, if any. A load is abandoned on
+    // the next poll; a job stops at its next check.
+    pub(crate) fn cancel_job(&mut self) {
+        if let Some(load) = &self.load {
+            if !load.handle.is_cancelled() {
+                log::info!("Cancelling load...");
+                load.handle.cancel();
+            }
+        }
+        else if let Some(job) = &self.job {
+            if !job.is_cancelled() {
+                log::info!("Cancelling operation...");
+                job.cancel();
+            }
+        }
+        else {
+            return;
+        }
+        if let ApplicationState::Modal(modal_state) = &mut self.state {
+            modal_state.set_cancelling();
+        }
     }
 }
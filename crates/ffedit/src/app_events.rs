@@ -29,12 +29,12 @@ use crate::{
     app::{App, AppEvent, ApplicationState},
     components::history::HistoryEntry,
     logger::LogEntry,
-    modal::ModalState,
     util::strip_path,
 };
 
 impl App {
     pub(crate) fn handle_app_events(&mut self) {
+        self.ctx.poll_load();
         while let Ok(msg) = self.receiver.try_recv() {
             let mut history = self.history.borrow_mut();
             match msg {
@@ -58,17 +58,24 @@ impl App {
                 AppEvent::OpenFileRequest(path) => {
                     self.ctx.load_disk_image(path);
                 }
-                AppEvent::LoadingStatus(progress) => {
-                    self.ctx.state =
-                        ApplicationState::Modal(ModalState::ProgressBar("Loading Disk Image".to_string(), progress));
+                AppEvent::OpenSecondaryRequest(path) => {
+                    self.ctx.load_secondary_image(path);
                 }
                 AppEvent::DiskImageLoaded(di, di_name) => {
                     self.ctx.di = Some(di);
                     self.ctx.di_name = Some(strip_path(&di_name));
+                    self.ctx.di_path = Some(di_name.clone());
                     self.ctx.state = ApplicationState::Normal;
 
-                    // Reset the selection.
+                    // Reset the selection, edit history and search results.
                     self.ctx.selection = Default::default();
+                    self.ctx.undo.clear();
+                    self.ctx.search = Default::default();
+                    self.ctx
+                        .track_map
+                        .borrow_mut()
+                        .load(self.ctx.di.as_ref().unwrap(), self.ctx.secondary.as_ref());
+                    self.ctx.track_map.borrow_mut().set_cursor(&self.ctx.selection);
                     // Load the data block.
                     match self
                         .ctx
@@ -87,9 +94,74 @@ impl App {
                         }
                     }
                 }
+                AppEvent::SecondaryImageLoaded(di, di_name) => {
+                    self.ctx.secondary = Some(di);
+                    self.ctx.secondary_name = Some(strip_path(&di_name));
+                    self.ctx.state = ApplicationState::Normal;
+
+                    self.ctx.track_map.borrow_mut().invalidate();
+                    if let Some(di) = &self.ctx.di {
+                        self.ctx.track_map.borrow_mut().refresh(di, self.ctx.secondary.as_ref());
+                    }
+                    history.push(HistoryEntry::CommandResponse(format!(
+                        "Loaded secondary disk image: {}. Use 'diffmap' to compare",
+                        di_name.display()
+                    )));
+                }
                 AppEvent::DiskImageLoadingFailed(msg) => {
                     self.ctx.state = ApplicationState::Normal;
                     history.push(HistoryEntry::CommandResponse(msg));
+                    if let Some(msg) = self.script.abort() {
+                        history.push(HistoryEntry::Error(msg));
+                    }
+                }
+                AppEvent::DiskImageSaved(path) => {
+                    // The saved file is now the image's file.
+                    self.ctx.di_name = Some(strip_path(&path));
+                    self.ctx.di_path = Some(path);
+                }
+                AppEvent::JobProgress(progress) => {
+                    if let ApplicationState::Modal(modal_state) = &mut self.ctx.state {
+                        modal_state.update_progress(progress);
+                    }
+                }
+                AppEvent::JobFinished(di, result) => {
+                    self.ctx.di = Some(di);
+                    self.ctx.job = None;
+                    self.ctx.state = ApplicationState::Normal;
+
+                    match result {
+                        Ok(msg) => history.push(HistoryEntry::CommandResponse(msg)),
+                        Err(msg) => {
+                            history.push(HistoryEntry::Error(msg));
+                            if let Some(msg) = self.script.abort() {
+                                history.push(HistoryEntry::Error(msg));
+                            }
+                        }
+                    }
+                    // The job may have modified the image, so reload the data block and track map.
+                    if let Some(di) = &mut self.ctx.di {
+                        _ = self.ctx.db.borrow_mut().load(di, &self.ctx.selection);
+                        self.ctx.track_map.borrow_mut().load(di, self.ctx.secondary.as_ref());
+                    }
+                }
+                AppEvent::RunScript(lines) => {
+                    if let Err(e) = self.script.push(lines) {
+                        history.push(HistoryEntry::Error(e));
+                        if let Some(msg) = self.script.abort() {
+                            history.push(HistoryEntry::Error(msg));
+                        }
+                    }
+                }
+                AppEvent::DiskImageModified => {
+                    // Edits may have changed the condition of a track, or how it differs from the
+                    // secondary image, so rebuild the track map.
+                    if let Some(di) = &self.ctx.di {
+                        let mut track_map = self.ctx.track_map.borrow_mut();
+                        track_map.invalidate();
+                        track_map.refresh(di, self.ctx.secondary.as_ref());
+                    }
+                    _ = self.ctx.sender.send(AppEvent::DiskSelectionChanged);
                 }
                 AppEvent::DiskSelectionChanged => {
                     // Depending on selection, we need to read the current track or sector,
@@ -97,6 +169,7 @@ impl App {
                     if let Some(di) = &mut self.ctx.di {
                         _ = self.ctx.db.borrow_mut().load(di, &self.ctx.selection);
                     }
+                    self.ctx.track_map.borrow_mut().set_cursor(&self.ctx.selection);
                 }
             }
         }
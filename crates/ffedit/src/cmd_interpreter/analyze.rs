@@ -0,0 +1,95 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::AppContext,
+    cmd_interpreter::{Command, CommandArgs, CommandResult},
+};
+use fluxfox::prelude::*;
+
+pub(crate) struct AnalyzeCommand;
+
+impl Command for AnalyzeCommand {
+    fn execute(&self, app: &mut AppContext, _args: CommandArgs) -> Result<CommandResult, String> {
+        app.start_job("Analyzing Disk Image", |di, job| {
+            let track_chs: Vec<DiskCh> = di.track_ch_iter().collect();
+            let mut result_string = String::new();
+            let mut flagged = 0;
+
+            for (i, ch) in track_chs.iter().enumerate() {
+                job.check_cancel()?;
+                let track = di.track(*ch).ok_or_else(|| format!("Track {} not found", ch))?;
+                let ta = track
+                    .analysis()
+                    .map_err(|e| format!("Error analyzing track {}: {}", ch, e))?;
+                let short_gaps = track.gap_report().is_some_and(|report| !report.is_write_safe());
+
+                let flags: Vec<&str> = [
+                    (ta.data_error, "data errors"),
+                    (ta.address_error, "address errors"),
+                    (ta.deleted_data, "deleted data"),
+                    (ta.no_dam, "missing DAMs"),
+                    (ta.consistent_sector_size.is_none(), "varying sector sizes"),
+                    (ta.nonconsecutive_sectors, "nonconsecutive sectors"),
+                    (ta.overlapping_sectors, "overlapping sectors"),
+                    (ta.sector_crossing_index, "sector crossing index"),
+                    (short_gaps, "gaps too short for writing"),
+                ]
+                .iter()
+                .filter_map(|&(set, desc)| set.then_some(desc))
+                .collect();
+
+                if !flags.is_empty() {
+                    flagged += 1;
+                    result_string.push_str(&format!(
+                        "\n  Track {}: {} sectors, {}",
+                        ch,
+                        ta.sector_ct,
+                        flags.join(", ")
+                    ));
+                }
+                job.progress((i + 1) as f64 / track_chs.len() as f64);
+            }
+
+            Ok(format!(
+                "Analyzed {} tracks, {} with notable features{}",
+                track_chs.len(),
+                flagged,
+                result_string
+            ))
+        })?;
+
+        Ok(CommandResult::Success("Analyzing disk image...".into()))
+    }
+
+    fn usage(&self) -> String {
+        "No arguments".into()
+    }
+
+    fn desc(&self) -> String {
+        "Analyze each track in the disk image and report notable features".into()
+    }
+}
@@ -41,7 +41,7 @@ impl Command for CylinderCommand {
             let new_cylinder: u16 = argv[0].parse::<u16>().map_err(|_| "Invalid cylinder number")?;
 
             if let Some(di) = &app.di {
-                if new_cylinder >= di.tracks(app.selection.head.unwrap_or(0)) {
+                if new_cylinder >= di.track_ct(app.selection.head.unwrap_or(0) as usize) as u16 {
                     return Err(format!("Invalid cylinder number: {}", new_cylinder));
                 }
             }
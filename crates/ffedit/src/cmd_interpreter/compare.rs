@@ -0,0 +1,99 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::AppContext,
+    cmd_interpreter::{parse_chs, read_sector_data, Command, CommandArgs, CommandResult},
+};
+
+/// The number of differing bytes listed by `compare`.
+const MAX_LISTED_DIFFERENCES: usize = 16;
+
+pub(crate) struct CompareCommand;
+
+impl Command for CompareCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let argv = args.argv.unwrap_or_default();
+        if argv.len() != 2 {
+            return Err(format!("Usage: compare {}", self.usage()));
+        }
+        let a = parse_chs(&argv[0], &app.selection)?;
+        let b = parse_chs(&argv[1], &app.selection)?;
+
+        let di = app.di.as_ref().ok_or_else(|| "No disk image loaded".to_string())?;
+        let a_rsr = read_sector_data(di, a.ch(), a)?;
+        let b_rsr = read_sector_data(di, b.ch(), b)?;
+        let (a_data, b_data) = (a_rsr.data(), b_rsr.data());
+
+        let differences: Vec<usize> = a_data
+            .iter()
+            .zip(b_data.iter())
+            .enumerate()
+            .filter(|(_, (x, y))| x != y)
+            .map(|(offset, _)| offset)
+            .collect();
+
+        if differences.is_empty() && a_data.len() == b_data.len() {
+            return Ok(CommandResult::Success(format!(
+                "Sectors {} and {} are identical ({} bytes)",
+                a,
+                b,
+                a_data.len()
+            )));
+        }
+
+        let mut response = format!("Sectors {} and {} differ in {} bytes", a, b, differences.len());
+        if a_data.len() != b_data.len() {
+            response.push_str(&format!(
+                "\n  Sizes differ: {} bytes and {} bytes; only the first {} bytes were compared",
+                a_data.len(),
+                b_data.len(),
+                a_data.len().min(b_data.len())
+            ));
+        }
+        for &offset in differences.iter().take(MAX_LISTED_DIFFERENCES) {
+            response.push_str(&format!(
+                "\n  {:#06X}: {:02X} {:02X}",
+                offset, a_data[offset], b_data[offset]
+            ));
+        }
+        if differences.len() > MAX_LISTED_DIFFERENCES {
+            response.push_str(&format!(
+                "\n  ...and {} more",
+                differences.len() - MAX_LISTED_DIFFERENCES
+            ));
+        }
+        Ok(CommandResult::Success(response))
+    }
+
+    fn usage(&self) -> String {
+        "<c/h/s | s> <c/h/s | s>".into()
+    }
+
+    fn desc(&self) -> String {
+        "Compare the data of two sectors".into()
+    }
+}
@@ -0,0 +1,194 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::AppContext,
+    cmd_interpreter::{Command, CommandArgs, CommandResult},
+};
+use fluxfox::{format_from_ext, prelude::*};
+use std::{io::Cursor, path::PathBuf};
+
+/// Human-readable descriptions of what is lost when an output format lacks a capability the disk
+/// image requires.
+const CAP_LOSS_DESCRIPTIONS: &[(FormatCaps, &str)] = &[
+    (FormatCaps::CAP_VARIABLE_SPT, "varying sector counts per track"),
+    (FormatCaps::CAP_VARIABLE_SSPT, "varying sector sizes"),
+    (FormatCaps::CAP_ADDRESS_CRC, "address mark CRC errors"),
+    (FormatCaps::CAP_DATA_CRC, "data CRC errors"),
+    (FormatCaps::CAP_DATA_DELETED, "deleted data marks"),
+    (FormatCaps::CAP_SID_OVERRIDE, "non-standard sector IDs"),
+    (FormatCaps::CAP_COMMENT, "image comment"),
+    (FormatCaps::CAP_TRACK_ENCODING, "per-track encoding"),
+    (FormatCaps::CAP_TRACK_DATA_RATE, "per-track data rate"),
+    (FormatCaps::CAP_WEAK_BITS, "weak bits"),
+    (FormatCaps::CAP_HOLES, "holes"),
+    (FormatCaps::CAP_ENCODING_FM, "FM encoded tracks"),
+    (FormatCaps::CAP_ENCODING_MFM, "MFM encoded tracks"),
+    (FormatCaps::CAP_ENCODING_GCR, "GCR encoded tracks"),
+    (FormatCaps::CAP_NO_DAM, "sector IDs without data"),
+];
+
+struct ConvertOptions {
+    format: DiskImageFileFormat,
+    filename: PathBuf,
+    revolutions: Option<usize>,
+    confirmed: bool,
+}
+
+impl ConvertOptions {
+    fn parse(argv: &[String]) -> Result<Self, String> {
+        if argv.len() < 2 {
+            return Err("A target format and output filename are required".into());
+        }
+        let format = format_from_ext(&argv[0]).ok_or_else(|| format!("Unknown output format: {}", argv[0]))?;
+        let filename = PathBuf::from(&argv[argv.len() - 1]);
+
+        let mut revolutions = None;
+        let mut confirmed = false;
+        let mut opts = argv[1..argv.len() - 1].iter();
+        while let Some(opt) = opts.next() {
+            match opt.as_str() {
+                "-y" | "--yes" => confirmed = true,
+                "-r" | "--revolutions" => {
+                    let value = opts.next().ok_or_else(|| format!("{} requires a value", opt))?;
+                    revolutions = Some(
+                        value
+                            .parse::<usize>()
+                            .ok()
+                            .filter(|&revs| revs > 0)
+                            .ok_or_else(|| format!("Invalid revolution count: {}", value))?,
+                    );
+                }
+                _ => return Err(format!("Unknown option: {}", opt)),
+            }
+        }
+
+        Ok(Self {
+            format,
+            filename,
+            revolutions,
+            confirmed,
+        })
+    }
+
+    fn write_options(&self) -> ParserWriteOptions {
+        match self.revolutions {
+            Some(revolutions) => ParserWriteOptions::default().with_flux_revolutions(revolutions),
+            None => ParserWriteOptions::default(),
+        }
+    }
+}
+
+/// Build a list of the features of `di` that cannot be represented by `format`.
+fn lossiness_summary(di: &DiskImage, format: DiskImageFileFormat) -> Vec<&'static str> {
+    let missing_caps = di.required_caps().difference(format.capabilities());
+    let mut losses: Vec<&'static str> = CAP_LOSS_DESCRIPTIONS
+        .iter()
+        .filter_map(|&(cap, desc)| missing_caps.contains(cap).then_some(desc))
+        .collect();
+
+    // Metadata is stored in the image comment, which isn't reflected in the image's required caps.
+    if !di.image_metadata().is_empty() && !format.capabilities().contains(FormatCaps::CAP_COMMENT) {
+        losses.push("image metadata");
+    }
+    losses
+}
+
+/// Check that `format` can write `di`, and return a list of the features of `di` it would lose.
+pub(super) fn write_losses(di: &DiskImage, format: DiskImageFileFormat) -> Result<Vec<&'static str>, String> {
+    match format.can_write(Some(di)) {
+        ParserWriteCompatibility::Ok => Ok(lossiness_summary(di, format)),
+        ParserWriteCompatibility::DataLoss => {
+            let mut losses = lossiness_summary(di, format);
+            if losses.is_empty() {
+                losses.push("unspecified image features");
+            }
+            Ok(losses)
+        }
+        ParserWriteCompatibility::Incompatible | ParserWriteCompatibility::UnsupportedFormat => {
+            Err(format!("Output format {} cannot write this image", format))
+        }
+    }
+}
+
+pub(crate) struct ConvertCommand;
+
+impl Command for ConvertCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let argv = args.argv.unwrap_or_default();
+        let opts = ConvertOptions::parse(&argv).map_err(|e| format!("{}\nUsage: convert {}", e, self.usage()))?;
+        let di = app.di.as_ref().ok_or_else(|| "No disk image loaded".to_string())?;
+
+        let output_format = opts.format;
+        let losses = write_losses(di, output_format)?;
+
+        let mut summary = String::new();
+        if !losses.is_empty() {
+            summary.push_str(&format!("Converting to {} will lose:", output_format));
+            for loss in &losses {
+                summary.push_str(&format!("\n  {}", loss));
+            }
+            if !opts.confirmed {
+                summary.push_str("\nRe-run with --yes to convert anyway.");
+                return Ok(CommandResult::Success(summary));
+            }
+            summary.push('\n');
+        }
+
+        let write_options = opts.write_options();
+        let inner_filename = opts.filename.clone();
+        app.start_job("Converting Disk Image", move |di, job| {
+            let mut out_buffer = Cursor::new(Vec::new());
+            let write_options = write_options.with_callback(job.saving_callback());
+            output_format
+                .save_image(di, &write_options, &mut out_buffer)
+                .map_err(|e| format!("Error converting image: {}", e))?;
+
+            // Writing the file is the last chance to back out.
+            job.check_cancel()?;
+            std::fs::write(&inner_filename, out_buffer.into_inner())
+                .map_err(|e| format!("Error writing {}: {}", inner_filename.display(), e))?;
+            job.progress(1.0);
+
+            Ok(format!(
+                "Converted image to {}: {}",
+                output_format,
+                inner_filename.display()
+            ))
+        })?;
+
+        summary.push_str(&format!("Converting to {}...", opts.filename.display()));
+        Ok(CommandResult::Success(summary))
+    }
+
+    fn usage(&self) -> String {
+        "<format> [--revolutions <n>] [--yes] <filename>".into()
+    }
+
+    fn desc(&self) -> String {
+        "Convert the disk image to the format given by name or extension".into()
+    }
+}
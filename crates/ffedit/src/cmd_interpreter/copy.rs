@@ -0,0 +1,85 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::{AppContext, AppEvent},
+    cmd_interpreter::{parse_chs, read_sector_data, write_sector_data, Command, CommandArgs, CommandResult},
+    undo::{Snapshot, UndoEntry},
+};
+use fluxfox::{prelude::*, types::DiskImageFlags};
+
+pub(crate) struct CopyCommand;
+
+impl Command for CopyCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let argv = args.argv.unwrap_or_default();
+        if argv.len() != 2 {
+            return Err(format!("Usage: copy {}", self.usage()));
+        }
+        let src = parse_chs(&argv[0], &app.selection)?;
+        let dst = parse_chs(&argv[1], &app.selection)?;
+        if src == dst {
+            return Err("Source and destination are the same sector".into());
+        }
+
+        let di = app.di.as_mut().ok_or_else(|| "No disk image loaded".to_string())?;
+        let src_rsr = read_sector_data(di, src.ch(), src)?;
+        let dst_rsr = read_sector_data(di, dst.ch(), dst)?;
+        if src_rsr.data().len() != dst_rsr.data().len() {
+            return Err(format!(
+                "Sector sizes differ: {} is {} bytes, {} is {} bytes",
+                src,
+                src_rsr.data().len(),
+                dst,
+                dst_rsr.data().len()
+            ));
+        }
+
+        // The destination keeps its own data mark; only the data is copied.
+        let snapshot = Snapshot::track(di, dst.ch())?;
+        write_sector_data(di, dst.ch(), dst, src_rsr.data(), dst_rsr.deleted_mark)?;
+        di.set_flag(DiskImageFlags::DIRTY);
+        app.undo.push(UndoEntry::new(
+            format!("copy sector {} to {}", src, dst),
+            vec![snapshot],
+        ));
+        _ = app.sender.send(AppEvent::DiskImageModified);
+
+        let mut response = format!("Copied {} bytes from {} to {}", src_rsr.data().len(), src, dst);
+        if src_rsr.data_crc_error {
+            response.push_str(&format!("\nWarning: source sector {} has a data CRC error", src));
+        }
+        Ok(CommandResult::Success(response))
+    }
+
+    fn usage(&self) -> String {
+        "<src c/h/s | s> <dst c/h/s | s>".into()
+    }
+
+    fn desc(&self) -> String {
+        "Copy the data of one sector to another".into()
+    }
+}
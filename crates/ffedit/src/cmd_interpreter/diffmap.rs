@@ -0,0 +1,136 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::AppContext,
+    cmd_interpreter::{Command, CommandArgs, CommandResult},
+};
+use fluxfox::{
+    diff::{DiffSide, SectorDifference, TrackDifference},
+    prelude::*,
+};
+
+/// The number of sector differences listed by `diffmap`.
+const MAX_LISTED_DIFFERENCES: usize = 50;
+
+fn side(side: &DiffSide) -> &'static str {
+    match side {
+        DiffSide::Left => "primary",
+        DiffSide::Right => "secondary",
+    }
+}
+
+fn track_difference(difference: &TrackDifference) -> String {
+    match difference {
+        TrackDifference::OnlyIn(s) => format!("only in {}", side(s)),
+        TrackDifference::WeakBits { left, right } => format!("weak bits: primary {} secondary {}", left, right),
+    }
+}
+
+fn sector_difference(difference: &SectorDifference) -> String {
+    match difference {
+        SectorDifference::OnlyIn(s) => format!("only in {}", side(s)),
+        SectorDifference::Unreadable { left, right } => format!("read failed: primary {} secondary {}", left, right),
+        SectorDifference::Data { differing_bytes, .. } => format!("{} bytes differ", differing_bytes),
+        SectorDifference::AddressCrc { left, right } => {
+            format!("address CRC valid: primary {} secondary {}", !left, !right)
+        }
+        SectorDifference::DataCrc { left, right } => {
+            format!("data CRC valid: primary {} secondary {}", !left, !right)
+        }
+        SectorDifference::DeletedMark { left, right } => format!("deleted: primary {} secondary {}", left, right),
+        SectorDifference::NoDam { left, right } => format!("no DAM: primary {} secondary {}", left, right),
+        SectorDifference::WeakMask => "weak bit masks differ".to_string(),
+    }
+}
+
+pub(crate) struct DiffMapCommand;
+
+impl Command for DiffMapCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        match args.argv.as_deref() {
+            None => {}
+            Some([arg]) if arg == "off" => {
+                match &app.di {
+                    Some(di) => app.track_map.borrow_mut().set_diff_mode(false, di, None),
+                    None => app.track_map.borrow_mut().diff_mode = false,
+                }
+                return Ok(CommandResult::Success("Showing the track map".into()));
+            }
+            Some(_) => return Err(format!("Usage: diffmap {}", self.usage())),
+        }
+
+        let di = app.di.as_ref().ok_or_else(|| "No disk image loaded".to_string())?;
+        let secondary = app
+            .secondary
+            .as_ref()
+            .ok_or_else(|| "No secondary image loaded. Open one with 'open --secondary <filename>'".to_string())?;
+
+        let mut track_map = app.track_map.borrow_mut();
+        track_map.set_diff_mode(true, di, Some(secondary));
+
+        // Share the diff with the track map, which only recomputes it after either image changes.
+        let diff = track_map.diff(di, secondary);
+        if diff.is_empty() {
+            return Ok(CommandResult::Success("Images are identical".into()));
+        }
+
+        let mut response = format!(
+            "Images differ in {} tracks ({} track and {} sector differences)",
+            diff.differing_tracks().len(),
+            diff.tracks.len(),
+            diff.sectors.len()
+        );
+        for track in &diff.tracks {
+            for difference in &track.differences {
+                response.push_str(&format!("\n  track {}: {}", track.ch, track_difference(difference)));
+            }
+        }
+        for sector in diff.sectors.iter().take(MAX_LISTED_DIFFERENCES) {
+            let differences: Vec<String> = sector.differences.iter().map(sector_difference).collect();
+            response.push_str(&format!(
+                "\n  sector {}: {}",
+                DiskChs::from((sector.ch, sector.id.s())),
+                differences.join(", ")
+            ));
+        }
+        if diff.sectors.len() > MAX_LISTED_DIFFERENCES {
+            response.push_str(&format!(
+                "\n  ...and {} more sectors",
+                diff.sectors.len() - MAX_LISTED_DIFFERENCES
+            ));
+        }
+        Ok(CommandResult::Success(response))
+    }
+
+    fn usage(&self) -> String {
+        "[off]".into()
+    }
+
+    fn desc(&self) -> String {
+        "Show the sectors that differ from the secondary image".into()
+    }
+}
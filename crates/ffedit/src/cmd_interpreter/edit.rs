@@ -0,0 +1,81 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::{AppContext, AppEvent},
+    cmd_interpreter::{s::SectorCommand, Command, CommandArgs, CommandResult},
+    disk_selection::SelectionLevel,
+};
+
+pub(crate) struct EditCommand;
+
+impl Command for EditCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        if app.di.is_none() {
+            return Err("No disk image loaded".into());
+        }
+
+        match args.argv.as_deref() {
+            None => {}
+            Some([arg]) if arg == "off" => {
+                app.db.borrow_mut().edit_mode = false;
+                _ = app.sender.send(AppEvent::DiskSelectionChanged);
+                return Ok(CommandResult::Success("Finished editing".into()));
+            }
+            Some([sector]) => {
+                // Select the sector to edit as the 's' command would.
+                SectorCommand.execute(
+                    app,
+                    CommandArgs {
+                        command: "s".into(),
+                        argv: Some(vec![sector.clone()]),
+                        raw_args: None,
+                    },
+                )?;
+            }
+            Some(_) => return Err(format!("Usage: edit {}", self.usage())),
+        }
+
+        if app.selection.level < SelectionLevel::Sector {
+            return Err("No sector selected. Select a sector with 's' or give a sector # to edit".into());
+        }
+
+        app.db.borrow_mut().edit_mode = true;
+        _ = app.sender.send(AppEvent::DiskSelectionChanged);
+        Ok(CommandResult::Success(format!(
+            "Editing sector {}. Patch bytes with 'poke <offset> <bytes...>', finish with 'edit off'",
+            app.selection.sector.unwrap_or(0)
+        )))
+    }
+
+    fn usage(&self) -> String {
+        "[sector # | off]".into()
+    }
+
+    fn desc(&self) -> String {
+        "Open the selected sector in the hex editor, or close it".into()
+    }
+}
@@ -0,0 +1,107 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::{AppContext, AppEvent},
+    cmd_interpreter::{poke::parse_byte, read_sector_data, write_sector_data, Command, CommandArgs, CommandResult},
+    disk_selection::SelectionLevel,
+    undo::{Snapshot, UndoEntry},
+};
+use fluxfox::{prelude::*, types::DiskImageFlags};
+
+pub(crate) struct FillCommand;
+
+impl Command for FillCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let argv = args.argv.unwrap_or_default();
+        if argv.len() != 1 {
+            return Err(format!("Usage: fill {}", self.usage()));
+        }
+        let byte = parse_byte(&argv[0])?;
+
+        let ch = app
+            .selection
+            .into_ch()
+            .map_err(|_| "Select a track or sector to fill".to_string())?;
+        let di = app.di.as_mut().ok_or_else(|| "No disk image loaded".to_string())?;
+
+        // Fill the selected sector, or every sector with data on the selected track. Sectors on the
+        // track are matched by their full IDs, which need not match the physical track.
+        let sector = match app.selection.level() {
+            SelectionLevel::Sector => Some(app.selection.into_chs().map_err(|e| e.to_string())?),
+            _ => None,
+        };
+        let targets: Vec<DiskChsnQuery> = match sector {
+            Some(chs) => vec![chs.into()],
+            None => {
+                let track = di.track(ch).ok_or_else(|| format!("Track {} not found", ch))?;
+                track
+                    .sector_list()
+                    .iter()
+                    .filter(|entry| !entry.attributes.no_dam && entry.duplicate_idx == 0)
+                    .map(|entry| entry.chsn.into())
+                    .collect()
+            }
+        };
+        if targets.is_empty() {
+            return Err(format!("No sectors with data on track {}", ch));
+        }
+
+        let snapshots = vec![Snapshot::track(di, ch)?];
+        let mut filled = 0;
+        let mut result = Ok(());
+        for &id in &targets {
+            result = read_sector_data(di, ch, id)
+                .and_then(|rsr| write_sector_data(di, ch, id, &vec![byte; rsr.data().len()], rsr.deleted_mark));
+            if result.is_err() {
+                break;
+            }
+            filled += 1;
+        }
+
+        let description = match sector {
+            Some(chs) => format!("fill sector {} with {:02X}", chs, byte),
+            None => format!("fill {} sectors on track {} with {:02X}", filled, ch, byte),
+        };
+        // Record the sectors filled even if a later one failed, so they can be undone.
+        if filled > 0 {
+            di.set_flag(DiskImageFlags::DIRTY);
+            app.undo.push(UndoEntry::new(description.clone(), snapshots));
+            _ = app.sender.send(AppEvent::DiskImageModified);
+        }
+        result?;
+
+        Ok(CommandResult::Success(format!("Done: {}", description)))
+    }
+
+    fn usage(&self) -> String {
+        "<byte>".into()
+    }
+
+    fn desc(&self) -> String {
+        "Fill the selected sector, or every sector on the selected track, with a byte".into()
+    }
+}
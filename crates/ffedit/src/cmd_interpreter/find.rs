@@ -0,0 +1,146 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::{AppContext, AppEvent},
+    cmd_interpreter::{poke::parse_byte, Command, CommandArgs, CommandResult},
+    disk_selection::{DiskSelection, SelectionLevel},
+    search::{search_sectors, SearchResults, SearchScope},
+};
+
+/// The number of matches listed by `find`. Use `findnext` to step through the rest.
+const MAX_LISTED_MATCHES: usize = 50;
+
+/// Parse a hex search pattern. Each argument is one or more bytes in hexadecimal, such as
+/// `de ad` or `dead`.
+fn parse_hex_pattern(argv: &[String]) -> Result<Vec<u8>, String> {
+    let mut pattern = Vec::new();
+    for arg in argv {
+        let hex = arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")).unwrap_or(arg);
+        if hex.is_empty() || hex.len() % 2 != 0 || !hex.is_ascii() {
+            return Err(format!("Invalid hex bytes: {}", arg));
+        }
+        for i in (0..hex.len()).step_by(2) {
+            pattern.push(parse_byte(&hex[i..i + 2])?);
+        }
+    }
+    Ok(pattern)
+}
+
+pub(crate) struct FindCommand;
+
+impl Command for FindCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let argv = args.argv.unwrap_or_default();
+        let here = argv.iter().any(|arg| arg == "--here");
+        let terms: Vec<String> = argv.into_iter().filter(|arg| arg != "--here").collect();
+        if terms.is_empty() {
+            return Err(format!("Usage: find {}", self.usage()));
+        }
+
+        // Quoted arguments are searched for as text, anything else as hex bytes.
+        let quoted = args.raw_args.as_deref().is_some_and(|raw| raw.contains('"'));
+        let pattern = if quoted {
+            terms.join(" ").into_bytes()
+        }
+        else {
+            parse_hex_pattern(&terms)?
+        };
+
+        let scope = if here {
+            SearchScope::from_selection(&app.selection)
+        }
+        else {
+            SearchScope::Disk
+        };
+
+        let di = app.di.as_ref().ok_or_else(|| "No disk image loaded".to_string())?;
+        let matches = search_sectors(di, &pattern, scope);
+
+        let mut response = format!(
+            "Found {} matches for {} bytes in {}",
+            matches.len(),
+            pattern.len(),
+            scope
+        );
+        for m in matches.iter().take(MAX_LISTED_MATCHES) {
+            response.push_str(&format!("\n  {}", m));
+        }
+        if matches.len() > MAX_LISTED_MATCHES {
+            response.push_str(&format!(
+                "\n  ...and {} more. Use 'findnext' to step through all matches",
+                matches.len() - MAX_LISTED_MATCHES
+            ));
+        }
+
+        app.search = SearchResults::new(matches);
+        Ok(CommandResult::Success(response))
+    }
+
+    fn usage(&self) -> String {
+        "[--here] <hex bytes... | \"text\">".into()
+    }
+
+    fn desc(&self) -> String {
+        "Search sector data, limited to the current selection with --here".into()
+    }
+}
+
+pub(crate) struct FindNextCommand;
+
+impl Command for FindNextCommand {
+    fn execute(&self, app: &mut AppContext, _args: CommandArgs) -> Result<CommandResult, String> {
+        let total = app.search.matches.len();
+        let (index, m) = app
+            .search
+            .next_match()
+            .ok_or_else(|| "No matches. Search with 'find' first".to_string())?;
+
+        // Select the sector containing the match.
+        app.selection = DiskSelection {
+            level: SelectionLevel::Sector,
+            head: Some(m.ch.h()),
+            cylinder: Some(m.ch.c()),
+            sector: Some(m.id.s()),
+        };
+        _ = app.sender.send(AppEvent::DiskSelectionChanged);
+
+        Ok(CommandResult::Success(format!(
+            "Match {} of {}: {}",
+            index + 1,
+            total,
+            m
+        )))
+    }
+
+    fn usage(&self) -> String {
+        "".into()
+    }
+
+    fn desc(&self) -> String {
+        "Select the sector containing the next match of the last search".into()
+    }
+}
@@ -55,7 +55,7 @@ impl Command for ListCommand {
                         .head
                         .ok_or_else(|| "Invalid selection level".to_string())?;
 
-                    let track_ct = di.tracks(h);
+                    let track_ct = di.track_ct(h as usize) as u16;
 
                     result_string.push_str(&format!("Head {}, {} tracks:\n", h, track_ct));
 
@@ -24,14 +24,30 @@
 
     --------------------------------------------------------------------------
 */
+mod analyze;
 mod c;
+mod compare;
+mod convert;
+mod copy;
+mod diffmap;
+mod edit;
+mod fill;
+mod find;
 mod h;
 mod list;
 mod open;
+mod poke;
+mod rawtrack;
+mod run;
 mod s;
+mod save;
+mod take;
+mod undo;
 mod up;
+mod verify;
 
-use crate::app::AppContext;
+use crate::{app::AppContext, disk_selection::DiskSelection};
+use fluxfox::{prelude::*, types::ReadSectorResult};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 
@@ -137,6 +153,32 @@ impl CommandInterpreter {
         self.registry.register_command("s", Box::new(s::SectorCommand));
         self.registry.register_command("up", Box::new(up::UpCommand));
         self.registry.register_command("list", Box::new(list::ListCommand));
+        self.registry
+            .register_command("convert", Box::new(convert::ConvertCommand));
+        self.registry
+            .register_command("verify", Box::new(verify::VerifyCommand));
+        self.registry
+            .register_command("analyze", Box::new(analyze::AnalyzeCommand));
+        self.registry.register_command("edit", Box::new(edit::EditCommand));
+        self.registry.register_command("poke", Box::new(poke::PokeCommand));
+        self.registry.register_command("save", Box::new(save::SaveCommand));
+        self.registry.register_command("undo", Box::new(undo::UndoCommand));
+        self.registry.register_command("redo", Box::new(undo::RedoCommand));
+        self.registry
+            .register_command("changes", Box::new(undo::ChangesCommand));
+        self.registry.register_command("run", Box::new(run::RunCommand));
+        self.registry.register_command("find", Box::new(find::FindCommand));
+        self.registry
+            .register_command("findnext", Box::new(find::FindNextCommand));
+        self.registry.register_command("fill", Box::new(fill::FillCommand));
+        self.registry.register_command("copy", Box::new(copy::CopyCommand));
+        self.registry
+            .register_command("compare", Box::new(compare::CompareCommand));
+        self.registry
+            .register_command("diffmap", Box::new(diffmap::DiffMapCommand));
+        self.registry.register_command("take", Box::new(take::TakeCommand));
+        self.registry
+            .register_command("rawtrack", Box::new(rawtrack::RawTrackCommand));
     }
 
     // Command processor
@@ -218,3 +260,60 @@ fn split_quoted(input: &str) -> Vec<String> {
 
     result
 }
+
+/// Parse a sector address given as `c/h/s`, or as a sector ID alone for a sector on the selected
+/// track.
+fn parse_chs(arg: &str, selection: &DiskSelection) -> Result<DiskChs, String> {
+    let invalid = || format!("Invalid sector address: {} (expected c/h/s or sector #)", arg);
+    let parts = arg.split('/').collect::<Vec<_>>();
+    match parts.as_slice() {
+        [s] => {
+            let ch = selection.into_ch().map_err(|_| invalid())?;
+            let s = s.parse::<u8>().map_err(|_| invalid())?;
+            Ok(DiskChs::from((ch, s)))
+        }
+        [c, h, s] => {
+            let c = c.parse::<u16>().map_err(|_| invalid())?;
+            let h = h.parse::<u8>().map_err(|_| invalid())?;
+            let s = s.parse::<u8>().map_err(|_| invalid())?;
+            Ok(DiskChs::new(c, h, s))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Read the data of the sector matching `id` on the track `ch`, failing if the sector is missing or
+/// has no data.
+fn read_sector_data(di: &DiskImage, ch: DiskCh, id: impl Into<DiskChsnQuery>) -> Result<ReadSectorResult, String> {
+    let id = id.into();
+    let chs = DiskChs::from((ch, id.s()));
+    let rsr = di
+        .read_sector(ch, id, None, None, RwScope::DataOnly, false)
+        .map_err(|e| format!("Error reading sector {}: {}", chs, e))?;
+    if rsr.not_found {
+        return Err(format!("Sector {} not found", chs));
+    }
+    if rsr.no_dam {
+        return Err(format!("Sector {} has no data", chs));
+    }
+    Ok(rsr)
+}
+
+/// Overwrite the data of the sector matching `id` on the track `ch`.
+fn write_sector_data(
+    di: &mut DiskImage,
+    ch: DiskCh,
+    id: impl Into<DiskChsnQuery>,
+    data: &[u8],
+    deleted: bool,
+) -> Result<(), String> {
+    let id = id.into();
+    let chs = DiskChs::from((ch, id.s()));
+    let wsr = di
+        .write_sector(ch, id, None, data, RwScope::DataOnly, deleted, false)
+        .map_err(|e| format!("Error writing sector {}: {}", chs, e))?;
+    if wsr.not_found || wsr.no_dam || wsr.address_crc_error {
+        return Err(format!("Sector {} could not be written", chs));
+    }
+    Ok(())
+}
@@ -35,16 +35,21 @@ pub(crate) struct OpenCommand;
 impl Command for OpenCommand {
     fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
         if let Some(argv) = args.argv {
-            if argv.len() != 1 {
-                return Err(format!("Usage: open {}", self.usage()));
-            }
-            let filename = &argv[0];
+            let (secondary, filename) = match argv.as_slice() {
+                [filename] => (false, filename),
+                [flag, filename] if flag == "--secondary" => (true, filename),
+                _ => return Err(format!("Usage: open {}", self.usage())),
+            };
             //app.file_opened = Some(filename.clone());
 
-            if let Err(e) = app
-                .sender
-                .send(AppEvent::OpenFileRequest(PathBuf::from(filename.clone())))
-            {
+            let path = PathBuf::from(filename.clone());
+            let event = if secondary {
+                AppEvent::OpenSecondaryRequest(path)
+            }
+            else {
+                AppEvent::OpenFileRequest(path)
+            };
+            if let Err(e) = app.sender.send(event) {
                 return Err(format!("Internal error: {}", e));
             }
 
@@ -56,10 +61,10 @@ impl Command for OpenCommand {
     }
 
     fn usage(&self) -> String {
-        "<filename>".into()
+        "[--secondary] <filename>".into()
     }
 
     fn desc(&self) -> String {
-        "Open a disk image file".into()
+        "Open a disk image file, or a secondary image to compare against".into()
     }
 }
@@ -0,0 +1,122 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::{AppContext, AppEvent},
+    cmd_interpreter::{read_sector_data, write_sector_data, Command, CommandArgs, CommandResult},
+    undo::{Snapshot, UndoEntry},
+};
+use fluxfox::{prelude::*, types::DiskImageFlags};
+
+/// Parse a byte offset, in hexadecimal with a `0x` prefix or otherwise in decimal.
+fn parse_offset(arg: &str) -> Result<usize, String> {
+    match arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => arg.parse::<usize>(),
+    }
+    .map_err(|_| format!("Invalid offset: {}", arg))
+}
+
+/// Parse a byte value in hexadecimal, with or without a `0x` prefix.
+pub(super) fn parse_byte(arg: &str) -> Result<u8, String> {
+    let hex = arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")).unwrap_or(arg);
+    u8::from_str_radix(hex, 16).map_err(|_| format!("Invalid byte value: {}", arg))
+}
+
+pub(crate) struct PokeCommand;
+
+impl Command for PokeCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let argv = args.argv.unwrap_or_default();
+        if argv.len() < 2 {
+            return Err(format!("Usage: poke {}", self.usage()));
+        }
+        if !app.db.borrow().edit_mode {
+            return Err("No sector is being edited. Open a sector with 'edit' first".into());
+        }
+
+        let offset = parse_offset(&argv[0])?;
+        let bytes = argv[1..]
+            .iter()
+            .map(|arg| parse_byte(arg))
+            .collect::<Result<Vec<u8>, String>>()?;
+
+        let ch = app.selection.into_ch().map_err(|e| e.to_string())?;
+        let chs = app.selection.into_chs().map_err(|e| e.to_string())?;
+        let di = app.di.as_mut().ok_or_else(|| "No disk image loaded".to_string())?;
+
+        // Sectors are written whole, so read the current data and patch it.
+        let rsr = read_sector_data(di, ch, chs)?;
+
+        let mut data = rsr.data().to_vec();
+        let Some(end) = offset.checked_add(bytes.len()).filter(|&end| end <= data.len())
+        else {
+            return Err(format!(
+                "Patch of {} bytes at offset {:#X} exceeds sector size of {} bytes",
+                bytes.len(),
+                offset,
+                data.len()
+            ));
+        };
+        // Rewriting the sector stores clean data, so a bad CRC or weak bits may not survive the poke.
+        let weak = di.track(ch).is_some_and(|track| track.has_weak_bits());
+        let snapshot = Snapshot::track(di, ch)?;
+        data[offset..end].copy_from_slice(&bytes);
+
+        write_sector_data(di, ch, chs, &data, rsr.deleted_mark)?;
+        di.set_flag(DiskImageFlags::DIRTY);
+        app.undo.push(UndoEntry::new(
+            format!("poke {} bytes at {:#X} in sector {}", bytes.len(), offset, chs),
+            vec![snapshot],
+        ));
+
+        app.db.borrow_mut().patched.extend(offset..end);
+        _ = app.sender.send(AppEvent::DiskImageModified);
+
+        let mut response = format!("Wrote {} bytes at offset {:#X} of sector {}", bytes.len(), offset, chs);
+        if rsr.data_crc_error {
+            response.push_str(&format!(
+                "\nWarning: sector {} had a data CRC error, which may not be preserved",
+                chs
+            ));
+        }
+        if weak {
+            response.push_str(&format!(
+                "\nWarning: track {} has weak bits, which may not be preserved",
+                ch
+            ));
+        }
+        Ok(CommandResult::Success(response))
+    }
+
+    fn usage(&self) -> String {
+        "<offset> <byte> [byte...]".into()
+    }
+
+    fn desc(&self) -> String {
+        "Patch bytes of the sector being edited, at an offset in decimal or 0x hex, with hex byte values".into()
+    }
+}
@@ -0,0 +1,114 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::{AppContext, AppEvent},
+    cmd_interpreter::{Command, CommandArgs, CommandResult},
+    components::data_block::DataBlockType,
+    disk_selection::SelectionLevel,
+};
+
+pub(crate) struct RawTrackCommand;
+
+impl RawTrackCommand {
+    /// Open the raw track view of the selected track, moving up from a selected sector.
+    fn open(&self, app: &mut AppContext) -> Result<CommandResult, String> {
+        let di = app.di.as_ref().ok_or_else(|| "No disk image loaded".to_string())?;
+        if app.selection.level < SelectionLevel::Cylinder {
+            return Err("No track selected. Select a track with 'c'".into());
+        }
+        let ch = app.selection.into_ch().map_err(|e| e.to_string())?;
+        if di.track(ch).is_none() {
+            return Err(format!("Track {} not found", ch));
+        }
+
+        app.selection.level = SelectionLevel::Cylinder;
+        app.selection.sector = None;
+        app.db.borrow_mut().raw_mode = true;
+        _ = app.sender.send(AppEvent::DiskSelectionChanged);
+
+        Ok(CommandResult::Success(format!(
+            "Showing raw track {}. Step through its elements with 'rawtrack next [name]'",
+            ch
+        )))
+    }
+}
+
+impl Command for RawTrackCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let argv = args.argv.unwrap_or_default();
+        let Some(subcommand) = argv.first()
+        else {
+            return self.open(app);
+        };
+
+        if subcommand == "off" {
+            app.db.borrow_mut().raw_mode = false;
+            _ = app.sender.send(AppEvent::DiskSelectionChanged);
+            return Ok(CommandResult::Success("Closed the raw track view".into()));
+        }
+
+        let mut db = app.db.borrow_mut();
+        if !db.raw_mode || !matches!(db.block_type, DataBlockType::Track) {
+            return Err("The raw track view is not open. Open it with 'rawtrack'".into());
+        }
+        if db.elements.is_empty() {
+            return Err("No elements were found on this track".into());
+        }
+
+        let index = match subcommand.as_str() {
+            "list" => {
+                let lines: Vec<String> = (0..db.elements.len()).map(|i| db.describe_element(i)).collect();
+                return Ok(CommandResult::Success(lines.join("\n")));
+            }
+            "next" | "prev" => {
+                let filter = argv[1..].join(" ");
+                db.find_element(subcommand == "next", &filter)
+                    .ok_or_else(|| format!("No element named '{}' on this track", filter))?
+            }
+            _ => subcommand
+                .parse::<usize>()
+                .map_err(|_| format!("Usage: rawtrack {}", self.usage()))?,
+        };
+
+        if !db.select_element(index) {
+            return Err(format!(
+                "Element {} not found. This track has {} elements",
+                index,
+                db.elements.len()
+            ));
+        }
+        Ok(CommandResult::Success(db.describe_element(index)))
+    }
+
+    fn usage(&self) -> String {
+        "[off | list | next [name] | prev [name] | <element #>]".into()
+    }
+
+    fn desc(&self) -> String {
+        "Show the selected track's raw data with its elements highlighted".into()
+    }
+}
@@ -0,0 +1,65 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::{AppContext, AppEvent},
+    cmd_interpreter::{Command, CommandArgs, CommandResult},
+    script::read_script,
+};
+use std::path::PathBuf;
+
+pub(crate) struct RunCommand;
+
+impl Command for RunCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let argv = args.argv.unwrap_or_default();
+        if argv.len() != 1 {
+            return Err(format!("Usage: run {}", self.usage()));
+        }
+
+        let path = PathBuf::from(&argv[0]);
+        let lines = read_script(&path)?;
+        let line_ct = lines.len();
+
+        app.sender
+            .send(AppEvent::RunScript(lines))
+            .map_err(|e| format!("Internal error: {}", e))?;
+
+        Ok(CommandResult::Success(format!(
+            "Running {} commands from {}...",
+            line_ct,
+            path.display()
+        )))
+    }
+
+    fn usage(&self) -> String {
+        "<filename>".into()
+    }
+
+    fn desc(&self) -> String {
+        "Run the commands in a script file".into()
+    }
+}
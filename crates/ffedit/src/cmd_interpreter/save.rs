@@ -0,0 +1,151 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::{AppContext, AppEvent},
+    cmd_interpreter::{convert::write_losses, Command, CommandArgs, CommandResult},
+};
+use fluxfox::{format_from_ext, prelude::*, types::DiskImageFlags};
+use std::path::PathBuf;
+
+struct SaveOptions {
+    filename:  Option<PathBuf>,
+    format:    Option<DiskImageFileFormat>,
+    verify:    bool,
+    confirmed: bool,
+}
+
+impl SaveOptions {
+    fn parse(argv: &[String]) -> Result<Self, String> {
+        let mut filename = None;
+        let mut format = None;
+        let mut verify = false;
+        let mut confirmed = false;
+        let mut opts = argv.iter();
+        while let Some(opt) = opts.next() {
+            match opt.as_str() {
+                "-y" | "--yes" => confirmed = true,
+                "--verify" => verify = true,
+                "-f" | "--format" => {
+                    let value = opts.next().ok_or_else(|| format!("{} requires a value", opt))?;
+                    format = Some(format_from_ext(value).ok_or_else(|| format!("Unknown output format: {}", value))?);
+                }
+                _ if opt.starts_with('-') => return Err(format!("Unknown option: {}", opt)),
+                _ if filename.is_none() => filename = Some(PathBuf::from(opt)),
+                _ => return Err("Only one filename may be given".into()),
+            }
+        }
+
+        Ok(Self {
+            filename,
+            format,
+            verify,
+            confirmed,
+        })
+    }
+}
+
+pub(crate) struct SaveCommand;
+
+impl Command for SaveCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let argv = args.argv.unwrap_or_default();
+        let opts = SaveOptions::parse(&argv).map_err(|e| format!("{}\nUsage: save {}", e, self.usage()))?;
+        let di = app.di.as_ref().ok_or_else(|| "No disk image loaded".to_string())?;
+
+        let filename = opts
+            .filename
+            .clone()
+            .or_else(|| app.di_path.clone())
+            .ok_or_else(|| "No filename given, and the disk image was not loaded from a file".to_string())?;
+
+        // Use the format given, then the format named by the file extension, then the format the
+        // image was loaded from.
+        let output_format = opts
+            .format
+            .or_else(|| {
+                filename
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .and_then(format_from_ext)
+            })
+            .or_else(|| di.source_format())
+            .ok_or_else(|| format!("Could not determine an output format for {}", filename.display()))?;
+
+        let losses = write_losses(di, output_format)?;
+
+        // Saving an image back to its own file is a normal save. Writing over any other existing
+        // file needs confirmation.
+        let overwrite = filename.exists() && app.di_path.as_ref() != Some(&filename);
+
+        let mut summary = String::new();
+        if !losses.is_empty() {
+            summary.push_str(&format!("Saving as {} will lose:", output_format));
+            for loss in &losses {
+                summary.push_str(&format!("\n  {}", loss));
+            }
+            summary.push('\n');
+        }
+        if overwrite {
+            summary.push_str(&format!(
+                "{} already exists and will be overwritten.\n",
+                filename.display()
+            ));
+        }
+        if (overwrite || !losses.is_empty()) && !opts.confirmed {
+            summary.push_str("Re-run with --yes to save anyway.");
+            return Ok(CommandResult::Success(summary));
+        }
+
+        let verify = opts.verify;
+        let sender = app.sender.clone();
+        let inner_filename = filename.clone();
+        app.start_job("Saving Disk Image", move |di, job| {
+            ImageWriter::new(di)
+                .with_format(output_format)
+                .with_path(inner_filename.clone())
+                .with_verify(verify)
+                .with_callback(job.saving_callback())
+                .write()
+                .map_err(|e| format!("Error saving {}: {}", inner_filename.display(), e))?;
+
+            di.clear_flag(DiskImageFlags::DIRTY);
+            _ = sender.send(AppEvent::DiskImageSaved(inner_filename.clone()));
+            Ok(format!("Saved {} image: {}", output_format, inner_filename.display()))
+        })?;
+
+        summary.push_str(&format!("Saving to {}...", filename.display()));
+        Ok(CommandResult::Success(summary))
+    }
+
+    fn usage(&self) -> String {
+        "[--format <format>] [--verify] [--yes] [filename]".into()
+    }
+
+    fn desc(&self) -> String {
+        "Save the disk image, to its own file or to a new one, in the format given by name or extension".into()
+    }
+}
@@ -0,0 +1,138 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::{AppContext, AppEvent},
+    cmd_interpreter::{parse_chs, read_sector_data, write_sector_data, Command, CommandArgs, CommandResult},
+    undo::{Snapshot, UndoEntry},
+};
+use fluxfox::{prelude::*, types::DiskImageFlags};
+
+pub(crate) struct TakeCommand;
+
+impl Command for TakeCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let argv = args.argv.unwrap_or_default();
+        if argv.is_empty() {
+            return Err(format!("Usage: take {}", self.usage()));
+        }
+
+        let di = app.di.as_mut().ok_or_else(|| "No disk image loaded".to_string())?;
+        let secondary = app
+            .secondary
+            .as_ref()
+            .ok_or_else(|| "No secondary image loaded. Open one with 'open --secondary <filename>'".to_string())?;
+
+        let bad_only = argv.len() == 1 && argv[0] == "--bad";
+        let targets: Vec<DiskChs> = if bad_only {
+            // Every sector with a data CRC error in the primary image.
+            di.sectors()
+                .filter(|s| s.entry.attributes.data_error && s.entry.duplicate_idx == 0)
+                .map(|s| DiskChs::from((s.ch, s.id().s())))
+                .collect()
+        }
+        else {
+            argv.iter()
+                .map(|arg| parse_chs(arg, &app.selection))
+                .collect::<Result<_, _>>()?
+        };
+
+        // Check every sector before writing any, so that a bad address leaves the image unchanged.
+        let mut takes = Vec::new();
+        let mut report = Vec::new();
+        for chs in targets {
+            let src = match read_sector_data(secondary, chs.ch(), chs) {
+                Ok(src) => src,
+                Err(e) if bad_only => {
+                    report.push(format!("Skipped {}: {}", chs, e));
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if bad_only && src.data_crc_error {
+                report.push(format!("Skipped {}: bad in both images", chs));
+                continue;
+            }
+            let dst = read_sector_data(di, chs.ch(), chs)?;
+            if src.data().len() != dst.data().len() {
+                return Err(format!(
+                    "Sector {} is {} bytes in the secondary image but {} bytes in the primary",
+                    chs,
+                    src.data().len(),
+                    dst.data().len()
+                ));
+            }
+
+            takes.push((chs, src, dst.deleted_mark));
+        }
+
+        if takes.is_empty() {
+            report.push("No sectors were taken from the secondary image".into());
+            return Ok(CommandResult::Success(report.join("\n")));
+        }
+
+        let mut snapshots = Vec::new();
+        let mut taken = 0;
+        let mut result = Ok(());
+        for (chs, src, deleted) in takes {
+            result = Snapshot::save_once(&mut snapshots, di, chs.ch())
+                .and_then(|_| write_sector_data(di, chs.ch(), chs, src.data(), deleted));
+            if result.is_err() {
+                break;
+            }
+            taken += 1;
+            if src.data_crc_error {
+                report.push(format!(
+                    "Took {} (warning: it has a data CRC error in the secondary)",
+                    chs
+                ));
+            }
+            else {
+                report.push(format!("Took {}", chs));
+            }
+        }
+
+        // Record the sectors written even if a later write failed, so they can be undone.
+        if taken > 0 {
+            di.set_flag(DiskImageFlags::DIRTY);
+            app.undo.push(UndoEntry::new(
+                format!("take {} sectors from secondary image", taken),
+                snapshots,
+            ));
+            _ = app.sender.send(AppEvent::DiskImageModified);
+        }
+        result?;
+        Ok(CommandResult::Success(report.join("\n")))
+    }
+
+    fn usage(&self) -> String {
+        "<c/h/s | s>... | --bad".into()
+    }
+
+    fn desc(&self) -> String {
+        "Copy sectors from the secondary image, or every sector with a CRC error with --bad".into()
+    }
+}
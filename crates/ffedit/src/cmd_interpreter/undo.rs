@@ -0,0 +1,131 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::{AppContext, AppEvent},
+    cmd_interpreter::{Command, CommandArgs, CommandResult},
+};
+
+/// Parse the number of steps to undo or redo, defaulting to 1.
+fn parse_steps(argv: &Option<Vec<String>>) -> Result<usize, String> {
+    match argv.as_deref() {
+        None => Ok(1),
+        Some([steps]) => steps
+            .parse::<usize>()
+            .ok()
+            .filter(|&steps| steps > 0)
+            .ok_or_else(|| format!("Invalid step count: {}", steps)),
+        Some(_) => Err("Too many arguments".into()),
+    }
+}
+
+pub(crate) struct UndoCommand;
+
+impl Command for UndoCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let steps = parse_steps(&args.argv).map_err(|e| format!("{}\nUsage: undo {}", e, self.usage()))?;
+        let di = app.di.as_mut().ok_or_else(|| "No disk image loaded".to_string())?;
+
+        let mut undone = Vec::new();
+        for _ in 0..steps {
+            match app.undo.undo(di) {
+                Ok(description) => undone.push(format!("Undid: {}", description)),
+                Err(e) if undone.is_empty() => return Err(e),
+                Err(_) => break,
+            }
+        }
+        _ = app.sender.send(AppEvent::DiskImageModified);
+        Ok(CommandResult::Success(undone.join("\n")))
+    }
+
+    fn usage(&self) -> String {
+        "[steps]".into()
+    }
+
+    fn desc(&self) -> String {
+        "Undo the last edit, or the given number of edits".into()
+    }
+}
+
+pub(crate) struct RedoCommand;
+
+impl Command for RedoCommand {
+    fn execute(&self, app: &mut AppContext, args: CommandArgs) -> Result<CommandResult, String> {
+        let steps = parse_steps(&args.argv).map_err(|e| format!("{}\nUsage: redo {}", e, self.usage()))?;
+        let di = app.di.as_mut().ok_or_else(|| "No disk image loaded".to_string())?;
+
+        let mut redone = Vec::new();
+        for _ in 0..steps {
+            match app.undo.redo(di) {
+                Ok(description) => redone.push(format!("Redid: {}", description)),
+                Err(e) if redone.is_empty() => return Err(e),
+                Err(_) => break,
+            }
+        }
+        _ = app.sender.send(AppEvent::DiskImageModified);
+        Ok(CommandResult::Success(redone.join("\n")))
+    }
+
+    fn usage(&self) -> String {
+        "[steps]".into()
+    }
+
+    fn desc(&self) -> String {
+        "Redo the last undone edit, or the given number of edits".into()
+    }
+}
+
+pub(crate) struct ChangesCommand;
+
+impl Command for ChangesCommand {
+    fn execute(&self, app: &mut AppContext, _args: CommandArgs) -> Result<CommandResult, String> {
+        let undo: Vec<&str> = app.undo.undo_list().collect();
+        let redo: Vec<&str> = app.undo.redo_list().collect();
+        if undo.is_empty() && redo.is_empty() {
+            return Ok(CommandResult::Success("No edits have been made".into()));
+        }
+
+        let mut result = String::from("Edits, oldest first:");
+        for (i, description) in undo.iter().enumerate() {
+            result.push_str(&format!("\n  {:3}  {}", i + 1, description));
+        }
+        if !redo.is_empty() {
+            result.push_str("\nUndone, next to redo first:");
+            for description in redo {
+                result.push_str(&format!("\n       {}", description));
+            }
+        }
+        Ok(CommandResult::Success(result))
+    }
+
+    fn usage(&self) -> String {
+        "No arguments".into()
+    }
+
+    fn desc(&self) -> String {
+        "List the edits that can be undone and redone".into()
+    }
+}
@@ -0,0 +1,81 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    app::AppContext,
+    cmd_interpreter::{Command, CommandArgs, CommandResult},
+};
+use fluxfox::prelude::*;
+
+pub(crate) struct VerifyCommand;
+
+impl Command for VerifyCommand {
+    fn execute(&self, app: &mut AppContext, _args: CommandArgs) -> Result<CommandResult, String> {
+        app.start_job("Verifying Disk Image", |di, job| {
+            let track_chs: Vec<DiskCh> = di.track_ch_iter().collect();
+            let mut sectors = 0;
+            let mut bad_sectors = Vec::new();
+
+            for (i, ch) in track_chs.iter().enumerate() {
+                job.check_cancel()?;
+                let track = di.track(*ch).ok_or_else(|| format!("Track {} not found", ch))?;
+
+                for entry in track.sector_list() {
+                    sectors += 1;
+                    match track.read_sector(entry.chsn.into(), None, None, RwScope::DataOnly, false) {
+                        Ok(result) if result.address_crc_error || result.data_crc_error || result.no_dam => {
+                            bad_sectors.push(entry.chsn);
+                        }
+                        Ok(_) => {}
+                        Err(_) => bad_sectors.push(entry.chsn),
+                    }
+                }
+                job.progress((i + 1) as f64 / track_chs.len() as f64);
+            }
+
+            let mut result_string = format!(
+                "Verified {} sectors on {} tracks, {} bad sectors",
+                sectors,
+                track_chs.len(),
+                bad_sectors.len()
+            );
+            for chsn in bad_sectors {
+                result_string.push_str(&format!("\n  {}", chsn));
+            }
+            Ok(result_string)
+        })?;
+
+        Ok(CommandResult::Success("Verifying disk image...".into()))
+    }
+
+    fn usage(&self) -> String {
+        "No arguments".into()
+    }
+
+    fn desc(&self) -> String {
+        "Read every sector in the disk image and report errors".into()
+    }
+}
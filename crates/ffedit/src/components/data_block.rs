@@ -31,21 +31,107 @@ use crate::{
     widget::{FoxWidget, ScrollableWidget, TabSelectableWidget, WidgetState},
 };
 use anyhow::{anyhow, Error};
-use fluxfox::prelude::*;
+use fluxfox::{
+    prelude::*,
+    track_schema::{system34::System34Element, GenericTrackElement, TrackElement, TrackElementInstance},
+};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Scrollbar, ScrollbarOrientation, ScrollbarState, WidgetRef},
 };
-use std::cell::RefCell;
+use std::{cell::RefCell, collections::HashSet, ops::Range};
 
 #[derive(Clone, Debug)]
 pub enum DataToken {
     Padding(u16),
     HexAddress(u16),
-    DataByte { byte: u8, last: bool, wrapping: bool },
+    DataByte {
+        byte: u8,
+        last: bool,
+        wrapping: bool,
+        patched: bool,
+        element: Option<ElementKind>,
+        selected: bool,
+    },
     AddressMarker(u8),
 }
 
+/// The kind of track element a byte belongs to in the raw track view, used to pick its color.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ElementKind {
+    Gap,
+    Sync,
+    Marker,
+    Header,
+    Data,
+    Bad,
+}
+
+impl ElementKind {
+    fn color(&self) -> Color {
+        match self {
+            ElementKind::Gap => Color::DarkGray,
+            ElementKind::Sync => Color::Blue,
+            ElementKind::Marker => Color::Magenta,
+            ElementKind::Header => Color::Cyan,
+            ElementKind::Data => Color::Green,
+            ElementKind::Bad => Color::Red,
+        }
+    }
+}
+
+/// A track element shown in the raw track view. The range is in bytes from the start of the
+/// track, as read by `read_track`.
+#[derive(Clone, Debug)]
+pub struct RawElement {
+    pub kind:  ElementKind,
+    pub name:  String,
+    pub range: Range<usize>,
+}
+
+impl RawElement {
+    fn new(instance: &TrackElementInstance) -> RawElement {
+        let (kind, name) = match instance.element() {
+            TrackElement::System34(element) => match element {
+                System34Element::Gap1 => (ElementKind::Gap, "Gap 1".to_string()),
+                System34Element::Gap2 => (ElementKind::Gap, "Gap 2".to_string()),
+                System34Element::Gap3 => (ElementKind::Gap, "Gap 3".to_string()),
+                System34Element::Gap4a => (ElementKind::Gap, "Gap 4a".to_string()),
+                System34Element::Gap4b => (ElementKind::Gap, "Gap 4b".to_string()),
+                System34Element::Sync => (ElementKind::Sync, "Sync".to_string()),
+                System34Element::Marker(marker, _) => (ElementKind::Marker, format!("{:?}", marker).to_uppercase()),
+                System34Element::SectorHeader {
+                    chsn, address_error, ..
+                } => match address_error {
+                    true => (ElementKind::Bad, format!("Sector Header {} (bad CRC)", chsn)),
+                    false => (ElementKind::Header, format!("Sector Header {}", chsn)),
+                },
+                System34Element::SectorData {
+                    chsn,
+                    address_error,
+                    data_error,
+                    deleted,
+                } => {
+                    let deleted = if deleted { " deleted" } else { "" };
+                    match address_error || data_error {
+                        true => (ElementKind::Bad, format!("Sector Data {}{} (bad CRC)", chsn, deleted)),
+                        false => (ElementKind::Data, format!("Sector Data {}{}", chsn, deleted)),
+                    }
+                }
+            },
+            other => (ElementKind::Gap, GenericTrackElement::from(other).to_string()),
+        };
+
+        // Track data is decoded from the first bit of the track, so each byte spans 16 bitcells.
+        let range = instance.range();
+        RawElement {
+            kind,
+            name,
+            range: range.start / 16..range.end.div_ceil(16),
+        }
+    }
+}
+
 #[derive(Default, Copy, Clone, Debug)]
 pub enum DataBlockType {
     #[default]
@@ -70,6 +156,20 @@ pub struct DataBlock {
     pub tab_selected: bool,
     pub data_header: MetaDataHeader,
     pub ui_state: RefCell<WidgetState>,
+    /// Whether the selected sector is open for editing. Edited sectors display only the sector
+    /// data, so that addresses match the offsets given to `poke`.
+    pub edit_mode: bool,
+    /// The offsets of bytes patched in the displayed sector, to be highlighted.
+    pub patched: HashSet<usize>,
+    /// Whether tracks are displayed in the raw track view, with the elements found by the track
+    /// schema highlighted.
+    pub raw_mode: bool,
+    /// The elements of the displayed track in the raw track view.
+    pub elements: Vec<RawElement>,
+    /// The index of the selected element in the raw track view.
+    pub element_cursor: Option<usize>,
+    /// The index of the innermost element covering each byte of the displayed track.
+    byte_elements: Vec<Option<usize>>,
 }
 
 impl Default for DataBlock {
@@ -91,12 +191,19 @@ impl Default for DataBlock {
             tab_selected: false,
             data_header: MetaDataHeader::new(MetaDataType::Track),
             ui_state: RefCell::new(WidgetState::default()),
+            edit_mode: false,
+            patched: HashSet::new(),
+            raw_mode: false,
+            elements: Vec::new(),
+            element_cursor: None,
+            byte_elements: Vec::new(),
         }
     }
 }
 
 impl DataBlock {
     pub fn load(&mut self, disk: &mut DiskImage, selection: &DiskSelection) -> Result<(), Error> {
+        let previous_block_type = self.block_type;
         self.block_type = match selection.level() {
             SelectionLevel::Cylinder => DataBlockType::Track,
             SelectionLevel::Sector => DataBlockType::Sector,
@@ -114,6 +221,13 @@ impl DataBlock {
                     return Err(anyhow!("No data read"));
                 }
 
+                // The selected element is kept only while the same track is displayed.
+                if !matches!(previous_block_type, DataBlockType::Track)
+                    || (self.head, self.cylinder, self.sector) != (ch.h(), ch.c(), None)
+                {
+                    self.element_cursor = None;
+                }
+
                 self.head = ch.h();
                 self.cylinder = ch.c();
                 self.sector = None;
@@ -123,9 +237,26 @@ impl DataBlock {
                 self.data_header.set_key_good("Bit Length", ti.bit_length.to_string());
                 self.data_header.set_key_good("Bitrate", ti.data_rate.to_string());
 
-                self.set_caption(&format!("Track: {}", ch));
-
                 self.scroll_offset = 0;
+                if self.raw_mode {
+                    let track = disk.track(ch).ok_or(anyhow!("Track not found"))?;
+                    self.elements = track
+                        .metadata()
+                        .map(|metadata| metadata.elements().iter().map(RawElement::new).collect())
+                        .unwrap_or_default();
+                    self.add_gaps(rtr.read_len_bytes);
+                    self.map_elements(rtr.read_len_bytes);
+                    self.set_caption(&format!("Raw Track: {}", ch));
+                    self.update_element_header();
+                    if let Some(element) = self.element_cursor.and_then(|i| self.elements.get(i)) {
+                        self.scroll_offset = element.range.start / self.columns;
+                    }
+                }
+                else {
+                    self.elements.clear();
+                    self.byte_elements.clear();
+                    self.set_caption(&format!("Track: {}", ch));
+                }
 
                 log::debug!("first byte of track is {:02X}", rtr.read_buf[0]);
                 self.update_data(rtr.read_buf, rtr.read_len_bytes);
@@ -143,6 +274,13 @@ impl DataBlock {
                     true,
                 )?;
 
+                // Patches are only highlighted until another sector is displayed.
+                if !matches!(previous_block_type, DataBlockType::Sector)
+                    || (self.head, self.cylinder, self.sector) != (ch.h(), ch.c(), selection.sector)
+                {
+                    self.patched.clear();
+                }
+
                 self.head = ch.h();
                 self.cylinder = ch.c();
                 self.sector = selection.sector;
@@ -155,11 +293,20 @@ impl DataBlock {
                 self.data_header
                     .set_key_good("Data: CRC Valid", (!rsr.data_crc_error).to_string());
 
-                self.set_caption(&format!("Sector: {}", chs));
-
                 self.scroll_offset = 0;
-                let read_buf_len = rsr.read_buf.len();
-                self.update_data(rsr.read_buf, read_buf_len);
+                if self.edit_mode {
+                    self.data_header
+                        .set_key_good("Mode", "Edit (poke <offset> <bytes...>)".to_string());
+                    self.set_caption(&format!("Editing Sector: {}", chs));
+                    let data = rsr.data().to_vec();
+                    let data_len = data.len();
+                    self.update_data(data, data_len);
+                }
+                else {
+                    self.set_caption(&format!("Sector: {}", chs));
+                    let read_buf_len = rsr.read_buf.len();
+                    self.update_data(rsr.read_buf, read_buf_len);
+                }
             }
         }
 
@@ -196,11 +343,35 @@ impl DataBlock {
                     line.spans
                         .push(Span::styled(" |", Style::default().fg(Color::DarkGray)));
                 }
-                DataToken::DataByte { byte, last, wrapping } => {
+                DataToken::DataByte {
+                    byte,
+                    last,
+                    wrapping,
+                    patched,
+                    element,
+                    selected,
+                } => {
                     let mut style = Style::default();
 
+                    style = match element {
+                        Some(kind) => style.fg(kind.color()),
+                        None => style,
+                    };
+                    style = if *selected {
+                        style.add_modifier(Modifier::REVERSED)
+                    }
+                    else {
+                        style
+                    };
+
                     style = if *last { style.underlined() } else { style };
                     style = if *wrapping { style.fg(Color::DarkGray) } else { style };
+                    style = if *patched {
+                        style.fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                    }
+                    else {
+                        style
+                    };
 
                     let mut pad_style = if byte_count == 0 { Style::default() } else { style };
 
@@ -262,6 +433,117 @@ impl DataBlock {
         self.format(); // Reformat the data when it changes
     }
 
+    fn is_patched(&self, offset: usize) -> bool {
+        matches!(self.block_type, DataBlockType::Sector) && self.patched.contains(&offset)
+    }
+
+    /// Add a gap element for each stretch of a track `len` bytes long not covered by any element,
+    /// so that gaps can be selected like the elements reported by the track schema.
+    fn add_gaps(&mut self, len: usize) {
+        self.elements.sort_by_key(|element| element.range.start);
+        let mut gaps = Vec::new();
+        let mut covered = 0;
+        for element in &self.elements {
+            if element.range.start > covered {
+                gaps.push(covered..element.range.start);
+            }
+            covered = covered.max(element.range.end);
+        }
+        if len > covered {
+            gaps.push(covered..len);
+        }
+        self.elements.extend(gaps.into_iter().map(|range| RawElement {
+            kind: ElementKind::Gap,
+            name: "Gap".to_string(),
+            range,
+        }));
+        // List enclosing elements before the elements they contain.
+        self.elements
+            .sort_by_key(|element| (element.range.start, std::cmp::Reverse(element.range.end)));
+    }
+
+    /// Record the innermost element covering each byte of a track `len` bytes long. Elements
+    /// nest, e.g. a marker lies within a sector header, so shorter elements are mapped last.
+    fn map_elements(&mut self, len: usize) {
+        self.byte_elements = vec![None; len];
+        let mut order: Vec<usize> = (0..self.elements.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.elements[i].range.len()));
+        for i in order {
+            let range = &self.elements[i].range;
+            for slot in &mut self.byte_elements[range.start.min(len)..range.end.min(len)] {
+                *slot = Some(i);
+            }
+        }
+    }
+
+    /// Return the kind of element covering the byte at `offset` in the raw track view, and whether
+    /// it is the selected element.
+    fn element_at(&self, offset: usize) -> (Option<ElementKind>, bool) {
+        if !matches!(self.block_type, DataBlockType::Track) {
+            return (None, false);
+        }
+        match self.byte_elements.get(offset).copied().flatten() {
+            Some(i) => (
+                Some(self.elements[i].kind),
+                self.element_cursor
+                    .is_some_and(|cursor| self.elements[cursor].range.contains(&offset)),
+            ),
+            None => (None, false),
+        }
+    }
+
+    fn update_element_header(&mut self) {
+        self.data_header
+            .set_key_good("Elements", self.elements.len().to_string());
+        if let Some(cursor) = self.element_cursor {
+            self.data_header.set_key_good("Element", self.describe_element(cursor));
+        }
+    }
+
+    /// Describe the element at `index` in the raw track view.
+    pub fn describe_element(&self, index: usize) -> String {
+        match self.elements.get(index) {
+            Some(element) => format!(
+                "{}/{}: {} [{:04X}-{:04X}] ({} bytes)",
+                index,
+                self.elements.len(),
+                element.name,
+                element.range.start,
+                element.range.end,
+                element.range.len()
+            ),
+            None => format!("No element {}", index),
+        }
+    }
+
+    /// Select the element at `index` in the raw track view and scroll it into view.
+    pub fn select_element(&mut self, index: usize) -> bool {
+        let Some(element) = self.elements.get(index)
+        else {
+            return false;
+        };
+        self.scroll_offset = element.range.start / self.columns;
+        self.element_cursor = Some(index);
+        self.update_element_header();
+        self.format();
+        true
+    }
+
+    /// Find the element after the selected one, or before it if `forward` is false, whose name
+    /// starts with `filter`, ignoring case. The search wraps around the track.
+    pub fn find_element(&self, forward: bool, filter: &str) -> Option<usize> {
+        let len = self.elements.len();
+        let filter = filter.to_lowercase();
+        (1..=len)
+            .map(|step| match (self.element_cursor, forward) {
+                (Some(cursor), true) => (cursor + step) % len,
+                (Some(cursor), false) => (cursor + len - step) % len,
+                (None, true) => step - 1,
+                (None, false) => len - step,
+            })
+            .find(|&i| self.elements[i].name.to_lowercase().starts_with(&filter))
+    }
+
     /// Formats the data into vectors of tokens
     fn format(&mut self) {
         let wrap = match self.block_type {
@@ -308,10 +590,14 @@ impl DataBlock {
                     mark_last_row = true;
                 }
 
+                let (element, selected) = self.element_at(row * bytes_per_line + bi);
                 let data_byte = DataToken::DataByte {
                     byte: *byte,
                     last: mark_last_row,
                     wrapping: false,
+                    patched: self.is_patched(row * bytes_per_line + bi),
+                    element,
+                    selected,
                 };
                 token_vec.push(data_byte);
             }
@@ -338,10 +624,14 @@ impl DataBlock {
 
                 // Add data bytes
                 for di in 0..data_partial_row_len {
+                    let (element, selected) = self.element_at((previous_row + 1) * bytes_per_line + di);
                     token_vec.push(DataToken::DataByte {
                         byte: incomplete_line[di],
                         last: true,
                         wrapping: false,
+                        patched: self.is_patched((previous_row + 1) * bytes_per_line + di),
+                        element,
+                        selected,
                     });
                 }
 
@@ -353,6 +643,9 @@ impl DataBlock {
                                 byte: incomplete_line[di],
                                 last: false,
                                 wrapping: true,
+                                patched: false,
+                                element: None,
+                                selected: false,
                             });
                         }
                         else {
@@ -55,6 +55,8 @@ pub(crate) struct HistoryWidget {
     pub vertical_scroll_state: ScrollbarState,
     pub tab_selected: bool,
     pub ui_state: RefCell<WidgetState>,
+    /// Print entries to stdout as they are added, when running without the TUI.
+    pub echo: bool,
 }
 
 impl HistoryWidget {
@@ -66,10 +68,21 @@ impl HistoryWidget {
             vertical_scroll_state: ScrollbarState::default(),
             tab_selected: false,
             ui_state: RefCell::new(WidgetState::default()),
+            echo: false,
         }
     }
 
     pub fn push(&mut self, entry: HistoryEntry) {
+        if self.echo {
+            match &entry {
+                HistoryEntry::UserCommand(cmd) => println!("> {}", cmd),
+                HistoryEntry::Trace(_) | HistoryEntry::Debug(_) => {}
+                HistoryEntry::CommandResponse(msg)
+                | HistoryEntry::Info(msg)
+                | HistoryEntry::Warning(msg)
+                | HistoryEntry::Error(msg) => println!("{}", msg),
+            }
+        }
         self.history.push_back(entry); // Push a new entry onto the history
         if self.history.len() > MAX_HISTORY {
             _ = self.history.pop_front(); // Remove the oldest entry if the history is too long
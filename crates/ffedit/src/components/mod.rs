@@ -28,3 +28,4 @@
 pub mod data_block;
 pub mod history;
 pub mod metadata_header;
+pub mod track_map;
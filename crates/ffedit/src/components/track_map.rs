@@ -0,0 +1,430 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    disk_selection::{DiskSelection, SelectionLevel},
+    widget::{FoxWidget, ScrollableWidget, TabSelectableWidget, WidgetState},
+};
+use fluxfox::{
+    diff::{ImageDiff, SectorDifference, TrackDifference},
+    prelude::*,
+};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph, WidgetRef},
+};
+use std::cell::RefCell;
+
+const LABEL_WIDTH: u16 = 4;
+const SECTOR_GLYPHS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The condition of a track, in increasing order of precedence when choosing its color. In diff
+/// mode, the status instead describes how the track differs from the secondary image.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TrackStatus {
+    #[default]
+    Missing,
+    Empty,
+    Good,
+    Nonstandard,
+    WeakBits,
+    BadCrc,
+    /// The track is identical in both images.
+    Same,
+    /// Sector status, such as CRC errors or data marks, differs but data does not.
+    StatusDiffers,
+    /// Sector data differs, or sectors exist in only one image.
+    DataDiffers,
+    /// The track exists in only one image.
+    OneSided,
+}
+
+impl TrackStatus {
+    fn color(&self) -> Color {
+        match self {
+            TrackStatus::Missing => Color::Reset,
+            TrackStatus::Empty => Color::DarkGray,
+            TrackStatus::Good => Color::Green,
+            TrackStatus::Nonstandard => Color::Yellow,
+            TrackStatus::WeakBits => Color::Magenta,
+            TrackStatus::BadCrc => Color::Red,
+            TrackStatus::Same => Color::Green,
+            TrackStatus::StatusDiffers => Color::Yellow,
+            TrackStatus::DataDiffers => Color::Red,
+            TrackStatus::OneSided => Color::Magenta,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TrackCell {
+    pub sector_ct: usize,
+    pub status:    TrackStatus,
+}
+
+impl TrackCell {
+    fn new(di: &DiskImage, ch: DiskCh, format: Option<StandardFormat>) -> TrackCell {
+        let Some(track) = di.track(ch)
+        else {
+            return TrackCell::default();
+        };
+
+        let sectors = track.sector_list();
+        if sectors.is_empty() {
+            return TrackCell {
+                sector_ct: 0,
+                status:    TrackStatus::Empty,
+            };
+        }
+
+        let bad_crc = sectors
+            .iter()
+            .any(|s| s.attributes.address_error || s.attributes.data_error || s.attributes.no_dam);
+
+        // A track is nonstandard if its layout differs from the image's closest standard format,
+        // or if any sector ID doesn't match the physical track.
+        let nonstandard = sectors.iter().any(|s| s.chsn.c() != ch.c() || s.chsn.h() != ch.h())
+            || format.is_some_and(|format| {
+                let layout = format.layout();
+                sectors.len() != layout.s() as usize || sectors.iter().any(|s| s.chsn.n_size() != layout.size())
+            });
+
+        let status = if bad_crc {
+            TrackStatus::BadCrc
+        }
+        else if track.has_weak_bits() {
+            TrackStatus::WeakBits
+        }
+        else if nonstandard {
+            TrackStatus::Nonstandard
+        }
+        else {
+            TrackStatus::Good
+        };
+
+        TrackCell {
+            sector_ct: sectors.len(),
+            status,
+        }
+    }
+
+    fn glyph(&self) -> char {
+        match self.status {
+            TrackStatus::Missing => ' ',
+            TrackStatus::Empty => '·',
+            TrackStatus::Same => '=',
+            TrackStatus::OneSided => '!',
+            _ => SECTOR_GLYPHS.get(self.sector_ct).map(|&b| b as char).unwrap_or('+'),
+        }
+    }
+}
+
+/// A grid of cylinders by heads, showing the sector count and condition of each track.
+/// The cursor follows the track selected at the command prompt.
+pub struct TrackMap {
+    /// Track cells, indexed by head and then cylinder.
+    pub cells: Vec<Vec<TrackCell>>,
+    /// The standard format that track layouts are compared against.
+    pub format: Option<StandardFormat>,
+    /// Whether the map shows the differences from the secondary image instead of the condition
+    /// of each track.
+    pub diff_mode: bool,
+    /// The differences from the secondary image, cached until either image changes.
+    diff: Option<ImageDiff>,
+    pub cursor: DiskCh,
+    pub tab_selected: bool,
+    pub ui_state: RefCell<WidgetState>,
+}
+
+impl Default for TrackMap {
+    fn default() -> Self {
+        TrackMap {
+            cells: Vec::new(),
+            format: None,
+            diff_mode: false,
+            diff: None,
+            cursor: DiskCh::new(0, 0),
+            tab_selected: false,
+            ui_state: RefCell::new(WidgetState::default()),
+        }
+    }
+}
+
+impl TrackMap {
+    /// Load the map for a newly opened disk image.
+    pub fn load(&mut self, di: &DiskImage, secondary: Option<&DiskImage>) {
+        self.format = di.closest_format(true);
+        self.invalidate();
+        self.refresh(di, secondary);
+    }
+
+    /// Update the condition of each track after the image has been modified.
+    pub fn refresh(&mut self, di: &DiskImage, secondary: Option<&DiskImage>) {
+        if let Some(secondary) = secondary.filter(|_| self.diff_mode) {
+            self.refresh_diff(di, secondary);
+            return;
+        }
+
+        let format = self.format;
+        self.cells = (0..di.heads())
+            .map(|h| {
+                (0..di.track_ct(h as usize) as u16)
+                    .map(|c| TrackCell::new(di, DiskCh::new(c, h), format))
+                    .collect()
+            })
+            .collect();
+    }
+
+    /// Switch between showing the condition of each track and its differences from the secondary
+    /// image.
+    pub fn set_diff_mode(&mut self, diff_mode: bool, di: &DiskImage, secondary: Option<&DiskImage>) {
+        self.diff_mode = diff_mode;
+        self.refresh(di, secondary);
+    }
+
+    /// Discard the cached differences from the secondary image, after either image has changed.
+    pub fn invalidate(&mut self) {
+        self.diff = None;
+    }
+
+    /// Return the differences between `di` and the secondary image, comparing them only if they
+    /// have changed since the last call.
+    pub fn diff(&mut self, di: &DiskImage, secondary: &DiskImage) -> &ImageDiff {
+        self.diff.get_or_insert_with(|| di.diff(secondary))
+    }
+
+    // Mark each track with how it differs from the secondary image, counting differing sectors.
+    fn refresh_diff(&mut self, di: &DiskImage, secondary: &DiskImage) {
+        let diff = self.diff.take().unwrap_or_else(|| di.diff(secondary));
+        let heads = di.heads().max(secondary.heads());
+        self.cells = (0..heads as usize)
+            .map(|h| {
+                let cylinders = di.track_ct(h).max(secondary.track_ct(h));
+                vec![
+                    TrackCell {
+                        sector_ct: 0,
+                        status:    TrackStatus::Same,
+                    };
+                    cylinders
+                ]
+            })
+            .collect();
+
+        for sector in &diff.sectors {
+            if let Some(cell) = self.cell_mut(sector.ch) {
+                let data_differs = sector.differences.iter().any(|d| {
+                    matches!(
+                        d,
+                        SectorDifference::Data { .. }
+                            | SectorDifference::OnlyIn(_)
+                            | SectorDifference::Unreadable { .. }
+                    )
+                });
+                cell.sector_ct += 1;
+                cell.status = if data_differs {
+                    TrackStatus::DataDiffers
+                }
+                else {
+                    cell.status.max(TrackStatus::StatusDiffers)
+                };
+            }
+        }
+        for track in &diff.tracks {
+            if let Some(cell) = self.cell_mut(track.ch) {
+                if track
+                    .differences
+                    .iter()
+                    .any(|d| matches!(d, TrackDifference::OnlyIn(_)))
+                {
+                    cell.status = TrackStatus::OneSided;
+                }
+                else if cell.status == TrackStatus::Same {
+                    cell.status = TrackStatus::StatusDiffers;
+                }
+            }
+        }
+        self.diff = Some(diff);
+    }
+
+    fn cell_mut(&mut self, ch: DiskCh) -> Option<&mut TrackCell> {
+        self.cells.get_mut(ch.h() as usize)?.get_mut(ch.c() as usize)
+    }
+
+    /// Move the cursor to the track of the selection, if a track is selected.
+    pub fn set_cursor(&mut self, selection: &DiskSelection) {
+        if selection.level() >= SelectionLevel::Cylinder {
+            if let Ok(ch) = selection.into_ch() {
+                self.cursor = ch;
+            }
+        }
+    }
+
+    /// Move the cursor by the specified number of cylinders and heads, staying within the map.
+    /// Returns the new cursor position if the cursor moved.
+    pub fn move_cursor(&mut self, dc: i32, dh: i32) -> Option<DiskCh> {
+        let heads = self.cells.len() as i32;
+        let h = (self.cursor.h() as i32 + dh).min(heads - 1).max(0);
+        let cylinders = self.cells.get(h as usize)?.len() as i32;
+        if cylinders == 0 {
+            return None;
+        }
+        let c = (self.cursor.c() as i32 + dc).min(cylinders - 1).max(0);
+
+        let new_cursor = DiskCh::new(c as u16, h as u8);
+        if new_cursor == self.cursor {
+            return None;
+        }
+        self.cursor = new_cursor;
+        Some(new_cursor)
+    }
+
+    fn cylinder_ct(&self) -> usize {
+        self.cells.iter().map(|head| head.len()).max().unwrap_or(0)
+    }
+
+    // The number of cylinders drawn per row of the map; always a multiple of 10 so that the
+    // cylinder numbers in the header line up.
+    fn chunk_width(&self, width: u16) -> usize {
+        let available = width.saturating_sub(2 + LABEL_WIDTH) as usize;
+        (available / 10 * 10).max(10)
+    }
+
+    /// Return the height required to display the whole map, including its border, within the
+    /// specified width.
+    pub fn required_height(&self, width: u16) -> u16 {
+        let chunks = self.cylinder_ct().div_ceil(self.chunk_width(width)).max(1);
+        // A header row and a row per head for each chunk, a legend, and the border.
+        (chunks * (1 + self.cells.len()) + 1 + 2) as u16
+    }
+
+    fn render_ref_internal(&self, area: Rect, buf: &mut Buffer) {
+        let mut state = self.ui_state.borrow_mut();
+
+        let border_style = if self.tab_selected {
+            Style::default().fg(Color::LightCyan).add_modifier(Modifier::BOLD)
+        }
+        else {
+            Style::default()
+        };
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(border_style)
+            .title(format!(
+                "{} [c:{} h:{}]",
+                if self.diff_mode { "Diff Map" } else { "Track Map" },
+                self.cursor.c(),
+                self.cursor.h()
+            ));
+        let inner = block.inner(area);
+        block.render(area, buf);
+        state.visible_rows = inner.height as usize;
+
+        let chunk_width = self.chunk_width(area.width);
+        let mut lines = Vec::new();
+        for chunk_start in (0..self.cylinder_ct().max(1)).step_by(chunk_width) {
+            let chunk_end = (chunk_start + chunk_width).min(self.cylinder_ct());
+
+            let mut header = " ".repeat(LABEL_WIDTH as usize);
+            for c in (chunk_start..chunk_end).step_by(10) {
+                header.push_str(&format!("{:<10}", c));
+            }
+            lines.push(Line::from(Span::styled(header, Style::default().fg(Color::Cyan))));
+
+            for (h, head) in self.cells.iter().enumerate() {
+                let mut spans = vec![Span::styled(
+                    format!("{:<width$}", format!("h{}", h), width = LABEL_WIDTH as usize),
+                    Style::default().fg(Color::Cyan),
+                )];
+                for c in chunk_start..chunk_end {
+                    let cell = head.get(c).copied().unwrap_or_default();
+                    let mut style = Style::default().fg(cell.status.color());
+                    if self.cursor == DiskCh::new(c as u16, h as u8) {
+                        style = style.add_modifier(Modifier::REVERSED);
+                    }
+                    spans.push(Span::styled(cell.glyph().to_string(), style));
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+
+        if self.diff_mode {
+            lines.push(Line::from(vec![
+                Span::styled("= same ", Style::default().fg(TrackStatus::Same.color())),
+                Span::styled("data ", Style::default().fg(TrackStatus::DataDiffers.color())),
+                Span::styled("status ", Style::default().fg(TrackStatus::StatusDiffers.color())),
+                Span::styled("! one image", Style::default().fg(TrackStatus::OneSided.color())),
+            ]));
+        }
+        else {
+            lines.push(Line::from(vec![
+                Span::styled("ok ", Style::default().fg(TrackStatus::Good.color())),
+                Span::styled("crc ", Style::default().fg(TrackStatus::BadCrc.color())),
+                Span::styled("weak ", Style::default().fg(TrackStatus::WeakBits.color())),
+                Span::styled("nonstd ", Style::default().fg(TrackStatus::Nonstandard.color())),
+                Span::styled("· empty", Style::default().fg(TrackStatus::Empty.color())),
+            ]));
+        }
+
+        Paragraph::new(lines).render(inner, buf);
+    }
+}
+
+impl FoxWidget for TrackMap {}
+
+// The map is always displayed in full, so scrolling does nothing.
+impl ScrollableWidget for TrackMap {
+    fn scroll_up(&mut self) {}
+    fn scroll_down(&mut self) {}
+    fn page_up(&mut self) {}
+    fn page_down(&mut self) {}
+    fn scroll_to_start(&mut self) {}
+    fn scroll_to_end(&mut self) {}
+}
+
+impl TabSelectableWidget for TrackMap {
+    fn can_select(&self) -> bool {
+        true
+    }
+    fn select(&mut self) {
+        self.tab_selected = true;
+    }
+    fn deselect(&mut self) {
+        self.tab_selected = false;
+    }
+}
+
+impl WidgetRef for &TrackMap {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.render_ref_internal(area, buf);
+    }
+}
+
+impl WidgetRef for TrackMap {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.render_ref_internal(area, buf);
+    }
+}
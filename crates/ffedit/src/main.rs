@@ -37,12 +37,16 @@ mod disk_selection;
 mod layout;
 mod logger;
 mod modal;
+mod script;
+mod search;
+mod undo;
 mod util;
 mod widget;
+mod worker;
 
 use std::{io, path::PathBuf};
 
-use bpaf::{construct, short, OptionParser, Parser};
+use bpaf::{construct, long, short, OptionParser, Parser};
 use crossterm::ExecutableCommand;
 
 use app::App;
@@ -51,6 +55,7 @@ use app::App;
 #[derive(Debug, Clone)]
 struct CmdParams {
     in_filename: Option<PathBuf>,
+    script: Option<PathBuf>,
     mouse: bool,
 }
 
@@ -62,13 +67,33 @@ fn opts() -> OptionParser<CmdParams> {
         .argument::<PathBuf>("IN_FILE")
         .optional();
 
+    let script = long("script")
+        .help("Run the commands in a script file without the interactive interface, then exit")
+        .argument::<PathBuf>("SCRIPT_FILE")
+        .optional();
+
     let mouse = short('m').long("switch").help("Enable mouse support").switch();
 
-    construct!(CmdParams { in_filename, mouse }).to_options()
+    construct!(CmdParams {
+        in_filename,
+        script,
+        mouse
+    })
+    .to_options()
 }
 
 fn main() -> io::Result<()> {
     let opts = opts().run();
+
+    if let Some(script) = opts.script.clone() {
+        let mut app = App::new(opts);
+        if let Err(e) = app.run_script(&script) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let mut terminal = ratatui::init();
 
     if opts.mouse {
@@ -25,27 +25,64 @@
     --------------------------------------------------------------------------
 */
 
+// A progress popup for a disk image load or a background job, either of which may be
+// cancelled with Esc
+pub(crate) struct ProgressModal {
+    pub(crate) title: String,
+    pub(crate) progress: f64, // Completion percentage (0.0 to 1.0)
+    pub(crate) cancelling: bool,
+}
+
+impl ProgressModal {
+    // Return the popup title, including a hint on how to cancel the operation
+    pub(crate) fn display_title(&self) -> String {
+        if self.cancelling {
+            format!("{} [Cancelling...]", self.title)
+        }
+        else {
+            format!("{} [Esc to cancel]", self.title)
+        }
+    }
+}
+
 // Modal state for the application
 pub(crate) enum ModalState {
-    ProgressBar(String, f64), // Title and completion percentage (0.0 to 1.0)
+    Progress(ProgressModal),
 }
 
 impl ModalState {
     // Create a new progress bar modal state
     pub(crate) fn new_progress_bar(title: &str) -> ModalState {
-        ModalState::ProgressBar(title.to_string(), 0.0)
+        ModalState::Progress(ProgressModal {
+            title: title.to_string(),
+            progress: 0.0,
+            cancelling: false,
+        })
     }
 
     // Update the progress bar completion percentage
     pub(crate) fn update_progress(&mut self, percentage: f64) {
-        if let ModalState::ProgressBar(_, ref mut p) = self {
-            *p = percentage;
+        let ModalState::Progress(modal) = self;
+        modal.progress = percentage;
+    }
+
+    // Update the progress bar title
+    pub(crate) fn update_title(&mut self, title: &str) {
+        let ModalState::Progress(modal) = self;
+        if modal.title != title {
+            modal.title = title.to_string();
         }
     }
 
+    // Mark the operation as cancelling, until it stops and the modal is dismissed
+    pub(crate) fn set_cancelling(&mut self) {
+        let ModalState::Progress(modal) = self;
+        modal.cancelling = true;
+    }
+
     pub(crate) fn input_enabled(&self) -> bool {
         match self {
-            ModalState::ProgressBar(_, _) => false,
+            ModalState::Progress(_) => false,
         }
     }
 }
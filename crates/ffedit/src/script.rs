@@ -0,0 +1,129 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/script.rs
+
+    Script files of interpreter commands.
+
+    A script is a text file with one command per line, as it would be typed
+    at the prompt. Blank lines and lines starting with '#' are ignored.
+    Scripts are run with the `run` command, or without the TUI with
+    `--script`. Each command waits for any file load or job started by the
+    previous command to finish, and the first command that fails stops the
+    script. Scripts may run other scripts, up to a nesting limit.
+*/
+use std::{collections::VecDeque, path::Path};
+
+/// The maximum depth of scripts run by other scripts, which stops a script that runs itself.
+const MAX_SCRIPT_DEPTH: usize = 16;
+
+/// A command read from a script, with its location for error messages.
+pub(crate) struct ScriptLine {
+    pub(crate) source:  String,
+    pub(crate) line_no: usize,
+    pub(crate) command: String,
+}
+
+/// Read the commands from the script file at `path`.
+pub(crate) fn read_script(path: &Path) -> Result<Vec<ScriptLine>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Error reading script {}: {}", path.display(), e))?;
+    let source = path.display().to_string();
+
+    Ok(text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_no, line)| ScriptLine {
+            source: source.clone(),
+            line_no,
+            command: line.to_string(),
+        })
+        .collect())
+}
+
+/// The commands remaining to be run from scripts.
+#[derive(Default)]
+pub(crate) struct ScriptQueue {
+    // The commands to run, each with the nesting depth of the script it came from.
+    lines:   VecDeque<(usize, ScriptLine)>,
+    // The location and depth of the last command run, which may still be waiting on a load or job.
+    current: Option<(String, usize, usize)>,
+    failed:  bool,
+}
+
+impl ScriptQueue {
+    /// Queue a script to run before any commands remaining from the script that ran it.
+    /// Returns an error if the script would be nested deeper than [MAX_SCRIPT_DEPTH].
+    pub(crate) fn push(&mut self, lines: Vec<ScriptLine>) -> Result<(), String> {
+        let depth = self.current.as_ref().map_or(0, |(_, _, depth)| depth + 1);
+        if depth >= MAX_SCRIPT_DEPTH {
+            return Err(format!(
+                "Scripts are nested more than {} deep. Does a script run itself?",
+                MAX_SCRIPT_DEPTH
+            ));
+        }
+        for line in lines.into_iter().rev() {
+            self.lines.push_front((depth, line));
+        }
+        Ok(())
+    }
+
+    /// Take the next command to run.
+    pub(crate) fn next(&mut self) -> Option<ScriptLine> {
+        let Some((depth, line)) = self.lines.pop_front()
+        else {
+            self.current = None;
+            return None;
+        };
+        self.current = Some((line.source.clone(), line.line_no, depth));
+        Some(line)
+    }
+
+    /// Return whether a script is running, including a last command that is still waiting on a
+    /// load or job.
+    pub(crate) fn is_active(&self) -> bool {
+        !self.lines.is_empty() || self.current.is_some()
+    }
+
+    /// Stop the running script after a command failed, discarding the remaining commands.
+    /// Returns a message giving where the script stopped, or `None` if no script was running.
+    pub(crate) fn abort(&mut self) -> Option<String> {
+        if !self.is_active() {
+            return None;
+        }
+        self.lines.clear();
+        self.failed = true;
+        self.current
+            .take()
+            .map(|(source, line_no, _)| format!("Script aborted at {}:{}", source, line_no))
+    }
+
+    /// Return whether a script was aborted because a command failed.
+    pub(crate) fn failed(&self) -> bool {
+        self.failed
+    }
+}
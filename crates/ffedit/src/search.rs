@@ -0,0 +1,157 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/search.rs
+
+    Searching sector data for a byte pattern.
+
+    The results of the last search are kept so that `findnext` can step
+    through them, selecting the sector containing each match in turn.
+*/
+use crate::disk_selection::{DiskSelection, SelectionLevel};
+use fluxfox::prelude::*;
+use std::fmt::{self, Display};
+
+/// The part of the disk image to search.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum SearchScope {
+    Disk,
+    Head(u8),
+    Track(DiskCh),
+    Sector(DiskCh, u8),
+}
+
+impl SearchScope {
+    /// Limit a search to the head, track or sector of the selection.
+    pub(crate) fn from_selection(selection: &DiskSelection) -> Self {
+        let head = selection.head.unwrap_or(0);
+        let ch = DiskCh::new(selection.cylinder.unwrap_or(0), head);
+        match selection.level() {
+            SelectionLevel::Disk => SearchScope::Disk,
+            SelectionLevel::Head => SearchScope::Head(head),
+            SelectionLevel::Cylinder => SearchScope::Track(ch),
+            SelectionLevel::Sector => SearchScope::Sector(ch, selection.sector.unwrap_or(0)),
+        }
+    }
+
+    fn contains(&self, ch: DiskCh, id: DiskChsn) -> bool {
+        match *self {
+            SearchScope::Disk => true,
+            SearchScope::Head(h) => ch.h() == h,
+            SearchScope::Track(track_ch) => ch == track_ch,
+            SearchScope::Sector(track_ch, s) => ch == track_ch && id.s() == s,
+        }
+    }
+}
+
+impl Display for SearchScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchScope::Disk => write!(f, "disk"),
+            SearchScope::Head(h) => write!(f, "head {}", h),
+            SearchScope::Track(ch) => write!(f, "track {}", ch),
+            SearchScope::Sector(ch, s) => write!(f, "sector {}", DiskChs::from((*ch, *s))),
+        }
+    }
+}
+
+/// The location of a match: the track containing the sector, the sector's ID, and the offset of
+/// the match within the sector data.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct SearchMatch {
+    pub(crate) ch: DiskCh,
+    pub(crate) id: DiskChsn,
+    pub(crate) offset: usize,
+}
+
+impl Display for SearchMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} offset {:#06X}",
+            DiskChs::from((self.ch, self.id.s())),
+            self.offset
+        )
+    }
+}
+
+/// Search the data of each sector within `scope` for `pattern`. Matches do not span sectors, and
+/// do not overlap.
+pub(crate) fn search_sectors(di: &DiskImage, pattern: &[u8], scope: SearchScope) -> Vec<SearchMatch> {
+    let mut matches = Vec::new();
+    if pattern.is_empty() {
+        return matches;
+    }
+
+    for sector in di.sectors().filter(|s| scope.contains(s.ch, s.id())) {
+        if sector.entry.attributes.no_dam {
+            continue;
+        }
+        let Ok(rsr) = sector.read(RwScope::DataOnly)
+        else {
+            continue;
+        };
+        let data = rsr.data();
+        let mut offset = 0;
+        while offset + pattern.len() <= data.len() {
+            if &data[offset..offset + pattern.len()] == pattern {
+                matches.push(SearchMatch {
+                    ch: sector.ch,
+                    id: sector.id(),
+                    offset,
+                });
+                offset += pattern.len();
+            }
+            else {
+                offset += 1;
+            }
+        }
+    }
+    matches
+}
+
+/// The results of the last search.
+#[derive(Default)]
+pub(crate) struct SearchResults {
+    pub(crate) matches: Vec<SearchMatch>,
+    next: usize,
+}
+
+impl SearchResults {
+    pub(crate) fn new(matches: Vec<SearchMatch>) -> Self {
+        SearchResults { matches, next: 0 }
+    }
+
+    /// Return the next match and its index, wrapping around to the first match after the last.
+    pub(crate) fn next_match(&mut self) -> Option<(usize, SearchMatch)> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        let index = self.next % self.matches.len();
+        self.next = index + 1;
+        Some((index, self.matches[index]))
+    }
+}
@@ -0,0 +1,178 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/undo.rs
+
+    Undo and redo for destructive edits.
+
+    Before a command modifies the disk image, it captures a snapshot of each
+    track it will change and records them in the undo stack as one entry.
+    Undoing an entry captures the current contents of the same tracks for
+    redo, then restores the snapshots.
+
+    Whole tracks are saved even for edits to sector data, since rewriting a
+    sector can change more than its data, such as its CRC or weak bits.
+*/
+use fluxfox::{prelude::*, track::DiskTrack, types::DiskImageFlags};
+
+/// The number of entries kept in the undo stack. The oldest entries are discarded beyond this.
+const UNDO_LIMIT: usize = 100;
+
+/// The contents of a track, saved so that it can be restored.
+#[derive(Clone)]
+pub(crate) struct Snapshot {
+    ch:    DiskCh,
+    track: DiskTrack,
+}
+
+impl Snapshot {
+    /// Save the entire track `ch`.
+    pub(crate) fn track(di: &DiskImage, ch: DiskCh) -> Result<Self, String> {
+        let track = di.track(ch).ok_or_else(|| format!("Track {} not found", ch))?;
+        Ok(Snapshot {
+            ch,
+            track: track.clone(),
+        })
+    }
+
+    /// Save the track `ch` into `snapshots`, unless it has been saved already.
+    pub(crate) fn save_once(snapshots: &mut Vec<Snapshot>, di: &DiskImage, ch: DiskCh) -> Result<(), String> {
+        if !snapshots.iter().any(|snapshot| snapshot.ch == ch) {
+            snapshots.push(Snapshot::track(di, ch)?);
+        }
+        Ok(())
+    }
+
+    /// Save the current contents of the track this snapshot covers.
+    fn recapture(&self, di: &DiskImage) -> Result<Self, String> {
+        Snapshot::track(di, self.ch)
+    }
+
+    /// Write the saved track back to the disk image.
+    fn restore(&self, di: &mut DiskImage) -> Result<(), String> {
+        let target = di
+            .track_mut(self.ch)
+            .ok_or_else(|| format!("Track {} not found", self.ch))?;
+        *target = self.track.clone();
+        Ok(())
+    }
+}
+
+/// A single undoable operation, and the snapshots taken before it.
+pub(crate) struct UndoEntry {
+    pub(crate) description: String,
+    snapshots: Vec<Snapshot>,
+}
+
+impl UndoEntry {
+    pub(crate) fn new(description: impl Into<String>, snapshots: Vec<Snapshot>) -> Self {
+        UndoEntry {
+            description: description.into(),
+            snapshots,
+        }
+    }
+
+    /// Restore the snapshots, returning an entry that reverses the restore. If a snapshot cannot
+    /// be restored, the tracks already restored are put back as they were.
+    fn apply(&self, di: &mut DiskImage) -> Result<UndoEntry, String> {
+        let current = self
+            .snapshots
+            .iter()
+            .map(|snapshot| snapshot.recapture(di))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        // Restore in reverse, so that if a track was saved more than once, the earliest contents win.
+        for (i, snapshot) in self.snapshots.iter().enumerate().rev() {
+            if let Err(e) = snapshot.restore(di) {
+                for restored in &current[i + 1..] {
+                    _ = restored.restore(di);
+                }
+                return Err(e);
+            }
+        }
+        di.set_flag(DiskImageFlags::DIRTY);
+
+        Ok(UndoEntry {
+            description: self.description.clone(),
+            snapshots:   current,
+        })
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct UndoStack {
+    undo: Vec<UndoEntry>,
+    redo: Vec<UndoEntry>,
+}
+
+impl UndoStack {
+    /// Record an operation that has been performed. This discards any operations available to
+    /// redo.
+    pub(crate) fn push(&mut self, entry: UndoEntry) {
+        self.redo.clear();
+        self.undo.push(entry);
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Discard all history, such as when a new disk image is loaded.
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Undo the most recent operation, returning its description.
+    pub(crate) fn undo(&mut self, di: &mut DiskImage) -> Result<String, String> {
+        let entry = self.undo.last().ok_or_else(|| "Nothing to undo".to_string())?;
+        // Only drop the entry once it has been restored, so a failed undo can be retried.
+        let redo = entry.apply(di)?;
+        self.undo.pop();
+        let description = redo.description.clone();
+        self.redo.push(redo);
+        Ok(description)
+    }
+
+    /// Redo the most recently undone operation, returning its description.
+    pub(crate) fn redo(&mut self, di: &mut DiskImage) -> Result<String, String> {
+        let entry = self.redo.last().ok_or_else(|| "Nothing to redo".to_string())?;
+        let undo = entry.apply(di)?;
+        self.redo.pop();
+        let description = undo.description.clone();
+        self.undo.push(undo);
+        Ok(description)
+    }
+
+    /// Return the descriptions of the operations that can be undone, oldest first.
+    pub(crate) fn undo_list(&self) -> impl Iterator<Item = &str> {
+        self.undo.iter().map(|entry| entry.description.as_str())
+    }
+
+    /// Return the descriptions of the operations that can be redone, next first.
+    pub(crate) fn redo_list(&self) -> impl Iterator<Item = &str> {
+        self.redo.iter().rev().map(|entry| entry.description.as_str())
+    }
+}
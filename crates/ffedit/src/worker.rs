@@ -0,0 +1,133 @@
+/*
+    ffedit
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/worker.rs
+
+    Background jobs for long-running interpreter commands.
+
+    A job takes ownership of the loaded disk image and runs on a worker
+    thread, so that the TUI keeps redrawing while it works. Progress is
+    reported back through AppEvent::JobProgress, and the image is handed back
+    along with the job's result in AppEvent::JobFinished. The job checks its
+    cancellation flag between units of work; the flag is set when the user
+    presses Esc.
+*/
+use crate::app::AppEvent;
+use crossbeam_channel::Sender;
+use fluxfox::{DiskImage, SavingCallback, SavingStatus};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// The message a job returns when it stops early due to cancellation.
+const CANCELLED_MSG: &str = "Operation cancelled";
+
+// Result of a job: a message for the history pane on success or failure.
+pub(crate) type JobResult = Result<String, String>;
+
+// Handle kept by the application to cancel a running job
+pub(crate) struct JobHandle {
+    cancel: Arc<AtomicBool>,
+}
+
+impl JobHandle {
+    pub(crate) fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
+// Context passed to a running job for progress reporting and cancellation checks
+pub(crate) struct JobContext {
+    sender: Sender<AppEvent>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl JobContext {
+    // Report job progress as a fraction from 0.0 to 1.0
+    pub(crate) fn progress(&self, progress: f64) {
+        _ = self.sender.send(AppEvent::JobProgress(progress.clamp(0.0, 1.0)));
+    }
+
+    // Return a callback that reports the per-track progress of an image save as job progress
+    pub(crate) fn saving_callback(&self) -> SavingCallback {
+        let sender = self.sender.clone();
+        Arc::new(move |status| {
+            if let SavingStatus::Track { index, total, .. } = status {
+                let progress = (index + 1) as f64 / total.max(1) as f64;
+                _ = sender.send(AppEvent::JobProgress(progress.clamp(0.0, 1.0)));
+            }
+        })
+    }
+
+    // Return an error if the user has requested cancellation. Intended to be used with `?`.
+    pub(crate) fn check_cancel(&self) -> Result<(), String> {
+        if self.cancel.load(Ordering::Relaxed) {
+            Err(CANCELLED_MSG.to_string())
+        }
+        else {
+            Ok(())
+        }
+    }
+}
+
+/// Run `job` on a worker thread with ownership of `di`. The disk image is always returned through
+/// [AppEvent::JobFinished], even if the job fails, is cancelled or panics. A job that panics may
+/// have left the image partially modified.
+pub(crate) fn spawn_job<F>(sender: Sender<AppEvent>, mut di: DiskImage, job: F) -> JobHandle
+where
+    F: FnOnce(&mut DiskImage, &JobContext) -> JobResult + Send + 'static,
+{
+    let cancel = Arc::new(AtomicBool::new(false));
+    let ctx = JobContext {
+        sender: sender.clone(),
+        cancel: cancel.clone(),
+    };
+
+    std::thread::spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| job(&mut di, &ctx))).unwrap_or_else(|payload| {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown error".to_string());
+            log::error!("spawn_job()... Worker panicked: {}", msg);
+            Err(format!("Operation failed: {}", msg))
+        });
+        if let Err(e) = sender.send(AppEvent::JobFinished(di, result)) {
+            log::error!("spawn_job()... Failed to return disk image from worker: {}", e);
+        }
+    });
+
+    JobHandle { cancel }
+}
@@ -0,0 +1,94 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::args::*;
+use bpaf::{construct, long, Parser};
+use std::{path::PathBuf, str::FromStr};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) enum AgeSeverity {
+    Light,
+    #[default]
+    Moderate,
+    Severe,
+}
+
+impl FromStr for AgeSeverity {
+    type Err = &'static str;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "light" => Ok(AgeSeverity::Light),
+            "moderate" => Ok(AgeSeverity::Moderate),
+            "severe" => Ok(AgeSeverity::Severe),
+            _ => Err("Invalid severity; expected 'light', 'moderate', or 'severe'"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AgeParams {
+    pub(crate) in_file: PathBuf,
+    pub(crate) out_file: PathBuf,
+    pub(crate) severity: Option<AgeSeverity>,
+    pub(crate) passes: Option<u32>,
+    pub(crate) seed: Option<u64>,
+}
+
+fn severity_parser() -> impl Parser<AgeSeverity> {
+    long("severity")
+        .argument::<AgeSeverity>("SEVERITY")
+        .help("Severity of aging: light, moderate, or severe")
+}
+
+fn passes_parser() -> impl Parser<u32> {
+    long("passes")
+        .argument::<u32>("PASSES")
+        .help("Number of times to apply aging. Weak regions grow on each pass.")
+        .guard(|&passes| passes > 0, "Passes must be at least 1")
+}
+
+fn seed_parser() -> impl Parser<u64> {
+    long("seed")
+        .argument::<u64>("SEED")
+        .help("Seed for the simulation. The same seed produces the same defects.")
+}
+
+pub(crate) fn age_parser() -> impl Parser<AgeParams> {
+    let in_file = in_file_parser();
+    let out_file = out_file_parser();
+    let severity = severity_parser().optional();
+    let passes = passes_parser().optional();
+    let seed = seed_parser().optional();
+
+    construct!(AgeParams {
+        in_file,
+        out_file,
+        severity,
+        passes,
+        seed,
+    })
+}
@@ -0,0 +1,89 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+pub mod args;
+
+use crate::{
+    age::args::{AgeParams, AgeSeverity},
+    args::GlobalOptions,
+    read_file,
+};
+use anyhow::{bail, Error};
+use fluxfox::{aging::AgingOptions, prelude::*};
+
+pub(crate) fn run(global: &GlobalOptions, params: &AgeParams) -> Result<(), Error> {
+    let ext_str = match params.out_file.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => ext,
+        None => {
+            bail!("Error: A file extension is required for the output file!");
+        }
+    };
+    let output_format = match format_from_ext(ext_str) {
+        Some(format) => format,
+        None => {
+            bail!("Error: Unknown output file extension: {}", ext_str);
+        }
+    };
+
+    let mut reader = read_file(&params.in_file)?;
+    let mut disk = match DiskImage::load(&mut reader, Some(&params.in_file), None, None) {
+        Ok(disk) => disk,
+        Err(e) => {
+            bail!("Error loading disk image: {}", e);
+        }
+    };
+
+    let mut opts = match params.severity.unwrap_or_default() {
+        AgeSeverity::Light => AgingOptions::light(),
+        AgeSeverity::Moderate => AgingOptions::default(),
+        AgeSeverity::Severe => AgingOptions::severe(),
+    };
+
+    for pass in 0..params.passes.unwrap_or(1) {
+        opts = opts.with_seed(params.seed.unwrap_or(0).wrapping_add(pass as u64));
+        let report = match disk.age(&opts) {
+            Ok(report) => report,
+            Err(e) => {
+                bail!("Error aging disk image: {}", e);
+            }
+        };
+        global.loud(|| print!("Pass {}: {}", pass + 1, report));
+    }
+
+    match ImageWriter::new(&mut disk)
+        .with_format(output_format)
+        .with_path(params.out_file.clone())
+        .write()
+    {
+        Ok(_) => {
+            global.loud(|| println!("Aged image saved to {}", params.out_file.display()));
+            Ok(())
+        }
+        Err(e) => {
+            bail!("Error saving output image: {}", e);
+        }
+    }
+}
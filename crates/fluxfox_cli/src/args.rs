@@ -33,11 +33,17 @@ use std::{
 };
 
 use crate::{
+    age::args::{age_parser, AgeParams},
     convert::args::{convert_parser, ConvertParams},
     create::args::{create_parser, CreateParams},
+    diff::args::{diff_parser, DiffParams},
     dump::args::{dump_parser, DumpParams},
+    extract::args::{extract_parser, ExtractParams},
     find::args::{find_parser, FindParams},
+    hash::args::{hash_parser, HashParams},
     info::args::{info_parser, InfoParams},
+    triage::args::{triage_parser, TriageParams},
+    verify::args::{verify_parser, VerifyParams},
 };
 use bpaf::*;
 use fluxfox::prelude::*;
@@ -65,22 +71,34 @@ impl FromStr for DumpFormat {
 #[derive(Clone, Debug)]
 pub(crate) enum Command {
     Version,
+    Age(AgeParams),
     Convert(ConvertParams),
     Create(CreateParams),
+    Diff(DiffParams),
     Dump(DumpParams),
+    Extract(ExtractParams),
     Find(FindParams),
+    Hash(HashParams),
     Info(InfoParams),
+    Triage(TriageParams),
+    Verify(VerifyParams),
 }
 
 impl Display for Command {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Command::Version => write!(f, "version"),
+            Command::Age(_) => write!(f, "age"),
             Command::Convert(_) => write!(f, "convert"),
             Command::Create(_) => write!(f, "create"),
+            Command::Diff(_) => write!(f, "diff"),
             Command::Dump(_) => write!(f, "dump"),
+            Command::Extract(_) => write!(f, "extract"),
             Command::Find(_) => write!(f, "find"),
+            Command::Hash(_) => write!(f, "hash"),
             Command::Info(_) => write!(f, "info"),
+            Command::Triage(_) => write!(f, "triage"),
+            Command::Verify(_) => write!(f, "verify"),
         }
     }
 }
@@ -135,6 +153,11 @@ pub(crate) fn command_parser() -> impl Parser<AppParams> {
         .command("version")
         .help("Display version information and exit");
 
+    let age = construct!(Command::Age(age_parser()))
+        .to_options()
+        .command("age")
+        .help("Degrade a disk image as if the media had aged, to produce test images");
+
     let convert = construct!(Command::Convert(convert_parser()))
         .to_options()
         .command("convert")
@@ -145,22 +168,47 @@ pub(crate) fn command_parser() -> impl Parser<AppParams> {
         .command("create")
         .help("Create a new disk image");
 
+    let diff = construct!(Command::Diff(diff_parser()))
+        .to_options()
+        .command("diff")
+        .help("Compare two disk images sector by sector");
+
     let dump = construct!(Command::Dump(dump_parser()))
         .to_options()
         .command("dump")
         .help("Dump data from a disk image");
 
+    let extract = construct!(Command::Extract(extract_parser()))
+        .to_options()
+        .command("extract")
+        .help("Extract the files from the FAT file system of a disk image");
+
     let find = construct!(Command::Find(find_parser()))
         .to_options()
         .command("find")
         .help("Find data in a disk image");
 
+    let hash = construct!(Command::Hash(hash_parser()))
+        .to_options()
+        .command("hash")
+        .help("Print digests of the sector data of a disk image");
+
     let info = construct!(Command::Info(info_parser()))
         .to_options()
         .command("info")
         .help("Display information about a disk image");
 
-    let command = construct!([version, convert, create, dump, find, info]);
+    let triage = construct!(Command::Triage(triage_parser()))
+        .to_options()
+        .command("triage")
+        .help("Rank a directory of disk images by health, grouping duplicates");
+
+    let verify = construct!(Command::Verify(verify_parser()))
+        .to_options()
+        .command("verify")
+        .help("Check that every sector of a disk image can be read without error");
+
+    let command = construct!([version, age, convert, create, diff, dump, extract, find, hash, info, triage, verify]);
 
     construct!(AppParams { global, command })
 }
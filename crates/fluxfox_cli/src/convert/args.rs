@@ -24,17 +24,32 @@
 
     --------------------------------------------------------------------------
 */
-use crate::args::*;
+use crate::{
+    args::*,
+    inputs::{inputs_parser, InputParams},
+};
 use bpaf::{construct, long, Parser};
+use fluxfox::batch::ErrorPolicy;
 use std::path::PathBuf;
 
+/// Where converted images are written.
+#[derive(Clone, Debug)]
+pub(crate) enum ConvertOutput {
+    /// Write a single input image to the named file.
+    File(PathBuf),
+    /// Write each input image into a directory, named after the input with a new extension.
+    Dir { dir: PathBuf, ext: String, on_error: ErrorPolicy },
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct ConvertParams {
-    pub(crate) in_file: PathBuf,
-    pub(crate) out_file: PathBuf,
+    pub(crate) inputs: InputParams,
+    pub(crate) output: ConvertOutput,
     #[allow(dead_code)]
     pub(crate) weak_to_holes: bool,
     pub(crate) prolok: bool,
+    pub(crate) revolutions: Option<u8>,
+    pub(crate) jitter: bool,
 }
 
 fn weak_to_holes_parser() -> impl Parser<bool> {
@@ -47,18 +62,63 @@ fn prolok_parser() -> impl Parser<bool> {
         .help("Convert weak bits to holes on Prolok-protected tracks")
 }
 
-pub(crate) fn convert_parser() -> impl Parser<ConvertParams> {
-    //let path = positional::<String>("PATH").help("Path to the file to dump");
+fn revolutions_parser() -> impl Parser<Option<u8>> {
+    long("revolutions")
+        .argument::<u8>("REVOLUTIONS")
+        .help("Number of revolutions to write per track when converting to a flux image format")
+        .guard(|&revs| revs > 0, "Revolutions must be at least 1")
+        .optional()
+}
+
+fn jitter_parser() -> impl Parser<bool> {
+    long("jitter")
+        .switch()
+        .help("Add realistic flux jitter and speed wobble when converting to a flux image format")
+}
 
-    let in_file = in_file_parser();
-    let out_file = out_file_parser();
+fn on_error_parser() -> impl Parser<ErrorPolicy> {
+    long("on-error")
+        .argument::<String>("POLICY")
+        .help("What to do when an image fails to convert, when converting many images: 'skip' (default), 'abort', or 'best-effort' to also write images the output format cannot fully represent")
+        .parse(|policy| match policy.to_lowercase().as_str() {
+            "skip" => Ok(ErrorPolicy::Skip),
+            "abort" => Ok(ErrorPolicy::Abort),
+            "best-effort" => Ok(ErrorPolicy::BestEffort),
+            _ => Err("Invalid policy; expected 'skip', 'abort', or 'best-effort'"),
+        })
+        .fallback(ErrorPolicy::Skip)
+}
+
+fn output_parser() -> impl Parser<ConvertOutput> {
+    let file = out_file_parser().map(ConvertOutput::File);
+
+    let dir = long("out_dir")
+        .argument::<PathBuf>("OUTPUT_DIR")
+        .help("Directory to write converted images to, when converting many images");
+    let ext = long("format")
+        .short('f')
+        .argument::<String>("EXTENSION")
+        .help("File extension of the output format, when converting many images (e.g. 'img', 'imd', 'scp')");
+    let on_error = on_error_parser();
+    let dir = construct!(ConvertOutput::Dir { dir, ext, on_error });
+
+    construct!([file, dir])
+}
+
+pub(crate) fn convert_parser() -> impl Parser<ConvertParams> {
+    let inputs = inputs_parser();
+    let output = output_parser();
     let weak_to_holes = weak_to_holes_parser();
     let prolok = prolok_parser();
+    let revolutions = revolutions_parser();
+    let jitter = jitter_parser();
 
     construct!(ConvertParams {
-        in_file,
-        out_file,
+        output,
         weak_to_holes,
         prolok,
+        revolutions,
+        jitter,
+        inputs,
     })
 }
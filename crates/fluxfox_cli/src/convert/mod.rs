@@ -26,15 +26,90 @@
 */
 pub mod args;
 
-use crate::{args::GlobalOptions, prompt, read_file};
+use crate::{args::GlobalOptions, convert::args::ConvertOutput, prompt, read_file};
 use anyhow::{bail, Error};
-use fluxfox::prelude::*;
-use std::io::Cursor;
+use fluxfox::{
+    batch::{BatchConverter, ConvertJob, ConvertResult},
+    prelude::*,
+    types::DiskImageFlags,
+};
+use std::{io::Cursor, path::Path, sync::Arc};
 
 pub(crate) fn run(global: &GlobalOptions, params: &args::ConvertParams) -> Result<(), Error> {
-    let mut reader = read_file(&params.in_file.clone())?;
+    let paths = params.inputs.paths()?;
+
+    match &params.output {
+        ConvertOutput::File(out_file) => {
+            if paths.len() != 1 {
+                bail!(
+                    "{} input images were specified; use --out_dir to convert more than one image",
+                    paths.len()
+                );
+            }
+            convert_file(global, params, &paths[0], out_file)
+        }
+        ConvertOutput::Dir { dir, ext, on_error } => {
+            let Some(output_format) = format_from_ext(ext)
+            else {
+                bail!("Error: Unknown output file extension: {}", ext);
+            };
+
+            let mut image_flags = DiskImageFlags::empty();
+            if params.prolok {
+                image_flags |= DiskImageFlags::PROLOK;
+            }
+            let silent = global.silent;
+            let converter = BatchConverter::new(output_format)
+                .with_policy(*on_error)
+                .with_image_flags(image_flags)
+                .with_options(write_options(params))
+                .with_callback(Arc::new(move |result: &ConvertResult| {
+                    if !silent {
+                        println!(
+                            "{} -> {}: {}",
+                            result.job.input.display(),
+                            result.job.output.display(),
+                            result.outcome
+                        );
+                    }
+                }));
+
+            let report = converter.convert(&ConvertJob::into_dir(&paths, dir, ext)?);
+            global.loud(|| println!("{}", report));
+            if report.converted() < report.results.len() {
+                bail!(
+                    "{} of {} images were not converted",
+                    report.results.len() - report.converted(),
+                    report.results.len()
+                );
+            }
+            Ok(())
+        }
+    }
+}
 
-    let disk_image_type = match DiskImage::detect_format(&mut reader, Some(&params.in_file)) {
+fn write_options(params: &args::ConvertParams) -> ParserWriteOptions {
+    let mut flux_opts = if params.jitter {
+        FluxSynthesisOptions::realistic()
+    }
+    else {
+        FluxSynthesisOptions::default()
+    };
+    if let Some(revolutions) = params.revolutions {
+        flux_opts = flux_opts.with_revolutions(revolutions as usize);
+    }
+    ParserWriteOptions::default().with_flux_synthesis(flux_opts)
+}
+
+fn convert_file(
+    global: &GlobalOptions,
+    params: &args::ConvertParams,
+    in_file: &Path,
+    out_file: &Path,
+) -> Result<(), Error> {
+    let mut reader = read_file(in_file)?;
+
+    let disk_image_type = match DiskImage::detect_format(&mut reader, Some(in_file)) {
         Ok(disk_image_type) => disk_image_type,
         Err(e) => {
             bail!("Error detecting input disk image type: {}", e);
@@ -46,7 +121,7 @@ pub(crate) fn run(global: &GlobalOptions, params: &args::ConvertParams) -> Resul
     }
 
     // Get extension from output filename
-    let output_extension = match params.out_file.extension() {
+    let output_extension = match out_file.extension() {
         Some(ext) => ext,
         None => {
             bail!("Error: A file extension is required for the output file!");
@@ -70,7 +145,7 @@ pub(crate) fn run(global: &GlobalOptions, params: &args::ConvertParams) -> Resul
     //std::process::exit(0);
 
     // Load disk image
-    let mut in_disk = match DiskImage::load(&mut reader, Some(&params.in_file), None, None) {
+    let mut in_disk = match DiskImage::load(&mut reader, Some(in_file), None, None) {
         Ok(disk) => disk,
         Err(e) => {
             bail!("Error loading disk image: {}", e);
@@ -100,14 +175,16 @@ pub(crate) fn run(global: &GlobalOptions, params: &args::ConvertParams) -> Resul
         }
     }
 
+    let write_opts = write_options(params);
+
     // Create an output buffer
     let mut out_buffer = Cursor::new(Vec::new());
-    match output_format.save_image(&mut in_disk, &ParserWriteOptions::default(), &mut out_buffer) {
+    match output_format.save_image(&mut in_disk, &write_opts, &mut out_buffer) {
         Ok(_) => {
             let out_inner: Vec<u8> = out_buffer.into_inner();
-            match std::fs::write(params.out_file.clone(), out_inner) {
+            match std::fs::write(out_file, out_inner) {
                 Ok(_) => {
-                    println!("Output image saved to {}", params.out_file.display());
+                    println!("Output image saved to {}", out_file.display());
                     Ok(())
                 }
                 Err(e) => {
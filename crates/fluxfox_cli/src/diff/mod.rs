@@ -0,0 +1,102 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{args::GlobalOptions, diff::args::DiffParams};
+use anyhow::{bail, Error};
+use fluxfox::{
+    diff::{DiffSide, SectorDifference, TrackDifference},
+    prelude::*,
+};
+
+pub mod args;
+
+/// Compare two images sector by sector, and list the differences.
+pub(crate) fn run(global: &GlobalOptions, params: &DiffParams) -> Result<(), Error> {
+    let load =
+        |path| DiskImage::load_from_file(path, None, None).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e));
+    let left = load(&params.left)?;
+    let right = load(&params.right)?;
+
+    let diff = left.diff(&right);
+    if diff.is_empty() {
+        global.loud(|| println!("Images are identical."));
+        return Ok(());
+    }
+
+    for track in &diff.tracks {
+        for difference in &track.differences {
+            println!("track {}: {}", track.ch, track_difference(difference));
+        }
+    }
+    for sector in &diff.sectors {
+        for difference in &sector.differences {
+            println!("sector {} {}: {}", sector.ch, sector.id, sector_difference(difference));
+        }
+    }
+
+    bail!(
+        "Images differ in {} tracks ({} track and {} sector differences)",
+        diff.differing_tracks().len(),
+        diff.tracks.len(),
+        diff.sectors.len()
+    );
+}
+
+fn side(side: &DiffSide) -> &'static str {
+    match side {
+        DiffSide::Left => "left",
+        DiffSide::Right => "right",
+    }
+}
+
+fn track_difference(difference: &TrackDifference) -> String {
+    match difference {
+        TrackDifference::OnlyIn(s) => format!("only in {}", side(s)),
+        TrackDifference::WeakBits { left, right } => format!("weak bits: left {} right {}", left, right),
+    }
+}
+
+fn sector_difference(difference: &SectorDifference) -> String {
+    match difference {
+        SectorDifference::OnlyIn(s) => format!("only in {}", side(s)),
+        SectorDifference::Unreadable { left, right } => format!("read failed: left {} right {}", left, right),
+        SectorDifference::Data {
+            left_len,
+            right_len,
+            differing_bytes,
+        } => format!(
+            "data differs: {} bytes differ (left {} bytes, right {} bytes)",
+            differing_bytes, left_len, right_len
+        ),
+        SectorDifference::AddressCrc { left, right } => {
+            format!("address CRC valid: left {} right {}", !left, !right)
+        }
+        SectorDifference::DataCrc { left, right } => format!("data CRC valid: left {} right {}", !left, !right),
+        SectorDifference::DeletedMark { left, right } => format!("deleted: left {} right {}", left, right),
+        SectorDifference::NoDam { left, right } => format!("no DAM: left {} right {}", left, right),
+        SectorDifference::WeakMask => "weak bit masks differ".to_string(),
+    }
+}
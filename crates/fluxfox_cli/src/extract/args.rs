@@ -0,0 +1,57 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::inputs::{inputs_parser, InputParams};
+use bpaf::{construct, long, Parser};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug)]
+pub(crate) struct ExtractParams {
+    pub(crate) inputs:  InputParams,
+    pub(crate) out_dir: PathBuf,
+    pub(crate) path:    Option<String>,
+}
+
+fn out_dir_parser() -> impl Parser<PathBuf> {
+    long("out_dir")
+        .short('o')
+        .argument::<PathBuf>("OUTPUT_DIR")
+        .help("Directory to extract files to")
+}
+
+fn path_parser() -> impl Parser<String> {
+    long("path")
+        .argument::<String>("PATH")
+        .help("Only extract files under this path in the disk image's file system")
+}
+
+pub(crate) fn extract_parser() -> impl Parser<ExtractParams> {
+    let inputs = inputs_parser();
+    let out_dir = out_dir_parser();
+    let path = path_parser().optional();
+
+    construct!(ExtractParams { out_dir, path, inputs })
+}
@@ -0,0 +1,109 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{args::GlobalOptions, extract::args::ExtractParams};
+use anyhow::{bail, Error};
+use fluxfox::{
+    disk_lock::{NonTrackingDiskLock, NullContext},
+    file_system::{fat::fat_fs::FatFileSystem, FileSystem},
+    prelude::*,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+pub mod args;
+
+/// Extract the files in the FAT file system of each image. When several images are given, each
+/// is extracted into a subdirectory named after the image.
+pub(crate) fn run(global: &GlobalOptions, params: &ExtractParams) -> Result<(), Error> {
+    let paths = params.inputs.paths()?;
+
+    let mut failed = 0;
+    for path in &paths {
+        let out_dir = match (paths.len(), path.file_stem()) {
+            (1, _) => params.out_dir.clone(),
+            (_, Some(stem)) => params.out_dir.join(stem),
+            (_, None) => continue,
+        };
+        match extract_image(params, path, &out_dir) {
+            Ok(file_ct) => global.loud(|| {
+                println!(
+                    "Extracted {} files from {} to {}",
+                    file_ct,
+                    path.display(),
+                    out_dir.display()
+                )
+            }),
+            Err(e) => {
+                eprintln!("Error extracting {}: {}", path.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("{} of {} images could not be extracted", failed, paths.len());
+    }
+    Ok(())
+}
+
+fn extract_image(params: &ExtractParams, path: &Path, out_dir: &Path) -> Result<usize, Error> {
+    let disk = DiskImage::load_from_file(path, None, None)?;
+    let lock: NonTrackingDiskLock<DiskImage> = Arc::new(RwLock::new(disk)).into();
+    let mut fs = FatFileSystem::mount(lock, NullContext::default(), None)?;
+
+    let prefix = params.path.as_deref().map(|p| p.trim_end_matches('/'));
+    let mut file_ct = 0;
+    let mut result = Ok(());
+    for file in fs.list_all_files() {
+        if let Some(prefix) = prefix {
+            if !file.starts_with(&format!("{}/", prefix)) {
+                continue;
+            }
+        }
+
+        // Rebuild the path from its components, so that it can't escape the output directory.
+        let out_path: PathBuf = out_dir.join(
+            file.split('/')
+                .filter(|c| !c.is_empty() && *c != "." && *c != "..")
+                .collect::<PathBuf>(),
+        );
+        result = fs.read_file(&file).map_err(Error::from).and_then(|data| {
+            if let Some(parent) = out_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            Ok(std::fs::write(&out_path, data)?)
+        });
+        if result.is_err() {
+            break;
+        }
+        file_ct += 1;
+    }
+    fs.unmount();
+    result.map(|_| file_ct)
+}
@@ -0,0 +1,59 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::inputs::{inputs_parser, InputParams};
+use bpaf::{construct, long, Parser};
+
+#[derive(Clone, Debug)]
+pub(crate) struct HashParams {
+    pub(crate) inputs: InputParams,
+    pub(crate) full: bool,
+    pub(crate) exclude_masked: bool,
+}
+
+fn full_parser() -> impl Parser<bool> {
+    long("full")
+        .help("List the digest of every track and sector, not only the whole image")
+        .switch()
+}
+
+fn exclude_masked_parser() -> impl Parser<bool> {
+    long("exclude-masked")
+        .help("Exclude weak and hole-masked bits from the digests, so that repeated reads of the same disk match")
+        .switch()
+}
+
+pub(crate) fn hash_parser() -> impl Parser<HashParams> {
+    let inputs = inputs_parser();
+    let full = full_parser();
+    let exclude_masked = exclude_masked_parser();
+
+    construct!(HashParams {
+        full,
+        exclude_masked,
+        inputs,
+    })
+}
@@ -0,0 +1,65 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{args::GlobalOptions, hash::args::HashParams};
+use anyhow::{bail, Error};
+use fluxfox::{hash_manifest::HashManifestOptions, prelude::*};
+
+pub mod args;
+
+/// Print the digests of the sector data of each image.
+pub(crate) fn run(_global: &GlobalOptions, params: &HashParams) -> Result<(), Error> {
+    let paths = params.inputs.paths()?;
+    let opts = HashManifestOptions {
+        exclude_masked: params.exclude_masked,
+    };
+
+    let mut failed = 0;
+    for path in &paths {
+        let disk = match DiskImage::load_from_file(path, None, None) {
+            Ok(disk) => disk,
+            Err(e) => {
+                eprintln!("Error loading {}: {}", path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let manifest = disk.hash_manifest(&opts);
+        if params.full {
+            println!("# {}", path.display());
+            print!("{}", manifest);
+        }
+        else {
+            println!("{}  {}", manifest.image, path.display());
+        }
+    }
+
+    if failed > 0 {
+        bail!("{} of {} images could not be loaded", failed, paths.len());
+    }
+    Ok(())
+}
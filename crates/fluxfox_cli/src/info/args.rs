@@ -24,14 +24,13 @@
 
     --------------------------------------------------------------------------
 */
-use crate::args::in_file_parser;
+use crate::inputs::{inputs_parser, InputParams};
 use bpaf::{construct, Parser};
-use std::path::PathBuf;
 
 #[derive(Clone, Debug)]
 pub(crate) struct InfoParams {
     // Define specific parameters for `info`
-    pub(crate) in_file: PathBuf,
+    pub(crate) inputs: InputParams,
     pub(crate) sector_list: bool,
     pub(crate) track_list: bool,
     pub(crate) rev_list: bool,
@@ -56,15 +55,15 @@ fn rev_list_parser() -> impl Parser<bool> {
 }
 
 pub(crate) fn info_parser() -> impl Parser<InfoParams> {
-    let in_file = in_file_parser();
+    let inputs = inputs_parser();
     let sector_list = sector_list_parser();
     let track_list = track_list_parser();
     let rev_list = rev_list_parser();
 
     construct!(InfoParams {
-        in_file,
         sector_list,
         track_list,
         rev_list,
+        inputs,
     })
 }
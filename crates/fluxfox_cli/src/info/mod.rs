@@ -27,13 +27,37 @@
 use crate::{args::GlobalOptions, read_file};
 use anyhow::{bail, Error};
 use fluxfox::{flux::FluxRevolutionType, prelude::*};
+use std::path::Path;
 
 pub mod args;
 
 pub(crate) fn run(_global: &GlobalOptions, params: &args::InfoParams) -> Result<(), Error> {
-    let mut reader = read_file(&params.in_file)?;
+    let paths = params.inputs.paths()?;
+    let mut failed = 0;
+    for path in &paths {
+        if paths.len() > 1 {
+            println!("{}", "=".repeat(79));
+            println!("{}", path.display());
+            println!("{}", "=".repeat(79));
+        }
+        if let Err(e) = info_file(params, path) {
+            if paths.len() == 1 {
+                return Err(e);
+            }
+            eprintln!("Error reading {}: {}", path.display(), e);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        bail!("{} of {} images could not be read", failed, paths.len());
+    }
+    Ok(())
+}
 
-    let disk_image_type = match DiskImage::detect_format(&mut reader, Some(&params.in_file)) {
+fn info_file(params: &args::InfoParams, path: &Path) -> Result<(), Error> {
+    let mut reader = read_file(path)?;
+
+    let disk_image_type = match DiskImage::detect_format(&mut reader, Some(path)) {
         Ok(disk_image_type) => disk_image_type,
         Err(e) => {
             bail!("Error detecting disk image type: {}", e);
@@ -42,7 +66,7 @@ pub(crate) fn run(_global: &GlobalOptions, params: &args::InfoParams) -> Result<
 
     println!("Detected disk image type: {}", disk_image_type);
 
-    let mut disk = match DiskImage::load(&mut reader, Some(&params.in_file), None, None) {
+    let mut disk = match DiskImage::load(&mut reader, Some(path), None, None) {
         Ok(disk) => disk,
         Err(e) => {
             bail!("Error loading disk image: {}", e);
@@ -57,6 +81,9 @@ pub(crate) fn run(_global: &GlobalOptions, params: &args::InfoParams) -> Result<
     println!("Disk analysis:");
     println!("{}", "-".repeat(79));
     let _ = disk.dump_analysis(&mut std::io::stdout());
+    println!("{}", disk.health());
+    println!("{}", disk.compressibility());
+    println!();
 
     println!("Image can be represented by the following formats with write support:");
     let formats = disk.compatible_formats(true);
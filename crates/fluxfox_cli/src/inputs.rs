@@ -0,0 +1,123 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    inputs.rs
+
+    Expand the inputs of commands that operate on many disk images: glob
+    patterns, and directories searched for disk images.
+*/
+use anyhow::{bail, Error};
+use bpaf::{construct, long, positional, Parser};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug)]
+pub(crate) struct InputParams {
+    pub(crate) inputs:    Vec<String>,
+    pub(crate) recursive: bool,
+}
+
+impl InputParams {
+    /// Expand the inputs into a sorted list of files. Glob patterns are matched against the file
+    /// system, and directories are searched for files with a supported disk image extension,
+    /// including subdirectories if `recursive` is set. Files named explicitly are always included.
+    pub(crate) fn paths(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut paths = Vec::new();
+        for input in &self.inputs {
+            let matched = if is_glob(input) {
+                glob::glob(input)?.collect::<Result<Vec<_>, _>>()?
+            }
+            else {
+                vec![PathBuf::from(input)]
+            };
+            if matched.is_empty() {
+                bail!("No files match '{}'", input);
+            }
+
+            for path in matched {
+                if path.is_dir() {
+                    collect_images(&path, self.recursive, &mut paths)?;
+                }
+                else if path.exists() {
+                    paths.push(path);
+                }
+                else {
+                    bail!("Input file not found: {}", path.display());
+                }
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+}
+
+fn is_glob(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+fn collect_images(dir: &Path, recursive: bool, paths: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let extensions = fluxfox::supported_extensions();
+    for dir_entry in std::fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        if path.is_dir() {
+            if recursive {
+                collect_images(&path, recursive, paths)?;
+            }
+        }
+        else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| extensions.contains(&ext.to_lowercase().as_str()))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn inputs_parser() -> impl Parser<InputParams> {
+    let flagged = long("in_file")
+        .short('i')
+        .argument::<String>("INPUT")
+        .help("Path to an input file or directory, or a glob pattern. May be repeated")
+        .many();
+    let positional = positional::<String>("INPUTS")
+        .help("Further input files, directories or glob patterns")
+        .many();
+    let inputs = construct!(flagged, positional)
+        .map(|(mut flagged, positional)| {
+            flagged.extend(positional);
+            flagged
+        })
+        .guard(|inputs| !inputs.is_empty(), "At least one input is required");
+    let recursive = long("recursive")
+        .short('R')
+        .help("Search input directories recursively")
+        .switch();
+
+    // Positional inputs must be parsed last.
+    construct!(InputParams { recursive, inputs })
+}
@@ -25,13 +25,20 @@
     --------------------------------------------------------------------------
 */
 
+mod age;
 pub mod args;
 pub mod convert;
 pub mod create;
+mod diff;
 pub mod dump;
+mod extract;
 mod find;
+mod hash;
 pub mod info;
+mod inputs;
 mod prompt;
+mod triage;
+mod verify;
 
 use anyhow::Error;
 use bpaf::Parser;
@@ -50,11 +57,17 @@ fn main() -> Result<(), Error> {
             println!("fftool v{}", env!("CARGO_PKG_VERSION"));
             Ok(())
         }
+        Command::Age(params) => age::run(&app_params.global, params),
         Command::Find(params) => find::run(&app_params.global, params),
         Command::Convert(params) => convert::run(&app_params.global, params),
         Command::Create(params) => create::run(&app_params.global, params),
+        Command::Diff(params) => diff::run(&app_params.global, params),
         Command::Dump(params) => dump::run(&app_params.global, params),
+        Command::Extract(params) => extract::run(&app_params.global, params),
+        Command::Hash(params) => hash::run(&app_params.global, params),
         Command::Info(params) => info::run(&app_params.global, params),
+        Command::Triage(params) => triage::run(&app_params.global, params),
+        Command::Verify(params) => verify::run(&app_params.global, params),
     };
 
     match command_result {
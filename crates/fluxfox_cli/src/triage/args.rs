@@ -0,0 +1,82 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use bpaf::{construct, long, Parser};
+use std::{path::PathBuf, str::FromStr};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) enum TriageFormat {
+    #[default]
+    Text,
+    Csv,
+    Json,
+}
+
+impl FromStr for TriageFormat {
+    type Err = &'static str;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "text" => Ok(TriageFormat::Text),
+            "csv" => Ok(TriageFormat::Csv),
+            "json" => Ok(TriageFormat::Json),
+            _ => Err("Invalid format; expected 'text', 'csv', or 'json'"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TriageParams {
+    pub(crate) in_dir:   PathBuf,
+    pub(crate) out_file: Option<PathBuf>,
+    pub(crate) format:   Option<TriageFormat>,
+}
+
+fn in_dir_parser() -> impl Parser<PathBuf> {
+    long("in_dir")
+        .short('i')
+        .argument::<PathBuf>("INPUT_DIR")
+        .help("Path to a directory of disk images")
+}
+
+fn triage_format_parser() -> impl Parser<TriageFormat> {
+    long("format")
+        .short('f')
+        .argument::<TriageFormat>("FORMAT")
+        .help("Report format: text, csv, or json")
+}
+
+pub(crate) fn triage_parser() -> impl Parser<TriageParams> {
+    let in_dir = in_dir_parser();
+    let out_file = crate::args::out_file_parser().optional();
+    let format = triage_format_parser().optional();
+
+    construct!(TriageParams {
+        in_dir,
+        out_file,
+        format,
+    })
+}
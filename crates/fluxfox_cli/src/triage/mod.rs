@@ -0,0 +1,86 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{
+    args::GlobalOptions,
+    triage::args::{TriageFormat, TriageParams},
+};
+use anyhow::{bail, Error};
+use fluxfox::batch::{triage, TriageReport};
+
+pub mod args;
+
+pub(crate) fn run(global: &GlobalOptions, params: &TriageParams) -> Result<(), Error> {
+    if !params.in_dir.is_dir() {
+        bail!("Input path is not a directory: {}", params.in_dir.display());
+    }
+
+    global.loud(|| println!("Triaging disk images in {}...", params.in_dir.display()));
+
+    let report = match triage(&params.in_dir) {
+        Ok(report) => report,
+        Err(e) => {
+            bail!("Error reading directory: {}", e);
+        }
+    };
+
+    let output = match params.format.unwrap_or_default() {
+        TriageFormat::Text => text_report(&report),
+        TriageFormat::Csv => report.to_csv(),
+        TriageFormat::Json => report.to_json(),
+    };
+
+    match &params.out_file {
+        Some(out_file) => {
+            std::fs::write(out_file, output)?;
+            global.loud(|| println!("Wrote triage report to {}", out_file.display()));
+        }
+        None => print!("{}", output),
+    }
+
+    Ok(())
+}
+
+fn text_report(report: &TriageReport) -> String {
+    let mut out = String::new();
+    for entry in &report.entries {
+        let group = entry
+            .duplicate_group
+            .map(|g| format!(" [duplicate group {}]", g))
+            .unwrap_or_default();
+        out.push_str(&format!("{:>3} {}{}\n", entry.score(), entry.path.display(), group));
+
+        if let Some(error) = &entry.error {
+            out.push_str(&format!("      Load error: {}\n", error));
+        }
+        if let Some(health) = &entry.health {
+            for factor in health.penalties() {
+                out.push_str(&format!("      {}\n", factor));
+            }
+        }
+    }
+    out
+}
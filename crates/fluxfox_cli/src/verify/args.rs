@@ -0,0 +1,45 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::inputs::{inputs_parser, InputParams};
+use bpaf::{construct, long, Parser};
+
+#[derive(Clone, Debug)]
+pub(crate) struct VerifyParams {
+    pub(crate) inputs: InputParams,
+    pub(crate) sector_list: bool,
+}
+
+fn sector_list_parser() -> impl Parser<bool> {
+    long("sector-list").help("List each bad sector found").switch()
+}
+
+pub(crate) fn verify_parser() -> impl Parser<VerifyParams> {
+    let inputs = inputs_parser();
+    let sector_list = sector_list_parser();
+
+    construct!(VerifyParams { sector_list, inputs })
+}
@@ -0,0 +1,93 @@
+/*
+    fftool
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+use crate::{args::GlobalOptions, verify::args::VerifyParams};
+use anyhow::{bail, Error};
+use fluxfox::prelude::*;
+
+pub mod args;
+
+/// Load each image and check that every sector can be read with valid CRCs.
+pub(crate) fn run(global: &GlobalOptions, params: &VerifyParams) -> Result<(), Error> {
+    let paths = params.inputs.paths()?;
+
+    let mut failed = 0;
+    for path in &paths {
+        let disk = match DiskImage::load_from_file(path, None, None) {
+            Ok(disk) => disk,
+            Err(e) => {
+                println!("FAIL {}: {}", path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let mut sector_ct = 0;
+        let mut bad_sectors = Vec::new();
+        for (_, track) in disk.iter_tracks() {
+            for entry in track.sector_list() {
+                sector_ct += 1;
+                let attr = entry.attributes;
+                if attr.address_error || attr.data_error || attr.no_dam {
+                    bad_sectors.push(entry);
+                }
+            }
+        }
+
+        if bad_sectors.is_empty() {
+            global.loud(|| println!("OK   {} ({} sectors)", path.display(), sector_ct));
+            continue;
+        }
+
+        failed += 1;
+        println!(
+            "FAIL {}: {} of {} sectors bad",
+            path.display(),
+            bad_sectors.len(),
+            sector_ct
+        );
+        if params.sector_list {
+            for entry in bad_sectors {
+                let attr = entry.attributes;
+                let reason = if attr.address_error {
+                    "bad address CRC"
+                }
+                else if attr.no_dam {
+                    "no data address mark"
+                }
+                else {
+                    "bad data CRC"
+                };
+                println!("       {}: {}", entry.chsn, reason);
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("{} of {} images failed verification", failed, paths.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,255 @@
+/* fluxfox C bindings. This file is generated by cbindgen from crates/fluxfox_ffi - do not edit. */
+
+#ifndef FLUXFOX_H
+#define FLUXFOX_H
+
+/* Regenerate this file by building the fluxfox_ffi crate. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The version of the C ABI. This is incremented whenever a function signature or the layout
+// of an exported type changes.
+#define FFX_ABI_VERSION 1
+
+// The sector was marked with a deleted data address mark.
+#define FFX_SECTOR_DELETED 1
+
+// The sector header had a CRC error.
+#define FFX_SECTOR_ADDRESS_CRC_ERROR 2
+
+// The sector data had a CRC error.
+#define FFX_SECTOR_DATA_CRC_ERROR 4
+
+// The sector header was found, but no sector data followed it.
+#define FFX_SECTOR_NO_DAM 8
+
+// The sector ID was not found, but a sector with a different cylinder ID was.
+#define FFX_SECTOR_WRONG_CYLINDER 16
+
+// The sector ID was not found, but a sector with a cylinder ID of 0xFF was.
+#define FFX_SECTOR_BAD_CYLINDER 32
+
+// The sector ID was not found, but a sector with a different head ID was.
+#define FFX_SECTOR_WRONG_HEAD 64
+
+// A sector size code that matches a sector of any size, when given in the [FfxSectorId] passed
+// to [ffx_image_read_sector] or [ffx_image_write_sector].
+#define FFX_SECTOR_SIZE_ANY 255
+
+// The result of a fluxfox function.
+typedef enum FfxStatus {
+  // The function succeeded.
+  FFX_STATUS_OK = 0,
+  // A required pointer argument was null.
+  FFX_STATUS_NULL_POINTER,
+  // An argument was invalid, such as a path that is not valid UTF-8.
+  FFX_STATUS_INVALID_ARGUMENT,
+  // A file could not be read or written.
+  FFX_STATUS_IO,
+  // The image format could not be detected, or does not support the operation.
+  FFX_STATUS_UNSUPPORTED_FORMAT,
+  // The requested cylinder or head does not exist.
+  FFX_STATUS_SEEK,
+  // The requested sector was not found.
+  FFX_STATUS_SECTOR_NOT_FOUND,
+  // The supplied buffer was too small. The required size has been reported.
+  FFX_STATUS_BUFFER_TOO_SMALL,
+  // The image is write protected.
+  FFX_STATUS_WRITE_PROTECTED,
+  // Any other error reported by fluxfox. See [ffx_last_error].
+  FFX_STATUS_IMAGE,
+  // fluxfox panicked. The image handle should not be used again.
+  FFX_STATUS_PANIC,
+} FfxStatus;
+
+// The resolution of a track's data.
+typedef enum FfxResolution {
+  // Only sector data and per-sector metadata are stored.
+  FFX_RESOLUTION_META_SECTOR = 0,
+  // A bitwise representation of the track is stored.
+  FFX_RESOLUTION_BIT_STREAM,
+  // Flux transition timings are stored, and resolved to a bitstream.
+  FFX_RESOLUTION_FLUX_STREAM,
+} FfxResolution;
+
+// The encoding of a track's data.
+typedef enum FfxEncoding {
+  // Frequency Modulation.
+  FFX_ENCODING_FM = 0,
+  // Modified Frequency Modulation.
+  FFX_ENCODING_MFM,
+  // Group Code Recording.
+  FFX_ENCODING_GCR,
+} FfxEncoding;
+
+// An opaque handle to a disk image.
+typedef struct FfxImage FfxImage;
+
+// Information about a single track.
+typedef struct FfxTrackInfo {
+  // The physical cylinder of the track.
+  uint16_t cylinder;
+  // The physical head of the track.
+  uint8_t head;
+  // The resolution of the track's data.
+  enum FfxResolution resolution;
+  // The encoding of the track's data.
+  enum FfxEncoding encoding;
+  // The data rate of the track, in bits per second.
+  uint32_t data_rate;
+  // The length of the track in bitcells, or 0 for `MetaSector` resolution tracks.
+  size_t bit_length;
+  // The number of sectors on the track.
+  size_t sector_count;
+} FfxTrackInfo;
+
+// A sector ID, as found in a sector header.
+typedef struct FfxSectorId {
+  // The cylinder ID.
+  uint16_t c;
+  // The head ID.
+  uint8_t h;
+  // The sector ID.
+  uint8_t s;
+  // The sector size code. The sector size in bytes is 128 << n. When reading or writing a
+  // sector, `FFX_SECTOR_SIZE_ANY` matches a sector of any size.
+  uint8_t n;
+} FfxSectorId;
+
+// The result of a sector read.
+typedef struct FfxReadResult {
+  // The ID of the sector that was read.
+  struct FfxSectorId id;
+  // The length of the sector data. If this exceeds the buffer length, the function returns
+  // `FFX_STATUS_BUFFER_TOO_SMALL` and no data is copied.
+  size_t data_len;
+  // A combination of the `FFX_SECTOR_*` flags.
+  uint32_t flags;
+} FfxReadResult;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Return the version of the fluxfox_ffi library as a NUL-terminated string. The string is
+// static and must not be freed.
+const char *ffx_version(void);
+
+// Return the version of the C ABI, [FFX_ABI_VERSION]. A caller should check this matches the
+// `FFX_ABI_VERSION` of the header it was compiled against.
+uint32_t ffx_abi_version(void);
+
+// Return a message describing the last error on the calling thread, or null if no error has
+// occurred. The string is owned by fluxfox and remains valid until the next failing call on
+// the same thread.
+const char *ffx_last_error(void);
+
+// Load a disk image from a file. The format is detected from the file's contents and
+// extension. On success, `*out_image` receives a handle that must be freed with
+// [ffx_image_free].
+//
+// # Safety
+// `path` must be a NUL-terminated UTF-8 string. `out_image` must be a valid pointer.
+enum FfxStatus ffx_image_load(const char *path, struct FfxImage **out_image);
+
+// Load a disk image from memory. `file_name` is optional, and may be null; when supplied, its
+// extension helps detect formats that have no signature, such as raw sector images. The data
+// is copied, and need not outlive the call. On success, `*out_image` receives a handle that
+// must be freed with [ffx_image_free].
+//
+// # Safety
+// `data` must point to `len` readable bytes. `file_name` must be null or a NUL-terminated
+// UTF-8 string. `out_image` must be a valid pointer.
+enum FfxStatus ffx_image_load_memory(const uint8_t *data,
+                                     size_t len,
+                                     const char *file_name,
+                                     struct FfxImage **out_image);
+
+// Free a disk image handle. Passing null does nothing.
+//
+// # Safety
+// `image` must be null or a handle returned by fluxfox that has not already been freed.
+void ffx_image_free(struct FfxImage *image);
+
+// Get the number of cylinders and heads of a disk image.
+//
+// # Safety
+// `image` must be a valid handle. `out_cylinders` and `out_heads` must be valid pointers.
+enum FfxStatus ffx_image_geometry(const struct FfxImage *image,
+                                  uint16_t *out_cylinders,
+                                  uint8_t *out_heads);
+
+// Get the number of tracks in a disk image. Tracks are numbered from 0 in cylinder order,
+// with the tracks of each head in turn for each cylinder.
+//
+// # Safety
+// `image` must be a valid handle. `out_count` must be a valid pointer.
+enum FfxStatus ffx_image_track_count(const struct FfxImage *image, size_t *out_count);
+
+// Get information about the track at `index`. See [ffx_image_track_count].
+//
+// # Safety
+// `image` must be a valid handle. `out_info` must be a valid pointer.
+enum FfxStatus ffx_image_track_info(const struct FfxImage *image,
+                                    size_t index,
+                                    struct FfxTrackInfo *out_info);
+
+// List the IDs of the sectors on a track, in physical order. The IDs are written to `out_ids`,
+// which has room for `capacity` IDs, and `*out_count` receives the number of sectors on the
+// track. If `capacity` is too small, `FFX_STATUS_BUFFER_TOO_SMALL` is returned and no IDs are
+// written. Pass a null `out_ids` to query the count alone.
+//
+// # Safety
+// `image` must be a valid handle. `out_ids` must be null or point to `capacity` writable
+// `FfxSectorId`s. `out_count` must be a valid pointer.
+enum FfxStatus ffx_image_sector_ids(const struct FfxImage *image,
+                                    uint16_t cylinder,
+                                    uint8_t head,
+                                    struct FfxSectorId *out_ids,
+                                    size_t capacity,
+                                    size_t *out_count);
+
+// Read the sector with the specified ID from the track at `cylinder` and `head`. The sector
+// data is copied to `buf`, and `*out_result` receives the ID of the sector read, the length of
+// its data and its status flags. A sector with a CRC error is read successfully; check the
+// flags of the result.
+//
+// # Safety
+// `image` must be a valid handle. `buf` must point to `buf_len` writable bytes. `out_result`
+// must be a valid pointer.
+enum FfxStatus ffx_image_read_sector(const struct FfxImage *image,
+                                     uint16_t cylinder,
+                                     uint8_t head,
+                                     struct FfxSectorId id,
+                                     uint8_t *buf,
+                                     size_t buf_len,
+                                     struct FfxReadResult *out_result);
+
+// Write `len` bytes of data to the sector with the specified ID on the track at `cylinder` and
+// `head`. If `deleted` is true, the sector is written with a deleted data address mark.
+//
+// # Safety
+// `image` must be a valid handle. `data` must point to `len` readable bytes.
+enum FfxStatus ffx_image_write_sector(struct FfxImage *image,
+                                      uint16_t cylinder,
+                                      uint8_t head,
+                                      struct FfxSectorId id,
+                                      const uint8_t *data,
+                                      size_t len,
+                                      bool deleted);
+
+// Save a disk image to a file. The file format is chosen from the extension of `path`.
+//
+// # Safety
+// `image` must be a valid handle. `path` must be a NUL-terminated UTF-8 string.
+enum FfxStatus ffx_image_save(struct FfxImage *image, const char *path);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FLUXFOX_H */
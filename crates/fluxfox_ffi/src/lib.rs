@@ -0,0 +1,787 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    crates/fluxfox_ffi/src/lib.rs
+
+    A C ABI for loading, reading, writing and saving disk images with fluxfox.
+*/
+
+//! C bindings for fluxfox.
+//!
+//! This crate exposes a small, stable C ABI over [DiskImage] so that emulators written in C or
+//! C++ can load, read, write and save disk images with fluxfox. The header `include/fluxfox.h`
+//! is generated from this file by cbindgen when the crate is built.
+//!
+//! Every function returns an [FfxStatus]. On failure, [ffx_last_error] returns a message
+//! describing the most recent error on the calling thread. A disk image is accessed through an
+//! opaque [FfxImage] handle, which is created by [ffx_image_load] or [ffx_image_load_memory] and
+//! must be released with [ffx_image_free]. A handle must not be used by two threads at once.
+//!
+//! Panics are caught at the boundary and reported as [FfxStatus::Panic]; they never unwind into
+//! the caller.
+
+use fluxfox::{io::Cursor, prelude::*, DiskImageError};
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::PathBuf,
+    ptr,
+    slice,
+};
+
+/// The version of the C ABI. This is incremented whenever a function signature or the layout
+/// of an exported type changes.
+pub const FFX_ABI_VERSION: u32 = 1;
+
+/// The sector was marked with a deleted data address mark.
+pub const FFX_SECTOR_DELETED: u32 = 0x01;
+/// The sector header had a CRC error.
+pub const FFX_SECTOR_ADDRESS_CRC_ERROR: u32 = 0x02;
+/// The sector data had a CRC error.
+pub const FFX_SECTOR_DATA_CRC_ERROR: u32 = 0x04;
+/// The sector header was found, but no sector data followed it.
+pub const FFX_SECTOR_NO_DAM: u32 = 0x08;
+/// The sector ID was not found, but a sector with a different cylinder ID was.
+pub const FFX_SECTOR_WRONG_CYLINDER: u32 = 0x10;
+/// The sector ID was not found, but a sector with a cylinder ID of 0xFF was.
+pub const FFX_SECTOR_BAD_CYLINDER: u32 = 0x20;
+/// The sector ID was not found, but a sector with a different head ID was.
+pub const FFX_SECTOR_WRONG_HEAD: u32 = 0x40;
+
+/// A sector size code that matches a sector of any size, when given in the [FfxSectorId] passed
+/// to [ffx_image_read_sector] or [ffx_image_write_sector].
+pub const FFX_SECTOR_SIZE_ANY: u8 = 0xFF;
+
+/// The result of a fluxfox function.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FfxStatus {
+    /// The function succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer,
+    /// An argument was invalid, such as a path that is not valid UTF-8.
+    InvalidArgument,
+    /// A file could not be read or written.
+    Io,
+    /// The image format could not be detected, or does not support the operation.
+    UnsupportedFormat,
+    /// The requested cylinder or head does not exist.
+    Seek,
+    /// The requested sector was not found.
+    SectorNotFound,
+    /// The supplied buffer was too small. The required size has been reported.
+    BufferTooSmall,
+    /// The image is write protected.
+    WriteProtected,
+    /// Any other error reported by fluxfox. See [ffx_last_error].
+    Image,
+    /// fluxfox panicked. The image handle should not be used again.
+    Panic,
+}
+
+/// The resolution of a track's data.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FfxResolution {
+    /// Only sector data and per-sector metadata are stored.
+    MetaSector = 0,
+    /// A bitwise representation of the track is stored.
+    BitStream,
+    /// Flux transition timings are stored, and resolved to a bitstream.
+    FluxStream,
+}
+
+/// The encoding of a track's data.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FfxEncoding {
+    /// Frequency Modulation.
+    Fm = 0,
+    /// Modified Frequency Modulation.
+    Mfm,
+    /// Group Code Recording.
+    Gcr,
+}
+
+/// A sector ID, as found in a sector header.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FfxSectorId {
+    /// The cylinder ID.
+    pub c: u16,
+    /// The head ID.
+    pub h: u8,
+    /// The sector ID.
+    pub s: u8,
+    /// The sector size code. The sector size in bytes is 128 << n. When reading or writing a
+    /// sector, `FFX_SECTOR_SIZE_ANY` matches a sector of any size.
+    pub n: u8,
+}
+
+/// Information about a single track.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct FfxTrackInfo {
+    /// The physical cylinder of the track.
+    pub cylinder: u16,
+    /// The physical head of the track.
+    pub head: u8,
+    /// The resolution of the track's data.
+    pub resolution: FfxResolution,
+    /// The encoding of the track's data.
+    pub encoding: FfxEncoding,
+    /// The data rate of the track, in bits per second.
+    pub data_rate: u32,
+    /// The length of the track in bitcells, or 0 for `MetaSector` resolution tracks.
+    pub bit_length: usize,
+    /// The number of sectors on the track.
+    pub sector_count: usize,
+}
+
+/// The result of a sector read.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FfxReadResult {
+    /// The ID of the sector that was read.
+    pub id: FfxSectorId,
+    /// The length of the sector data. If this exceeds the buffer length, the function returns
+    /// `FFX_STATUS_BUFFER_TOO_SMALL` and no data is copied.
+    pub data_len: usize,
+    /// A combination of the `FFX_SECTOR_*` flags.
+    pub flags: u32,
+}
+
+/// An opaque handle to a disk image.
+pub struct FfxImage {
+    disk: DiskImage,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(msg: impl ToString) {
+    // Interior NUL bytes would truncate the message, so replace them.
+    let msg = CString::new(msg.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+}
+
+/// An error returned from the body of an exported function.
+struct FfxError {
+    status: FfxStatus,
+    msg:    String,
+}
+
+impl FfxError {
+    fn new(status: FfxStatus, msg: impl ToString) -> Self {
+        FfxError {
+            status,
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<DiskImageError> for FfxError {
+    fn from(err: DiskImageError) -> Self {
+        let status = match err {
+            DiskImageError::IoError(_) | DiskImageError::FsError => FfxStatus::Io,
+            DiskImageError::UnknownFormat | DiskImageError::UnsupportedFormat | DiskImageError::FormatMismatch => {
+                FfxStatus::UnsupportedFormat
+            }
+            DiskImageError::SeekError => FfxStatus::Seek,
+            DiskImageError::IdError => FfxStatus::SectorNotFound,
+            DiskImageError::WriteProtectError => FfxStatus::WriteProtected,
+            DiskImageError::ParameterError => FfxStatus::InvalidArgument,
+            _ => FfxStatus::Image,
+        };
+        FfxError::new(status, err)
+    }
+}
+
+/// Run the body of an exported function, recording any error or panic for [ffx_last_error].
+fn ffi_call(name: &str, f: impl FnOnce() -> Result<(), FfxError>) -> FfxStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => FfxStatus::Ok,
+        Ok(Err(e)) => {
+            log::debug!("{}(): {}", name, e.msg);
+            set_last_error(e.msg);
+            e.status
+        }
+        Err(_) => {
+            log::error!("{}(): Panic caught at FFI boundary", name);
+            set_last_error(format!("{}: fluxfox panicked", name));
+            FfxStatus::Panic
+        }
+    }
+}
+
+fn null_error(arg: &str) -> FfxError {
+    FfxError::new(FfxStatus::NullPointer, format!("Argument '{}' is null", arg))
+}
+
+/// Convert a C string argument to a path.
+unsafe fn path_arg(path: *const c_char, arg: &str) -> Result<PathBuf, FfxError> {
+    if path.is_null() {
+        return Err(null_error(arg));
+    }
+    CStr::from_ptr(path).to_str().map(PathBuf::from).map_err(|_| {
+        FfxError::new(
+            FfxStatus::InvalidArgument,
+            format!("Argument '{}' is not valid UTF-8", arg),
+        )
+    })
+}
+
+unsafe fn image_ref<'a>(image: *const FfxImage) -> Result<&'a DiskImage, FfxError> {
+    image.as_ref().map(|i| &i.disk).ok_or_else(|| null_error("image"))
+}
+
+unsafe fn image_mut<'a>(image: *mut FfxImage) -> Result<&'a mut DiskImage, FfxError> {
+    image.as_mut().map(|i| &mut i.disk).ok_or_else(|| null_error("image"))
+}
+
+unsafe fn out_ref<'a, T>(out: *mut T, arg: &str) -> Result<&'a mut T, FfxError> {
+    out.as_mut().ok_or_else(|| null_error(arg))
+}
+
+fn store_image(out_image: &mut *mut FfxImage, disk: DiskImage) {
+    *out_image = Box::into_raw(Box::new(FfxImage { disk }));
+}
+
+impl From<DiskChsn> for FfxSectorId {
+    fn from(id: DiskChsn) -> Self {
+        FfxSectorId {
+            c: id.c(),
+            h: id.h(),
+            s: id.s(),
+            n: id.n(),
+        }
+    }
+}
+
+impl From<FfxSectorId> for DiskChsnQuery {
+    fn from(id: FfxSectorId) -> Self {
+        DiskChsnQuery::new(id.c, id.h, id.s, (id.n != FFX_SECTOR_SIZE_ANY).then_some(id.n))
+    }
+}
+
+/// Return the version of the fluxfox_ffi library as a NUL-terminated string. The string is
+/// static and must not be freed.
+#[no_mangle]
+pub extern "C" fn ffx_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+/// Return the version of the C ABI, [FFX_ABI_VERSION]. A caller should check this matches the
+/// `FFX_ABI_VERSION` of the header it was compiled against.
+#[no_mangle]
+pub extern "C" fn ffx_abi_version() -> u32 {
+    FFX_ABI_VERSION
+}
+
+/// Return a message describing the last error on the calling thread, or null if no error has
+/// occurred. The string is owned by fluxfox and remains valid until the next failing call on
+/// the same thread.
+#[no_mangle]
+pub extern "C" fn ffx_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |msg| msg.as_ptr()))
+}
+
+/// Load a disk image from a file. The format is detected from the file's contents and
+/// extension. On success, `*out_image` receives a handle that must be freed with
+/// [ffx_image_free].
+///
+/// # Safety
+/// `path` must be a NUL-terminated UTF-8 string. `out_image` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ffx_image_load(path: *const c_char, out_image: *mut *mut FfxImage) -> FfxStatus {
+    ffi_call("ffx_image_load", || {
+        let out_image = out_ref(out_image, "out_image")?;
+        let path = path_arg(path, "path")?;
+        let disk = DiskImage::load_from_file(&path, None, None)?;
+        store_image(out_image, disk);
+        Ok(())
+    })
+}
+
+/// Load a disk image from memory. `file_name` is optional, and may be null; when supplied, its
+/// extension helps detect formats that have no signature, such as raw sector images. The data
+/// is copied, and need not outlive the call. On success, `*out_image` receives a handle that
+/// must be freed with [ffx_image_free].
+///
+/// # Safety
+/// `data` must point to `len` readable bytes. `file_name` must be null or a NUL-terminated
+/// UTF-8 string. `out_image` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ffx_image_load_memory(
+    data: *const u8,
+    len: usize,
+    file_name: *const c_char,
+    out_image: *mut *mut FfxImage,
+) -> FfxStatus {
+    ffi_call("ffx_image_load_memory", || {
+        let out_image = out_ref(out_image, "out_image")?;
+        if data.is_null() {
+            return Err(null_error("data"));
+        }
+        let file_name = match file_name.is_null() {
+            true => None,
+            false => Some(path_arg(file_name, "file_name")?),
+        };
+        let mut cursor = Cursor::new(slice::from_raw_parts(data, len).to_vec());
+        let disk = DiskImage::load(&mut cursor, file_name.as_deref(), None, None)?;
+        store_image(out_image, disk);
+        Ok(())
+    })
+}
+
+/// Free a disk image handle. Passing null does nothing.
+///
+/// # Safety
+/// `image` must be null or a handle returned by fluxfox that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ffx_image_free(image: *mut FfxImage) {
+    if !image.is_null() {
+        drop(Box::from_raw(image));
+    }
+}
+
+/// Get the number of cylinders and heads of a disk image.
+///
+/// # Safety
+/// `image` must be a valid handle. `out_cylinders` and `out_heads` must be valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn ffx_image_geometry(
+    image: *const FfxImage,
+    out_cylinders: *mut u16,
+    out_heads: *mut u8,
+) -> FfxStatus {
+    ffi_call("ffx_image_geometry", || {
+        let disk = image_ref(image)?;
+        let out_cylinders = out_ref(out_cylinders, "out_cylinders")?;
+        let out_heads = out_ref(out_heads, "out_heads")?;
+        let geometry = disk.geometry();
+        *out_cylinders = geometry.c();
+        *out_heads = geometry.h();
+        Ok(())
+    })
+}
+
+/// Get the number of tracks in a disk image. Tracks are numbered from 0 in cylinder order,
+/// with the tracks of each head in turn for each cylinder.
+///
+/// # Safety
+/// `image` must be a valid handle. `out_count` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ffx_image_track_count(image: *const FfxImage, out_count: *mut usize) -> FfxStatus {
+    ffi_call("ffx_image_track_count", || {
+        let disk = image_ref(image)?;
+        *out_ref(out_count, "out_count")? = disk.track_ch_iter().count();
+        Ok(())
+    })
+}
+
+/// Get information about the track at `index`. See [ffx_image_track_count].
+///
+/// # Safety
+/// `image` must be a valid handle. `out_info` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ffx_image_track_info(
+    image: *const FfxImage,
+    index: usize,
+    out_info: *mut FfxTrackInfo,
+) -> FfxStatus {
+    ffi_call("ffx_image_track_info", || {
+        let disk = image_ref(image)?;
+        let out_info = out_ref(out_info, "out_info")?;
+        let (ch, track) = disk
+            .iter_tracks()
+            .nth(index)
+            .ok_or_else(|| FfxError::new(FfxStatus::Seek, format!("Track index {} out of range", index)))?;
+
+        let info = track.info();
+        *out_info = FfxTrackInfo {
+            cylinder: ch.c(),
+            head: ch.h(),
+            resolution: match info.resolution {
+                TrackDataResolution::MetaSector => FfxResolution::MetaSector,
+                TrackDataResolution::BitStream => FfxResolution::BitStream,
+                TrackDataResolution::FluxStream => FfxResolution::FluxStream,
+            },
+            encoding: match info.encoding {
+                TrackDataEncoding::Fm => FfxEncoding::Fm,
+                TrackDataEncoding::Mfm => FfxEncoding::Mfm,
+                TrackDataEncoding::Gcr => FfxEncoding::Gcr,
+            },
+            data_rate: u32::from(info.data_rate),
+            bit_length: info.bit_length,
+            sector_count: info.sector_ct,
+        };
+        Ok(())
+    })
+}
+
+/// List the IDs of the sectors on a track, in physical order. The IDs are written to `out_ids`,
+/// which has room for `capacity` IDs, and `*out_count` receives the number of sectors on the
+/// track. If `capacity` is too small, `FFX_STATUS_BUFFER_TOO_SMALL` is returned and no IDs are
+/// written. Pass a null `out_ids` to query the count alone.
+///
+/// # Safety
+/// `image` must be a valid handle. `out_ids` must be null or point to `capacity` writable
+/// `FfxSectorId`s. `out_count` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ffx_image_sector_ids(
+    image: *const FfxImage,
+    cylinder: u16,
+    head: u8,
+    out_ids: *mut FfxSectorId,
+    capacity: usize,
+    out_count: *mut usize,
+) -> FfxStatus {
+    ffi_call("ffx_image_sector_ids", || {
+        let disk = image_ref(image)?;
+        let out_count = out_ref(out_count, "out_count")?;
+        let ch = DiskCh::new(cylinder, head);
+        let track = disk
+            .track(ch)
+            .ok_or_else(|| FfxError::new(FfxStatus::Seek, format!("Track {} not found", ch)))?;
+
+        let ids: Vec<FfxSectorId> = track
+            .sector_list()
+            .iter()
+            .filter(|s| !s.orphan)
+            .map(|s| s.chsn.into())
+            .collect();
+        *out_count = ids.len();
+        if out_ids.is_null() {
+            return Ok(());
+        }
+        if capacity < ids.len() {
+            return Err(FfxError::new(
+                FfxStatus::BufferTooSmall,
+                format!("Track {} has {} sectors, capacity is {}", ch, ids.len(), capacity),
+            ));
+        }
+        slice::from_raw_parts_mut(out_ids, ids.len()).copy_from_slice(&ids);
+        Ok(())
+    })
+}
+
+/// Read the sector with the specified ID from the track at `cylinder` and `head`. The sector
+/// data is copied to `buf`, and `*out_result` receives the ID of the sector read, the length of
+/// its data and its status flags. A sector with a CRC error is read successfully; check the
+/// flags of the result.
+///
+/// # Safety
+/// `image` must be a valid handle. `buf` must point to `buf_len` writable bytes. `out_result`
+/// must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn ffx_image_read_sector(
+    image: *const FfxImage,
+    cylinder: u16,
+    head: u8,
+    id: FfxSectorId,
+    buf: *mut u8,
+    buf_len: usize,
+    out_result: *mut FfxReadResult,
+) -> FfxStatus {
+    ffi_call("ffx_image_read_sector", || {
+        let disk = image_ref(image)?;
+        let out_result = out_ref(out_result, "out_result")?;
+        if buf.is_null() {
+            return Err(null_error("buf"));
+        }
+        let ch = DiskCh::new(cylinder, head);
+        let result = disk.read_sector(ch, id.into(), None, None, RwScope::DataOnly, false)?;
+
+        let flags = [
+            (result.deleted_mark, FFX_SECTOR_DELETED),
+            (result.address_crc_error, FFX_SECTOR_ADDRESS_CRC_ERROR),
+            (result.data_crc_error, FFX_SECTOR_DATA_CRC_ERROR),
+            (result.no_dam, FFX_SECTOR_NO_DAM),
+            (result.wrong_cylinder, FFX_SECTOR_WRONG_CYLINDER),
+            (result.bad_cylinder, FFX_SECTOR_BAD_CYLINDER),
+            (result.wrong_head, FFX_SECTOR_WRONG_HEAD),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |flags, (_, flag)| flags | flag);
+
+        let data = &result.read_buf[result.data_range.clone()];
+        *out_result = FfxReadResult {
+            id: result.id_chsn.map(FfxSectorId::from).unwrap_or_default(),
+            data_len: data.len(),
+            flags,
+        };
+        if result.not_found {
+            return Err(FfxError::new(
+                FfxStatus::SectorNotFound,
+                format!("Sector {} not found on track {}", DiskChsnQuery::from(id), ch),
+            ));
+        }
+        if buf_len < data.len() {
+            return Err(FfxError::new(
+                FfxStatus::BufferTooSmall,
+                format!("Sector data is {} bytes, buffer is {}", data.len(), buf_len),
+            ));
+        }
+        slice::from_raw_parts_mut(buf, data.len()).copy_from_slice(data);
+        Ok(())
+    })
+}
+
+/// Write `len` bytes of data to the sector with the specified ID on the track at `cylinder` and
+/// `head`. If `deleted` is true, the sector is written with a deleted data address mark.
+///
+/// # Safety
+/// `image` must be a valid handle. `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn ffx_image_write_sector(
+    image: *mut FfxImage,
+    cylinder: u16,
+    head: u8,
+    id: FfxSectorId,
+    data: *const u8,
+    len: usize,
+    deleted: bool,
+) -> FfxStatus {
+    ffi_call("ffx_image_write_sector", || {
+        let disk = image_mut(image)?;
+        if data.is_null() {
+            return Err(null_error("data"));
+        }
+        let ch = DiskCh::new(cylinder, head);
+        let data = slice::from_raw_parts(data, len);
+        let result = disk.write_sector(ch, id.into(), None, data, RwScope::DataOnly, deleted, false)?;
+        if result.not_found {
+            return Err(FfxError::new(
+                FfxStatus::SectorNotFound,
+                format!("Sector {} not found on track {}", DiskChsnQuery::from(id), ch),
+            ));
+        }
+        Ok(())
+    })
+}
+
+/// Save a disk image to a file. The file format is chosen from the extension of `path`.
+///
+/// # Safety
+/// `image` must be a valid handle. `path` must be a NUL-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ffx_image_save(image: *mut FfxImage, path: *const c_char) -> FfxStatus {
+    ffi_call("ffx_image_save", || {
+        let disk = image_mut(image)?;
+        let path = path_arg(path, "path")?;
+        let format = path
+            .extension()
+            .and_then(|ext| format_from_ext(&ext.to_string_lossy()))
+            .ok_or_else(|| {
+                FfxError::new(
+                    FfxStatus::UnsupportedFormat,
+                    format!("No image format for file extension of {}", path.display()),
+                )
+            })?;
+
+        ImageWriter::new(disk).with_format(format).with_path(path).write()?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn last_error() -> String {
+        let msg = ffx_last_error();
+        assert!(!msg.is_null());
+        unsafe { CStr::from_ptr(msg) }.to_string_lossy().into_owned()
+    }
+
+    /// Load the 360K ImageDisk image from the fluxfox test suite. This loads as MetaSector tracks, which
+    /// support writing sectors.
+    fn load_test_image() -> *mut FfxImage {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/images/sector_test/sector_test_360k.imd");
+        let data = std::fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+        let file_name = CString::new("sector_test_360k.imd").unwrap();
+
+        let mut image = ptr::null_mut();
+        let status = unsafe { ffx_image_load_memory(data.as_ptr(), data.len(), file_name.as_ptr(), &mut image) };
+        assert_eq!(status, FfxStatus::Ok);
+        assert!(!image.is_null());
+        image
+    }
+
+    #[test]
+    fn test_null_pointers() {
+        let path = CString::new("image.img").unwrap();
+        let mut image = ptr::null_mut();
+        unsafe {
+            assert_eq!(ffx_image_load(ptr::null(), &mut image), FfxStatus::NullPointer);
+            assert!(last_error().contains("'path'"));
+            assert_eq!(ffx_image_load(path.as_ptr(), ptr::null_mut()), FfxStatus::NullPointer);
+            assert_eq!(
+                ffx_image_load_memory(ptr::null(), 0, ptr::null(), &mut image),
+                FfxStatus::NullPointer
+            );
+            assert!(image.is_null());
+
+            let (mut cylinders, mut heads, mut count) = (0, 0, 0);
+            assert_eq!(
+                ffx_image_geometry(ptr::null(), &mut cylinders, &mut heads),
+                FfxStatus::NullPointer
+            );
+            assert!(last_error().contains("'image'"));
+            assert_eq!(ffx_image_track_count(ptr::null(), &mut count), FfxStatus::NullPointer);
+            assert_eq!(ffx_image_save(ptr::null_mut(), path.as_ptr()), FfxStatus::NullPointer);
+            ffx_image_free(ptr::null_mut());
+
+            let image = load_test_image();
+            assert_eq!(
+                ffx_image_geometry(image, ptr::null_mut(), &mut heads),
+                FfxStatus::NullPointer
+            );
+            let mut result = FfxReadResult::default();
+            let id = FfxSectorId { c: 0, h: 0, s: 1, n: 2 };
+            assert_eq!(
+                ffx_image_read_sector(image, 0, 0, id, ptr::null_mut(), 0, &mut result),
+                FfxStatus::NullPointer
+            );
+            assert_eq!(
+                ffx_image_write_sector(image, 0, 0, id, ptr::null(), 0, false),
+                FfxStatus::NullPointer
+            );
+            ffx_image_free(image);
+        }
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let mut image = ptr::null_mut();
+        unsafe {
+            // A path that is not valid UTF-8.
+            let path = CString::new(vec![b'a', 0xFF, b'.', b'i', b'm', b'g']).unwrap();
+            assert_eq!(ffx_image_load(path.as_ptr(), &mut image), FfxStatus::InvalidArgument);
+            assert!(last_error().contains("UTF-8"));
+
+            let path = CString::new("does_not_exist.img").unwrap();
+            assert_ne!(ffx_image_load(path.as_ptr(), &mut image), FfxStatus::Ok);
+            assert!(image.is_null());
+
+            let data = [0u8; 100];
+            assert_eq!(
+                ffx_image_load_memory(data.as_ptr(), data.len(), ptr::null(), &mut image),
+                FfxStatus::UnsupportedFormat
+            );
+            assert!(image.is_null());
+        }
+    }
+
+    #[test]
+    fn test_read_write() {
+        let image = load_test_image();
+        unsafe {
+            let (mut cylinders, mut heads, mut count) = (0, 0, 0);
+            assert_eq!(ffx_image_geometry(image, &mut cylinders, &mut heads), FfxStatus::Ok);
+            assert_eq!((cylinders, heads), (40, 2));
+            assert_eq!(ffx_image_track_count(image, &mut count), FfxStatus::Ok);
+            assert_eq!(count, 80);
+
+            let mut info = std::mem::MaybeUninit::<FfxTrackInfo>::uninit();
+            assert_eq!(ffx_image_track_info(image, 3, info.as_mut_ptr()), FfxStatus::Ok);
+            let info = info.assume_init();
+            assert_eq!((info.cylinder, info.head, info.sector_count), (1, 1, 9));
+            let mut info = std::mem::MaybeUninit::<FfxTrackInfo>::uninit();
+            assert_eq!(ffx_image_track_info(image, 80, info.as_mut_ptr()), FfxStatus::Seek);
+
+            // Query the sector count, then list the IDs.
+            assert_eq!(
+                ffx_image_sector_ids(image, 0, 0, ptr::null_mut(), 0, &mut count),
+                FfxStatus::Ok
+            );
+            assert_eq!(count, 9);
+            let mut ids = vec![FfxSectorId::default(); count];
+            assert_eq!(
+                ffx_image_sector_ids(image, 0, 0, ids.as_mut_ptr(), 1, &mut count),
+                FfxStatus::BufferTooSmall
+            );
+            assert_eq!(
+                ffx_image_sector_ids(image, 0, 0, ids.as_mut_ptr(), ids.len(), &mut count),
+                FfxStatus::Ok
+            );
+            assert!(ids.iter().all(|id| id.c == 0 && id.h == 0 && id.n == 2));
+            assert_eq!(
+                ffx_image_sector_ids(image, 40, 0, ptr::null_mut(), 0, &mut count),
+                FfxStatus::Seek
+            );
+
+            // Write a sector, and read it back with a wildcard size.
+            let data = [0xA5u8; 512];
+            let id = FfxSectorId { c: 2, h: 1, s: 5, n: 2 };
+            assert_eq!(
+                ffx_image_write_sector(image, 2, 1, id, data.as_ptr(), data.len(), false),
+                FfxStatus::Ok
+            );
+            let mut buf = [0u8; 1024];
+            let mut result = FfxReadResult::default();
+            let query = FfxSectorId {
+                n: FFX_SECTOR_SIZE_ANY,
+                ..id
+            };
+            assert_eq!(
+                ffx_image_read_sector(image, 2, 1, query, buf.as_mut_ptr(), buf.len(), &mut result),
+                FfxStatus::Ok
+            );
+            assert_eq!((result.id, result.data_len, result.flags), (id, 512, 0));
+            assert_eq!(buf[..512], data);
+
+            // A short buffer reports the required length without copying.
+            let mut short = [0u8; 128];
+            assert_eq!(
+                ffx_image_read_sector(image, 2, 1, id, short.as_mut_ptr(), short.len(), &mut result),
+                FfxStatus::BufferTooSmall
+            );
+            assert_eq!(result.data_len, 512);
+            assert_eq!(short, [0; 128]);
+
+            let missing = FfxSectorId { s: 20, ..id };
+            assert_eq!(
+                ffx_image_read_sector(image, 2, 1, missing, buf.as_mut_ptr(), buf.len(), &mut result),
+                FfxStatus::SectorNotFound
+            );
+            assert_eq!(
+                ffx_image_write_sector(image, 2, 1, missing, data.as_ptr(), data.len(), false),
+                FfxStatus::SectorNotFound
+            );
+
+            let path = CString::new("image.unknown").unwrap();
+            assert_eq!(ffx_image_save(image, path.as_ptr()), FfxStatus::UnsupportedFormat);
+            ffx_image_free(image);
+        }
+    }
+}
@@ -31,6 +31,7 @@ mod document;
 mod overlays;
 mod render_display_list;
 mod render_elements;
+mod render_legend;
 mod render_overlays;
 mod renderer;
 mod styles;
@@ -31,18 +31,26 @@ use fluxfox::{
     track_schema::GenericTrackElement,
     visualization::{
         prelude::VizRect,
-        types::display_list::{VizDataSliceDisplayList, VizElementDisplayList},
+        types::{
+            display_list::{VizDataSliceDisplayList, VizElementDisplayList},
+            shapes::{VizElementFlags, VizShape},
+        },
     },
     FoxHashMap,
 };
 use svg::node::element::Group;
 
+/// Render a metadata display list as an SVG group of vector paths.
+///
+/// If `boundary_style` is provided, the radial edges of each sector element are additionally
+/// stroked with that style, in a group drawn on top of the elements.
 pub fn render_display_list_as_svg(
     viewbox: VizRect<f32>,
     angle: f32,
     display_list: &VizElementDisplayList,
     track_style: &ElementStyle,
     element_styles: &FoxHashMap<GenericTrackElement, ElementStyle>,
+    boundary_style: Option<&ElementStyle>,
 ) -> Group {
     let center = viewbox.center();
     let angle_degrees = angle.to_degrees();
@@ -65,6 +73,19 @@ pub fn render_display_list_as_svg(
         }
     }
 
+    if let Some(boundary_style) = boundary_style {
+        let mut boundary_group = Group::new();
+        for element in display_list.iter() {
+            if element.flags.contains(VizElementFlags::TRACK) {
+                continue;
+            }
+            if let VizShape::Sector(sector) = &element.shape {
+                boundary_group = boundary_group.add(svg_render_sector_boundaries(sector, boundary_style));
+            }
+        }
+        group = group.add(boundary_group);
+    }
+
     group
 }
 
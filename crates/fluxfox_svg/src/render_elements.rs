@@ -123,6 +123,20 @@ pub fn svg_render_element(
         _ => element_styles.get(&element.info.element_type).unwrap_or(&default_style),
     };
 
+    // Track-level arcs represent a ring segment as wide as the track. Render them as a stroke of the
+    // track width rather than as a filled path, so they remain true rings at any zoom level.
+    if element.flags.contains(VizElementFlags::TRACK) {
+        if let VizShape::CubicArc(arc, width) = &element.shape {
+            return RenderNode::Path(
+                Path::new()
+                    .set("d", svg_render_arc(data, arc, false))
+                    .set("fill", "none")
+                    .set("stroke", viz_color_to_value(style.fill))
+                    .set("stroke-width", *width),
+            );
+        }
+    }
+
     match element.shape {
         VizShape::CubicArc(_, _) | VizShape::QuadraticArc(_, _) | VizShape::Sector(_) => {
             data = svg_render_shape(data, &element.shape);
@@ -151,6 +165,21 @@ pub fn svg_render_element(
     )
 }
 
+/// Render the two radial edges of a sector as an open SVG path, marking the sector's boundaries.
+pub fn svg_render_sector_boundaries(sector: &VizSector, style: &ElementStyle) -> Path {
+    let data = Data::new()
+        .move_to((sector.inner.end.x, sector.inner.end.y))
+        .line_to((sector.outer.start.x, sector.outer.start.y))
+        .move_to((sector.outer.end.x, sector.outer.end.y))
+        .line_to((sector.inner.start.x, sector.inner.start.y));
+
+    Path::new()
+        .set("d", data)
+        .set("fill", "none")
+        .set("stroke", viz_color_to_value(style.stroke))
+        .set("stroke-width", style.stroke_width)
+}
+
 /// Render a single data slice as an SVG path. Unlike a sector element, a data slice is a single
 /// arc with a stroke rendered at the track width.
 pub fn svg_render_data_slice(slice: &VizDataSlice, stroke: f32) -> Path {
@@ -0,0 +1,81 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+
+//! Module to render a legend produced by `fluxfox::visualization::legend::render_legend()` as an
+//! SVG group, to be composed on top of a visualization.
+
+use crate::render_elements::viz_color_to_value;
+use fluxfox::visualization::legend::{VizLegendElement, VizTextAnchor};
+use svg::node::element::{Group, Line, Rectangle, Text};
+
+pub(crate) fn render_legend_as_svg(elements: &[VizLegendElement]) -> Group {
+    let mut group = Group::new();
+
+    for element in elements {
+        match element {
+            VizLegendElement::Rect(rect, color) => {
+                group = group.add(
+                    Rectangle::new()
+                        .set("x", rect.top_left.x)
+                        .set("y", rect.top_left.y)
+                        .set("width", rect.width())
+                        .set("height", rect.height())
+                        .set("fill", viz_color_to_value(*color)),
+                );
+            }
+            VizLegendElement::Line(line, stroke, color) => {
+                group = group.add(
+                    Line::new()
+                        .set("x1", line.start.x)
+                        .set("y1", line.start.y)
+                        .set("x2", line.end.x)
+                        .set("y2", line.end.y)
+                        .set("stroke", viz_color_to_value(*color))
+                        .set("stroke-width", *stroke),
+                );
+            }
+            VizLegendElement::Text(text) => {
+                let anchor = match text.anchor {
+                    VizTextAnchor::Start => "start",
+                    VizTextAnchor::Middle => "middle",
+                    VizTextAnchor::End => "end",
+                };
+                group = group.add(
+                    Text::new(text.text.clone())
+                        .set("x", text.pos.x)
+                        .set("y", text.pos.y)
+                        .set("font-size", text.size)
+                        .set("font-family", "sans-serif")
+                        .set("text-anchor", anchor)
+                        .set("fill", viz_color_to_value(text.color)),
+                );
+            }
+        }
+    }
+
+    group
+}
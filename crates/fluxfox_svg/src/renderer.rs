@@ -40,6 +40,7 @@ use crate::{
     overlays::Overlay,
     prelude::{DocumentSide, RenderedDocument},
     render_elements::viz_color_to_value,
+    render_legend::render_legend_as_svg,
     render_overlays::svg_to_group,
 };
 use svg::node::element::Group;
@@ -102,15 +103,20 @@ pub struct SvgRenderer {
     // Default style for track elements - a solid ring that is the background of each track.
     // Default is transparent fill and 0 stroke.
     track_style: ElementStyle,
+    // Style used to stroke the radial boundaries of each sector, if any. Default is None.
+    sector_boundary_style: Option<ElementStyle>,
     // Internal state
     common_params: CommonVizParams,
 
     overlay: Option<Overlay>,
     overlay_style: ElementStyle,
+    // Parameters for the legend to render on top of the visualization, if any.
+    legend: Option<RenderLegendParams>,
 
     data_groups: [Option<Group>; 2],
     metadata_groups: [Option<Group>; 2],
     overlay_groups: [Option<Group>; 2],
+    legend_groups: [Option<Group>; 2],
 
     composited_group: Option<Group>,
     export_path: Option<String>,
@@ -298,6 +304,15 @@ impl SvgRenderer {
         self
     }
 
+    /// Render a legend on top of the visualization, using the specified parameters. The color key
+    /// and data rate annotation are rendered for the first side only; each side receives its own
+    /// scale bar. If no palette is specified, the fill colors of the current element styles will
+    /// be used.
+    pub fn with_legend(mut self, params: RenderLegendParams) -> Self {
+        self.legend = Some(params);
+        self
+    }
+
     /// Specify the inner and outer radius ratios, as a fraction of the side view box width.
     pub fn with_radius_ratios(mut self, inner: f32, outer: f32) -> Self {
         self.common_params.min_radius_ratio = inner;
@@ -306,18 +321,28 @@ impl SvgRenderer {
     }
 
     /// Override the default styles with a custom set of styles. This must be a hash map of
-    /// `GenericTrackElement` to `ElementStyle`.
-    pub fn with_styles(mut self, _styles: FoxHashMap<GenericTrackElement, ElementStyle>) -> Self {
+    /// `GenericTrackElement` to `ElementStyle`. Elements missing from `styles` keep their
+    /// default style.
+    pub fn with_styles(mut self, styles: FoxHashMap<GenericTrackElement, ElementStyle>) -> Self {
+        self.element_styles.extend(styles);
         self
     }
 
     /// Override the default track style. Useful if you want to render metadata on its own,
-    /// with some sort of background.
+    /// with some sort of background. Each track is rendered as a ring the width of the track,
+    /// filled with the style's fill color.
     pub fn with_track_style(mut self, style: ElementStyle) -> Self {
         self.track_style = style;
         self
     }
 
+    /// Stroke the radial boundaries of each sector on the metadata layer with the stroke color
+    /// and width of the specified style. Pass None to disable (the default).
+    pub fn with_sector_boundaries(mut self, style: Option<ElementStyle>) -> Self {
+        self.sector_boundary_style = style;
+        self
+    }
+
     fn render_data_group(&mut self, disk: &DiskImage, side: u8) -> Result<Group, String> {
         log::trace!("Vectorizing data group for side {}...", side);
         let data_params = RenderTrackDataParams {
@@ -380,13 +405,8 @@ impl SvgRenderer {
             draw_sector_lookup: false,
         };
 
-        let display_list = match vectorize_disk_elements_by_quadrants(disk, &self.common_params, &metadata_params) {
-            Ok(display_list) => display_list,
-            Err(e) => {
-                eprintln!("Error rendering metadata: {}", e);
-                std::process::exit(1);
-            }
-        };
+        let display_list = vectorize_disk_elements_by_quadrants(disk, &self.common_params, &metadata_params)
+            .map_err(|e| format!("Failed to vectorize metadata for side {}: {}", side, e))?;
 
         let mut group = render_display_list_as_svg(
             self.side_view_box.clone(),
@@ -394,6 +414,7 @@ impl SvgRenderer {
             &display_list,
             &self.track_style,
             &self.element_styles,
+            self.sector_boundary_style.as_ref(),
         );
 
         // Directly apply our blend mode to this group - blend modes cannot be inherited!
@@ -416,6 +437,46 @@ impl SvgRenderer {
         Ok(group)
     }
 
+    fn render_legend_group(&mut self, disk: &DiskImage, side: u8, first: bool) -> Result<Group, String> {
+        log::trace!("Rendering legend group for side {}...", side);
+        let Some(legend) = &self.legend
+        else {
+            return Err("No legend parameters specified.".to_string());
+        };
+
+        let mut params = legend.clone();
+        params.side = side;
+        if first {
+            if params.palette.is_none() {
+                params.palette = Some(
+                    self.element_styles
+                        .iter()
+                        .map(|(element, style)| (*element, style.fill))
+                        .collect(),
+                );
+            }
+        }
+        else {
+            params.palette = None;
+            params.data_rate = false;
+        }
+
+        let elements = render_legend(disk, &self.common_params, &params)
+            .map_err(|e| format!("Failed to render legend for side {}: {}", side, e))?;
+        let mut group = render_legend_as_svg(&elements);
+
+        // Move the scale bar over with the rest of this side's groups if we're rendering
+        // side-by-side.
+        if (side > 0) && (self.total_sides_to_render > 1) && self.render_side_by_side {
+            group = group.set(
+                "transform",
+                format!("translate({:.3}, 0)", self.side_spacing + self.side_view_box.width()),
+            );
+        }
+
+        Ok(group)
+    }
+
     pub fn render(mut self, disk: &DiskImage) -> Result<Self, String> {
         if self.build_error {
             return Err(self.error_message.unwrap_or("Unknown error.".to_string()));
@@ -469,6 +530,12 @@ impl SvgRenderer {
                     &self.overlay_style,
                 )?);
             }
+
+            // Render legend group
+            if self.legend.is_some() {
+                log::trace!("render(): Rendering legend group for side {}", side);
+                self.legend_groups[side as usize] = Some(self.render_legend_group(disk, side, si == 0)?);
+            }
         }
 
         Ok(self)
@@ -570,6 +637,20 @@ impl SvgRenderer {
                 }
             };
 
+            let legend_group = {
+                let legend_sides: Vec<Group> = self.legend_groups.iter_mut().filter_map(|g| g.take()).collect();
+                if legend_sides.is_empty() {
+                    None
+                }
+                else {
+                    let mut group = Group::new();
+                    for side_group in legend_sides {
+                        group = group.add(side_group);
+                    }
+                    Some(group)
+                }
+            };
+
             log::trace!(
                 "create_documents(): Got data layer?: {} Got metadata layer? {}.",
                 data_group.is_some(),
@@ -622,6 +703,9 @@ impl SvgRenderer {
                 if let Some(group) = overlay_group {
                     document = document.add(group);
                 }
+                if let Some(group) = legend_group {
+                    document = document.add(group);
+                }
 
                 output_documents.push(RenderedDocument {
                     side: DocumentSide::Both,
@@ -647,6 +731,9 @@ impl SvgRenderer {
                 if let Some(group) = metadata_group {
                     let mut document = Document::new().set("viewBox", self.global_view_box.to_tuple());
                     document = document.add(group);
+                    if let Some(group) = legend_group {
+                        document = document.add(group);
+                    }
                     output_documents.push(RenderedDocument {
                         side: DocumentSide::Both,
                         layer: DocumentLayer::Metadata,
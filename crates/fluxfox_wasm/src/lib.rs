@@ -0,0 +1,407 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    crates/fluxfox_wasm/src/lib.rs
+
+    JavaScript bindings for fluxfox, built with wasm-bindgen.
+*/
+
+//! JavaScript bindings for fluxfox.
+//!
+//! This crate exposes the core of fluxfox to JavaScript through wasm-bindgen, so that web pages
+//! can load disk images, enumerate their tracks and sectors, extract files and render
+//! visualizations without the egui application. Build it with `wasm-pack build --target web`.
+//!
+//! A disk image is loaded with [JsDiskImage::load], which is exported to JavaScript as
+//! `DiskImage.load()`. Errors are thrown as JavaScript `Error` objects.
+
+use std::{
+    io::Cursor,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use fluxfox::{
+    disk_lock::{NonTrackingDiskLock, NullContext},
+    file_system::{fat::fat_fs::FatFileSystem, FileSystem},
+    prelude::*,
+    visualization::prelude::*,
+};
+use fluxfox_tiny_skia::{
+    render_display_list::render_data_display_list,
+    render_elements::skia_render_display_list,
+    styles::{default_skia_styles, SkiaStyle},
+    tiny_skia::{BlendMode, Color, Paint, Pixmap, PixmapPaint, Transform},
+};
+use wasm_bindgen::prelude::*;
+
+/// The number of angular slices used when rendering the data layer.
+const DATA_SLICES: usize = 1440;
+/// The radius of the innermost track, as a fraction of the radius of the disk.
+const MIN_RADIUS_RATIO: f32 = 0.30;
+
+/// Return the version of the fluxfox_wasm library.
+#[wasm_bindgen]
+pub fn version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+/// Information about a single track.
+#[wasm_bindgen(js_name = TrackInfo)]
+#[derive(Clone, Debug)]
+pub struct JsTrackInfo {
+    /// The physical cylinder of the track.
+    #[wasm_bindgen(readonly)]
+    pub cylinder: u16,
+    /// The physical head of the track.
+    #[wasm_bindgen(readonly)]
+    pub head: u8,
+    resolution: String,
+    encoding: String,
+    /// The data rate of the track, in bits per second.
+    #[wasm_bindgen(readonly, js_name = dataRate)]
+    pub data_rate: u32,
+    /// The length of the track in bitcells, or 0 for sector-based tracks.
+    #[wasm_bindgen(readonly, js_name = bitLength)]
+    pub bit_length: usize,
+    /// The number of sectors on the track.
+    #[wasm_bindgen(readonly, js_name = sectorCount)]
+    pub sector_count: usize,
+}
+
+#[wasm_bindgen(js_class = TrackInfo)]
+impl JsTrackInfo {
+    /// The resolution of the track's data: `MetaSector`, `BitStream` or `FluxStream`.
+    #[wasm_bindgen(getter)]
+    pub fn resolution(&self) -> String {
+        self.resolution.clone()
+    }
+
+    /// The encoding of the track's data: `FM`, `MFM` or `GCR`.
+    #[wasm_bindgen(getter)]
+    pub fn encoding(&self) -> String {
+        self.encoding.clone()
+    }
+}
+
+/// A sector on a track, as listed by [JsDiskImage::sectors].
+#[wasm_bindgen(js_name = SectorInfo)]
+#[derive(Copy, Clone, Debug)]
+pub struct JsSectorInfo {
+    /// The cylinder ID from the sector header.
+    #[wasm_bindgen(readonly)]
+    pub c: u16,
+    /// The head ID from the sector header.
+    #[wasm_bindgen(readonly)]
+    pub h: u8,
+    /// The sector ID from the sector header.
+    #[wasm_bindgen(readonly)]
+    pub s: u8,
+    /// The sector size code. The sector size in bytes is 128 << n.
+    #[wasm_bindgen(readonly)]
+    pub n: u8,
+    /// The sector has a deleted data address mark.
+    #[wasm_bindgen(readonly)]
+    pub deleted: bool,
+    /// The sector header has a CRC error.
+    #[wasm_bindgen(readonly, js_name = addressCrcError)]
+    pub address_crc_error: bool,
+    /// The sector data has a CRC error.
+    #[wasm_bindgen(readonly, js_name = dataCrcError)]
+    pub data_crc_error: bool,
+    /// The sector header has no data following it.
+    #[wasm_bindgen(readonly, js_name = noDam)]
+    pub no_dam: bool,
+}
+
+/// The result of a sector read.
+#[wasm_bindgen(js_name = SectorData)]
+#[derive(Clone, Debug)]
+pub struct JsSectorData {
+    data: Vec<u8>,
+    /// The sector has a deleted data address mark.
+    #[wasm_bindgen(readonly)]
+    pub deleted: bool,
+    /// The sector header has a CRC error.
+    #[wasm_bindgen(readonly, js_name = addressCrcError)]
+    pub address_crc_error: bool,
+    /// The sector data has a CRC error.
+    #[wasm_bindgen(readonly, js_name = dataCrcError)]
+    pub data_crc_error: bool,
+}
+
+#[wasm_bindgen(js_class = SectorData)]
+impl JsSectorData {
+    /// The sector data, as a `Uint8Array`.
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+/// A disk image, exported to JavaScript as `DiskImage`.
+#[wasm_bindgen(js_name = DiskImage)]
+pub struct JsDiskImage {
+    // The image is shared behind a lock so that a file system can be mounted on it.
+    disk: Arc<RwLock<DiskImage>>,
+}
+
+#[wasm_bindgen(js_class = DiskImage)]
+impl JsDiskImage {
+    /// Load a disk image from an `ArrayBuffer` or typed array. The format is detected from the
+    /// image's contents. `fileName` is optional; when supplied, its extension helps detect
+    /// formats that have no signature, such as raw sector images.
+    pub fn load(
+        data: &JsValue,
+        #[wasm_bindgen(js_name = fileName)] file_name: Option<String>,
+    ) -> Result<JsDiskImage, JsError> {
+        let bytes = js_sys::Uint8Array::new(data).to_vec();
+        let mut cursor = Cursor::new(bytes);
+        let file_name = file_name.map(PathBuf::from);
+        let disk = DiskImage::load(&mut cursor, file_name.as_deref(), None, None)?;
+
+        Ok(JsDiskImage {
+            disk: Arc::new(RwLock::new(disk)),
+        })
+    }
+
+    /// The number of cylinders in the image.
+    #[wasm_bindgen(getter)]
+    pub fn cylinders(&self) -> u16 {
+        self.disk().geometry().c()
+    }
+
+    /// The number of heads in the image.
+    #[wasm_bindgen(getter)]
+    pub fn heads(&self) -> u8 {
+        self.disk().heads()
+    }
+
+    /// The name of the file format the image was loaded from.
+    #[wasm_bindgen(getter)]
+    pub fn format(&self) -> Option<String> {
+        self.disk().source_format().map(|format| format.to_string())
+    }
+
+    /// List the tracks of the image, in cylinder order, with the tracks of each head in turn.
+    pub fn tracks(&self) -> Vec<JsTrackInfo> {
+        self.disk()
+            .iter_tracks()
+            .map(|(ch, track)| {
+                let info = track.info();
+                JsTrackInfo {
+                    cylinder: ch.c(),
+                    head: ch.h(),
+                    resolution: format!("{:?}", info.resolution),
+                    encoding: info.encoding.to_string(),
+                    data_rate: u32::from(info.data_rate),
+                    bit_length: info.bit_length,
+                    sector_count: info.sector_ct,
+                }
+            })
+            .collect()
+    }
+
+    /// List the sectors on the track at `cylinder` and `head`, in physical order.
+    pub fn sectors(&self, cylinder: u16, head: u8) -> Result<Vec<JsSectorInfo>, JsError> {
+        let disk = self.disk();
+        let ch = DiskCh::new(cylinder, head);
+        let track = disk
+            .track(ch)
+            .ok_or_else(|| JsError::new(&format!("Track {} not found", ch)))?;
+
+        Ok(track
+            .sector_list()
+            .iter()
+            .filter(|s| !s.orphan)
+            .map(|s| JsSectorInfo {
+                c: s.chsn.c(),
+                h: s.chsn.h(),
+                s: s.chsn.s(),
+                n: s.chsn.n(),
+                deleted: s.attributes.deleted_mark,
+                address_crc_error: s.attributes.address_error,
+                data_crc_error: s.attributes.data_error,
+                no_dam: s.attributes.no_dam,
+            })
+            .collect())
+    }
+
+    /// Read the sector with ID `sector` from the track at `cylinder` and `head`. The cylinder
+    /// and head IDs of the sector are not checked. If `n` is supplied, the sector's size code
+    /// must also match. A sector with a CRC error is read successfully; check the flags of the
+    /// result.
+    #[wasm_bindgen(js_name = readSector)]
+    pub fn read_sector(&self, cylinder: u16, head: u8, sector: u8, n: Option<u8>) -> Result<JsSectorData, JsError> {
+        let ch = DiskCh::new(cylinder, head);
+        let query = DiskChsnQuery::new(None, None, sector, n);
+        let result = self
+            .disk()
+            .read_sector(ch, query, None, None, RwScope::DataOnly, false)?;
+
+        if result.not_found {
+            return Err(JsError::new(&format!("Sector {} not found on track {}", query, ch)));
+        }
+
+        Ok(JsSectorData {
+            data: result.read_buf[result.data_range].to_vec(),
+            deleted: result.deleted_mark,
+            address_crc_error: result.address_crc_error,
+            data_crc_error: result.data_crc_error,
+        })
+    }
+
+    /// List the paths of all files in the image's FAT file system.
+    #[wasm_bindgen(js_name = listFiles)]
+    pub fn list_files(&self) -> Result<Vec<String>, JsError> {
+        let mut fs = self.mount()?;
+        let files = fs.list_all_files();
+        fs.unmount();
+        Ok(files)
+    }
+
+    /// Read the file at `path` from the image's FAT file system.
+    #[wasm_bindgen(js_name = readFile)]
+    pub fn read_file(&self, path: &str) -> Result<Vec<u8>, JsError> {
+        let mut fs = self.mount()?;
+        let result = fs.read_file(path);
+        fs.unmount();
+        Ok(result?)
+    }
+
+    /// Render a visualization of `side` of the disk, returning `size * size` pixels of RGBA data
+    /// suitable for a canvas `ImageData`. The data layer shows the track data, decoded if
+    /// `decode` is true. If `metadata` is true, sectors and other track elements are colored on
+    /// top of the data layer.
+    pub fn render(
+        &self,
+        side: u8,
+        size: u32,
+        decode: bool,
+        metadata: bool,
+    ) -> Result<js_sys::Uint8ClampedArray, JsError> {
+        let disk = self.disk();
+        if !disk.can_visualize() {
+            return Err(JsError::new("Image resolution does not support visualization"));
+        }
+        if side >= disk.heads() {
+            return Err(JsError::new(&format!("Side {} not present in image", side)));
+        }
+        let mut pixmap = Pixmap::new(size, size).ok_or_else(|| JsError::new("Invalid render size"))?;
+
+        let common_params = CommonVizParams {
+            radius: Some(size as f32 / 2.0),
+            max_radius_ratio: 1.0,
+            min_radius_ratio: MIN_RADIUS_RATIO,
+            pos_offset: None,
+            index_angle: 0.0,
+            track_limit: Some(disk.track_ct(side as usize)),
+            pin_last_standard_track: true,
+            track_gap: 0.0,
+            direction: match side {
+                0 => TurningDirection::Clockwise,
+                _ => TurningDirection::CounterClockwise,
+            },
+            ..CommonVizParams::default()
+        };
+
+        let data_params = RenderTrackDataParams {
+            side,
+            decode,
+            slices: DATA_SLICES,
+            ..Default::default()
+        };
+        let display_list = vectorize_disk_data(
+            &disk,
+            &common_params,
+            &data_params,
+            &RenderVectorizationParams::default(),
+        )?;
+        // Disable antialiasing to reduce moiré.
+        let mut paint = Paint {
+            anti_alias: false,
+            ..Default::default()
+        };
+        render_data_display_list(&mut pixmap, &mut paint, common_params.index_angle, &display_list)
+            .map_err(|e| JsError::new(&e))?;
+
+        if metadata {
+            let metadata_params = RenderTrackMetadataParams {
+                quadrant: None,
+                side,
+                geometry: RenderGeometry::Sector,
+                winding: Default::default(),
+                draw_empty_tracks: false,
+                draw_sector_lookup: false,
+            };
+            let display_list = vectorize_disk_elements_by_quadrants(&disk, &common_params, &metadata_params)?;
+
+            let mut meta_pixmap = Pixmap::new(size, size).ok_or_else(|| JsError::new("Invalid render size"))?;
+            meta_pixmap.fill(Color::TRANSPARENT);
+            skia_render_display_list(
+                &mut meta_pixmap,
+                &mut Paint::default(),
+                &Transform::identity(),
+                &display_list,
+                &SkiaStyle::default(),
+                &default_skia_styles(),
+            );
+
+            // Composite the metadata layer over the data layer the same way the egui viewer does.
+            let paint = PixmapPaint {
+                blend_mode: BlendMode::Color,
+                ..Default::default()
+            };
+            pixmap.draw_pixmap(0, 0, meta_pixmap.as_ref(), &paint, Transform::identity(), None);
+        }
+
+        // tiny_skia stores premultiplied alpha, but ImageData expects straight alpha.
+        let rgba: Vec<u8> = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|p| {
+                let c = p.demultiply();
+                [c.red(), c.green(), c.blue(), c.alpha()]
+            })
+            .collect();
+        Ok(js_sys::Uint8ClampedArray::from(rgba.as_slice()))
+    }
+}
+
+impl JsDiskImage {
+    fn disk(&self) -> std::sync::RwLockReadGuard<'_, DiskImage> {
+        // A panic while the lock is held aborts a wasm module, so the lock cannot be poisoned.
+        self.disk.read().unwrap()
+    }
+
+    fn mount(&self) -> Result<FatFileSystem, JsError> {
+        let lock: NonTrackingDiskLock<DiskImage> = self.disk.clone().into();
+        FatFileSystem::mount(lock, NullContext::default(), None).map_err(|e| {
+            log::error!("mount(): Error mounting FAT file system: {}", e);
+            JsError::new(&format!("Error mounting FAT file system: {}", e))
+        })
+    }
+}
@@ -0,0 +1,75 @@
+//! Tests of the JavaScript API. These require a JavaScript runtime; run them with
+//! `wasm-pack test --node crates/fluxfox_wasm`.
+
+use fluxfox_wasm::JsDiskImage;
+use wasm_bindgen_test::*;
+
+/// A sector-based image.
+const IMD_IMAGE: &[u8] = include_bytes!("../../../tests/images/sector_test/sector_test_360k.imd");
+/// A bitstream image, which can be visualized.
+const HFE_IMAGE: &[u8] = include_bytes!("../../../tests/images/sector_test/sector_test_360k.hfe");
+
+fn load(data: &[u8], file_name: &str) -> JsDiskImage {
+    let array = js_sys::Uint8Array::from(data);
+    JsDiskImage::load(&array, Some(file_name.to_string())).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test_load() {
+    let disk = load(IMD_IMAGE, "sector_test_360k.imd");
+    assert_eq!((disk.cylinders(), disk.heads()), (40, 2));
+    assert!(disk.format().is_some());
+
+    let tracks = disk.tracks();
+    assert_eq!(tracks.len(), 80);
+    assert_eq!((tracks[3].cylinder, tracks[3].head), (1, 1));
+    assert_eq!(tracks[0].encoding(), "MFM");
+    assert_eq!(tracks[0].resolution(), "MetaSector");
+
+    // Data that is not a disk image fails to load.
+    let garbage = js_sys::Uint8Array::from(&[0u8; 100][..]);
+    assert!(JsDiskImage::load(&garbage, None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_read_sector() {
+    let disk = load(IMD_IMAGE, "sector_test_360k.imd");
+    let sectors = disk.sectors(0, 0).unwrap();
+    assert_eq!(sectors.len(), 9);
+    assert!(sectors.iter().all(|s| s.n == 2 && !s.data_crc_error));
+    assert!(disk.sectors(40, 0).is_err());
+
+    let sector = disk.read_sector(0, 0, sectors[0].s, None).unwrap();
+    assert_eq!(sector.data().len(), 512);
+    assert!(!sector.data_crc_error);
+    assert!(disk.read_sector(0, 0, sectors[0].s, Some(2)).is_ok());
+
+    // A size code or sector ID that doesn't match is not found.
+    assert!(disk.read_sector(0, 0, sectors[0].s, Some(3)).is_err());
+    assert!(disk.read_sector(0, 0, 20, None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_files() {
+    // The test image has no FAT file system.
+    let disk = load(IMD_IMAGE, "sector_test_360k.imd");
+    assert!(disk.list_files().is_err());
+    assert!(disk.read_file("/README.TXT").is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_render() {
+    let size = 64;
+    let disk = load(HFE_IMAGE, "sector_test_360k.hfe");
+    let pixels = disk.render(0, size, true, true).unwrap();
+    assert_eq!(pixels.length(), size * size * 4);
+    // Rendering draws at least some opaque pixels.
+    assert!(pixels.to_vec().chunks(4).any(|p| p[3] == 0xFF));
+
+    assert!(disk.render(2, size, true, false).is_err());
+    assert!(disk.render(0, 0, true, false).is_err());
+
+    // Sector-based images cannot be visualized.
+    let disk = load(IMD_IMAGE, "sector_test_360k.imd");
+    assert!(disk.render(0, size, true, false).is_err());
+}
@@ -131,7 +131,7 @@ fn main() {
         min_radius_ratio: 1.0,
         pos_offset: None,
         index_angle: opts.angle,
-        track_limit: Some(disk.tracks(0) as usize),
+        track_limit: Some(disk.track_ct(0)),
         pin_last_standard_track: false,
         track_gap: 0.0,
         direction: TurningDirection::Clockwise,
@@ -155,7 +155,7 @@ fn main() {
     //     image_size: (pixmap0.width(), pixmap0.height()),
     //     image_pos: (0, 0),
     //     side: 0,
-    //     track_limit: disk.tracks(0) as usize,
+    //     track_limit: disk.track_ct(0),
     //     min_radius_ratio: opts.hole_ratio.unwrap_or(match opts.applesauce {
     //         false => 0.3, // Good hole ratio for HxC and fluxfox
     //         true => 0.27, // Applesauce has slightly smaller hole
@@ -210,7 +210,7 @@ fn main() {
             if !opts.applesauce {
                 common_params.direction = common_params.direction.opposite();
             }
-            common_params.track_limit = Some(disk.tracks(1) as usize);
+            common_params.track_limit = Some(disk.track_ct(1));
             data_params.side = 1;
             common_params.index_angle = common_params.direction.adjust_angle(opts.angle);
             println!("Rendering side 1...");
@@ -35,7 +35,7 @@ use std::path::PathBuf;
 use bpaf::*;
 use fluxfox::{
     disk_lock::{NonTrackingDiskLock, NullContext},
-    file_system::fat::fat_fs::FatFileSystem,
+    file_system::{fat::fat_fs::FatFileSystem, FileSystem},
     io::Cursor,
     prelude::*,
 };
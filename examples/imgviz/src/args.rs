@@ -57,6 +57,7 @@ pub(crate) struct VizArgs {
     pub(crate) weak: bool,
     pub(crate) errors: bool,
     pub(crate) metadata: bool,
+    pub(crate) sector_boundaries: bool,
     pub(crate) index_hole: bool,
     pub(crate) decode: bool,
     pub(crate) cc: bool,
@@ -143,6 +144,10 @@ pub(crate) fn opts() -> OptionParser<VizArgs> {
 
     let metadata = long("metadata").help("Render metadata").switch();
 
+    let sector_boundaries = long("sector_boundaries")
+        .help("Stroke the boundaries of each sector when rendering metadata (SVG output only)")
+        .switch();
+
     let decode = long("decode").help("Decode data").switch();
 
     let index_hole = long("index_hole").help("Render index hole").switch();
@@ -192,6 +197,7 @@ pub(crate) fn opts() -> OptionParser<VizArgs> {
         weak,
         errors,
         metadata,
+        sector_boundaries,
         index_hole,
         decode,
         cc,
@@ -192,7 +192,7 @@ fn main() {
     }
 
     let image_size = opts.resolution;
-    let track_ct = disk.tracks(0) as usize;
+    let track_ct = disk.track_ct(0);
     log::trace!("Image has {} tracks.", track_ct);
 
     // Determine whether our output format is SVG or PNG. Only do so if `use_svg` is enabled.
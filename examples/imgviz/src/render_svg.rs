@@ -66,6 +66,16 @@ pub(crate) fn render_svg(
             opts.resolution as f32,
         )))
         .with_styles(style_map_to_fluxfox_svg(&style.element_styles))
+        .with_track_style(ElementStyle {
+            fill: opts.track_bg_color.unwrap_or(style.track_style.fill),
+            stroke: VizColor::TRANSPARENT,
+            stroke_width: 0.0,
+        })
+        .with_sector_boundaries(opts.sector_boundaries.then_some(ElementStyle {
+            fill: VizColor::TRANSPARENT,
+            stroke: VizColor::from_rgba8(0, 0, 0, 192),
+            stroke_width: opts.resolution as f32 / 1024.0,
+        }))
         .with_blend_mode(style.blend_mode.into())
         .with_overlay(Overlay::Overlay5_25)
         .with_initial_turning(if opts.cc {
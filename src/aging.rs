@@ -0,0 +1,575 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/aging.rs
+
+    Simulates the degradation of magnetic media over time.
+*/
+
+//! A media aging simulator.
+//!
+//! [DiskImage::age()] degrades an image the way old floppy media degrades: weak regions appear
+//! and grow, the oxide drops out in clusters, and sectors begin to fail their CRC checks. Inner
+//! tracks are more likely to be affected, as they have the highest recording density. The
+//! simulation is seeded, so a given image and [AgingOptions] always produce the same result.
+//!
+//! This is intended to produce test corpora for recovery software and for exercising the error
+//! paths of emulators. The returned [AgingReport] lists every defect that was introduced, so that
+//! the results of recovery can be checked against it.
+//!
+//! `MetaSector` tracks model weak regions with weak bit masks and dropouts with hole masks.
+//! `BitStream` and `FluxStream` tracks are modified at the bitcell level: dropouts erase flux
+//! transitions and are marked weak, as a drive reading a dropout returns noise.
+
+use crate::{
+    bitstream_codec::{mfm::MFM_BYTE_LEN, EncodingVariant},
+    random::SeededRng,
+    track::Track,
+    types::{DiskCh, DiskChsn, RwScope, TrackDataEncoding},
+    DiskImage,
+    DiskImageError,
+};
+use bit_vec::BitVec;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Options controlling a single application of [DiskImage::age()]. Applying the same options
+/// repeatedly simulates progressive aging, as existing weak regions grow on each pass.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AgingOptions {
+    /// The seed for the simulation.
+    pub seed: u64,
+    /// The number of new weak regions to create.
+    pub weak_regions: usize,
+    /// The maximum length of a new weak region, in bytes.
+    pub weak_region_len: usize,
+    /// The number of bytes by which existing weak regions grow in each direction.
+    pub weak_growth: usize,
+    /// The number of dropout clusters to create. Each cluster contains up to four dropouts
+    /// within the same sector.
+    pub dropout_clusters: usize,
+    /// The maximum length of a single dropout, in bytes.
+    pub dropout_len: usize,
+    /// The number of sectors to corrupt, so that their data fails its CRC check.
+    pub crc_failures: usize,
+    /// How strongly defects favor inner tracks. A track at the innermost cylinder is
+    /// `1 + inner_track_bias` times as likely to be chosen as a track at cylinder 0.
+    pub inner_track_bias: f64,
+}
+
+impl Default for AgingOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            weak_regions: 4,
+            weak_region_len: 16,
+            weak_growth: 2,
+            dropout_clusters: 2,
+            dropout_len: 8,
+            crc_failures: 4,
+            inner_track_bias: 2.0,
+        }
+    }
+}
+
+impl AgingOptions {
+    /// Return options for lightly aged media, with a single defect of each kind.
+    pub fn light() -> Self {
+        Self {
+            weak_regions: 1,
+            weak_growth: 1,
+            dropout_clusters: 1,
+            crc_failures: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Return options for severely aged media.
+    pub fn severe() -> Self {
+        Self {
+            weak_regions: 16,
+            weak_region_len: 64,
+            weak_growth: 8,
+            dropout_clusters: 8,
+            dropout_len: 32,
+            crc_failures: 16,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+}
+
+/// A kind of defect introduced by [DiskImage::age()].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AgingEffect {
+    /// A new weak region was created.
+    WeakRegion,
+    /// An existing weak region grew.
+    WeakGrowth,
+    /// The media dropped out.
+    Dropout,
+    /// Sector data was corrupted, so that it fails its CRC check.
+    CrcFailure,
+}
+
+impl Display for AgingEffect {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            AgingEffect::WeakRegion => write!(f, "weak region"),
+            AgingEffect::WeakGrowth => write!(f, "weak region growth"),
+            AgingEffect::Dropout => write!(f, "dropout"),
+            AgingEffect::CrcFailure => write!(f, "CRC failure"),
+        }
+    }
+}
+
+/// A single defect introduced by [DiskImage::age()].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AgingEvent {
+    /// The physical cylinder and head of the affected track.
+    pub ch: DiskCh,
+    /// The ID of the affected sector, or `None` if the defect is not confined to a sector.
+    pub sector: Option<DiskChsn>,
+    /// The kind of defect introduced.
+    pub effect: AgingEffect,
+    /// The number of bytes affected.
+    pub len: usize,
+}
+
+/// The defects introduced by [DiskImage::age()].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AgingReport {
+    /// Each defect introduced, in the order it was applied.
+    pub events: Vec<AgingEvent>,
+}
+
+impl AgingReport {
+    /// Return the number of events of the specified kind.
+    pub fn count(&self, effect: AgingEffect) -> usize {
+        self.events.iter().filter(|e| e.effect == effect).count()
+    }
+
+    /// Return the distinct sectors affected by any defect, in the order they were first affected.
+    pub fn affected_sectors(&self) -> Vec<(DiskCh, DiskChsn)> {
+        let mut sectors = Vec::new();
+        for event in &self.events {
+            if let Some(sector) = event.sector {
+                if !sectors.contains(&(event.ch, sector)) {
+                    sectors.push((event.ch, sector));
+                }
+            }
+        }
+        sectors
+    }
+}
+
+impl Display for AgingReport {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        writeln!(
+            f,
+            "{} weak regions, {} grown, {} dropouts, {} CRC failures",
+            self.count(AgingEffect::WeakRegion),
+            self.count(AgingEffect::WeakGrowth),
+            self.count(AgingEffect::Dropout),
+            self.count(AgingEffect::CrcFailure)
+        )?;
+        for event in &self.events {
+            match event.sector {
+                Some(sector) => writeln!(f, "  {} {}: {} ({} bytes)", event.ch, sector, event.effect, event.len)?,
+                None => writeln!(f, "  {}: {} ({} bytes)", event.ch, event.effect, event.len)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The data field of a sector that a defect can be applied to.
+struct SectorTarget {
+    /// The index of the sector in a `MetaSector` track.
+    index: usize,
+    chsn: DiskChsn,
+    /// The bit offset of the first data byte in a `BitStream` track.
+    bit_offset: usize,
+    len: usize,
+}
+
+fn sector_targets(track: &dyn Track) -> Vec<SectorTarget> {
+    if let Some(meta_track) = track.as_metasector_track() {
+        // The stored data may be shorter or longer than the sector ID's size code implies.
+        return track
+            .sector_list()
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.attributes.no_dam)
+            .filter_map(|(index, entry)| {
+                Some(SectorTarget {
+                    index,
+                    chsn: entry.chsn,
+                    bit_offset: 0,
+                    len: meta_track.data_len_at(index)?,
+                })
+            })
+            .filter(|target| target.len > 0)
+            .collect();
+    }
+
+    let Some(metadata) = track.metadata()
+    else {
+        return Vec::new();
+    };
+    metadata
+        .items
+        .iter()
+        .filter(|item| item.element.is_sector_data())
+        .filter_map(|item| {
+            let range = item.element.range(RwScope::DataOnly)?;
+            Some(SectorTarget {
+                index: 0,
+                chsn: item.chsn?,
+                bit_offset: item.start + range.start * MFM_BYTE_LEN,
+                len: range.len(),
+            })
+        })
+        .filter(|target| target.len > 0)
+        .collect()
+}
+
+/// A defect applied to a randomly chosen sector by [DiskImage::age()].
+#[derive(Copy, Clone)]
+enum SectorDefect {
+    WeakRegion,
+    Dropout,
+    CrcFailure,
+}
+
+impl From<SectorDefect> for AgingEffect {
+    fn from(defect: SectorDefect) -> Self {
+        match defect {
+            SectorDefect::WeakRegion => AgingEffect::WeakRegion,
+            SectorDefect::Dropout => AgingEffect::Dropout,
+            SectorDefect::CrcFailure => AgingEffect::CrcFailure,
+        }
+    }
+}
+
+/// Grow each run of set bits in `mask` by `growth` bits in each direction, returning the number
+/// of runs.
+fn grow_mask(mask: &mut BitVec, growth: usize) -> usize {
+    let mut runs = Vec::new();
+    let mut run_start = None;
+    for (i, bit) in mask.iter().enumerate() {
+        match (bit, run_start) {
+            (true, None) => run_start = Some(i),
+            (false, Some(start)) => {
+                runs.push(start..i);
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = run_start {
+        runs.push(start..mask.len());
+    }
+
+    for run in &runs {
+        let end = (run.end + growth).min(mask.len());
+        for i in run.start.saturating_sub(growth)..end {
+            mask.set(i, true);
+        }
+    }
+    runs.len()
+}
+
+/// Set the weak bits of `stream` covering `len` bytes from `bit_offset`.
+fn set_weak_bits(track: &mut dyn Track, bit_offset: usize, len: usize) -> Result<(), DiskImageError> {
+    let stream = track.stream_mut().ok_or(DiskImageError::UnsupportedFormat)?;
+    let stream_len = stream.len();
+    if stream.weak_mask().len() != stream_len {
+        stream.set_weak_mask(BitVec::from_elem(stream_len, false));
+    }
+    let mask = stream.weak_mask_mut();
+    for i in bit_offset..(bit_offset + len * MFM_BYTE_LEN).min(stream_len) {
+        mask.set(i, true);
+    }
+    Ok(())
+}
+
+impl DiskImage {
+    /// Degrade the image as if the media had aged, according to `opts`. Returns an [AgingReport]
+    /// listing every defect introduced.
+    ///
+    /// Tracks that have no sector data to degrade are skipped. Applying aging more than once
+    /// compounds the damage, as existing weak regions grow on each pass.
+    pub fn age(&mut self, opts: &AgingOptions) -> Result<AgingReport, DiskImageError> {
+        let mut rng = SeededRng::new(opts.seed);
+        let mut report = AgingReport::default();
+
+        // Collect the sectors of each track before anything is modified, and weight each track by
+        // how far in it lies.
+        let max_c = self.iter_tracks().map(|(ch, _)| ch.c()).max().unwrap_or(0).max(1) as f64;
+        let mut candidates = Vec::new();
+        for (ch, track) in self.iter_tracks() {
+            let targets = sector_targets(track);
+            if !targets.is_empty() {
+                let weight = 1.0 + opts.inner_track_bias.max(0.0) * ch.c() as f64 / max_c;
+                candidates.push((ch, weight, targets));
+            }
+        }
+
+        // Grow existing weak regions first, so that new regions are created at their initial size.
+        if opts.weak_growth > 0 {
+            for (ch, track) in self.iter_tracks_mut() {
+                if let Some(meta_track) = track.as_metasector_track_mut() {
+                    let Some((_, _, targets)) = candidates.iter().find(|(target_ch, _, _)| *target_ch == ch)
+                    else {
+                        continue;
+                    };
+                    for target in targets {
+                        for region in meta_track.weak_regions_at(target.index) {
+                            let grown = region.start.saturating_sub(opts.weak_growth)..region.end + opts.weak_growth;
+                            meta_track.set_weak_region_at(target.index, grown)?;
+                            report.events.push(AgingEvent {
+                                ch,
+                                sector: Some(target.chsn),
+                                effect: AgingEffect::WeakGrowth,
+                                len: opts.weak_growth,
+                            });
+                        }
+                    }
+                }
+                else if let Some(stream) = track.stream_mut() {
+                    if stream.weak_mask().any() {
+                        let runs = grow_mask(stream.weak_mask_mut(), opts.weak_growth * MFM_BYTE_LEN);
+                        report.events.extend((0..runs).map(|_| AgingEvent {
+                            ch,
+                            sector: None,
+                            effect: AgingEffect::WeakGrowth,
+                            len: opts.weak_growth,
+                        }));
+                    }
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            log::warn!("age(): Image has no sector data to degrade.");
+            return Ok(report);
+        }
+        let total_weight: f64 = candidates.iter().map(|(_, weight, _)| weight).sum();
+
+        let defects = [
+            (SectorDefect::WeakRegion, opts.weak_regions),
+            (SectorDefect::Dropout, opts.dropout_clusters),
+            (SectorDefect::CrcFailure, opts.crc_failures),
+        ]
+        .into_iter()
+        .flat_map(|(defect, count)| (0..count).map(move |_| defect));
+
+        for defect in defects {
+            // Choose a track, favoring inner tracks, then a sector on that track.
+            let mut pick = rng.next_f64() * total_weight;
+            let (ch, _, targets) = candidates
+                .iter()
+                .find(|(_, weight, _)| {
+                    pick -= weight;
+                    pick < 0.0
+                })
+                .unwrap_or(&candidates[candidates.len() - 1]);
+            let target = &targets[rng.below(targets.len())];
+            let ch = *ch;
+
+            let track = self.track_mut(ch).ok_or_else(|| {
+                log::error!("age(): Track {} not found.", ch);
+                DiskImageError::SeekError
+            })?;
+
+            let effect = AgingEffect::from(defect);
+            match defect {
+                SectorDefect::WeakRegion => {
+                    let len = 1 + rng.below(opts.weak_region_len.clamp(1, target.len));
+                    let offset = rng.below(target.len - len + 1);
+                    if let Some(meta_track) = track.as_metasector_track_mut() {
+                        meta_track.set_weak_region_at(target.index, offset..offset + len)?;
+                    }
+                    else {
+                        set_weak_bits(track.as_mut(), target.bit_offset + offset * MFM_BYTE_LEN, len)?;
+                    }
+                    report.events.push(AgingEvent {
+                        ch,
+                        sector: Some(target.chsn),
+                        effect,
+                        len,
+                    });
+                }
+                SectorDefect::Dropout => {
+                    let mut offset = rng.below(target.len);
+                    for _ in 0..1 + rng.below(4) {
+                        let len = (1 + rng.below(opts.dropout_len.max(1))).min(target.len - offset);
+                        if let Some(meta_track) = track.as_metasector_track_mut() {
+                            meta_track.set_dropout_at(target.index, offset..offset + len)?;
+                        }
+                        else {
+                            let bit_offset = target.bit_offset + offset * MFM_BYTE_LEN;
+                            let bit_len = (len * MFM_BYTE_LEN).min(track.info().bit_length - bit_offset);
+                            track.write_raw_bits(bit_offset, &BitVec::from_elem(bit_len, false))?;
+                            set_weak_bits(track.as_mut(), bit_offset, len)?;
+                        }
+                        report.events.push(AgingEvent {
+                            ch,
+                            sector: Some(target.chsn),
+                            effect,
+                            len,
+                        });
+
+                        // The next dropout of the cluster follows closely.
+                        offset += len + rng.below(16);
+                        if offset >= target.len {
+                            break;
+                        }
+                    }
+                }
+                SectorDefect::CrcFailure => {
+                    let offset = rng.below(target.len);
+                    let xor = 1u8 << rng.below(8);
+                    if let Some(meta_track) = track.as_metasector_track_mut() {
+                        meta_track.corrupt_data_at(target.index, offset, xor)?;
+                    }
+                    else {
+                        let bit_offset = target.bit_offset + offset * MFM_BYTE_LEN;
+                        let stream = track.stream().ok_or(DiskImageError::UnsupportedFormat)?;
+                        let byte = stream.read_decoded_u8(bit_offset).ok_or(DiskImageError::SeekError)?;
+                        let prev_bit = bit_offset > 0 && stream.data()[bit_offset - 1];
+                        let mut encoded = stream.encode(&[byte ^ xor], prev_bit, EncodingVariant::Data);
+                        // An MFM clock bit depends on the data bits either side of it, so the clock
+                        // bit following the byte must change with its last data bit.
+                        let next_clock = bit_offset + MFM_BYTE_LEN;
+                        if matches!(stream.encoding(), TrackDataEncoding::Mfm) && next_clock + 1 < stream.len() {
+                            let last_bit = encoded[MFM_BYTE_LEN - 1];
+                            encoded.push(!last_bit && !stream.data()[next_clock + 1]);
+                        }
+                        track.write_raw_bits(bit_offset, &encoded)?;
+                    }
+                    report.events.push(AgingEvent {
+                        ch,
+                        sector: Some(target.chsn),
+                        effect,
+                        len: 1,
+                    });
+                }
+            }
+        }
+
+        self.update_analysis();
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{formatted_disk, sector_disk},
+        types::TrackDataResolution,
+        SectorIdQuery,
+        StandardFormat,
+    };
+
+    fn test_disk(resolution: TrackDataResolution) -> DiskImage {
+        match resolution {
+            TrackDataResolution::BitStream => formatted_disk(),
+            _ => sector_disk(StandardFormat::PcFloppy360, |_| (vec![0xF6; 512], Default::default())),
+        }
+    }
+
+    fn sector_errors(disk: &DiskImage) -> usize {
+        disk.sectors()
+            .filter(|sector| sector.read(RwScope::DataOnly).map_or(true, |r| r.data_crc_error))
+            .count()
+    }
+
+    #[test]
+    fn test_age_image() {
+        for resolution in [TrackDataResolution::MetaSector, TrackDataResolution::BitStream] {
+            let mut disk = test_disk(resolution);
+            assert_eq!(sector_errors(&disk), 0, "{:?}", resolution);
+
+            let opts = AgingOptions::default().with_seed(0xA6E);
+            let report = disk.age(&opts).unwrap();
+            assert_eq!(report.count(AgingEffect::WeakRegion), opts.weak_regions);
+            assert_eq!(report.count(AgingEffect::CrcFailure), opts.crc_failures);
+            assert!(report.count(AgingEffect::Dropout) >= opts.dropout_clusters);
+            assert_eq!(report.count(AgingEffect::WeakGrowth), 0);
+
+            // Every sector with a CRC failure or dropout now fails its CRC check.
+            for event in &report.events {
+                if matches!(event.effect, AgingEffect::CrcFailure | AgingEffect::Dropout) {
+                    let track = disk.track(event.ch).unwrap();
+                    let result = track
+                        .read_sector(
+                            SectorIdQuery::from(event.sector.unwrap()),
+                            None,
+                            None,
+                            RwScope::DataOnly,
+                            false,
+                        )
+                        .unwrap();
+                    assert!(result.data_crc_error, "{:?} {}", resolution, event.sector.unwrap());
+                }
+            }
+            assert!(sector_errors(&disk) > 0);
+
+            // Aging is reproducible, and a second pass grows the existing weak regions.
+            assert_eq!(test_disk(resolution).age(&opts).unwrap().events, report.events);
+            let report = disk.age(&opts).unwrap();
+            assert!(report.count(AgingEffect::WeakGrowth) > 0);
+        }
+    }
+
+    #[test]
+    fn test_crc_failure_clock_bits() {
+        let mut disk = formatted_disk();
+        let opts = AgingOptions {
+            weak_regions: 0,
+            dropout_clusters: 0,
+            crc_failures: 64,
+            ..AgingOptions::default()
+        };
+        disk.age(&opts).unwrap();
+
+        // Valid MFM never has two adjacent one bits, so every corrupted byte was re-encoded with
+        // clock bits matching its neighbors.
+        for (ch, track) in disk.iter_tracks() {
+            let data = track.stream().unwrap().data();
+            let adjacent = data.iter().zip(data.iter().skip(1)).position(|(a, b)| a && b);
+            assert_eq!(adjacent, None, "track {}", ch);
+        }
+    }
+}
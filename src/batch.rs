@@ -0,0 +1,771 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/batch.rs
+
+    Batch operations over directories of disk images: a triage report that
+    ranks images by health and groups duplicate dumps, and conversion of many
+    images between formats.
+*/
+
+//! Batch operations over directories of disk images.
+//!
+//! [triage()] loads every disk image found under a directory, scores each with
+//! [DiskImage::health()], identifies its format and platform, and groups images with identical
+//! track contents. The resulting [TriageReport] lists the worst dumps first and can be written
+//! out as CSV or JSON.
+//!
+//! [BatchConverter] converts a list of [ConvertJob]s to a single output format. Its
+//! [ErrorPolicy] decides whether an image that fails to convert, or that the output format cannot
+//! represent without losing data, stops the batch. Every job is accounted for in the resulting
+//! [ConvertReport].
+
+use crate::{
+    file_parsers::{ImageFormatParser, ParserWriteCompatibility, ParserWriteOptions},
+    health::HealthReport,
+    image_writer::ImageWriter,
+    platform::Platform,
+    types::{DiskImageFileFormat, DiskImageFlags, StandardFormat},
+    DiskImage,
+    DiskImageError,
+    FoxHashMap,
+};
+use std::{
+    ffi::OsStr,
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// The triage result for a single disk image file.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TriageEntry {
+    /// The path to the disk image file.
+    pub path: PathBuf,
+    /// The detected file format, if the file could be identified.
+    pub format: Option<DiskImageFileFormat>,
+    /// The closest standard disk format, if any.
+    pub standard_format: Option<StandardFormat>,
+    /// The platforms the disk image is intended for, if determined.
+    pub platforms: Vec<Platform>,
+    /// A description of the copy protection scheme detected on the disk, if any.
+    pub copy_protection: Option<String>,
+    /// The health report for the disk image, or None if it failed to load.
+    pub health: Option<HealthReport>,
+    /// A hash of the disk image's track contents, used to find duplicate dumps.
+    pub hash: Option<String>,
+    /// The index of the group of duplicates this image belongs to, if any other image in the
+    /// report has identical track contents.
+    pub duplicate_group: Option<usize>,
+    /// The error encountered loading the image, if any.
+    pub error: Option<String>,
+}
+
+impl TriageEntry {
+    /// Return the health score of the image, treating images that failed to load as a score of 0.
+    pub fn score(&self) -> u8 {
+        self.health.as_ref().map(|h| h.score).unwrap_or(0)
+    }
+
+    fn from_image(path: PathBuf, disk: &DiskImage) -> Self {
+        let mut hasher = sha1_smol::Sha1::new();
+        for track in disk.track_iter() {
+            hasher.update(&track.hash().bytes());
+        }
+
+        TriageEntry {
+            path,
+            format: disk.source_format(),
+            standard_format: disk.closest_format(true),
+            platforms: disk.image_format().platforms.clone().unwrap_or_default(),
+            copy_protection: disk.detect_copy_protection().map(|cp| cp.to_string()),
+            health: Some(disk.health()),
+            hash: Some(hasher.digest().to_string()),
+            duplicate_group: None,
+            error: None,
+        }
+    }
+
+    fn from_error(path: PathBuf, error: DiskImageError) -> Self {
+        TriageEntry {
+            path,
+            format: None,
+            standard_format: None,
+            platforms: Vec::new(),
+            copy_protection: None,
+            health: None,
+            hash: None,
+            duplicate_group: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// A triage report over a collection of disk images. Entries are sorted with the lowest health
+/// scores first, and duplicate images are listed together, ranked by the worst dump among them.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TriageReport {
+    pub entries: Vec<TriageEntry>,
+}
+
+impl TriageReport {
+    /// Return an iterator over the groups of duplicate images, as slices of the report entries.
+    pub fn duplicates(&self) -> impl Iterator<Item = &[TriageEntry]> {
+        self.entries
+            .chunk_by(|a, b| a.duplicate_group.is_some() && a.duplicate_group == b.duplicate_group)
+            .filter(|group| group[0].duplicate_group.is_some())
+    }
+
+    /// Serialize the report as CSV, with a header row.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "path,score,format,standard_format,platforms,copy_protection,duplicate_group,hash,error,factors\n",
+        );
+        for entry in &self.entries {
+            let fields = [
+                entry.path.display().to_string(),
+                entry.score().to_string(),
+                opt_string(&entry.format),
+                opt_string(&entry.standard_format),
+                platform_list(&entry.platforms),
+                entry.copy_protection.clone().unwrap_or_default(),
+                opt_string(&entry.duplicate_group),
+                entry.hash.clone().unwrap_or_default(),
+                entry.error.clone().unwrap_or_default(),
+                factor_list(entry),
+            ];
+            let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Serialize the report as a JSON array of objects, one per image.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        let rows: Vec<JsonEntry> = self.entries.iter().map(JsonEntry::from).collect();
+        let mut out = serde_json::to_string_pretty(&rows).unwrap_or_default();
+        out.push('\n');
+        out
+    }
+}
+
+/// Load every disk image under `dir`, searching subdirectories recursively, and produce a
+/// [TriageReport]. Files are considered disk images if their extension is one of
+/// [crate::supported_extensions()]. Images that fail to load are included in the report with a
+/// score of 0 and the error that occurred.
+///
+/// # Returns
+/// - `Ok(TriageReport)` on success.
+/// - `Err(DiskImageError::IoError)` if the directory could not be read.
+pub fn triage(dir: impl AsRef<Path>) -> Result<TriageReport, DiskImageError> {
+    let mut paths = Vec::new();
+    collect_image_paths(dir.as_ref(), &mut paths)?;
+    paths.sort();
+
+    let mut entries: Vec<TriageEntry> = paths
+        .into_iter()
+        .map(|path| {
+            log::debug!("triage(): Loading {}", path.display());
+            match DiskImage::load_from_file(&path, None, None) {
+                Ok(disk) => TriageEntry::from_image(path, &disk),
+                Err(e) => {
+                    log::warn!("triage(): Failed to load {}: {}", path.display(), e);
+                    TriageEntry::from_error(path, e)
+                }
+            }
+        })
+        .collect();
+
+    // Group images with identical track contents, numbering groups in path order.
+    let mut hash_counts: FoxHashMap<String, usize> = FoxHashMap::new();
+    for hash in entries.iter().filter_map(|e| e.hash.clone()) {
+        *hash_counts.entry(hash).or_default() += 1;
+    }
+    let mut group_ids: FoxHashMap<String, usize> = FoxHashMap::new();
+    for entry in entries.iter_mut() {
+        if let Some(hash) = entry.hash.as_ref().filter(|h| hash_counts[*h] > 1) {
+            let next_id = group_ids.len();
+            entry.duplicate_group = Some(*group_ids.entry(hash.clone()).or_insert(next_id));
+        }
+    }
+
+    // Rank each group by its worst member, so that duplicates stay together.
+    let mut group_scores: FoxHashMap<usize, u8> = FoxHashMap::new();
+    for entry in entries.iter() {
+        if let Some(group) = entry.duplicate_group {
+            let score = group_scores.entry(group).or_insert(u8::MAX);
+            *score = (*score).min(entry.score());
+        }
+    }
+    entries.sort_by_key(|e| {
+        let rank = e.duplicate_group.map(|g| group_scores[&g]).unwrap_or(e.score());
+        (
+            rank,
+            e.duplicate_group.is_none(),
+            e.duplicate_group,
+            e.score(),
+            e.path.clone(),
+        )
+    });
+
+    Ok(TriageReport { entries })
+}
+
+/// How a [BatchConverter] responds to an image that fails to convert.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Record the failure and continue with the next image. Images that the output format cannot
+    /// represent without losing data are not written.
+    #[default]
+    Skip,
+    /// Stop the batch at the first image that fails to convert, or that the output format cannot
+    /// represent without losing data. The remaining jobs are not attempted.
+    Abort,
+    /// Record the failure and continue with the next image. Images that the output format cannot
+    /// represent without losing data are written anyway.
+    BestEffort,
+}
+
+/// A single conversion in a batch: the image to read, and the file to write it to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConvertJob {
+    pub input:  PathBuf,
+    pub output: PathBuf,
+}
+
+impl ConvertJob {
+    pub fn new(input: impl Into<PathBuf>, output: impl Into<PathBuf>) -> Self {
+        ConvertJob {
+            input:  input.into(),
+            output: output.into(),
+        }
+    }
+
+    /// Create a job for each of `inputs`, writing into `out_dir` with the file name of the input
+    /// and the extension `ext`. Where several inputs share a file stem, such as `game.imd` and
+    /// `game.td0`, their outputs keep the input extension (`game.imd.img`) so they don't collide.
+    ///
+    /// Returns `Err(DiskImageError::ParameterError)` if an input has no file name, or if two inputs
+    /// would still be written to the same output file, as happens for inputs with the same file
+    /// name in different directories.
+    pub fn into_dir(inputs: &[PathBuf], out_dir: &Path, ext: &str) -> Result<Vec<ConvertJob>, DiskImageError> {
+        let stems = inputs
+            .iter()
+            .map(|input| {
+                input.file_stem().ok_or_else(|| {
+                    log::error!("into_dir(): Input {} has no file name", input.display());
+                    DiskImageError::ParameterError
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stem_counts: FoxHashMap<&OsStr, usize> = FoxHashMap::new();
+        for stem in &stems {
+            *stem_counts.entry(*stem).or_default() += 1;
+        }
+
+        let jobs: Vec<ConvertJob> = inputs
+            .iter()
+            .zip(stems.iter())
+            .map(|(input, stem)| {
+                // An input with a file stem always has a file name.
+                let name = match stem_counts[stem] {
+                    1 => *stem,
+                    _ => input.file_name().unwrap_or(*stem),
+                };
+                let mut out_name = name.to_os_string();
+                out_name.push(".");
+                out_name.push(ext);
+                ConvertJob::new(input, out_dir.join(out_name))
+            })
+            .collect();
+
+        let mut outputs: FoxHashMap<&Path, &Path> = FoxHashMap::new();
+        for job in &jobs {
+            if let Some(other) = outputs.insert(&job.output, &job.input) {
+                log::error!(
+                    "into_dir(): {} and {} would both be written to {}",
+                    other.display(),
+                    job.input.display(),
+                    job.output.display()
+                );
+                return Err(DiskImageError::ParameterError);
+            }
+        }
+        Ok(jobs)
+    }
+}
+
+/// The outcome of a single [ConvertJob].
+#[derive(Clone, Debug)]
+pub enum ConvertOutcome {
+    /// The image was converted and written.
+    Converted,
+    /// The image was written, but the output format could not represent all of its data. Only
+    /// produced under [ErrorPolicy::BestEffort].
+    ConvertedWithDataLoss,
+    /// The image was not written, as the output format could not represent all of its data.
+    Skipped,
+    /// The image could not be loaded, converted or written.
+    Failed(DiskImageError),
+    /// The job was not attempted, as the batch was aborted by an earlier job.
+    NotAttempted,
+}
+
+impl ConvertOutcome {
+    /// Return true if the job was attempted.
+    pub fn is_attempted(&self) -> bool {
+        !matches!(self, ConvertOutcome::NotAttempted)
+    }
+
+    /// Return true if the image was written.
+    pub fn is_converted(&self) -> bool {
+        matches!(self, ConvertOutcome::Converted | ConvertOutcome::ConvertedWithDataLoss)
+    }
+}
+
+impl Display for ConvertOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConvertOutcome::Converted => write!(f, "converted"),
+            ConvertOutcome::ConvertedWithDataLoss => write!(f, "converted with data loss"),
+            ConvertOutcome::Skipped => write!(f, "skipped: output format would lose data"),
+            ConvertOutcome::Failed(e) => write!(f, "failed: {}", e),
+            ConvertOutcome::NotAttempted => write!(f, "not attempted"),
+        }
+    }
+}
+
+/// The result of a single [ConvertJob] within a [ConvertReport].
+#[derive(Clone, Debug)]
+pub struct ConvertResult {
+    pub job: ConvertJob,
+    pub outcome: ConvertOutcome,
+}
+
+/// A report of a batch conversion, with a result for every job in the order given.
+#[derive(Clone, Debug, Default)]
+pub struct ConvertReport {
+    pub results: Vec<ConvertResult>,
+    /// True if the batch was stopped early under [ErrorPolicy::Abort].
+    pub aborted: bool,
+}
+
+impl ConvertReport {
+    /// Return the number of images written, including those written with data loss.
+    pub fn converted(&self) -> usize {
+        self.results.iter().filter(|r| r.outcome.is_converted()).count()
+    }
+
+    /// Return the number of images written with data loss.
+    pub fn data_loss(&self) -> usize {
+        self.count(|o| matches!(o, ConvertOutcome::ConvertedWithDataLoss))
+    }
+
+    /// Return the number of images skipped to avoid data loss.
+    pub fn skipped(&self) -> usize {
+        self.count(|o| matches!(o, ConvertOutcome::Skipped))
+    }
+
+    /// Return the number of images that failed to convert.
+    pub fn failed(&self) -> usize {
+        self.count(|o| matches!(o, ConvertOutcome::Failed(_)))
+    }
+
+    /// Return an iterator over the results of jobs that did not convert cleanly.
+    pub fn problems(&self) -> impl Iterator<Item = &ConvertResult> {
+        self.results
+            .iter()
+            .filter(|r| !matches!(r.outcome, ConvertOutcome::Converted))
+    }
+
+    /// Return true if every image was converted without data loss.
+    pub fn is_success(&self) -> bool {
+        self.problems().next().is_none()
+    }
+
+    fn count(&self, f: impl Fn(&ConvertOutcome) -> bool) -> usize {
+        self.results.iter().filter(|r| f(&r.outcome)).count()
+    }
+}
+
+impl Display for ConvertReport {
+    /// Format a one-line summary of the batch.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Converted {} of {} images", self.converted(), self.results.len())?;
+        if self.data_loss() > 0 {
+            write!(f, " ({} with data loss)", self.data_loss())?;
+        }
+        write!(f, ", {} skipped, {} failed", self.skipped(), self.failed())?;
+        if self.aborted {
+            write!(f, "; batch aborted")?;
+        }
+        Ok(())
+    }
+}
+
+/// A callback receiving the result of each job in a batch conversion as it completes.
+pub type BatchCallback = Arc<dyn Fn(&ConvertResult) + Send + Sync>;
+
+/// Converts a batch of disk images to a single output format.
+///
+/// ```no_run
+/// use fluxfox::{batch::{BatchConverter, ConvertJob, ErrorPolicy}, DiskImageFileFormat};
+///
+/// let jobs = vec![ConvertJob::new("in/a.imd", "out/a.img")];
+/// let report = BatchConverter::new(DiskImageFileFormat::RawSectorImage)
+///     .with_policy(ErrorPolicy::Skip)
+///     .convert(&jobs);
+/// println!("{}", report);
+/// ```
+#[derive(Clone)]
+pub struct BatchConverter {
+    format:   DiskImageFileFormat,
+    policy:   ErrorPolicy,
+    verify:   bool,
+    flags:    DiskImageFlags,
+    options:  ParserWriteOptions,
+    callback: Option<BatchCallback>,
+}
+
+impl BatchConverter {
+    pub fn new(format: DiskImageFileFormat) -> Self {
+        BatchConverter {
+            format,
+            policy: ErrorPolicy::default(),
+            verify: false,
+            flags: DiskImageFlags::empty(),
+            options: ParserWriteOptions::default(),
+            callback: None,
+        }
+    }
+
+    /// Set how the batch responds to an image that fails to convert.
+    pub fn with_policy(self, policy: ErrorPolicy) -> Self {
+        Self { policy, ..self }
+    }
+
+    /// Verify each converted image after it is written. See [ImageWriter::with_verify].
+    pub fn with_verify(self, verify: bool) -> Self {
+        Self { verify, ..self }
+    }
+
+    /// Set flags on each image after it is loaded, such as [DiskImageFlags::PROLOK].
+    pub fn with_image_flags(self, flags: DiskImageFlags) -> Self {
+        Self { flags, ..self }
+    }
+
+    /// Set the options passed to the output format parser.
+    pub fn with_options(self, options: ParserWriteOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Set a callback to receive the result of each job as it completes.
+    pub fn with_callback(self, callback: BatchCallback) -> Self {
+        Self {
+            callback: Some(callback),
+            ..self
+        }
+    }
+
+    /// Convert each of `jobs` in order, creating output directories as needed.
+    pub fn convert(&self, jobs: &[ConvertJob]) -> ConvertReport {
+        let mut report = ConvertReport::default();
+
+        for job in jobs {
+            let outcome = if report.aborted {
+                ConvertOutcome::NotAttempted
+            }
+            else {
+                let outcome = self.convert_job(job);
+                report.aborted = self.policy == ErrorPolicy::Abort
+                    && matches!(outcome, ConvertOutcome::Failed(_) | ConvertOutcome::Skipped);
+                outcome
+            };
+
+            let result = ConvertResult {
+                job: job.clone(),
+                outcome,
+            };
+            if let Some(callback) = self.callback.as_ref().filter(|_| result.outcome.is_attempted()) {
+                callback(&result);
+            }
+            report.results.push(result);
+        }
+        report
+    }
+
+    fn convert_job(&self, job: &ConvertJob) -> ConvertOutcome {
+        log::debug!(
+            "convert_job(): Converting {} to {}",
+            job.input.display(),
+            job.output.display()
+        );
+        match self.try_convert(job) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                log::warn!("convert_job(): Failed to convert {}: {}", job.input.display(), e);
+                ConvertOutcome::Failed(e)
+            }
+        }
+    }
+
+    fn try_convert(&self, job: &ConvertJob) -> Result<ConvertOutcome, DiskImageError> {
+        let mut disk = DiskImage::load_from_file(&job.input, None, None)?;
+        disk.set_flag(self.flags);
+
+        let outcome = match self.format.can_write(Some(&disk)) {
+            ParserWriteCompatibility::Ok => ConvertOutcome::Converted,
+            ParserWriteCompatibility::DataLoss => match self.policy {
+                ErrorPolicy::BestEffort => ConvertOutcome::ConvertedWithDataLoss,
+                _ => return Ok(ConvertOutcome::Skipped),
+            },
+            ParserWriteCompatibility::Incompatible => {
+                return Err(DiskImageError::IncompatibleImage(format!(
+                    "{} cannot represent this image",
+                    self.format
+                )))
+            }
+            ParserWriteCompatibility::UnsupportedFormat => return Err(DiskImageError::UnsupportedFormat),
+        };
+
+        if let Some(parent) = job.output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        ImageWriter::new(&mut disk)
+            .with_format(self.format)
+            .with_path(job.output.clone())
+            .with_verify(self.verify)
+            .with_options(self.options.clone())
+            .write()?;
+        Ok(outcome)
+    }
+}
+
+/// A flattened [TriageEntry] as written by [TriageReport::to_json()].
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct JsonEntry {
+    path: String,
+    score: u8,
+    format: Option<String>,
+    standard_format: Option<String>,
+    platforms: Vec<String>,
+    copy_protection: Option<String>,
+    duplicate_group: Option<usize>,
+    hash: Option<String>,
+    error: Option<String>,
+    factors: Vec<JsonFactor>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct JsonFactor {
+    factor:  String,
+    count:   usize,
+    total:   usize,
+    penalty: f64,
+}
+
+#[cfg(feature = "serde")]
+impl From<&TriageEntry> for JsonEntry {
+    fn from(entry: &TriageEntry) -> Self {
+        JsonEntry {
+            path: entry.path.display().to_string(),
+            score: entry.score(),
+            format: entry.format.map(|f| f.to_string()),
+            standard_format: entry.standard_format.map(|f| f.to_string()),
+            platforms: entry.platforms.iter().map(|p| p.to_string()).collect(),
+            copy_protection: entry.copy_protection.clone(),
+            duplicate_group: entry.duplicate_group,
+            hash: entry.hash.clone(),
+            error: entry.error.clone(),
+            factors: entry
+                .health
+                .iter()
+                .flat_map(|h| h.penalties())
+                .map(|f| JsonFactor {
+                    factor:  f.kind.to_string(),
+                    count:   f.count,
+                    total:   f.total,
+                    penalty: f.penalty,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn collect_image_paths(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), DiskImageError> {
+    let extensions = crate::supported_extensions();
+    for dir_entry in std::fs::read_dir(dir)? {
+        let dir_entry = dir_entry?;
+        let path = dir_entry.path();
+        // Don't follow symlinked directories, which could lead us around in circles.
+        let file_type = dir_entry.file_type()?;
+        if file_type.is_dir() {
+            collect_image_paths(&path, paths)?;
+        }
+        else if file_type.is_symlink() && path.is_dir() {
+            log::debug!("collect_image_paths(): Skipping symlinked directory {}", path.display());
+        }
+        else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| extensions.contains(&ext.to_lowercase().as_str()))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn opt_string<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn platform_list(platforms: &[Platform]) -> String {
+    platforms.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(";")
+}
+
+fn factor_list(entry: &TriageEntry) -> String {
+    match &entry.health {
+        Some(health) => health.penalties().map(|f| f.to_string()).collect::<Vec<_>>().join(";"),
+        None => String::new(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triage() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let sub_dir = dir.join("sub");
+        std::fs::create_dir_all(&sub_dir).unwrap();
+
+        // Two identical raw sector images, one distinct one, a corrupt image and a non-image.
+        let image = vec![0xF6; 368_640];
+        let mut other = image.clone();
+        other[0] = 0;
+        std::fs::write(dir.join("a.img"), &image).unwrap();
+        std::fs::write(sub_dir.join("b.img"), &image).unwrap();
+        std::fs::write(dir.join("c.img"), &other).unwrap();
+        std::fs::write(dir.join("broken.imd"), b"not a disk image").unwrap();
+        std::fs::write(dir.join("readme.txt"), b"ignored").unwrap();
+
+        let report = triage(dir).unwrap();
+
+        assert_eq!(report.entries.len(), 4);
+        // The broken image ranks first.
+        assert!(report.entries[0].path.ends_with("broken.imd"));
+        assert!(report.entries[0].error.is_some());
+        assert_eq!(report.entries[0].score(), 0);
+
+        let groups: Vec<&[TriageEntry]> = report.duplicates().collect();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].len(), 2);
+        assert!(groups[0]
+            .iter()
+            .all(|e| e.format == Some(DiskImageFileFormat::RawSectorImage)));
+
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.starts_with("path,score,"));
+        #[cfg(feature = "serde")]
+        {
+            let json = report.to_json();
+            assert_eq!(json.matches("\"path\"").count(), 4);
+            assert!(json.contains("\"duplicate_group\": 0"));
+        }
+    }
+
+    #[test]
+    fn test_batch_convert() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let out_dir = dir.join("out");
+
+        let image = vec![0xF6; 368_640];
+        std::fs::write(dir.join("a.img"), &image).unwrap();
+        std::fs::write(dir.join("b.imd"), b"not a disk image").unwrap();
+        std::fs::write(dir.join("c.img"), &image).unwrap();
+        let inputs = [dir.join("a.img"), dir.join("b.imd"), dir.join("c.img")];
+        let jobs = ConvertJob::into_dir(&inputs, &out_dir, "img").unwrap();
+        assert_eq!(jobs[1].output, out_dir.join("b.img"));
+        let collisions = ConvertJob::into_dir(&[dir.join("d.imd"), dir.join("d.td0")], &out_dir, "img").unwrap();
+        assert_eq!(collisions[0].output, out_dir.join("d.imd.img"));
+        assert_eq!(collisions[1].output, out_dir.join("d.td0.img"));
+        let same_name = [dir.join("x").join("e.imd"), dir.join("y").join("e.imd")];
+        assert!(matches!(
+            ConvertJob::into_dir(&same_name, &out_dir, "img"),
+            Err(DiskImageError::ParameterError)
+        ));
+        assert!(matches!(
+            ConvertJob::into_dir(&[dir.join("f.imd"), dir.join("..")], &out_dir, "img"),
+            Err(DiskImageError::ParameterError)
+        ));
+
+        let seen = Arc::new(std::sync::Mutex::new(0));
+        let seen_cb = seen.clone();
+        let report = BatchConverter::new(DiskImageFileFormat::RawSectorImage)
+            .with_callback(Arc::new(move |_| *seen_cb.lock().unwrap() += 1))
+            .convert(&jobs);
+        assert_eq!(*seen.lock().unwrap(), 3);
+        assert_eq!(report.converted(), 2);
+        assert_eq!(report.failed(), 1);
+        assert!(!report.aborted);
+        assert!(!report.is_success());
+        assert!(out_dir.join("a.img").exists());
+        assert!(out_dir.join("c.img").exists());
+        assert_eq!(report.to_string(), "Converted 2 of 3 images, 0 skipped, 1 failed");
+
+        std::fs::remove_dir_all(&out_dir).unwrap();
+        let report = BatchConverter::new(DiskImageFileFormat::RawSectorImage)
+            .with_policy(ErrorPolicy::Abort)
+            .convert(&jobs);
+
+        assert!(report.aborted);
+        assert_eq!(report.results.len(), 3);
+        assert!(report.results[0].outcome.is_converted());
+        assert!(matches!(report.results[1].outcome, ConvertOutcome::Failed(_)));
+        assert!(matches!(report.results[2].outcome, ConvertOutcome::NotAttempted));
+    }
+}
@@ -45,7 +45,10 @@ pub const FM_MARKER_LEN: usize = 64;
 pub const FM_MARKER_DATA_MASK: u64 = 0x0000_0000_0000_5555;
 
 pub const FM_MARKER_CLOCK_MASK: u64 = 0xAAAA_AAAA_AAAA_AAAA;
+/// The clock bits of three sync bytes followed by an address mark with the 0xC7 clock pattern.
 pub const FM_MARKER_CLOCK_PATTERN: u64 = 0xAAAA_AAAA_AAAA_A02A;
+/// The clock pattern of the IDAM, DAM and DDAM address marks. The IAM uses 0xD7.
+pub const FM_ADDRESS_MARK_CLOCK: u8 = 0xC7;
 
 #[doc(hidden)]
 #[macro_export]
@@ -177,7 +180,7 @@ impl TrackCodec for FmCodec {
     }
 
     fn has_weak_bits(&self) -> bool {
-        !self.detect_weak_bits(6).0 > 0
+        self.detect_weak_bits(6).0 > 0
     }
 
     fn error_map(&self) -> &BitVec {
@@ -284,13 +287,19 @@ impl TrackCodec for FmCodec {
             );
             return None;
         }
-        let p_off: usize = self.clock_map[index] as usize;
         let mut byte = 0;
-        for bi in (index..std::cmp::min(index + FM_BYTE_LEN, self.bit_vec.len()))
-            .skip(p_off)
-            .step_by(2)
-        {
-            byte = (byte << 1) | self.bit_vec[bi] as u8;
+        let mut cursor = index;
+        for _ in 0..8 {
+            // Align to the next clock bit for every bit, as next() does, so that a phase change in
+            // the clock map within the byte is followed.
+            if cursor + 1 < self.bit_vec.len() && !self.clock_map[cursor] && self.clock_map[cursor + 1] {
+                cursor += 1;
+            }
+            if cursor + 1 >= self.bit_vec.len() {
+                break;
+            }
+            byte = (byte << 1) | self.bit_vec[cursor + 1] as u8;
+            cursor += 2;
         }
         Some(byte)
     }
@@ -306,7 +315,7 @@ impl TrackCodec for FmCodec {
     fn read_decoded_buf(&self, buf: &mut [u8], offset: usize) -> usize {
         let mut bytes_read = 0;
         for byte in buf.iter_mut() {
-            *byte = self.read_decoded_u8(offset + bytes_read).unwrap();
+            *byte = self.read_decoded_u8(offset + (bytes_read * FM_BYTE_LEN)).unwrap();
             bytes_read += 1;
         }
         bytes_read
@@ -334,51 +343,7 @@ impl TrackCodec for FmCodec {
     }
 
     fn encode(&self, data: &[u8], prev_bit: bool, encoding_type: EncodingVariant) -> BitVec {
-        let mut bitvec = BitVec::new();
-        let mut bit_count = 0;
-
-        for &byte in data {
-            for i in (0..8).rev() {
-                let bit = (byte & (1 << i)) != 0;
-                if bit {
-                    // 1 is encoded as 01
-                    bitvec.push(false);
-                    bitvec.push(true);
-                }
-                else {
-                    // 0 is encoded as 10 if previous bit was 0, otherwise 00
-                    let previous_bit = if bitvec.is_empty() {
-                        prev_bit
-                    }
-                    else {
-                        bitvec[bitvec.len() - 1]
-                    };
-
-                    if previous_bit {
-                        bitvec.push(false);
-                    }
-                    else {
-                        bitvec.push(true);
-                    }
-                    bitvec.push(false);
-                }
-
-                bit_count += 1;
-
-                // Omit clock bit between source bits 3 and 4 for address marks
-                if let EncodingVariant::AddressMark = encoding_type {
-                    if bit_count == 4 {
-                        // Clear the previous clock bit (which is between bit 3 and 4)
-                        bitvec.set(bitvec.len() - 2, false);
-                    }
-                }
-            }
-
-            // Reset bit_count for the next byte
-            bit_count = 0;
-        }
-
-        bitvec
+        FmCodec::encode(data, prev_bit, encoding_type)
     }
 
     fn find_marker(&self, marker: &MarkerEncoding, start: usize, limit: Option<usize>) -> Option<(usize, u16)> {
@@ -401,7 +366,7 @@ impl TrackCodec for FmCodec {
             shift_reg = (shift_reg << 1) | self.bit_vec[bi] as u64;
             shift_ct += 1;
 
-            let have_marker = (shift_reg & FM_MARKER_CLOCK_MASK) == FM_MARKER_CLOCK_PATTERN;
+            let have_marker = (shift_reg & FM_MARKER_CLOCK_MASK) == marker.bits & FM_MARKER_CLOCK_MASK;
             let have_data =
                 (shift_reg & FM_MARKER_DATA_MASK & marker.mask) == marker.bits & FM_MARKER_DATA_MASK & marker.mask;
 
@@ -524,51 +489,23 @@ impl FmCodec {
         Ok(())
     }
 
-    pub fn encode(data: &[u8], prev_bit: bool, encoding_type: EncodingVariant) -> BitVec {
-        let mut bitvec = BitVec::new();
-        let mut bit_count = 0;
+    /// Encode `data` as FM. Each data bit is preceded by a clock bit, which is always set for
+    /// `Data`. An `AddressMark` is encoded with the 0xC7 clock pattern used by the IDAM, DAM and
+    /// DDAM marks. Unlike MFM, FM clock bits do not depend on the previous bit, so `_prev_bit` is
+    /// unused.
+    pub fn encode(data: &[u8], _prev_bit: bool, encoding_type: EncodingVariant) -> BitVec {
+        let clock = match encoding_type {
+            EncodingVariant::Data => 0xFF,
+            EncodingVariant::AddressMark => FM_ADDRESS_MARK_CLOCK,
+        };
 
+        let mut bitvec = BitVec::with_capacity(data.len() * FM_BYTE_LEN);
         for &byte in data {
             for i in (0..8).rev() {
-                let bit = (byte & (1 << i)) != 0;
-                if bit {
-                    // 1 is encoded as 01
-                    bitvec.push(false);
-                    bitvec.push(true);
-                }
-                else {
-                    // 0 is encoded as 10 if previous bit was 0, otherwise 00
-                    let previous_bit = if bitvec.is_empty() {
-                        prev_bit
-                    }
-                    else {
-                        bitvec[bitvec.len() - 1]
-                    };
-
-                    if previous_bit {
-                        bitvec.push(false);
-                    }
-                    else {
-                        bitvec.push(true);
-                    }
-                    bitvec.push(false);
-                }
-
-                bit_count += 1;
-
-                // Omit clock bit between source bits 3 and 4 for address marks
-                if let EncodingVariant::AddressMark = encoding_type {
-                    if bit_count == 4 {
-                        // Clear the previous clock bit (which is between bit 3 and 4)
-                        bitvec.set(bitvec.len() - 2, false);
-                    }
-                }
+                bitvec.push(clock & (1 << i) != 0);
+                bitvec.push(byte & (1 << i) != 0);
             }
-
-            // Reset bit_count for the next byte
-            bit_count = 0;
         }
-
         bitvec
     }
 
@@ -724,84 +661,62 @@ impl Iterator for FmCodec {
     type Item = bool;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.bit_cursor >= (self.bit_vec.len() - 1) {
-            return None;
+        // The bit cursor should always be aligned to a clock bit. If it is not, we can try to nudge
+        // it to the next clock bit. If the next bit is also not a clock bit, we are in an
+        // unsynchronized region and can't really do anything about it.
+        if self.bit_cursor + 2 < self.bit_vec.len()
+            && !self.clock_map[self.bit_cursor]
+            && self.clock_map[self.bit_cursor + 1]
+        {
+            self.bit_cursor += 1;
         }
-
-        // The bit cursor should always be aligned to a clock bit.
-        // So retrieve the next bit which is the data bit, then point to the next clock.
-        let mut data_idx = self.bit_cursor + 1;
-        if data_idx > (self.bit_vec.len() - self.track_padding) {
-            // Wrap around to the beginning of the track
-            data_idx = 0;
+        if self.bit_cursor + 1 >= self.bit_vec.len() {
+            return None;
         }
 
-        let decoded_bit = if self.weak_enabled && self.weak_mask[data_idx] {
-            // Weak bits return random data
+        // Retrieve the data bit following the clock bit, or return a random bit if weak bits are
+        // enabled and the current bit is weak.
+        let decoded_bit = if self.weak_enabled && self.weak_mask[self.bit_cursor + 1] {
             rand::random()
         }
         else {
-            self.bit_vec[data_idx]
+            self.bit_vec[self.bit_cursor + 1]
         };
 
-        let new_cursor = data_idx + 1;
-        if new_cursor >= (self.bit_vec.len() - self.track_padding) {
-            // Wrap around to the beginning of the track
-            self.bit_cursor = 0;
-        }
-        else {
-            self.bit_cursor = new_cursor;
-        }
-
+        // Advance to the next clock bit.
+        self.bit_cursor += 2;
         Some(decoded_bit)
     }
 }
 
 impl Seek for FmCodec {
     fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
-        let (base, offset) = match pos {
-            // TODO: avoid casting to isize
-            SeekFrom::Start(offset) => (0, offset as isize),
-            SeekFrom::End(offset) => (self.bit_vec.len() as isize, offset as isize),
-            SeekFrom::Current(offset) => (self.bit_cursor as isize, offset as isize),
-        };
-
-        let new_pos = base.checked_add(offset).ok_or(Error::new(
-            ErrorKind::InvalidInput,
-            "invalid seek to a negative or overflowed position",
-        ))?;
-
-        let mut new_cursor = (new_pos as usize) << 1;
-        /*
-        let mut debug_vec = Vec::new();
-        for i in 0..5 {
-            debug_vec.push(self.clock_map[new_cursor - 2 + i]);
+        if self.bit_vec.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "Cannot seek on an empty bitstream"));
         }
 
-        log::debug!(
-            "seek() clock_map[{}]: {} {:?}",
-            new_cursor,
-            self.clock_map[new_cursor],
-            debug_vec
-        );
-        */
-
-        // If we have seeked to a data bit, nudge the bit cursor to the next clock bit.
-        if !self.clock_map[new_cursor] {
-            //log::trace!("seek(): nudging to next clock bit");
-            new_cursor += 1;
-        }
+        // Like MfmCodec, positions are bitcell offsets into the track.
+        let mut new_cursor = match pos {
+            SeekFrom::Start(offset) => offset as usize,
+            SeekFrom::End(offset) => self.bit_vec.len().saturating_add_signed(offset as isize),
+            SeekFrom::Current(offset) => self.bit_cursor.saturating_add_signed(offset as isize),
+        };
 
-        if new_cursor > self.bit_vec.len() {
+        if new_cursor >= self.bit_vec.len() {
             return Err(Error::new(
                 ErrorKind::InvalidInput,
                 "invalid seek to a negative or overflowed position",
             ));
         }
 
-        self.bit_cursor = new_cursor;
-        //log::trace!("seek(): new_pos: {}", self.bit_cursor);
+        // If we have seeked to a data bit, nudge the bit cursor to the next clock bit.
+        // Don't bother if the next bit isn't a clock bit either, as we're in some unsynchronized
+        // track region.
+        if new_cursor + 1 < self.bit_vec.len() && !self.clock_map[new_cursor] && self.clock_map[new_cursor + 1] {
+            new_cursor += 1;
+        }
 
+        self.bit_cursor = new_cursor;
         Ok(self.bit_cursor as u64)
     }
 }
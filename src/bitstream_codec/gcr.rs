@@ -127,7 +127,7 @@ impl TrackCodec for GcrCodec {
     }
 
     fn has_weak_bits(&self) -> bool {
-        !self.detect_weak_bits(6).0 > 0
+        self.detect_weak_bits(6).0 > 0
     }
 
     fn error_map(&self) -> &BitVec {
@@ -161,7 +161,7 @@ impl TrackCodec for MfmCodec {
     }
 
     fn has_weak_bits(&self) -> bool {
-        !self.detect_weak_bits(6).0 > 0
+        self.detect_weak_bits(6).0 > 0
     }
 
     fn error_map(&self) -> &BitVec {
@@ -241,26 +241,23 @@ impl TrackCodec for MfmCodec {
         let mut byte = 0;
         let mut cursor = index;
 
-        // If we are not pointing to a clock bit, advance to the next data bit.
-        // If the next bit is not a clock bit either, we are in an unsynchronized region, so don't
-        // bother adjusting the index
-        if !self.clock_map[cursor] && self.clock_map[cursor + 1] {
-            cursor += 1;
-        }
-
-        // Advance to the data bit.
-        cursor += 1;
-
         for _ in 0..8 {
-            let decoded_bit = if self.weak_enabled && !self.weak_mask.is_empty() && self.weak_mask[cursor] {
+            // If we are not pointing to a clock bit, advance to the next data bit.
+            // If the next bit is not a clock bit either, we are in an unsynchronized region, so don't
+            // bother adjusting the index. This is checked for every bit, as next() does, so that a
+            // phase change in the clock map within the byte is followed.
+            if !self.clock_map[cursor] && self.clock_map[cursor + 1] {
+                cursor += 1;
+            }
+            let decoded_bit = if self.weak_enabled && !self.weak_mask.is_empty() && self.weak_mask[cursor + 1] {
                 // Weak bits return random data
                 rand::random()
             }
             else {
-                self.bits[cursor]
+                self.bits[cursor + 1]
             };
             byte = (byte << 1) | decoded_bit as u8;
-            // Advance to next data bit.
+            // Advance to next clock bit.
             cursor += 2;
         }
         Some(byte)
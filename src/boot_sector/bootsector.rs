@@ -26,8 +26,18 @@
 */
 
 use crate::{
-    boot_sector::bpb::{BiosParameterBlock2, BiosParameterBlock3, BPB_OFFSET},
+    boot_sector::bpb::{
+        BiosParameterBlock2,
+        BiosParameterBlock3,
+        ExtendedBiosParameterBlock,
+        BPB_OFFSET,
+        EBPB_END,
+        EBPB_OFFSET,
+        OEM_NAME_LEN,
+        OEM_NAME_OFFSET,
+    },
     io::{Cursor, ReadSeek, ReadWriteSeek, Seek, SeekFrom, Write},
+    DiskChs,
     DiskImageError,
     StandardFormat,
 };
@@ -74,10 +84,16 @@ impl BootSignature {
 /// [BootSector] is designed to be created from byte data instead of directly from a [DiskImage].
 /// This allows flexibility in creating and interpreting a boot sector from other sources, such
 /// as in external .bin file.
+///
+/// The BPB fields can be modified with the setter methods, which keep the sector data returned by
+/// [BootSector::as_bytes] in sync. A modified boot sector can be written back to a disk image with
+/// [DiskImage::set_boot_sector](crate::DiskImage::set_boot_sector).
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug)]
 pub struct BootSector {
     pub(crate) bpb2: BiosParameterBlock2,
     pub(crate) bpb3: BiosParameterBlock3,
+    pub(crate) ebpb: Option<ExtendedBiosParameterBlock>,
     pub(crate) marker: BootSignature,
     pub(crate) sector_buf: Vec<u8>,
 }
@@ -101,6 +117,10 @@ impl BootSector {
         let bpb2 = BiosParameterBlock2::read(buffer)?;
         let bpb3 = BiosParameterBlock3::read(buffer)?;
 
+        // Read the extended BPB, if present. DOS 3.x boot sectors may have code here instead.
+        buffer.seek(SeekFrom::Start(EBPB_OFFSET))?;
+        let ebpb = Some(ExtendedBiosParameterBlock::read(buffer)?).filter(|ebpb| ebpb.is_valid());
+
         // Seek to the end and check the marker.
         buffer.seek(SeekFrom::End(-2))?;
         let mut marker = [0; 2];
@@ -109,6 +129,7 @@ impl BootSector {
         Ok(BootSector {
             bpb2,
             bpb3,
+            ebpb,
             marker: BootSignature::new(marker),
             sector_buf: sector_buf.to_vec(),
         })
@@ -168,17 +189,173 @@ impl BootSector {
         self.bpb3
     }
 
+    /// Return the extended BPB, if present.
+    pub fn ebpb(&self) -> Option<ExtendedBiosParameterBlock> {
+        self.ebpb
+    }
+
+    /// Replace the DOS 2.0 BPB.
+    pub fn set_bpb2(&mut self, bpb2: BiosParameterBlock2) -> Result<(), DiskImageError> {
+        self.bpb2 = bpb2;
+        self.sync_buffer()
+    }
+
+    /// Replace the DOS 3.0 BPB.
+    pub fn set_bpb3(&mut self, bpb3: BiosParameterBlock3) -> Result<(), DiskImageError> {
+        self.bpb3 = bpb3;
+        self.sync_buffer()
+    }
+
+    /// Return the OEM name, with trailing spaces removed.
+    pub fn oem_name(&self) -> String {
+        let bytes = &self.sector_buf[OEM_NAME_OFFSET..OEM_NAME_OFFSET + OEM_NAME_LEN];
+        String::from_utf8_lossy(bytes).trim_end().to_string()
+    }
+
+    /// Set the OEM name. The name must be ASCII and at most 8 characters; shorter names are
+    /// padded with spaces.
+    pub fn set_oem_name(&mut self, name: &str) -> Result<(), DiskImageError> {
+        if !name.is_ascii() || name.len() > OEM_NAME_LEN {
+            log::error!(
+                "set_oem_name(): OEM name must be up to {} ASCII characters",
+                OEM_NAME_LEN
+            );
+            return Err(DiskImageError::ParameterError);
+        }
+        let field = &mut self.sector_buf[OEM_NAME_OFFSET..OEM_NAME_OFFSET + OEM_NAME_LEN];
+        field.fill(b' ');
+        field[..name.len()].copy_from_slice(name.as_bytes());
+        Ok(())
+    }
+
+    /// Return the media descriptor byte.
+    pub fn media_descriptor(&self) -> u8 {
+        self.bpb2.media_descriptor
+    }
+
+    /// Set the media descriptor byte.
+    pub fn set_media_descriptor(&mut self, media_descriptor: u8) -> Result<(), DiskImageError> {
+        self.bpb2.media_descriptor = media_descriptor;
+        self.sync_buffer()
+    }
+
+    /// Return the number of sectors per FAT.
+    pub fn sectors_per_fat(&self) -> u16 {
+        self.bpb2.sectors_per_fat
+    }
+
+    /// Set the number of sectors per FAT. See [BootSector::recalculate_sectors_per_fat] to derive
+    /// the value from the other BPB parameters instead.
+    pub fn set_sectors_per_fat(&mut self, sectors_per_fat: u16) -> Result<(), DiskImageError> {
+        self.bpb2.sectors_per_fat = sectors_per_fat;
+        self.sync_buffer()
+    }
+
+    /// Recalculate the number of sectors per FAT from the other BPB parameters, assuming a FAT12
+    /// volume as used on floppy disks.
+    pub fn recalculate_sectors_per_fat(&mut self) -> Result<(), DiskImageError> {
+        let Some(sectors_per_fat) = self.bpb2.fat12_sectors_per_fat()
+        else {
+            log::error!("recalculate_sectors_per_fat(): BPB does not describe a usable FAT12 volume");
+            return Err(DiskImageError::ParameterError);
+        };
+        self.set_sectors_per_fat(sectors_per_fat)
+    }
+
+    /// Return the volume serial number, if the boot sector has an extended BPB.
+    pub fn volume_serial(&self) -> Option<u32> {
+        self.ebpb.map(|ebpb| ebpb.volume_serial)
+    }
+
+    /// Set the volume serial number. If the boot sector has no extended BPB, one is created, but
+    /// only if the boot sector's initial jump skips over the bytes it would occupy, so that boot
+    /// code is not overwritten.
+    pub fn set_volume_serial(&mut self, volume_serial: u32) -> Result<(), DiskImageError> {
+        let has_room = self.jump_target().is_some_and(|target| target >= EBPB_END);
+        match &mut self.ebpb {
+            Some(ebpb) => ebpb.volume_serial = volume_serial,
+            None if has_room => {
+                self.ebpb = Some(ExtendedBiosParameterBlock::new(volume_serial));
+            }
+            None => {
+                log::error!("set_volume_serial(): Boot sector has no room for an extended BPB");
+                return Err(DiskImageError::IncompatibleImage(
+                    "Boot sector code occupies the extended BPB area".to_string(),
+                ));
+            }
+        }
+        self.sync_buffer()
+    }
+
+    /// Return the volume label, with trailing spaces removed, if the boot sector has an extended
+    /// BPB that includes one.
+    pub fn volume_label(&self) -> Option<String> {
+        self.ebpb
+            .filter(|ebpb| ebpb.has_label())
+            .map(|ebpb| String::from_utf8_lossy(&ebpb.volume_label).trim_end().to_string())
+    }
+
+    /// Update the geometry fields of the BPB - sectors per track, number of heads and total
+    /// sectors - to describe the specified geometry, and recalculate the number of sectors per FAT
+    /// to match. This is useful for fixing images whose BPB disagrees with their physical geometry.
+    ///
+    /// If the geometry cannot be described by the BPB, an error is returned and the boot sector is
+    /// left unchanged.
+    pub fn set_geometry(&mut self, chs: DiskChs) -> Result<(), DiskImageError> {
+        let total_sectors = chs.c() as usize * chs.h() as usize * chs.s() as usize;
+        let Ok(total_sectors) = u16::try_from(total_sectors)
+        else {
+            log::error!("set_geometry(): Geometry {} exceeds the BPB's total sector count", chs);
+            return Err(DiskImageError::ParameterError);
+        };
+
+        // Validate the new geometry against a copy of the BPB before changing anything.
+        let mut bpb2 = self.bpb2;
+        bpb2.total_sectors = total_sectors;
+        let Some(sectors_per_fat) = bpb2.fat12_sectors_per_fat()
+        else {
+            log::error!(
+                "set_geometry(): Geometry {} does not describe a usable FAT12 volume",
+                chs
+            );
+            return Err(DiskImageError::ParameterError);
+        };
+        bpb2.sectors_per_fat = sectors_per_fat;
+
+        self.bpb2 = bpb2;
+        self.bpb3.sectors_per_track = chs.s() as u16;
+        self.bpb3.number_of_heads = chs.h() as u16;
+        self.sync_buffer()
+    }
+
     pub(crate) fn update_bpb_from_format(&mut self, format: StandardFormat) -> Result<(), DiskImageError> {
         self.bpb2 = BiosParameterBlock2::try_from(format)?;
         self.bpb3 = BiosParameterBlock3::try_from(format)?;
 
         // Update the internal buffer.
+        self.sync_buffer()
+    }
+
+    /// Return the offset targeted by a short jump at the start of the boot sector, if present.
+    fn jump_target(&self) -> Option<u64> {
+        match self.sector_buf.as_slice() {
+            [0xEB, rel, ..] => Some(2 + *rel as u64),
+            _ => None,
+        }
+    }
+
+    /// Write the parameter blocks back into the internal sector buffer.
+    fn sync_buffer(&mut self) -> Result<(), DiskImageError> {
         let mut cursor = Cursor::new(&mut self.sector_buf);
         cursor.seek(SeekFrom::Start(BPB_OFFSET))?;
 
         self.bpb2.write(&mut cursor)?;
         self.bpb3.write(&mut cursor)?;
 
+        if let Some(ebpb) = &self.ebpb {
+            cursor.seek(SeekFrom::Start(EBPB_OFFSET))?;
+            ebpb.write(&mut cursor)?;
+        }
         Ok(())
     }
 
@@ -210,6 +387,8 @@ impl BootSector {
 
     /// Dump the BPB values to a Write implementor for debugging purposes.
     pub fn dump_bpb<T: Write>(&self, buffer: &mut T) -> Result<(), crate::io::Error> {
+        writeln!(buffer, "OEM name: {}", self.oem_name())?;
+        writeln!(buffer)?;
         writeln!(buffer, "BIOS Parameter Block v2.0:")?;
         writeln!(buffer, "\tBytes per sector: {}", self.bpb2.bytes_per_sector)?;
         writeln!(buffer, "\tSectors per cluster: {}", self.bpb2.sectors_per_cluster)?;
@@ -225,6 +404,20 @@ impl BootSector {
         writeln!(buffer, "\tNumber of heads: {}", self.bpb3.number_of_heads)?;
         writeln!(buffer, "\tHidden sectors: {}", self.bpb3.hidden_sectors)?;
         writeln!(buffer)?;
+        if let Some(ebpb) = &self.ebpb {
+            writeln!(buffer, "Extended BIOS Parameter Block:")?;
+            writeln!(buffer, "\tDrive number: 0x{:02X}", ebpb.drive_number)?;
+            writeln!(
+                buffer,
+                "\tVolume serial: {:04X}-{:04X}",
+                ebpb.volume_serial >> 16,
+                ebpb.volume_serial & 0xFFFF
+            )?;
+            if let Some(label) = self.volume_label() {
+                writeln!(buffer, "\tVolume label: {}", label)?;
+            }
+            writeln!(buffer)?;
+        }
         writeln!(buffer, "Boot sector signature: {:02X?}", self.marker.bytes())?;
 
         if let Some(fmt) = self.standard_format() {
@@ -238,3 +431,69 @@ impl BootSector {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diskimage::DEFAULT_BOOT_SECTOR;
+
+    fn default_boot_sector() -> BootSector {
+        let mut bs = BootSector::new(&mut Cursor::new(DEFAULT_BOOT_SECTOR)).unwrap();
+        bs.update_bpb_from_format(StandardFormat::PcFloppy360).unwrap();
+        bs
+    }
+
+    #[test]
+    fn test_fat12_sectors_per_fat() {
+        for format in [
+            StandardFormat::PcFloppy360,
+            StandardFormat::PcFloppy720,
+            StandardFormat::PcFloppy1200,
+            StandardFormat::PcFloppy1440,
+        ] {
+            let bpb2 = BiosParameterBlock2::try_from(format).unwrap();
+            assert_eq!(bpb2.fat12_sectors_per_fat(), Some(bpb2.sectors_per_fat), "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_edit_bpb() {
+        let mut bs = default_boot_sector();
+        assert!(bs.ebpb().is_none());
+
+        bs.set_oem_name("MSDOS5.0").unwrap();
+        assert!(bs.set_oem_name("TOO LONG NAME").is_err());
+        bs.set_volume_serial(0x1234_ABCD).unwrap();
+        bs.set_geometry(DiskChs::new(80, 2, 9)).unwrap();
+        bs.set_media_descriptor(0xF9).unwrap();
+
+        // Re-parse the sector data to check the edits were written back.
+        let bs = BootSector::new(&mut Cursor::new(bs.as_bytes())).unwrap();
+        assert_eq!(bs.oem_name(), "MSDOS5.0");
+        assert_eq!(bs.volume_serial(), Some(0x1234_ABCD));
+        assert_eq!(bs.volume_label().as_deref(), Some("NO NAME"));
+        assert_eq!(bs.media_descriptor(), 0xF9);
+        assert_eq!(bs.bpb2().total_sectors, 1440);
+        assert_eq!(bs.sectors_per_fat(), 3);
+        assert_eq!(bs.standard_format(), Some(StandardFormat::PcFloppy720));
+        assert!(bs.boot_signature().is_valid());
+
+        // A geometry too small to hold the FAT12 structures is rejected without changing the BPB.
+        let mut bs = default_boot_sector();
+        let original = bs.as_bytes().to_vec();
+        assert!(bs.set_geometry(DiskChs::new(1, 1, 1)).is_err());
+        assert_eq!(bs.as_bytes(), &original[..]);
+        assert_eq!(bs.bpb2().total_sectors, 720);
+        assert_eq!(bs.bpb3().sectors_per_track, 9);
+    }
+
+    #[test]
+    fn test_ebpb_requires_room() {
+        let mut buf = default_boot_sector().as_bytes().to_vec();
+        // Jump to just past the DOS 3.0 BPB, where boot code would overlap the EBPB.
+        buf[1] = 0x1E;
+        let mut bs = BootSector::new(&mut Cursor::new(&buf)).unwrap();
+        assert!(bs.set_volume_serial(1).is_err());
+        assert_eq!(bs.as_bytes(), &buf[..]);
+    }
+}
@@ -41,6 +41,15 @@ use binrw::binrw;
 
 // Offset of the bios parameter block in the boot sector.
 pub const BPB_OFFSET: u64 = 0x0B;
+// Offset and length of the OEM name in the boot sector.
+pub const OEM_NAME_OFFSET: usize = 0x03;
+pub const OEM_NAME_LEN: usize = 8;
+// Offset of the extended bios parameter block in the boot sector of a FAT12 or FAT16 volume, and
+// the offset of the first byte following it.
+pub const EBPB_OFFSET: u64 = 0x24;
+pub const EBPB_END: u64 = 0x3E;
+// Size of a directory entry in the root directory.
+const DIR_ENTRY_SIZE: usize = 32;
 
 #[derive(Debug, Copy, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -84,6 +93,39 @@ impl BiosParameterBlock2 {
         }
         true
     }
+
+    /// Return the number of sectors occupied by the root directory.
+    pub fn root_dir_sectors(&self) -> usize {
+        if self.bytes_per_sector == 0 {
+            return 0;
+        }
+        (self.root_entries as usize * DIR_ENTRY_SIZE).div_ceil(self.bytes_per_sector as usize)
+    }
+
+    /// Calculate the number of sectors each FAT needs to map every cluster of a FAT12 volume,
+    /// given the other BPB parameters. This is the smallest FAT size that covers the clusters left
+    /// over once the FATs themselves are allocated.
+    /// Returns None if the BPB parameters do not describe a usable volume.
+    pub fn fat12_sectors_per_fat(&self) -> Option<u16> {
+        if self.bytes_per_sector == 0 || self.sectors_per_cluster == 0 || self.number_of_fats == 0 {
+            return None;
+        }
+        let bytes_per_sector = self.bytes_per_sector as usize;
+        let overhead = self.reserved_sectors as usize + self.root_dir_sectors();
+
+        let mut sectors_per_fat = 1;
+        loop {
+            let fat_sectors = self.number_of_fats as usize * sectors_per_fat;
+            let data_sectors = (self.total_sectors as usize).checked_sub(overhead + fat_sectors)?;
+            // Each FAT12 entry is 12 bits, and the first two entries are reserved.
+            let clusters = data_sectors / self.sectors_per_cluster as usize;
+            let needed = ((clusters + 2) * 3).div_ceil(2).div_ceil(bytes_per_sector);
+            if needed <= sectors_per_fat {
+                return u16::try_from(sectors_per_fat).ok();
+            }
+            sectors_per_fat = needed;
+        }
+    }
 }
 
 impl TryFrom<&BiosParameterBlock2> for StandardFormat {
@@ -282,3 +324,46 @@ impl TryFrom<StandardFormat> for BiosParameterBlock3 {
         Ok(pc_fmt)
     }
 }
+
+/// Extended BIOS Parameter Block introduced in MS-DOS 4.0
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[binrw]
+#[brw(little)]
+pub struct ExtendedBiosParameterBlock {
+    pub drive_number: u8,
+    pub flags: u8,
+    pub signature: u8,
+    pub volume_serial: u32,
+    pub volume_label: [u8; 11],
+    pub fs_type: [u8; 8],
+}
+
+impl ExtendedBiosParameterBlock {
+    /// The signature of an EBPB including the volume label and filesystem type.
+    pub const SIGNATURE: u8 = 0x29;
+    /// The signature of an early EBPB that ends after the volume serial number.
+    pub const SHORT_SIGNATURE: u8 = 0x28;
+
+    /// Create a new EBPB for a FAT12 floppy with the specified volume serial number.
+    pub fn new(volume_serial: u32) -> Self {
+        ExtendedBiosParameterBlock {
+            drive_number: 0,
+            flags: 0,
+            signature: Self::SIGNATURE,
+            volume_serial,
+            volume_label: *b"NO NAME    ",
+            fs_type: *b"FAT12   ",
+        }
+    }
+
+    /// Return true if the EBPB has a recognized signature.
+    pub fn is_valid(&self) -> bool {
+        self.signature == Self::SIGNATURE || self.signature == Self::SHORT_SIGNATURE
+    }
+
+    /// Return true if the EBPB includes the volume label and filesystem type fields.
+    pub fn has_label(&self) -> bool {
+        self.signature == Self::SIGNATURE
+    }
+}
@@ -39,4 +39,4 @@ pub mod bootsector;
 mod bpb;
 
 pub use bootsector::{BootSector, BootSignature};
-pub use bpb::{BiosParameterBlock2, BiosParameterBlock3};
+pub use bpb::{BiosParameterBlock2, BiosParameterBlock3, ExtendedBiosParameterBlock};
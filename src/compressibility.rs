@@ -0,0 +1,327 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/compressibility.rs
+
+    Reports how compressible each track's sector data is, to help choose
+    between raw and compressed output formats.
+*/
+
+//! Sector data compressibility statistics.
+//!
+//! [DiskImage::compressibility()] reads every sector in an image and measures how much of the
+//! data consists of repeated fill bytes and long runs, then estimates the size the data would
+//! occupy under the two compression schemes common to sector image formats:
+//!
+//! * Sector fill compression, as used by `IMD`, which stores a sector consisting of a single
+//!   repeated byte as that byte alone.
+//! * Run-length encoding, as used by `MSA` and by `TD0` in its non-advanced mode, which encodes
+//!   runs of repeated bytes anywhere in a track.
+//!
+//! Tracks whose sectors are all filled with the same byte are reported as blank. These are
+//! typically tracks that were formatted but never written, which is useful to know when deciding
+//! whether a dump can be trimmed.
+
+use crate::{
+    prelude::{DiskCh, RwScope},
+    track::DiskTrack,
+    types::DiskChsnQuery,
+    DiskImage,
+};
+use std::fmt::{Display, Formatter, Result};
+
+/// The minimum length of a run of identical bytes that run-length encoding will compress.
+pub const MIN_RUN_LEN: usize = 4;
+/// The number of bytes taken to encode a single run (marker, value and 16-bit length).
+const RUN_ENCODED_LEN: usize = 4;
+/// The marker byte that introduces a run. A literal marker byte must be encoded as a run.
+const RUN_MARKER: u8 = 0xE5;
+/// The number of bytes of per-sector overhead in a sector fill compressed format.
+const FILL_SECTOR_OVERHEAD: usize = 1;
+
+/// The compression scheme best suited to an image, as returned by
+/// [CompressibilityReport::advice()].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CompressionAdvice {
+    /// Compression would not save significant space. Prefer an uncompressed format.
+    Uncompressed,
+    /// Most of the savings come from sectors consisting of a single fill byte. Formats with
+    /// sector fill compression such as `IMD` are a good choice.
+    SectorFill,
+    /// The data contains runs of repeated bytes within sectors. Formats with run-length encoding
+    /// such as `TD0` or `MSA` are a good choice.
+    RunLength,
+}
+
+impl Display for CompressionAdvice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            CompressionAdvice::Uncompressed => write!(f, "Uncompressed"),
+            CompressionAdvice::SectorFill => write!(f, "Sector fill compression (IMD)"),
+            CompressionAdvice::RunLength => write!(f, "Run-length encoding (TD0, MSA)"),
+        }
+    }
+}
+
+/// Compressibility statistics for a single track.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackCompressibility {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    /// The number of sectors on the track.
+    pub sectors: usize,
+    /// The number of sectors whose data could not be read. These are not included in any other
+    /// statistics.
+    pub unreadable_sectors: usize,
+    /// The total length of the sector data read from the track, in bytes.
+    pub data_len: usize,
+    /// The number of sectors consisting of a single repeated byte.
+    pub fill_sectors: usize,
+    /// If every readable sector on the track consists of the same repeated byte, that byte.
+    pub fill_byte: Option<u8>,
+    /// The number of zero bytes in the sector data.
+    pub zero_bytes: usize,
+    /// The number of bytes in runs of at least [MIN_RUN_LEN] identical bytes.
+    pub run_bytes: usize,
+    /// The estimated length of the sector data with sector fill compression.
+    pub fill_len: usize,
+    /// The estimated length of the sector data with run-length encoding.
+    pub rle_len: usize,
+}
+
+impl TrackCompressibility {
+    fn new(track: &DiskTrack) -> Self {
+        let mut stats = TrackCompressibility {
+            ch: track.ch(),
+            ..Default::default()
+        };
+        let mut fill_bytes = Vec::new();
+
+        for entry in track.sector_list() {
+            stats.sectors += 1;
+            let data = match track.read_sector(DiskChsnQuery::from(entry.chsn), None, None, RwScope::DataOnly, false) {
+                Ok(result) if !result.not_found && !result.no_dam => result.data().to_vec(),
+                _ => {
+                    stats.unreadable_sectors += 1;
+                    continue;
+                }
+            };
+            stats.add_sector(&data, &mut fill_bytes);
+        }
+
+        let readable = stats.sectors - stats.unreadable_sectors;
+        if readable > 0 && fill_bytes.len() == readable && fill_bytes.iter().all(|b| *b == fill_bytes[0]) {
+            stats.fill_byte = Some(fill_bytes[0]);
+        }
+        stats
+    }
+
+    fn add_sector(&mut self, data: &[u8], fill_bytes: &mut Vec<u8>) {
+        self.data_len += data.len();
+        self.zero_bytes += data.iter().filter(|b| **b == 0).count();
+
+        match data.first() {
+            Some(first) if data.iter().all(|b| b == first) => {
+                self.fill_sectors += 1;
+                fill_bytes.push(*first);
+                self.fill_len += FILL_SECTOR_OVERHEAD + 1;
+            }
+            _ => self.fill_len += FILL_SECTOR_OVERHEAD + data.len(),
+        }
+
+        for run in data.chunk_by(|a, b| a == b) {
+            if run.len() >= MIN_RUN_LEN {
+                self.run_bytes += run.len();
+                self.rle_len += RUN_ENCODED_LEN;
+            }
+            else if run[0] == RUN_MARKER {
+                self.rle_len += RUN_ENCODED_LEN;
+            }
+            else {
+                self.rle_len += run.len();
+            }
+        }
+    }
+
+    /// Return true if every readable sector on the track consists of the same repeated byte.
+    /// Such a track was likely formatted but never written.
+    pub fn is_blank(&self) -> bool {
+        self.fill_byte.is_some()
+    }
+}
+
+/// The result of [DiskImage::compressibility()].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompressibilityReport {
+    /// Compressibility statistics for each track, in track order.
+    pub tracks: Vec<TrackCompressibility>,
+}
+
+impl CompressibilityReport {
+    /// Return the total length of the sector data in the image, in bytes.
+    pub fn data_len(&self) -> usize {
+        self.tracks.iter().map(|t| t.data_len).sum()
+    }
+
+    /// Return the estimated total length of the sector data with sector fill compression.
+    pub fn fill_len(&self) -> usize {
+        self.tracks.iter().map(|t| t.fill_len).sum()
+    }
+
+    /// Return the estimated total length of the sector data with run-length encoding.
+    pub fn rle_len(&self) -> usize {
+        self.tracks.iter().map(|t| t.rle_len).sum()
+    }
+
+    /// Return the estimated ratio of sector fill compressed to uncompressed data length.
+    pub fn fill_ratio(&self) -> f64 {
+        Self::ratio(self.fill_len(), self.data_len())
+    }
+
+    /// Return the estimated ratio of run-length encoded to uncompressed data length.
+    pub fn rle_ratio(&self) -> f64 {
+        Self::ratio(self.rle_len(), self.data_len())
+    }
+
+    /// Return an iterator over the tracks that are present but blank. See
+    /// [TrackCompressibility::is_blank()].
+    pub fn blank_tracks(&self) -> impl Iterator<Item = &TrackCompressibility> {
+        self.tracks.iter().filter(|t| t.is_blank())
+    }
+
+    /// Suggest a compression scheme for the image. Compression is suggested if it is estimated
+    /// to save at least `min_savings` of the data length, as a fraction from 0.0 to 1.0.
+    pub fn advice(&self, min_savings: f64) -> CompressionAdvice {
+        let fill_ratio = self.fill_ratio();
+        let rle_ratio = self.rle_ratio();
+
+        if 1.0 - fill_ratio.min(rle_ratio) < min_savings {
+            CompressionAdvice::Uncompressed
+        }
+        else if fill_ratio <= rle_ratio {
+            CompressionAdvice::SectorFill
+        }
+        else {
+            CompressionAdvice::RunLength
+        }
+    }
+
+    fn ratio(len: usize, data_len: usize) -> f64 {
+        if data_len > 0 {
+            len as f64 / data_len as f64
+        }
+        else {
+            1.0
+        }
+    }
+}
+
+impl Display for CompressibilityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(
+            f,
+            "Compressibility: {} bytes of sector data, sector fill {:.1}%, run-length {:.1}%",
+            self.data_len(),
+            self.fill_ratio() * 100.0,
+            self.rle_ratio() * 100.0
+        )?;
+        let blank = self.blank_tracks().count();
+        if blank > 0 {
+            writeln!(f, "  {} of {} tracks are blank", blank, self.tracks.len())?;
+        }
+        write!(f, "  Suggested compression: {}", self.advice(0.1))
+    }
+}
+
+impl DiskImage {
+    /// Measure how compressible the sector data of each track in the image is.
+    pub fn compressibility(&self) -> CompressibilityReport {
+        CompressibilityReport {
+            tracks: self.track_iter().map(TrackCompressibility::new).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prelude::*,
+        test_util::{add_sector, add_track, empty_disk},
+    };
+
+    fn test_disk(tracks: &[&[&[u8]]]) -> DiskImage {
+        let mut disk = empty_disk();
+        for (c, sectors) in tracks.iter().enumerate() {
+            let track = add_track(&mut disk, DiskCh::new(c as u16, 0));
+            for (s, data) in sectors.iter().enumerate() {
+                add_sector(
+                    track,
+                    DiskChsn::new(c as u16, 0, s as u8 + 1, 2),
+                    data,
+                    Default::default(),
+                );
+            }
+        }
+        disk
+    }
+
+    #[test]
+    fn test_compressibility() {
+        let fill = [0xF6; 512];
+        let mut runs = [0u8; 512];
+        for (i, b) in runs.iter_mut().enumerate().take(256) {
+            *b = i as u8;
+        }
+        let mut noise = [0u8; 512];
+        for (i, b) in noise.iter_mut().enumerate() {
+            *b = (i * 7 + i / 3) as u8;
+        }
+
+        let report = test_disk(&[&[&fill, &fill], &[&fill, &runs]]).compressibility();
+        assert_eq!(report.tracks.len(), 2);
+        assert!(report.tracks[0].is_blank());
+        assert_eq!(report.tracks[0].fill_byte, Some(0xF6));
+        assert_eq!(report.tracks[0].fill_len, 4);
+        assert_eq!(report.tracks[0].rle_len, 8);
+        assert!(!report.tracks[1].is_blank());
+        assert_eq!(report.tracks[1].fill_sectors, 1);
+        assert_eq!(report.tracks[1].zero_bytes, 257);
+        assert_eq!(report.tracks[1].run_bytes, 512 + 256);
+        assert_eq!(report.blank_tracks().count(), 1);
+        assert_eq!(report.advice(0.1), CompressionAdvice::RunLength);
+
+        let report = test_disk(&[&[&fill, &noise, &noise, &noise]]).compressibility();
+        assert_eq!(report.advice(0.1), CompressionAdvice::SectorFill);
+
+        let report = test_disk(&[&[&noise, &noise]]).compressibility();
+        assert_eq!(report.tracks[0].fill_sectors, 0);
+        assert_eq!(report.advice(0.1), CompressionAdvice::Uncompressed);
+    }
+}
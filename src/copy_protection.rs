@@ -32,6 +32,7 @@ use crate::{types::chs::DiskChsnQuery, DiskImage};
 use std::fmt::{Display, Formatter, Result};
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CopyProtectionScheme {
     FormasterCopyLock(u8),
     SoftguardSuperlok(u8),
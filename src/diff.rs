@@ -0,0 +1,424 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/diff.rs
+
+    Implements a sector-level comparison between two disk images, useful for
+    verifying round-trip conversions and comparing multiple dumps of a disk.
+*/
+use crate::{
+    file_parsers::FormatCaps,
+    prelude::{DiskCh, DiskChsn, RwScope},
+    track::DiskTrack,
+    DiskImage,
+};
+
+/// Identifies which of the two compared disk images a difference refers to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiffSide {
+    /// The image `diff()` was called on.
+    Left,
+    /// The image passed to `diff()`.
+    Right,
+}
+
+/// A single difference found between two tracks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrackDifference {
+    /// The track exists only in the image on the specified side.
+    OnlyIn(DiffSide),
+    /// The track has weak bits in one image but not the other. Only reported for tracks that do
+    /// not have per-sector weak bit masks.
+    WeakBits { left: bool, right: bool },
+}
+
+/// A single difference found between two sectors with the same ID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SectorDifference {
+    /// The sector exists only in the image on the specified side.
+    OnlyIn(DiffSide),
+    /// The sector could not be read from one or both images, so its contents were not compared.
+    /// Each flag is set if reading the sector from that image failed.
+    Unreadable { left: bool, right: bool },
+    /// The sector data differs. `differing_bytes` counts mismatches over the common length.
+    Data { left_len: usize, right_len: usize, differing_bytes: usize },
+    /// The address CRC status differs.
+    AddressCrc { left: bool, right: bool },
+    /// The data CRC status differs.
+    DataCrc { left: bool, right: bool },
+    /// The deleted address mark status differs.
+    DeletedMark { left: bool, right: bool },
+    /// The presence of a data address mark differs.
+    NoDam { left: bool, right: bool },
+    /// The sector weak bit masks differ.
+    WeakMask,
+}
+
+/// The differences found for a single track.
+#[derive(Clone, Debug)]
+pub struct TrackDiff {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    pub differences: Vec<TrackDifference>,
+}
+
+/// The differences found for a single sector.
+#[derive(Clone, Debug)]
+pub struct SectorDiff {
+    /// The physical cylinder and head of the track containing the sector.
+    pub ch: DiskCh,
+    /// The sector ID.
+    pub id: DiskChsn,
+    pub differences: Vec<SectorDifference>,
+}
+
+/// A structured report of the differences between two [DiskImage]s, as returned by
+/// [DiskImage::diff]. Sectors are matched by physical track and sector ID. When a track contains
+/// duplicate sector IDs, the n-th occurrence in one image is compared against the n-th occurrence
+/// in the other.
+#[derive(Clone, Debug, Default)]
+pub struct ImageDiff {
+    pub tracks:  Vec<TrackDiff>,
+    pub sectors: Vec<SectorDiff>,
+}
+
+impl ImageDiff {
+    /// Compare `left` against `right`.
+    pub(crate) fn new(left: &DiskImage, right: &DiskImage) -> Self {
+        let mut diff = ImageDiff::default();
+
+        for head in 0..2u8 {
+            let cylinders = left.track_map[head as usize]
+                .len()
+                .max(right.track_map[head as usize].len());
+
+            for cylinder in 0..cylinders {
+                let ch = DiskCh::new(cylinder as u16, head);
+                match (left.track(ch), right.track(ch)) {
+                    (Some(lt), Some(rt)) => diff.diff_tracks(ch, lt, rt),
+                    (Some(_), None) => diff.tracks.push(TrackDiff {
+                        ch,
+                        differences: vec![TrackDifference::OnlyIn(DiffSide::Left)],
+                    }),
+                    (None, Some(_)) => diff.tracks.push(TrackDiff {
+                        ch,
+                        differences: vec![TrackDifference::OnlyIn(DiffSide::Right)],
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        diff
+    }
+
+    /// Returns `true` if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty() && self.sectors.is_empty()
+    }
+
+    /// Remove differences that a disk image format with capabilities `caps` cannot represent,
+    /// such as CRC errors or weak bits, returning the differences that remain. This is useful to
+    /// compare an image against a copy of it saved in another format.
+    pub fn retain_expressible(mut self, caps: FormatCaps) -> Self {
+        let expressible = |difference: &SectorDifference| match difference {
+            SectorDifference::AddressCrc { .. } => caps.contains(FormatCaps::CAP_ADDRESS_CRC),
+            SectorDifference::DataCrc { .. } => caps.contains(FormatCaps::CAP_DATA_CRC),
+            SectorDifference::DeletedMark { .. } => caps.contains(FormatCaps::CAP_DATA_DELETED),
+            SectorDifference::NoDam { .. } => caps.contains(FormatCaps::CAP_NO_DAM),
+            SectorDifference::WeakMask => caps.contains(FormatCaps::CAP_WEAK_BITS),
+            _ => true,
+        };
+
+        for sector in self.sectors.iter_mut() {
+            // A sector with no DAM has no data to compare once a DAM has been written for it.
+            if sector
+                .differences
+                .iter()
+                .any(|d| matches!(d, SectorDifference::NoDam { .. }) && !expressible(d))
+            {
+                sector
+                    .differences
+                    .retain(|d| !matches!(d, SectorDifference::Data { .. }));
+            }
+            sector.differences.retain(expressible);
+        }
+        self.sectors.retain(|s| !s.differences.is_empty());
+
+        if !caps.contains(FormatCaps::CAP_WEAK_BITS) {
+            for track in self.tracks.iter_mut() {
+                track
+                    .differences
+                    .retain(|d| !matches!(d, TrackDifference::WeakBits { .. }));
+            }
+        }
+        self.tracks.retain(|t| !t.differences.is_empty());
+        self
+    }
+
+    /// Return a sorted, de-duplicated list of the physical tracks containing any difference.
+    pub fn differing_tracks(&self) -> Vec<DiskCh> {
+        let mut chs: Vec<DiskCh> = self
+            .tracks
+            .iter()
+            .map(|t| t.ch)
+            .chain(self.sectors.iter().map(|s| s.ch))
+            .collect();
+        chs.sort_by_key(|ch| (ch.c(), ch.h()));
+        chs.dedup();
+        chs
+    }
+
+    fn diff_tracks(&mut self, ch: DiskCh, left: &DiskTrack, right: &DiskTrack) {
+        let left_list = left.sector_list();
+        let mut right_list: Vec<Option<(usize, DiskChsn)>> = right
+            .sector_list()
+            .iter()
+            .enumerate()
+            .map(|(i, s)| Some((i, s.chsn)))
+            .collect();
+
+        // Per-sector weak masks are only available for MetaSector tracks. Otherwise, fall back to
+        // comparing the track-level weak bit status.
+        let sector_masks = left.as_metasector_track().is_some() && right.as_metasector_track().is_some();
+        if !sector_masks && left.has_weak_bits() != right.has_weak_bits() {
+            self.tracks.push(TrackDiff {
+                ch,
+                differences: vec![TrackDifference::WeakBits {
+                    left:  left.has_weak_bits(),
+                    right: right.has_weak_bits(),
+                }],
+            });
+        }
+
+        for (left_idx, entry) in left_list.iter().enumerate() {
+            // Consume the first unmatched sector with the same ID from the right track. As both
+            // lists are in track order, this pairs the n-th occurrence of an ID on each side.
+            let matched = right_list
+                .iter_mut()
+                .find(|r| r.is_some_and(|(_, chsn)| chsn == entry.chsn))
+                .and_then(|r| r.take());
+
+            let Some((right_idx, _)) = matched
+            else {
+                self.sectors.push(SectorDiff {
+                    ch,
+                    id: entry.chsn,
+                    differences: vec![SectorDifference::OnlyIn(DiffSide::Left)],
+                });
+                continue;
+            };
+
+            let differences = Self::diff_sectors(entry.chsn, (left, left_idx), (right, right_idx));
+            if !differences.is_empty() {
+                self.sectors.push(SectorDiff {
+                    ch,
+                    id: entry.chsn,
+                    differences,
+                });
+            }
+        }
+
+        for (_, id) in right_list.into_iter().flatten() {
+            self.sectors.push(SectorDiff {
+                ch,
+                id,
+                differences: vec![SectorDifference::OnlyIn(DiffSide::Right)],
+            });
+        }
+    }
+
+    /// Compare the sector at position `index` of each track's sector list.
+    fn diff_sectors(
+        id: DiskChsn,
+        (left, left_idx): (&DiskTrack, usize),
+        (right, right_idx): (&DiskTrack, usize),
+    ) -> Vec<SectorDifference> {
+        let mut differences = Vec::new();
+
+        let (lr, rr) = match (
+            left.read_sector_at(left_idx, RwScope::DataOnly),
+            right.read_sector_at(right_idx, RwScope::DataOnly),
+        ) {
+            (Ok(lr), Ok(rr)) => (lr, rr),
+            (lr, rr) => {
+                log::warn!(
+                    "diff_sectors(): Failed to read sector {}: left: {:?} right: {:?}",
+                    id,
+                    lr.as_ref().err(),
+                    rr.as_ref().err()
+                );
+                return vec![SectorDifference::Unreadable {
+                    left:  lr.is_err(),
+                    right: rr.is_err(),
+                }];
+            }
+        };
+
+        if lr.address_crc_error != rr.address_crc_error {
+            differences.push(SectorDifference::AddressCrc {
+                left:  lr.address_crc_error,
+                right: rr.address_crc_error,
+            });
+        }
+        if lr.data_crc_error != rr.data_crc_error {
+            differences.push(SectorDifference::DataCrc {
+                left:  lr.data_crc_error,
+                right: rr.data_crc_error,
+            });
+        }
+        if lr.deleted_mark != rr.deleted_mark {
+            differences.push(SectorDifference::DeletedMark {
+                left:  lr.deleted_mark,
+                right: rr.deleted_mark,
+            });
+        }
+        if lr.no_dam != rr.no_dam {
+            differences.push(SectorDifference::NoDam {
+                left:  lr.no_dam,
+                right: rr.no_dam,
+            });
+        }
+
+        // Reading a MetaSector randomizes its weak bits, so compare the stored data directly.
+        let left_raw = left.as_metasector_track().and_then(|t| t.raw_sector_data_at(left_idx));
+        let right_raw = right
+            .as_metasector_track()
+            .and_then(|t| t.raw_sector_data_at(right_idx));
+
+        let left_data = left_raw
+            .map(|(d, _)| d)
+            .unwrap_or_else(|| lr.read_buf.get(lr.data_range.clone()).unwrap_or_default());
+        let right_data = right_raw
+            .map(|(d, _)| d)
+            .unwrap_or_else(|| rr.read_buf.get(rr.data_range.clone()).unwrap_or_default());
+
+        let differing_bytes = left_data.iter().zip(right_data).filter(|(l, r)| l != r).count();
+        if differing_bytes > 0 || left_data.len() != right_data.len() {
+            differences.push(SectorDifference::Data {
+                left_len: left_data.len(),
+                right_len: right_data.len(),
+                differing_bytes,
+            });
+        }
+
+        if let (Some((_, left_mask)), Some((_, right_mask))) = (left_raw, right_raw) {
+            if left_mask != right_mask {
+                differences.push(SectorDifference::WeakMask);
+            }
+        }
+
+        differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_util::single_track_disk, types::SectorAttributes};
+
+    #[test]
+    fn test_diff_identical() {
+        let sectors = [(1, 0xF6, Default::default()), (2, 0xF6, Default::default())];
+        let diff = single_track_disk(&sectors).diff(&single_track_disk(&sectors));
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn test_diff_sectors() {
+        let crc_error = SectorAttributes {
+            data_error: true,
+            ..Default::default()
+        };
+        let left = single_track_disk(&[(1, 0xF6, Default::default()), (2, 0xF6, Default::default())]);
+        let right = single_track_disk(&[(1, 0x00, crc_error), (3, 0xF6, Default::default())]);
+
+        let diff = left.diff(&right);
+        assert!(diff.tracks.is_empty());
+        assert_eq!(diff.differing_tracks(), vec![DiskCh::new(0, 0)]);
+        assert_eq!(diff.sectors.len(), 3);
+
+        assert_eq!(diff.sectors[0].id.s(), 1);
+        assert!(diff.sectors[0].differences.contains(&SectorDifference::DataCrc {
+            left:  false,
+            right: true,
+        }));
+        assert!(diff.sectors[0].differences.contains(&SectorDifference::Data {
+            left_len: 512,
+            right_len: 512,
+            differing_bytes: 512,
+        }));
+        assert_eq!(diff.sectors[1].id.s(), 2);
+        assert_eq!(
+            diff.sectors[1].differences,
+            vec![SectorDifference::OnlyIn(DiffSide::Left)]
+        );
+        assert_eq!(diff.sectors[2].id.s(), 3);
+        assert_eq!(
+            diff.sectors[2].differences,
+            vec![SectorDifference::OnlyIn(DiffSide::Right)]
+        );
+    }
+
+    #[test]
+    fn test_diff_duplicate_sectors() {
+        let left = single_track_disk(&[(1, 0xF6, Default::default()), (1, 0xF6, Default::default())]);
+        let right = single_track_disk(&[(1, 0xF6, Default::default()), (1, 0x00, Default::default())]);
+
+        // Only the second occurrence of the duplicated ID differs.
+        let diff = left.diff(&right);
+        assert_eq!(diff.sectors.len(), 1);
+        assert_eq!(
+            diff.sectors[0].differences,
+            vec![SectorDifference::Data {
+                left_len: 512,
+                right_len: 512,
+                differing_bytes: 512,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_retain_expressible() {
+        let crc_error = SectorAttributes {
+            data_error: true,
+            ..Default::default()
+        };
+        let left = single_track_disk(&[(1, 0xF6, crc_error), (2, 0xF6, crc_error)]);
+        let right = single_track_disk(&[(1, 0xF6, Default::default()), (2, 0x00, Default::default())]);
+
+        let diff = left.diff(&right);
+        assert_eq!(diff.sectors.len(), 2);
+        assert!(!diff.clone().retain_expressible(FormatCaps::CAP_DATA_CRC).is_empty());
+
+        let diff = diff.retain_expressible(FormatCaps::empty());
+        assert_eq!(diff.sectors.len(), 1);
+        assert_eq!(diff.sectors[0].id.s(), 2);
+        assert!(matches!(
+            diff.sectors[0].differences[..],
+            [SectorDifference::Data { .. }]
+        ));
+    }
+}
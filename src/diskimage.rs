@@ -39,6 +39,7 @@ use crate::{
     boot_sector::BootSector,
     containers::DiskImageContainer,
     detect::detect_container_format,
+    diff::ImageDiff,
     file_parsers::{
         filter_writable,
         formats_from_caps,
@@ -48,26 +49,55 @@ use crate::{
         ParserReadOptions,
     },
     io::ReadSeek,
+    load_telemetry::LoadTelemetry,
+    merge::merge_images,
+    random::{random_bit, WeakBitGenerator},
+    rotation::DiskRotation,
     source_map::{NullSourceMap, OptionalSourceMap, SourceMap, SourceValue},
-    track::{fluxstream::FluxStreamTrack, metasector::MetaSectorTrack, DiskTrack, Track, TrackAnalysis},
+    track::{
+        fluxstream::FluxStreamTrack,
+        gap_analysis::TrackGapReport,
+        interleave::SkewMap,
+        memory::MemoryUsage,
+        metasector::MetaSectorTrack,
+        DiskTrack,
+        SectorRef,
+        Track,
+        TrackAnalysis,
+        TrackInfo,
+    },
+    track_mapping::TrackMapping,
     track_schema::{system34::System34Standard, TrackMetadata, TrackSchema},
     types::{
         chs::*,
         standard_format::StandardFormat,
         BitStreamTrackParams,
+        DecodePolicy,
         DiskAnalysis,
         DiskDescriptor,
         DiskImageFlags,
+        DiskImageMetadata,
         DiskSelection,
         FluxStreamTrackParams,
         MetaSectorTrackParams,
+        NextIdResult,
+        ReadSectorRef,
         ReadSectorResult,
         ReadTrackResult,
+        RecoveryOptions,
+        RetentionPolicy,
         RwScope,
+        SectorLimits,
         SharedDiskContext,
+        SizeMismatchPolicy,
+        SourceFormatInfo,
+        SourceQuirks,
         TrackDataEncoding,
         TrackDataRate,
         TrackDataResolution,
+        TrackDensity,
+        TrackFormatting,
+        WeakBitPolicy,
         WriteSectorResult,
     },
     util,
@@ -75,6 +105,7 @@ use crate::{
     FoxHashMap,
     FoxHashSet,
     LoadingCallback,
+    LoadingStage,
     LoadingStatus,
 };
 use bit_vec::BitVec;
@@ -83,7 +114,12 @@ use std::{
     io::Cursor,
     path::Path,
     sync::{Arc, Mutex, RwLock},
+    time::Duration,
 };
+use web_time::Instant;
+
+#[cfg(not(target_arch = "wasm32"))]
+use {crate::load_handle::LoadHandle, std::path::PathBuf};
 
 pub(crate) const DEFAULT_BOOT_SECTOR: &[u8] = include_bytes!("../resources/bootsector.bin");
 
@@ -104,8 +140,8 @@ pub struct DiskImage {
     pub(crate) flags: DiskImageFlags,
     /// The standard format of the disk image, if it adheres to one. (Nonstandard images will be None)
     pub(crate) standard_format: Option<StandardFormat>,
-    /// The image format the disk image was sourced from, if any
-    pub(crate) source_format: Option<DiskImageFileFormat>,
+    /// A description of the image file the disk image was sourced from, if any
+    pub(crate) source_info: Option<SourceFormatInfo>,
     /// A flag indicating if this disk image is allowed to contain multiple track resolution types.
     /// Attempts to add tracks with a different resolution will fail if this is false.
     pub(crate) multires: bool,
@@ -114,8 +150,8 @@ pub struct DiskImage {
     pub(crate) resolution: FoxHashSet<TrackDataResolution>,
     /// A [DiskDescriptor] describing this image with more thorough parameters.
     pub(crate) descriptor: DiskDescriptor,
-    /// A k/v store of metadata. Keys are normalized to a standard set of strings; see the metadata
-    /// module for key names.
+    /// A k/v store of metadata. Keys are normalized to a standard set of strings; see the
+    /// [metadata](crate::types::metadata) module for key names.
     pub(crate) metadata: FoxHashMap<String, String>,
     /// A structure containing information about the disks internal consistency. Used to construct image_caps.
     pub(crate) analysis: DiskAnalysis,
@@ -128,12 +164,21 @@ pub struct DiskImage {
     /// An array of vectors containing indices into the track pool. The first index is the head
     /// number, the second is the cylinder number.
     pub(crate) track_map: [Vec<usize>; 2],
+    /// A mapping between the physical cylinders of a drive and the tracks in `track_map`.
+    pub(crate) track_mapping: TrackMapping,
     /// A shared context for the disk image, accessible by Tracks.
     #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) shared: Option<Arc<Mutex<SharedDiskContext>>>,
+    /// A copy of the [DecodePolicy] held in the shared context, so that track access can check
+    /// it without locking the context.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) decode_policy: DecodePolicy,
     /// A sourcemap for the disk image. This is not serialized as it is not necessary
     #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) source_map: Option<Box<dyn OptionalSourceMap>>,
+    /// Timings recorded while loading the disk image, if it was loaded from an image file.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) load_telemetry: Option<LoadTelemetry>,
 }
 
 impl Default for DiskImage {
@@ -141,7 +186,7 @@ impl Default for DiskImage {
         Self {
             flags: DiskImageFlags::empty(),
             standard_format: None,
-            source_format: None,
+            source_info: None,
             multires: false,
             resolution: Default::default(),
             descriptor: DiskDescriptor::default(),
@@ -151,8 +196,11 @@ impl Default for DiskImage {
             volume_name: None,
             track_pool: Vec::new(),
             track_map: [Vec::new(), Vec::new()],
+            track_mapping: TrackMapping::default(),
             shared: Some(Arc::new(Mutex::new(SharedDiskContext::default()))),
+            decode_policy: DecodePolicy::default(),
             source_map: Some(Box::new(NullSourceMap::new())),
+            load_telemetry: None,
         }
     }
 }
@@ -173,7 +221,7 @@ impl DiskImage {
             standard_format: Some(disk_format),
             descriptor: disk_format.descriptor(),
             metadata: FoxHashMap::new(),
-            source_format: None,
+            source_info: None,
             multires: false,
             resolution: FoxHashSet::new(),
             analysis: DiskAnalysis {
@@ -186,13 +234,17 @@ impl DiskImage {
                 overlapped: false,
                 consistent_sector_size: Some(2),
                 consistent_track_length: Some(disk_format.chs().s() as u32),
+                unformatted_tracks: 0,
             },
             boot_sector: None,
             volume_name: None,
             track_pool: Vec::new(),
             track_map: [Vec::new(), Vec::new()],
+            track_mapping: TrackMapping::default(),
             shared: Some(Arc::new(Mutex::new(SharedDiskContext::default()))),
+            decode_policy: DecodePolicy::default(),
             source_map: Some(Box::new(NullSourceMap::new())),
+            load_telemetry: None,
         }
     }
 
@@ -225,7 +277,55 @@ impl DiskImage {
             .map(move |track_idx| self.track_pool[track_idx].ch())
     }
 
+    /// Return an iterator over the tracks in the image, in cylinder order, with the tracks of
+    /// each head in turn for each cylinder.
+    pub fn iter_tracks(&self) -> impl Iterator<Item = (DiskCh, &dyn Track)> + '_ {
+        self.track_iter().map(|track| (track.ch(), track.as_ref()))
+    }
+
+    /// Return an iterator over mutable references to the tracks in the image, in the same order
+    /// as [DiskImage::iter_tracks()].
+    pub fn iter_tracks_mut(&mut self) -> impl Iterator<Item = (DiskCh, &mut dyn Track)> + '_ {
+        let order: Vec<usize> = self.track_idx_iter().collect();
+        let mut pool: Vec<Option<&mut DiskTrack>> = self.track_pool.iter_mut().map(Some).collect();
+
+        order
+            .into_iter()
+            .filter_map(move |track_idx| pool.get_mut(track_idx).and_then(Option::take))
+            .map(|track| (track.ch(), track.as_mut() as &mut dyn Track))
+    }
+
+    /// Return an iterator over every sector in the image, in track order, and then in the order
+    /// sectors appear on each track.
+    pub fn sectors(&self) -> impl Iterator<Item = SectorRef<'_>> + '_ {
+        self.iter_tracks().flat_map(|(ch, track)| {
+            track
+                .sector_list()
+                .into_iter()
+                .enumerate()
+                .map(move |(index, entry)| SectorRef {
+                    ch,
+                    index,
+                    entry,
+                    track,
+                })
+        })
+    }
+
+    /// Visit every sector in the image in the order of [DiskImage::sectors()], reading each
+    /// sector with the specified [RwScope] and passing the result of the read to `visitor`.
+    pub fn for_each_sector<F>(&self, scope: RwScope, mut visitor: F)
+    where
+        F: FnMut(&SectorRef, Result<ReadSectorResult, DiskImageError>),
+    {
+        for sector in self.sectors() {
+            let result = sector.read(scope);
+            visitor(&sector, result);
+        }
+    }
+
     pub fn track(&self, ch: DiskCh) -> Option<&DiskTrack> {
+        self.prefetch_after(ch);
         self.track_map[ch.h() as usize]
             .get(ch.c() as usize)
             .and_then(|&track_idx| self.track_pool.get(track_idx))
@@ -281,31 +381,77 @@ impl DiskImage {
         Ok(image)
     }
 
+    /// Load a disk image from `image_io`, detecting its format. `image_path` is used as a hint
+    /// for format detection, and is required to load a KryoFlux set from a directory.
+    ///
+    /// If a `callback` is provided, it receives each [LoadingStage] of the load, per-track and
+    /// percentage progress from file parsers that support it, and a final
+    /// [LoadingStatus::Complete] or [LoadingStatus::Error].
     pub fn load<RS: ReadSeek>(
         image_io: &mut RS,
         image_path: Option<&Path>,
         disk_selection: Option<DiskSelection>,
         callback: Option<LoadingCallback>,
     ) -> Result<Self, DiskImageError> {
+        DiskImage::load_with_options(
+            image_io,
+            image_path,
+            disk_selection,
+            &ParserReadOptions::default(),
+            callback,
+        )
+    }
+
+    /// Load a disk image from `image_io` as [DiskImage::load] does, passing `options` to the file
+    /// parser. Use this to load a flux image with a lazy [DecodePolicy].
+    pub fn load_with_options<RS: ReadSeek>(
+        image_io: &mut RS,
+        image_path: Option<&Path>,
+        disk_selection: Option<DiskSelection>,
+        options: &ParserReadOptions,
+        callback: Option<LoadingCallback>,
+    ) -> Result<Self, DiskImageError> {
+        if let Some(ref callback_fn) = callback {
+            callback_fn(LoadingStatus::Stage(LoadingStage::Detecting));
+        }
+        let result = DiskImage::load_inner(image_io, image_path, disk_selection, options, callback.clone());
+        DiskImage::report_load_result(&result, callback);
+        result
+    }
+
+    /// Report the final status of a load to `callback`.
+    fn report_load_result(result: &Result<Self, DiskImageError>, callback: Option<LoadingCallback>) {
+        if let Some(callback_fn) = callback {
+            match result {
+                Ok(_) => callback_fn(LoadingStatus::Complete),
+                Err(_) => callback_fn(LoadingStatus::Error),
+            }
+        }
+    }
+
+    fn load_inner<RS: ReadSeek>(
+        image_io: &mut RS,
+        image_path: Option<&Path>,
+        disk_selection: Option<DiskSelection>,
+        options: &ParserReadOptions,
+        callback: Option<LoadingCallback>,
+    ) -> Result<Self, DiskImageError> {
+        let detect_start = Instant::now();
         let container = DiskImage::detect_format(image_io, image_path.clone())?;
+        let detect_time = detect_start.elapsed();
         log::debug!("load(): Detected format: {:?}", container);
+        options.check_cancelled()?;
 
         // TODO: DiskImage should probably not concern itself with archives or disk sets...
         //       We should probably move most of this into an ImageLoader interface similar to
         //       ImageBuilder
         match container {
             DiskImageContainer::File(format, _path) => {
-                let mut image = DiskImage::default();
-                format.load_image(image_io, &mut image, &ParserReadOptions::default(), callback)?;
-                image.post_load_process();
-                Ok(image)
+                DiskImage::load_format(format, image_io, detect_time, options, callback)
             }
             DiskImageContainer::ResolvedFile(format, file_vec, _path, _archive_path) => {
                 let mut cursor = Cursor::new(file_vec);
-                let mut image = DiskImage::default();
-                format.load_image(&mut cursor, &mut image, &ParserReadOptions::default(), callback)?;
-                image.post_load_process();
-                Ok(image)
+                DiskImage::load_format(format, &mut cursor, detect_time, options, callback)
             }
             DiskImageContainer::Archive(_archive_format, _containers, _path) => {
                 // We should have received any single-file archives as ResolvedFiles, so this
@@ -348,16 +494,25 @@ impl DiskImage {
                     // append tracks to them as we go.
                     let mut image = DiskImage::default();
                     image.descriptor.geometry = disk.geometry;
+                    image.set_decode_policy(options.decode_policy());
+                    image.set_dd_band_normalization(options.dd_band_normalization());
+                    image.load_telemetry = Some(LoadTelemetry::default());
+                    let parse_start = Instant::now();
 
                     if let Some(ref callback_fn) = callback {
                         // Let caller know to show a progress bar
                         callback_fn(LoadingStatus::ProgressSupport);
+                        callback_fn(LoadingStatus::Stage(LoadingStage::Reading));
                     }
 
                     // Enable source map.
                     image.assign_source_map(true);
 
+                    // Read every stream file before decoding, so that the tracks may be decoded in parallel.
+                    let mut flux_tracks = Vec::new();
+                    let mut next_ch = KfxFormat::next_ch(&image);
                     for (fi, file_path) in disk.file_set.iter().enumerate() {
+                        options.check_cancelled()?;
                         let mut file_vec = crate::containers::zip::extract_file(image_io, &file_path.clone())?;
                         let mut cursor = Cursor::new(&mut file_vec);
                         log::debug!("load(): Loading Kryoflux stream file from zip: {:?}", file_path);
@@ -371,8 +526,8 @@ impl DiskImage {
 
                         // We won't give the callback to the kryoflux loader - instead we will call it here ourselves
                         // updating percentage complete as a fraction of files loaded.
-                        match KfxFormat::load_image(&mut cursor, &mut image, &ParserReadOptions::default(), None) {
-                            Ok(_) => {}
+                        match KfxFormat::read_track(&mut cursor, &mut image, next_ch) {
+                            Ok(flux_track) => flux_tracks.push((flux_track, next_ch)),
                             Err(e) => {
                                 // It's okay to fail if we have already added the standard number of tracks to an image.
                                 log::error!("load(): Error loading Kryoflux stream file: {:?}", e);
@@ -382,16 +537,25 @@ impl DiskImage {
                         }
 
                         if let Some(ref callback_fn) = callback {
+                            callback_fn(LoadingStatus::Track {
+                                ch:    next_ch,
+                                index: fi,
+                                total: disk.file_set.len(),
+                            });
                             let completion = (fi + 1) as f64 / disk.file_set.len() as f64;
                             callback_fn(LoadingStatus::Progress(completion));
                         }
+                        next_ch.seek_next_track(image.geometry());
                     }
 
-                    if let Some(callback_fn) = callback {
-                        callback_fn(LoadingStatus::Complete);
+                    if let Some(ref callback_fn) = callback {
+                        callback_fn(LoadingStatus::Stage(LoadingStage::Decoding));
+                    }
+                    if let Err(e) = KfxFormat::add_tracks(&mut image, flux_tracks) {
+                        log::error!("load(): Error decoding Kryoflux stream file: {:?}", e);
                     }
 
-                    image.post_load_process();
+                    image.finish_load(detect_time, parse_start.elapsed(), callback.as_ref());
                     Ok(image)
                 }
                 else {
@@ -424,11 +588,23 @@ impl DiskImage {
                     let mut image = DiskImage::default();
                     // Set the geometry of the disk image to the geometry of the Kryoflux set.
                     image.descriptor.geometry = set_ch;
+                    image.set_decode_policy(options.decode_policy());
+                    image.set_dd_band_normalization(options.dd_band_normalization());
+                    image.load_telemetry = Some(LoadTelemetry::default());
+                    let parse_start = Instant::now();
+
+                    if let Some(ref callback_fn) = callback {
+                        callback_fn(LoadingStatus::Stage(LoadingStage::Reading));
+                    }
 
                     // Enable source map.
                     image.assign_source_map(true);
 
+                    // Read every stream file before decoding, so that the tracks may be decoded in parallel.
+                    let mut flux_tracks = Vec::new();
+                    let mut next_ch = KfxFormat::next_ch(&image);
                     for (fi, file_path) in file_set.iter().enumerate() {
+                        options.check_cancelled()?;
                         // Reading the entire file in one go and wrapping in a cursor is much faster
                         // than a BufReader.
                         let mut file_vec = std::fs::read(file_path.clone())?;
@@ -445,8 +621,8 @@ impl DiskImage {
 
                         // We won't give the callback to the kryoflux loader - instead we will call it here ourselves
                         // updating percentage complete as a fraction of files loaded.
-                        match KfxFormat::load_image(&mut cursor, &mut image, &ParserReadOptions::default(), None) {
-                            Ok(_) => {}
+                        match KfxFormat::read_track(&mut cursor, &mut image, next_ch) {
+                            Ok(flux_track) => flux_tracks.push((flux_track, next_ch)),
                             Err(e) => {
                                 // It's okay to fail if we have already added the standard number of tracks to an image.
                                 log::error!("load(): Error loading Kryoflux stream file: {:?}", e);
@@ -456,18 +632,28 @@ impl DiskImage {
                         }
 
                         if let Some(ref callback_fn) = callback {
+                            callback_fn(LoadingStatus::Track {
+                                ch:    next_ch,
+                                index: fi,
+                                total: file_set.len(),
+                            });
                             let completion = (fi + 1) as f64 / file_set.len() as f64;
                             callback_fn(LoadingStatus::Progress(completion));
                         }
+                        next_ch.seek_next_track(image.geometry());
+                    }
+
+                    if let Some(ref callback_fn) = callback {
+                        callback_fn(LoadingStatus::Stage(LoadingStage::Decoding));
+                    }
+                    if let Err(e) = KfxFormat::add_tracks(&mut image, flux_tracks) {
+                        log::error!("load(): Error decoding Kryoflux stream file: {:?}", e);
                     }
 
                     //let ch = DiskCh::new(build_image.track_map[0].len() as u16, build_image.track_map.len() as u8);
                     //build_image.descriptor.geometry = ch;
 
-                    if let Some(callback_fn) = callback {
-                        callback_fn(LoadingStatus::Complete);
-                    }
-                    image.post_load_process();
+                    image.finish_load(detect_time, parse_start.elapsed(), callback.as_ref());
                     Ok(image)
                 }
                 else {
@@ -478,6 +664,44 @@ impl DiskImage {
         }
     }
 
+    /// Load a single image file of the specified format, recording load telemetry.
+    fn load_format<RS: ReadSeek>(
+        format: DiskImageFileFormat,
+        image_io: &mut RS,
+        detect_time: Duration,
+        options: &ParserReadOptions,
+        callback: Option<LoadingCallback>,
+    ) -> Result<Self, DiskImageError> {
+        let mut image = DiskImage::default();
+        image.set_decode_policy(options.decode_policy());
+        image.set_dd_band_normalization(options.dd_band_normalization());
+        image.load_telemetry = Some(LoadTelemetry::default());
+
+        let parse_start = Instant::now();
+        if let Some(ref callback_fn) = callback {
+            callback_fn(LoadingStatus::Stage(LoadingStage::Reading));
+        }
+        format.load_image(image_io, &mut image, options, callback.clone())?;
+        options.check_cancelled()?;
+        image.finish_load(detect_time, parse_start.elapsed(), callback.as_ref());
+        Ok(image)
+    }
+
+    /// Run post-load processing on a newly loaded image, and complete its load telemetry.
+    fn finish_load(&mut self, detect_time: Duration, parse_time: Duration, callback: Option<&LoadingCallback>) {
+        if let Some(callback_fn) = callback {
+            callback_fn(LoadingStatus::Stage(LoadingStage::Analyzing));
+        }
+        let post_start = Instant::now();
+        self.post_load_process();
+        let post_time = post_start.elapsed();
+
+        if let Some(telemetry) = &mut self.load_telemetry {
+            telemetry.finish(detect_time, parse_time, post_time);
+        }
+    }
+
+    /// Load a disk image from `image_io` asynchronously. See [DiskImage::load].
     #[cfg(feature = "async")]
     pub async fn load_async<RS: ReadSeek>(
         image_io: &mut RS,
@@ -485,21 +709,51 @@ impl DiskImage {
         disk_selection: Option<DiskSelection>,
         callback: Option<LoadingCallback>,
     ) -> Result<Self, DiskImageError> {
+        if let Some(ref callback_fn) = callback {
+            callback_fn(LoadingStatus::Stage(LoadingStage::Detecting));
+        }
+        let result = DiskImage::load_async_inner(image_io, image_path, disk_selection, callback.clone()).await;
+        DiskImage::report_load_result(&result, callback);
+        result
+    }
+
+    /// Load a disk image from `image_io` on a background thread, returning a [LoadHandle] that may
+    /// be polled for progress and the result without blocking. The arguments are as for
+    /// [DiskImage::load]; `callback` is invoked from the background thread.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_in_background<RS: ReadSeek + Send + 'static>(
+        image_io: RS,
+        image_path: Option<PathBuf>,
+        disk_selection: Option<DiskSelection>,
+        callback: Option<LoadingCallback>,
+    ) -> LoadHandle {
+        LoadHandle::spawn(image_io, image_path, disk_selection, callback)
+    }
+
+    #[cfg(feature = "async")]
+    async fn load_async_inner<RS: ReadSeek>(
+        image_io: &mut RS,
+        image_path: Option<&Path>,
+        disk_selection: Option<DiskSelection>,
+        callback: Option<LoadingCallback>,
+    ) -> Result<Self, DiskImageError> {
+        let detect_start = Instant::now();
         let container = DiskImage::detect_format(image_io, image_path)?;
+        let detect_time = detect_start.elapsed();
 
         match container {
             DiskImageContainer::File(format, _) => {
-                let mut image = DiskImage::default();
-                format.load_image(image_io, &mut image, &ParserReadOptions::default(), callback)?;
-                image.post_load_process();
-                Ok(image)
+                DiskImage::load_format(format, image_io, detect_time, &ParserReadOptions::default(), callback)
             }
             DiskImageContainer::ResolvedFile(format, file_vec, _path, _archive_path) => {
                 let mut cursor = Cursor::new(file_vec);
-                let mut image = DiskImage::default();
-                format.load_image(&mut cursor, &mut image, &ParserReadOptions::default(), callback)?;
-                image.post_load_process();
-                Ok(image)
+                DiskImage::load_format(
+                    format,
+                    &mut cursor,
+                    detect_time,
+                    &ParserReadOptions::default(),
+                    callback,
+                )
             }
             DiskImageContainer::Archive(_archive, _items, _) => {
                 // We should have received any single-file archives as ResolvedFiles, so this
@@ -569,10 +823,6 @@ impl DiskImage {
                             }
                         }
 
-                        if let Some(callback_fn) = callback {
-                            callback_fn(LoadingStatus::Complete);
-                        }
-
                         // Unwrap image from Arc
                         let mut image = Arc::try_unwrap(image_arc)
                             .map_err(|_| DiskImageError::SyncError("Failed to unwrap image from Arc".to_string()))?
@@ -620,7 +870,12 @@ impl DiskImage {
                     let mut image = DiskImage::default();
                     // Set the geometry of the disk image to the geometry of the Kryoflux set.
                     image.descriptor.geometry = set_ch;
+                    image.load_telemetry = Some(LoadTelemetry::default());
+                    let parse_start = Instant::now();
 
+                    // Read every stream file before decoding, so that the tracks may be decoded in parallel.
+                    let mut flux_tracks = Vec::new();
+                    let mut next_ch = KfxFormat::next_ch(&image);
                     for (fi, file_path) in file_set.iter().enumerate() {
                         // Reading the entire file in one go and wrapping in a cursor is much faster
                         // than a BufReader.
@@ -631,8 +886,8 @@ impl DiskImage {
 
                         // We won't give the callback to the kryoflux loader - instead we will call it here ourselves
                         // updating percentage complete as a fraction of files loaded.
-                        match KfxFormat::load_image(&mut cursor, &mut image, &ParserReadOptions::default(), None) {
-                            Ok(_) => {}
+                        match KfxFormat::read_track(&mut cursor, &mut image, next_ch) {
+                            Ok(flux_track) => flux_tracks.push((flux_track, next_ch)),
                             Err(e) => {
                                 // It's okay to fail if we have already added the standard number of tracks to an image.
                                 log::error!("load(): Error loading Kryoflux stream file: {:?}", e);
@@ -642,18 +897,28 @@ impl DiskImage {
                         }
 
                         if let Some(ref callback_fn) = callback {
+                            callback_fn(LoadingStatus::Track {
+                                ch:    next_ch,
+                                index: fi,
+                                total: file_set.len(),
+                            });
                             let completion = (fi + 1) as f64 / file_set.len() as f64;
                             callback_fn(LoadingStatus::Progress(completion));
                         }
+                        next_ch.seek_next_track(image.geometry());
+                    }
+
+                    if let Some(ref callback_fn) = callback {
+                        callback_fn(LoadingStatus::Stage(LoadingStage::Decoding));
+                    }
+                    if let Err(e) = KfxFormat::add_tracks(&mut image, flux_tracks) {
+                        log::error!("load(): Error decoding Kryoflux stream file: {:?}", e);
                     }
 
                     //let ch = DiskCh::new(build_image.track_map[0].len() as u16, build_image.track_map.len() as u8);
                     //build_image.descriptor.geometry = ch;
 
-                    if let Some(callback_fn) = callback {
-                        callback_fn(LoadingStatus::Complete);
-                    }
-                    image.post_load_process();
+                    image.finish_load(detect_time, parse_start.elapsed(), callback.as_ref());
                     Ok(image)
                 }
                 else {
@@ -717,12 +982,202 @@ impl DiskImage {
         }
     }
 
+    /// Return the [SectorLimits] applied when adding sectors to `MetaSector` resolution tracks.
+    pub fn sector_limits(&self) -> SectorLimits {
+        self.shared
+            .as_ref()
+            .map(|shared| shared.lock().unwrap().sector_limits)
+            .unwrap_or_default()
+    }
+
+    /// Set the [SectorLimits] applied when adding sectors to `MetaSector` resolution tracks.
+    /// Sectors that violate these limits will be rejected by `add_sector`.
+    pub fn set_sector_limits(&mut self, limits: SectorLimits) {
+        if let Some(shared) = &self.shared {
+            shared.lock().unwrap().sector_limits = limits;
+        }
+    }
+
+    /// Return the [WeakBitPolicy] used to resolve weak bits when reading sectors from `MetaSector`
+    /// resolution tracks.
+    pub fn weak_bit_policy(&self) -> WeakBitPolicy {
+        self.shared
+            .as_ref()
+            .map(|shared| shared.lock().unwrap().weak_bits.policy())
+            .unwrap_or_default()
+    }
+
+    /// Set the [WeakBitPolicy] used to resolve weak bits when reading sectors from `MetaSector`
+    /// resolution tracks. Setting a policy resets its state, so a [WeakBitPolicy::Seeded] policy
+    /// set with the same seed will reproduce the same sequence of reads.
+    pub fn set_weak_bit_policy(&mut self, policy: WeakBitPolicy) {
+        if let Some(shared) = &self.shared {
+            shared.lock().unwrap().weak_bits = WeakBitGenerator::new(policy);
+        }
+    }
+
+    /// Return the [SizeMismatchPolicy] applied when a sector read requests a sector size other
+    /// than the size recorded in the sector's ID.
+    pub fn size_mismatch_policy(&self) -> SizeMismatchPolicy {
+        self.shared
+            .as_ref()
+            .map(|shared| shared.lock().unwrap().size_mismatch)
+            .unwrap_or_default()
+    }
+
+    /// Set the [SizeMismatchPolicy] applied when a sector read requests a sector size other than
+    /// the size recorded in the sector's ID.
+    pub fn set_size_mismatch_policy(&mut self, policy: SizeMismatchPolicy) {
+        if let Some(shared) = &self.shared {
+            shared.lock().unwrap().size_mismatch = policy;
+        }
+    }
+
+    /// Return the [DecodePolicy] that determines when the flux data of `FluxStream` resolution
+    /// tracks is decoded.
+    pub fn decode_policy(&self) -> DecodePolicy {
+        self.decode_policy
+    }
+
+    /// Set the [DecodePolicy] that determines when the flux data of `FluxStream` resolution
+    /// tracks is decoded. The policy applies to tracks added afterward; to load an image with a
+    /// lazy policy, use [DiskImage::load_with_options].
+    pub fn set_decode_policy(&mut self, policy: DecodePolicy) {
+        self.decode_policy = policy;
+        if let Some(shared) = &self.shared {
+            shared.lock().unwrap().decode_policy = policy;
+        }
+    }
+
+    /// Return whether the transition bands of double-density `FluxStream` resolution tracks are
+    /// normalized before decoding.
+    pub fn dd_band_normalization(&self) -> bool {
+        self.shared
+            .as_ref()
+            .map(|shared| shared.lock().unwrap().normalize_dd_bands)
+            .unwrap_or_default()
+    }
+
+    /// Set whether the transition bands of double-density `FluxStream` resolution tracks are
+    /// normalized before decoding. Double-density media read in a high-density drive often shows
+    /// narrowed short pulses that the PLL struggles to track. When enabled, a normalized copy of
+    /// each skewed revolution's flux is decoded; the captured flux itself is never modified.
+    /// Like [DiskImage::set_decode_policy], this applies to tracks decoded afterward.
+    pub fn set_dd_band_normalization(&mut self, normalize: bool) {
+        if let Some(shared) = &self.shared {
+            shared.lock().unwrap().normalize_dd_bands = normalize;
+        }
+    }
+
+    /// Return true if every track in the image has been decoded. Only `FluxStream` resolution
+    /// tracks deferred by a lazy [DecodePolicy] may not be.
+    pub fn all_tracks_decoded(&self) -> bool {
+        !self.track_pool.iter().any(|track| {
+            track
+                .as_fluxstream_track()
+                .is_some_and(|flux_track| !flux_track.is_decoded())
+        })
+    }
+
+    /// Decode every `FluxStream` resolution track whose decoding was deferred by a lazy
+    /// [DecodePolicy], then normalize the image and update its analysis, as is done when an
+    /// image is loaded eagerly. If the `rayon` feature is enabled (except on wasm32), the tracks
+    /// are decoded in parallel.
+    pub fn decode_all_tracks(&mut self) {
+        if self.all_tracks_decoded() {
+            return;
+        }
+        let materialize = |track: &mut DiskTrack| {
+            if let Some(flux_track) = track.as_fluxstream_track_mut() {
+                flux_track.materialize();
+            }
+        };
+        #[cfg(all(feature = "rayon", not(target_arch = "wasm32")))]
+        {
+            use rayon::prelude::*;
+            self.track_pool.par_iter_mut().for_each(materialize);
+        }
+        #[cfg(not(all(feature = "rayon", not(target_arch = "wasm32"))))]
+        self.track_pool.iter_mut().for_each(materialize);
+
+        self.normalize();
+        self.update_analysis();
+    }
+
+    /// Under a [DecodePolicy::LazyPrefetch] policy, start decoding the tracks following the
+    /// track at `ch`, in the order of [DiskImage::track_iter].
+    fn prefetch_after(&self, ch: DiskCh) {
+        let prefetch_ct = self.decode_policy.prefetch_ct();
+        if prefetch_ct == 0 {
+            return;
+        }
+        let Some(&track_idx) = self.track_map.get(ch.h() as usize).and_then(|h| h.get(ch.c() as usize))
+        else {
+            return;
+        };
+        self.track_idx_iter()
+            .skip_while(|&idx| idx != track_idx)
+            .skip(1)
+            .take(prefetch_ct)
+            .filter_map(|idx| self.track_pool[idx].as_fluxstream_track())
+            .for_each(|flux_track| flux_track.prefetch());
+    }
+
+    /// Return the [RecoveryOptions] applied when scanning `BitStream` resolution tracks for
+    /// sectors.
+    pub fn recovery_options(&self) -> RecoveryOptions {
+        self.shared
+            .as_ref()
+            .map(|shared| shared.lock().unwrap().recovery)
+            .unwrap_or_default()
+    }
+
+    /// Set the [RecoveryOptions] applied when scanning `BitStream` resolution tracks for sectors.
+    /// All `BitStream` resolution tracks are rescanned with the new options; tracks loaded or
+    /// written afterward will also use them.
+    pub fn set_recovery_options(&mut self, options: RecoveryOptions) {
+        if let Some(shared) = &self.shared {
+            shared.lock().unwrap().recovery = options;
+        }
+        for track in self.track_pool.iter_mut() {
+            if let Some(bitstream_track) = track.as_bitstream_track_mut() {
+                bitstream_track.rescan_elements();
+            }
+        }
+    }
+
     pub fn source_format(&self) -> Option<DiskImageFileFormat> {
-        self.source_format
+        self.source_info.as_ref().map(|info| info.format)
     }
 
+    /// Set the source format of the disk image. This resets any version, variant and quirks
+    /// previously recorded for the source image.
     pub fn set_source_format(&mut self, format: DiskImageFileFormat) {
-        self.source_format = Some(format);
+        self.source_info = Some(SourceFormatInfo::new(format));
+    }
+
+    /// Return a [SourceFormatInfo] describing the image file the disk image was loaded from,
+    /// including the parser, format version and any quirks applied during loading.
+    /// Returns `None` if the disk image was not loaded from a file.
+    pub fn source_format_info(&self) -> Option<&SourceFormatInfo> {
+        self.source_info.as_ref()
+    }
+
+    /// Record the version and variant of the source image format.
+    /// Has no effect if no source format has been set.
+    pub(crate) fn set_source_version(&mut self, version: Option<String>, variant: Option<String>) {
+        if let Some(info) = &mut self.source_info {
+            info.version = version;
+            info.variant = variant;
+        }
+    }
+
+    /// Record a quirk the parser worked around while loading the source image.
+    /// Has no effect if no source format has been set.
+    pub(crate) fn add_source_quirk(&mut self, quirk: SourceQuirks) {
+        if let Some(info) = &mut self.source_info {
+            info.quirks |= quirk;
+        }
     }
 
     /// Return a list of track resolutions present in the disk image.
@@ -766,8 +1221,73 @@ impl DiskImage {
         mut track: FluxStreamTrack,
         params: &FluxStreamTrackParams,
     ) -> Result<&mut DiskTrack, DiskImageError> {
-        let head = params.ch.h() as usize;
-        if head >= 2 {
+        self.check_fluxstream_track(params)?;
+        let shared = self.shared.clone().expect("Shared context not found.");
+        let times = Self::decode_fluxstream_track(&mut track, params, shared)?;
+        self.push_fluxstream_track(track, params, Some(times));
+        Ok(self.track_pool.last_mut().unwrap())
+    }
+
+    /// Adds a batch of new `FluxStream` resolution tracks to the disk image, as
+    /// [DiskImage::add_track_fluxstream] does for a single track.
+    ///
+    /// Decoding a flux track is independent of every other track, so if the `rayon` feature is
+    /// enabled the tracks are decoded in parallel (except on wasm32). Tracks are added to the
+    /// image in the order given.
+    ///
+    /// Under a lazy [DecodePolicy], only as many tracks as the policy decodes at load are decoded,
+    /// counting the tracks already in the image. The decoding of the remaining tracks is deferred
+    /// until they are accessed.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<TrackInfo>)` with the [TrackInfo] of each track decoded, in order. Tracks whose
+    ///   decoding was deferred have no entry.
+    /// - `Err(DiskImageError)` if any track could not be added. The tracks preceding the first
+    ///   track that failed are still added to the image.
+    pub fn add_tracks_fluxstream(
+        &mut self,
+        mut tracks: Vec<(FluxStreamTrack, FluxStreamTrackParams)>,
+    ) -> Result<Vec<TrackInfo>, DiskImageError> {
+        for (_, params) in &tracks {
+            self.check_fluxstream_track(params)?;
+        }
+        let shared = self.shared.clone().expect("Shared context not found.");
+
+        let load_ct = self.decode_policy().load_ct().saturating_sub(self.track_pool.len());
+        let deferred_tracks = tracks.split_off(load_ct.min(tracks.len()));
+
+        let decode = |(mut track, params): (FluxStreamTrack, FluxStreamTrackParams)| {
+            let result = Self::decode_fluxstream_track(&mut track, &params, shared.clone());
+            (track, params, result)
+        };
+        #[cfg(all(feature = "rayon", not(target_arch = "wasm32")))]
+        let decoded: Vec<_> = {
+            use rayon::prelude::*;
+            tracks.into_par_iter().map(decode).collect()
+        };
+        #[cfg(not(all(feature = "rayon", not(target_arch = "wasm32"))))]
+        let decoded: Vec<_> = tracks.into_iter().map(decode).collect();
+
+        let mut infos = Vec::with_capacity(decoded.len());
+        for (track, params, result) in decoded {
+            let times = result?;
+            infos.push(track.info());
+            self.push_fluxstream_track(track, &params, Some(times));
+        }
+
+        for (mut track, params) in deferred_tracks {
+            track.set_ch(params.ch);
+            track.set_shared(shared.clone());
+            let deferred_track = FluxStreamTrack::new_deferred(track, params.clock, params.rpm);
+            self.push_fluxstream_track(deferred_track, &params, None);
+        }
+        Ok(infos)
+    }
+
+    /// Check that a `FluxStream` resolution track can be added to the image, and lock the image
+    /// to `FluxStream` resolution.
+    fn check_fluxstream_track(&mut self, params: &FluxStreamTrackParams) -> Result<(), DiskImageError> {
+        if params.ch.h() >= 2 {
             return Err(DiskImageError::SeekError);
         }
 
@@ -786,26 +1306,48 @@ impl DiskImage {
             // Otherwise, set FluxStream resolution in the resolution set.
             self.resolution.insert(TrackDataResolution::FluxStream);
         }
+        Ok(())
+    }
 
+    /// Decode the revolutions of a `FluxStream` resolution track. This does not touch the image,
+    /// so that tracks may be decoded in parallel. Returns the decode and scan times.
+    fn decode_fluxstream_track(
+        track: &mut FluxStreamTrack,
+        params: &FluxStreamTrackParams,
+        shared: Arc<Mutex<SharedDiskContext>>,
+    ) -> Result<(Duration, Duration), DiskImageError> {
         track.set_ch(params.ch);
-        track.set_shared(self.shared.clone().expect("Shared context not found."));
-        track.synthesize_revolutions(); // Create synthetic revolutions to increase chances of successful decoding.
-        track.decode_revolutions(params.clock, params.rpm)?;
-        track.analyze_revolutions();
+        track.set_shared(shared);
+        track.decode(params.clock, params.rpm)
+    }
 
-        log::debug!(
-            "add_track_fluxstream(): adding {:?} track {}",
-            track.encoding(),
-            track.ch(),
-        );
+    /// Add a `FluxStream` resolution track to the image, with the decode and scan times of the
+    /// track, or `None` if its decoding has been deferred.
+    fn push_fluxstream_track(
+        &mut self,
+        track: FluxStreamTrack,
+        params: &FluxStreamTrackParams,
+        times: Option<(Duration, Duration)>,
+    ) {
+        if let Some((decode_time, scan_time)) = times {
+            if let Some(telemetry) = &mut self.load_telemetry {
+                telemetry.add_track(params.ch, decode_time, scan_time);
+            }
+            log::debug!(
+                "add_track_fluxstream(): adding {:?} track {}",
+                track.encoding(),
+                track.ch(),
+            );
+        }
+        else {
+            log::debug!("add_track_fluxstream(): adding deferred track {}", track.ch());
+        }
 
         self.track_pool.push(Box::new(track));
-        self.track_map[head].push(self.track_pool.len() - 1);
+        self.track_map[params.ch.h() as usize].push(self.track_pool.len() - 1);
 
         // Consider adding a track to an image to be a single 'write' operation.
         self.incr_writes();
-
-        Ok(self.track_pool.last_mut().unwrap())
     }
 
     /// Adds a new track to the disk image, of BitStream resolution.
@@ -850,7 +1392,11 @@ impl DiskImage {
         );
 
         let head = params.ch.h() as usize;
+        let scan_start = Instant::now();
         let new_track = BitStreamTrack::new(params, self.shared.clone().expect("Shared context not found"))?;
+        if let Some(telemetry) = &mut self.load_telemetry {
+            telemetry.add_track(params.ch, Duration::ZERO, scan_start.elapsed());
+        }
 
         self.track_pool.push(Box::new(new_track));
         self.track_map[head].push(self.track_pool.len() - 1);
@@ -904,6 +1450,7 @@ impl DiskImage {
             schema: Some(TrackSchema::System34),
             data_rate: params.data_rate,
             sectors: Vec::new(),
+            metadata: Default::default(),
             shared: self.shared.clone().expect("Shared context not found"),
         }));
         self.track_map[params.ch.h() as usize].push(self.track_pool.len() - 1);
@@ -942,7 +1489,7 @@ impl DiskImage {
     /// Offsets are provided within ReadSectorResult so these can be skipped when processing the
     /// read operation.
     pub fn read_sector(
-        &mut self,
+        &self,
         phys_ch: DiskCh,
         id: DiskChsnQuery,
         n: Option<u8>,
@@ -955,12 +1502,84 @@ impl DiskImage {
             return Err(DiskImageError::SeekError);
         }
 
+        self.prefetch_after(phys_ch);
         let ti = self.track_map[phys_ch.h() as usize][phys_ch.c() as usize];
-        let track = &mut self.track_pool[ti];
+        let track = &self.track_pool[ti];
 
         track.read_sector(id, n, offset, scope, debug)
     }
 
+    /// Read the data of the sector identified by `id` at the physical location `phys_ch`,
+    /// borrowing the sector data from the track instead of copying it where possible. See
+    /// [Track::read_sector_ref].
+    pub fn read_sector_ref(
+        &self,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        n: Option<u8>,
+    ) -> Result<ReadSectorRef<'_>, DiskImageError> {
+        // Check that the head and cylinder are within the bounds of the track map.
+        if phys_ch.h() > 1 || phys_ch.c() as usize >= self.track_map[phys_ch.h() as usize].len() {
+            return Err(DiskImageError::SeekError);
+        }
+
+        self.prefetch_after(phys_ch);
+        let ti = self.track_map[phys_ch.h() as usize][phys_ch.c() as usize];
+        self.track_pool[ti].read_sector_ref(id, n)
+    }
+
+    /// Read the sector data from the sector at the physical location `phys_ch`, with the
+    /// semantics of the READ DATA command of a µPD765-style floppy disk controller.
+    ///
+    /// The size parameter `n` is passed to [DiskImage::read_sector] as an override, so the
+    /// current [SizeMismatchPolicy] applies when it differs from the sector's. When `n` is 0, the
+    /// data length parameter `dtl` limits the number of bytes transferred, and the data range of
+    /// the result is shortened to at most `dtl` bytes. The CRC status of the sector is reported
+    /// as if the entire sector had been read, as the controller continues to read to the end of
+    /// the sector without transferring the remaining bytes. When `n` is not 0, `dtl` is ignored.
+    ///
+    /// With [RwScope::DataOnly], bytes beyond the shortened data range are also removed from
+    /// the read buffer.
+    pub fn read_sector_dtl(
+        &self,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        n: u8,
+        dtl: u8,
+        scope: RwScope,
+        debug: bool,
+    ) -> Result<ReadSectorResult, DiskImageError> {
+        let mut rsr = self.read_sector(phys_ch, id, Some(n), None, scope, debug)?;
+
+        if n == 0 {
+            let data_end = rsr.data_range.end.min(rsr.data_range.start + dtl as usize);
+            rsr.data_range.end = data_end;
+            if matches!(scope, RwScope::DataOnly) {
+                rsr.read_buf.truncate(data_end);
+            }
+        }
+        Ok(rsr)
+    }
+
+    /// Read the sector identified by `id` from a single captured revolution of the track at
+    /// `phys_ch`. See [Track::read_sector_rev] and [Track::revolutions].
+    pub fn read_sector_rev(
+        &self,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        revolution: usize,
+        n: Option<u8>,
+        scope: RwScope,
+    ) -> Result<ReadSectorResult, DiskImageError> {
+        // Check that the head and cylinder are within the bounds of the track map.
+        if phys_ch.h() > 1 || phys_ch.c() as usize >= self.track_map[phys_ch.h() as usize].len() {
+            return Err(DiskImageError::SeekError);
+        }
+
+        let ti = self.track_map[phys_ch.h() as usize][phys_ch.c() as usize];
+        self.track_pool[ti].read_sector_rev(id, revolution, n, scope)
+    }
+
     /// A simplified version of read_sector() which only returns the sector data as a Vec<u8>,
     /// or an `DiskImageError` if the sector could not be read.
     pub fn read_sector_basic(
@@ -973,6 +1592,7 @@ impl DiskImage {
         if phys_ch.h() > 1 || phys_ch.c() as usize >= self.track_map[phys_ch.h() as usize].len() {
             return Err(DiskImageError::SeekError);
         }
+        self.prefetch_after(phys_ch);
         let ti = self.track_map[phys_ch.h() as usize][phys_ch.c() as usize];
         let track = &self.track_pool[ti];
         let rsr = track.read_sector(id, id.n(), offset, RwScope::DataOnly, false)?;
@@ -983,6 +1603,88 @@ impl DiskImage {
         Ok(rsr.read_buf[rsr.data_range].to_vec())
     }
 
+    /// Return the [StandardFormat] used to map logical block addresses onto the disk image.
+    fn lba_format(&self) -> Result<StandardFormat, DiskImageError> {
+        self.standard_format
+            .or_else(|| self.closest_format(true))
+            .ok_or_else(|| {
+                log::error!("lba_format(): Disk image does not match a standard format.");
+                DiskImageError::IncompatibleImage(
+                    "Disk geometry is irregular; LBA addressing is unavailable.".to_string(),
+                )
+            })
+    }
+
+    /// Resolve `lba` to the sector ID it addresses under the standard geometry of the disk image.
+    fn lba_to_chsn(&self, lba: usize) -> Result<(StandardFormat, DiskChsn), DiskImageError> {
+        let format = self.lba_format()?;
+        let layout = format.layout();
+        let chs = DiskChs::from_lba(lba, &layout).ok_or(DiskImageError::SeekError)?;
+        let chsn = DiskChsn::from((chs, layout.n()));
+
+        let present = self
+            .track(chs.into())
+            .is_some_and(|track| track.sector_list().iter().any(|entry| entry.chsn == chsn));
+        if !present {
+            log::error!(
+                "lba_to_chsn(): LBA {} maps to sector {}, which is not present.",
+                lba,
+                chsn
+            );
+            return Err(DiskImageError::IncompatibleImage(format!(
+                "Sector {} for LBA {} is not present; disk geometry is irregular.",
+                chsn, lba
+            )));
+        }
+        Ok((format, chsn))
+    }
+
+    /// Read the sector at logical block address `lba`, using the standard geometry mapping for
+    /// the disk image's format (cylinder, then head, then sector).
+    /// # Returns
+    /// - `Ok(Vec<u8>)` containing the sector data.
+    /// - `Err(DiskImageError::IncompatibleImage)` if the disk image does not have a standard
+    ///   geometry, or the addressed sector is missing or of the wrong size.
+    /// - `Err(DiskImageError::SeekError)` if `lba` is beyond the end of the disk.
+    pub fn read_lba(&self, lba: usize) -> Result<Vec<u8>, DiskImageError> {
+        let (format, chsn) = self.lba_to_chsn(lba)?;
+        let data = self.read_sector_basic(chsn.ch(), DiskChsnQuery::from(chsn), None)?;
+        if data.len() != format.sector_size() {
+            log::error!(
+                "read_lba(): Sector {} has size {}, expected {}",
+                chsn,
+                data.len(),
+                format.sector_size()
+            );
+            return Err(DiskImageError::IncompatibleImage(format!(
+                "Sector {} has irregular size {}.",
+                chsn,
+                data.len()
+            )));
+        }
+        Ok(data)
+    }
+
+    /// Write `data` to the sector at logical block address `lba`, using the standard geometry
+    /// mapping for the disk image's format. `data` must be exactly one sector in length.
+    /// # Returns
+    /// - `Err(DiskImageError::ParameterError)` if `data` is not the size of a sector.
+    /// - `Err(DiskImageError::IncompatibleImage)` if the disk image does not have a standard
+    ///   geometry, or the addressed sector is missing.
+    /// - `Err(DiskImageError::SeekError)` if `lba` is beyond the end of the disk.
+    pub fn write_lba(&mut self, lba: usize, data: &[u8]) -> Result<(), DiskImageError> {
+        let (format, chsn) = self.lba_to_chsn(lba)?;
+        if data.len() != format.sector_size() {
+            log::error!(
+                "write_lba(): Data length {} does not match sector size {}",
+                data.len(),
+                format.sector_size()
+            );
+            return Err(DiskImageError::ParameterError);
+        }
+        self.write_sector_basic(chsn.ch(), DiskChsnQuery::from(chsn), None, data)
+    }
+
     pub fn write_sector(
         &mut self,
         phys_ch: DiskCh,
@@ -1035,7 +1737,7 @@ impl DiskImage {
     /// CRCs are not included in the data.
     /// This function is intended for use in implementing the Read Track FDC command.
     pub fn read_all_sectors(
-        &mut self,
+        &self,
         phys_ch: DiskCh,
         id_ch: DiskCh,
         n: u8,
@@ -1047,7 +1749,7 @@ impl DiskImage {
         }
 
         let ti = self.track_map[phys_ch.h() as usize][phys_ch.c() as usize];
-        let track = &mut self.track_pool[ti];
+        let track = &self.track_pool[ti];
 
         track.read_all_sectors(id_ch, n, eot)
     }
@@ -1059,14 +1761,14 @@ impl DiskImage {
     /// - `ch`: The cylinder and head of the track to read.
     /// - `overdump`: An optional parameter to specify the number of bytes to read past the end of
     ///               the track. This is useful for examining track wrapping behavior.
-    pub fn read_track(&mut self, ch: DiskCh, overdump: Option<usize>) -> Result<ReadTrackResult, DiskImageError> {
+    pub fn read_track(&self, ch: DiskCh, overdump: Option<usize>) -> Result<ReadTrackResult, DiskImageError> {
         // Check that the head and cylinder are within the bounds of the track map.
         if ch.h() > 1 || ch.c() as usize >= self.track_map[ch.h() as usize].len() {
             return Err(DiskImageError::SeekError);
         }
 
         let ti = self.track_map[ch.h() as usize][ch.c() as usize];
-        let track = &mut self.track_pool[ti];
+        let track = &self.track_pool[ti];
 
         track.read(None, overdump)
     }
@@ -1078,14 +1780,14 @@ impl DiskImage {
     /// - `ch`: The cylinder and head of the track to read.
     /// - `overdump`: An optional parameter to specify the number of bytes to read past the end of
     ///               the track. This is useful for examining track wrapping behavior.
-    pub fn read_track_raw(&mut self, ch: DiskCh, overdump: Option<usize>) -> Result<ReadTrackResult, DiskImageError> {
+    pub fn read_track_raw(&self, ch: DiskCh, overdump: Option<usize>) -> Result<ReadTrackResult, DiskImageError> {
         // Check that the head and cylinder are within the bounds of the track map.
         if ch.h() > 1 || ch.c() as usize >= self.track_map[ch.h() as usize].len() {
             return Err(DiskImageError::SeekError);
         }
 
         let ti = self.track_map[ch.h() as usize][ch.c() as usize];
-        let track = &mut self.track_pool[ti];
+        let track = &self.track_pool[ti];
 
         track.read_raw(overdump)
     }
@@ -1201,6 +1903,7 @@ impl DiskImage {
                     data_rate,
                     ch,
                     sectors: Vec::new(),
+                    metadata: Default::default(),
                     shared: self.shared.clone().expect("Shared context not found"),
                 }));
 
@@ -1226,6 +1929,39 @@ impl DiskImage {
         Ok(new_track_index)
     }
 
+    /// Add an unformatted track to the image at the specified physical cylinder and head. If
+    /// `noise` is true, the track is filled with random bitcells, modeling the noise read from a
+    /// blank disk surface. Otherwise, the track contains no flux transitions.
+    ///
+    /// Noise can only be represented by BitStream tracks. On a MetaSector image, an unformatted
+    /// track is simply a track with no sectors.
+    pub fn add_unformatted_track(
+        &mut self,
+        ch: DiskCh,
+        encoding: TrackDataEncoding,
+        data_rate: TrackDataRate,
+        bitcells: usize,
+        noise: bool,
+    ) -> Result<usize, DiskImageError> {
+        let new_track_idx = self.add_empty_track(ch, encoding, None, data_rate, bitcells, None)?;
+
+        if noise {
+            if let Some(track) = self.track_pool[new_track_idx].as_bitstream_track_mut() {
+                let bits = BitVec::from_fn(bitcells, random_bit);
+                track.write_raw_bits(0, &bits)?;
+            }
+        }
+        Ok(new_track_idx)
+    }
+
+    /// Return a list of the unformatted tracks in the image. See [TrackFormatting].
+    pub fn unformatted_tracks(&self) -> Vec<DiskCh> {
+        self.iter_tracks()
+            .filter(|(_, track)| track.formatting().is_unformatted())
+            .map(|(ch, _)| ch)
+            .collect()
+    }
+
     pub fn format_track(
         &mut self,
         ch: DiskCh,
@@ -1245,7 +1981,22 @@ impl DiskImage {
 
         // Formatting can change disk layout. Update image consistency to ensure export support
         // is accurate.
-        self.analyze();
+        self.update_analysis();
+        Ok(())
+    }
+
+    /// Write the entire track at `ch` from the data of a write track command, as an emulated
+    /// WD177x-style floppy disk controller would receive it. See [Track::write_track].
+    pub fn write_track(&mut self, ch: DiskCh, data: &[u8]) -> Result<(), DiskImageError> {
+        if ch.h() > 1 || ch.c() as usize >= self.track_map[ch.h() as usize].len() {
+            return Err(DiskImageError::SeekError);
+        }
+
+        let ti = self.track_map[ch.h() as usize][ch.c() as usize];
+        self.track_pool[ti].write_track(data)?;
+
+        // Writing a track can change disk layout, as with formatting.
+        self.update_analysis();
         Ok(())
     }
 
@@ -1256,13 +2007,13 @@ impl DiskImage {
 
         let standard_format = self.standard_format;
         let descriptor = self.descriptor.clone();
-        let source_format = self.source_format;
+        let source_info = self.source_info.take();
         let resolution = self.resolution.clone();
 
         *self = DiskImage::default();
         self.descriptor = descriptor;
         self.standard_format = standard_format;
-        self.source_format = source_format;
+        self.source_info = source_info;
         self.resolution = resolution;
     }
 
@@ -1324,6 +2075,9 @@ impl DiskImage {
         Ok(())
     }
 
+    /// Return the ID of the sector following the sector `chs` on its track, wrapping around to the
+    /// first sector after the last. See [DiskImage::next_id_timed] to locate the next ID address
+    /// mark by rotational position instead.
     pub fn get_next_id(&self, chs: DiskChs) -> Option<DiskChsn> {
         if chs.h() > 1 || chs.c() as usize >= self.track_map[chs.h() as usize].len() {
             return None;
@@ -1334,12 +2088,115 @@ impl DiskImage {
         track.next_id(chs)
     }
 
-    pub(crate) fn read_boot_sector(&mut self) -> Result<Vec<u8>, DiskImageError> {
+    /// Return the next ID address mark to pass under the head on the track at `ch`, starting from
+    /// the rotational position `bit_position`, a bitcell offset from the index. Positions past the
+    /// end of the track wrap around. Sector headers with a bad CRC are included, as a controller
+    /// would encounter them.
+    ///
+    /// Along with the sector ID, the result gives the bitcell offset of the address mark and the
+    /// estimated time to reach it, both from the index and from `bit_position`, so that an
+    /// emulator can model rotational latency or the sector timing measured by some copy
+    /// protection schemes.
+    ///
+    /// Returns `None` if there is no track at `ch` or the track has no sector headers.
+    pub fn next_id_timed(&self, ch: DiskCh, bit_position: usize) -> Option<NextIdResult> {
+        let track = self.track(ch)?;
+        let id_marks = track.metadata()?.id_marks();
+        let info = track.info();
+        let bit_length = Self::track_bit_length(&info)?;
+        let rpm = info.rpm.or(self.descriptor.rpm).unwrap_or_default();
+        let revolution_time = 60.0 / rpm.rpm_at(ch);
+
+        let position = bit_position % bit_length;
+        let (mark, bit_distance) = match id_marks.iter().find(|mark| mark.bit_offset >= position) {
+            Some(mark) => (mark, mark.bit_offset - position),
+            None => {
+                let mark = id_marks.first()?;
+                (mark, bit_length - position + mark.bit_offset)
+            }
+        };
+
+        let to_time = |bits: usize| bits as f64 / bit_length as f64 * revolution_time;
+        Some(NextIdResult {
+            chsn: mark.chsn,
+            address_error: mark.address_error,
+            bit_offset: mark.bit_offset,
+            bit_distance,
+            time_from_index: to_time(mark.bit_offset),
+            latency: to_time(bit_distance),
+        })
+    }
+
+    /// Read the sector matching `id` on the track at `phys_ch`, as a controller would when starting
+    /// its search from the current rotational position of `rotation` rather than from the index.
+    /// Of several sectors sharing the ID, the first to pass under the head is read.
+    ///
+    /// `rotation` is advanced to the end of the sector's data field, or by a full revolution if
+    /// no sector matching `id` is found.
+    ///
+    /// MetaSector tracks cannot start a read from a bit position, so the first sector matching
+    /// `id` in track order is read from them, though `rotation` is advanced by the estimated time
+    /// to reach the first matching sector from its position.
+    pub fn read_sector_rotational(
+        &self,
+        rotation: &mut DiskRotation,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        n: Option<u8>,
+        scope: RwScope,
+    ) -> Result<ReadSectorResult, DiskImageError> {
+        let track = self.track(phys_ch).ok_or(DiskImageError::SeekError)?;
+        let Some(bit_length) = Self::track_bit_length(&track.info())
+        else {
+            return self.read_sector(phys_ch, id, n, None, scope, false);
+        };
+        let elements = track.metadata().map(|metadata| metadata.elements()).unwrap_or_default();
+        let id_marks = track.metadata().map(|metadata| metadata.id_marks()).unwrap_or_default();
+
+        let position = rotation.bit_position(bit_length);
+        let distance_to = |offset: usize| (offset % bit_length + bit_length - position) % bit_length;
+        let Some(mark) = id_marks
+            .iter()
+            .filter(|mark| id.matches(&mark.chsn))
+            .min_by_key(|mark| distance_to(mark.bit_offset))
+        else {
+            // The controller searches the whole track without finding the sector.
+            rotation.advance_bits(bit_length, bit_length);
+            return self.read_sector(phys_ch, id, n, None, scope, false);
+        };
+
+        let rsr = self.read_sector(phys_ch, id, n, Some(mark.bit_offset), scope, false)?;
+
+        // The head passes the end of the sector's data field, or of its ID field if the sector has
+        // no data field.
+        let mut following = elements.iter().filter(|element| element.start >= mark.bit_offset);
+        let header_end = following
+            .find(|element| element.element.is_sector_header())
+            .map_or(mark.bit_offset, |element| element.end);
+        let end = following
+            .find(|element| element.element.is_sector_header() || element.element.is_sector_data())
+            .filter(|element| element.element.is_sector_data())
+            .map_or(header_end, |element| element.end);
+
+        rotation.advance_bits(distance_to(mark.bit_offset) + (end - mark.bit_offset), bit_length);
+        Ok(rsr)
+    }
+
+    /// Return the length of a track in bitcells. MetaSector tracks have no bitstream; their element
+    /// positions are synthesized for the nominal bitcell count of the track's density.
+    fn track_bit_length(info: &TrackInfo) -> Option<usize> {
+        match info.bit_length {
+            0 => TrackDensity::from(info.data_rate).bitcells(None),
+            len => Some(len),
+        }
+    }
+
+    pub(crate) fn read_boot_sector(&self) -> Result<Vec<u8>, DiskImageError> {
         if self.track_map.is_empty() || self.track_map[0].is_empty() {
             return Err(DiskImageError::IncompatibleImage("No tracks found.".to_string()));
         }
         let ti = self.track_map[0][0];
-        let track = &mut self.track_pool[ti];
+        let track = &self.track_pool[ti];
 
         match track.read_sector(DiskChsnQuery::new(0, 0, 1, 2), None, None, RwScope::DataOnly, true) {
             Ok(result) => Ok(result.read_buf[result.data_range].to_vec()),
@@ -1392,11 +2249,14 @@ impl DiskImage {
             shared.lock().unwrap().writes = 1;
         }
 
-        // Normalize the disk image
-        self.normalize();
+        // Normalize the disk image. Normalizing examines the contents of every track, so it is
+        // left to decode_all_tracks() if the decoding of any track has been deferred.
+        if self.all_tracks_decoded() {
+            self.normalize();
+        }
 
         // Set the DiskAnalysis
-        self.analyze();
+        self.update_analysis();
 
         // Examine the boot sector if present. Use this to determine if this image is a standard
         // format disk image (but do not rely on this as the sole method of determining the disk
@@ -1430,6 +2290,15 @@ impl DiskImage {
         self.boot_sector.as_ref()
     }
 
+    /// Write a boot sector to sector 1 of track 0, and update the image's parsed boot sector.
+    /// To edit the BPB of an image, clone the boot sector returned by [DiskImage::boot_sector],
+    /// modify it, and pass it to this function.
+    pub fn set_boot_sector(&mut self, boot_sector: BootSector) -> Result<(), DiskImageError> {
+        self.write_boot_sector(boot_sector.as_bytes())?;
+        self.boot_sector = Some(boot_sector);
+        Ok(())
+    }
+
     pub fn track_ct(&self, head: usize) -> usize {
         self.track_map[head].len()
     }
@@ -1495,9 +2364,58 @@ impl DiskImage {
         }
     }
 
+    /// Measure the gaps of every track in the image and check them against the minimum lengths a
+    /// floppy disk controller needs to write sectors reliably. Tracks that cannot be measured,
+    /// such as MetaSector tracks, are skipped. See [Track::gap_report].
+    pub fn gap_reports(&self) -> Vec<TrackGapReport> {
+        self.track_iter().filter_map(|track| track.gap_report()).collect()
+    }
+
+    /// Determine the interleave of every track in the image, and the skew of the lowest numbered
+    /// sector between the heads of each cylinder and between adjacent cylinders. Tracks with
+    /// fewer than two sectors are skipped. See [Track::interleave].
+    pub fn skew_map(&self) -> SkewMap {
+        SkewMap::new(self.track_iter().filter_map(|track| track.interleave()).collect())
+    }
+
+    /// Return the approximate memory used by each track of the image, by representation. Tracks
+    /// whose decoding was deferred by a lazy [DecodePolicy] are measured without decoding them.
+    /// See [Track::memory_usage].
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            tracks: self.track_iter().map(|track| track.memory_usage()).collect(),
+        }
+    }
+
+    /// Drop redundant representations of track data according to `policy`, to reduce the memory
+    /// used by the image. Tracks whose decoding was deferred by a lazy [DecodePolicy] are decoded
+    /// first. Returns the approximate number of bytes freed, as measured by
+    /// [DiskImage::memory_usage].
+    pub fn compact(&mut self, policy: RetentionPolicy) -> usize {
+        let before = self.memory_usage().total();
+        for track in self.track_pool.iter_mut() {
+            let Some(flux_track) = track.as_fluxstream_track_mut()
+            else {
+                continue;
+            };
+            match policy {
+                RetentionPolicy::BestRevolution => flux_track.retain_best_revolution(),
+                RetentionPolicy::BitStream => {
+                    if let Some(bitstream_track) = flux_track.take_bitstream() {
+                        *track = Box::new(bitstream_track);
+                    }
+                }
+            }
+        }
+        if policy == RetentionPolicy::BitStream {
+            self.resolution = self.track_pool.iter().map(|track| track.resolution()).collect();
+        }
+        before.saturating_sub(self.memory_usage().total())
+    }
+
     /// Update a [DiskImage]'s [DiskAnalysis] struct to reflect the current state of the image.
     /// This function should be called after any changes to a track.
-    pub(crate) fn analyze(&mut self) {
+    pub(crate) fn update_analysis(&mut self) {
         let mut spt: FoxHashSet<usize> = FoxHashSet::new();
 
         let mut all_consistency: TrackAnalysis = Default::default();
@@ -1506,10 +2424,19 @@ impl DiskImage {
         let mut consistent_size_map: FoxHashSet<u8> = FoxHashSet::new();
 
         let mut last_track_sector_size = 0;
+        let mut unformatted_tracks = 0;
 
-        log::debug!("analyze(): Running consistency check...");
+        log::debug!("update_analysis(): Running consistency check...");
         for track_idx in self.track_idx_iter() {
             let td = &self.track_pool[track_idx];
+            // Don't decode tracks deferred by a lazy DecodePolicy just to analyze them.
+            if td.as_fluxstream_track().is_some_and(|track| !track.is_decoded()) {
+                continue;
+            }
+            if let TrackFormatting::Unformatted { noise } = td.formatting() {
+                log::debug!("update_analysis(): Track {} is unformatted (noise: {})", td.ch(), noise);
+                unformatted_tracks += 1;
+            }
             match td.analysis() {
                 Ok(track_consistency) => {
                     match track_consistency.consistent_sector_size {
@@ -1532,13 +2459,14 @@ impl DiskImage {
                     }
                 }
                 Err(_) => {
-                    log::warn!("analyze(): Track {} has no analysis data.", track_idx);
+                    log::warn!("update_analysis(): Track {} has no analysis data.", track_idx);
                     continue;
                 }
             };
         }
 
         self.analysis.set_track_analysis(&all_consistency);
+        self.analysis.unformatted_tracks = unformatted_tracks;
         if consistent_size_map.len() > 1 {
             self.analysis.consistent_sector_size = None;
         }
@@ -1760,6 +2688,12 @@ impl DiskImage {
         //     None => "Unknown".to_string(),
         // };
 
+        if let Some(info) = &self.source_info {
+            out.write_fmt(format_args!("Source Format: {} [{}]\n", info, info.parser_name()))?;
+            for quirk in info.quirk_descriptions() {
+                out.write_fmt(format_args!("  Quirk: {}\n", quirk))?;
+            }
+        }
         out.write_fmt(format_args!("Disk Format: {}\n", disk_format_string))?;
         out.write_fmt(format_args!("Geometry: {}\n", self.descriptor.geometry))?;
         out.write_fmt(format_args!("Density: {}\n", self.descriptor.density))?;
@@ -1832,6 +2766,13 @@ impl DiskImage {
             }
         }
 
+        if self.analysis.unformatted_tracks > 0 {
+            out.write_fmt(format_args!(
+                "Disk contains {} unformatted tracks\n",
+                self.analysis.unformatted_tracks
+            ))?;
+        }
+
         Ok(())
     }
 
@@ -1855,6 +2796,60 @@ impl DiskImage {
         head_map
     }
 
+    /// Compare this disk image against `other`, sector by sector, returning an [ImageDiff]
+    /// describing each track and sector that differs in presence, data, CRC status, address marks
+    /// or weak bit masks.
+    pub fn diff(&self, other: &DiskImage) -> ImageDiff {
+        ImageDiff::new(self, other)
+    }
+
+    /// Merge multiple dumps of the same disk into a single best-of `MetaSector` resolution
+    /// [DiskImage]. `FluxStream` tracks contribute each decoded revolution as a separate dump.
+    ///
+    /// Sectors with a valid CRC take priority. Bits that differ between dumps of a sector become
+    /// weak bits, similar to adding a sector with `alternate` set.
+    /// # Returns
+    /// - `Err(DiskImageError::ParameterError)` if `images` is empty.
+    pub fn merge(images: &[&DiskImage]) -> Result<DiskImage, DiskImageError> {
+        merge_images(images)
+    }
+
+    /// Export the disk image as a tar archive containing one file per sector, named by physical
+    /// track and sector ID, along with a `manifest.txt` recording track parameters and sector
+    /// flags. Weak bit masks are stored alongside sector data where present.
+    /// The resulting archive can be edited and re-imported with [DiskImage::import_sector_tar].
+    #[cfg(feature = "tar")]
+    pub fn export_sector_tar(&self) -> Result<Vec<u8>, DiskImageError> {
+        crate::sector_tar::export_sector_tar(self)
+    }
+
+    /// Create a `MetaSector` resolution [DiskImage] from a tar archive produced by
+    /// [DiskImage::export_sector_tar].
+    /// # Returns
+    /// - `Err(DiskImageError::ImageCorruptError)` if the manifest is missing or invalid, or
+    ///   references a sector file not present in the archive.
+    #[cfg(feature = "tar")]
+    pub fn import_sector_tar(tar_data: &[u8]) -> Result<DiskImage, DiskImageError> {
+        crate::sector_tar::import_sector_tar(tar_data)
+    }
+
+    /// Export the disk image as a deterministic text dump, with each sector written as a hex dump
+    /// annotated with its ID and flags. Weak bit masks are dumped after sector data where present.
+    /// Small changes to an image produce small changes to the dump, making it suitable for
+    /// keeping in version control. The dump can be re-imported with [DiskImage::import_text_dump].
+    pub fn export_text_dump(&self) -> Result<String, DiskImageError> {
+        crate::text_dump::export_text_dump(self)
+    }
+
+    /// Create a `MetaSector` resolution [DiskImage] from a text dump produced by
+    /// [DiskImage::export_text_dump].
+    /// # Returns
+    /// - `Err(DiskImageError::ImageCorruptError)` if the dump header is missing, or the dump
+    ///   contains an invalid or out of sequence line.
+    pub fn import_text_dump(text: &str) -> Result<DiskImage, DiskImageError> {
+        crate::text_dump::import_text_dump(text)
+    }
+
     pub fn find_duplication_mark(&self) -> Option<(DiskCh, DiskChsn)> {
         for track in self.track_iter() {
             if let TrackDataEncoding::Fm = track.encoding() {
@@ -1990,6 +2985,8 @@ impl DiskImage {
     ///   non-standard tracks and sectors.
     /// - `None` if no format is found that closely matches the disk image, or the image data
     ///          is too inconsistent to determine a format.
+    ///
+    /// To see every plausible format ranked by confidence, use [DiskImage::detect_formats].
     pub fn closest_format(&self, trust_bpb: bool) -> Option<StandardFormat> {
         let mut bpb_format = None;
 
@@ -2107,4 +3104,886 @@ impl DiskImage {
     pub fn set_metadata_key(&mut self, key: &str, value: &str) {
         self.metadata.insert(key.to_string(), value.to_string());
     }
+
+    /// Return the descriptive metadata of the image, such as its title and comment, as a
+    /// [DiskImageMetadata].
+    pub fn image_metadata(&self) -> DiskImageMetadata {
+        let mut metadata = DiskImageMetadata::default();
+        for key in DiskImageMetadata::keys() {
+            metadata.set(key, self.metadata.get(key).map(String::as_str));
+        }
+        metadata
+    }
+
+    /// Replace the descriptive metadata of the image. Fields that are not set are removed from the
+    /// image. Image formats that can store a comment will carry this metadata when the image is
+    /// saved.
+    pub fn set_image_metadata(&mut self, metadata: &DiskImageMetadata) {
+        for key in DiskImageMetadata::keys() {
+            match metadata.get(key) {
+                Some(value) => self.set_metadata_key(key, value),
+                None => _ = self.metadata.remove(key),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bitstream_codec::mfm::MFM_BYTE_LEN,
+        file_parsers::{ParserWriteCompatibility, ParserWriteOptions},
+        flux::synthesis::FluxSynthesisOptions,
+        test_util::{formatted_disk, sector_disk},
+        types::{AddSectorParams, METADATA_KEY_TITLE},
+        SavingStatus,
+    };
+
+    fn test_disk(format: StandardFormat) -> DiskImage {
+        let layout = format.layout();
+        sector_disk(format, |chs| {
+            (
+                vec![chs.to_lba(&layout) as u8; format.sector_size()],
+                Default::default(),
+            )
+        })
+    }
+
+    #[test]
+    fn test_lba_read_write() {
+        let format = StandardFormat::PcFloppy360;
+        let mut disk = test_disk(format);
+        let total = format.layout().total_sectors();
+
+        for lba in [0, 8, 9, 17, 18, total - 1] {
+            let data = disk.read_lba(lba).unwrap();
+            assert!(data.iter().all(|&b| b == lba as u8), "LBA {} mismatch", lba);
+        }
+        assert!(matches!(disk.read_lba(total), Err(DiskImageError::SeekError)));
+
+        let data = vec![0xA5; format.sector_size()];
+        disk.write_lba(100, &data).unwrap();
+        assert_eq!(disk.read_lba(100).unwrap(), data);
+        assert!(matches!(
+            disk.write_lba(100, &data[..128]),
+            Err(DiskImageError::ParameterError)
+        ));
+    }
+
+    #[test]
+    fn test_large_sectors() {
+        // A MetaSector track stores sectors of any size up to N=7.
+        let mut disk = test_disk(StandardFormat::PcFloppy360);
+        let ch = DiskCh::new(0, 0);
+        let data: Vec<u8> = (0..16384).map(|i| (i / 128) as u8).collect();
+        disk.track_mut(ch)
+            .unwrap()
+            .add_sector(&AddSectorParams {
+                id_chsn: DiskChsn::new(0, 0, 0x20, 7),
+                data: &data,
+                ..Default::default()
+            })
+            .unwrap();
+        let rsr = disk
+            .read_sector(
+                ch,
+                DiskChsnQuery::new(0, 0, 0x20, 7),
+                None,
+                None,
+                RwScope::DataOnly,
+                false,
+            )
+            .unwrap();
+        assert_eq!(rsr.data(), &data[..]);
+
+        // An 8K sector overruns a BitStream track, so reading it wraps past the index.
+        let mut disk = formatted_disk();
+        disk.format_track(ch, vec![DiskChsn::new(0, 0, 1, 6)], &[0xE5], 0x54)
+            .unwrap();
+        let rsr = disk
+            .read_sector(ch, DiskChsnQuery::new(0, 0, 1, 6), None, None, RwScope::DataOnly, false)
+            .unwrap();
+        assert_eq!(rsr.data().len(), 8192);
+        assert!(rsr.data()[..4096].iter().all(|&b| b == 0xE5));
+        assert!(rsr.data_crc_error);
+    }
+
+    #[test]
+    fn test_read_sector_dtl() {
+        let mut disk = test_disk(StandardFormat::PcFloppy360);
+        let ch = DiskCh::new(0, 0);
+        let data: Vec<u8> = (0..128).collect();
+        disk.track_mut(ch)
+            .unwrap()
+            .add_sector(&AddSectorParams {
+                id_chsn: DiskChsn::new(0, 0, 0x20, 0),
+                data: &data,
+                ..Default::default()
+            })
+            .unwrap();
+        let id = DiskChsnQuery::new(0, 0, 0x20, None);
+
+        // With N=0, only DTL bytes are transferred, but the CRC covers the whole sector.
+        let rsr = disk.read_sector_dtl(ch, id, 0, 40, RwScope::DataOnly, false).unwrap();
+        assert_eq!(rsr.data(), &data[..40]);
+        assert!(!rsr.data_crc_error);
+        let rsr = disk.read_sector_dtl(ch, id, 0, 0xFF, RwScope::DataOnly, false).unwrap();
+        assert_eq!(rsr.data(), &data[..]);
+
+        // With any other N, DTL is ignored.
+        disk.set_size_mismatch_policy(SizeMismatchPolicy::Truncate);
+        let rsr = disk.read_sector_dtl(ch, id, 1, 40, RwScope::DataOnly, false).unwrap();
+        assert_eq!(rsr.data(), &data[..]);
+        assert!(rsr.data_crc_error);
+    }
+
+    #[test]
+    fn test_track_and_sector_iterators() {
+        let format = StandardFormat::PcFloppy360;
+        let mut disk = test_disk(format);
+        let layout = format.layout();
+
+        let chs: Vec<DiskCh> = disk.iter_tracks().map(|(ch, _)| ch).collect();
+        assert_eq!(chs.len(), layout.c() as usize * layout.h() as usize);
+        assert_eq!(chs[..3], [DiskCh::new(0, 0), DiskCh::new(0, 1), DiskCh::new(1, 0)]);
+        assert_eq!(disk.sectors().count(), layout.total_sectors());
+
+        let mut visited = 0;
+        disk.for_each_sector(RwScope::DataOnly, |sector, result| {
+            let lba = DiskChs::from(sector.id()).to_lba(&layout);
+            assert!(result.unwrap().data().iter().all(|&b| b == lba as u8));
+            visited += 1;
+        });
+        assert_eq!(visited, layout.total_sectors());
+
+        for (ch, track) in disk.iter_tracks_mut() {
+            if ch.h() == 1 {
+                track
+                    .remove_sector(DiskChsnQuery::new(ch.c(), ch.h(), 1, None))
+                    .unwrap();
+            }
+        }
+        assert_eq!(disk.sectors().count(), layout.total_sectors() - layout.c() as usize);
+        assert!(disk.sectors().all(|s| s.ch.h() == 0 || s.id().s() != 1));
+
+        // A sector sharing its ID with an earlier sector reads its own data.
+        disk.track_mut(DiskCh::new(0, 0))
+            .unwrap()
+            .add_sector(&AddSectorParams {
+                id_chsn: DiskChsn::new(0, 0, 1, layout.n()),
+                data: &vec![0xAA; format.sector_size()],
+                ..Default::default()
+            })
+            .unwrap();
+        let duplicates: Vec<Vec<u8>> = disk
+            .sectors()
+            .filter(|s| s.ch == DiskCh::new(0, 0) && s.id().s() == 1)
+            .map(|s| s.read(RwScope::DataOnly).unwrap().data().to_vec())
+            .collect();
+        assert_eq!(duplicates.len(), 2);
+        assert!(duplicates[0].iter().all(|&b| b == 0));
+        assert!(duplicates[1].iter().all(|&b| b == 0xAA));
+    }
+
+    #[test]
+    fn test_lba_irregular() {
+        let mut disk = test_disk(StandardFormat::PcFloppy360);
+        disk.track_mut(DiskCh::new(1, 0))
+            .unwrap()
+            .as_metasector_track_mut()
+            .unwrap()
+            .sectors
+            .clear();
+
+        assert!(disk.read_lba(0).is_ok());
+        assert!(matches!(disk.read_lba(18), Err(DiskImageError::IncompatibleImage(_))));
+    }
+
+    #[test]
+    fn test_concurrent_reads() {
+        let format = StandardFormat::PcFloppy360;
+        let disk = test_disk(format);
+        let layout = format.layout();
+
+        // Reads only need a shared reference, so several readers can work on the same image.
+        std::thread::scope(|scope| {
+            for h in 0..layout.h() {
+                let disk = &disk;
+                scope.spawn(move || {
+                    for c in 0..layout.c() {
+                        let ch = DiskCh::new(c, h);
+                        let rtr = disk.read_all_sectors(ch, ch, layout.n(), layout.s()).unwrap();
+                        assert_eq!(rtr.sectors_read, layout.s() as u16);
+                        let rsr = disk
+                            .read_sector(
+                                ch,
+                                DiskChsnQuery::new(c, h, 1, None),
+                                None,
+                                None,
+                                RwScope::DataOnly,
+                                false,
+                            )
+                            .unwrap();
+                        let lba = DiskChs::from((ch, 1)).to_lba(&layout);
+                        assert_eq!(rsr.read_buf[rsr.data_range], vec![lba as u8; format.sector_size()]);
+                    }
+                });
+            }
+        });
+
+        let track = disk.track(DiskCh::new(0, 0)).unwrap();
+        assert_eq!(track.hash(), track.hash());
+    }
+
+    #[test]
+    fn test_write_track() {
+        let mut disk = formatted_disk();
+
+        // Build write track data as an emulated WD177x would receive it for a 9 sector track, with
+        // sectors in reverse order, and a final gap long enough to run past the index.
+        let mut data = Vec::new();
+        data.extend_from_slice(&[0x4E; 80]);
+        data.extend_from_slice(&[0x00; 12]);
+        data.extend_from_slice(&[0xF6, 0xF6, 0xF6, 0xFC]);
+        data.extend_from_slice(&[0x4E; 50]);
+        for s in (1..=9u8).rev() {
+            data.extend_from_slice(&[0x00; 12]);
+            data.extend_from_slice(&[0xF5, 0xF5, 0xF5, 0xFE, 2, 1, s, 2, 0xF7]);
+            data.extend_from_slice(&[0x4E; 22]);
+            data.extend_from_slice(&[0x00; 12]);
+            data.extend_from_slice(&[0xF5, 0xF5, 0xF5, 0xFB]);
+            data.extend_from_slice(&[s; 512]);
+            data.push(0xF7);
+            data.extend_from_slice(&[0x4E; 80]);
+        }
+        data.extend_from_slice(&[0x4E; 1000]);
+
+        let ch = DiskCh::new(2, 1);
+        disk.write_track(ch, &data).unwrap();
+
+        let track = disk.track(ch).unwrap();
+        let ids: Vec<u8> = track.sector_list().iter().map(|s| s.chsn.s()).collect();
+        assert_eq!(ids, (1..=9).rev().collect::<Vec<u8>>());
+        assert!(track
+            .decoded_elements()
+            .all(|e| e.integrity.is_none_or(|crc| crc.is_valid())));
+
+        for s in 1..=9u8 {
+            let rsr = disk
+                .read_sector(ch, DiskChsnQuery::new(2, 1, s, 2), None, None, RwScope::DataOnly, false)
+                .unwrap();
+            assert!(!rsr.data_crc_error);
+            assert!(rsr.read_buf[rsr.data_range].iter().all(|&b| b == s));
+        }
+
+        // MetaSector tracks cannot be written as raw track data.
+        let mut disk = test_disk(StandardFormat::PcFloppy360);
+        assert!(matches!(
+            disk.write_track(DiskCh::new(0, 0), &data),
+            Err(DiskImageError::UnsupportedFormat)
+        ));
+    }
+
+    #[test]
+    fn test_next_id_timed() {
+        let disk = formatted_disk();
+        let ch = DiskCh::new(1, 0);
+        let bit_length = disk.track(ch).unwrap().info().bit_length;
+        let marks = disk.track(ch).unwrap().metadata().unwrap().id_marks();
+        assert_eq!(marks.len(), 9);
+
+        let first = disk.next_id_timed(ch, 0).unwrap();
+        assert_eq!(first.chsn, DiskChsn::new(1, 0, 1, 2));
+        assert_eq!(first.bit_offset, marks[0].bit_offset);
+        assert_eq!(first.bit_distance, first.bit_offset);
+        let expected = first.bit_offset as f64 / bit_length as f64 * 0.2;
+        assert!((first.time_from_index - expected).abs() < 1e-9);
+
+        // Just past the first address mark, the second sector is next.
+        let second = disk.next_id_timed(ch, first.bit_offset + 1).unwrap();
+        assert_eq!(second.chsn.s(), 2);
+        assert!((second.latency - (second.bit_distance as f64 / bit_length as f64 * 0.2)).abs() < 1e-9);
+
+        // Past the last address mark, the search wraps around the index. Positions past the end of
+        // the track wrap as well.
+        let wrapped = disk.next_id_timed(ch, marks[8].bit_offset + 1).unwrap();
+        assert_eq!(wrapped.chsn.s(), 1);
+        assert_eq!(
+            wrapped.bit_distance,
+            bit_length - marks[8].bit_offset - 1 + first.bit_offset
+        );
+        assert_eq!(disk.next_id_timed(ch, bit_length).unwrap().chsn.s(), 1);
+
+        // MetaSector tracks report estimated positions in track order.
+        let disk = test_disk(StandardFormat::PcFloppy360);
+        let first = disk.next_id_timed(ch, 0).unwrap();
+        let second = disk.next_id_timed(ch, first.bit_offset + 1).unwrap();
+        assert_eq!((first.chsn.s(), second.chsn.s()), (1, 2));
+        assert!(second.time_from_index > first.time_from_index && second.time_from_index < 0.2);
+        assert!(disk.next_id_timed(DiskCh::new(60, 0), 0).is_none());
+    }
+
+    #[test]
+    fn test_read_sector_rotational() {
+        let mut disk = formatted_disk();
+        let ch = DiskCh::new(0, 0);
+
+        // Two sectors share ID 1, distinguished by their size.
+        let ids = vec![
+            DiskChsn::new(0, 0, 1, 2),
+            DiskChsn::new(0, 0, 2, 2),
+            DiskChsn::new(0, 0, 1, 1),
+            DiskChsn::new(0, 0, 3, 2),
+        ];
+        disk.format_track(ch, ids, &[0xE5], 0x54).unwrap();
+        let marks = disk.track(ch).unwrap().metadata().unwrap().id_marks();
+        let bit_length = disk.track(ch).unwrap().info().bit_length;
+        let query = DiskChsnQuery::new(0, 0, 1, None);
+
+        // From the index, the first sector 1 is read, leaving the head past its data field.
+        let mut rotation = DiskRotation::default();
+        let rsr = disk
+            .read_sector_rotational(&mut rotation, ch, query, None, RwScope::DataOnly)
+            .unwrap();
+        assert_eq!(rsr.id_chsn.unwrap().n(), 2);
+        let position = rotation.bit_position(bit_length);
+        assert!(position > marks[0].bit_offset && position < marks[1].bit_offset);
+
+        // The next read of sector 1 finds the second sector with that ID.
+        let rsr = disk
+            .read_sector_rotational(&mut rotation, ch, query, None, RwScope::DataOnly)
+            .unwrap();
+        assert_eq!(rsr.id_chsn.unwrap().n(), 1);
+        assert_eq!(rsr.data().len(), 256);
+        assert_eq!(rotation.revolutions(), 0);
+
+        // And then wraps around the index to the first again.
+        let rsr = disk
+            .read_sector_rotational(&mut rotation, ch, query, None, RwScope::DataOnly)
+            .unwrap();
+        assert_eq!(rsr.id_chsn.unwrap().n(), 2);
+        assert_eq!(rotation.revolutions(), 1);
+
+        // A missing sector costs a full revolution.
+        let rsr = disk
+            .read_sector_rotational(
+                &mut rotation,
+                ch,
+                DiskChsnQuery::new(0, 0, 9, None),
+                None,
+                RwScope::DataOnly,
+            )
+            .unwrap();
+        assert!(rsr.not_found);
+        assert_eq!(rotation.revolutions(), 2);
+    }
+
+    #[test]
+    fn test_raw_bits() {
+        let mut disk = formatted_disk();
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+        let bit_len = track.info().bit_length;
+
+        // Find the sector ID address mark of the first sector.
+        let mark = track
+            .decoded_elements()
+            .find(|e| e.instance.element().is_sector_header())
+            .unwrap()
+            .instance
+            .range();
+        let mark_bits = track.read_raw_bits(mark.start..mark.start + 4 * MFM_BYTE_LEN).unwrap();
+        assert_eq!(mark_bits.len(), 64);
+
+        // Overwrite the address mark with a copy of the preceding gap bytes, and the sector is lost.
+        let gap_bits = track.read_raw_bits(mark.start - 4 * MFM_BYTE_LEN..mark.start).unwrap();
+        track.write_raw_bits(mark.start, &gap_bits).unwrap();
+        assert_eq!(track.sector_ct(), 8);
+        assert!(!track.has_sector_id(1, None));
+
+        // Restoring the address mark restores the sector.
+        track.write_raw_bits(mark.start, &mark_bits).unwrap();
+        assert_eq!(track.sector_ct(), 9);
+        assert!(track.has_sector_id(1, None));
+
+        assert!(matches!(
+            track.read_raw_bits(bit_len - 8..bit_len + 8),
+            Err(DiskImageError::ParameterError)
+        ));
+        assert!(matches!(
+            track.write_raw_bits(bit_len - 8, &mark_bits),
+            Err(DiskImageError::ParameterError)
+        ));
+
+        // MetaSector tracks have no bitcells.
+        let mut disk = test_disk(StandardFormat::PcFloppy360);
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+        assert!(matches!(
+            track.read_raw_bits(0..8),
+            Err(DiskImageError::UnsupportedFormat)
+        ));
+    }
+
+    #[test]
+    fn test_size_mismatch_policy() {
+        let mut disk = formatted_disk();
+        let ch = DiskCh::new(0, 0);
+        let id = DiskChsnQuery::new(0, 0, 1, None);
+        let read = |disk: &DiskImage, n: u8| {
+            disk.read_sector(ch, id, Some(n), None, RwScope::DataOnly, false)
+                .unwrap()
+        };
+
+        // Matching sizes are unaffected by the policy.
+        disk.set_size_mismatch_policy(SizeMismatchPolicy::Truncate);
+        assert_eq!(disk.size_mismatch_policy(), SizeMismatchPolicy::Truncate);
+        let stored = read(&disk, 2);
+        assert!(!stored.data_crc_error);
+        let stored_data = stored.read_buf[stored.data_range].to_vec();
+        assert_eq!(stored_data.len(), 512);
+
+        let short = read(&disk, 1);
+        assert_eq!(&short.read_buf[short.data_range], &stored_data[..256]);
+        assert!(short.data_crc_error);
+        let long = read(&disk, 3);
+        assert_eq!(&long.read_buf[long.data_range], &stored_data[..]);
+        assert!(long.data_crc_error);
+
+        // A long controller read continues into the data CRC and the following gap.
+        disk.set_size_mismatch_policy(SizeMismatchPolicy::Controller);
+        let long = read(&disk, 3);
+        let long_data = &long.read_buf[long.data_range];
+        assert_eq!(long_data.len(), 1024);
+        assert_eq!(&long_data[..512], &stored_data[..]);
+        assert!(long_data[514..530].iter().all(|&b| b == 0x4E));
+        assert!(long.data_crc_error);
+
+        disk.set_size_mismatch_policy(SizeMismatchPolicy::NotFound);
+        assert!(read(&disk, 1).not_found);
+        assert!(!read(&disk, 2).not_found);
+    }
+
+    #[test]
+    fn test_scp_flux_export() {
+        let format = StandardFormat::PcFloppy360;
+        let mut disk = formatted_disk();
+
+        // Sector images can't be written as flux.
+        assert_eq!(
+            DiskImageFileFormat::SuperCardPro.can_write(Some(&test_disk(format))),
+            ParserWriteCompatibility::Incompatible
+        );
+        assert_eq!(
+            DiskImageFileFormat::SuperCardPro.can_write(Some(&disk)),
+            ParserWriteCompatibility::Ok
+        );
+
+        let opts = ParserWriteOptions::default()
+            .with_flux_synthesis(FluxSynthesisOptions::realistic().with_seed(0x5C9))
+            .with_flux_revolutions(5);
+        let mut out = Cursor::new(Vec::new());
+        DiskImageFileFormat::SuperCardPro
+            .save_image(&mut disk, &opts, &mut out)
+            .unwrap();
+
+        let reloaded = DiskImage::load(&mut Cursor::new(out.into_inner()), None, None, None).unwrap();
+        assert_eq!(reloaded.source_format(), Some(DiskImageFileFormat::SuperCardPro));
+        assert_eq!(reloaded.geometry(), disk.geometry());
+        for ((ch, track), (_, source)) in reloaded.iter_tracks().zip(disk.iter_tracks()) {
+            assert_eq!(track.revolutions(), 5, "track {}", ch);
+            assert_eq!(track.sector_ct(), source.sector_ct(), "track {}", ch);
+        }
+        let diff = disk
+            .diff(&reloaded)
+            .retain_expressible(DiskImageFileFormat::SuperCardPro.capabilities());
+        assert!(diff.sectors.is_empty());
+    }
+
+    /// Return a formatted BitStream 360K disk, and an SCP image of it with two revolutions per
+    /// track.
+    fn scp_test_image() -> (DiskImage, Vec<u8>) {
+        let mut disk = formatted_disk();
+        let mut out = Cursor::new(Vec::new());
+        DiskImageFileFormat::SuperCardPro
+            .save_image(
+                &mut disk,
+                &ParserWriteOptions::default().with_flux_revolutions(2),
+                &mut out,
+            )
+            .unwrap();
+        (disk, out.into_inner())
+    }
+
+    #[test]
+    fn test_lazy_decode() {
+        let (disk, scp_image) = scp_test_image();
+
+        let is_decoded = |image: &DiskImage, ch: DiskCh| {
+            image.track_map[ch.h() as usize]
+                .get(ch.c() as usize)
+                .and_then(|&idx| image.track_pool[idx].as_fluxstream_track())
+                .unwrap()
+                .is_decoded()
+        };
+
+        let options = ParserReadOptions::default().with_decode_policy(DecodePolicy::Lazy);
+        let mut lazy = DiskImage::load_with_options(&mut Cursor::new(&scp_image), None, None, &options, None).unwrap();
+        assert_eq!(lazy.decode_policy(), DecodePolicy::Lazy);
+        assert_eq!(lazy.geometry(), disk.geometry());
+        assert!(is_decoded(&lazy, DiskCh::new(0, 0)));
+        assert!(!is_decoded(&lazy, DiskCh::new(0, 1)));
+        assert!(!lazy.all_tracks_decoded());
+
+        // Reading a sector decodes only the track it is on.
+        let ch = DiskCh::new(20, 1);
+        let id = DiskChsnQuery::new(20, 1, 1, 2);
+        let data = lazy.read_sector_basic(ch, id, None).unwrap();
+        assert_eq!(data, disk.read_sector_basic(ch, id, None).unwrap());
+        assert!(is_decoded(&lazy, ch));
+        assert!(!is_decoded(&lazy, DiskCh::new(21, 0)));
+
+        lazy.decode_all_tracks();
+        assert!(lazy.all_tracks_decoded());
+        let eager = DiskImage::load(&mut Cursor::new(&scp_image), None, None, None).unwrap();
+        assert!(eager.diff(&lazy).sectors.is_empty());
+        assert_eq!(
+            eager.analysis.consistent_track_length,
+            lazy.analysis.consistent_track_length
+        );
+
+        // Prefetching decodes the tracks following each track accessed.
+        let options = ParserReadOptions::default().with_decode_policy(DecodePolicy::LazyPrefetch(2));
+        let prefetch = DiskImage::load_with_options(&mut Cursor::new(&scp_image), None, None, &options, None).unwrap();
+        assert!(is_decoded(&prefetch, DiskCh::new(1, 0)));
+        assert!(!is_decoded(&prefetch, DiskCh::new(1, 1)));
+        _ = prefetch.track(DiskCh::new(10, 0));
+        // Prefetched tracks may be decoded in the background.
+        let start = Instant::now();
+        while !(is_decoded(&prefetch, DiskCh::new(10, 1)) && is_decoded(&prefetch, DiskCh::new(11, 0))) {
+            assert!(start.elapsed() < Duration::from_secs(60), "prefetch timed out");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!is_decoded(&prefetch, DiskCh::new(10, 0)));
+        assert!(!is_decoded(&prefetch, DiskCh::new(11, 1)));
+
+        // Writing a lazily loaded image as flux decodes it first.
+        let mut lazy = DiskImage::load_with_options(&mut Cursor::new(&scp_image), None, None, &options, None).unwrap();
+        let mut out = Cursor::new(Vec::new());
+        DiskImageFileFormat::SuperCardPro
+            .save_image(&mut lazy, &ParserWriteOptions::default(), &mut out)
+            .unwrap();
+        assert!(lazy.all_tracks_decoded());
+        let rewritten = DiskImage::load(&mut Cursor::new(out.into_inner()), None, None, None).unwrap();
+        assert!(rewritten.diff(&disk).sectors.is_empty());
+    }
+
+    #[test]
+    fn test_memory_usage_and_compact() {
+        let usage = test_disk(StandardFormat::PcFloppy360).memory_usage();
+        assert_eq!(usage.tracks.len(), 80);
+        assert!(usage.sum().sectors >= 360 * 1024);
+        assert_eq!(usage.sum().flux, 0);
+
+        let (disk, scp_image) = scp_test_image();
+        let mut flux = DiskImage::load(&mut Cursor::new(&scp_image), None, None, None).unwrap();
+        let usage = flux.memory_usage().sum();
+        assert!(usage.flux > 0 && usage.bitcells > 0 && usage.metadata > 0);
+        assert_eq!(usage.sectors, 0);
+
+        let total = flux.memory_usage().total();
+        let freed = flux.compact(RetentionPolicy::BestRevolution);
+        assert!(freed > 0);
+        assert_eq!(flux.memory_usage().total(), total - freed);
+        assert_eq!(flux.memory_usage().sum().flux, usage.flux);
+        assert!(flux.diff(&disk).sectors.is_empty());
+
+        assert!(flux.compact(RetentionPolicy::BitStream) > 0);
+        assert_eq!(flux.resolution(), vec![TrackDataResolution::BitStream]);
+        assert_eq!(flux.memory_usage().sum().flux, 0);
+        assert!(flux.diff(&disk).sectors.is_empty());
+    }
+
+    #[test]
+    fn test_progress_callbacks() {
+        use std::sync::{Arc, Mutex};
+
+        let mut disk = test_disk(StandardFormat::PcFloppy360);
+        disk.update_analysis();
+        let saved = Arc::new(Mutex::new(Vec::new()));
+        let sink = saved.clone();
+        let opts = ParserWriteOptions::default().with_callback(Arc::new(move |status| {
+            if let SavingStatus::Track { ch, index, total } = status {
+                sink.lock().unwrap().push((ch, index, total));
+            }
+        }));
+        let mut out = Cursor::new(Vec::new());
+        DiskImageFileFormat::RawSectorImage
+            .save_image(&mut disk, &opts, &mut out)
+            .unwrap();
+
+        let raw_saved = saved.lock().unwrap().clone();
+        assert_eq!(raw_saved.len(), 80);
+        assert_eq!(raw_saved[0], (DiskCh::new(0, 0), 0, 80));
+        assert_eq!(raw_saved[79], (DiskCh::new(39, 1), 79, 80));
+
+        // Every writer reports each track it writes.
+        let mut bitstream_disk = formatted_disk();
+        for format in [
+            DiskImageFileFormat::PceBitstreamImage,
+            DiskImageFileFormat::F86Image,
+            DiskImageFileFormat::SuperCardPro,
+        ] {
+            saved.lock().unwrap().clear();
+            format
+                .save_image(&mut bitstream_disk, &opts, &mut Cursor::new(Vec::new()))
+                .unwrap();
+            let saved = saved.lock().unwrap();
+            assert!(saved.len() >= 80, "{:?}", format);
+            for (i, (_, index, total)) in saved.iter().enumerate() {
+                assert_eq!((*index, *total), (i, saved.len()), "{:?}", format);
+            }
+            assert_eq!(saved[0].0, DiskCh::new(0, 0), "{:?}", format);
+        }
+
+        // Record the stages of a load, and whether it completed or failed.
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let callback: LoadingCallback = Arc::new(move |status| {
+            let event = match status {
+                LoadingStatus::Stage(stage) => Ok(stage),
+                LoadingStatus::Complete => Err(true),
+                LoadingStatus::Error => Err(false),
+                _ => return,
+            };
+            sink.lock().unwrap().push(event);
+        });
+
+        DiskImage::load(&mut Cursor::new(out.into_inner()), None, None, Some(callback.clone())).unwrap();
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                Ok(LoadingStage::Detecting),
+                Ok(LoadingStage::Reading),
+                Ok(LoadingStage::Analyzing),
+                Err(true)
+            ]
+        );
+
+        events.lock().unwrap().clear();
+        assert!(DiskImage::load(&mut Cursor::new(vec![0x55; 100]), None, None, Some(callback)).is_err());
+        assert_eq!(*events.lock().unwrap(), vec![Ok(LoadingStage::Detecting), Err(false)]);
+    }
+
+    #[test]
+    fn test_source_format_info() {
+        let mut disk = formatted_disk();
+        assert!(disk.source_format_info().is_none());
+
+        let mut out = Cursor::new(Vec::new());
+        DiskImageFileFormat::SuperCardPro
+            .save_image(&mut disk, &ParserWriteOptions::default(), &mut out)
+            .unwrap();
+        let mut reloaded = DiskImage::load(&mut Cursor::new(out.into_inner()), None, None, None).unwrap();
+
+        let info = reloaded.source_format_info().unwrap();
+        assert_eq!(info.format, DiskImageFileFormat::SuperCardPro);
+        assert_eq!(info.parser_name(), "scp");
+        assert_eq!(info.version.as_deref(), Some("2.2"));
+        assert_eq!(info.variant.as_deref(), Some("index aligned, non-SCP capture"));
+        assert!(info.quirks.is_empty());
+
+        let mut report = Vec::new();
+        reloaded.dump_info(&mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.contains("Source Format: SuperCard Pro Flux v2.2 (index aligned, non-SCP capture) [scp]"));
+
+        // Resetting the image retains its source format.
+        reloaded.reset_image();
+        assert_eq!(reloaded.source_format(), Some(DiskImageFileFormat::SuperCardPro));
+    }
+
+    #[test]
+    fn test_unformatted_tracks() {
+        let format = StandardFormat::PcFloppy360;
+        let mut disk = test_disk(format);
+        disk.track_mut(DiskCh::new(1, 0))
+            .unwrap()
+            .as_metasector_track_mut()
+            .unwrap()
+            .sectors
+            .clear();
+        disk.update_analysis();
+        assert_eq!(disk.unformatted_tracks(), vec![DiskCh::new(1, 0)]);
+        assert_eq!(disk.analysis.unformatted_tracks, 1);
+
+        // A raw sector image writes the sectors of an unformatted track as zeros.
+        let mut out = Cursor::new(Vec::new());
+        DiskImageFileFormat::RawSectorImage
+            .save_image(&mut disk, &ParserWriteOptions::default(), &mut out)
+            .unwrap();
+        let track_len = format.layout().s() as usize * format.sector_size();
+        let raw = out.into_inner();
+        assert_eq!(raw.len(), format.layout().total_sectors() * format.sector_size());
+        assert!(raw[2 * track_len..3 * track_len].iter().all(|&b| b == 0));
+        assert!(raw[3 * track_len..4 * track_len].iter().all(|&b| b != 0));
+
+        // A blank BitStream track is unformatted, and so is a track of noise.
+        let mut disk = formatted_disk();
+        let track = disk.track_mut(DiskCh::new(1, 0)).unwrap();
+        let bit_len = track.info().bit_length;
+        track.write_raw_bits(0, &BitVec::from_fn(bit_len, random_bit)).unwrap();
+        assert_eq!(track.formatting(), TrackFormatting::Unformatted { noise: true });
+        assert_eq!(disk.unformatted_tracks(), vec![DiskCh::new(1, 0)]);
+
+        // 86F preserves noise as weak bits. A 40-track image is written double-stepped, so the
+        // track is found at cylinders 2 and 3 of the reloaded image.
+        let mut out = Cursor::new(Vec::new());
+        DiskImageFileFormat::F86Image
+            .save_image(&mut disk, &ParserWriteOptions::default(), &mut out)
+            .unwrap();
+        let reloaded = DiskImage::load(&mut Cursor::new(out.into_inner()), None, None, None).unwrap();
+        assert_eq!(
+            reloaded.unformatted_tracks(),
+            vec![DiskCh::new(2, 0), DiskCh::new(3, 0)]
+        );
+        assert_eq!(
+            reloaded.track(DiskCh::new(2, 0)).unwrap().formatting(),
+            TrackFormatting::Unformatted { noise: true }
+        );
+
+        let mut disk = DiskImage::create(format);
+        disk.set_resolution(TrackDataResolution::BitStream);
+        disk.add_unformatted_track(DiskCh::new(0, 0), format.encoding(), format.data_rate(), bit_len, false)
+            .unwrap();
+        disk.add_unformatted_track(DiskCh::new(0, 1), format.encoding(), format.data_rate(), bit_len, true)
+            .unwrap();
+        assert_eq!(
+            disk.track(DiskCh::new(0, 0)).unwrap().formatting(),
+            TrackFormatting::Unformatted { noise: false }
+        );
+        assert_eq!(
+            disk.track(DiskCh::new(0, 1)).unwrap().formatting(),
+            TrackFormatting::Unformatted { noise: true }
+        );
+
+        // PRI writes noise as weak bits, and SCP writes a blank track with no flux transitions.
+        for format in [
+            DiskImageFileFormat::PceBitstreamImage,
+            DiskImageFileFormat::SuperCardPro,
+        ] {
+            let mut out = Cursor::new(Vec::new());
+            format
+                .save_image(&mut disk, &ParserWriteOptions::default(), &mut out)
+                .unwrap();
+            let reloaded = DiskImage::load(&mut Cursor::new(out.into_inner()), None, None, None).unwrap();
+            for (ch, noise) in [(DiskCh::new(0, 0), false), (DiskCh::new(0, 1), true)] {
+                assert_eq!(
+                    reloaded.track(ch).unwrap().formatting(),
+                    TrackFormatting::Unformatted { noise },
+                    "{:?} track {}",
+                    format,
+                    ch
+                );
+            }
+            if format == DiskImageFileFormat::PceBitstreamImage {
+                assert!(reloaded.track(DiskCh::new(0, 1)).unwrap().has_weak_bits());
+            }
+        }
+    }
+
+    #[test]
+    fn test_image_metadata() {
+        let mut disk = formatted_disk();
+        assert!(disk.image_metadata().is_empty());
+
+        // PRI images store the metadata in TEXT chunks, split if longer than a single chunk.
+        let metadata = DiskImageMetadata {
+            title: Some("Demo Disk".to_string()),
+            comment: Some("\u{00E9}".repeat(700)),
+            dumper: Some("fluxfox".to_string()),
+            ..Default::default()
+        };
+        disk.set_image_metadata(&metadata);
+        assert_eq!(disk.metadata_key(METADATA_KEY_TITLE).as_deref(), Some("Demo Disk"));
+
+        let mut out = Cursor::new(Vec::new());
+        DiskImageFileFormat::PceBitstreamImage
+            .save_image(&mut disk, &ParserWriteOptions::default(), &mut out)
+            .unwrap();
+        let reloaded = DiskImage::load(&mut Cursor::new(out.into_inner()), None, None, None).unwrap();
+        assert_eq!(reloaded.image_metadata(), metadata);
+
+        disk.set_image_metadata(&DiskImageMetadata::default());
+        assert_eq!(disk.metadata_key(METADATA_KEY_TITLE), None);
+    }
+
+    #[test]
+    fn test_decoded_elements() {
+        let mut disk = formatted_disk();
+
+        let ch = DiskCh::new(0, 0);
+        let track = disk.track(ch).unwrap();
+        let headers: Vec<_> = track
+            .decoded_elements()
+            .filter(|e| e.instance.element().is_sector_header())
+            .collect();
+        assert_eq!(headers.len(), 9);
+        for (i, header) in headers.iter().enumerate() {
+            assert_eq!(header.chsn.unwrap().s(), i as u8 + 1);
+            let crc = header.integrity.unwrap();
+            assert!(crc.is_valid());
+            assert_eq!(crc.recorded(), Some(crc.calculated()));
+        }
+
+        // Corrupt the first sector's data and check that the recorded CRC no longer matches.
+        let data = track
+            .decoded_elements()
+            .find(|e| e.instance.element().is_sector_data())
+            .unwrap();
+        let stream = disk.track_mut(ch).unwrap().stream_mut().unwrap();
+        stream.write_encoded_buf(&[0xA5], data.instance.range().start + 8 * 16);
+
+        let track = disk.track(ch).unwrap();
+        let data = track
+            .decoded_elements()
+            .find(|e| e.instance.element().is_sector_data())
+            .unwrap();
+        let crc = data.integrity.unwrap();
+        assert!(crc.is_error());
+        assert_ne!(crc.recorded(), Some(crc.calculated()));
+        assert!(track
+            .decoded_elements()
+            .all(|e| e.chsn.is_some() == e.integrity.is_some()));
+    }
+
+    #[test]
+    fn test_sector_list_orphans() {
+        let mut disk = formatted_disk();
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+
+        // Overwrite the address mark of the third sector with gap bytes, orphaning its data field.
+        let mark = track
+            .decoded_elements()
+            .filter(|e| e.instance.element().is_sector_header())
+            .nth(2)
+            .unwrap()
+            .instance
+            .range();
+        let gap_bits = track.read_raw_bits(mark.start - 4 * MFM_BYTE_LEN..mark.start).unwrap();
+        track.write_raw_bits(mark.start, &gap_bits).unwrap();
+
+        let sector_list = track.sector_list();
+        assert_eq!(sector_list.len(), 8);
+        assert!(sector_list
+            .iter()
+            .all(|e| !e.orphan && !e.is_duplicate() && e.is_read_target()));
+
+        let full_list = track.sector_list_with_orphans();
+        assert_eq!(full_list.len(), 9);
+        assert_eq!(full_list[1].chsn.s(), 2);
+        assert!(full_list[2].orphan);
+        assert!(!full_list[2].is_read_target());
+        assert_eq!(full_list[3].chsn.s(), 4);
+        assert!(full_list.windows(2).all(|w| w[0].data_offset < w[1].data_offset));
+    }
 }
@@ -0,0 +1,195 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/fdc/mod.rs
+
+    Facades mapping floppy disk controller commands onto DiskImage operations,
+    and the controller-agnostic primitives they share.
+*/
+
+//! Facades for emulating floppy disk controllers on top of a [DiskImage].
+//!
+//! Each submodule maps the commands of one family of controllers onto [DiskImage] operations and
+//! synthesizes that controller's status registers:
+//!
+//! * [upd765] - the NEC µPD765 and compatibles, as used in the IBM PC and Amstrad CPC/PCW.
+//! * [wd177x] - the Western Digital WD1771, WD1772 and WD179x, as used in the TRS-80, Atari ST
+//!   and many other machines.
+//!
+//! This module provides the primitives that are common to all controllers: the ID fields of a
+//! track as the controller encounters them, with their recorded CRC, and the type of data address
+//! mark preceding a sector's data.
+
+pub mod upd765;
+pub mod wd177x;
+
+use crate::{
+    types::{DiskCh, DiskChsn, RwScope, TrackDataEncoding},
+    util::crc_ibm_3740,
+    DiskImage,
+};
+
+/// The type of data address mark preceding the data field of a sector.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DataMark {
+    /// A normal data address mark (0xFB).
+    #[default]
+    Normal,
+    /// A deleted data address mark (0xF8).
+    Deleted,
+}
+
+impl DataMark {
+    /// Return the [DataMark] for a sector given whether it has a deleted data address mark.
+    pub fn from_deleted(deleted: bool) -> Self {
+        match deleted {
+            true => DataMark::Deleted,
+            false => DataMark::Normal,
+        }
+    }
+
+    /// Return true if this is a deleted data address mark.
+    pub fn is_deleted(&self) -> bool {
+        matches!(self, DataMark::Deleted)
+    }
+
+    /// Return the address mark byte that identifies this data mark on disk.
+    pub fn byte(&self) -> u8 {
+        match self {
+            DataMark::Normal => 0xFB,
+            DataMark::Deleted => 0xF8,
+        }
+    }
+}
+
+/// An ID field as read by a controller from an ID address mark (IDAM).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IdField {
+    /// The sector ID (C, H, R, N) recorded in the ID field.
+    pub id: DiskChsn,
+    /// The CRC recorded in the ID field.
+    pub crc: u16,
+    /// Whether the recorded CRC does not match the ID field.
+    pub crc_error: bool,
+}
+
+/// Calculate the CRC of an ID field with the sector ID `id` on a track using `encoding`. The CRC
+/// covers the address mark and, for MFM, the three 0xA1 sync bytes preceding it.
+pub fn id_field_crc(encoding: TrackDataEncoding, id: DiskChsn) -> u16 {
+    let mut header = match encoding {
+        TrackDataEncoding::Mfm => vec![0xA1; 3],
+        _ => Vec::with_capacity(5),
+    };
+    header.extend_from_slice(&[0xFE, id.c() as u8, id.h(), id.s(), id.n()]);
+    crc_ibm_3740(&header, None)
+}
+
+/// Return the ID fields of the track at `phys_ch` in the order a controller encounters them from
+/// the index, including those with CRC errors. Returns an empty list if there is no track at
+/// `phys_ch`.
+pub fn id_fields(disk: &DiskImage, phys_ch: DiskCh) -> Vec<IdField> {
+    let Some(track) = disk.track(phys_ch)
+    else {
+        return Vec::new();
+    };
+    let encoding = track.encoding();
+
+    track
+        .sector_list()
+        .iter()
+        .map(|entry| {
+            let calculated = id_field_crc(encoding, entry.chsn);
+            let crc = match entry.attributes.address_error {
+                // Prefer the CRC actually recorded on the track, where the track can report it.
+                true => track
+                    .read_sector(entry.chsn.into(), None, None, RwScope::EntireElement, false)
+                    .ok()
+                    .and_then(|rsr| rsr.address_crc)
+                    .and_then(|crc| crc.recorded())
+                    .unwrap_or(!calculated),
+                false => calculated,
+            };
+            IdField {
+                id: entry.chsn,
+                crc,
+                crc_error: entry.attributes.address_error,
+            }
+        })
+        .collect()
+}
+
+/// Return the next ID field a controller encounters on the track at `phys_ch`, whether or not its
+/// CRC is valid, or `None` if the track has no ID fields.
+///
+/// `index` selects which ID field is encountered, counted from the index and wrapping around the
+/// track, so that an emulator can model the rotation of the disk if it wishes.
+pub fn read_address(disk: &DiskImage, phys_ch: DiskCh, index: usize) -> Option<IdField> {
+    let fields = id_fields(disk, phys_ch);
+    match fields.is_empty() {
+        true => None,
+        false => Some(fields[index % fields.len()]),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{test_util::sector_disk, types::SectorAttributes, StandardFormat};
+
+    // Build a 360K MetaSector disk where every byte of a sector is its sector number. Sector 5 of
+    // track 0 has a deleted data mark, sector 7 of track 0 has a data CRC error and sector 3 of
+    // track 0, head 1 has an address CRC error.
+    pub(crate) fn test_disk() -> DiskImage {
+        sector_disk(StandardFormat::PcFloppy360, |chs| {
+            let first_track = chs.ch() == DiskCh::new(0, 0);
+            let attributes = SectorAttributes {
+                deleted_mark: first_track && chs.s() == 5,
+                data_error: first_track && chs.s() == 7,
+                address_error: chs.ch() == DiskCh::new(0, 1) && chs.s() == 3,
+                ..Default::default()
+            };
+            (vec![chs.s(); 512], attributes)
+        })
+    }
+
+    #[test]
+    fn test_read_address() {
+        let disk = test_disk();
+
+        let field = read_address(&disk, DiskCh::new(0, 0), 0).unwrap();
+        assert_eq!(field.id, DiskChsn::new(0, 0, 1, 2));
+        assert_eq!(field.crc, 0xCA6F);
+        assert!(!field.crc_error);
+
+        // ID fields with a bad CRC are reported, with the bad CRC recorded on the track.
+        let field = read_address(&disk, DiskCh::new(0, 1), 11).unwrap();
+        assert_eq!(field.id, DiskChsn::new(0, 1, 3, 2));
+        assert!(field.crc_error);
+        assert_ne!(field.crc, id_field_crc(TrackDataEncoding::Mfm, field.id));
+
+        assert!(read_address(&disk, DiskCh::new(60, 0), 0).is_none());
+    }
+}
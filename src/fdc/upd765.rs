@@ -0,0 +1,812 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/fdc/upd765.rs
+
+    A facade mapping NEC µPD765 floppy disk controller commands onto DiskImage
+    operations.
+*/
+
+//! A facade for emulating a NEC µPD765-compatible floppy disk controller on top of a [DiskImage].
+//!
+//! Each function in this module carries out the execution phase of a single controller command
+//! and returns an [FdcResult] containing the ST0, ST1 and ST2 status registers and the sector ID
+//! reported in the result phase, synthesized from the [ReadSectorResult] and
+//! [WriteSectorResult](crate::types::WriteSectorResult) of the underlying [DiskImage] operations.
+//! An emulator only needs to translate the command bytes written by the guest into an
+//! [FdcCommand] and return the result bytes from [FdcResult::result_bytes].
+//!
+//! The controller's terminal count (TC) signal, normally asserted by the DMA controller once the
+//! requested number of bytes is transferred, is modelled by [FdcCommand::terminal_count]. Without
+//! it, a data transfer command continues to the sector given by EOT and ends with an End of
+//! Cylinder error, as real hardware does.
+//!
+//! The facade keeps no state of its own. Seeking, drive selection and the rotational position of
+//! the disk are left to the emulator; [read_id] takes the index of the ID field to report so that
+//! an emulator can model rotation if it wishes, as described for [read_address](super::read_address).
+
+use crate::{
+    fdc::id_fields,
+    types::{DiskCh, DiskChsn, DiskChsnQuery, ReadSectorResult, RwScope},
+    DiskImage,
+    DiskImageError,
+};
+use bitflags::bitflags;
+
+bitflags! {
+    /// Bits of the µPD765 status register 0 (ST0).
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    #[rustfmt::skip]
+    pub struct St0: u8 {
+        #[doc = "Interrupt code 01: the command terminated abnormally"]
+        const ABNORMAL_TERMINATION = 0b0100_0000;
+        #[doc = "Interrupt code 10: the command was invalid"]
+        const INVALID_COMMAND      = 0b1000_0000;
+        #[doc = "Seek end"]
+        const SEEK_END             = 0b0010_0000;
+        #[doc = "Equipment check"]
+        const EQUIPMENT_CHECK      = 0b0001_0000;
+        #[doc = "Not ready"]
+        const NOT_READY            = 0b0000_1000;
+        #[doc = "Head address: head 1 was selected"]
+        const HEAD_ADDRESS         = 0b0000_0100;
+        #[doc = "Unit select 1"]
+        const UNIT_SELECT_1        = 0b0000_0010;
+        #[doc = "Unit select 0"]
+        const UNIT_SELECT_0        = 0b0000_0001;
+    }
+}
+
+bitflags! {
+    /// Bits of the µPD765 status register 1 (ST1).
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    #[rustfmt::skip]
+    pub struct St1: u8 {
+        #[doc = "End of cylinder: a transfer continued past the sector given by EOT"]
+        const END_OF_CYLINDER      = 0b1000_0000;
+        #[doc = "Data error: a CRC error was detected in the ID or data field"]
+        const DATA_ERROR           = 0b0010_0000;
+        #[doc = "Overrun: the host did not service the controller in time"]
+        const OVERRUN              = 0b0001_0000;
+        #[doc = "No data: the requested sector could not be found"]
+        const NO_DATA              = 0b0000_0100;
+        #[doc = "Not writable: the disk is write protected"]
+        const NOT_WRITABLE         = 0b0000_0010;
+        #[doc = "Missing address mark: no ID address mark was found"]
+        const MISSING_ADDRESS_MARK = 0b0000_0001;
+    }
+}
+
+bitflags! {
+    /// Bits of the µPD765 status register 2 (ST2).
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    #[rustfmt::skip]
+    pub struct St2: u8 {
+        #[doc = "Control mark: a sector with the opposite kind of data address mark was encountered"]
+        const CONTROL_MARK              = 0b0100_0000;
+        #[doc = "Data error in data field: a CRC error was detected in the data field"]
+        const DATA_ERROR_IN_DATA_FIELD  = 0b0010_0000;
+        #[doc = "Wrong cylinder: a sector ID with a different cylinder was found"]
+        const WRONG_CYLINDER            = 0b0001_0000;
+        #[doc = "Scan equal hit: a scan command found matching data"]
+        const SCAN_EQUAL_HIT            = 0b0000_1000;
+        #[doc = "Scan not satisfied: a scan command found no matching data"]
+        const SCAN_NOT_SATISFIED        = 0b0000_0100;
+        #[doc = "Bad cylinder: a sector ID with a cylinder of 0xFF was found"]
+        const BAD_CYLINDER              = 0b0000_0010;
+        #[doc = "Missing data address mark: a sector ID had no following data field"]
+        const MISSING_DATA_ADDRESS_MARK = 0b0000_0001;
+    }
+}
+
+/// The parameters of a µPD765 data transfer command, as written to the controller in the command
+/// phase.
+#[derive(Copy, Clone, Debug)]
+pub struct FdcCommand {
+    /// The drive unit select bits (US0, US1).
+    pub drive: u8,
+    /// The physical cylinder the head is positioned over, and the head selected (HD).
+    pub phys_ch: DiskCh,
+    /// The sector ID (C, H, R, N) of the first sector to transfer.
+    pub id: DiskChsn,
+    /// The sector number of the final sector on the track (EOT).
+    pub eot: u8,
+    /// The number of bytes to transfer per sector (DTL). Only used when N is 0.
+    pub dtl: u8,
+    /// The multi-track flag (MT). Continue on head 1 after the final sector on head 0.
+    pub multi_track: bool,
+    /// The skip flag (SK). Skip sectors with a control mark instead of terminating on them.
+    pub skip: bool,
+    /// The number of bytes after which the host signals terminal count (TC), if any.
+    pub terminal_count: Option<usize>,
+}
+
+impl FdcCommand {
+    /// Create a new [FdcCommand] transferring sectors from `id` to `eot` on the track at
+    /// `phys_ch`. DTL defaults to 0xFF, MT and SK are unset, and no terminal count is signalled.
+    pub fn new(drive: u8, phys_ch: DiskCh, id: DiskChsn, eot: u8) -> Self {
+        Self {
+            drive,
+            phys_ch,
+            id,
+            eot,
+            dtl: 0xFF,
+            multi_track: false,
+            skip: false,
+            terminal_count: None,
+        }
+    }
+
+    /// Set the data length (DTL) parameter.
+    pub fn with_dtl(self, dtl: u8) -> Self {
+        Self { dtl, ..self }
+    }
+
+    /// Set the multi-track (MT) flag.
+    pub fn with_multi_track(self, multi_track: bool) -> Self {
+        Self { multi_track, ..self }
+    }
+
+    /// Set the skip (SK) flag.
+    pub fn with_skip(self, skip: bool) -> Self {
+        Self { skip, ..self }
+    }
+
+    /// Signal terminal count (TC) once `bytes` bytes have been transferred.
+    pub fn with_terminal_count(self, bytes: usize) -> Self {
+        Self {
+            terminal_count: Some(bytes),
+            ..self
+        }
+    }
+}
+
+/// The outcome of a µPD765 command: its status registers, the sector ID reported in the result
+/// phase, and any data read from the disk.
+#[derive(Clone, Debug)]
+pub struct FdcResult {
+    pub st0:  St0,
+    pub st1:  St1,
+    pub st2:  St2,
+    /// The sector ID (C, H, R, N) reported in the result phase.
+    pub id:   DiskChsn,
+    /// The data transferred to the host by a read or scan command.
+    pub data: Vec<u8>,
+}
+
+impl FdcResult {
+    fn new(drive: u8, phys_ch: DiskCh, id: DiskChsn) -> Self {
+        let mut result = Self {
+            st0: St0::from_bits_retain(drive & 0x03),
+            st1: St1::empty(),
+            st2: St2::empty(),
+            id,
+            data: Vec::new(),
+        };
+        result.set_head(phys_ch.h());
+        result
+    }
+
+    fn set_head(&mut self, head: u8) {
+        self.st0.set(St0::HEAD_ADDRESS, head & 0x01 != 0);
+    }
+
+    fn fail(&mut self, st1: St1, st2: St2) {
+        self.st0 |= St0::ABNORMAL_TERMINATION;
+        self.st1 |= st1;
+        self.st2 |= st2;
+    }
+
+    /// Return the seven bytes of the result phase: ST0, ST1, ST2, C, H, R and N.
+    pub fn result_bytes(&self) -> [u8; 7] {
+        [
+            self.st0.bits(),
+            self.st1.bits(),
+            self.st2.bits(),
+            self.id.c() as u8,
+            self.id.h(),
+            self.id.s(),
+            self.id.n(),
+        ]
+    }
+
+    /// Return true if the command terminated normally (interrupt code 00).
+    pub fn is_ok(&self) -> bool {
+        !self.st0.intersects(St0::ABNORMAL_TERMINATION | St0::INVALID_COMMAND)
+    }
+}
+
+/// Tracks the sector ID and physical head as a command steps through the sectors of a track.
+struct SectorWalk {
+    cmd: FdcCommand,
+    c: u16,
+    h: u8,
+    r: u8,
+    phys_h: u8,
+}
+
+impl SectorWalk {
+    fn new(cmd: &FdcCommand) -> Self {
+        Self {
+            cmd: *cmd,
+            c: cmd.id.c(),
+            h: cmd.id.h(),
+            r: cmd.id.s(),
+            phys_h: cmd.phys_ch.h(),
+        }
+    }
+
+    fn phys_ch(&self) -> DiskCh {
+        DiskCh::new(self.cmd.phys_ch.c(), self.phys_h)
+    }
+
+    fn id(&self) -> DiskChsn {
+        DiskChsn::new(self.c, self.h, self.r, self.cmd.id.n())
+    }
+
+    fn query(&self) -> DiskChsnQuery {
+        DiskChsnQuery::new(self.c, self.h, self.r, None)
+    }
+
+    fn at_eot(&self, step: u8) -> bool {
+        self.r as u16 + step as u16 > self.cmd.eot as u16
+    }
+
+    /// Step to the next sector, switching to head 1 if multi-track is set. Returns false if the
+    /// end of the cylinder was reached.
+    fn advance(&mut self, step: u8) -> bool {
+        if !self.at_eot(step) {
+            self.r += step;
+            true
+        }
+        else if self.cmd.multi_track && self.phys_h == 0 {
+            self.phys_h = 1;
+            self.h ^= 1;
+            self.r = 1;
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    /// Return the sector ID the controller reports after completing the current sector.
+    fn next_id(&self) -> DiskChsn {
+        let n = self.cmd.id.n();
+        match (self.at_eot(1), self.cmd.multi_track, self.phys_h) {
+            (false, _, _) => DiskChsn::new(self.c, self.h, self.r + 1, n),
+            (true, true, 0) => DiskChsn::new(self.c, self.h ^ 1, 1, n),
+            (true, true, _) => DiskChsn::new(self.c + 1, self.h ^ 1, 1, n),
+            (true, false, _) => DiskChsn::new(self.c + 1, self.h, 1, n),
+        }
+    }
+}
+
+/// Read the sector at the current position of `walk`, updating the status of `result` if the
+/// sector cannot be read. Returns `None` if the command must terminate.
+fn read_walk_sector(
+    disk: &DiskImage,
+    walk: &SectorWalk,
+    result: &mut FdcResult,
+) -> Result<Option<ReadSectorResult>, DiskImageError> {
+    let rsr = match disk.read_sector_dtl(
+        walk.phys_ch(),
+        walk.query(),
+        walk.cmd.id.n(),
+        walk.cmd.dtl,
+        RwScope::DataOnly,
+        false,
+    ) {
+        Ok(rsr) => rsr,
+        // No track under the head. The controller finds no address marks.
+        Err(DiskImageError::SeekError) => {
+            result.fail(St1::MISSING_ADDRESS_MARK, St2::empty());
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+
+    if rsr.not_found {
+        let mut st2 = St2::empty();
+        st2.set(St2::WRONG_CYLINDER, rsr.wrong_cylinder);
+        st2.set(St2::BAD_CYLINDER, rsr.bad_cylinder);
+        result.fail(no_id_status(disk, walk.phys_ch()), st2);
+        return Ok(None);
+    }
+    if rsr.address_crc_error {
+        result.fail(St1::DATA_ERROR, St2::empty());
+        return Ok(None);
+    }
+    if rsr.no_dam {
+        result.fail(St1::MISSING_ADDRESS_MARK, St2::MISSING_DATA_ADDRESS_MARK);
+        return Ok(None);
+    }
+    Ok(Some(rsr))
+}
+
+/// Return the ST1 status for a sector that was not found: Missing Address Mark if the track has
+/// no sector IDs at all, otherwise No Data.
+fn no_id_status(disk: &DiskImage, phys_ch: DiskCh) -> St1 {
+    match disk.track(phys_ch) {
+        Some(track) if !track.sector_list().is_empty() => St1::NO_DATA,
+        _ => St1::MISSING_ADDRESS_MARK,
+    }
+}
+
+fn tc_reached(cmd: &FdcCommand, transferred: usize) -> bool {
+    cmd.terminal_count.is_some_and(|tc| transferred >= tc)
+}
+
+fn end_of_cylinder(result: &mut FdcResult, walk: &SectorWalk) {
+    result.id = walk.next_id();
+    result.fail(St1::END_OF_CYLINDER, St2::empty());
+}
+
+fn read_sectors(disk: &DiskImage, cmd: &FdcCommand, read_deleted: bool) -> Result<FdcResult, DiskImageError> {
+    let mut result = FdcResult::new(cmd.drive, cmd.phys_ch, cmd.id);
+    let mut walk = SectorWalk::new(cmd);
+
+    loop {
+        result.id = walk.id();
+        result.set_head(walk.phys_h);
+        let Some(rsr) = read_walk_sector(disk, &walk, &mut result)?
+        else {
+            break;
+        };
+
+        // A control mark is a deleted data mark for read data, or a normal data mark for
+        // read deleted data.
+        let control_mark = rsr.deleted_mark != read_deleted;
+        if control_mark {
+            result.st2 |= St2::CONTROL_MARK;
+        }
+
+        if !(control_mark && cmd.skip) {
+            let data = &rsr.read_buf[rsr.data_range.clone()];
+            let len = cmd
+                .terminal_count
+                .map_or(data.len(), |tc| data.len().min(tc.saturating_sub(result.data.len())));
+            result.data.extend_from_slice(&data[..len]);
+
+            if rsr.data_crc_error {
+                result.fail(St1::DATA_ERROR, St2::DATA_ERROR_IN_DATA_FIELD);
+                break;
+            }
+            // Without SK, the controller stops after reading a sector with a control mark.
+            if control_mark || tc_reached(cmd, result.data.len()) {
+                result.id = walk.next_id();
+                break;
+            }
+        }
+
+        if !walk.advance(1) {
+            end_of_cylinder(&mut result, &walk);
+            break;
+        }
+    }
+    Ok(result)
+}
+
+/// Execute a READ DATA command, reading sectors from `cmd.id` through EOT.
+///
+/// Sectors with a deleted data address mark set Control Mark in ST2. They are skipped if SK is
+/// set; otherwise the sector is read and the command terminates.
+pub fn read_data(disk: &DiskImage, cmd: &FdcCommand) -> Result<FdcResult, DiskImageError> {
+    read_sectors(disk, cmd, false)
+}
+
+/// Execute a READ DELETED DATA command. This is the same as [read_data], except that sectors
+/// with a normal data address mark set Control Mark.
+pub fn read_deleted_data(disk: &DiskImage, cmd: &FdcCommand) -> Result<FdcResult, DiskImageError> {
+    read_sectors(disk, cmd, true)
+}
+
+/// Execute a READ ID command, reporting a sector ID from the track at `phys_ch`.
+///
+/// `index` selects which ID field on the track is encountered first, counted from the index
+/// and wrapping around the track. ID fields with a CRC error are passed over. If no valid ID
+/// field is found, Missing Address Mark is set, along with Data Error if any ID field had a
+/// CRC error.
+pub fn read_id(disk: &DiskImage, drive: u8, phys_ch: DiskCh, index: usize) -> FdcResult {
+    let ids = id_fields(disk, phys_ch);
+    let mut result = FdcResult::new(drive, phys_ch, DiskChsn::new(phys_ch.c(), phys_ch.h(), 0, 0));
+
+    let valid = (0..ids.len())
+        .map(|i| &ids[(index + i) % ids.len()])
+        .find(|field| !field.crc_error);
+    match valid {
+        Some(field) => result.id = field.id,
+        None if ids.is_empty() => result.fail(St1::MISSING_ADDRESS_MARK, St2::empty()),
+        None => result.fail(St1::MISSING_ADDRESS_MARK | St1::DATA_ERROR, St2::empty()),
+    }
+    result
+}
+
+/// Execute a READ TRACK command, reading EOT sectors in physical order from the index,
+/// regardless of their IDs. CRC errors are reported in the status but do not stop the read.
+pub fn read_track(disk: &DiskImage, cmd: &FdcCommand) -> Result<FdcResult, DiskImageError> {
+    let mut result = FdcResult::new(cmd.drive, cmd.phys_ch, cmd.id);
+    let id_ch = DiskCh::new(cmd.id.c(), cmd.id.h());
+
+    let rtr = match disk.read_all_sectors(cmd.phys_ch, id_ch, cmd.id.n(), cmd.eot) {
+        Ok(rtr) => rtr,
+        Err(DiskImageError::SeekError) => {
+            result.fail(St1::MISSING_ADDRESS_MARK, St2::empty());
+            return Ok(result);
+        }
+        Err(e) => return Err(e),
+    };
+    if rtr.not_found {
+        result.fail(St1::MISSING_ADDRESS_MARK, St2::empty());
+        return Ok(result);
+    }
+
+    result.data = rtr.read_buf;
+    if let Some(tc) = cmd.terminal_count {
+        result.data.truncate(tc);
+    }
+    if rtr.address_crc_error || rtr.data_crc_error {
+        result.st1 |= St1::DATA_ERROR;
+    }
+    if rtr.data_crc_error {
+        result.st2 |= St2::DATA_ERROR_IN_DATA_FIELD;
+    }
+
+    let mut walk = SectorWalk::new(cmd);
+    walk.r = rtr.sectors_read.clamp(1, u8::MAX as u16) as u8;
+    if tc_reached(cmd, result.data.len()) {
+        result.id = walk.next_id();
+        if !result.st1.is_empty() {
+            result.st0 |= St0::ABNORMAL_TERMINATION;
+        }
+    }
+    else {
+        end_of_cylinder(&mut result, &walk);
+    }
+    Ok(result)
+}
+
+/// Execute a WRITE DATA command, writing `data` to sectors from `cmd.id` through EOT.
+///
+/// Each sector receives 128 * 2^N bytes of `data`, or DTL bytes padded with zeros to 128 bytes
+/// if N is 0. The end of `data` signals terminal count; a final partial sector is padded with
+/// zeros. The sector sizes on the disk must match N. A command with N and DTL both 0 would
+/// transfer no data per sector, and is rejected with an invalid command status.
+pub fn write_data(disk: &mut DiskImage, cmd: &FdcCommand, data: &[u8]) -> Result<FdcResult, DiskImageError> {
+    let mut result = FdcResult::new(cmd.drive, cmd.phys_ch, cmd.id);
+    if disk.image_format().write_protect.unwrap_or(false) {
+        result.fail(St1::NOT_WRITABLE, St2::empty());
+        return Ok(result);
+    }
+
+    let sector_size = DiskChsn::n_to_bytes(cmd.id.n());
+    let chunk_size = match cmd.id.n() {
+        0 => sector_size.min(cmd.dtl as usize),
+        _ => sector_size,
+    };
+    if chunk_size == 0 {
+        result.st0 |= St0::INVALID_COMMAND;
+        return Ok(result);
+    }
+
+    let mut walk = SectorWalk::new(cmd);
+    let mut chunks = data.chunks(chunk_size).peekable();
+    while let Some(chunk) = chunks.next() {
+        result.id = walk.id();
+        result.set_head(walk.phys_h);
+
+        let mut sector_data = chunk.to_vec();
+        sector_data.resize(sector_size, 0);
+        let wsr = match disk.write_sector(
+            walk.phys_ch(),
+            walk.query(),
+            None,
+            &sector_data,
+            RwScope::DataOnly,
+            false,
+            false,
+        ) {
+            Ok(wsr) => wsr,
+            Err(DiskImageError::SeekError) => {
+                result.fail(St1::MISSING_ADDRESS_MARK, St2::empty());
+                return Ok(result);
+            }
+            Err(e) => return Err(e),
+        };
+
+        if wsr.not_found {
+            let mut st2 = St2::empty();
+            st2.set(St2::WRONG_CYLINDER, wsr.wrong_cylinder);
+            st2.set(St2::BAD_CYLINDER, wsr.bad_cylinder);
+            result.fail(no_id_status(disk, walk.phys_ch()), st2);
+            return Ok(result);
+        }
+        if wsr.address_crc_error {
+            result.fail(St1::DATA_ERROR, St2::empty());
+            return Ok(result);
+        }
+        if wsr.no_dam {
+            result.fail(St1::MISSING_ADDRESS_MARK, St2::MISSING_DATA_ADDRESS_MARK);
+            return Ok(result);
+        }
+
+        if chunks.peek().is_none() {
+            result.id = walk.next_id();
+            break;
+        }
+        if !walk.advance(1) {
+            end_of_cylinder(&mut result, &walk);
+            break;
+        }
+    }
+    Ok(result)
+}
+
+/// Execute a FORMAT TRACK command, formatting the track at `phys_ch` with the sector IDs in
+/// `ids` as supplied by the host, a GAP3 length of `gap3` bytes, and sector data filled with
+/// `filler`. The size of each sector is given by the N of its ID.
+pub fn format_track(
+    disk: &mut DiskImage,
+    drive: u8,
+    phys_ch: DiskCh,
+    ids: &[DiskChsn],
+    gap3: u8,
+    filler: u8,
+) -> Result<FdcResult, DiskImageError> {
+    let last_id = ids
+        .last()
+        .copied()
+        .unwrap_or(DiskChsn::new(phys_ch.c(), phys_ch.h(), 0, 0));
+    let mut result = FdcResult::new(drive, phys_ch, last_id);
+    if disk.image_format().write_protect.unwrap_or(false) {
+        result.fail(St1::NOT_WRITABLE, St2::empty());
+        return Ok(result);
+    }
+
+    match disk.format_track(phys_ch, ids.to_vec(), &[filler], gap3 as usize) {
+        Ok(()) => {}
+        Err(DiskImageError::SeekError) => {
+            result.st0 |= St0::ABNORMAL_TERMINATION | St0::EQUIPMENT_CHECK;
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(result)
+}
+
+/// Execute a SCAN EQUAL command, comparing the sectors from `cmd.id` through EOT, stepping by
+/// `step` (STP), against `host_data`.
+///
+/// Each sector is compared to the next sector-sized portion of `host_data`, a byte of 0xFF on
+/// either side matching any value. The command terminates with Scan Equal Hit on the first
+/// matching sector, or with Scan Not Satisfied once EOT is passed or `host_data` is exhausted.
+pub fn scan_equal(disk: &DiskImage, cmd: &FdcCommand, host_data: &[u8], step: u8) -> Result<FdcResult, DiskImageError> {
+    let mut result = FdcResult::new(cmd.drive, cmd.phys_ch, cmd.id);
+    let mut walk = SectorWalk::new(cmd);
+    let mut host_offset = 0;
+    let step = step.max(1);
+
+    loop {
+        result.id = walk.id();
+        result.set_head(walk.phys_h);
+        let Some(rsr) = read_walk_sector(disk, &walk, &mut result)?
+        else {
+            break;
+        };
+
+        let control_mark = rsr.deleted_mark;
+        if control_mark {
+            result.st2 |= St2::CONTROL_MARK;
+        }
+
+        if !(control_mark && cmd.skip) {
+            let disk_data = &rsr.read_buf[rsr.data_range.clone()];
+            let host = &host_data[host_offset.min(host_data.len())..];
+            let len = disk_data.len().min(host.len());
+            host_offset += len;
+
+            if rsr.data_crc_error {
+                result.fail(St1::DATA_ERROR, St2::DATA_ERROR_IN_DATA_FIELD);
+                break;
+            }
+
+            let equal = len > 0
+                && disk_data[..len]
+                    .iter()
+                    .zip(&host[..len])
+                    .all(|(&d, &h)| d == h || d == 0xFF || h == 0xFF);
+            if equal {
+                result.st2 |= St2::SCAN_EQUAL_HIT;
+                result.id = walk.id();
+                break;
+            }
+            if control_mark || host_offset >= host_data.len() {
+                result.st2 |= St2::SCAN_NOT_SATISFIED;
+                result.id = walk.next_id();
+                break;
+            }
+        }
+
+        if !walk.advance(step) {
+            result.st2 |= St2::SCAN_NOT_SATISFIED;
+            result.id = walk.next_id();
+            break;
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fdc::tests::test_disk;
+
+    fn command(ch: DiskCh, r: u8, eot: u8) -> FdcCommand {
+        FdcCommand::new(1, ch, DiskChsn::new(ch.c(), ch.h(), r, 2), eot)
+    }
+
+    #[test]
+    fn test_read_data() {
+        let disk = test_disk();
+
+        // Terminal count after two sectors ends the command normally at the next sector.
+        let cmd = command(DiskCh::new(1, 0), 1, 9).with_terminal_count(1024);
+        let result = read_data(&disk, &cmd).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(result.data.len(), 1024);
+        assert!(result.data[..512].iter().all(|&b| b == 1) && result.data[512..].iter().all(|&b| b == 2));
+        assert_eq!(result.result_bytes(), [0x01, 0, 0, 1, 0, 3, 2]);
+
+        // Without terminal count, the controller runs off the end of the cylinder.
+        let result = read_data(&disk, &command(DiskCh::new(1, 0), 8, 9)).unwrap();
+        assert_eq!(result.data.len(), 1024);
+        assert_eq!(result.result_bytes(), [0x41, 0x80, 0, 2, 0, 1, 2]);
+
+        // Multi-track continues on head 1, reporting head 1 in ST0.
+        let cmd = command(DiskCh::new(1, 0), 9, 9)
+            .with_multi_track(true)
+            .with_terminal_count(1024);
+        let result = read_data(&disk, &cmd).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(result.result_bytes(), [0x05, 0, 0, 1, 1, 2, 2]);
+
+        // A sector with the wrong cylinder in its ID is not found.
+        let cmd = FdcCommand::new(0, DiskCh::new(2, 0), DiskChsn::new(3, 0, 1, 2), 9);
+        let result = read_data(&disk, &cmd).unwrap();
+        assert_eq!(result.result_bytes(), [0x40, 0x04, 0x10, 3, 0, 1, 2]);
+        assert!(result.data.is_empty());
+
+        // Seeking past the last track finds no address marks.
+        let result = read_data(&disk, &command(DiskCh::new(60, 0), 1, 9)).unwrap();
+        assert_eq!(result.st1, St1::MISSING_ADDRESS_MARK);
+    }
+
+    #[test]
+    fn test_read_errors_and_control_marks() {
+        let disk = test_disk();
+        let ch = DiskCh::new(0, 0);
+
+        // A deleted sector is read, then the command stops with Control Mark.
+        let result = read_data(&disk, &command(ch, 4, 9)).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(result.data.len(), 1024);
+        assert_eq!(result.result_bytes(), [0x01, 0, 0x40, 0, 0, 6, 2]);
+
+        // With SK set, the deleted sector is skipped and the read continues to the CRC error.
+        let result = read_data(&disk, &command(ch, 4, 9).with_skip(true)).unwrap();
+        assert_eq!(result.data.len(), 3 * 512);
+        assert!(result.data[512..].iter().all(|&b| b == 6 || b == 7));
+        assert_eq!(result.result_bytes(), [0x41, 0x20, 0x60, 0, 0, 7, 2]);
+
+        // Read deleted data reads the deleted sector normally and stops on the next one.
+        let result = read_deleted_data(&disk, &command(ch, 5, 9)).unwrap();
+        assert!(result.data[..512].iter().all(|&b| b == 5));
+        assert_eq!(result.result_bytes(), [0x01, 0, 0x40, 0, 0, 7, 2]);
+    }
+
+    #[test]
+    fn test_read_id_and_track() {
+        let disk = test_disk();
+        let ch = DiskCh::new(2, 1);
+
+        assert_eq!(read_id(&disk, 0, ch, 0).id, DiskChsn::new(2, 1, 1, 2));
+        assert_eq!(read_id(&disk, 0, ch, 10).id, DiskChsn::new(2, 1, 2, 2));
+        // The ID field of sector 3 has a CRC error and is passed over.
+        assert_eq!(read_id(&disk, 0, DiskCh::new(0, 1), 2).id, DiskChsn::new(0, 1, 4, 2));
+        let result = read_id(&disk, 2, DiskCh::new(60, 1), 0);
+        assert_eq!(result.result_bytes()[..2], [0x46, 0x01]);
+
+        let cmd = command(ch, 1, 9).with_terminal_count(9 * 512);
+        let result = read_track(&disk, &cmd).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(result.data.len(), 9 * 512);
+        assert_eq!(result.id, DiskChsn::new(3, 1, 1, 2));
+
+        // Read track continues past CRC errors, reporting them in the status.
+        let result = read_track(&disk, &command(DiskCh::new(0, 0), 1, 9)).unwrap();
+        assert_eq!(result.data.len(), 9 * 512);
+        assert_eq!(result.st1, St1::END_OF_CYLINDER | St1::DATA_ERROR);
+        assert_eq!(result.st2, St2::DATA_ERROR_IN_DATA_FIELD);
+    }
+
+    #[test]
+    fn test_write_and_format() {
+        let mut disk = test_disk();
+        let ch = DiskCh::new(3, 0);
+
+        let result = write_data(&mut disk, &command(ch, 2, 9), &[0xAA; 700]).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(result.id, DiskChsn::new(3, 0, 4, 2));
+        let read = read_data(&disk, &command(ch, 2, 9).with_terminal_count(1024)).unwrap();
+        assert!(read.data[..700].iter().all(|&b| b == 0xAA));
+        assert!(read.data[700..].iter().all(|&b| b == 0));
+
+        let result = write_data(&mut disk, &command(ch, 12, 12), &[0; 512]).unwrap();
+        assert_eq!(result.st1, St1::NO_DATA);
+
+        let cmd = FdcCommand::new(0, ch, DiskChsn::new(3, 0, 1, 0), 9).with_dtl(0);
+        let result = write_data(&mut disk, &cmd, &[0; 512]).unwrap();
+        assert!(result.st0.contains(St0::INVALID_COMMAND));
+        assert!(!result.is_ok());
+
+        let ids: Vec<DiskChsn> = (1..=4).map(|s| DiskChsn::new(3, 0, s, 3)).collect();
+        let result = format_track(&mut disk, 0, ch, &ids, 0x54, 0xE5).unwrap();
+        assert!(result.is_ok());
+        let cmd = FdcCommand::new(0, ch, DiskChsn::new(3, 0, 4, 3), 4).with_terminal_count(1024);
+        let read = read_data(&disk, &cmd).unwrap();
+        assert!(read.is_ok());
+        assert!(read.data.iter().all(|&b| b == 0xE5));
+
+        let mut descriptor = disk.image_format().clone();
+        descriptor.write_protect = Some(true);
+        disk.set_image_format(descriptor);
+        let result = write_data(&mut disk, &command(ch, 1, 9), &[0; 512]).unwrap();
+        assert_eq!(result.result_bytes()[..2], [0x41, 0x02]);
+    }
+
+    #[test]
+    fn test_scan_equal() {
+        let disk = test_disk();
+        let ch = DiskCh::new(1, 1);
+
+        // Sector 4 matches; 0xFF bytes in the host data match anything.
+        let mut host = vec![4; 9 * 512];
+        host[3 * 512] = 0xFF;
+        let result = scan_equal(&disk, &command(ch, 1, 9), &host, 1).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(result.st2, St2::SCAN_EQUAL_HIT);
+        assert_eq!(result.id.s(), 4);
+
+        // The scan ends when the host data is exhausted.
+        let result = scan_equal(&disk, &command(ch, 1, 9), &host[..1024], 1).unwrap();
+        assert_eq!(result.st2, St2::SCAN_NOT_SATISFIED);
+        assert_eq!(result.id.s(), 3);
+
+        // Stepping by two skips sector 4.
+        let result = scan_equal(&disk, &command(ch, 1, 9), &host, 2).unwrap();
+        assert_eq!(result.st2, St2::SCAN_NOT_SATISFIED);
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,534 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/fdc/wd177x.rs
+
+    A facade mapping Western Digital WD1771/WD1772/WD179x floppy disk
+    controller commands onto DiskImage operations.
+*/
+
+//! A facade for emulating a Western Digital WD1771, WD1772 or WD179x floppy disk controller on
+//! top of a [DiskImage].
+//!
+//! An emulator decodes the command byte written by the guest with [WdCommand::decode], then calls
+//! the function for the type II or type III command with the current contents of the track and
+//! sector registers. Each function returns a [WdResult] containing the status register, the new
+//! value of the sector register and any data read from the disk. Type I commands (restore, seek
+//! and step) and Force Interrupt only move the head or affect the controller itself, and are left
+//! to the emulator.
+//!
+//! Unlike the µPD765, the WD controllers take the size of a sector from the N of its ID field, so
+//! no sector size is supplied by the host. The meaning of several command flags and status bits
+//! differs between the members of the family; these differences are selected by [WdVariant].
+
+use crate::{
+    fdc::{id_fields, read_address as next_id_field, DataMark},
+    types::{DiskCh, DiskChsnQuery, RwScope},
+    DiskImage,
+    DiskImageError,
+};
+use bitflags::bitflags;
+
+/// The member of the WD177x/179x controller family being emulated.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum WdVariant {
+    /// The FM-only WD1771, as used in the TRS-80 Model I.
+    Wd1771,
+    /// The WD1772, as used in the Atari ST. It has no side compare and a motor on status bit.
+    Wd1772,
+    /// The WD1791/1793/1795/1797 family, and compatibles such as the FD179x and VG93.
+    #[default]
+    Wd179x,
+}
+
+bitflags! {
+    /// Bits of the WD177x/179x status register, as reported after a type II or type III command.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    #[rustfmt::skip]
+    pub struct WdStatus: u8 {
+        #[doc = "Not ready. Motor on on the WD1772"]
+        const NOT_READY        = 0b1000_0000;
+        #[doc = "Write protect: a write command was attempted on a write protected disk"]
+        const WRITE_PROTECT    = 0b0100_0000;
+        #[doc = "Record type: a deleted data mark was read (WD1772, WD179x). Write fault on writes"]
+        const RECORD_TYPE      = 0b0010_0000;
+        #[doc = "Record type bits 6-5 of the WD1771: both are set for a deleted data mark"]
+        const RECORD_TYPE_1771 = 0b0110_0000;
+        #[doc = "Record not found: the requested sector, or any ID field, could not be found"]
+        const RECORD_NOT_FOUND = 0b0001_0000;
+        #[doc = "CRC error: in the ID field if Record Not Found is also set, otherwise in the data field"]
+        const CRC_ERROR        = 0b0000_1000;
+        #[doc = "Lost data: the host did not service a data request in time"]
+        const LOST_DATA        = 0b0000_0100;
+        #[doc = "Data request"]
+        const DATA_REQUEST     = 0b0000_0010;
+        #[doc = "Busy"]
+        const BUSY             = 0b0000_0001;
+    }
+}
+
+impl WdStatus {
+    /// Return the record type status bits `variant` reports after reading a sector with the data
+    /// mark `mark`.
+    pub fn record_type(variant: WdVariant, mark: DataMark) -> WdStatus {
+        match (mark, variant) {
+            (DataMark::Normal, _) => WdStatus::empty(),
+            (DataMark::Deleted, WdVariant::Wd1771) => WdStatus::RECORD_TYPE_1771,
+            (DataMark::Deleted, _) => WdStatus::RECORD_TYPE,
+        }
+    }
+}
+
+/// The flags of a type II (read sector or write sector) command.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WdSectorFlags {
+    /// The multiple record flag (m). Continue with the next sector number after each sector.
+    pub multiple: bool,
+    /// The side to compare with the H of the sector ID, if side compare is enabled (C and S
+    /// flags). Only the WD179x supports side compare.
+    pub side_compare: Option<u8>,
+    /// The head settle delay flag (E).
+    pub delay: bool,
+    /// The data mark written by a write sector command (a0, or a1 and a0 on the WD1771).
+    pub data_mark: DataMark,
+}
+
+/// A WD177x/179x command, decoded from the byte written to the command register.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WdCommand {
+    /// A type I command: restore, seek, step, step in or step out.
+    TypeI(u8),
+    /// A type II read sector command.
+    ReadSector(WdSectorFlags),
+    /// A type II write sector command.
+    WriteSector(WdSectorFlags),
+    /// A type III read address command.
+    ReadAddress,
+    /// A type III read track command.
+    ReadTrack,
+    /// A type III write track command.
+    WriteTrack,
+    /// A type IV force interrupt command, with its interrupt condition bits.
+    ForceInterrupt(u8),
+}
+
+impl WdCommand {
+    /// Decode the command byte `command` as interpreted by `variant`.
+    pub fn decode(variant: WdVariant, command: u8) -> WdCommand {
+        let sector_flags = || WdSectorFlags {
+            multiple: command & 0x10 != 0,
+            side_compare: match variant {
+                WdVariant::Wd179x if command & 0x02 != 0 => Some((command >> 3) & 0x01),
+                _ => None,
+            },
+            delay: command & 0x04 != 0,
+            data_mark: match variant {
+                // The WD1771 selects one of four data marks with a1 a0. We only distinguish F8.
+                WdVariant::Wd1771 => DataMark::from_deleted(command & 0x03 == 0x03),
+                _ => DataMark::from_deleted(command & 0x01 != 0),
+            },
+        };
+
+        match command >> 4 {
+            0x0..=0x7 => WdCommand::TypeI(command),
+            0x8 | 0x9 => WdCommand::ReadSector(sector_flags()),
+            0xA | 0xB => WdCommand::WriteSector(sector_flags()),
+            0xC => WdCommand::ReadAddress,
+            0xD => WdCommand::ForceInterrupt(command & 0x0F),
+            0xE => WdCommand::ReadTrack,
+            _ => WdCommand::WriteTrack,
+        }
+    }
+
+    /// Return the type (1 to 4) of the command, which determines the meaning of the status
+    /// register bits.
+    pub fn command_type(&self) -> u8 {
+        match self {
+            WdCommand::TypeI(_) => 1,
+            WdCommand::ReadSector(_) | WdCommand::WriteSector(_) => 2,
+            WdCommand::ReadAddress | WdCommand::ReadTrack | WdCommand::WriteTrack => 3,
+            WdCommand::ForceInterrupt(_) => 4,
+        }
+    }
+}
+
+/// The outcome of a WD177x/179x type II or type III command.
+#[derive(Clone, Debug, Default)]
+pub struct WdResult {
+    /// The status register after the command.
+    pub status: WdStatus,
+    /// The new value of the sector register, if the command changed it.
+    pub sector: Option<u8>,
+    /// The data transferred to the host by a read command.
+    pub data:   Vec<u8>,
+}
+
+impl WdResult {
+    /// Return true if the command completed without any error bits set.
+    pub fn is_ok(&self) -> bool {
+        !self.status.intersects(
+            WdStatus::WRITE_PROTECT | WdStatus::RECORD_NOT_FOUND | WdStatus::CRC_ERROR | WdStatus::LOST_DATA,
+        )
+    }
+}
+
+fn sector_query(track: u8, sector: u8, flags: &WdSectorFlags) -> DiskChsnQuery {
+    DiskChsnQuery::new(track as u16, flags.side_compare, sector, None)
+}
+
+fn is_write_protected(disk: &DiskImage) -> bool {
+    disk.image_format().write_protect.unwrap_or(false)
+}
+
+/// Execute a READ SECTOR command for the sector with the ID given by the `track` and `sector`
+/// registers, on the track at `phys_ch`.
+///
+/// With the multiple record flag set, the sector register is incremented after each sector and
+/// reading continues until a sector is not found. The host normally ends such a read with a Force
+/// Interrupt; here the command runs to the end of the records and reports Record Not Found, as
+/// the hardware does if left to run. The record type bits reflect the data mark of the last
+/// sector read.
+pub fn read_sector(
+    disk: &DiskImage,
+    variant: WdVariant,
+    phys_ch: DiskCh,
+    track: u8,
+    sector: u8,
+    flags: &WdSectorFlags,
+) -> Result<WdResult, DiskImageError> {
+    let mut result = WdResult::default();
+    let mut sector = sector;
+
+    loop {
+        let rsr = match disk.read_sector(
+            phys_ch,
+            sector_query(track, sector, flags),
+            None,
+            None,
+            RwScope::DataOnly,
+            false,
+        ) {
+            Ok(rsr) => rsr,
+            Err(DiskImageError::SeekError) => {
+                result.status |= WdStatus::RECORD_NOT_FOUND;
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+
+        if rsr.not_found || rsr.no_dam {
+            result.status |= WdStatus::RECORD_NOT_FOUND;
+            break;
+        }
+        if rsr.address_crc_error {
+            result.status |= WdStatus::RECORD_NOT_FOUND | WdStatus::CRC_ERROR;
+            break;
+        }
+
+        result.status.remove(WdStatus::RECORD_TYPE_1771);
+        result.status |= WdStatus::record_type(variant, DataMark::from_deleted(rsr.deleted_mark));
+        result.data.extend_from_slice(&rsr.read_buf[rsr.data_range.clone()]);
+
+        if rsr.data_crc_error {
+            result.status |= WdStatus::CRC_ERROR;
+            break;
+        }
+        if !flags.multiple {
+            break;
+        }
+        sector = sector.wrapping_add(1);
+        result.sector = Some(sector);
+    }
+    Ok(result)
+}
+
+/// Execute a WRITE SECTOR command for the sector with the ID given by the `track` and `sector`
+/// registers, on the track at `phys_ch`, writing the data mark given by `flags`.
+///
+/// Each sector receives as many bytes of `data` as the N of its ID field specifies. If `data` runs
+/// out before a sector is complete, the rest of the sector is filled with zeros and Lost Data is
+/// set. With the multiple record flag set, the sector register is incremented after each sector
+/// and writing continues until `data` is exhausted or a sector is not found.
+pub fn write_sector(
+    disk: &mut DiskImage,
+    phys_ch: DiskCh,
+    track: u8,
+    sector: u8,
+    flags: &WdSectorFlags,
+    data: &[u8],
+) -> Result<WdResult, DiskImageError> {
+    let mut result = WdResult::default();
+    if is_write_protected(disk) {
+        result.status |= WdStatus::WRITE_PROTECT;
+        return Ok(result);
+    }
+
+    let mut sector = sector;
+    let mut offset = 0;
+    loop {
+        let query = sector_query(track, sector, flags);
+        let Some(field) = id_fields(disk, phys_ch)
+            .into_iter()
+            .find(|field| query.matches(&field.id))
+        else {
+            result.status |= WdStatus::RECORD_NOT_FOUND;
+            break;
+        };
+
+        let sector_size = field.id.n_size();
+        let chunk = &data[offset.min(data.len())..(offset + sector_size).min(data.len())];
+        offset += chunk.len();
+        let mut sector_data = chunk.to_vec();
+        if sector_data.len() < sector_size {
+            sector_data.resize(sector_size, 0);
+            result.status |= WdStatus::LOST_DATA;
+        }
+
+        let wsr = disk.write_sector(
+            phys_ch,
+            query,
+            None,
+            &sector_data,
+            RwScope::DataOnly,
+            flags.data_mark.is_deleted(),
+            false,
+        )?;
+        if wsr.not_found || wsr.no_dam {
+            result.status |= WdStatus::RECORD_NOT_FOUND;
+            break;
+        }
+        if wsr.address_crc_error {
+            result.status |= WdStatus::RECORD_NOT_FOUND | WdStatus::CRC_ERROR;
+            break;
+        }
+
+        if !flags.multiple || offset >= data.len() {
+            break;
+        }
+        sector = sector.wrapping_add(1);
+        result.sector = Some(sector);
+    }
+    Ok(result)
+}
+
+/// Execute a READ ADDRESS command on the track at `phys_ch`, returning the six bytes of the next
+/// ID field encountered: C, H, R, N and the two bytes of the recorded CRC.
+///
+/// `index` selects which ID field is encountered, as described for
+/// [read_address](super::read_address). As on the hardware, an ID field with a bad CRC is
+/// returned with CRC Error set, and the C of the ID field is copied into the sector register.
+pub fn read_address(disk: &DiskImage, phys_ch: DiskCh, index: usize) -> WdResult {
+    let mut result = WdResult::default();
+    match next_id_field(disk, phys_ch, index) {
+        Some(field) => {
+            let id = field.id;
+            result.data = vec![
+                id.c() as u8,
+                id.h(),
+                id.s(),
+                id.n(),
+                (field.crc >> 8) as u8,
+                field.crc as u8,
+            ];
+            result.sector = Some(id.c() as u8);
+            result.status.set(WdStatus::CRC_ERROR, field.crc_error);
+        }
+        None => result.status |= WdStatus::RECORD_NOT_FOUND,
+    }
+    result
+}
+
+/// Execute a READ TRACK command on the track at `phys_ch`, returning the decoded bytes of the
+/// entire track from the index, including gaps, address marks and CRCs.
+///
+/// If there is no track at `phys_ch`, no data is returned. Returns an error if the track has no
+/// bitstream to read, as with a MetaSector track.
+pub fn read_track(disk: &DiskImage, phys_ch: DiskCh) -> Result<WdResult, DiskImageError> {
+    let mut result = WdResult::default();
+    match disk.read_track(phys_ch, None) {
+        Ok(rtr) => result.data = rtr.read_buf,
+        Err(DiskImageError::SeekError) => {}
+        Err(e) => return Err(e),
+    }
+    Ok(result)
+}
+
+/// Execute a WRITE TRACK command on the track at `phys_ch` with `data` as supplied by the host.
+/// See [DiskImage::write_track] for the interpretation of the control bytes in `data`.
+pub fn write_track(disk: &mut DiskImage, phys_ch: DiskCh, data: &[u8]) -> Result<WdResult, DiskImageError> {
+    let mut result = WdResult::default();
+    if is_write_protected(disk) {
+        result.status |= WdStatus::WRITE_PROTECT;
+        return Ok(result);
+    }
+    disk.write_track(phys_ch, data)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fdc::tests::test_disk;
+
+    #[test]
+    fn test_decode() {
+        let cmd = WdCommand::decode(WdVariant::Wd179x, 0x9E);
+        assert_eq!(
+            cmd,
+            WdCommand::ReadSector(WdSectorFlags {
+                multiple: true,
+                side_compare: Some(1),
+                delay: true,
+                data_mark: DataMark::Normal,
+            })
+        );
+        assert_eq!(cmd.command_type(), 2);
+
+        // The WD1772 uses the S and C bits for motor on and precompensation.
+        let WdCommand::ReadSector(flags) = WdCommand::decode(WdVariant::Wd1772, 0x8A)
+        else {
+            panic!("expected read sector");
+        };
+        assert_eq!(flags.side_compare, None);
+
+        // a0 writes a deleted data mark, except on the WD1771 which requires a1 a0 = 11.
+        let deleted = |variant, command| match WdCommand::decode(variant, command) {
+            WdCommand::WriteSector(flags) => flags.data_mark.is_deleted(),
+            _ => panic!("expected write sector"),
+        };
+        assert!(deleted(WdVariant::Wd179x, 0xA1));
+        assert!(!deleted(WdVariant::Wd1771, 0xA1));
+        assert!(deleted(WdVariant::Wd1771, 0xA3));
+
+        assert_eq!(WdCommand::decode(WdVariant::Wd179x, 0xC0), WdCommand::ReadAddress);
+        assert_eq!(
+            WdCommand::decode(WdVariant::Wd179x, 0xD8),
+            WdCommand::ForceInterrupt(0x08)
+        );
+        assert_eq!(WdCommand::decode(WdVariant::Wd179x, 0x18).command_type(), 1);
+    }
+
+    #[test]
+    fn test_read_sector() {
+        let disk = test_disk();
+        let single = WdSectorFlags::default();
+
+        let result = read_sector(&disk, WdVariant::Wd179x, DiskCh::new(1, 1), 1, 4, &single).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(result.sector, None);
+        assert!(result.data.len() == 512 && result.data.iter().all(|&b| b == 4));
+
+        // Deleted data marks are reported in the record type bits.
+        let ch = DiskCh::new(0, 0);
+        let result = read_sector(&disk, WdVariant::Wd179x, ch, 0, 5, &single).unwrap();
+        assert_eq!(result.status, WdStatus::RECORD_TYPE);
+        let result = read_sector(&disk, WdVariant::Wd1771, ch, 0, 5, &single).unwrap();
+        assert_eq!(result.status, WdStatus::RECORD_TYPE_1771);
+
+        // A multiple sector read continues until the CRC error in sector 7.
+        let multiple = WdSectorFlags {
+            multiple: true,
+            ..Default::default()
+        };
+        let result = read_sector(&disk, WdVariant::Wd179x, ch, 0, 5, &multiple).unwrap();
+        assert_eq!(result.data.len(), 3 * 512);
+        assert_eq!(result.sector, Some(7));
+        assert_eq!(result.status, WdStatus::CRC_ERROR);
+
+        // Or until the end of the track.
+        let result = read_sector(&disk, WdVariant::Wd179x, DiskCh::new(2, 0), 2, 8, &multiple).unwrap();
+        assert_eq!(result.data.len(), 2 * 512);
+        assert_eq!(result.sector, Some(10));
+        assert_eq!(result.status, WdStatus::RECORD_NOT_FOUND);
+
+        // Side compare checks the head of the sector ID against the S flag.
+        let compare = |side| WdSectorFlags {
+            side_compare: Some(side),
+            ..Default::default()
+        };
+        let result = read_sector(&disk, WdVariant::Wd179x, DiskCh::new(2, 1), 2, 1, &compare(0)).unwrap();
+        assert_eq!(result.status, WdStatus::RECORD_NOT_FOUND);
+        let result = read_sector(&disk, WdVariant::Wd179x, DiskCh::new(2, 1), 2, 1, &compare(1)).unwrap();
+        assert!(result.is_ok());
+
+        // The track register must match the C of the sector ID.
+        let result = read_sector(&disk, WdVariant::Wd179x, DiskCh::new(2, 0), 3, 1, &single).unwrap();
+        assert_eq!(result.status, WdStatus::RECORD_NOT_FOUND);
+
+        // An ID field with a bad CRC sets CRC Error along with Record Not Found.
+        let result = read_sector(&disk, WdVariant::Wd179x, DiskCh::new(0, 1), 0, 3, &single).unwrap();
+        assert_eq!(result.status, WdStatus::RECORD_NOT_FOUND | WdStatus::CRC_ERROR);
+    }
+
+    #[test]
+    fn test_read_address() {
+        let disk = test_disk();
+
+        let result = read_address(&disk, DiskCh::new(0, 0), 0);
+        assert!(result.is_ok());
+        assert_eq!(result.data, [0, 0, 1, 2, 0xCA, 0x6F]);
+        assert_eq!(result.sector, Some(0));
+
+        let result = read_address(&disk, DiskCh::new(0, 1), 2);
+        assert_eq!(result.status, WdStatus::CRC_ERROR);
+        assert_eq!(result.data[..4], [0, 1, 3, 2]);
+
+        let result = read_address(&disk, DiskCh::new(60, 0), 0);
+        assert_eq!(result.status, WdStatus::RECORD_NOT_FOUND);
+        assert_eq!(result.sector, None);
+    }
+
+    #[test]
+    fn test_write_sector() {
+        let mut disk = test_disk();
+        let ch = DiskCh::new(3, 0);
+
+        let flags = WdSectorFlags {
+            multiple: true,
+            data_mark: DataMark::Deleted,
+            ..Default::default()
+        };
+        let result = write_sector(&mut disk, ch, 3, 2, &flags, &[0xAA; 700]).unwrap();
+        assert_eq!(result.status, WdStatus::LOST_DATA);
+        assert_eq!(result.sector, Some(3));
+
+        let read = read_sector(&disk, WdVariant::Wd1772, ch, 3, 3, &WdSectorFlags::default()).unwrap();
+        assert_eq!(read.status, WdStatus::RECORD_TYPE);
+        assert!(read.data[..188].iter().all(|&b| b == 0xAA));
+        assert!(read.data[188..].iter().all(|&b| b == 0));
+
+        let result = write_sector(&mut disk, ch, 3, 12, &WdSectorFlags::default(), &[0; 512]).unwrap();
+        assert_eq!(result.status, WdStatus::RECORD_NOT_FOUND);
+
+        let mut descriptor = disk.image_format().clone();
+        descriptor.write_protect = Some(true);
+        disk.set_image_format(descriptor);
+        let result = write_sector(&mut disk, ch, 3, 1, &WdSectorFlags::default(), &[0; 512]).unwrap();
+        assert_eq!(result.status, WdStatus::WRITE_PROTECT);
+        let result = write_track(&mut disk, ch, &[0x4E; 32]).unwrap();
+        assert_eq!(result.status, WdStatus::WRITE_PROTECT);
+    }
+}
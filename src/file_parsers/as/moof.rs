@@ -54,7 +54,7 @@ use crate::{
     prelude::{DiskCh, TrackDataEncoding, TrackDataRate, TrackDataResolution, TrackDensity},
     source_map::{MapDump, OptionalSourceMap, SourceValue},
     track::fluxstream::FluxStreamTrack,
-    types::{BitStreamTrackParams, DiskDescriptor, DiskRpm, FluxStreamTrackParams},
+    types::{BitStreamTrackParams, DiskDescriptor, DiskRpm, FluxStreamTrackParams, SourceQuirks},
 };
 use binrw::{binrw, BinRead};
 
@@ -389,6 +389,10 @@ impl MoofFormat {
                             ));
                         }
                         info_chunk.write_to_map(disk_image.source_map_mut(), 0);
+                        disk_image.set_source_version(
+                            Some(info_chunk.info_version.to_string()),
+                            Some(format!("created by {}", info_chunk.creator.trim_end())),
+                        );
                         info_chunk_opt = Some(info_chunk);
                     }
                     MoofChunk::TMap(tmap_chunk) => {
@@ -427,6 +431,7 @@ impl MoofFormat {
                     }
                     MoofChunk::Unknown => {
                         log::debug!("Got Unknown Chunk");
+                        disk_image.add_source_quirk(SourceQuirks::UNKNOWN_CHUNKS);
                     }
                 }
             }
@@ -54,7 +54,18 @@ use crate::{
     prelude::{DiskCh, TrackDataEncoding, TrackDataRate, TrackDataResolution, TrackDensity},
     source_map::{MapDump, OptionalSourceMap, SourceValue},
     track::fluxstream::FluxStreamTrack,
-    types::{BitStreamTrackParams, DiskDescriptor, DiskRpm, FluxStreamTrackParams},
+    types::{
+        BitStreamTrackParams,
+        DiskDescriptor,
+        DiskImageMetadata,
+        DiskRpm,
+        FluxStreamTrackParams,
+        SourceQuirks,
+        METADATA_KEY_COMMENT,
+        METADATA_KEY_DUMPER,
+        METADATA_KEY_DUMP_DATE,
+        METADATA_KEY_TITLE,
+    },
 };
 use binrw::{binrw, BinRead};
 
@@ -416,6 +427,10 @@ impl WozFormat {
                             return Err(DiskImageError::IncompatibleImage(err_str));
                         }
                         info_chunk.write_to_map(disk_image.source_map_mut(), 0);
+                        disk_image.set_source_version(
+                            Some(info_chunk.info_version.to_string()),
+                            Some(format!("created by {}", info_chunk.creator.trim_end())),
+                        );
                         info_chunk_opt = Some(info_chunk);
                     }
                     WozChunk::TMap(tmap_chunk) => {
@@ -451,9 +466,11 @@ impl WozFormat {
                             }
                             log::debug!("{}: {}", key, value);
                         }
+                        disk_image.set_image_metadata(&Self::image_metadata(&meta_map));
                     }
                     WozChunk::Unknown => {
                         log::debug!("Got Unknown Chunk");
+                        disk_image.add_source_quirk(SourceQuirks::UNKNOWN_CHUNKS);
                     }
                 }
             }
@@ -845,6 +862,20 @@ impl WozFormat {
         meta_map
     }
 
+    /// Map the keys of a META chunk to the fields of a [DiskImageMetadata].
+    fn image_metadata(meta_map: &FoxHashMap<String, String>) -> DiskImageMetadata {
+        let mut metadata = DiskImageMetadata::default();
+        for (woz_key, key) in [
+            ("title", METADATA_KEY_TITLE),
+            ("notes", METADATA_KEY_COMMENT),
+            ("contributor", METADATA_KEY_DUMPER),
+            ("image_date", METADATA_KEY_DUMP_DATE),
+        ] {
+            metadata.set(key, meta_map.get(woz_key).map(String::as_str));
+        }
+        metadata
+    }
+
     pub fn save_image<RWS: ReadWriteSeek>(
         _image: &DiskImage,
         _opts: &ParserWriteOptions,
@@ -853,3 +884,21 @@ impl WozFormat {
         Err(DiskImageError::UnsupportedFormat)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_woz_meta() {
+        let meta_map = WozFormat::parse_meta(
+            "title\tDemo Disk\nnotes\tOriginal master\ncontributor\tfluxfox\nimage_date\t2024-05-01T00:00:00.000Z\npublisher\t",
+        );
+        let metadata = WozFormat::image_metadata(&meta_map);
+        assert_eq!(metadata.title.as_deref(), Some("Demo Disk"));
+        assert_eq!(metadata.comment.as_deref(), Some("Original master"));
+        assert_eq!(metadata.dumper.as_deref(), Some("fluxfox"));
+        assert_eq!(metadata.dump_date.as_deref(), Some("2024-05-01T00:00:00.000Z"));
+        assert_eq!(metadata.source_hardware, None);
+    }
+}
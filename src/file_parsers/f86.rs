@@ -39,7 +39,7 @@ use crate::{
     file_parsers::{bitstream_flags, FormatCaps, ParserReadOptions, ParserWriteCompatibility, ParserWriteOptions},
     io::{ReadSeek, ReadWriteSeek},
     source_map::{MapDump, OptionalSourceMap, SourceValue},
-    track::bitstream::BitStreamTrack,
+    track::{bitstream::BitStreamTrack, Track},
     track_schema::TrackSchema,
     types::{
         BitStreamTrackParams,
@@ -52,11 +52,13 @@ use crate::{
         TrackDataRate,
         TrackDataResolution,
         TrackDensity,
+        TrackFormatting,
     },
     DiskImage,
     DiskImageError,
     DiskImageFileFormat,
     LoadingCallback,
+    SavingStatus,
 };
 use binrw::{binrw, BinRead, BinWrite};
 use bitflags::bitflags;
@@ -429,6 +431,10 @@ impl F86Format {
         }
         // Write the header to the source map.
         header.write_to_map(disk_image.source_map_mut(), 0);
+        disk_image.set_source_version(
+            Some(format!("{}.{}", header.major_version, header.minor_version)),
+            has_surface_desc.then(|| "surface description".to_string()),
+        );
 
         log::debug!(
             "bitcell flags: {},{},{},{}",
@@ -836,7 +842,7 @@ impl F86Format {
     /// When writing track data, the size must be rounded to the nearest word (2 bytes).
     pub fn save_image<RWS: ReadWriteSeek>(
         image: &DiskImage,
-        _opts: &ParserWriteOptions,
+        opts: &ParserWriteOptions,
         output: &mut RWS,
     ) -> Result<(), DiskImageError> {
         if Self::can_write(Some(&image)) == ParserWriteCompatibility::Incompatible {
@@ -849,9 +855,14 @@ impl F86Format {
 
         let mut has_surface_description = false;
         let has_weak_bits = image.has_weak_bits();
-        if has_weak_bits {
+        // 86F represents a noisy unformatted track as a track of entirely weak bits, which also
+        // requires a surface descriptor.
+        let has_noise = image
+            .iter_tracks()
+            .any(|(_, track)| track.formatting() == TrackFormatting::Unformatted { noise: true });
+        if has_weak_bits || has_noise {
             // We'll need to include a surface descriptor.
-            log::trace!("Image has weak/hole bits.");
+            log::trace!("Image has weak/hole bits or noise tracks.");
             has_surface_description = true;
             disk_flags |= F86_DISK_HAS_SURFACE_DESC;
         }
@@ -976,6 +987,13 @@ impl F86Format {
             log::trace!("Writing track entry {}, c: {} h: {}, offset: {}", i, c, h, *offset);
 
             let ti = image.track_map[h][c as usize];
+            if let Some(callback_fn) = opts.callback() {
+                callback_fn(SavingStatus::Track {
+                    ch:    image.track_pool[ti].ch(),
+                    index: i,
+                    total: track_entries,
+                });
+            }
 
             if let Some(track) = image.track_pool[ti].as_any().downcast_ref::<BitStreamTrack>() {
                 let absolute_bit_count = track.data.len();
@@ -1005,7 +1023,12 @@ impl F86Format {
                     }
                 }
 
-                if image.has_flag(DiskImageFlags::PROLOK) && c == 39 && h == 0 {
+                if track.formatting() == (TrackFormatting::Unformatted { noise: true }) {
+                    log::debug!("Writing unformatted track {} as weak bits.", track.ch());
+                    bit_data.fill(0xFF);
+                    weak_data.fill(0xFF);
+                }
+                else if image.has_flag(DiskImageFlags::PROLOK) && c == 39 && h == 0 {
                     log::debug!(
                         "PROLOK: Converting {} weak bits to holes.",
                         track.data.weak_data().len()
@@ -35,7 +35,16 @@ use crate::{
     file_parsers::{FormatCaps, ParserReadOptions, ParserWriteCompatibility, ParserWriteOptions},
     io::{ReadSeek, ReadWriteSeek},
     source_map::{MapDump, OptionalSourceMap, SourceValue},
-    types::{BitStreamTrackParams, DiskCh, DiskDescriptor, Platform, TrackDataEncoding, TrackDataRate, TrackDensity},
+    types::{
+        BitStreamTrackParams,
+        DiskCh,
+        DiskDescriptor,
+        Platform,
+        SourceQuirks,
+        TrackDataEncoding,
+        TrackDataRate,
+        TrackDensity,
+    },
     DiskImage,
     DiskImageError,
     DiskImageFileFormat,
@@ -327,6 +336,11 @@ impl HfeFormat {
 
         let hfe_floppy_interface = HfeFloppyInterface::from(file_header.interface_mode);
         let hfe_track_encoding = HfeFloppyEncoding::from(file_header.track_encoding);
+        // Only the original "HXCPICFE" signature (HFEv1) is supported.
+        disk_image.set_source_version(
+            Some(format!("1.{}", file_header.format_revision)),
+            Some(format!("{:?} interface", hfe_floppy_interface)),
+        );
         log::trace!(
             "Got HXE header. Cylinders: {} Heads: {} Encoding: {:?}",
             file_header.number_of_tracks,
@@ -368,6 +382,7 @@ impl HfeFormat {
                     ti,
                     track.len
                 );
+                disk_image.add_source_quirk(SourceQuirks::UNALIGNED_TRACK_DATA);
             }
             else {
                 log::trace!(
@@ -37,6 +37,8 @@ use crate::{
         TrackDataEncoding,
         TrackDataRate,
         TrackDensity,
+        METADATA_KEY_COMMENT,
+        METADATA_KEY_DUMP_DATE,
     },
     util::{get_length, read_ascii},
     DiskImage,
@@ -183,13 +185,21 @@ impl ImdFormat {
                     terminator,
                     &comment.clone().unwrap_or("None".to_string())
                 );
+                disk_image.set_source_version(Some(format!("{}.{}", v_major, v_minor)), None);
 
                 if let Some(comment) = comment {
                     if !comment.is_empty() {
                         log::trace!("load_image(): Setting comment metadata: {}", &comment);
-                        disk_image.set_metadata_key("comment", &comment);
+                        disk_image.set_metadata_key(METADATA_KEY_COMMENT, &comment);
                     }
                 }
+
+                // The header timestamp records when the image was created.
+                let dump_date = format!(
+                    "{}-{}-{:0>2} {:0>2}:{}:{}",
+                    &caps["year"], &caps["month"], &caps["day"], &caps["hh"], &caps["mm"], &caps["ss"]
+                );
+                disk_image.set_metadata_key(METADATA_KEY_DUMP_DATE, &dump_date);
             }
         }
 
@@ -300,7 +310,7 @@ impl ImdFormat {
                             s + 1,
                             data_marker,
                             &data.data.len(),
-                            &data.data[0..16],
+                            &data.data[..data.data.len().min(16)],
                             &data.deleted,
                             &data.error
                         );
@@ -315,10 +325,12 @@ impl ImdFormat {
                                 address_error: false,
                                 data_error: data.error,
                                 deleted_mark: data.deleted,
-                                no_dam: false,
+                                // A sector whose data was unavailable to the imager has no data field.
+                                no_dam: data_marker == 0x00,
                             },
                             alternate: false,
                             bit_index: None,
+                            ..Default::default()
                         };
 
                         new_track.add_sector(&params)?;
@@ -40,7 +40,8 @@ use crate::{
     io,
     io::{ReadBytesExt, ReadSeek, ReadWriteSeek},
     source_map::{OptionalSourceMap, SourceValue},
-    track::fluxstream::FluxStreamTrack,
+    track::{fluxstream::FluxStreamTrack, TrackInfo},
+    track_mapping::Sectoring,
     types::{DiskCh, DiskDescriptor, FluxStreamTrackParams, Platform, TrackDataEncoding, TrackDataResolution},
     util::read_ascii,
     DiskImage,
@@ -178,11 +179,38 @@ impl<'a> KfxFormat<'a> {
     }
 
     pub(crate) fn load_image<RWS: ReadSeek>(
-        mut image: RWS,
+        image: RWS,
         disk_image: &mut DiskImage,
         _opts: &ParserReadOptions,
         _callback: Option<LoadingCallback>,
     ) -> Result<(), DiskImageError> {
+        let next_ch = Self::next_ch(disk_image);
+        let flux_track = Self::read_track(image, disk_image, next_ch)?;
+        Self::add_track(disk_image, flux_track, next_ch)
+    }
+
+    /// Return the physical cylinder and head of the next track to be added to `disk_image`.
+    pub(crate) fn next_ch(disk_image: &DiskImage) -> DiskCh {
+        if disk_image.track_ch_iter().count() == 0 {
+            log::debug!("No tracks in image, starting at c:0 h:0");
+            DiskCh::new(0, 0)
+        }
+        else {
+            let mut last_ch = disk_image.track_ch_iter().last().unwrap_or(DiskCh::new(0, 0));
+            log::debug!("Previous track in image: {} heads: {}", last_ch, disk_image.heads());
+
+            last_ch.seek_next_track(disk_image.geometry());
+            last_ch
+        }
+    }
+
+    /// Read a stream file into a [FluxStreamTrack] for the track `next_ch`, without decoding it.
+    /// The track is added to `disk_image` with [KfxFormat::add_track] or [KfxFormat::add_tracks].
+    pub(crate) fn read_track<RWS: ReadSeek>(
+        mut image: RWS,
+        disk_image: &mut DiskImage,
+        next_ch: DiskCh,
+    ) -> Result<FluxStreamTrack, DiskImageError> {
         disk_image.set_resolution(TrackDataResolution::FluxStream);
         disk_image.set_source_format(DiskImageFileFormat::KryofluxStream);
 
@@ -238,19 +266,6 @@ impl<'a> KfxFormat<'a> {
             index_times.len()
         );
 
-        // Get last ch in image.
-        let next_ch = if disk_image.track_ch_iter().count() == 0 {
-            log::debug!("No tracks in image, starting at c:0 h:0");
-            DiskCh::new(0, 0)
-        }
-        else {
-            let mut last_ch = disk_image.track_ch_iter().last().unwrap_or(DiskCh::new(0, 0));
-            log::debug!("Previous track in image: {} heads: {}", last_ch, disk_image.heads());
-
-            last_ch.seek_next_track(disk_image.geometry());
-            last_ch
-        };
-
         for ((_ri, rev), index_time) in streams
             .iter()
             .enumerate()
@@ -434,8 +449,19 @@ impl<'a> KfxFormat<'a> {
         //     disk_image.add_track_bitstream(params)?;
         // }
 
-        let data_rate = disk_image.data_rate();
+        // A hard-sectored disk pulses the index sensor at every sector hole.
+        if next_ch == DiskCh::new(0, 0) {
+            let sectoring = Sectoring::from_index_times(&index_times);
+            log::debug!("Detected sectoring from index times: {}", sectoring);
+            disk_image.set_track_mapping(disk_image.track_mapping().with_sectoring(sectoring));
+        }
 
+        Ok(flux_track)
+    }
+
+    /// Return the track parameters for the track `ch`, with hints from `disk_image` if it already
+    /// has tracks.
+    fn track_params(disk_image: &DiskImage, ch: DiskCh) -> FluxStreamTrackParams {
         // Get hints from disk image if we aren't the first track.
         let (clock_hint, rpm_hint) = if !disk_image.track_pool.is_empty() {
             (
@@ -447,22 +473,59 @@ impl<'a> KfxFormat<'a> {
             (None, None)
         };
 
-        let params = FluxStreamTrackParams {
-            ch: next_ch,
+        FluxStreamTrackParams {
+            ch,
             schema: None,
             encoding: None,
             clock: clock_hint,
             rpm: rpm_hint,
-        };
+        }
+    }
+
+    /// Decode and add a track read by [KfxFormat::read_track] to `disk_image`.
+    pub(crate) fn add_track(
+        disk_image: &mut DiskImage,
+        flux_track: FluxStreamTrack,
+        ch: DiskCh,
+    ) -> Result<(), DiskImageError> {
+        let params = Self::track_params(disk_image, ch);
+        let info = disk_image.add_track_fluxstream(flux_track, &params)?.info();
+        Self::update_descriptor(disk_image, &info);
+        Ok(())
+    }
 
-        let new_track = disk_image.add_track_fluxstream(flux_track, &params)?;
+    /// Decode and add a set of tracks read by [KfxFormat::read_track] to `disk_image`. The first
+    /// track is decoded on its own to provide clock and rotation rate hints for the rest, which
+    /// are then decoded together and so may be decoded in parallel.
+    pub(crate) fn add_tracks(
+        disk_image: &mut DiskImage,
+        flux_tracks: Vec<(FluxStreamTrack, DiskCh)>,
+    ) -> Result<(), DiskImageError> {
+        let mut flux_tracks = flux_tracks.into_iter();
+        if disk_image.track_pool.is_empty() {
+            if let Some((flux_track, ch)) = flux_tracks.next() {
+                Self::add_track(disk_image, flux_track, ch)?;
+            }
+        }
 
-        let (new_density, new_rpm) = if new_track.sector_ct() == 0 {
+        let batch = flux_tracks
+            .map(|(flux_track, ch)| (flux_track, Self::track_params(disk_image, ch)))
+            .collect();
+        for info in disk_image.add_tracks_fluxstream(batch)? {
+            Self::update_descriptor(disk_image, &info);
+        }
+        Ok(())
+    }
+
+    /// Update the disk descriptor of `disk_image` from the info of a newly added track.
+    fn update_descriptor(disk_image: &mut DiskImage, info: &TrackInfo) {
+        let data_rate = disk_image.data_rate();
+
+        let (new_density, new_rpm) = if info.sector_ct == 0 {
             log::warn!("Track did not decode any sectors. Not updating disk image descriptor.");
             (disk_image.descriptor.density, disk_image.descriptor.rpm)
         }
         else {
-            let info = new_track.info();
             log::debug!(
                 "Updating disk descriptor with density: {:?} and RPM: {:?}",
                 info.density,
@@ -483,8 +546,6 @@ impl<'a> KfxFormat<'a> {
             rpm: new_rpm,
             write_protect: Some(true),
         };
-
-        Ok(())
     }
 
     pub fn save_image<RWS: ReadWriteSeek>(
@@ -66,6 +66,7 @@ use crate::{
         DiskRpm,
         FluxStreamTrackParams,
         Platform,
+        SourceQuirks,
         TrackDataEncoding,
         TrackDensity,
     },
@@ -270,7 +271,7 @@ impl MfiFormat {
     pub(crate) fn load_image<RWS: ReadSeek>(
         mut read_buf: RWS,
         disk: &mut DiskImage,
-        _opts: &ParserReadOptions,
+        opts: &ParserReadOptions,
         callback: Option<LoadingCallback>,
     ) -> Result<(), DiskImageError> {
         if let Some(ref callback_fn) = callback {
@@ -326,6 +327,7 @@ impl MfiFormat {
                 "Unknown or unsupported disk variant: {:08X?}",
                 file_header.variant.as_slice()
             );
+            disk.add_source_quirk(SourceQuirks::UNKNOWN_DISK_TYPE);
             // Unfortunately some versions of Applesauce < 2.0 failed to set this properly, so we
             // have to deal with it and can't bail.
             //return Err(DiskImageError::UnsupportedFormat);
@@ -467,6 +469,7 @@ impl MfiFormat {
         let mut last_bitcell_ct = None;
 
         for (ti, track) in tracks.iter().enumerate() {
+            opts.check_cancelled()?;
             let flux_track = Self::process_track_data_new(track, disk_rpm)?;
 
             if flux_track.is_empty() {
@@ -528,6 +531,11 @@ impl MfiFormat {
                 }
 
                 if let Some(ref callback_fn) = callback {
+                    callback_fn(LoadingStatus::Track {
+                        ch:    track.ch,
+                        index: ti,
+                        total: total_tracks,
+                    });
                     let progress = ti as f64 / total_tracks as f64;
                     callback_fn(LoadingStatus::Progress(progress));
                 }
@@ -44,7 +44,7 @@ pub mod tc;
 pub mod td0;
 
 #[cfg(feature = "async")]
-use std::sync::{Arc, Mutex};
+use std::sync::Mutex;
 
 #[cfg(feature = "moof")]
 use r#as::moof;
@@ -52,14 +52,23 @@ use r#as::moof;
 use r#as::woz;
 
 use pce::{pfi, pri, psi};
+use std::{
+    fmt::{Debug, Formatter},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use crate::{
+    flux::synthesis::FluxSynthesisOptions,
     io::{ReadSeek, ReadWriteSeek, SeekFrom},
-    types::Platform,
+    types::{DecodePolicy, Platform},
     DiskImage,
     DiskImageError,
     DiskImageFileFormat,
     LoadingCallback,
+    SavingCallback,
 };
 
 use bitflags::bitflags;
@@ -69,13 +78,128 @@ use strum::IntoEnumIterator;
 #[derive(Clone, Debug, Default)]
 pub struct ParserReadOptions {
     platform: Option<Platform>, // If we know the platform, we can give it to the parser as a hint if the platform is otherwise ambiguous.
-    flags:    ReadFlags,
+    flags: ReadFlags,
+    decode_policy: DecodePolicy,     // When flux format parsers decode the tracks they load.
+    normalize_dd_bands: bool,        // Whether skewed double-density flux bands are normalized before decoding.
+    cancel: Option<Arc<AtomicBool>>, // When set, aborts the load between tracks.
+}
+
+impl ParserReadOptions {
+    /// Set the [DecodePolicy] used by flux format parsers to decide when to decode tracks.
+    pub fn with_decode_policy(self, decode_policy: DecodePolicy) -> Self {
+        Self { decode_policy, ..self }
+    }
+
+    /// Retrieve the [DecodePolicy] used by flux format parsers.
+    pub fn decode_policy(&self) -> DecodePolicy {
+        self.decode_policy
+    }
+
+    /// Set whether the transition bands of double-density flux tracks are normalized before
+    /// decoding. See [DiskImage::set_dd_band_normalization].
+    pub fn with_dd_band_normalization(self, normalize_dd_bands: bool) -> Self {
+        Self {
+            normalize_dd_bands,
+            ..self
+        }
+    }
+
+    /// Retrieve whether the transition bands of double-density flux tracks are normalized.
+    pub fn dd_band_normalization(&self) -> bool {
+        self.normalize_dd_bands
+    }
+
+    /// Set a flag that cancels the load when set. The load is checked between stages and, by file
+    /// parsers that report per-track progress, between tracks, and fails with
+    /// [DiskImageError::Cancelled] once the flag is set.
+    pub fn with_cancel_flag(self, cancel: Arc<AtomicBool>) -> Self {
+        Self {
+            cancel: Some(cancel),
+            ..self
+        }
+    }
+
+    /// Return [DiskImageError::Cancelled] if the cancel flag has been set.
+    pub(crate) fn check_cancelled(&self) -> Result<(), DiskImageError> {
+        match &self.cancel {
+            Some(cancel) if cancel.load(Ordering::Relaxed) => Err(DiskImageError::Cancelled),
+            _ => Ok(()),
+        }
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct ParserWriteOptions {
     platform: Option<Platform>, // If we know the platform, we can give it to the parser as a hint if the platform is otherwise ambiguous.
+    flux: FluxSynthesisOptions, // Used by flux format writers when synthesizing flux from bitstream tracks.
+    callback: Option<SavingCallback>, // Used by file parsers that support progress reporting.
+    raw_fill: RawFillPolicy,    // Used by the raw sector image writer for sectors it cannot copy.
+}
+
+/// Controls how the raw sector image writer fills sectors that have no data to copy from the
+/// source image. A raw sector image has no way to mark such sectors, so they must be written
+/// with filler data.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RawFillPolicy {
+    /// The byte used to fill sectors on unformatted tracks and to pad short sectors.
+    pub fill_byte: u8,
+    /// Fill sectors that cannot be read with `fill_byte` instead of failing the write.
+    pub fill_unreadable: bool,
+}
+
+impl Debug for ParserWriteOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParserWriteOptions")
+            .field("platform", &self.platform)
+            .field("flux", &self.flux)
+            .field("callback", &self.callback.is_some())
+            .field("raw_fill", &self.raw_fill)
+            .finish()
+    }
+}
+
+impl ParserWriteOptions {
+    /// Set a callback to receive per-track progress from file parsers that support it.
+    pub fn with_callback(self, callback: SavingCallback) -> Self {
+        Self {
+            callback: Some(callback),
+            ..self
+        }
+    }
+
+    /// Retrieve the progress callback, if one was set.
+    pub fn callback(&self) -> Option<&SavingCallback> {
+        self.callback.as_ref()
+    }
+
+    /// Set the options used when synthesizing flux from bitstream tracks for flux image formats.
+    pub fn with_flux_synthesis(self, flux: FluxSynthesisOptions) -> Self {
+        Self { flux, ..self }
+    }
+
+    /// Set the number of revolutions to emit per track when writing flux image formats.
+    pub fn with_flux_revolutions(self, revolutions: usize) -> Self {
+        Self {
+            flux: self.flux.with_revolutions(revolutions),
+            ..self
+        }
+    }
+
+    /// Retrieve the options used when synthesizing flux for flux image formats.
+    pub fn flux_synthesis(&self) -> &FluxSynthesisOptions {
+        &self.flux
+    }
+
+    /// Set the [RawFillPolicy] used when writing raw sector images.
+    pub fn with_raw_fill(self, raw_fill: RawFillPolicy) -> Self {
+        Self { raw_fill, ..self }
+    }
+
+    /// Retrieve the [RawFillPolicy] used when writing raw sector images.
+    pub fn raw_fill(&self) -> RawFillPolicy {
+        self.raw_fill
+    }
 }
 
 bitflags! {
@@ -126,6 +250,33 @@ bitflags! {
     }
 }
 
+impl FormatCaps {
+    /// Return a short description of each capability flag set, suitable for display to the user,
+    /// for example when warning which features of an image a format cannot represent.
+    pub fn descriptions(&self) -> Vec<&'static str> {
+        self.iter()
+            .map(|cap| match cap {
+                FormatCaps::CAP_VARIABLE_SPT => "Variable sectors per track",
+                FormatCaps::CAP_VARIABLE_SSPT => "Variable sector sizes",
+                FormatCaps::CAP_ADDRESS_CRC => "Sector address CRC errors",
+                FormatCaps::CAP_DATA_CRC => "Sector data CRC errors",
+                FormatCaps::CAP_DATA_DELETED => "Deleted data marks",
+                FormatCaps::CAP_SID_OVERRIDE => "Non-standard sector IDs",
+                FormatCaps::CAP_COMMENT => "Comment",
+                FormatCaps::CAP_TRACK_ENCODING => "Per-track encoding",
+                FormatCaps::CAP_TRACK_DATA_RATE => "Per-track data rate",
+                FormatCaps::CAP_WEAK_BITS => "Weak bits",
+                FormatCaps::CAP_HOLES => "Holes",
+                FormatCaps::CAP_ENCODING_FM => "FM encoding",
+                FormatCaps::CAP_ENCODING_MFM => "MFM encoding",
+                FormatCaps::CAP_ENCODING_GCR => "GCR encoding",
+                FormatCaps::CAP_NO_DAM => "Sectors with no data mark",
+                _ => "Unknown capability",
+            })
+            .collect()
+    }
+}
+
 /// Return a set of FormatCaps flags implicitly supported by the nature of any bitstream format.
 pub fn bitstream_flags() -> FormatCaps {
     FormatCaps::CAP_VARIABLE_SPT
@@ -470,7 +621,12 @@ impl ImageFormatParser for DiskImageFileFormat {
             DiskImageFileFormat::HfeImage => hfe::HfeFormat::save_image(image, opts, write_buf),
             DiskImageFileFormat::F86Image => f86::F86Format::save_image(image, opts, write_buf),
             DiskImageFileFormat::TransCopyImage => tc::TCFormat::save_image(image, opts, write_buf),
-            DiskImageFileFormat::SuperCardPro => scp::ScpFormat::save_image(image, opts, write_buf),
+            DiskImageFileFormat::SuperCardPro => {
+                // Flux is synthesized from the bitstream of each track, so finish decoding a lazily
+                // loaded image first, as an eager load would have.
+                image.decode_all_tracks();
+                scp::ScpFormat::save_image(image, opts, write_buf)
+            }
             DiskImageFileFormat::PceFluxImage => pfi::PfiFormat::save_image(image, opts, write_buf),
             DiskImageFileFormat::KryofluxStream => kryoflux::KfxFormat::save_image(image, opts, write_buf),
             #[cfg(feature = "mfi")]
@@ -45,7 +45,15 @@ use crate::{
     },
     io::{Cursor, ReadBytesExt, ReadSeek, ReadWriteSeek},
     track::fluxstream::FluxStreamTrack,
-    types::{chs::DiskCh, DiskDescriptor, FluxStreamTrackParams, Platform, TrackDataEncoding, TrackDensity},
+    types::{
+        chs::DiskCh,
+        DiskDescriptor,
+        DiskImageMetadata,
+        FluxStreamTrackParams,
+        Platform,
+        TrackDataEncoding,
+        TrackDensity,
+    },
     DiskImage,
     DiskImageError,
     DiskImageFileFormat,
@@ -388,6 +396,9 @@ impl PfiFormat {
         }
 
         log::trace!("Comment: {}", comment_string);
+        if !comment_string.is_empty() {
+            disk_image.set_image_metadata(&DiskImageMetadata::from_comment(&comment_string));
+        }
 
         let head_ct = heads_seen.len() as u8;
         let cylinder_ct = cylinders_seen.len() as u16;
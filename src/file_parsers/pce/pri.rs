@@ -38,20 +38,33 @@
 use crate::{
     file_parsers::{bitstream_flags, FormatCaps, ParserWriteCompatibility},
     io::{Cursor, ReadSeek, ReadWriteSeek, Write},
-    types::{BitStreamTrackParams, DiskDescriptor},
+    types::{BitStreamTrackParams, DiskDescriptor, DiskImageMetadata},
 };
 
 use crate::{
     file_parsers::{pce::crc::pce_crc, ParserReadOptions, ParserWriteOptions},
-    track::bitstream::BitStreamTrack,
-    types::{chs::DiskCh, Platform, TrackDataEncoding, TrackDataRate, TrackDataResolution, TrackDensity},
+    track::{bitstream::BitStreamTrack, Track},
+    types::{
+        chs::DiskCh,
+        Platform,
+        TrackDataEncoding,
+        TrackDataRate,
+        TrackDataResolution,
+        TrackDensity,
+        TrackFormatting,
+    },
     DiskImage,
     DiskImageError,
     DiskImageFileFormat,
     FoxHashSet,
     LoadingCallback,
+    SavingStatus,
 };
 use binrw::{binrw, meta::WriteEndian, BinRead, BinWrite};
+use bit_vec::BitVec;
+
+/// The maximum length of text written to a single TEXT chunk.
+const PRI_MAX_TEXT_LEN: usize = 1000;
 
 pub struct PriFormat;
 pub const MAXIMUM_CHUNK_SIZE: usize = 0x100000; // Reasonable 1MB limit for chunk sizes.
@@ -308,7 +321,7 @@ impl PriFormat {
         // Create a chunk buffer Cursor to write our chunk data into.
         let mut chunk_buf = Cursor::new(Vec::new());
 
-        if text.len() > 1000 {
+        if text.len() > PRI_MAX_TEXT_LEN {
             panic!("Text chunk too large.");
         }
 
@@ -527,6 +540,9 @@ impl PriFormat {
         }
 
         log::trace!("Comment: {}", comment_string);
+        if !comment_string.is_empty() {
+            disk_image.set_image_metadata(&DiskImageMetadata::from_comment(&comment_string));
+        }
 
         let head_ct = heads_seen.len() as u8;
         let cylinder_ct = cylinders_seen.len() as u16;
@@ -545,7 +561,7 @@ impl PriFormat {
 
     pub fn save_image<RWS: ReadWriteSeek>(
         image: &DiskImage,
-        _opts: &ParserWriteOptions,
+        opts: &ParserWriteOptions,
         output: &mut RWS,
     ) -> Result<(), DiskImageError> {
         if (image.resolution.len() > 1) || !image.resolution.contains(&TrackDataResolution::BitStream) {
@@ -561,13 +577,25 @@ impl PriFormat {
         };
         PriFormat::write_chunk(output, PriChunkType::FileHeader, &file_header)?;
 
-        // Write any comments present in the image to a TEXT chunk.
-        image
-            .metadata_key("comment")
-            .map(|comment| PriFormat::write_text(output, &comment));
+        // Write the image metadata to TEXT chunks. Readers concatenate the contents of multiple
+        // TEXT chunks, so long text is split across chunks.
+        if let Some(comment) = image.image_metadata().to_comment() {
+            for text in split_text(&comment, PRI_MAX_TEXT_LEN) {
+                PriFormat::write_text(output, text)?;
+            }
+        }
 
         // Iterate through tracks and write track headers and data.
-        for track in image.track_iter() {
+        let track_ct = image.track_iter().count();
+        for (ti, track) in image.track_iter().enumerate() {
+            if let Some(callback_fn) = opts.callback() {
+                callback_fn(SavingStatus::Track {
+                    ch:    track.ch(),
+                    index: ti,
+                    total: track_ct,
+                });
+            }
+
             if let Some(track) = track.as_any().downcast_ref::<BitStreamTrack>() {
                 log::trace!(
                     "Track {}: encoding: {:?} data_rate: {:?} bit length: {}",
@@ -590,10 +618,19 @@ impl PriFormat {
                 let track_data = track.data.data_copied();
                 PriFormat::write_chunk(output, PriChunkType::TrackData, &track_data)?;
 
-                if track.data.weak_mask().any() {
-                    // At least one bit is set in the weak bit mask, so let's export it.
-                    let weak_mask = track.data.weak_mask();
+                // PRI represents a noisy unformatted track as a track of entirely weak bits.
+                let noise_mask;
+                let weak_mask = if track.formatting() == (TrackFormatting::Unformatted { noise: true }) {
+                    log::debug!("Writing unformatted track {} as weak bits.", track.ch);
+                    noise_mask = BitVec::from_elem(track.data.len(), true);
+                    &noise_mask
+                }
+                else {
+                    track.data.weak_mask()
+                };
 
+                if weak_mask.any() {
+                    // At least one bit is set in the weak bit mask, so let's export it.
                     // Create a buffer for our weak mask table.
                     let mut weak_buffer = Cursor::new(Vec::new());
 
@@ -643,3 +680,19 @@ impl PriFormat {
         Ok(())
     }
 }
+
+/// Split `text` into pieces of at most `max_len` bytes, on character boundaries.
+fn split_text(text: &str, max_len: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(max_len);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (piece, remainder) = rest.split_at(end);
+        pieces.push(piece);
+        rest = remainder;
+    }
+    pieces
+}
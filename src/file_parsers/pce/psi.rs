@@ -38,7 +38,7 @@
 use crate::{
     file_parsers::{FormatCaps, ParserWriteCompatibility},
     io::{Cursor, ReadSeek, ReadWriteSeek},
-    types::{AddSectorParams, DiskDescriptor},
+    types::{AddSectorParams, DiskDescriptor, DiskImageMetadata, SourceQuirks},
 };
 
 use crate::{
@@ -308,6 +308,8 @@ impl PsiFormat {
             decode_psi_sector_format(file_header.sector_format).ok_or(DiskImageError::FormatParseError)?;
 
         let mut comment_string = String::new();
+        // Tracks are borrowed from the disk image while loading, so collect quirks to apply later.
+        let mut quirks = SourceQuirks::empty();
 
         let mut ctx = SectorContext::default();
         let mut track_set: FoxHashSet<DiskCh> = FoxHashSet::new();
@@ -381,6 +383,7 @@ impl PsiFormat {
                                 },
                                 alternate: ctx.alternate,
                                 bit_index: ctx.bit_offset.map(|x| x as usize),
+                                ..Default::default()
                             };
 
                             track.add_sector(&params)?;
@@ -417,6 +420,7 @@ impl PsiFormat {
                             ctx.phys_size,
                             chunk.data.len()
                         );
+                        quirks |= SourceQuirks::SECTOR_SIZE_MISMATCH;
                     }
 
                     if let Some(ref mut track) = current_track {
@@ -434,6 +438,7 @@ impl PsiFormat {
                             },
                             alternate: ctx.alternate,
                             bit_index: ctx.bit_offset.map(|x| x as usize),
+                            ..Default::default()
                         };
 
                         track.add_sector(&params)?;
@@ -462,6 +467,7 @@ impl PsiFormat {
 
                     if ctx.ibm_chsn.is_some() {
                         log::warn!("Duplicate IBM sector header or context not reset");
+                        quirks |= SourceQuirks::DUPLICATE_SECTOR_HEADER;
                     }
 
                     ctx.ibm_chsn = Some(DiskChsn::from((
@@ -482,12 +488,20 @@ impl PsiFormat {
                 }
                 _ => {
                     log::warn!("Unhandled chunk type: {:?}", chunk.chunk_type);
+                    quirks |= SourceQuirks::UNKNOWN_CHUNKS;
                 }
             }
 
             chunk = PsiFormat::read_chunk(&mut read_buf)?;
         }
 
+        disk_image.set_source_version(Some(file_header.version.to_string()), None);
+        disk_image.add_source_quirk(quirks);
+
+        if !comment_string.is_empty() {
+            disk_image.set_image_metadata(&DiskImageMetadata::from_comment(&comment_string));
+        }
+
         let head_ct = heads_seen.len() as u8;
         let track_ct = track_set.len() as u16;
         disk_image.descriptor = DiskDescriptor {
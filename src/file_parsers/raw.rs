@@ -48,6 +48,7 @@ use crate::{
     DiskImageError,
     DiskImageFileFormat,
     LoadingCallback,
+    SavingStatus,
     StandardFormat,
 };
 
@@ -279,6 +280,7 @@ impl RawFormat {
                     attributes: Default::default(),
                     alternate: false,
                     bit_index: None,
+                    ..Default::default()
                 };
 
                 new_track.add_sector(&sector_params)?;
@@ -300,7 +302,7 @@ impl RawFormat {
 
     pub fn save_image<RWS: ReadWriteSeek>(
         disk: &mut DiskImage,
-        _opts: &ParserWriteOptions,
+        opts: &ParserWriteOptions,
         output: &mut RWS,
     ) -> Result<(), DiskImageError> {
         let format = disk.closest_format(true).ok_or(DiskImageError::UnsupportedFormat)?;
@@ -308,8 +310,38 @@ impl RawFormat {
         // An IMG file basically represents DOS's view of a disk. Non-standard sectors may as well not
         // exist. The same basically applies for ADF files as well.
 
+        let fill = opts.raw_fill();
+        let track_ct = format.layout().ch_iter().count();
+        let mut last_ch = None;
+        let mut track_idx = 0;
+
         // Write out the sectors in the standard order using DiskChsn::iter().
         for chsn in format.layout().chsn_iter() {
+            if last_ch != Some(chsn.ch()) {
+                if let Some(callback_fn) = opts.callback() {
+                    callback_fn(SavingStatus::Track {
+                        ch:    chsn.ch(),
+                        index: track_idx,
+                        total: track_ct,
+                    });
+                }
+                last_ch = Some(chsn.ch());
+                track_idx += 1;
+            }
+
+            // A raw sector image has no way to represent an unformatted track, so write its sectors
+            // with the fill byte, as a disk copier would for an unreadable track.
+            if disk.track(chsn.ch()).is_some_and(|t| t.formatting().is_unformatted()) {
+                log::warn!(
+                    "Raw::save_image(): Track {} is unformatted. Filling sector {} with {:02X}",
+                    chsn.ch(),
+                    chsn,
+                    fill.fill_byte
+                );
+                output.write_all(&vec![fill.fill_byte; chsn.n_size()])?;
+                continue;
+            }
+
             match disk.read_sector_basic(chsn.ch(), DiskChsnQuery::from(chsn), None) {
                 Ok(read_buf) => {
                     log::trace!("Raw::save_image(): Read {} bytes from sector: {}", read_buf.len(), chsn);
@@ -332,7 +364,7 @@ impl RawFormat {
                                 new_buf.len(),
                                 chsn.n_size()
                             );
-                            new_buf.extend(vec![0u8; chsn.n_size() - new_buf.len()]);
+                            new_buf.extend(vec![fill.fill_byte; chsn.n_size() - new_buf.len()]);
                         }
                         Ordering::Equal => {}
                     }
@@ -342,6 +374,14 @@ impl RawFormat {
                     //println!("Raw::save_image(): Writing chs: {}...", chs);
                     output.write_all(new_buf.as_ref())?;
                 }
+                Err(e) if fill.fill_unreadable => {
+                    log::warn!(
+                        "Raw::save_image(): Error reading sector {}: {}. Filling sector",
+                        chsn,
+                        e
+                    );
+                    output.write_all(&vec![fill.fill_byte; chsn.n_size()])?;
+                }
                 Err(e) => {
                     log::error!("Raw::save_image(): Error reading sector {}: {}", chsn, e);
                     return Err(DiskImageError::DataError);
@@ -353,3 +393,82 @@ impl RawFormat {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        file_parsers::{ParserWriteOptions, RawFillPolicy},
+        io::Cursor,
+        prelude::*,
+        test_util::sector_disk,
+        types::SectorAttributes,
+    };
+
+    const TRACK_LEN: usize = 9 * 512;
+
+    // Build a 360K disk where every byte of a sector is its sector number, and sector 3 of track
+    // 0, head 1 has no data address mark, so that it cannot be read.
+    fn test_disk() -> DiskImage {
+        let mut disk = sector_disk(StandardFormat::PcFloppy360, |chs| {
+            let attributes = SectorAttributes {
+                no_dam: chs == DiskChs::new(0, 1, 3),
+                ..Default::default()
+            };
+            (vec![chs.s(); 512], attributes)
+        });
+        disk.update_analysis();
+        disk
+    }
+
+    fn save(disk: &mut DiskImage, fill: RawFillPolicy) -> Result<Vec<u8>, DiskImageError> {
+        let mut out = Cursor::new(Vec::new());
+        DiskImageFileFormat::RawSectorImage.save_image(
+            disk,
+            &ParserWriteOptions::default().with_raw_fill(fill),
+            &mut out,
+        )?;
+        Ok(out.into_inner())
+    }
+
+    #[test]
+    fn test_fill_byte() {
+        let mut disk = test_disk();
+        disk.track_mut(DiskCh::new(1, 0))
+            .unwrap()
+            .as_metasector_track_mut()
+            .unwrap()
+            .sectors
+            .clear();
+        disk.update_analysis();
+
+        let fill = RawFillPolicy {
+            fill_byte: 0xE5,
+            fill_unreadable: true,
+        };
+        let raw = save(&mut disk, fill).unwrap();
+        assert_eq!(raw.len(), 80 * TRACK_LEN);
+        assert!(raw[2 * TRACK_LEN..3 * TRACK_LEN].iter().all(|&b| b == 0xE5));
+        assert!(raw[3 * TRACK_LEN..4 * TRACK_LEN].iter().all(|&b| b != 0xE5));
+    }
+
+    #[test]
+    fn test_fill_unreadable() {
+        let mut disk = test_disk();
+        let bad_sector = TRACK_LEN + 2 * 512;
+
+        // By default, an unreadable sector fails the write.
+        assert!(matches!(
+            save(&mut disk, RawFillPolicy::default()),
+            Err(DiskImageError::DataError)
+        ));
+
+        let fill = RawFillPolicy {
+            fill_byte: 0xF6,
+            fill_unreadable: true,
+        };
+        let raw = save(&mut disk, fill).unwrap();
+        assert!(raw[bad_sector..bad_sector + 512].iter().all(|&b| b == 0xF6));
+        assert!(raw[bad_sector - 512..bad_sector].iter().all(|&b| b == 2));
+        assert!(raw[bad_sector + 512..bad_sector + 1024].iter().all(|&b| b == 4));
+    }
+}
@@ -40,25 +40,41 @@
 
 use crate::{
     file_parsers::{bitstream_flags, FormatCaps, ParserReadOptions, ParserWriteOptions},
+    flux::synthesis::synthesize_track_flux,
     io::{ReadSeek, ReadWriteSeek},
     track::fluxstream::FluxStreamTrack,
-    types::{DiskCh, DiskDescriptor, DiskRpm, Platform, TrackDataEncoding, TrackDensity},
+    track_mapping::Sectoring,
+    types::{
+        DiskCh,
+        DiskDescriptor,
+        DiskRpm,
+        Platform,
+        SourceQuirks,
+        TrackDataEncoding,
+        TrackDataResolution,
+        TrackDensity,
+    },
     DiskImage,
     DiskImageError,
     DiskImageFileFormat,
     LoadingCallback,
+    LoadingStage,
+    LoadingStatus,
     ParserWriteCompatibility,
+    SavingStatus,
     StandardFormat,
 };
 
 use crate::types::FluxStreamTrackParams;
-use binrw::{binrw, BinRead, BinReaderExt};
+use binrw::{binrw, BinRead, BinReaderExt, BinWrite};
 use strum::IntoEnumIterator;
 
 pub const BASE_CAPTURE_RES: u32 = 25;
 pub const SCP_FLUX_TIME_BASE: u32 = 25;
 
 pub const SCP_TRACK_COUNT: usize = 168;
+pub const SCP_FILE_HEADER_LEN: usize = 0x10;
+pub const SCP_WRITE_VERSION: u8 = 0x22;
 //pub const MAX_TRACK_NUMBER: usize = SCP_TRACK_COUNT - 1;
 
 pub const SCP_FB_INDEX: u8 = 0b0000_0001;
@@ -200,16 +216,31 @@ impl ScpFormat {
         header.id == "SCP".as_bytes()
     }
 
-    pub fn can_write(_image: Option<&DiskImage>) -> ParserWriteCompatibility {
-        ParserWriteCompatibility::UnsupportedFormat
+    pub fn can_write(image: Option<&DiskImage>) -> ParserWriteCompatibility {
+        image
+            .map(|image| {
+                if image.resolution.is_empty() || image.resolution.contains(&TrackDataResolution::MetaSector) {
+                    // We can only synthesize flux for tracks that have a bitstream.
+                    ParserWriteCompatibility::Incompatible
+                }
+                else {
+                    ParserWriteCompatibility::Ok
+                }
+            })
+            .unwrap_or(ParserWriteCompatibility::Ok)
     }
 
     pub(crate) fn load_image<RWS: ReadSeek>(
         mut read_buf: RWS,
         disk_image: &mut DiskImage,
-        _opts: &ParserReadOptions,
-        _callback: Option<LoadingCallback>,
+        opts: &ParserReadOptions,
+        callback: Option<LoadingCallback>,
     ) -> Result<(), DiskImageError> {
+        if let Some(ref callback_fn) = callback {
+            // Let caller know to show a progress bar
+            callback_fn(LoadingStatus::ProgressSupport);
+        }
+
         disk_image.set_source_format(DiskImageFileFormat::SuperCardPro);
 
         let disk_image_size = read_buf.seek(std::io::SeekFrom::End(0))?;
@@ -249,6 +280,7 @@ impl ScpFormat {
                 disk_manufacturer,
                 header.disk_type & 0x0F
             );
+            disk_image.add_source_quirk(SourceQuirks::UNKNOWN_DISK_TYPE);
             //return Err(DiskImageError::UnsupportedFormat);
         }
 
@@ -305,6 +337,23 @@ impl ScpFormat {
             log::trace!("SCP image was not created by SuperCardPro device.");
         }
 
+        // The version byte is only meaningful if there is no extension footer.
+        let version = (header.flags & SCP_FB_FOOTER == 0).then(|| {
+            let (major, minor) = scp_parse_version(header.version);
+            format!("{}.{}", major, minor)
+        });
+        let variant = [
+            (SCP_FB_INDEX, "index aligned"),
+            (SCP_FB_TYPE, "normalized flux"),
+            (SCP_FB_FOOTER, "extension footer"),
+            (SCP_NON_SCP_CAPTURE, "non-SCP capture"),
+        ]
+        .iter()
+        .filter_map(|&(flag, desc)| (header.flags & flag != 0).then_some(desc))
+        .collect::<Vec<_>>()
+        .join(", ");
+        disk_image.set_source_version(version, (!variant.is_empty()).then_some(variant));
+
         log::trace!("Disk contains {} revolutions per track.", header.revolutions);
         log::trace!(
             "Starting track: {} Ending track: {}",
@@ -384,6 +433,7 @@ impl ScpFormat {
                 "Track offset table is too short. Truncating to {} entries.",
                 track_table_len
             );
+            disk_image.add_source_quirk(SourceQuirks::TRUNCATED_TRACK_TABLE);
         }
         track_offsets.push(track_offset);
 
@@ -412,11 +462,12 @@ impl ScpFormat {
         //let mut c = 0;
         //let mut h = 0;
         let mut ch = DiskCh::default();
-        let mut disk_data_rate = None;
 
         let mut ch_iter = DiskCh::new((SCP_TRACK_COUNT / 2) as u16, disk_heads).iter();
+        let mut flux_tracks = Vec::with_capacity(track_offsets.len());
 
         for (ti, offset) in track_offsets.iter().enumerate() {
+            opts.check_cancelled()?;
             ch = ch_iter.next().unwrap();
 
             // Seek to the track header.
@@ -447,6 +498,17 @@ impl ScpFormat {
                 revolutions.push(revolution);
             }
 
+            // A hard-sectored disk pulses the index sensor at every sector hole.
+            if ti == 0 {
+                let index_times: Vec<f64> = revolutions
+                    .iter()
+                    .map(|rev| (rev.index_time * SCP_FLUX_TIME_BASE) as f64 * 1e-9)
+                    .collect();
+                let sectoring = Sectoring::from_index_times(&index_times);
+                log::debug!("Detected sectoring from index times: {}", sectoring);
+                disk_image.set_track_mapping(disk_image.track_mapping().with_sectoring(sectoring));
+            }
+
             let mut flux_track = FluxStreamTrack::new();
 
             #[allow(clippy::never_loop)]
@@ -495,16 +557,25 @@ impl ScpFormat {
                 rpm: None,
             };
 
-            let new_track = disk_image.add_track_fluxstream(flux_track, &params)?;
+            flux_tracks.push((flux_track, params));
 
-            let ti = new_track.info();
-
-            if disk_data_rate.is_none() {
-                log::trace!("Setting disk data rate to {}", ti.data_rate);
-                disk_data_rate = Some(ti.data_rate);
+            if let Some(ref callback_fn) = callback {
+                callback_fn(LoadingStatus::Track {
+                    ch,
+                    index: ti,
+                    total: track_offsets.len(),
+                });
+                callback_fn(LoadingStatus::Progress((ti + 1) as f64 / track_offsets.len() as f64));
             }
         }
 
+        // Decode all the tracks at once, so that they may be decoded in parallel.
+        if let Some(ref callback_fn) = callback {
+            callback_fn(LoadingStatus::Stage(LoadingStage::Decoding));
+        }
+        let track_infos = disk_image.add_tracks_fluxstream(flux_tracks)?;
+        let disk_data_rate = track_infos.first().map(|ti| ti.data_rate);
+
         log::trace!("Read {} valid track offsets. Final track {}", track_offsets.len(), ch);
 
         if disk_data_rate.is_none() {
@@ -549,11 +620,149 @@ impl ScpFormat {
         flux_f64
     }
 
+    /// Convert flux deltas in seconds to SCP flux values at the given capture resolution.
+    /// Rounding error is carried forward so that revolution timing does not drift.
+    fn encode_flux_data(fluxes: &[f64], capture_resolution: u32) -> Vec<u16> {
+        let mut data = Vec::with_capacity(fluxes.len());
+        let resolution_secs = capture_resolution as f64 * 1e-9;
+        let mut remainder = 0.0;
+
+        for flux in fluxes {
+            let exact = flux / resolution_secs + remainder;
+            let mut ticks = (exact.round() as u64).max(1);
+            remainder = exact - ticks as f64;
+
+            // A flux time of 0 indicates rollover.
+            while ticks > u64::from(u16::MAX) {
+                data.push(0);
+                ticks -= u64::from(u16::MAX);
+            }
+            data.push(ticks as u16);
+        }
+        data
+    }
+
+    /// Save a bitstream or fluxstream image as SCP. Flux is synthesized from the bitstream of each
+    /// track (for fluxstream tracks, the resolved bitstream) according to the
+    /// [FluxSynthesisOptions](crate::flux::synthesis::FluxSynthesisOptions) in `opts`.
     pub fn save_image<RWS: ReadWriteSeek>(
-        _image: &DiskImage,
-        _opts: &ParserWriteOptions,
-        _output: &mut RWS,
+        image: &DiskImage,
+        opts: &ParserWriteOptions,
+        output: &mut RWS,
     ) -> Result<(), DiskImageError> {
-        Err(DiskImageError::UnsupportedFormat)
+        if Self::can_write(Some(image)) != ParserWriteCompatibility::Ok {
+            log::error!("save_image(): Unsupported image resolution.");
+            return Err(DiskImageError::UnsupportedFormat);
+        }
+
+        let heads = image.heads();
+        if heads == 0 || heads > 2 {
+            log::error!("save_image(): Unsupported number of heads: {}", heads);
+            return Err(DiskImageError::UnsupportedFormat);
+        }
+
+        let tracks: Vec<_> = image.iter_tracks().collect();
+        if tracks.is_empty() || tracks.len() > SCP_TRACK_COUNT {
+            log::error!("save_image(): Unsupported number of tracks: {}", tracks.len());
+            return Err(DiskImageError::UnsupportedFormat);
+        }
+
+        let flux_opts = opts.flux_synthesis();
+        let revolutions = flux_opts.revolutions.clamp(1, u8::MAX as usize);
+        let flux_opts = flux_opts.with_revolutions(revolutions);
+        log::trace!(
+            "save_image(): Saving SCP image with {} tracks, {} revolutions per track...",
+            tracks.len(),
+            revolutions
+        );
+
+        // Build everything after the file header first, so that we can calculate the checksum.
+        let table_len = SCP_TRACK_COUNT * 4;
+        let mut body = vec![0u8; table_len];
+        let mut rpm_300 = true;
+
+        for (ti, (ch, track)) in tracks.iter().enumerate() {
+            if let Some(callback_fn) = opts.callback() {
+                callback_fn(SavingStatus::Track {
+                    ch:    *ch,
+                    index: ti,
+                    total: tracks.len(),
+                });
+            }
+
+            rpm_300 &= !matches!(track.info().rpm, Some(DiskRpm::Rpm360(_)));
+
+            // Seed each track differently, so that jitter and weak bits don't repeat across tracks.
+            let track_opts = flux_opts.with_seed(flux_opts.seed.wrapping_add(ti as u64));
+            let synthesized = synthesize_track_flux(*track, &track_opts)?;
+
+            let track_offset = SCP_FILE_HEADER_LEN + body.len();
+            body[ti * 4..ti * 4 + 4].copy_from_slice(&(track_offset as u32).to_le_bytes());
+
+            let mut track_buf = binrw::io::Cursor::new(Vec::new());
+            ScpTrackHeader {
+                id: *b"TRK",
+                track_number: ti as u8,
+            }
+            .write(&mut track_buf)?;
+
+            let mut data_offset = 4 + synthesized.len() * 12;
+            let mut rev_data = Vec::with_capacity(synthesized.len());
+            for rev in &synthesized {
+                let data = Self::encode_flux_data(&rev.flux_deltas, BASE_CAPTURE_RES);
+                ScpTrackRevolution {
+                    index_time: (rev.index_time / (SCP_FLUX_TIME_BASE as f64 * 1e-9)).round() as u32,
+                    length: data.len() as u32,
+                    data_offset: data_offset as u32,
+                }
+                .write(&mut track_buf)?;
+                data_offset += data.len() * 2;
+                rev_data.push(data);
+            }
+
+            let mut track_buf = track_buf.into_inner();
+            for data in rev_data {
+                for d in data {
+                    track_buf.extend_from_slice(&d.to_be_bytes());
+                }
+            }
+            body.extend_from_slice(&track_buf);
+        }
+
+        let disk_type = match image.standard_format.or_else(|| image.closest_format(true)) {
+            Some(StandardFormat::PcFloppy360) => ScpDiskManufacturer::Pc as u8,
+            Some(StandardFormat::PcFloppy720) => ScpDiskManufacturer::Pc as u8 | 0x01,
+            Some(StandardFormat::PcFloppy1200) => ScpDiskManufacturer::Pc as u8 | 0x02,
+            Some(StandardFormat::PcFloppy1440) => ScpDiskManufacturer::Pc as u8 | 0x03,
+            _ => ScpDiskManufacturer::Other as u8,
+        };
+
+        let mut flags = SCP_FB_INDEX | SCP_NON_SCP_CAPTURE;
+        if rpm_300 {
+            flags |= SCP_FB_RPM;
+        }
+        if image.descriptor.write_protect != Some(true) {
+            flags |= SCP_FB_READONLY;
+        }
+
+        let header = ScpFileHeader {
+            id: *b"SCP",
+            version: SCP_WRITE_VERSION,
+            disk_type,
+            revolutions: revolutions as u8,
+            start_track: 0,
+            end_track: (tracks.len() - 1) as u8,
+            flags,
+            bit_cell_width: 0,
+            heads: if heads == 2 { 0 } else { 1 },
+            resolution: 0,
+            checksum: body.iter().fold(0u32, |sum, b| sum.wrapping_add(*b as u32)),
+        };
+
+        output.seek(std::io::SeekFrom::Start(0))?;
+        header.write(output)?;
+        output.write_all(&body)?;
+
+        Ok(())
     }
 }
@@ -58,9 +58,12 @@ use crate::{
         MetaSectorTrackParams,
         Platform,
         SectorAttributes,
+        SourceQuirks,
         TrackDataEncoding,
         TrackDataRate,
         TrackDensity,
+        METADATA_KEY_COMMENT,
+        METADATA_KEY_DUMP_DATE,
     },
     DiskImage,
     DiskImageError,
@@ -263,6 +266,13 @@ impl Td0Format {
         let has_comment_block = file_header.stepping & 0x80 != 0;
 
         let disk_data_rate = td0_data_rate(file_header.data_rate);
+        if file_header.data_rate & 0x03 > 2 {
+            disk_image.add_source_quirk(SourceQuirks::DEFAULT_DATA_RATE);
+        }
+        disk_image.set_source_version(
+            Some(format!("{}.{}", major_version, minor_version)),
+            compressed.then(|| "advanced compression".to_string()),
+        );
 
         log::trace!(
             "Detected Teledisk Image, version {}.{}, compressed: {} has_comment_block: {}",
@@ -330,6 +340,7 @@ impl Td0Format {
 
             if comment_header.crc != calculated_crc {
                 log::warn!("Bad Comment block header CRC");
+                disk_image.add_source_quirk(SourceQuirks::BAD_HEADER_CRC);
                 //return Err(DiskImageError::ImageCorruptError("Bad Comment CRC".to_string()));
             }
 
@@ -352,6 +363,22 @@ impl Td0Format {
 
             let comment = String::from_utf8(comment_data_block).map_err(|_| DiskImageError::FormatParseError)?;
             log::trace!("Comment block data: {}", comment);
+            let comment = comment.trim_end();
+            if !comment.is_empty() {
+                disk_image.set_metadata_key(METADATA_KEY_COMMENT, comment);
+            }
+            // The comment timestamp records when the image was created. The year is stored as an
+            // offset from 1900, and the month is zero-based.
+            let dump_date = format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                1900 + comment_header.year as u16,
+                comment_header.month + 1,
+                comment_header.day,
+                comment_header.hour,
+                comment_header.minute,
+                comment_header.second
+            );
+            disk_image.set_metadata_key(METADATA_KEY_DUMP_DATE, &dump_date);
         }
 
         // Read tracks in
@@ -477,6 +504,7 @@ impl Td0Format {
                         },
                         alternate: false,
                         bit_index: None,
+                        ..Default::default()
                     };
 
                     new_track.add_sector(&params)?;
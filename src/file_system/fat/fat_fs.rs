@@ -25,9 +25,6 @@
     --------------------------------------------------------------------------
 */
 
-#[cfg(feature = "zip")]
-use crate::io::Write;
-
 #[cfg(feature = "tar")]
 use crate::io::Cursor;
 
@@ -35,18 +32,25 @@ use crate::{
     disk_lock::{DiskLock, LockContext, NonTrackingDiskLock},
     file_system::{
         file_tree::{FileEntry, FileEntryType, FileNameType, FileTreeNode},
+        FileExtent,
+        FileSystem,
         FileSystemArchive,
         FileSystemError,
+        FileSystemStats,
     },
-    io::{Read, Seek},
+    io::{Read, Seek, Write},
     sector_view::StandardSectorView,
+    DiskChsn,
     DiskImage,
     StandardFormat,
 };
-use fluxfox_fat::{Dir, FileSystem, FsOptions, OemCpConverter, ReadWriteSeek, StdIoWrapper, TimeProvider};
+use fluxfox_fat::{Dir, DirEntry, FsOptions, OemCpConverter, ReadWriteSeek, StdIoWrapper, TimeProvider};
 
 pub struct FatFileSystem {
-    fat: Option<FileSystem<StdIoWrapper<StandardSectorView>>>,
+    fat:    Option<fluxfox_fat::FileSystem<StdIoWrapper<StandardSectorView>>>,
+    // The format of the sector view the file system was mounted on, including any geometry
+    // remap. Used to map volume offsets to sectors.
+    format: StandardFormat,
 }
 
 impl FatFileSystem {
@@ -55,7 +59,9 @@ impl FatFileSystem {
     /// # Arguments
     /// - `disk_lock`: A reference-counted `RwLock` wrapping a `DiskImage` object.
     /// - `format`: An optional `StandardFormat` to use when mounting the filesystem. This can
-    ///             be used to override auto-detection of the disk format.
+    ///             be used to override auto-detection of the disk format. If not provided, the
+    ///             image's standard format is used, so that a geometry remapped by
+    ///             [DiskImage::check_bpb_geometry] is honored.
     ///
     ///
     pub fn mount<L, C>(disk_lock: L, lock_context: C, format: Option<StandardFormat>) -> Result<Self, FileSystemError>
@@ -68,66 +74,41 @@ impl FatFileSystem {
             disk_lock.strong_count()
         );
 
-        // If a format was not provided, attempt to auto-detect the format
+        // If a format was not provided, use the image's standard format, which reflects any
+        // geometry remap, and otherwise attempt to auto-detect the format.
         let format = match format {
             Some(f) => Some(f),
-            None => disk_lock.read(lock_context).unwrap().closest_format(true),
+            None => {
+                let disk = disk_lock.read(lock_context).unwrap();
+                disk.standard_format().or_else(|| disk.closest_format(true))
+            }
         };
 
-        if format.is_none() {
+        let Some(format) = format
+        else {
             // Auto-detection failed. We can't mount the filesystem.
             return Err(FileSystemError::MountError(
                 "Could not auto-detect disk format".to_string(),
             ));
-        }
+        };
 
         // Move the arc into the view without cloning.
-        let mut view = StandardSectorView::new(disk_lock, format.unwrap())
-            .map_err(|e| FileSystemError::MountError(e.to_string()))?;
+        let mut view =
+            StandardSectorView::new(disk_lock, format).map_err(|e| FileSystemError::MountError(e.to_string()))?;
+        // Keep the view's format, so that file extents resolve to the same sectors the view reads.
+        let format = view.format();
 
         // Reset the cursor to the beginning of the view or the mount will fail
         view.seek(std::io::SeekFrom::Start(0))
             .map_err(|e| FileSystemError::MountError(e.to_string()))?;
 
         // Mount the filesystem
-        let fat = match FileSystem::new(view, FsOptions::new()) {
+        let fat = match fluxfox_fat::FileSystem::new(view, FsOptions::new()) {
             Ok(fs) => fs,
             Err(e) => return Err(FileSystemError::MountError(e.to_string())),
         };
 
-        Ok(Self { fat: Some(fat) })
-    }
-
-    pub fn unmount(&mut self) {
-        self.fat = None;
-    }
-
-    pub fn read_file(&self, path: &str) -> Result<Vec<u8>, FileSystemError> {
-        if let Some(fat) = &self.fat {
-            let mut file = fat
-                .root_dir()
-                .open_file(path)
-                .map_err(|e| FileSystemError::ReadError(e.to_string()))?;
-            let mut data = Vec::new();
-
-            match file.read_to_end(&mut data) {
-                Ok(_) => Ok(data),
-                Err(e) => Err(FileSystemError::ReadError(e.to_string())),
-            }
-        }
-        else {
-            Err(FileSystemError::MountError("Filesystem not mounted".to_string()))
-        }
-    }
-
-    pub fn list_all_files(&self) -> Vec<String> {
-        let mut files = Vec::new();
-        if let Some(fat) = &self.fat {
-            let dir = fat.root_dir();
-
-            Self::list_files_recursive(&dir, &mut files);
-        }
-        files
+        Ok(Self { fat: Some(fat), format })
     }
 
     pub fn list_files_recursive<IO: ReadWriteSeek, TP: TimeProvider, OCC: OemCpConverter>(
@@ -150,19 +131,29 @@ impl FatFileSystem {
         }
     }
 
-    pub fn build_file_tree_from_root(&self) -> Option<FileTreeNode> {
-        if let Some(fat) = &self.fat {
-            let root_dir = fat.root_dir();
-            let mut path_stack = Vec::new();
-            Some(Self::build_file_tree_recursive(None, &root_dir, &mut path_stack))
-        }
-        else {
-            None
+    /// Create a [FileEntry] from a FAT directory entry.
+    fn file_entry<IO: ReadWriteSeek, TP: TimeProvider, OCC: OemCpConverter>(
+        entry: &DirEntry<IO, TP, OCC>,
+        path: String,
+    ) -> FileEntry {
+        FileEntry {
+            e_type: if entry.is_dir() {
+                FileEntryType::Directory
+            }
+            else {
+                FileEntryType::File
+            },
+            short_name: entry.short_file_name(),
+            long_name: Some(entry.file_name()),
+            size: if entry.is_dir() { 0 } else { entry.len() },
+            path,
+            created: None, // Created date was not implemented by DOS. Added in NT + later
+            modified: Some(entry.modified().into()),
         }
     }
 
     pub fn build_file_tree_recursive<IO: ReadWriteSeek, TP: TimeProvider, OCC: OemCpConverter>(
-        dir_entry: Option<&fluxfox_fat::DirEntry<IO, TP, OCC>>,
+        dir_entry: Option<&DirEntry<IO, TP, OCC>>,
         dir: &Dir<IO, TP, OCC>,
         path_stack: &mut Vec<String>,
     ) -> FileTreeNode {
@@ -173,7 +164,6 @@ impl FatFileSystem {
 
         for entry in dir.iter().flatten() {
             let entry_name = entry.short_file_name();
-            let entry_size = entry.len();
             let full_path = format!("{}/{}", path_stack.join("/"), entry_name);
 
             if entry.is_dir() {
@@ -191,15 +181,7 @@ impl FatFileSystem {
                 //     entry_name,
                 //     FsDateTime::from(entry.modified())
                 // );
-                children.push(FileTreeNode::File(FileEntry {
-                    e_type: FileEntryType::File,
-                    short_name: entry_name,
-                    long_name: Some(entry.file_name()),
-                    size: entry_size,
-                    path: full_path,
-                    created: None, // Created date was not implemented by DOS. Added in NT + later
-                    modified: Some(entry.modified().into()),
-                }));
+                children.push(FileTreeNode::File(Self::file_entry(&entry, full_path)));
             }
         }
 
@@ -235,7 +217,7 @@ impl FatFileSystem {
             root_node = Self::build_file_tree_recursive(None, &root_dir, &mut Vec::new());
         }
         else {
-            return Err(FileSystemError::MountError("Filesystem not mounted".to_string()));
+            return Err(FileSystemError::NotMountedError);
         }
         self.node_as_archive(&root_node, true, FileNameType::Short, archive_type)
     }
@@ -253,7 +235,7 @@ impl FatFileSystem {
             root_node = Self::build_file_tree_recursive(None, &root_dir, &mut Vec::new());
         }
         else {
-            return Err(FileSystemError::MountError("Filesystem not mounted".to_string()));
+            return Err(FileSystemError::NotMountedError);
         }
 
         // Resolve the path to the node
@@ -357,3 +339,230 @@ impl FatFileSystem {
         Ok(tar_data)
     }
 }
+
+impl FileSystem for FatFileSystem {
+    fn fs_type(&self) -> String {
+        match &self.fat {
+            Some(fat) => format!("{:?}", fat.fat_type()).to_uppercase(),
+            None => "FAT".to_string(),
+        }
+    }
+
+    fn unmount(&mut self) {
+        self.fat = None;
+    }
+
+    fn read_file(&self, path: &str) -> Result<Vec<u8>, FileSystemError> {
+        if let Some(fat) = &self.fat {
+            let mut file = fat
+                .root_dir()
+                .open_file(path)
+                .map_err(|e| FileSystemError::ReadError(e.to_string()))?;
+            let mut data = Vec::new();
+
+            match file.read_to_end(&mut data) {
+                Ok(_) => Ok(data),
+                Err(e) => Err(FileSystemError::ReadError(e.to_string())),
+            }
+        }
+        else {
+            Err(FileSystemError::NotMountedError)
+        }
+    }
+
+    fn list_all_files(&self) -> Vec<String> {
+        let mut files = Vec::new();
+        if let Some(fat) = &self.fat {
+            let dir = fat.root_dir();
+
+            Self::list_files_recursive(&dir, &mut files);
+        }
+        files
+    }
+
+    fn build_file_tree_from_root(&self) -> Option<FileTreeNode> {
+        if let Some(fat) = &self.fat {
+            let root_dir = fat.root_dir();
+            let mut path_stack = Vec::new();
+            Some(Self::build_file_tree_recursive(None, &root_dir, &mut path_stack))
+        }
+        else {
+            None
+        }
+    }
+
+    fn write_file(&mut self, path: &str, data: &[u8]) -> Result<(), FileSystemError> {
+        let fat = self.fat.as_ref().ok_or(FileSystemError::NotMountedError)?;
+        let mut file = fat
+            .root_dir()
+            .create_file(path)
+            .map_err(|e| FileSystemError::WriteError(e.to_string()))?;
+
+        // Discard any existing contents of the file before writing.
+        file.truncate()
+            .map_err(|e| FileSystemError::WriteError(e.to_string()))?;
+        file.write_all(data)
+            .map_err(|e| FileSystemError::WriteError(e.to_string()))?;
+        file.flush().map_err(|e| FileSystemError::WriteError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn metadata(&self, path: &str) -> Result<FileEntry, FileSystemError> {
+        let fat = self.fat.as_ref().ok_or(FileSystemError::NotMountedError)?;
+
+        let trimmed = path.trim_matches('/');
+        if trimmed.is_empty() {
+            // The root directory has no directory entry of its own.
+            return Ok(FileEntry::root());
+        }
+
+        let (parent, name) = trimmed.rsplit_once('/').unwrap_or(("", trimmed));
+        let dir = if parent.is_empty() {
+            fat.root_dir()
+        }
+        else {
+            fat.root_dir()
+                .open_dir(parent)
+                .map_err(|_| FileSystemError::PathNotFound(path.to_string()))?
+        };
+
+        dir.iter()
+            .flatten()
+            .find(|entry| {
+                entry.short_file_name().eq_ignore_ascii_case(name) || entry.file_name().eq_ignore_ascii_case(name)
+            })
+            .map(|entry| Self::file_entry(&entry, format!("/{}", trimmed)))
+            .ok_or_else(|| FileSystemError::PathNotFound(path.to_string()))
+    }
+
+    fn stats(&self) -> Result<FileSystemStats, FileSystemError> {
+        let fat = self.fat.as_ref().ok_or(FileSystemError::NotMountedError)?;
+        let stats = fat.stats().map_err(|e| FileSystemError::IoError(e.to_string()))?;
+        let cluster_size = stats.cluster_size();
+        Ok(FileSystemStats {
+            total_bytes: stats.total_clusters() as u64 * cluster_size as u64,
+            free_bytes: stats.free_clusters() as u64 * cluster_size as u64,
+            cluster_size,
+        })
+    }
+
+    fn file_extents(&self, path: &str) -> Result<Vec<FileExtent>, FileSystemError> {
+        let fat = self.fat.as_ref().ok_or(FileSystemError::NotMountedError)?;
+        let mut file = fat
+            .root_dir()
+            .open_file(path.trim_start_matches('/'))
+            .map_err(|_| FileSystemError::PathNotFound(path.to_string()))?;
+
+        let mut extents: Vec<FileExtent> = Vec::new();
+        for extent in file.extents() {
+            let extent = extent.map_err(|e| FileSystemError::ReadError(e.to_string()))?;
+            match extents.last_mut() {
+                // Merge clusters that follow on from the previous one.
+                Some(last) if last.offset + last.len == extent.offset => last.len += extent.size as u64,
+                _ => extents.push(FileExtent {
+                    offset: extent.offset,
+                    len: extent.size as u64,
+                    sectors: Vec::new(),
+                }),
+            }
+        }
+
+        let layout = self.format.layout();
+        let sector_size = layout.size() as u64;
+        for extent in extents.iter_mut() {
+            let first_lba = extent.offset / sector_size;
+            let end_lba = (extent.offset + extent.len).div_ceil(sector_size);
+            extent.sectors = (first_lba..end_lba)
+                .filter_map(|lba| DiskChsn::from_lba(lba as usize, &layout))
+                .collect();
+        }
+        Ok(extents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        boot_sector::BootSector,
+        disk_lock::NullContext,
+        diskimage::DEFAULT_BOOT_SECTOR,
+        io::{Cursor, SeekFrom},
+        test_util::sector_disk,
+    };
+
+    /// Build a 360K disk holding an empty FAT12 volume.
+    fn fat_disk() -> FatFileSystem {
+        let format = StandardFormat::PcFloppy360;
+        let mut disk = sector_disk(format, |_| (vec![0; format.sector_size()], Default::default()));
+
+        let mut boot_sector = BootSector::new(&mut Cursor::new(DEFAULT_BOOT_SECTOR)).unwrap();
+        boot_sector.update_bpb_from_format(format).unwrap();
+        disk.set_boot_sector(boot_sector).unwrap();
+
+        let lock = NonTrackingDiskLock::new(disk.into_arc());
+        FatFileSystem::mount(lock, NullContext::default(), Some(format)).unwrap()
+    }
+
+    #[test]
+    fn test_file_extents() {
+        let mut fs = fat_disk();
+
+        // A 360K volume has two-sector clusters, with the data area starting at sector 12.
+        // A.TXT takes cluster 2 and B.TXT cluster 3.
+        fs.write_file("A.TXT", &[b'A'; 1024]).unwrap();
+        fs.write_file("B.TXT", &[b'B'; 1024]).unwrap();
+
+        // Growing A.TXT past B.TXT fragments it over clusters 4 and 5.
+        {
+            let fat = fs.fat.as_ref().unwrap();
+            let mut file = fat.root_dir().open_file("A.TXT").unwrap();
+            file.seek(SeekFrom::End(0)).unwrap();
+            file.write_all(&[b'A'; 2048]).unwrap();
+            file.flush().unwrap();
+        }
+
+        let chsn = |c, h, s| DiskChsn::new(c, h, s, 2);
+
+        let extents = fs.file_extents("/B.TXT").unwrap();
+        assert_eq!(
+            extents,
+            vec![FileExtent {
+                offset: 14 * 512,
+                len: 1024,
+                sectors: vec![chsn(0, 1, 6), chsn(0, 1, 7)],
+            }]
+        );
+
+        // The two new clusters merge into a single extent, which crosses onto the next track.
+        let extents = fs.file_extents("/A.TXT").unwrap();
+        assert_eq!(
+            extents,
+            vec![
+                FileExtent {
+                    offset: 12 * 512,
+                    len: 1024,
+                    sectors: vec![chsn(0, 1, 4), chsn(0, 1, 5)],
+                },
+                FileExtent {
+                    offset: 16 * 512,
+                    len: 2048,
+                    sectors: vec![chsn(0, 1, 8), chsn(0, 1, 9), chsn(1, 0, 1), chsn(1, 0, 2)],
+                },
+            ]
+        );
+        assert_eq!(fs.read_file("A.TXT").unwrap(), vec![b'A'; 3072]);
+
+        assert!(matches!(
+            fs.file_extents("/C.TXT"),
+            Err(FileSystemError::PathNotFound(_))
+        ));
+
+        fs.unmount();
+        assert!(matches!(fs.read_file("/B.TXT"), Err(FileSystemError::NotMountedError)));
+        assert!(matches!(
+            fs.file_extents("/B.TXT"),
+            Err(FileSystemError::NotMountedError)
+        ));
+    }
+}
@@ -64,6 +64,19 @@ impl Display for FileEntry {
 }
 
 impl FileEntry {
+    /// Returns a [FileEntry] representing the root directory of a file system.
+    pub(crate) fn root() -> Self {
+        FileEntry {
+            e_type: FileEntryType::Directory,
+            short_name: "/".to_string(),
+            long_name: Some("/".to_string()),
+            path: "/".to_string(),
+            size: 0,
+            created: None,
+            modified: None,
+        }
+    }
+
     /// Returns the name of the file, of the requested `FileNameType`.
     /// # Arguments
     /// * `name_type` - The type of name to return.
@@ -147,15 +160,7 @@ pub enum FileTreeNode {
 impl Default for FileTreeNode {
     fn default() -> Self {
         FileTreeNode::Directory {
-            dfe: FileEntry {
-                e_type: FileEntryType::Directory,
-                short_name: "/".to_string(),
-                long_name: Some("/".to_string()),
-                path: "/".to_string(),
-                size: 0,
-                created: None,
-                modified: None,
-            },
+            dfe: FileEntry::root(),
             children: Vec::new(),
         }
     }
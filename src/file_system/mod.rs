@@ -28,6 +28,7 @@
 #[cfg(feature = "serde")]
 use serde;
 
+use crate::DiskChsn;
 use std::fmt::{self, Display, Formatter};
 use thiserror::Error;
 
@@ -64,6 +65,83 @@ impl FileSystemArchive {
     }
 }
 
+/// Capacity information for a mounted [FileSystem].
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileSystemStats {
+    /// The total capacity of the file system's data area, in bytes.
+    pub total_bytes:  u64,
+    /// The number of unallocated bytes in the file system's data area.
+    pub free_bytes:   u64,
+    /// The allocation unit size, in bytes.
+    pub cluster_size: u32,
+}
+
+impl FileSystemStats {
+    /// Returns the number of allocated bytes in the file system's data area.
+    pub fn used_bytes(&self) -> u64 {
+        self.total_bytes.saturating_sub(self.free_bytes)
+    }
+}
+
+/// A contiguous run of sectors holding part of a file, as returned by
+/// [FileSystem::file_extents]. A file stored in more than one extent is fragmented.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileExtent {
+    /// The byte offset of the extent from the start of the volume.
+    pub offset: u64,
+    /// The length of the extent in bytes. This is a whole number of allocation units, so may
+    /// extend past the end of the file.
+    pub len: u64,
+    /// The IDs of the sectors holding the extent, in order.
+    pub sectors: Vec<DiskChsn>,
+}
+
+/// A trait providing a common interface to the file systems fluxfox can mount from a [DiskImage].
+/// This allows front ends to present a file browser regardless of the underlying file system.
+///
+/// Paths are '/'-separated and relative to the root directory. A leading '/' is optional.
+///
+/// [DiskImage]: crate::DiskImage
+pub trait FileSystem {
+    /// Return a short description of the mounted file system type, such as "FAT12".
+    fn fs_type(&self) -> String;
+
+    /// Unmount the file system. Further operations will return [FileSystemError::NotMountedError].
+    fn unmount(&mut self);
+
+    /// Return a flat list of the names of all files in the file system.
+    fn list_all_files(&self) -> Vec<String>;
+
+    /// Build a tree of [FileTreeNode]s representing the entire file system, starting from the root
+    /// directory. Returns `None` if the file system is not mounted.
+    fn build_file_tree_from_root(&self) -> Option<FileTreeNode>;
+
+    /// Read the entire contents of the file at `path`.
+    fn read_file(&self, path: &str) -> Result<Vec<u8>, FileSystemError>;
+
+    /// Write `data` to the file at `path`, creating the file if it does not exist and replacing
+    /// its contents if it does. The parent directory must already exist.
+    fn write_file(&mut self, path: &str, data: &[u8]) -> Result<(), FileSystemError>;
+
+    /// Return the [FileEntry] describing the file or directory at `path`.
+    fn metadata(&self, path: &str) -> Result<FileEntry, FileSystemError>;
+
+    /// Return the capacity and free space of the file system.
+    fn stats(&self) -> Result<FileSystemStats, FileSystemError>;
+
+    /// Return the [FileExtent]s holding the contents of the file at `path`, in file order.
+    /// Adjacent allocation units are merged into a single extent, so the number of extents
+    /// returned is the number of fragments the file is stored in. An empty file has no extents.
+    ///
+    /// The default implementation returns [FileSystemError::Unsupported], for file systems that
+    /// cannot locate the sectors of a file.
+    fn file_extents(&self, _path: &str) -> Result<Vec<FileExtent>, FileSystemError> {
+        Err(FileSystemError::Unsupported("file extents".to_string()))
+    }
+}
+
 #[derive(Clone, Debug, Error)]
 pub enum FileSystemError {
     #[error("An IO error occurred reading or writing the disk image: {0}")]
@@ -80,8 +158,12 @@ pub enum FileSystemError {
     ArchiveError(String),
     #[error("The requested path was not found: {0}")]
     PathNotFound(String),
+    #[error("An error occurred writing a file: {0}")]
+    WriteError(String),
     #[error("Feature {0} option required but not compiled.")]
     FeatureError(String),
+    #[error("The file system does not support {0}")]
+    Unsupported(String),
 }
 
 impl From<crate::io::Error> for FileSystemError {
@@ -66,6 +66,33 @@ pub struct FluxRevolutionStats {
     pub last_ft: f64,
 }
 
+/// The measured centers of the short, medium and long MFM transition bands of a revolution,
+/// corresponding to flux transitions of 2, 3 and 4 bitcells, in seconds.
+#[derive(Copy, Clone, Debug)]
+pub struct FluxBandCenters {
+    pub short:  f64,
+    pub medium: f64,
+    pub long:   f64,
+}
+
+impl FluxBandCenters {
+    /// Return the bitcell period that best fits the measured band centers.
+    pub fn fit_period(&self) -> f64 {
+        // Least-squares fit of period * k to each band center, for k = 2, 3, 4.
+        (2.0 * self.short + 3.0 * self.medium + 4.0 * self.long) / 29.0
+    }
+
+    /// Return the largest deviation of a band center from its ideal position, as a fraction of
+    /// the fitted bitcell period.
+    pub fn max_skew(&self) -> f64 {
+        let period = self.fit_period();
+        [(self.short, 2.0), (self.medium, 3.0), (self.long, 4.0)]
+            .iter()
+            .map(|(center, k)| ((center - period * k) / period).abs())
+            .fold(0.0, f64::max)
+    }
+}
+
 /// A struct representing one revolution of a fluxstream track.
 #[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -314,8 +341,153 @@ impl FluxRevolution {
         decode_result.flux_stats
     }
 
+    /// Measure the centers of the 2, 3 and 4 bitcell MFM transition bands, given an estimated
+    /// bitcell period. Returns None if any band is missing or the bands are not well separated.
+    pub fn mfm_band_centers(&self, base_clock: f64) -> Option<FluxBandCenters> {
+        if base_clock <= 0.0 || self.flux_deltas.is_empty() {
+            return None;
+        }
+
+        // Start with the ideal band centers, then refine each by taking the mean of the deltas
+        // closest to it. Two passes are enough to follow bands shifted by up to half a bitcell.
+        let mut centers = [2.0 * base_clock, 3.0 * base_clock, 4.0 * base_clock];
+        for _ in 0..2 {
+            let lower = centers[0] - (centers[1] - centers[0]) / 2.0;
+            let upper = centers[2] + (centers[2] - centers[1]) / 2.0;
+            let mid_lo = (centers[0] + centers[1]) / 2.0;
+            let mid_hi = (centers[1] + centers[2]) / 2.0;
+
+            let mut sums = [0.0; 3];
+            let mut counts = [0usize; 3];
+            for &delta in self.flux_deltas.iter().filter(|&&d| d >= lower && d < upper) {
+                let band = if delta < mid_lo {
+                    0
+                }
+                else if delta < mid_hi {
+                    1
+                }
+                else {
+                    2
+                };
+                sums[band] += delta;
+                counts[band] += 1;
+            }
+
+            // Require each band to hold at least 1% of the revolution's transitions.
+            let min_count = (self.flux_deltas.len() / 100).max(1);
+            if counts.iter().any(|&c| c < min_count) {
+                return None;
+            }
+            for band in 0..3 {
+                centers[band] = sums[band] / counts[band] as f64;
+            }
+        }
+
+        if centers[0] >= centers[1] || centers[1] >= centers[2] {
+            return None;
+        }
+
+        Some(FluxBandCenters {
+            short:  centers[0],
+            medium: centers[1],
+            long:   centers[2],
+        })
+    }
+
+    /// Normalize the flux deltas of a double-density MFM revolution whose transition bands are
+    /// shifted from their ideal 2:3:4 ratio. This is typical of double-density media read in a
+    /// high-density drive, where weaker signals and peak shift produce narrow short pulses.
+    ///
+    /// Each delta is remapped piecewise-linearly so that the measured band centers land on
+    /// their ideal positions. Deltas are left untouched if the bands are within `tolerance`
+    /// of a bitcell period of their ideal positions, or if the bands could not be measured.
+    /// Returns the measured band centers if the revolution was normalized.
+    ///
+    /// This replaces the captured flux deltas. To decode with normalized bands while keeping the
+    /// original flux, use [FluxRevolution::normalized_deltas].
+    pub fn normalize_bands(&mut self, base_clock: f64, tolerance: f64) -> Option<FluxBandCenters> {
+        let (deltas, centers) = self.normalized_deltas(base_clock, tolerance)?;
+        self.flux_deltas = deltas;
+        Some(centers)
+    }
+
+    /// Return a copy of the revolution's flux deltas with skewed transition bands normalized, as
+    /// described by [FluxRevolution::normalize_bands], along with the measured band centers.
+    /// Returns `None` if the bands did not need normalizing.
+    pub fn normalized_deltas(&self, base_clock: f64, tolerance: f64) -> Option<(Vec<f64>, FluxBandCenters)> {
+        let centers = self.mfm_band_centers(base_clock)?;
+        if centers.max_skew() < tolerance {
+            return None;
+        }
+
+        let period = centers.fit_period();
+        let points = [
+            (0.0, 0.0),
+            (centers.short, 2.0 * period),
+            (centers.medium, 3.0 * period),
+            (centers.long, 4.0 * period),
+        ];
+
+        let deltas = self
+            .flux_deltas
+            .iter()
+            .map(|&delta| {
+                // Find the segment containing the delta, extrapolating past the long band.
+                let seg = points[1..].iter().position(|p| delta < p.0).unwrap_or(2);
+                let (x0, y0) = points[seg];
+                let (x1, y1) = points[seg + 1];
+                y0 + (delta - x0) * (y1 - y0) / (x1 - x0)
+            })
+            .collect();
+
+        log::debug!(
+            "FluxRevolution::normalized_deltas(): Normalized bands {:?} to period {:.4}us",
+            centers,
+            period * 1e6
+        );
+        Some((deltas, centers))
+    }
+
     /// Create an iterator over the flux delta times in a revolution.
     pub fn delta_iter(&self) -> std::slice::Iter<f64> {
         self.flux_deltas.iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skewed_deltas(period: f64, bands: [f64; 3]) -> Vec<f64> {
+        // A repeating 2T, 3T, 4T, 2T pattern with a little deterministic jitter.
+        (0..4000)
+            .map(|i| {
+                let band = [0, 1, 2, 0][i % 4];
+                let jitter = ((i % 7) as f64 - 3.0) * 0.01 * period;
+                bands[band] * period + jitter
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize_bands() {
+        let period = 2e-6;
+
+        // Short pulses pulled in and long pulses pushed out, as seen with DD media in an HD drive.
+        let mut rev = FluxRevolution::from_f64(DiskCh::new(0, 0), &skewed_deltas(period, [1.7, 3.0, 4.2]), 0.2);
+        let before = rev.mfm_band_centers(period).unwrap();
+        assert!(before.max_skew() > 0.1);
+
+        let corrected = rev.normalize_bands(period, 0.1).unwrap();
+        assert!((corrected.short - before.short).abs() < 1e-12);
+
+        let after = rev.mfm_band_centers(period).unwrap();
+        assert!(after.max_skew() < 0.02);
+
+        // A well-formed revolution is left untouched.
+        let deltas = skewed_deltas(period, [2.0, 3.0, 4.0]);
+        let mut rev = FluxRevolution::from_f64(DiskCh::new(0, 0), &deltas, 0.2);
+        assert!(rev.normalize_bands(period, 0.1).is_none());
+        assert_eq!(rev.flux_deltas, deltas);
+    }
+}
@@ -34,8 +34,9 @@ pub mod flux_revolution;
 #[macro_use]
 pub mod pll;
 pub mod histogram;
+pub mod synthesis;
 
-pub use flux_revolution::FluxRevolutionType;
+pub use flux_revolution::{FluxBandCenters, FluxRevolutionType};
 
 //pub const AVERAGE_FLUX_DENSITY: f64 = 2.636; // Average number of bits encoded per flux transition
 
@@ -0,0 +1,270 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+
+//! This module synthesizes flux transitions from a track bitstream so that bitstream images can
+//! be exported to flux formats such as SCP.
+//!
+//! A bitstream contains exactly one revolution of perfectly timed bitcells. Some downstream
+//! hardware and emulators expect several revolutions per track, and copy protection checks may
+//! rely on the small timing variations a real drive produces between revolutions. The
+//! [FluxSynthesisOptions] struct controls how many revolutions are emitted, and how much
+//! transition jitter and spindle speed wobble is applied to each.
+//!
+//! Weak bits are resolved independently for each revolution, so they read back as weak when the
+//! exported image is loaded and its revolutions are compared.
+//!
+//! [synthesize_track_flux] synthesizes flux for a whole track, writing unformatted tracks as a
+//! blank or noisy surface would be captured.
+
+use crate::{random::SeededRng, track::Track, types::TrackFormatting, DiskImageError};
+use bit_vec::BitVec;
+use std::f64::consts::TAU;
+
+/// The default number of revolutions to synthesize per track.
+pub const DEFAULT_REVOLUTIONS: usize = 3;
+
+/// Options controlling flux synthesis, set with
+/// [ParserWriteOptions::with_flux_synthesis](crate::prelude::ParserWriteOptions::with_flux_synthesis).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FluxSynthesisOptions {
+    /// The number of revolutions to emit per track.
+    pub revolutions: usize,
+    /// The maximum random displacement of each flux transition from its ideal position, in
+    /// seconds. This is limited to a quarter of a bitcell.
+    pub jitter: f64,
+    /// The peak deviation of spindle speed from nominal, as a fraction (0.01 = 1%). Speed varies
+    /// sinusoidally over each revolution with a random phase, and by a random offset between
+    /// revolutions.
+    pub wobble: f64,
+    /// The seed for the jitter, wobble and weak bit generator. The same seed produces the same
+    /// flux.
+    pub seed: u64,
+}
+
+impl Default for FluxSynthesisOptions {
+    fn default() -> Self {
+        Self {
+            revolutions: DEFAULT_REVOLUTIONS,
+            jitter: 0.0,
+            wobble: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+impl FluxSynthesisOptions {
+    /// Return options approximating a real drive: 50ns of transition jitter and 0.5% speed
+    /// wobble.
+    pub fn realistic() -> Self {
+        Self {
+            jitter: 50e-9,
+            wobble: 0.005,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_revolutions(self, revolutions: usize) -> Self {
+        Self {
+            revolutions: revolutions.max(1),
+            ..self
+        }
+    }
+
+    pub fn with_jitter(self, jitter: f64) -> Self {
+        Self {
+            jitter: jitter.max(0.0),
+            ..self
+        }
+    }
+
+    pub fn with_wobble(self, wobble: f64) -> Self {
+        Self {
+            wobble: wobble.clamp(0.0, 0.1),
+            ..self
+        }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+}
+
+/// A single synthesized revolution.
+#[derive(Clone, Debug, Default)]
+pub struct SynthesizedRevolution {
+    /// The time from index to index, in seconds.
+    pub index_time:  f64,
+    /// The times between flux transitions, in seconds.
+    pub flux_deltas: Vec<f64>,
+}
+
+/// Synthesize flux transitions for a track bitstream.
+///
+/// `bits` holds one revolution of encoded bitcells, where a set bit is a flux transition.
+/// `weak` is the weak bit mask for `bits`, and may be empty. `bitcell_time` is the nominal
+/// duration of one bitcell in seconds.
+///
+/// Revolutions are contiguous: the time between the last transition of a revolution and the
+/// index is carried into the first flux delta of the next revolution.
+pub fn synthesize_flux(
+    bits: &BitVec,
+    weak: &BitVec,
+    bitcell_time: f64,
+    opts: &FluxSynthesisOptions,
+) -> Vec<SynthesizedRevolution> {
+    let mut revolutions = Vec::with_capacity(opts.revolutions);
+    if bits.is_empty() || bitcell_time <= 0.0 {
+        return revolutions;
+    }
+
+    let mut rng = SeededRng::new(opts.seed);
+    let mut unit = || rng.next_f64();
+
+    let jitter = opts.jitter.min(bitcell_time / 4.0);
+    let bit_len = bits.len() as f64;
+
+    // Time since the last emitted transition, carried across revolutions.
+    let mut elapsed = 0.0;
+    // The jitter applied to the last emitted transition.
+    let mut last_jitter = 0.0;
+    let mut last_bit = false;
+
+    for _ in 0..opts.revolutions.max(1) {
+        let speed_offset = opts.wobble * (unit() * 2.0 - 1.0) * 0.5;
+        let phase = unit() * TAU;
+
+        let mut rev = SynthesizedRevolution::default();
+        for (i, bit) in bits.iter().enumerate() {
+            let speed = 1.0 + speed_offset + opts.wobble * 0.5 * (TAU * i as f64 / bit_len + phase).sin();
+            let cell_time = bitcell_time / speed;
+            rev.index_time += cell_time;
+            elapsed += cell_time;
+
+            let mut bit = bit;
+            if weak.get(i).unwrap_or(false) {
+                // Resolve weak bits randomly, but never produce adjacent transitions.
+                bit = !last_bit && unit() < 0.5;
+            }
+            last_bit = bit;
+
+            if bit {
+                let this_jitter = if jitter > 0.0 {
+                    (unit() * 2.0 - 1.0) * jitter
+                }
+                else {
+                    0.0
+                };
+                rev.flux_deltas.push(elapsed + this_jitter - last_jitter);
+                last_jitter = this_jitter;
+                elapsed = 0.0;
+            }
+        }
+        revolutions.push(rev);
+    }
+
+    revolutions
+}
+
+/// Synthesize flux transitions for `track`, as [synthesize_flux] does for its bitstream.
+///
+/// Unformatted tracks are synthesized as they would be captured from a disk: a blank track has no
+/// flux transitions, and every bit of a track of noise is resolved as a weak bit, so that each
+/// revolution differs. Returns [DiskImageError::UnsupportedFormat] if the track has no bitstream.
+pub fn synthesize_track_flux(
+    track: &dyn Track,
+    opts: &FluxSynthesisOptions,
+) -> Result<Vec<SynthesizedRevolution>, DiskImageError> {
+    let stream = track.stream().ok_or_else(|| {
+        log::error!("synthesize_track_flux(): Track {} has no bitstream.", track.ch());
+        DiskImageError::UnsupportedFormat
+    })?;
+    let bitcell_time = 1.0 / (2.0 * u32::from(track.info().data_rate) as f64);
+
+    Ok(match track.formatting() {
+        TrackFormatting::Formatted => synthesize_flux(stream.data(), stream.weak_mask(), bitcell_time, opts),
+        TrackFormatting::Unformatted { noise: true } => {
+            let weak = BitVec::from_elem(stream.len(), true);
+            synthesize_flux(stream.data(), &weak, bitcell_time, opts)
+        }
+        TrackFormatting::Unformatted { noise: false } => {
+            let blank = SynthesizedRevolution {
+                index_time:  stream.len() as f64 * bitcell_time,
+                flux_deltas: Vec::new(),
+            };
+            vec![blank; opts.revolutions.max(1)]
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthesize_flux() {
+        // 0x5555 repeated: a transition every other bitcell.
+        let bits = BitVec::from_bytes(&[0x55; 64]);
+        let weak = BitVec::new();
+        let bitcell = 1e-6;
+
+        let revs = synthesize_flux(&bits, &weak, bitcell, &FluxSynthesisOptions::default());
+        assert_eq!(revs.len(), DEFAULT_REVOLUTIONS);
+        for rev in &revs {
+            assert_eq!(rev.flux_deltas.len(), 256);
+            assert!((rev.index_time - 512.0 * bitcell).abs() < 1e-12);
+        }
+        // The first transition of the first revolution is at bitcell 1.
+        assert!((revs[0].flux_deltas[0] - 2.0 * bitcell).abs() < 1e-12);
+        assert!(revs[0].flux_deltas[1..]
+            .iter()
+            .all(|d| (d - 2.0 * bitcell).abs() < 1e-12));
+
+        // Jitter and wobble vary revolutions but keep their total time close to nominal.
+        let opts = FluxSynthesisOptions::realistic().with_revolutions(5).with_seed(1234);
+        let revs = synthesize_flux(&bits, &weak, bitcell, &opts);
+        assert_eq!(revs.len(), 5);
+        assert_ne!(revs[0].flux_deltas, revs[1].flux_deltas);
+        for rev in &revs {
+            assert!((rev.index_time / (512.0 * bitcell) - 1.0).abs() < opts.wobble);
+            assert!(rev.flux_deltas.iter().all(|d| *d > bitcell));
+        }
+        // The same seed reproduces the same flux.
+        let again = synthesize_flux(&bits, &weak, bitcell, &opts);
+        assert_eq!(revs[3].flux_deltas, again[3].flux_deltas);
+
+        // Weak bits differ between revolutions, and never produce adjacent transitions. The first
+        // transition may fall on the first bitcell after the index.
+        let weak = BitVec::from_elem(bits.len(), true);
+        let revs = synthesize_flux(&bits, &weak, bitcell, &FluxSynthesisOptions::default().with_seed(7));
+        assert_ne!(revs[0].flux_deltas, revs[1].flux_deltas);
+        assert!(revs
+            .iter()
+            .flat_map(|rev| rev.flux_deltas.iter())
+            .skip(1)
+            .all(|d| *d >= 2.0 * bitcell - 1e-12));
+    }
+}
@@ -0,0 +1,474 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/format_detection.rs
+
+    Ranks the standard formats an image may be, with the evidence for each.
+*/
+
+//! Standard format detection with confidence scores.
+//!
+//! [DiskImage::closest_format()] returns a single guess at an image's [StandardFormat].
+//! [DiskImage::detect_formats()] instead scores every [StandardFormat] against the evidence
+//! available in the image - the BPB of the boot sector, the image's track and head count, and the
+//! sector map of each track - and returns the candidates ranked by confidence, along with the
+//! evidence that supports each one. If detection picks the wrong format, it can be overridden with
+//! [DiskImage::set_standard_format()].
+//!
+//! Mastered images commonly carry a BPB describing a different geometry than the tracks actually
+//! present. [DiskImage::check_bpb_geometry()] is an optional post-load pass that detects this, and
+//! either records the discrepancy in the image metadata or remaps logical reads onto the physical
+//! geometry.
+
+use crate::{
+    prelude::{DiskChs, StandardFormat},
+    DiskImage,
+    FoxHashMap,
+};
+use std::fmt::{Display, Formatter, Result};
+use strum::IntoEnumIterator;
+
+/// The metadata key [DiskImage::check_bpb_geometry()] records the BPB geometry under.
+pub const BPB_GEOMETRY_KEY: &str = "bpb_geometry";
+/// The metadata key [DiskImage::check_bpb_geometry()] records the physical geometry under.
+pub const PHYSICAL_GEOMETRY_KEY: &str = "physical_geometry";
+
+/// The confidence contributed by a BPB describing the format.
+pub const BOOT_SECTOR_WEIGHT: f64 = 0.35;
+/// The confidence contributed by the image's track and head count matching the format.
+pub const TRACK_COUNT_WEIGHT: f64 = 0.25;
+/// The confidence contributed by every track having the format's sector map. Images where only
+/// some tracks match contribute proportionally less.
+pub const SECTOR_MAP_WEIGHT: f64 = 0.40;
+
+/// A piece of evidence supporting a [FormatCandidate].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FormatEvidence {
+    /// The BPB of the boot sector describes the format.
+    BootSector,
+    /// The image's track and head count match the format. `cylinders` is the image's track
+    /// count normalized to 40 or 80 tracks.
+    TrackCount { cylinders: usize, heads: u8 },
+    /// `matching` of the image's `total` tracks have exactly the sector IDs and sizes of the
+    /// format.
+    SectorMap { matching: usize, total: usize },
+}
+
+impl FormatEvidence {
+    /// Return the confidence this evidence contributes to a candidate.
+    pub fn weight(&self) -> f64 {
+        match self {
+            FormatEvidence::BootSector => BOOT_SECTOR_WEIGHT,
+            FormatEvidence::TrackCount { .. } => TRACK_COUNT_WEIGHT,
+            FormatEvidence::SectorMap { matching, total } => match total {
+                0 => 0.0,
+                _ => SECTOR_MAP_WEIGHT * *matching as f64 / *total as f64,
+            },
+        }
+    }
+}
+
+impl Display for FormatEvidence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            FormatEvidence::BootSector => write!(f, "boot sector BPB"),
+            FormatEvidence::TrackCount { cylinders, heads } => {
+                write!(f, "{} cylinders, {} heads", cylinders, heads)
+            }
+            FormatEvidence::SectorMap { matching, total } => {
+                write!(f, "sector map of {}/{} tracks", matching, total)
+            }
+        }
+    }
+}
+
+/// A [StandardFormat] an image may be, with a confidence score and the evidence supporting it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FormatCandidate {
+    /// The candidate format.
+    pub format: StandardFormat,
+    /// The confidence that the image is of this format, from 0.0 to 1.0. This is the sum of the
+    /// weights of the evidence.
+    pub confidence: f64,
+    /// The evidence supporting the candidate.
+    pub evidence: Vec<FormatEvidence>,
+}
+
+/// The result of [DiskImage::detect_formats()].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FormatDetection {
+    /// Every format with any supporting evidence, ordered by descending confidence.
+    pub candidates: Vec<FormatCandidate>,
+}
+
+impl FormatDetection {
+    /// Return the candidate with the highest confidence, if any.
+    pub fn best(&self) -> Option<&FormatCandidate> {
+        self.candidates.first()
+    }
+
+    /// Return the confidence of the specified format, or 0.0 if it is not a candidate.
+    pub fn confidence(&self, format: StandardFormat) -> f64 {
+        self.candidates
+            .iter()
+            .find(|candidate| candidate.format == format)
+            .map_or(0.0, |candidate| candidate.confidence)
+    }
+
+    /// Return true if the best candidate is uncontested - no other candidate has the same
+    /// confidence.
+    pub fn is_unambiguous(&self) -> bool {
+        match self.candidates.as_slice() {
+            [] => false,
+            [_] => true,
+            [first, second, ..] => first.confidence > second.confidence,
+        }
+    }
+}
+
+impl Display for FormatDetection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.candidates.is_empty() {
+            return writeln!(f, "No standard format detected");
+        }
+        for candidate in &self.candidates {
+            let evidence: Vec<String> = candidate.evidence.iter().map(|e| e.to_string()).collect();
+            writeln!(
+                f,
+                "{:>5.1}% {} ({})",
+                candidate.confidence * 100.0,
+                candidate.format,
+                evidence.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The action taken by [DiskImage::check_bpb_geometry()] when the BPB geometry disagrees with the
+/// physical geometry of the image.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum GeometryCorrection {
+    /// Record both geometries in the image metadata, under [BPB_GEOMETRY_KEY] and
+    /// [PHYSICAL_GEOMETRY_KEY], for tools to display.
+    #[default]
+    Record,
+    /// Record both geometries, and set the image's standard format to the one matching the
+    /// physical geometry, so that logical block addresses and file systems mounted from the image
+    /// map onto the sectors actually present.
+    Remap,
+}
+
+/// A disagreement between the geometry described by an image's BPB and its physical geometry.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeometryMismatch {
+    /// The geometry described by the BPB. The cylinder count is derived from the total sector
+    /// count.
+    pub bpb: DiskChs,
+    /// The physical geometry of the image. The sector count is the most common sector count of
+    /// the image's tracks.
+    pub physical: DiskChs,
+    /// The standard format matching the physical geometry, if any.
+    pub physical_format: Option<StandardFormat>,
+    /// Whether logical reads were remapped to `physical_format`.
+    pub remapped: bool,
+}
+
+impl Display for GeometryMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "BPB geometry {} does not match physical geometry {}",
+            self.bpb, self.physical
+        )?;
+        if self.remapped {
+            if let Some(format) = self.physical_format {
+                write!(f, " (remapped to {})", format)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DiskImage {
+    /// Score every [StandardFormat] against the image, and return the formats with any
+    /// supporting evidence ranked by confidence.
+    ///
+    /// Unlike [DiskImage::closest_format()], this does not depend on the image's consistency
+    /// analysis; a sector map is compared for each track, so an image with a few nonstandard
+    /// tracks still ranks its underlying format highest.
+    pub fn detect_formats(&self) -> FormatDetection {
+        let bpb_format = self.boot_sector.as_ref().and_then(|bs| bs.standard_format());
+        let cylinders = StandardFormat::normalized_track_ct(self.track_ct(0));
+        let heads = self.heads();
+
+        // Collect the sorted (sector number, size code) pairs of each track once, as they are
+        // compared against every format.
+        let track_maps: Vec<Vec<(u8, u8)>> = self
+            .iter_tracks()
+            .map(|(_, track)| {
+                let mut ids: Vec<(u8, u8)> = track
+                    .sector_list()
+                    .iter()
+                    .map(|entry| (entry.chsn.s(), entry.chsn.n()))
+                    .collect();
+                ids.sort_unstable();
+                ids
+            })
+            .collect();
+
+        let mut candidates: Vec<FormatCandidate> = StandardFormat::iter()
+            .filter_map(|format| {
+                let layout = format.layout();
+                let mut evidence = Vec::new();
+
+                if bpb_format == Some(format) {
+                    evidence.push(FormatEvidence::BootSector);
+                }
+
+                if let Some(cylinders) = cylinders {
+                    if cylinders == layout.c() as usize && heads == layout.h() {
+                        evidence.push(FormatEvidence::TrackCount { cylinders, heads });
+                    }
+                }
+
+                let expected: Vec<(u8, u8)> = (0..layout.s()).map(|s| (layout.s_off() + s, layout.n())).collect();
+                let matching = track_maps.iter().filter(|ids| **ids == expected).count();
+                if matching > 0 {
+                    evidence.push(FormatEvidence::SectorMap {
+                        matching,
+                        total: track_maps.len(),
+                    });
+                }
+
+                (!evidence.is_empty()).then(|| FormatCandidate {
+                    format,
+                    confidence: evidence.iter().map(|e| e.weight()).sum(),
+                    evidence,
+                })
+            })
+            .collect();
+
+        // A stable sort keeps formats with equal confidence in declaration order.
+        candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        FormatDetection { candidates }
+    }
+
+    /// Return the geometry described by the BPB of the boot sector, if the image has a valid BPB.
+    /// The cylinder count is derived from the BPB's total sector count.
+    pub fn bpb_geometry(&self) -> Option<DiskChs> {
+        let boot_sector = self.boot_sector.as_ref().filter(|bs| bs.has_valid_bpb())?;
+        let (bpb2, bpb3) = (boot_sector.bpb2(), boot_sector.bpb3());
+        let track_sectors = bpb3.sectors_per_track as usize * bpb3.number_of_heads as usize;
+        if track_sectors == 0 || bpb3.sectors_per_track > u8::MAX as u16 || bpb3.number_of_heads > 2 {
+            return None;
+        }
+        let cylinders = bpb2.total_sectors as usize / track_sectors;
+        Some(DiskChs::new(
+            cylinders as u16,
+            bpb3.number_of_heads as u8,
+            bpb3.sectors_per_track as u8,
+        ))
+    }
+
+    /// Return the physical geometry of the image: its track count normalized to 40 or 80 tracks
+    /// where possible, its head count, and the most common sector count of its tracks. Returns
+    /// None if the image has no sectors.
+    pub fn physical_geometry(&self) -> Option<DiskChs> {
+        let mut sector_cts: FoxHashMap<usize, usize> = FoxHashMap::new();
+        for (_, track) in self.iter_tracks() {
+            *sector_cts.entry(track.sector_ct()).or_default() += 1;
+        }
+        // Break ties toward the larger sector count, so the result doesn't depend on hash order.
+        let (spt, _) = sector_cts
+            .into_iter()
+            .filter(|(spt, _)| *spt > 0)
+            .max_by_key(|(spt, count)| (*count, *spt))?;
+
+        let track_ct = self.track_ct(0);
+        let cylinders = StandardFormat::normalized_track_ct(track_ct).unwrap_or(track_ct);
+        Some(DiskChs::new(cylinders as u16, self.heads(), u8::try_from(spt).ok()?))
+    }
+
+    /// Check whether the geometry described by the image's BPB agrees with its physical geometry.
+    /// This pass is optional; call it after loading an image to detect mastered images whose BPB
+    /// was never updated for the disk they were written to.
+    ///
+    /// If the geometries disagree, the discrepancy is handled as specified by `correction`.
+    /// [GeometryCorrection::Remap] has no effect if the physical geometry does not match a
+    /// [StandardFormat].
+    /// # Returns
+    /// The [GeometryMismatch] found, or None if the geometries agree, or either is unavailable.
+    pub fn check_bpb_geometry(&mut self, correction: GeometryCorrection) -> Option<GeometryMismatch> {
+        let bpb = self.bpb_geometry()?;
+        let physical = self.physical_geometry()?;
+        if bpb == physical {
+            return None;
+        }
+        log::warn!(
+            "check_bpb_geometry(): BPB geometry {} does not match physical geometry {}",
+            bpb,
+            physical
+        );
+
+        let physical_format = StandardFormat::try_from(&physical).ok();
+        let remapped = correction == GeometryCorrection::Remap && physical_format.is_some();
+        if remapped {
+            self.standard_format = physical_format;
+        }
+        self.set_metadata_key(BPB_GEOMETRY_KEY, &bpb.to_string());
+        self.set_metadata_key(PHYSICAL_GEOMETRY_KEY, &physical.to_string());
+
+        Some(GeometryMismatch {
+            bpb,
+            physical,
+            physical_format,
+            remapped,
+        })
+    }
+
+    /// Return the [StandardFormat] of the image, if one was declared by the image file, set by
+    /// the boot sector during load, or forced with [DiskImage::set_standard_format()].
+    pub fn standard_format(&self) -> Option<StandardFormat> {
+        self.standard_format
+    }
+
+    /// Force the [StandardFormat] of the image, overriding detection. The format is used for
+    /// logical block addressing and integrity analysis. Specifying `None` clears the format, so
+    /// that the closest detected format is used instead.
+    pub fn set_standard_format(&mut self, format: Option<StandardFormat>) {
+        self.standard_format = format;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        boot_sector::BootSector,
+        diskimage::DEFAULT_BOOT_SECTOR,
+        io::Cursor,
+        prelude::*,
+        test_util::{add_sector, sector_disk},
+    };
+
+    fn build_disk(format: StandardFormat) -> DiskImage {
+        let mut disk = sector_disk(format, |_| (vec![0xF6; format.sector_size()], Default::default()));
+
+        // Give one track an extra sector, so it matches no format.
+        let layout = format.layout();
+        let ch = DiskCh::new(5, 1);
+        add_sector(
+            disk.track_mut(ch).unwrap(),
+            DiskChsn::new(ch.c(), ch.h(), layout.s() + 1, layout.n()),
+            &vec![0xF6; format.sector_size()],
+            Default::default(),
+        );
+        disk
+    }
+
+    #[test]
+    fn test_detect_formats() {
+        let format = StandardFormat::PcFloppy720;
+        let mut disk = build_disk(format);
+
+        let detection = disk.detect_formats();
+        let best = detection.best().unwrap();
+        assert_eq!(best.format, format);
+        assert!(detection.is_unambiguous());
+        assert_eq!(
+            best.evidence,
+            vec![
+                FormatEvidence::TrackCount {
+                    cylinders: 80,
+                    heads: 2,
+                },
+                FormatEvidence::SectorMap {
+                    matching: 159,
+                    total:    160,
+                },
+            ]
+        );
+        // Formats sharing the geometry but not the sector map rank below the best match.
+        assert_eq!(detection.confidence(StandardFormat::PcFloppy1440), TRACK_COUNT_WEIGHT);
+        assert_eq!(
+            detection.confidence(StandardFormat::PcFloppy360),
+            SECTOR_MAP_WEIGHT * 159.0 / 160.0
+        );
+        assert_eq!(detection.confidence(StandardFormat::PcFloppy160), 0.0);
+
+        disk.set_standard_format(Some(StandardFormat::PcFloppy1440));
+        assert_eq!(disk.standard_format(), Some(StandardFormat::PcFloppy1440));
+        assert_eq!(disk.analyze().standard_format, Some(StandardFormat::PcFloppy1440));
+        disk.set_standard_format(None);
+        assert_eq!(disk.analyze().standard_format, disk.closest_format(true));
+    }
+
+    #[test]
+    fn test_check_bpb_geometry() {
+        let mut disk = build_disk(StandardFormat::PcFloppy360);
+        assert_eq!(disk.check_bpb_geometry(GeometryCorrection::Remap), None);
+
+        // Write a boot sector describing a 720K disk, as a mastering tool might have.
+        let mut boot_sector = BootSector::new(&mut Cursor::new(DEFAULT_BOOT_SECTOR)).unwrap();
+        boot_sector.update_bpb_from_format(StandardFormat::PcFloppy720).unwrap();
+        disk.set_boot_sector(boot_sector).unwrap();
+        disk.set_standard_format(Some(StandardFormat::PcFloppy720));
+        assert_eq!(disk.bpb_geometry(), Some(DiskChs::new(80, 2, 9)));
+        assert_eq!(disk.physical_geometry(), Some(DiskChs::new(40, 2, 9)));
+
+        let mismatch = disk.check_bpb_geometry(GeometryCorrection::Record).unwrap();
+        assert!(!mismatch.remapped);
+        assert_eq!(mismatch.bpb, DiskChs::new(80, 2, 9));
+        assert_eq!(mismatch.physical, DiskChs::new(40, 2, 9));
+        assert_eq!(mismatch.physical_format, Some(StandardFormat::PcFloppy360));
+        assert_eq!(disk.standard_format(), Some(StandardFormat::PcFloppy720));
+        assert_eq!(
+            disk.metadata_key(BPB_GEOMETRY_KEY),
+            Some(DiskChs::new(80, 2, 9).to_string())
+        );
+        assert_eq!(
+            disk.metadata_key(PHYSICAL_GEOMETRY_KEY),
+            Some(DiskChs::new(40, 2, 9).to_string())
+        );
+        // Under the BPB geometry, the first block past the physical disk maps to a missing cylinder.
+        assert!(matches!(
+            disk.read_lba(9 * 2 * 40),
+            Err(DiskImageError::IncompatibleImage(_))
+        ));
+
+        // Remapping makes logical block addresses follow the physical geometry.
+        let mismatch = disk.check_bpb_geometry(GeometryCorrection::Remap).unwrap();
+        assert!(mismatch.remapped);
+        assert_eq!(disk.standard_format(), Some(StandardFormat::PcFloppy360));
+        assert!(disk.read_lba(9 * 2 * 40 - 1).is_ok());
+        assert!(matches!(disk.read_lba(9 * 2 * 40), Err(DiskImageError::SeekError)));
+    }
+}
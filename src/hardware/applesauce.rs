@@ -0,0 +1,321 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/hardware/applesauce.rs
+
+    Support for Applesauce compatible floppy controllers.
+
+    The Applesauce presents a USB CDC serial port, and is driven by text
+    commands of the form `group:action`, each terminated by a newline. The
+    device answers each command with a line: `.` for success, `!` for an
+    error, `?` for an unknown command, or the value of a query such as
+    `sync:?speed`.
+
+    Flux is captured into a buffer on the device, starting at the index, and
+    then transferred with `data:<`. Flux is sampled at 8MHz, and each byte is
+    a count of ticks ending in a flux transition, except for 255, which adds
+    255 ticks to the next interval without a transition. The stream does not
+    mark index pulses; their positions in the capture, in ticks, are queried
+    with `data:?index` as a comma-separated list.
+*/
+
+use crate::{
+    hardware::{split_revolutions, CapturedRevolution, DriveSelect, FluxSource},
+    io::{Read, Write},
+    types::DiskCh,
+    DiskImageError,
+};
+use std::time::Duration;
+
+const TICK_FREQ: u32 = 8_000_000;
+/// The byte that adds to the next interval without a transition.
+const TICK_EXTEND: u8 = 255;
+/// The longest reply line accepted from the device.
+const MAX_LINE_LEN: usize = 256;
+
+/// A connection to an Applesauce compatible device.
+///
+/// Open a device connected to a serial port with [Applesauce::open], or wrap any other transport
+/// with [Applesauce::new].
+pub struct Applesauce<P: Read + Write> {
+    port: P,
+}
+
+impl Applesauce<Box<dyn serialport::SerialPort>> {
+    /// Open the Applesauce connected to the serial port at `path`, such as `COM3` or
+    /// `/dev/ttyACM0`.
+    pub fn open(path: &str) -> Result<Self, DiskImageError> {
+        let port = serialport::new(path, 115200)
+            .timeout(Duration::from_secs(10))
+            .open()
+            .map_err(|e| {
+                log::error!("open(): Failed to open serial port {}: {}", path, e);
+                DiskImageError::HardwareError(format!("Failed to open {}: {}", path, e))
+            })?;
+        Applesauce::new(port)
+    }
+}
+
+impl<P: Read + Write> Applesauce<P> {
+    /// Connect to an Applesauce over `port`, and check that it identifies itself as one.
+    pub fn new(port: P) -> Result<Self, DiskImageError> {
+        let mut asc = Applesauce { port };
+        let id = asc.query("?")?;
+        if id != "Applesauce" {
+            log::error!("Applesauce::new(): Device identified itself as {:?}", id);
+            return Err(DiskImageError::HardwareError(format!(
+                "Device is not an Applesauce: {}",
+                id
+            )));
+        }
+        asc.command("connect")?;
+        Ok(asc)
+    }
+
+    /// Return the intervals between the index pulses of the last capture, in ticks. The first
+    /// interval is measured from the start of the capture.
+    fn index_intervals(&mut self) -> Result<Vec<u32>, DiskImageError> {
+        let reply = self.query("data:?index")?;
+        let mut positions = Vec::new();
+        for field in reply.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let pos = field
+                .parse::<u32>()
+                .map_err(|_| DiskImageError::HardwareError(format!("Invalid index position: {}", field)))?;
+            positions.push(pos);
+        }
+        if positions.is_empty() {
+            return Err(DiskImageError::HardwareError(
+                "No index pulse detected. Is there a disk in the drive?".to_string(),
+            ));
+        }
+
+        let mut last = 0;
+        let mut intervals = Vec::with_capacity(positions.len());
+        for pos in positions {
+            if pos < last {
+                log::error!("index_intervals(): Index positions out of order: {:?}", reply);
+                return Err(DiskImageError::HardwareError("Invalid index positions".to_string()));
+            }
+            intervals.push(pos - last);
+            last = pos;
+        }
+        Ok(intervals)
+    }
+
+    /// Send a command, and check that it succeeded.
+    fn command(&mut self, cmd: &str) -> Result<(), DiskImageError> {
+        match self.query(cmd)?.as_str() {
+            "." => Ok(()),
+            reply => {
+                let msg = match reply {
+                    "!" => "Command failed",
+                    "?" => "Unknown command",
+                    _ => "Unexpected reply",
+                };
+                log::error!("command(): {}: {} ({:?})", cmd, msg, reply);
+                Err(DiskImageError::HardwareError(format!("{}: {}", cmd, msg)))
+            }
+        }
+    }
+
+    /// Send a command, and return the line the device replies with.
+    fn query(&mut self, cmd: &str) -> Result<String, DiskImageError> {
+        self.port.write_all(cmd.as_bytes())?;
+        self.port.write_all(b"\n")?;
+        self.port.flush()?;
+
+        let mut line = Vec::new();
+        let mut byte = [0u8; 1];
+        loop {
+            self.port.read_exact(&mut byte)?;
+            match byte[0] {
+                b'\n' => break,
+                b'\r' => {}
+                b => line.push(b),
+            }
+            if line.len() > MAX_LINE_LEN {
+                return Err(DiskImageError::HardwareError("Reply too long".to_string()));
+            }
+        }
+        Ok(String::from_utf8_lossy(&line).into_owned())
+    }
+}
+
+impl<P: Read + Write> FluxSource for Applesauce<P> {
+    fn name(&self) -> &str {
+        "Applesauce"
+    }
+
+    fn select_drive(&mut self, drive: &DriveSelect) -> Result<(), DiskImageError> {
+        // The Applesauce drives a single drive, and selects its interface from the drive attached.
+        if drive.unit != 0 || drive.density.is_some() {
+            log::warn!("select_drive(): Drive unit and density selection are ignored by the Applesauce.");
+        }
+        self.command("drive:enable")?;
+        self.command("motor:on")?;
+        self.command("head:zero")
+    }
+
+    fn deselect_drive(&mut self, _drive: &DriveSelect) -> Result<(), DiskImageError> {
+        self.command("motor:off")?;
+        self.command("drive:disable")
+    }
+
+    fn seek(&mut self, ch: DiskCh) -> Result<(), DiskImageError> {
+        if ch.c() == 0 {
+            self.command("head:zero")?;
+        }
+        else {
+            self.command(&format!("head:track{}", ch.c()))?;
+        }
+        self.command(&format!("head:side{}", ch.h()))
+    }
+
+    fn read_revolutions(&mut self, revolutions: u8) -> Result<Vec<CapturedRevolution>, DiskImageError> {
+        // Capture one extra revolution, so the last requested revolution is complete.
+        self.command("sync:on")?;
+        self.command("data:clear")?;
+        self.command(&format!("disk:revs{}", revolutions as u32 + 1))?;
+        self.command("disk:read")?;
+
+        let size = self
+            .query("data:?size")?
+            .parse::<usize>()
+            .map_err(|_| DiskImageError::HardwareError("Invalid capture size".to_string()))?;
+        self.command(&format!("data:<{}", size))?;
+        let mut stream = vec![0u8; size];
+        self.port.read_exact(&mut stream)?;
+
+        // Divide the flux into revolutions at the index pulses the device recorded, so each
+        // revolution keeps its own rotation time.
+        let flux = decode_flux(&stream);
+        let mut index = self.index_intervals()?;
+        index.truncate(revolutions as usize + 1);
+        Ok(split_revolutions(&flux, &index, TICK_FREQ))
+    }
+
+    fn write_revolution(&mut self, flux: &[f64], _retries: u8) -> Result<(), DiskImageError> {
+        // The whole track is buffered on the device before writing, so writes cannot underrun.
+        let stream = encode_flux(flux);
+        self.command("data:clear")?;
+        self.command(&format!("data:>{}", stream.len()))?;
+        self.port.write_all(&stream)?;
+        self.port.flush()?;
+        self.command("sync:on")?;
+        self.command("disk:write")
+    }
+}
+
+/// Decode a flux stream read from the device into flux intervals, in ticks.
+fn decode_flux(stream: &[u8]) -> Vec<u32> {
+    let mut flux = Vec::with_capacity(stream.len());
+    let mut ticks = 0u32;
+    for &b in stream {
+        ticks += b as u32;
+        if b != TICK_EXTEND {
+            flux.push(ticks);
+            ticks = 0;
+        }
+    }
+    flux
+}
+
+/// Encode flux intervals, in seconds, into a flux stream for the device.
+fn encode_flux(flux: &[f64]) -> Vec<u8> {
+    let mut stream = Vec::with_capacity(flux.len());
+    for &delta in flux {
+        let mut ticks = (delta * TICK_FREQ as f64).round() as u32;
+        if ticks == 0 {
+            continue;
+        }
+        while ticks >= TICK_EXTEND as u32 {
+            stream.push(TICK_EXTEND);
+            ticks -= TICK_EXTEND as u32;
+        }
+        stream.push(ticks as u8);
+    }
+    stream
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// A transport that replays canned device responses and records what is sent to it.
+    struct MockPort {
+        input:  VecDeque<u8>,
+        output: Vec<u8>,
+    }
+
+    impl Read for MockPort {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for MockPort {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.output.write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_applesauce_flux_roundtrip() {
+        let ticks = [16u32, 32, 254, 255, 256, 510, 4000];
+        let flux: Vec<f64> = ticks.iter().map(|&t| t as f64 / TICK_FREQ as f64).collect();
+        assert_eq!(decode_flux(&encode_flux(&flux)), ticks);
+    }
+
+    #[test]
+    fn test_applesauce_read_revolutions() {
+        // Capture 3 revolutions of 40000 tick flux, with the disk slowing from 300 to 250 RPM, so
+        // the first two revolutions take 1,600,000 and 1,920,000 ticks.
+        let stream = encode_flux(&vec![40000.0 / TICK_FREQ as f64; 130]);
+
+        let mut input = b"Applesauce\n.\n.\n.\n.\n.\n".to_vec();
+        input.extend_from_slice(format!("{}\n.\n", stream.len()).as_bytes());
+        input.extend_from_slice(&stream);
+        input.extend_from_slice(b"0,1600000,3520000,5120000\n");
+        let port = MockPort {
+            input:  input.into(),
+            output: Vec::new(),
+        };
+
+        let mut asc = Applesauce::new(port).unwrap();
+        let revolutions = asc.read_revolutions(2).unwrap();
+        assert_eq!(revolutions.len(), 2);
+        assert_eq!(revolutions[0].flux.len(), 40);
+        assert!((revolutions[0].index_time - 0.2).abs() < 1e-9);
+        assert_eq!(revolutions[1].flux.len(), 48);
+        assert!((revolutions[1].index_time - 0.24).abs() < 1e-9);
+        let sent = String::from_utf8_lossy(&asc.port.output).into_owned();
+        assert!(sent.contains("disk:revs3\ndisk:read\n"));
+    }
+}
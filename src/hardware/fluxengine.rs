@@ -0,0 +1,459 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/hardware/fluxengine.rs
+
+    Support for the FluxEngine USB floppy controller.
+
+    The FluxEngine is a USB device with two pairs of bulk endpoints: one for
+    command frames and one for flux data. Each command frame starts with a
+    frame type and the total length of the frame in bytes, followed by its
+    arguments; the device answers each command with a reply frame, or an
+    error frame carrying an error code.
+
+    Flux is transferred as a byte stream sampled at 12MHz. The low 6 bits of
+    each byte are a count of ticks; bit 7 marks a flux transition at the end
+    of the count and bit 6 marks an index pulse. Intervals longer than 63
+    ticks are sent as several bytes, only the last of which is a transition.
+    A stream read from the device ends with a transfer shorter than a frame.
+*/
+
+use crate::{
+    hardware::{split_revolutions, CapturedRevolution, DriveSelect, FluxSource, PinLevel},
+    types::DiskCh,
+    DiskImageError,
+};
+use std::time::Duration;
+
+/// The USB vendor ID of the FluxEngine.
+pub const FLUXENGINE_VID: u16 = 0x1209;
+/// The USB product ID of the FluxEngine.
+pub const FLUXENGINE_PID: u16 = 0x6e00;
+
+const DATA_OUT_EP: u8 = 0x01;
+const DATA_IN_EP: u8 = 0x82;
+const CMD_OUT_EP: u8 = 0x03;
+const CMD_IN_EP: u8 = 0x84;
+const FRAME_SIZE: usize = 64;
+const TICK_FREQ: u32 = 12_000_000;
+
+// Frame types. Each reply is the frame type of its command plus one.
+const FRAME_ERROR: u8 = 0;
+const FRAME_GET_VERSION: u8 = 2;
+const FRAME_SEEK: u8 = 4;
+const FRAME_MEASURE_SPEED: u8 = 6;
+const FRAME_READ: u8 = 12;
+const FRAME_WRITE: u8 = 14;
+const FRAME_RECALIBRATE: u8 = 18;
+const FRAME_SET_DRIVE: u8 = 20;
+const FRAME_SET_PIN: u8 = 24;
+
+// Error codes.
+const ERROR_BAD_COMMAND: u8 = 1;
+const ERROR_UNDERRUN: u8 = 2;
+const ERROR_INVALID_VALUE: u8 = 3;
+
+const F_BIT_PULSE: u8 = 0x80;
+const F_BIT_INDEX: u8 = 0x40;
+const F_TICK_MASK: u8 = 0x3f;
+
+/// The interface pin carrying the density select line.
+const PIN_DENSITY: u8 = 2;
+
+/// The transport used to talk to a FluxEngine: its command and data endpoints.
+///
+/// [UsbTransport] talks to a device over USB. Other implementations may be supplied to
+/// [FluxEngine::new], for example to replay a recorded session.
+pub trait FluxEngineTransport {
+    /// Send a command frame.
+    fn write_command(&mut self, frame: &[u8]) -> Result<(), DiskImageError>;
+    /// Receive a reply frame.
+    fn read_command(&mut self) -> Result<Vec<u8>, DiskImageError>;
+    /// Send flux data.
+    fn write_data(&mut self, data: &[u8]) -> Result<(), DiskImageError>;
+    /// Receive flux data into `buf`, returning the number of bytes received. Each call receives
+    /// a single transfer, so that the end of a stream can be recognized by a short transfer.
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<usize, DiskImageError>;
+}
+
+/// A [FluxEngineTransport] that talks to a FluxEngine over USB.
+pub struct UsbTransport {
+    handle:  rusb::DeviceHandle<rusb::GlobalContext>,
+    timeout: Duration,
+}
+
+impl UsbTransport {
+    /// Open the first FluxEngine connected to the system.
+    pub fn open() -> Result<Self, DiskImageError> {
+        let handle = rusb::open_device_with_vid_pid(FLUXENGINE_VID, FLUXENGINE_PID).ok_or_else(|| {
+            log::error!("UsbTransport::open(): No FluxEngine found.");
+            DiskImageError::HardwareError("No FluxEngine found".to_string())
+        })?;
+        handle.claim_interface(0).map_err(usb_error)?;
+        Ok(UsbTransport {
+            handle,
+            timeout: Duration::from_secs(10),
+        })
+    }
+}
+
+impl FluxEngineTransport for UsbTransport {
+    fn write_command(&mut self, frame: &[u8]) -> Result<(), DiskImageError> {
+        self.handle
+            .write_bulk(CMD_OUT_EP, frame, self.timeout)
+            .map_err(usb_error)?;
+        Ok(())
+    }
+
+    fn read_command(&mut self) -> Result<Vec<u8>, DiskImageError> {
+        let mut buf = [0u8; FRAME_SIZE];
+        let n = self
+            .handle
+            .read_bulk(CMD_IN_EP, &mut buf, self.timeout)
+            .map_err(usb_error)?;
+        Ok(buf[..n].to_vec())
+    }
+
+    fn write_data(&mut self, data: &[u8]) -> Result<(), DiskImageError> {
+        for chunk in data.chunks(FRAME_SIZE) {
+            self.handle
+                .write_bulk(DATA_OUT_EP, chunk, self.timeout)
+                .map_err(usb_error)?;
+        }
+        Ok(())
+    }
+
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<usize, DiskImageError> {
+        let len = buf.len().min(FRAME_SIZE);
+        self.handle
+            .read_bulk(DATA_IN_EP, &mut buf[..len], self.timeout)
+            .map_err(usb_error)
+    }
+}
+
+fn usb_error(e: rusb::Error) -> DiskImageError {
+    log::error!("UsbTransport: USB error: {}", e);
+    DiskImageError::HardwareError(format!("USB error: {}", e))
+}
+
+/// A connection to a FluxEngine device.
+///
+/// Open the device connected over USB with [FluxEngine::open], or wrap any other transport with
+/// [FluxEngine::new].
+pub struct FluxEngine<T: FluxEngineTransport> {
+    transport: T,
+    version: u8,
+    head: u8,
+}
+
+impl FluxEngine<UsbTransport> {
+    /// Open the first FluxEngine connected to the system.
+    pub fn open() -> Result<Self, DiskImageError> {
+        FluxEngine::new(UsbTransport::open()?)
+    }
+}
+
+impl<T: FluxEngineTransport> FluxEngine<T> {
+    /// Connect to a FluxEngine over `transport`, and query its protocol version.
+    pub fn new(transport: T) -> Result<Self, DiskImageError> {
+        let mut fe = FluxEngine {
+            transport,
+            version: 0,
+            head: 0,
+        };
+        let reply = fe.command(&[FRAME_GET_VERSION, 2])?;
+        fe.version = *reply.get(2).ok_or_else(|| invalid_reply(FRAME_GET_VERSION))?;
+
+        log::debug!("FluxEngine::new(): Connected, protocol version {}", fe.version);
+        Ok(fe)
+    }
+
+    /// Return the protocol version the device reported.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Return the rotation period of the disk in the selected drive, in milliseconds.
+    fn rotation_period_ms(&mut self) -> Result<u16, DiskImageError> {
+        let reply = self.command(&[FRAME_MEASURE_SPEED, 3, 0])?;
+        let period = reply
+            .get(2..4)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(|| invalid_reply(FRAME_MEASURE_SPEED))?;
+        if period == 0 {
+            return Err(DiskImageError::HardwareError(
+                "No index pulse detected. Is there a disk in the drive?".to_string(),
+            ));
+        }
+        Ok(period)
+    }
+
+    /// Receive a flux stream up to the short transfer that ends it.
+    fn read_stream(&mut self) -> Result<Vec<u8>, DiskImageError> {
+        let mut stream = Vec::new();
+        let mut buf = [0u8; FRAME_SIZE];
+        loop {
+            let n = self.transport.read_data(&mut buf)?;
+            stream.extend_from_slice(&buf[..n]);
+            if n < FRAME_SIZE {
+                return Ok(stream);
+            }
+        }
+    }
+
+    /// Send a command frame and return its reply frame.
+    fn command(&mut self, frame: &[u8]) -> Result<Vec<u8>, DiskImageError> {
+        self.transport.write_command(frame)?;
+        let reply = self.transport.read_command()?;
+        match reply.first() {
+            Some(&FRAME_ERROR) => Err(error_result(frame[0], reply.get(2).copied().unwrap_or(0))),
+            Some(&t) if t == frame[0] + 1 => Ok(reply),
+            _ => Err(invalid_reply(frame[0])),
+        }
+    }
+}
+
+impl<T: FluxEngineTransport> FluxSource for FluxEngine<T> {
+    fn name(&self) -> &str {
+        "FluxEngine"
+    }
+
+    fn select_drive(&mut self, drive: &DriveSelect) -> Result<(), DiskImageError> {
+        // The FluxEngine has two drive selects, and no choice of bus. Index pulses are taken from
+        // the drive.
+        self.command(&[FRAME_SET_DRIVE, 4, drive.unit, 0])?;
+        if let Some(level) = drive.density {
+            self.command(&[FRAME_SET_PIN, 4, PIN_DENSITY, (level == PinLevel::High) as u8])?;
+        }
+        self.command(&[FRAME_RECALIBRATE, 2])?;
+        Ok(())
+    }
+
+    fn deselect_drive(&mut self, _drive: &DriveSelect) -> Result<(), DiskImageError> {
+        // The FluxEngine turns the motor off and deselects the drive by itself when idle.
+        Ok(())
+    }
+
+    fn seek(&mut self, ch: DiskCh) -> Result<(), DiskImageError> {
+        let c = u8::try_from(ch.c()).map_err(|_| DiskImageError::SeekError)?;
+        self.command(&[FRAME_SEEK, 3, c])?;
+        self.head = ch.h();
+        Ok(())
+    }
+
+    fn read_revolutions(&mut self, revolutions: u8) -> Result<Vec<CapturedRevolution>, DiskImageError> {
+        // The device reads for a length of time rather than a number of index pulses. The read is
+        // synchronized to the index, but read a spare revolution in case the first pulse is missed.
+        let period = self.rotation_period_ms()? as u32;
+        let read_ms = u16::try_from(period * (revolutions as u32 + 2)).map_err(|_| DiskImageError::ParameterError)?;
+
+        let mut frame = vec![FRAME_READ, 7, self.head, 1];
+        frame.extend_from_slice(&read_ms.to_le_bytes());
+        frame.push(0);
+        self.transport.write_command(&frame)?;
+        let stream = self.read_stream()?;
+        // The reply to the read command follows the flux.
+        match self.transport.read_command()?.first() {
+            Some(&t) if t == FRAME_READ + 1 => {}
+            Some(&FRAME_ERROR) => return Err(error_result(FRAME_READ, 0)),
+            _ => return Err(invalid_reply(FRAME_READ)),
+        }
+
+        let (flux, index) = decode_flux(&stream);
+        let mut revs = split_revolutions(&flux, &index, TICK_FREQ);
+        revs.truncate(revolutions as usize);
+        Ok(revs)
+    }
+
+    fn write_revolution(&mut self, flux: &[f64], retries: u8) -> Result<(), DiskImageError> {
+        let data = encode_flux(flux);
+        let mut attempt = 0;
+        loop {
+            let mut frame = vec![FRAME_WRITE, 8, self.head];
+            frame.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            frame.push(0);
+            self.transport.write_command(&frame)?;
+            self.transport.write_data(&data)?;
+
+            let reply = self.transport.read_command()?;
+            match reply.first() {
+                Some(&t) if t == FRAME_WRITE + 1 => return Ok(()),
+                Some(&FRAME_ERROR) => {
+                    let code = reply.get(2).copied().unwrap_or(0);
+                    if code == ERROR_UNDERRUN && attempt < retries {
+                        attempt += 1;
+                        log::warn!("write_revolution(): Flux underrun, retrying ({}/{})", attempt, retries);
+                        continue;
+                    }
+                    return Err(error_result(FRAME_WRITE, code));
+                }
+                _ => return Err(invalid_reply(FRAME_WRITE)),
+            }
+        }
+    }
+}
+
+fn invalid_reply(frame: u8) -> DiskImageError {
+    log::error!("command(): Invalid reply to command frame {}", frame);
+    DiskImageError::HardwareError(format!("Invalid reply to command frame {}", frame))
+}
+
+/// Convert an error code into an error.
+fn error_result(frame: u8, code: u8) -> DiskImageError {
+    let msg = match code {
+        ERROR_BAD_COMMAND => "Bad command",
+        ERROR_UNDERRUN => "Flux underrun",
+        ERROR_INVALID_VALUE => "Invalid value",
+        _ => "Internal error",
+    };
+    log::error!("command(): Command frame {} failed: {} ({})", frame, msg, code);
+    DiskImageError::HardwareError(format!("{} ({})", msg, code))
+}
+
+/// Decode a flux stream read from the device into flux intervals and index pulse intervals, in
+/// ticks. The first index interval is measured from the start of the stream; the rest are
+/// measured from the preceding index pulse.
+fn decode_flux(stream: &[u8]) -> (Vec<u32>, Vec<u32>) {
+    let mut flux = Vec::with_capacity(stream.len());
+    let mut index = Vec::new();
+    let mut ticks = 0u32;
+    let mut elapsed = 0u64;
+    let mut last_index = 0u64;
+
+    for &b in stream {
+        ticks += (b & F_TICK_MASK) as u32;
+        if b & F_BIT_INDEX != 0 {
+            let pos = elapsed + ticks as u64;
+            index.push((pos - last_index) as u32);
+            last_index = pos;
+        }
+        if b & F_BIT_PULSE != 0 {
+            flux.push(ticks);
+            elapsed += ticks as u64;
+            ticks = 0;
+        }
+    }
+    (flux, index)
+}
+
+/// Encode flux intervals, in seconds, into a flux stream for the device, padded to a whole
+/// number of frames.
+fn encode_flux(flux: &[f64]) -> Vec<u8> {
+    let mut stream = Vec::with_capacity(flux.len() + FRAME_SIZE);
+    for &delta in flux {
+        let mut ticks = (delta * TICK_FREQ as f64).round() as u32;
+        if ticks == 0 {
+            continue;
+        }
+        while ticks > F_TICK_MASK as u32 {
+            stream.push(F_TICK_MASK);
+            ticks -= F_TICK_MASK as u32;
+        }
+        stream.push(F_BIT_PULSE | ticks as u8);
+    }
+    let padded = stream.len().div_ceil(FRAME_SIZE) * FRAME_SIZE;
+    stream.resize(padded, 0);
+    stream
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// A transport that replays canned device responses and records what is sent to it.
+    #[derive(Default)]
+    struct MockTransport {
+        replies:  VecDeque<Vec<u8>>,
+        data_in:  VecDeque<Vec<u8>>,
+        commands: Vec<Vec<u8>>,
+        data_out: Vec<u8>,
+    }
+
+    impl FluxEngineTransport for MockTransport {
+        fn write_command(&mut self, frame: &[u8]) -> Result<(), DiskImageError> {
+            self.commands.push(frame.to_vec());
+            Ok(())
+        }
+        fn read_command(&mut self) -> Result<Vec<u8>, DiskImageError> {
+            self.replies
+                .pop_front()
+                .ok_or(DiskImageError::IoError("No reply".to_string()))
+        }
+        fn write_data(&mut self, data: &[u8]) -> Result<(), DiskImageError> {
+            self.data_out.extend_from_slice(data);
+            Ok(())
+        }
+        fn read_data(&mut self, buf: &mut [u8]) -> Result<usize, DiskImageError> {
+            let transfer = self.data_in.pop_front().unwrap_or_default();
+            buf[..transfer.len()].copy_from_slice(&transfer);
+            Ok(transfer.len())
+        }
+    }
+
+    #[test]
+    fn test_fluxengine_flux_roundtrip() {
+        let ticks = [24u32, 36, 48, 63, 64, 126, 127, 1000];
+        let flux: Vec<f64> = ticks.iter().map(|&t| t as f64 / TICK_FREQ as f64).collect();
+
+        let stream = encode_flux(&flux);
+        assert_eq!(stream.len() % FRAME_SIZE, 0);
+        let (decoded, index) = decode_flux(&stream);
+        assert!(index.is_empty());
+        assert_eq!(decoded, ticks);
+    }
+
+    #[test]
+    fn test_fluxengine_read_revolutions() {
+        // Index pulses at ticks 24, 120 and 216, with a flux transition every 24 ticks.
+        let mut stream = Vec::new();
+        for i in 0..10 {
+            let index = if i % 4 == 0 { F_BIT_INDEX } else { 0 };
+            stream.push(F_BIT_PULSE | index | 24);
+        }
+        let mut transport = MockTransport {
+            replies: [
+                vec![FRAME_GET_VERSION + 1, 3, 18],
+                vec![FRAME_MEASURE_SPEED + 1, 4, 200, 0],
+                vec![FRAME_READ + 1, 2],
+            ]
+            .into(),
+            ..Default::default()
+        };
+        transport.data_in.push_back(stream);
+
+        let mut fe = FluxEngine::new(transport).unwrap();
+        assert_eq!(fe.version(), 18);
+        let revolutions = fe.read_revolutions(2).unwrap();
+        assert_eq!(revolutions.len(), 2);
+        for rev in &revolutions {
+            assert_eq!(rev.flux.len(), 4);
+            assert!((rev.index_time - 96.0 / TICK_FREQ as f64).abs() < 1e-12);
+        }
+        // 4 revolutions of 200ms are read.
+        assert_eq!(fe.transport.commands[2], vec![FRAME_READ, 7, 0, 1, 0x20, 0x03, 0]);
+    }
+}
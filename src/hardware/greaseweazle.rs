@@ -0,0 +1,544 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/hardware/greaseweazle.rs
+
+    Support for the Greaseweazle USB floppy controller.
+
+    The Greaseweazle presents a USB CDC serial port. Each command is sent as a
+    command byte, the total length of the command in bytes, and the command's
+    arguments; the device replies with the command byte and an ack code.
+
+    Flux is streamed as a byte sequence terminated by a 0 byte. Bytes 1-249
+    are flux intervals in sample ticks. Bytes 250-254 begin a two-byte
+    interval of 250-1524 ticks. Byte 255 begins an opcode: Index marks an
+    index pulse, Space adds to the next interval, and Astable (written only)
+    requests a region with no flux. Opcode arguments are 28-bit values packed
+    into four bytes with the low bit of each byte set, so that no byte of the
+    stream but the terminator is 0.
+*/
+
+use crate::{
+    hardware::{split_revolutions, BusType, CapturedRevolution, DriveSelect, FluxSource, PinLevel},
+    io::{Read, Write},
+    types::DiskCh,
+    DiskImageError,
+};
+use std::time::Duration;
+
+// Command codes.
+const CMD_GET_INFO: u8 = 0;
+const CMD_SEEK: u8 = 2;
+const CMD_HEAD: u8 = 3;
+const CMD_MOTOR: u8 = 6;
+const CMD_READ_FLUX: u8 = 7;
+const CMD_WRITE_FLUX: u8 = 8;
+const CMD_GET_FLUX_STATUS: u8 = 9;
+const CMD_SELECT: u8 = 12;
+const CMD_DESELECT: u8 = 13;
+const CMD_SET_BUS_TYPE: u8 = 14;
+const CMD_SET_PIN: u8 = 15;
+
+// Ack codes.
+const ACK_OKAY: u8 = 0;
+const ACK_NO_INDEX: u8 = 2;
+const ACK_NO_TRK0: u8 = 3;
+const ACK_FLUX_OVERFLOW: u8 = 4;
+const ACK_FLUX_UNDERFLOW: u8 = 5;
+const ACK_WRPROT: u8 = 6;
+const ACK_NO_UNIT: u8 = 7;
+const ACK_NO_BUS: u8 = 8;
+const ACK_BAD_UNIT: u8 = 9;
+const ACK_BAD_CYLINDER: u8 = 11;
+
+// Flux stream opcodes, following a 255 byte.
+const FLUXOP_INDEX: u8 = 1;
+const FLUXOP_SPACE: u8 = 2;
+const FLUXOP_ASTABLE: u8 = 3;
+
+/// The interface pin carrying the density select line.
+const PIN_DENSITY: u8 = 2;
+/// The number of times to retry a read that overflowed the device's buffer.
+const MAX_OVERFLOW_RETRIES: u32 = 5;
+/// The length of the GetInfo response.
+const INFO_LEN: usize = 32;
+/// Flux intervals longer than this, in seconds, are written as a region with no flux.
+const NO_FLUX_THRESHOLD: f64 = 150e-6;
+/// The period of the astable signal used to write a region with no flux, in seconds.
+const NO_FLUX_PERIOD: f64 = 1.25e-6;
+
+/// Information reported by a Greaseweazle about itself.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GreaseweazleInfo {
+    /// The major version of the firmware.
+    pub major: u8,
+    /// The minor version of the firmware.
+    pub minor: u8,
+    /// The hardware model number.
+    pub hw_model: u8,
+    /// The hardware submodel number.
+    pub hw_submodel: u8,
+    /// The frequency at which flux intervals are sampled, in Hz.
+    pub sample_freq: u32,
+}
+
+/// A connection to a Greaseweazle device.
+///
+/// Open a device connected to a serial port with [Greaseweazle::open], or wrap any other
+/// transport with [Greaseweazle::new].
+pub struct Greaseweazle<P: Read + Write> {
+    port: P,
+    info: GreaseweazleInfo,
+}
+
+impl Greaseweazle<Box<dyn serialport::SerialPort>> {
+    /// Open the Greaseweazle connected to the serial port at `path`, such as `COM3` or
+    /// `/dev/ttyACM0`.
+    pub fn open(path: &str) -> Result<Self, DiskImageError> {
+        let mut port = serialport::new(path, 9600)
+            .timeout(Duration::from_secs(5))
+            .open()
+            .map_err(|e| {
+                log::error!("open(): Failed to open serial port {}: {}", path, e);
+                DiskImageError::HardwareError(format!("Failed to open {}: {}", path, e))
+            })?;
+
+        // Setting the baud rate to 10000 tells the device to reset its communication state,
+        // discarding any command or stream left over from a previous session.
+        let reset = |port: &mut Box<dyn serialport::SerialPort>| -> serialport::Result<()> {
+            port.clear(serialport::ClearBuffer::Output)?;
+            port.set_baud_rate(10000)?;
+            port.set_baud_rate(9600)?;
+            port.clear(serialport::ClearBuffer::Input)
+        };
+        reset(&mut port).map_err(|e| DiskImageError::HardwareError(format!("Failed to reset {}: {}", path, e)))?;
+
+        Greaseweazle::new(port)
+    }
+}
+
+impl<P: Read + Write> Greaseweazle<P> {
+    /// Connect to a Greaseweazle over `port`, and query its firmware information.
+    pub fn new(port: P) -> Result<Self, DiskImageError> {
+        let mut gw = Greaseweazle {
+            port,
+            info: GreaseweazleInfo::default(),
+        };
+
+        gw.command(&[CMD_GET_INFO, 3, 0])?;
+        let mut buf = [0u8; INFO_LEN];
+        gw.port.read_exact(&mut buf)?;
+        gw.info = GreaseweazleInfo {
+            major: buf[0],
+            minor: buf[1],
+            hw_model: buf[8],
+            hw_submodel: buf[9],
+            sample_freq: u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]),
+        };
+        if gw.info.sample_freq == 0 {
+            return Err(DiskImageError::HardwareError(
+                "Device reported no sample frequency".to_string(),
+            ));
+        }
+
+        log::debug!(
+            "Greaseweazle::new(): Connected to model {}.{}, firmware v{}.{}, sample frequency {}Hz",
+            gw.info.hw_model,
+            gw.info.hw_submodel,
+            gw.info.major,
+            gw.info.minor,
+            gw.info.sample_freq
+        );
+        Ok(gw)
+    }
+
+    /// Return the information the device reported about itself.
+    pub fn info(&self) -> GreaseweazleInfo {
+        self.info
+    }
+
+    /// Read a flux stream up to and including its 0 terminator.
+    fn read_stream(&mut self) -> Result<Vec<u8>, DiskImageError> {
+        let mut stream = Vec::new();
+        let mut buf = [0u8; 4096];
+        while stream.last() != Some(&0) {
+            let n = self.port.read(&mut buf)?;
+            if n == 0 {
+                return Err(DiskImageError::HardwareError(
+                    "Flux stream ended unexpectedly".to_string(),
+                ));
+            }
+            stream.extend_from_slice(&buf[..n]);
+        }
+        Ok(stream)
+    }
+
+    /// Send a command and check its ack.
+    fn command(&mut self, cmd: &[u8]) -> Result<(), DiskImageError> {
+        let ack = self.command_ack(cmd)?;
+        ack_result(cmd[0], ack)
+    }
+
+    /// Send a command and return its ack code.
+    fn command_ack(&mut self, cmd: &[u8]) -> Result<u8, DiskImageError> {
+        self.port.write_all(cmd)?;
+        self.port.flush()?;
+
+        let mut reply = [0u8; 2];
+        self.port.read_exact(&mut reply)?;
+        if reply[0] != cmd[0] {
+            log::error!("command(): Expected reply to command {}, got {}", cmd[0], reply[0]);
+            return Err(DiskImageError::HardwareError(format!(
+                "Invalid reply to command {}",
+                cmd[0]
+            )));
+        }
+        Ok(reply[1])
+    }
+}
+
+impl<P: Read + Write> FluxSource for Greaseweazle<P> {
+    fn name(&self) -> &str {
+        "Greaseweazle"
+    }
+
+    fn select_drive(&mut self, drive: &DriveSelect) -> Result<(), DiskImageError> {
+        let bus = match drive.bus {
+            BusType::IbmPc => 1,
+            BusType::Shugart => 2,
+        };
+        self.command(&[CMD_SET_BUS_TYPE, 3, bus])?;
+        if let Some(level) = drive.density {
+            self.command(&[CMD_SET_PIN, 4, PIN_DENSITY, (level == PinLevel::High) as u8])?;
+        }
+        self.command(&[CMD_SELECT, 3, drive.unit])?;
+        self.command(&[CMD_MOTOR, 4, drive.unit, 1])
+    }
+
+    fn deselect_drive(&mut self, drive: &DriveSelect) -> Result<(), DiskImageError> {
+        self.command(&[CMD_MOTOR, 4, drive.unit, 0])?;
+        self.command(&[CMD_DESELECT, 2])
+    }
+
+    fn seek(&mut self, ch: DiskCh) -> Result<(), DiskImageError> {
+        match i8::try_from(ch.c()) {
+            Ok(c) => self.command(&[CMD_SEEK, 3, c as u8])?,
+            Err(_) => {
+                let c = ch.c().to_le_bytes();
+                self.command(&[CMD_SEEK, 4, c[0], c[1]])?
+            }
+        }
+        self.command(&[CMD_HEAD, 3, ch.h()])
+    }
+
+    fn read_revolutions(&mut self, revolutions: u8) -> Result<Vec<CapturedRevolution>, DiskImageError> {
+        // The device may not be able to keep up with a USB bus under load. Overflows are transient,
+        // so retry a few times before giving up.
+        let mut overflows = 0;
+        loop {
+            // Read until one more index pulse than revolutions, so every revolution is complete.
+            let index_ct = (revolutions as u16 + 1).to_le_bytes();
+            let mut cmd = vec![CMD_READ_FLUX, 8, 0, 0, 0, 0];
+            cmd.extend_from_slice(&index_ct);
+            self.command(&cmd)?;
+
+            let stream = self.read_stream()?;
+            let ack = self.command_ack(&[CMD_GET_FLUX_STATUS, 2])?;
+            if ack == ACK_FLUX_OVERFLOW && overflows < MAX_OVERFLOW_RETRIES {
+                overflows += 1;
+                log::warn!(
+                    "read_revolutions(): Flux overflow, retrying ({}/{})",
+                    overflows,
+                    MAX_OVERFLOW_RETRIES
+                );
+                continue;
+            }
+            ack_result(CMD_GET_FLUX_STATUS, ack)?;
+
+            let (flux, index) = decode_flux(&stream)?;
+            return Ok(split_revolutions(&flux, &index, self.info.sample_freq));
+        }
+    }
+
+    fn write_revolution(&mut self, flux: &[f64], retries: u8) -> Result<(), DiskImageError> {
+        let stream = encode_flux(flux, self.info.sample_freq);
+        let mut attempt = 0;
+        loop {
+            self.command(&[CMD_WRITE_FLUX, 4, 1, 1])?;
+            self.port.write_all(&stream)?;
+            self.port.flush()?;
+            // The device sends a single byte once it has finished writing.
+            let mut sync = [0u8; 1];
+            self.port.read_exact(&mut sync)?;
+
+            let ack = self.command_ack(&[CMD_GET_FLUX_STATUS, 2])?;
+            if ack == ACK_FLUX_UNDERFLOW && attempt < retries {
+                attempt += 1;
+                log::warn!("write_revolution(): Flux underflow, retrying ({}/{})", attempt, retries);
+                continue;
+            }
+            return ack_result(CMD_GET_FLUX_STATUS, ack);
+        }
+    }
+}
+
+/// Convert an ack code into a result.
+fn ack_result(cmd: u8, ack: u8) -> Result<(), DiskImageError> {
+    let msg = match ack {
+        ACK_OKAY => return Ok(()),
+        ACK_WRPROT => return Err(DiskImageError::WriteProtectError),
+        ACK_NO_INDEX => "No index pulse detected. Is there a disk in the drive?",
+        ACK_NO_TRK0 => "Track 0 not found",
+        ACK_FLUX_OVERFLOW => "Flux overflow",
+        ACK_FLUX_UNDERFLOW => "Flux underflow",
+        ACK_NO_UNIT => "No drive unit selected",
+        ACK_NO_BUS => "No bus type selected",
+        ACK_BAD_UNIT => "Invalid drive unit",
+        ACK_BAD_CYLINDER => "Invalid cylinder",
+        _ => "Unknown error",
+    };
+    log::error!("command(): Command {} failed: {} ({})", cmd, msg, ack);
+    Err(DiskImageError::HardwareError(format!("{} ({})", msg, ack)))
+}
+
+/// Read a 28-bit opcode argument from the flux stream.
+fn read_28(bytes: &[u8]) -> Option<u32> {
+    let b = bytes.get(..4)?;
+    Some(
+        ((b[0] as u32 & 0xFE) >> 1)
+            | ((b[1] as u32 & 0xFE) << 6)
+            | ((b[2] as u32 & 0xFE) << 13)
+            | ((b[3] as u32 & 0xFE) << 20),
+    )
+}
+
+/// Encode a 28-bit opcode argument for the flux stream.
+fn write_28(value: u32, out: &mut Vec<u8>) {
+    out.extend_from_slice(&[
+        1 | (value << 1) as u8,
+        1 | (value >> 6) as u8,
+        1 | (value >> 13) as u8,
+        1 | (value >> 20) as u8,
+    ]);
+}
+
+/// Decode a flux stream read from the device into flux intervals and index pulse intervals, in
+/// sample ticks. The first index interval is measured from the start of the stream; the rest are
+/// measured from the preceding index pulse.
+fn decode_flux(stream: &[u8]) -> Result<(Vec<u32>, Vec<u32>), DiskImageError> {
+    let truncated = || DiskImageError::HardwareError("Truncated flux stream".to_string());
+
+    let mut flux = Vec::with_capacity(stream.len());
+    let mut index = Vec::new();
+    let mut ticks: i64 = 0;
+    let mut ticks_since_index: i64 = 0;
+    let mut pos = 0;
+
+    while let Some(&b) = stream.get(pos) {
+        pos += 1;
+        let value = match b {
+            0 => break,
+            1..=249 => b as i64,
+            250..=254 => {
+                let next = *stream.get(pos).ok_or_else(truncated)?;
+                pos += 1;
+                250 + (b as i64 - 250) * 255 + next as i64 - 1
+            }
+            255 => {
+                let opcode = *stream.get(pos).ok_or_else(truncated)?;
+                let arg = read_28(&stream[pos + 1..]).ok_or_else(truncated)? as i64;
+                pos += 5;
+                match opcode {
+                    FLUXOP_INDEX => {
+                        index.push((ticks_since_index + ticks + arg) as u32);
+                        ticks_since_index = -(ticks + arg);
+                    }
+                    FLUXOP_SPACE => ticks += arg,
+                    _ => {
+                        log::error!("decode_flux(): Unknown flux opcode: {}", opcode);
+                        return Err(DiskImageError::HardwareError(format!("Unknown flux opcode {}", opcode)));
+                    }
+                }
+                continue;
+            }
+        };
+        ticks += value;
+        flux.push(ticks as u32);
+        ticks_since_index += ticks;
+        ticks = 0;
+    }
+    Ok((flux, index))
+}
+
+/// Encode flux intervals, in seconds, into a flux stream for the device sampling at
+/// `sample_freq` Hz.
+fn encode_flux(flux: &[f64], sample_freq: u32) -> Vec<u8> {
+    let freq = sample_freq as f64;
+    let no_flux_threshold = (NO_FLUX_THRESHOLD * freq).round() as u32;
+    let no_flux_period = (NO_FLUX_PERIOD * freq).round() as u32;
+
+    let mut stream = Vec::with_capacity(flux.len() + 1);
+    for &delta in flux {
+        let value = (delta * freq).round() as u32;
+        if value == 0 {
+            continue;
+        }
+        else if value < 250 {
+            stream.push(value as u8);
+        }
+        else if value > no_flux_threshold {
+            stream.extend_from_slice(&[255, FLUXOP_SPACE]);
+            write_28(value, &mut stream);
+            stream.extend_from_slice(&[255, FLUXOP_ASTABLE]);
+            write_28(no_flux_period, &mut stream);
+        }
+        else {
+            let high = (value - 250) / 255;
+            if high < 5 {
+                stream.push(250 + high as u8);
+                stream.push(1 + ((value - 250) % 255) as u8);
+            }
+            else {
+                stream.extend_from_slice(&[255, FLUXOP_SPACE]);
+                write_28(value - 249, &mut stream);
+                stream.push(249);
+            }
+        }
+    }
+    stream.push(0);
+    stream
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// A transport that replays canned device responses and records what is sent to it. Like a
+    /// serial port, it returns no more than has been sent so far; here, a byte at a time.
+    struct MockPort {
+        input:  VecDeque<u8>,
+        output: Vec<u8>,
+    }
+
+    impl Read for MockPort {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(1);
+            self.input.read(&mut buf[..n])
+        }
+    }
+
+    impl Write for MockPort {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.output.write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    const SAMPLE_FREQ: u32 = 72_000_000;
+
+    fn info_reply() -> Vec<u8> {
+        let mut reply = vec![CMD_GET_INFO, ACK_OKAY, 1, 2, 0, 22];
+        reply.extend_from_slice(&SAMPLE_FREQ.to_le_bytes());
+        reply.extend_from_slice(&[4, 1, 2]);
+        reply.resize(2 + INFO_LEN, 0);
+        reply
+    }
+
+    #[test]
+    fn test_greaseweazle_flux_roundtrip() {
+        let ticks = [3, 100, 249, 250, 400, 1524, 1525, 5000, 10000];
+        let flux: Vec<f64> = ticks.iter().map(|&t| t as f64 / SAMPLE_FREQ as f64).collect();
+
+        let stream = encode_flux(&flux, SAMPLE_FREQ);
+        assert_eq!(stream.last(), Some(&0));
+        assert!(!stream[..stream.len() - 1].contains(&0));
+
+        let (decoded, index) = decode_flux(&stream).unwrap();
+        assert!(index.is_empty());
+        assert_eq!(decoded, ticks);
+
+        // An interval over the no flux threshold is written as a space and an astable region.
+        let stream = encode_flux(&[200e-6], SAMPLE_FREQ);
+        assert_eq!(&stream[..2], &[255, FLUXOP_SPACE]);
+        assert_eq!(read_28(&stream[2..]), Some(14400));
+        assert_eq!(&stream[6..8], &[255, FLUXOP_ASTABLE]);
+        assert_eq!(read_28(&stream[8..]), Some(90));
+    }
+
+    #[test]
+    fn test_greaseweazle_read_revolutions() {
+        // Index pulses at ticks 150, 450 and 750, with a flux transition every 100 ticks.
+        let mut stream = vec![100];
+        for _ in 0..3 {
+            stream.extend_from_slice(&[255, FLUXOP_INDEX]);
+            write_28(50, &mut stream);
+            stream.extend_from_slice(&[100, 100, 100]);
+        }
+        stream.push(0);
+
+        let mut input = info_reply();
+        input.extend_from_slice(&[CMD_READ_FLUX, ACK_OKAY]);
+        input.extend_from_slice(&stream);
+        input.extend_from_slice(&[CMD_GET_FLUX_STATUS, ACK_OKAY]);
+
+        let port = MockPort {
+            input:  input.into(),
+            output: Vec::new(),
+        };
+        let mut gw = Greaseweazle::new(port).unwrap();
+        assert_eq!(gw.info().sample_freq, SAMPLE_FREQ);
+        assert_eq!((gw.info().major, gw.info().minor), (1, 2));
+
+        let revolutions = gw.read_revolutions(2).unwrap();
+        assert_eq!(revolutions.len(), 2);
+        for rev in &revolutions {
+            assert_eq!(rev.flux.len(), 3);
+            assert!((rev.index_time - 300.0 / SAMPLE_FREQ as f64).abs() < 1e-12);
+        }
+        assert_eq!(
+            &gw.port.output[3..],
+            &[CMD_READ_FLUX, 8, 0, 0, 0, 0, 3, 0, CMD_GET_FLUX_STATUS, 2]
+        );
+    }
+
+    #[test]
+    fn test_greaseweazle_write_protect() {
+        let mut input = info_reply();
+        input.extend_from_slice(&[CMD_WRITE_FLUX, ACK_WRPROT]);
+        let port = MockPort {
+            input:  input.into(),
+            output: Vec::new(),
+        };
+        let mut gw = Greaseweazle::new(port).unwrap();
+        assert!(matches!(
+            gw.write_revolution(&[2e-6; 16], 0),
+            Err(DiskImageError::WriteProtectError)
+        ));
+    }
+}
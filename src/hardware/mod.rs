@@ -0,0 +1,408 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/hardware/mod.rs
+
+    Capture of flux images directly from floppy imaging hardware, and writing
+    of images back to physical media.
+*/
+
+//! Floppy imaging hardware.
+//!
+//! This module talks to floppy controllers that capture raw flux from a physical drive, so that
+//! a disk can be read directly into a `FluxStream` resolution [DiskImage](crate::DiskImage)
+//! without an intermediate flux file, and so that an image can be written back to a disk.
+//!
+//! Each supported device implements [FluxSource], which provides
+//! [read_disk](FluxSource::read_disk) and [write_disk](FluxSource::write_disk). The supported
+//! devices are the [Greaseweazle], the [FluxEngine] and the [Applesauce]. This module requires
+//! the `hardware` feature, and is not available on wasm.
+
+pub mod applesauce;
+pub mod fluxengine;
+pub mod greaseweazle;
+
+pub use applesauce::Applesauce;
+pub use fluxengine::FluxEngine;
+pub use greaseweazle::Greaseweazle;
+
+use crate::{
+    flux::synthesis::{synthesize_track_flux, FluxSynthesisOptions},
+    track::{fluxstream::FluxStreamTrack, Track},
+    types::{DiskCh, FluxStreamTrackParams, SharedDiskContext, TrackDataResolution, TrackDensity},
+    DiskImage,
+    DiskImageError,
+    LoadingCallback,
+    LoadingStatus,
+    SavingCallback,
+    SavingStatus,
+};
+use std::sync::{Arc, Mutex};
+
+/// The interface of the floppy drive bus a drive is connected to.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum BusType {
+    /// An IBM PC style bus, with a twisted cable. Unit 0 is drive A, unit 1 is drive B.
+    #[default]
+    IbmPc,
+    /// A Shugart bus, with a straight cable. Units 0-3 are drive selects DS0-DS3.
+    Shugart,
+}
+
+/// The level to drive an interface pin to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PinLevel {
+    Low,
+    High,
+}
+
+/// The drive to read from or write to.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DriveSelect {
+    /// The bus the drive is connected to.
+    pub bus: BusType,
+    /// The unit number of the drive on the bus.
+    pub unit: u8,
+    /// The level to drive the density select line (pin 2) to, if any. Most PC drives select high
+    /// density when the line is high, and some other drives require it to be set for double
+    /// density media. If `None`, the line is left as it is.
+    pub density: Option<PinLevel>,
+}
+
+/// Options for reading a disk from a drive.
+#[derive(Copy, Clone, Debug)]
+pub struct CaptureOptions {
+    /// The drive to read from.
+    pub drive: DriveSelect,
+    /// The number of cylinders to read.
+    pub cylinders: u16,
+    /// The number of heads to read.
+    pub heads: u8,
+    /// The number of revolutions to capture from each track.
+    pub revolutions: u8,
+    /// The number of times to re-read a track that decodes with sector errors. The revolutions
+    /// of each read are combined, so that fluxfox can select the best of them.
+    pub retries: u8,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        CaptureOptions {
+            drive: DriveSelect::default(),
+            cylinders: 80,
+            heads: 2,
+            revolutions: 3,
+            retries: 3,
+        }
+    }
+}
+
+/// Options for writing a disk image to a drive.
+#[derive(Copy, Clone, Debug)]
+pub struct WriteOptions {
+    /// The drive to write to.
+    pub drive:   DriveSelect,
+    /// The number of times to retry writing a track if the device could not keep up with the
+    /// flux data.
+    pub retries: u8,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            drive:   DriveSelect::default(),
+            retries: 3,
+        }
+    }
+}
+
+/// One revolution of flux read from a track, between two index pulses.
+#[derive(Clone, Debug, Default)]
+pub struct CapturedRevolution {
+    /// The intervals between flux transitions, in seconds.
+    pub flux: Vec<f64>,
+    /// The time between the index pulses bounding the revolution, in seconds.
+    pub index_time: f64,
+}
+
+/// A device that can read flux from, and write flux to, a floppy drive.
+///
+/// A device implements the low level operations: selecting a drive, seeking, and reading or
+/// writing the flux of a single track. Reading and writing whole disks is provided on top of
+/// these, so that every device feeds the same decoding pipeline and produces the same kind of
+/// [DiskImage].
+pub trait FluxSource {
+    /// Return the name of the device, for logging.
+    fn name(&self) -> &str;
+    /// Select `drive` and turn its motor on.
+    fn select_drive(&mut self, drive: &DriveSelect) -> Result<(), DiskImageError>;
+    /// Turn the motor of `drive` off and deselect it.
+    fn deselect_drive(&mut self, drive: &DriveSelect) -> Result<(), DiskImageError>;
+    /// Seek the selected drive to the cylinder of `ch`, and select its head.
+    fn seek(&mut self, ch: DiskCh) -> Result<(), DiskImageError>;
+    /// Read `revolutions` complete revolutions of flux from the current track.
+    fn read_revolutions(&mut self, revolutions: u8) -> Result<Vec<CapturedRevolution>, DiskImageError>;
+    /// Write flux intervals, in seconds, to the current track, starting at the index and stopping
+    /// at the next index. A write the device could not keep up with is retried up to `retries`
+    /// times.
+    fn write_revolution(&mut self, flux: &[f64], retries: u8) -> Result<(), DiskImageError>;
+
+    /// Read a disk from the selected drive into a new `FluxStream` resolution [DiskImage].
+    ///
+    /// Each track is read for `opts.revolutions` revolutions. If a track decodes with sector
+    /// errors, it is read again up to `opts.retries` times. If `callback` is supplied, it receives
+    /// [LoadingStatus::Track] and [LoadingStatus::Progress] updates as each track is read.
+    ///
+    /// # Returns
+    /// - `Err(DiskImageError::HardwareError)` if the device reports an error, such as no index
+    ///   pulse being detected because there is no disk in the drive.
+    fn read_disk(
+        &mut self,
+        opts: &CaptureOptions,
+        callback: Option<LoadingCallback>,
+    ) -> Result<DiskImage, DiskImageError> {
+        if opts.heads == 0 || opts.heads > 2 || opts.cylinders == 0 || opts.revolutions == 0 {
+            return Err(DiskImageError::ParameterError);
+        }
+        if let Some(callback) = &callback {
+            callback(LoadingStatus::ProgressSupport);
+        }
+
+        self.select_drive(&opts.drive)?;
+        let result = read_tracks(self, opts, callback.as_ref());
+        let deselect_result = self.deselect_drive(&opts.drive);
+        let mut disk = result?;
+        deselect_result?;
+
+        if let Some(callback) = &callback {
+            callback(LoadingStatus::Complete);
+        }
+        disk.post_load_process();
+        Ok(disk)
+    }
+
+    /// Write `disk` to the selected drive. Each track is written from its bitstream, starting at
+    /// the index. If `callback` is supplied, it receives [SavingStatus::Track] updates as each
+    /// track is written.
+    ///
+    /// # Returns
+    /// - `Err(DiskImageError::UnsupportedFormat)` if a track of the image has no bitstream, such as
+    ///   a track of `MetaSector` resolution.
+    /// - `Err(DiskImageError::WriteProtectError)` if the disk in the drive is write protected.
+    fn write_disk(
+        &mut self,
+        disk: &DiskImage,
+        opts: &WriteOptions,
+        callback: Option<SavingCallback>,
+    ) -> Result<(), DiskImageError> {
+        if disk.resolution().contains(&TrackDataResolution::MetaSector) {
+            log::error!("write_disk(): MetaSector resolution images cannot be written to a disk.");
+            return Err(DiskImageError::UnsupportedFormat);
+        }
+
+        self.select_drive(&opts.drive)?;
+        let result = write_tracks(self, disk, opts, callback.as_ref());
+        let deselect_result = self.deselect_drive(&opts.drive);
+        result?;
+        deselect_result?;
+
+        if let Some(callback) = &callback {
+            callback(SavingStatus::Complete);
+        }
+        Ok(())
+    }
+}
+
+/// Read every track of a disk from `source` into a new disk image.
+fn read_tracks<S: FluxSource + ?Sized>(
+    source: &mut S,
+    opts: &CaptureOptions,
+    callback: Option<&LoadingCallback>,
+) -> Result<DiskImage, DiskImageError> {
+    let mut disk = DiskImage::default();
+    disk.set_resolution(TrackDataResolution::FluxStream);
+
+    let total = opts.cylinders as usize * opts.heads as usize;
+    for c in 0..opts.cylinders {
+        for h in 0..opts.heads {
+            let ch = DiskCh::new(c, h);
+            let index = c as usize * opts.heads as usize + h as usize;
+            if let Some(callback) = callback {
+                callback(LoadingStatus::Track { ch, index, total });
+            }
+
+            // Once a track has been read, use the disk descriptor as a hint for the rest.
+            let (clock, rpm) = if !disk.track_pool.is_empty() {
+                (
+                    Some(disk.descriptor.density.base_clock(disk.descriptor.rpm)),
+                    disk.descriptor.rpm,
+                )
+            }
+            else {
+                (None, None)
+            };
+            let params = FluxStreamTrackParams {
+                ch,
+                schema: None,
+                encoding: None,
+                clock,
+                rpm,
+            };
+
+            let flux_track = capture_track(source, ch, opts, &params)?;
+            let info = disk.add_track_fluxstream(flux_track, &params)?.info();
+            if info.sector_ct > 0 {
+                disk.descriptor.data_rate = info.data_rate;
+                disk.descriptor.data_encoding = info.encoding;
+                disk.descriptor.density = info.density.unwrap_or(TrackDensity::from(info.data_rate));
+                disk.descriptor.rpm = info.rpm;
+            }
+            else {
+                log::warn!("read_tracks(): Track {} did not decode any sectors.", ch);
+            }
+
+            if let Some(callback) = callback {
+                callback(LoadingStatus::Progress((index + 1) as f64 / total as f64));
+            }
+        }
+    }
+
+    disk.descriptor.geometry = DiskCh::new(opts.cylinders, opts.heads);
+    disk.descriptor.write_protect = Some(false);
+    Ok(disk)
+}
+
+/// Read a track, reading it again while it decodes with sector errors and retries remain.
+fn capture_track<S: FluxSource + ?Sized>(
+    source: &mut S,
+    ch: DiskCh,
+    opts: &CaptureOptions,
+    params: &FluxStreamTrackParams,
+) -> Result<FluxStreamTrack, DiskImageError> {
+    source.seek(ch)?;
+
+    let mut flux_track = FluxStreamTrack::new();
+    let mut attempt = 0;
+    loop {
+        for rev in source.read_revolutions(opts.revolutions)? {
+            flux_track.add_revolution(ch, &rev.flux, rev.index_time);
+        }
+        if attempt >= opts.retries {
+            break;
+        }
+
+        // Decode a copy of the track to check it. Unformatted tracks have no sectors and are
+        // not retried.
+        let mut trial = flux_track.clone();
+        trial.set_ch(ch);
+        trial.set_shared(Arc::new(Mutex::new(SharedDiskContext::default())));
+        trial.decode(params.clock, params.rpm)?;
+        let errors = trial
+            .sector_list()
+            .iter()
+            .filter(|s| s.attributes.address_error || s.attributes.data_error || s.attributes.no_dam)
+            .count();
+        if errors == 0 {
+            break;
+        }
+
+        attempt += 1;
+        log::warn!(
+            "capture_track(): {}: Track {} has {} bad sectors, retrying ({}/{})",
+            source.name(),
+            ch,
+            errors,
+            attempt,
+            opts.retries
+        );
+    }
+    Ok(flux_track)
+}
+
+/// Write every track of `disk` to `source`.
+fn write_tracks<S: FluxSource + ?Sized>(
+    source: &mut S,
+    disk: &DiskImage,
+    opts: &WriteOptions,
+    callback: Option<&SavingCallback>,
+) -> Result<(), DiskImageError> {
+    let tracks: Vec<_> = disk.iter_tracks().collect();
+    for (index, (ch, track)) in tracks.iter().enumerate() {
+        if let Some(callback) = callback {
+            callback(SavingStatus::Track {
+                ch: *ch,
+                index,
+                total: tracks.len(),
+            });
+        }
+
+        // Synthesize two revolutions so that the flux runs past the index, where the device
+        // stops writing.
+        let synth_opts = FluxSynthesisOptions::default().with_revolutions(2);
+        let flux: Vec<f64> = synthesize_track_flux(*track, &synth_opts)?
+            .into_iter()
+            .flat_map(|rev| rev.flux_deltas)
+            .collect();
+
+        source.seek(*ch)?;
+        source.write_revolution(&flux, opts.retries)?;
+    }
+    Ok(())
+}
+
+/// Split flux intervals into complete revolutions between index pulses, converting ticks at
+/// `sample_freq` Hz into seconds. `index` holds the intervals between index pulses, the first
+/// measured from the start of the flux. Flux before the first index pulse and after the last is
+/// discarded.
+fn split_revolutions(flux: &[u32], index: &[u32], sample_freq: u32) -> Vec<CapturedRevolution> {
+    let tick_time = 1.0 / sample_freq as f64;
+    let mut revolutions = Vec::new();
+
+    let mut flux_iter = flux.iter().copied().peekable();
+    let mut elapsed: u64 = 0;
+    let mut index_pos: u64 = 0;
+    for (i, &interval) in index.iter().enumerate() {
+        index_pos += interval as u64;
+        let mut deltas = Vec::new();
+        while let Some(&delta) = flux_iter.peek() {
+            if elapsed + delta as u64 > index_pos {
+                break;
+            }
+            elapsed += delta as u64;
+            if i > 0 {
+                deltas.push(delta as f64 * tick_time);
+            }
+            flux_iter.next();
+        }
+        if i > 0 {
+            revolutions.push(CapturedRevolution {
+                flux: deltas,
+                index_time: interval as f64 * tick_time,
+            });
+        }
+    }
+    revolutions
+}
@@ -0,0 +1,470 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/hash_manifest.rs
+
+    Per-sector, per-track and whole-image digests for comparison against
+    preservation databases.
+*/
+
+//! Hash manifests.
+//!
+//! [DiskImage::hash_manifest()] computes SHA1 and CRC32 digests of the sector data of an image at
+//! three levels: each sector, each track, and the whole image. Track and image digests cover the
+//! sector data in sector ID order, so the image digest of a standard format image matches the
+//! digest of the equivalent raw sector image, as recorded by most preservation databases.
+//!
+//! [DiskImage::fingerprint()] computes a single content-addressed [Fingerprint] from the sector
+//! IDs and sector data of an image, ignoring everything a container format may or may not
+//! preserve, so that the same disk imaged into different formats produces the same fingerprint.
+//!
+//! Digests are computed over the data stored in the image. Weak bits are hashed as stored, rather
+//! than randomized as they would be when read. With [HashManifestOptions::exclude_masked], bits
+//! covered by a weak or hole mask are hashed as zero instead, so that images of the same disk that
+//! differ only in the contents of their weak regions produce the same digests.
+
+use crate::{
+    bitstream_codec::mfm::MFM_BYTE_LEN,
+    file_parsers::r#as::crc::applesauce_crc32,
+    track::Track,
+    track_schema::TrackElement,
+    types::{DiskCh, DiskChsn, RwScope},
+    DiskImage,
+    SectorIdQuery,
+};
+use std::fmt::{Display, Formatter, Result};
+
+/// Options for [DiskImage::hash_manifest()].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct HashManifestOptions {
+    /// Hash bits covered by a weak or hole mask as zero.
+    pub exclude_masked: bool,
+}
+
+/// The version of the fingerprint algorithm. Hashed first, so that a change to the algorithm
+/// changes every fingerprint.
+const FINGERPRINT_VERSION: &[u8] = b"fluxfox-fingerprint-1";
+
+/// A sector's ID fields as (c, h, s, n) and its stored data, in the order they are fingerprinted.
+type FingerprintSector = ((u16, u8, u8, u8), Vec<u8>);
+
+/// A content-addressed fingerprint of a disk image, returned by [DiskImage::fingerprint()].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fingerprint(pub [u8; 20]);
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        for b in &self.0 {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// A SHA1 and CRC32 digest of a block of data.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentDigest {
+    pub sha1:  [u8; 20],
+    pub crc32: u32,
+}
+
+impl ContentDigest {
+    /// Compute the digest of a single buffer.
+    pub fn of(data: &[u8]) -> Self {
+        let mut hasher = DigestBuilder::new();
+        hasher.update(data);
+        hasher.finish()
+    }
+
+    /// Return the SHA1 digest as a lowercase hexadecimal string.
+    pub fn sha1_hex(&self) -> String {
+        self.sha1.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Return the CRC32 as a lowercase hexadecimal string.
+    pub fn crc32_hex(&self) -> String {
+        format!("{:08x}", self.crc32)
+    }
+}
+
+impl Display for ContentDigest {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "crc32:{} sha1:{}", self.crc32_hex(), self.sha1_hex())
+    }
+}
+
+struct DigestBuilder {
+    sha1:  sha1_smol::Sha1,
+    crc32: u32,
+}
+
+impl DigestBuilder {
+    fn new() -> Self {
+        Self {
+            sha1:  sha1_smol::Sha1::new(),
+            crc32: 0,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.sha1.update(data);
+        self.crc32 = applesauce_crc32(data, self.crc32);
+    }
+
+    fn finish(&self) -> ContentDigest {
+        ContentDigest {
+            sha1:  self.sha1.digest().bytes(),
+            crc32: self.crc32,
+        }
+    }
+}
+
+/// The digest of a single sector.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SectorHash {
+    /// The physical cylinder and head of the track containing the sector.
+    pub ch: DiskCh,
+    /// The ID of the sector.
+    pub id: DiskChsn,
+    /// The length of the sector data, in bytes.
+    pub len: usize,
+    /// The number of bytes containing weak or hole-masked bits.
+    pub masked_bytes: usize,
+    pub digest: ContentDigest,
+}
+
+/// The digest of the sector data of a single track.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackHash {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    /// The number of sectors with data on the track.
+    pub sector_ct: usize,
+    /// The digest of the track's sector data, in sector ID order.
+    pub digest: ContentDigest,
+    /// The SHA1 digest of the track as returned by [Track::hash]. For `BitStream` tracks this
+    /// covers the raw track bitstream, including gaps and address marks.
+    pub track_sha1: [u8; 20],
+}
+
+/// Per-sector, per-track and whole-image digests of a [DiskImage].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HashManifest {
+    /// The digest of all sector data in the image, in track order and then sector ID order.
+    pub image:   ContentDigest,
+    pub tracks:  Vec<TrackHash>,
+    /// The digest of each sector, in the order sectors appear on each track.
+    pub sectors: Vec<SectorHash>,
+}
+
+impl HashManifest {
+    /// Return the digest of the first sector matching `id` on track `ch`.
+    pub fn sector(&self, ch: DiskCh, id: DiskChsn) -> Option<&SectorHash> {
+        self.sectors.iter().find(|s| s.ch == ch && s.id == id)
+    }
+
+    /// Return the digest of track `ch`.
+    pub fn track(&self, ch: DiskCh) -> Option<&TrackHash> {
+        self.tracks.iter().find(|t| t.ch == ch)
+    }
+}
+
+impl Display for HashManifest {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        writeln!(f, "image {}", self.image)?;
+        for track in &self.tracks {
+            writeln!(f, "track {} {}", track.ch, track.digest)?;
+        }
+        for sector in &self.sectors {
+            writeln!(f, "sector {} {} {}", sector.ch, sector.id, sector.digest)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read the stored data of each sector on `track`, in the order returned by
+/// [Track::sector_list], along with a mask of the bits that would read back as random data.
+/// Sectors with no data are omitted.
+fn stored_sectors(track: &dyn Track) -> Vec<(DiskChsn, Vec<u8>, Vec<u8>)> {
+    let entries = track.sector_list();
+
+    if let Some(meta_track) = track.as_metasector_track() {
+        return entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.attributes.no_dam)
+            .filter_map(|(index, entry)| {
+                let (data, _) = meta_track.raw_sector_data_at(index)?;
+                let mask = meta_track.read_mask_at(index)?;
+                Some((entry.chsn, data.to_vec(), mask))
+            })
+            .collect();
+    }
+
+    // Decode sector data directly from the bitstream with weak bits disabled, so that the
+    // stored value of weak bits is returned.
+    let (Some(metadata), Some(stream)) = (track.metadata(), track.stream())
+    else {
+        return Vec::new();
+    };
+    let mut stream = stream.clone();
+    stream.enable_weak(false);
+    let weak_mask = stream.weak_mask().clone();
+
+    let mut sectors = Vec::new();
+    for item in metadata.items.iter().filter(|item| item.element.is_sector_data()) {
+        let (Some(chsn), Some(range)) = (item.chsn, item.element.range(RwScope::DataOnly))
+        else {
+            continue;
+        };
+        let bit_start = item.start + range.start * MFM_BYTE_LEN;
+        let len = range.len();
+
+        let (data, mask) = if let TrackElement::System34(_) = item.element {
+            let mut data = vec![0u8; len];
+            stream.read_decoded_buf(&mut data, bit_start);
+            // Each decoded bit is stored in the second bitcell of its pair.
+            let mask = (0..len)
+                .map(|byte| {
+                    (0..8).fold(0u8, |acc, bit| {
+                        let weak = weak_mask
+                            .get(bit_start + byte * MFM_BYTE_LEN + bit * 2 + 1)
+                            .unwrap_or(false);
+                        (acc << 1) | weak as u8
+                    })
+                })
+                .collect();
+            (data, mask)
+        }
+        else {
+            // Other schemas don't store bytes contiguously, so fall back to a normal read, and
+            // mask the whole sector if any of it is weak.
+            let Ok(result) = track.read_sector(SectorIdQuery::from(chsn), None, None, RwScope::DataOnly, false)
+            else {
+                continue;
+            };
+            let weak = (item.start..item.end).any(|i| weak_mask.get(i).unwrap_or(false));
+            (
+                result.data().to_vec(),
+                vec![if weak { 0xFF } else { 0 }; result.data().len()],
+            )
+        };
+        sectors.push((chsn, data, mask));
+    }
+    sectors
+}
+
+impl DiskImage {
+    /// Compute SHA1 and CRC32 digests of each sector, each track, and the whole image.
+    pub fn hash_manifest(&self, opts: &HashManifestOptions) -> HashManifest {
+        let mut manifest = HashManifest::default();
+        let mut image_hasher = DigestBuilder::new();
+
+        for (ch, track) in self.iter_tracks() {
+            let mut sectors = stored_sectors(track);
+            for (id, data, mask) in sectors.iter_mut() {
+                let masked_bytes = mask.iter().filter(|&&m| m != 0).count();
+                if opts.exclude_masked {
+                    for (byte, mask) in data.iter_mut().zip(mask.iter()) {
+                        *byte &= !mask;
+                    }
+                }
+                manifest.sectors.push(SectorHash {
+                    ch,
+                    id: *id,
+                    len: data.len(),
+                    masked_bytes,
+                    digest: ContentDigest::of(data),
+                });
+            }
+
+            sectors.sort_by_key(|(id, _, _)| id.s());
+            let mut track_hasher = DigestBuilder::new();
+            for (_, data, _) in &sectors {
+                track_hasher.update(data);
+                image_hasher.update(data);
+            }
+            manifest.tracks.push(TrackHash {
+                ch,
+                sector_ct: sectors.len(),
+                digest: track_hasher.finish(),
+                track_sha1: track.hash().bytes(),
+            });
+        }
+
+        manifest.image = image_hasher.finish();
+        manifest
+    }
+}
+
+impl DiskImage {
+    /// Compute a [Fingerprint] of the image from its sector IDs and sector data.
+    ///
+    /// The fingerprint is independent of the container format the image was loaded from. It
+    /// ignores the physical location and order of sectors, track timing and encoding, gaps, and
+    /// sector flags such as CRC errors and deleted marks, none of which survive conversion to all
+    /// formats. Sectors that are identical in ID and data are counted once, so a 40-track disk
+    /// stored double-stepped in an 80-track container fingerprints the same as one that is not.
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut sectors: Vec<FingerprintSector> = self
+            .iter_tracks()
+            .flat_map(|(_, track)| stored_sectors(track))
+            .map(|(id, data, _)| ((id.c(), id.h(), id.s(), id.n()), data))
+            .collect();
+        sectors.sort();
+        sectors.dedup();
+
+        let mut hasher = sha1_smol::Sha1::new();
+        hasher.update(FINGERPRINT_VERSION);
+        for ((c, h, s, n), data) in &sectors {
+            hasher.update(&c.to_le_bytes());
+            hasher.update(&[*h, *s, *n]);
+            hasher.update(&(data.len() as u32).to_le_bytes());
+            hasher.update(data);
+        }
+        Fingerprint(hasher.digest().bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bitstream_codec::EncodingVariant,
+        file_parsers::{ImageFormatParser, ParserWriteOptions},
+        io::Cursor,
+        prelude::*,
+        test_util::formatted_disk,
+    };
+    use bit_vec::BitVec;
+
+    #[test]
+    fn test_hash_manifest() {
+        // The CRC32 check value.
+        assert_eq!(ContentDigest::of(b"123456789").crc32, 0xCBF43926);
+        assert_eq!(
+            ContentDigest::of(b"abc").sha1_hex(),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+
+        let format = StandardFormat::PcFloppy360;
+        let mut disk = formatted_disk();
+        let ch = DiskCh::new(2, 1);
+        let id = DiskChsn::new(2, 1, 5, 2);
+
+        // Overwrite the data of one sector, leaving its CRC stale.
+        let track = disk.track_mut(ch).unwrap();
+        let data_bit = track
+            .metadata()
+            .unwrap()
+            .items
+            .iter()
+            .find(|item| item.element.is_sector_data() && item.chsn == Some(id))
+            .unwrap()
+            .start
+            + 4 * 16;
+        let stream = track.stream().unwrap();
+        let encoded = stream.encode(&[0x55; 512], stream.data()[data_bit - 1], EncodingVariant::Data);
+        track.write_raw_bits(data_bit, &encoded).unwrap();
+
+        let manifest = disk.hash_manifest(&HashManifestOptions::default());
+        assert_eq!(manifest.tracks.len(), 80);
+        assert_eq!(manifest.sectors.len(), format.layout().total_sectors());
+        assert_eq!(manifest.sector(ch, id).unwrap().digest, ContentDigest::of(&[0x55; 512]));
+
+        // The image digest matches that of the equivalent raw sector image.
+        let mut raw = Cursor::new(Vec::new());
+        DiskImageFileFormat::RawSectorImage
+            .save_image(&mut disk, &ParserWriteOptions::default(), &mut raw)
+            .unwrap();
+        assert_eq!(manifest.image, ContentDigest::of(&raw.into_inner()));
+
+        // Weak bits are hashed as stored, unless masked bits are excluded.
+        let mut weak = BitVec::from_elem(disk.track(ch).unwrap().info().bit_length, false);
+        for i in data_bit..data_bit + 16 {
+            weak.set(i, true);
+        }
+        disk.track_mut(ch).unwrap().stream_mut().unwrap().set_weak_mask(weak);
+
+        let stored = disk.hash_manifest(&HashManifestOptions::default());
+        let masked = disk.hash_manifest(&HashManifestOptions { exclude_masked: true });
+        assert_eq!(stored.image, manifest.image);
+        let sector = masked.sector(ch, id).unwrap();
+        assert_eq!(sector.masked_bytes, 1);
+        let mut expected = [0x55; 512];
+        expected[0] = 0;
+        assert_eq!(sector.digest, ContentDigest::of(&expected));
+        assert_ne!(masked.image, manifest.image);
+        assert_eq!(
+            masked.track(DiskCh::new(0, 0)).unwrap().digest,
+            manifest.track(DiskCh::new(0, 0)).unwrap().digest
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let mut disk = formatted_disk();
+        let blank = disk.fingerprint();
+
+        // Give one sector distinct data.
+        let ch = DiskCh::new(7, 0);
+        let track = disk.track_mut(ch).unwrap();
+        let data_bit = track
+            .metadata()
+            .unwrap()
+            .items
+            .iter()
+            .find(|item| item.element.is_sector_data() && item.chsn == Some(DiskChsn::new(7, 0, 3, 2)))
+            .unwrap()
+            .start
+            + 4 * 16;
+        let data: Vec<u8> = (0..512).map(|i| i as u8).collect();
+        let stream = track.stream().unwrap();
+        let encoded = stream.encode(&data, stream.data()[data_bit - 1], EncodingVariant::Data);
+        track.write_raw_bits(data_bit, &encoded).unwrap();
+        disk.update_analysis();
+
+        let fingerprint = disk.fingerprint();
+        assert_ne!(fingerprint, blank);
+        assert_eq!(fingerprint.to_string().len(), 40);
+
+        // The same disk saved as a raw sector image and as a double-stepped 86F image has the
+        // same fingerprint.
+        for format in [DiskImageFileFormat::RawSectorImage, DiskImageFileFormat::F86Image] {
+            let mut out = Cursor::new(Vec::new());
+            format
+                .save_image(&mut disk, &ParserWriteOptions::default(), &mut out)
+                .unwrap();
+            let reloaded = DiskImage::load(&mut Cursor::new(out.into_inner()), None, None, None).unwrap();
+            assert_eq!(reloaded.fingerprint(), fingerprint, "{}", format);
+        }
+    }
+}
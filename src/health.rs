@@ -0,0 +1,312 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/health.rs
+
+    Computes a single health score for a disk image from its sector errors,
+    weak regions, flux decode confidence and missing sectors.
+*/
+
+//! A heuristic "health score" for disk images, useful for triaging large collections.
+//!
+//! [DiskImage::health()] combines several indicators of a bad or damaged dump into a single
+//! score from 0 (unreadable) to 100 (no detected problems). Each indicator contributes a penalty
+//! proportional to the square root of the fraction of the disk it affects, so that even a single
+//! bad sector is visible in the score, while a disk with many errors does not immediately bottom
+//! out. The contributing factors are itemized in the returned [HealthReport].
+
+use crate::{track::DiskTrack, DiskImage, FoxHashMap};
+use std::fmt::{Display, Formatter, Result};
+
+/// An indicator contributing to a disk image's health score.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HealthFactorKind {
+    /// Sectors with bad data CRCs.
+    DataCrcErrors,
+    /// Sectors with bad address mark CRCs.
+    AddressCrcErrors,
+    /// Tracks containing weak or unstable bits.
+    WeakRegions,
+    /// Flux tracks whose revolutions disagree on which sectors decoded without error.
+    DecodeConfidence,
+    /// Sectors missing from tracks, or present without a data address mark.
+    MissingSectors,
+}
+
+impl HealthFactorKind {
+    /// The maximum number of points this factor can subtract from the score.
+    pub fn weight(&self) -> f64 {
+        match self {
+            HealthFactorKind::DataCrcErrors => 35.0,
+            HealthFactorKind::AddressCrcErrors => 15.0,
+            HealthFactorKind::WeakRegions => 10.0,
+            HealthFactorKind::DecodeConfidence => 15.0,
+            HealthFactorKind::MissingSectors => 25.0,
+        }
+    }
+}
+
+impl Display for HealthFactorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            HealthFactorKind::DataCrcErrors => write!(f, "Data CRC errors"),
+            HealthFactorKind::AddressCrcErrors => write!(f, "Address CRC errors"),
+            HealthFactorKind::WeakRegions => write!(f, "Weak regions"),
+            HealthFactorKind::DecodeConfidence => write!(f, "Decode confidence"),
+            HealthFactorKind::MissingSectors => write!(f, "Missing sectors"),
+        }
+    }
+}
+
+/// A single itemized contribution to a disk image's health score.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HealthFactor {
+    /// The indicator this factor measures.
+    pub kind: HealthFactorKind,
+    /// The number of affected sectors or tracks, depending on the indicator. For
+    /// [HealthFactorKind::DecodeConfidence] this is the number of flux tracks with disagreeing
+    /// revolutions.
+    pub count: usize,
+    /// The number of sectors or tracks the indicator was measured over.
+    pub total: usize,
+    /// The fraction of the disk affected, from 0.0 to 1.0.
+    pub fraction: f64,
+    /// The number of points subtracted from the score.
+    pub penalty: f64,
+}
+
+impl HealthFactor {
+    fn new(kind: HealthFactorKind, count: usize, total: usize, fraction: f64) -> Self {
+        let fraction = fraction.clamp(0.0, 1.0);
+        HealthFactor {
+            kind,
+            count,
+            total,
+            fraction,
+            penalty: kind.weight() * fraction.sqrt(),
+        }
+    }
+
+    fn from_count(kind: HealthFactorKind, count: usize, total: usize) -> Self {
+        let fraction = if total > 0 { count as f64 / total as f64 } else { 0.0 };
+        HealthFactor::new(kind, count, total, fraction)
+    }
+}
+
+impl Display for HealthFactor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}: {}/{} (-{:.1})", self.kind, self.count, self.total, self.penalty)
+    }
+}
+
+/// The result of [DiskImage::health()].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HealthReport {
+    /// The overall health score, from 0 to 100. A score of 100 means no problems were detected.
+    pub score:   u8,
+    /// The factors contributing to the score. Every factor is listed, including those that did
+    /// not reduce the score.
+    pub factors: Vec<HealthFactor>,
+}
+
+impl HealthReport {
+    /// Return an iterator over the factors that reduced the score.
+    pub fn penalties(&self) -> impl Iterator<Item = &HealthFactor> {
+        self.factors.iter().filter(|f| f.penalty > 0.0)
+    }
+}
+
+impl Display for HealthReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "Health score: {}", self.score)?;
+        for factor in self.penalties() {
+            write!(f, "\n  {}", factor)?;
+        }
+        Ok(())
+    }
+}
+
+impl DiskImage {
+    /// Compute a heuristic health score for the disk image from 0 to 100, with the contributing
+    /// factors itemized. Higher is better.
+    ///
+    /// Missing sectors are counted against the most common number of sectors per track, so
+    /// images with intentionally short or unformatted tracks will score lower than a clean dump.
+    pub fn health(&self) -> HealthReport {
+        let mut sector_ct = 0;
+        let mut data_errors = 0;
+        let mut address_errors = 0;
+        let mut no_dam = 0;
+        let mut weak_tracks = 0;
+        let mut track_ct = 0;
+        let mut flux_tracks = 0;
+        let mut unconfident_tracks = 0;
+        let mut confidence_sum = 0.0;
+        let mut spt_counts: FoxHashMap<usize, usize> = FoxHashMap::new();
+        let mut track_spt = Vec::new();
+
+        for track in self.track_iter() {
+            track_ct += 1;
+            let sectors = track.sector_list();
+            sector_ct += sectors.len();
+            data_errors += sectors.iter().filter(|s| s.attributes.data_error).count();
+            address_errors += sectors.iter().filter(|s| s.attributes.address_error).count();
+            no_dam += sectors.iter().filter(|s| s.attributes.no_dam).count();
+
+            if track.has_weak_bits() {
+                weak_tracks += 1;
+            }
+
+            if let Some(confidence) = Self::track_confidence(track) {
+                flux_tracks += 1;
+                confidence_sum += confidence;
+                if confidence < 1.0 {
+                    unconfident_tracks += 1;
+                }
+            }
+
+            if !sectors.is_empty() {
+                *spt_counts.entry(sectors.len()).or_default() += 1;
+            }
+            track_spt.push(sectors.len());
+        }
+
+        // Use the most common sector count as the expected track length, preferring the larger
+        // count on a tie.
+        let expected_spt = spt_counts
+            .iter()
+            .max_by_key(|(spt, ct)| (**ct, **spt))
+            .map(|(spt, _)| *spt)
+            .unwrap_or(0);
+        let missing = track_spt
+            .iter()
+            .map(|spt| expected_spt.saturating_sub(*spt))
+            .sum::<usize>()
+            + no_dam;
+        let expected_sectors = expected_spt * track_ct;
+
+        let confidence_fraction = if flux_tracks > 0 {
+            1.0 - confidence_sum / flux_tracks as f64
+        }
+        else {
+            0.0
+        };
+
+        let factors = vec![
+            HealthFactor::from_count(HealthFactorKind::DataCrcErrors, data_errors, sector_ct),
+            HealthFactor::from_count(HealthFactorKind::AddressCrcErrors, address_errors, sector_ct),
+            HealthFactor::from_count(HealthFactorKind::WeakRegions, weak_tracks, track_ct),
+            HealthFactor::new(
+                HealthFactorKind::DecodeConfidence,
+                unconfident_tracks,
+                flux_tracks,
+                confidence_fraction,
+            ),
+            HealthFactor::from_count(
+                HealthFactorKind::MissingSectors,
+                missing,
+                expected_sectors.max(sector_ct),
+            ),
+        ];
+
+        let penalty: f64 = factors.iter().map(|f| f.penalty).sum();
+        let score = if track_ct == 0 || sector_ct == 0 {
+            // An image with no readable sectors at all is as unhealthy as it gets.
+            0
+        }
+        else {
+            (100.0 - penalty).floor().clamp(0.0, 100.0) as u8
+        };
+
+        HealthReport { score, factors }
+    }
+
+    fn track_confidence(track: &DiskTrack) -> Option<f64> {
+        track.as_fluxstream_track()?.decode_confidence()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prelude::*,
+        test_util::{add_sector, add_track, empty_disk},
+        types::SectorAttributes,
+    };
+
+    fn test_disk(errors: &[(u8, SectorAttributes)], spt: u8) -> DiskImage {
+        let mut disk = empty_disk();
+        for c in 0..2 {
+            let track = add_track(&mut disk, DiskCh::new(c, 0));
+
+            // Only the second track is short or has errors.
+            let track_spt = if c == 0 { 9 } else { spt };
+            for s in 1..=track_spt {
+                let attributes = errors
+                    .iter()
+                    .find(|(id, _)| c == 1 && *id == s)
+                    .map(|(_, a)| *a)
+                    .unwrap_or_default();
+                add_sector(track, DiskChsn::new(c, 0, s, 2), &[0; 512], attributes);
+            }
+        }
+        disk
+    }
+
+    #[test]
+    fn test_health() {
+        let clean = test_disk(&[], 9).health();
+        assert_eq!(clean.score, 100);
+        assert_eq!(clean.penalties().count(), 0);
+
+        let bad_data = SectorAttributes {
+            data_error: true,
+            ..Default::default()
+        };
+        let damaged = test_disk(&[(3, bad_data)], 9).health();
+        assert!(damaged.score < 100);
+        let factors: Vec<_> = damaged.penalties().collect();
+        assert_eq!(factors.len(), 1);
+        assert_eq!(factors[0].kind, HealthFactorKind::DataCrcErrors);
+        assert_eq!(factors[0].count, 1);
+        assert_eq!(factors[0].total, 18);
+
+        let short = test_disk(&[], 5).health();
+        let missing = short
+            .penalties()
+            .find(|f| f.kind == HealthFactorKind::MissingSectors)
+            .unwrap();
+        assert_eq!(missing.count, 4);
+
+        // More damage always scores lower.
+        let worse = test_disk(&[(3, bad_data), (4, bad_data), (5, bad_data)], 9).health();
+        assert!(worse.score < damaged.score);
+    }
+}
@@ -0,0 +1,360 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/image_analysis.rs
+
+    Aggregates per-track analysis into an image-wide integrity report.
+*/
+
+//! An image-wide integrity report.
+//!
+//! [DiskImage::analyze()] collects the [TrackAnalysis] of every track in an image, and combines
+//! it with checks that only make sense across the whole image, such as sectors missing compared to
+//! the image's [StandardFormat], or tracks recorded at different data rates. The resulting
+//! [ImageAnalysis] is serializable with the `serde` feature, for use by external tooling.
+
+use crate::{
+    prelude::{DiskCh, DiskChsn, StandardFormat, TrackDataRate},
+    track::TrackAnalysis,
+    DiskImage,
+    FoxHashMap,
+};
+use std::{
+    fmt::{Display, Formatter, Result},
+    mem::discriminant,
+};
+
+/// The fraction by which a track's bit length may differ from the standard length for its format
+/// before the track is reported as nonstandard. This allows for variation in drive speed when the
+/// track was written.
+pub const TRACK_LENGTH_TOLERANCE: f64 = 0.05;
+
+/// The [TrackAnalysis] of a single track, along with its location.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackReport {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    /// The data rate of the track.
+    pub data_rate: TrackDataRate,
+    /// The length of the track in bitcells, or 0 for tracks without a bitstream.
+    pub bit_length: usize,
+    /// The IDs of sectors on the track with a bad data or address CRC, in track order.
+    pub bad_sectors: Vec<DiskChsn>,
+    /// The analysis of the track, or None if the track could not be analyzed.
+    pub analysis: Option<TrackAnalysis>,
+}
+
+/// A sector ID that appears more than once on a track.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DuplicateSectorId {
+    /// The physical cylinder and head of the track.
+    pub ch:    DiskCh,
+    /// The duplicated sector ID.
+    pub id:    DiskChsn,
+    /// The number of times the ID appears on the track.
+    pub count: usize,
+}
+
+/// A sector expected by the image's [StandardFormat] that was not found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MissingSector {
+    /// The physical cylinder and head of the track the sector was expected on.
+    pub ch: DiskCh,
+    /// The expected sector ID.
+    pub id: DiskChsn,
+}
+
+/// A track whose sector count or bit length differs from the image's [StandardFormat].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NonstandardTrack {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    /// The number of sectors on the track.
+    pub sector_ct: usize,
+    /// The length of the track in bitcells, or 0 for tracks without a bitstream.
+    pub bit_length: usize,
+}
+
+/// The result of [DiskImage::analyze()].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageAnalysis {
+    /// The [StandardFormat] the image was compared against, if one could be determined.
+    pub standard_format: Option<StandardFormat>,
+    /// The number of tracks in the image.
+    pub track_ct: usize,
+    /// The number of sectors in the image.
+    pub sector_ct: usize,
+    /// The number of sectors with bad data CRCs.
+    pub data_crc_errors: usize,
+    /// The number of sectors with bad address CRCs.
+    pub address_crc_errors: usize,
+    /// The number of sectors with an ID but no data address mark.
+    pub no_dam: usize,
+    /// The number of sectors marked as deleted.
+    pub deleted: usize,
+    /// The sectors expected by `standard_format` that are not present in the image.
+    pub missing_sectors: Vec<MissingSector>,
+    /// Each distinct data rate category found in the image, in order of first appearance.
+    pub data_rates: Vec<TrackDataRate>,
+    /// Sector IDs that appear more than once on the same track.
+    pub duplicate_ids: Vec<DuplicateSectorId>,
+    /// Tracks whose sector count or bit length differs from `standard_format`.
+    pub nonstandard_tracks: Vec<NonstandardTrack>,
+    /// The analysis of each track, in track order.
+    pub tracks: Vec<TrackReport>,
+}
+
+impl ImageAnalysis {
+    /// Return true if the image contains tracks recorded at different data rates.
+    pub fn mixed_data_rates(&self) -> bool {
+        self.data_rates.len() > 1
+    }
+
+    /// Return true if no integrity problems were found.
+    pub fn is_clean(&self) -> bool {
+        self.data_crc_errors == 0
+            && self.address_crc_errors == 0
+            && self.no_dam == 0
+            && self.missing_sectors.is_empty()
+            && !self.mixed_data_rates()
+            && self.duplicate_ids.is_empty()
+            && self.nonstandard_tracks.is_empty()
+    }
+}
+
+impl Display for ImageAnalysis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.standard_format {
+            Some(format) => writeln!(f, "Standard format: {}", format)?,
+            None => writeln!(f, "Standard format: Unknown")?,
+        }
+        writeln!(f, "Tracks: {} Sectors: {}", self.track_ct, self.sector_ct)?;
+        writeln!(
+            f,
+            "Bad data CRCs: {} Bad address CRCs: {} No DAM: {} Deleted: {}",
+            self.data_crc_errors, self.address_crc_errors, self.no_dam, self.deleted
+        )?;
+        writeln!(f, "Missing sectors: {}", self.missing_sectors.len())?;
+        if self.mixed_data_rates() {
+            let rates: Vec<String> = self.data_rates.iter().map(|r| r.to_string()).collect();
+            writeln!(f, "Mixed data rates: {}", rates.join(", "))?;
+        }
+        for dup in &self.duplicate_ids {
+            writeln!(
+                f,
+                "Duplicate sector ID on track {}: {} (x{})",
+                dup.ch, dup.id, dup.count
+            )?;
+        }
+        for track in &self.nonstandard_tracks {
+            writeln!(
+                f,
+                "Nonstandard track {}: {} sectors, {} bitcells",
+                track.ch, track.sector_ct, track.bit_length
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl DiskImage {
+    /// Produce an image-wide integrity report, aggregating the [TrackAnalysis] of each track.
+    ///
+    /// Missing sectors and nonstandard tracks are determined against the image's standard format,
+    /// or the closest standard format if the image does not declare one. If no standard format can
+    /// be determined, these checks are skipped.
+    pub fn analyze(&self) -> ImageAnalysis {
+        let standard_format = self.standard_format.or_else(|| self.closest_format(true));
+        let mut report = ImageAnalysis {
+            standard_format,
+            ..Default::default()
+        };
+
+        for (ch, track) in self.iter_tracks() {
+            let info = track.info();
+            let sectors = track.sector_list();
+
+            report.track_ct += 1;
+            report.sector_ct += sectors.len();
+            for sector in &sectors {
+                report.data_crc_errors += sector.attributes.data_error as usize;
+                report.address_crc_errors += sector.attributes.address_error as usize;
+                report.no_dam += sector.attributes.no_dam as usize;
+                report.deleted += sector.attributes.deleted_mark as usize;
+            }
+
+            if !report
+                .data_rates
+                .iter()
+                .any(|rate| discriminant(rate) == discriminant(&info.data_rate))
+            {
+                report.data_rates.push(info.data_rate);
+            }
+
+            let mut id_counts: FoxHashMap<DiskChsn, usize> = FoxHashMap::new();
+            for sector in &sectors {
+                *id_counts.entry(sector.chsn).or_default() += 1;
+            }
+            let mut duplicates: Vec<DuplicateSectorId> = id_counts
+                .into_iter()
+                .filter(|(_, count)| *count > 1)
+                .map(|(id, count)| DuplicateSectorId { ch, id, count })
+                .collect();
+            duplicates.sort_by_key(|dup| dup.id.s());
+            report.duplicate_ids.extend(duplicates);
+
+            if let Some(format) = standard_format {
+                if Self::is_nonstandard_track(format, sectors.len(), info.bit_length) {
+                    report.nonstandard_tracks.push(NonstandardTrack {
+                        ch,
+                        sector_ct: sectors.len(),
+                        bit_length: info.bit_length,
+                    });
+                }
+            }
+
+            report.tracks.push(TrackReport {
+                ch,
+                data_rate: info.data_rate,
+                bit_length: info.bit_length,
+                bad_sectors: sectors
+                    .iter()
+                    .filter(|sector| sector.attributes.data_error || sector.attributes.address_error)
+                    .map(|sector| sector.chsn)
+                    .collect(),
+                analysis: track.analysis().ok(),
+            });
+        }
+
+        if let Some(format) = standard_format {
+            // The standard layout numbers each sector ID after the physical track it is on.
+            report.missing_sectors = format
+                .layout()
+                .chsn_iter()
+                .map(|id| MissingSector { ch: id.ch(), id })
+                .filter(|missing| {
+                    !self
+                        .track(missing.ch)
+                        .is_some_and(|track| track.has_sector_id(missing.id.s(), None))
+                })
+                .collect();
+        }
+
+        report
+    }
+
+    fn is_nonstandard_track(format: StandardFormat, sector_ct: usize, bit_length: usize) -> bool {
+        if sector_ct != format.layout().s() as usize {
+            return true;
+        }
+        // Tracks without a bitstream have no meaningful length.
+        if bit_length == 0 {
+            return false;
+        }
+        let expected = format.bitcell_ct() as f64;
+        (bit_length as f64 - expected).abs() / expected > TRACK_LENGTH_TOLERANCE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prelude::*,
+        test_util::{add_format_track, add_sector, empty_disk},
+        types::SectorAttributes,
+    };
+
+    #[test]
+    fn test_analyze() {
+        let format = StandardFormat::PcFloppy360;
+        let mut disk = empty_disk();
+
+        let layout = format.layout();
+        for ch in layout.ch_iter() {
+            // Record cylinder 39 at the high density data rate.
+            let track_format = match ch.c() {
+                39 => StandardFormat::PcFloppy1200,
+                _ => format,
+            };
+            let track = add_format_track(&mut disk, track_format, ch);
+
+            for s in 1..=layout.s() {
+                // Drop sector 9 of track 1/0, and duplicate sector 1 of track 2/1.
+                if ch == DiskCh::new(1, 0) && s == 9 {
+                    continue;
+                }
+                let copies = if ch == DiskCh::new(2, 1) && s == 1 { 2 } else { 1 };
+                let attributes = SectorAttributes {
+                    data_error: ch == DiskCh::new(3, 0) && s == 5,
+                    ..Default::default()
+                };
+                for _ in 0..copies {
+                    add_sector(
+                        track,
+                        DiskChsn::new(ch.c(), ch.h(), s, layout.n()),
+                        &vec![0xF6; format.sector_size()],
+                        attributes,
+                    );
+                }
+            }
+        }
+
+        let report = disk.analyze();
+        assert_eq!(report.standard_format, Some(format));
+        assert_eq!(report.track_ct, layout.c() as usize * layout.h() as usize);
+        assert_eq!(report.sector_ct, layout.total_sectors());
+        assert_eq!(report.data_crc_errors, 1);
+        assert_eq!(report.address_crc_errors, 0);
+        assert_eq!(
+            report.missing_sectors,
+            vec![MissingSector {
+                ch: DiskCh::new(1, 0),
+                id: DiskChsn::new(1, 0, 9, 2),
+            }]
+        );
+        assert!(report.mixed_data_rates());
+        assert_eq!(
+            report.duplicate_ids,
+            vec![DuplicateSectorId {
+                ch:    DiskCh::new(2, 1),
+                id:    DiskChsn::new(2, 1, 1, 2),
+                count: 2,
+            }]
+        );
+        let nonstandard: Vec<DiskCh> = report.nonstandard_tracks.iter().map(|t| t.ch).collect();
+        assert_eq!(nonstandard, vec![DiskCh::new(1, 0), DiskCh::new(2, 1)]);
+        assert_eq!(report.tracks.len(), report.track_ct);
+        let bad_sectors: Vec<DiskChsn> = report.tracks.iter().flat_map(|t| t.bad_sectors.clone()).collect();
+        assert_eq!(bad_sectors, vec![DiskChsn::new(3, 0, 5, 2)]);
+        assert!(!report.is_clean());
+    }
+}
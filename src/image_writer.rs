@@ -30,7 +30,7 @@
 
 */
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::{
     file_parsers::{ImageFormatParser, ParserWriteOptions},
@@ -38,20 +38,29 @@ use crate::{
     DiskImage,
     DiskImageError,
     DiskImageFileFormat,
+    SavingCallback,
+    SavingStage,
+    SavingStatus,
 };
 
 pub struct ImageWriter<'img> {
-    pub image:  &'img mut DiskImage,
-    pub path:   Option<PathBuf>,
+    pub image: &'img mut DiskImage,
+    pub path: Option<PathBuf>,
     pub format: Option<DiskImageFileFormat>,
+    pub verify: bool,
+    pub options: ParserWriteOptions,
+    pub callback: Option<SavingCallback>,
 }
 
 impl<'img> ImageWriter<'img> {
     pub fn new(img: &'img mut DiskImage) -> Self {
         Self {
-            image:  img,
-            path:   None,
+            image: img,
+            path: None,
             format: None,
+            verify: false,
+            options: ParserWriteOptions::default(),
+            callback: None,
         }
     }
 
@@ -69,7 +78,44 @@ impl<'img> ImageWriter<'img> {
         }
     }
 
+    /// After writing, reload the image from the output path and compare it against the source
+    /// image. Differences the output format cannot represent, such as CRC errors in a raw sector
+    /// image, are ignored. If any other difference is found, [ImageWriter::write] returns
+    /// [DiskImageError::VerifyError]. The output file is left in place so it can be inspected.
+    pub fn with_verify(self, verify: bool) -> Self {
+        Self { verify, ..self }
+    }
+
+    /// Set the options passed to the format parser, such as the number of revolutions to
+    /// synthesize when writing a flux image format.
+    pub fn with_options(self, options: ParserWriteOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Set a callback to receive the progress of [ImageWriter::write]. The callback receives each
+    /// stage of the write, per-track progress from file parsers that support it, and a final
+    /// [SavingStatus::Complete] or [SavingStatus::Error].
+    pub fn with_callback(self, callback: SavingCallback) -> Self {
+        Self {
+            callback: Some(callback),
+            ..self
+        }
+    }
+
     pub fn write(self) -> Result<(), DiskImageError> {
+        let callback = self.callback.clone();
+        let result = self.write_image();
+
+        if let Some(callback_fn) = callback {
+            match result {
+                Ok(_) => callback_fn(SavingStatus::Complete),
+                Err(_) => callback_fn(SavingStatus::Error),
+            }
+        }
+        result
+    }
+
+    fn write_image(self) -> Result<(), DiskImageError> {
         if self.path.is_none() {
             return Err(DiskImageError::ParameterError);
         }
@@ -82,11 +128,88 @@ impl<'img> ImageWriter<'img> {
 
         let mut buf = Cursor::new(Vec::with_capacity(1_000_000));
 
-        format.save_image(self.image, &ParserWriteOptions::default(), &mut buf)?;
+        let mut options = self.options;
+        if let Some(ref callback_fn) = self.callback {
+            callback_fn(SavingStatus::Stage(SavingStage::Encoding));
+            options = options.with_callback(callback_fn.clone());
+        }
+        format.save_image(self.image, &options, &mut buf)?;
 
+        if let Some(ref callback_fn) = self.callback {
+            callback_fn(SavingStatus::Stage(SavingStage::Writing));
+        }
         let data = buf.into_inner();
-        std::fs::write(path, data)?;
+        std::fs::write(&path, &data)?;
+
+        if self.verify {
+            if let Some(ref callback_fn) = self.callback {
+                callback_fn(SavingStatus::Stage(SavingStage::Verifying));
+            }
+            verify_image(self.image, format, &path, &data)?;
+        }
 
         Ok(())
     }
 }
+
+/// Reload `data`, written as `format` to `path`, and compare it against `image`.
+fn verify_image(
+    image: &DiskImage,
+    format: DiskImageFileFormat,
+    path: &Path,
+    data: &[u8],
+) -> Result<(), DiskImageError> {
+    let reloaded = DiskImage::load(&mut Cursor::new(data), Some(path), None, None)?;
+    let diff = image.diff(&reloaded).retain_expressible(format.capabilities());
+    if !diff.is_empty() {
+        log::error!(
+            "write(): Verification of {} image {} failed with {} track and {} sector differences",
+            format,
+            path.display(),
+            diff.tracks.len(),
+            diff.sectors.len()
+        );
+        return Err(DiskImageError::VerifyError(Box::new(diff)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_util::sector_disk};
+
+    #[test]
+    fn test_write_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("verify.img");
+        let format = DiskImageFileFormat::RawSectorImage;
+
+        // Fill each sector with its LBA, so that every sector has distinct data.
+        let layout = StandardFormat::PcFloppy360.layout();
+        let mut disk = sector_disk(StandardFormat::PcFloppy360, |chs| {
+            (vec![chs.to_lba(&layout) as u8; 512], Default::default())
+        });
+        disk.post_load_process();
+        ImageWriter::new(&mut disk)
+            .with_format(format)
+            .with_path(path.clone())
+            .with_verify(true)
+            .write()
+            .unwrap();
+
+        // Corrupting a sector of the written image fails verification, reporting that sector.
+        let mut data = std::fs::read(&path).unwrap();
+        let lba = DiskChs::new(1, 0, 3).to_lba(&layout);
+        assert_eq!(data[lba * 512], lba as u8);
+        data[lba * 512] ^= 0xFF;
+        match verify_image(&disk, format, &path, &data) {
+            Err(DiskImageError::VerifyError(diff)) => {
+                assert!(diff.tracks.is_empty());
+                let ids: Vec<DiskChsn> = diff.sectors.iter().map(|s| s.id).collect();
+                assert_eq!(ids, vec![DiskChsn::new(1, 0, 3, 2)]);
+            }
+            other => panic!("Expected VerifyError, got {:?}", other.err()),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/info_export.rs
+
+    Export of a machine-readable description of a disk image, for archive
+    catalogers.
+*/
+
+//! Structured image info export.
+//!
+//! [DiskImage::export_info()] describes a disk image in a single JSON or TOML document: its source
+//! format and any quirks the parser worked around, geometry, standard format and platforms,
+//! descriptive metadata, detected copy protection, health score, digests of the whole image, and
+//! a sector map of every track with CRC status and per-sector digests.
+//!
+//! The document has the same structure in both formats, and is built from the serde
+//! representations of the library's own types, such as [SourceFormatInfo], [DiskImageMetadata],
+//! [HealthReport], [TrackInfo] and [SectorMapEntry]. Absent values are written as `null` in JSON
+//! and omitted in TOML, which has no null value. Digests are the [TrackHash] and [SectorHash]
+//! entries reported by [DiskImage::hash_manifest()], so they can be compared directly against
+//! preservation databases.
+//!
+//! This module requires the `serde` feature.
+
+use crate::{
+    copy_protection::CopyProtectionScheme,
+    hash_manifest::{ContentDigest, Fingerprint, HashManifestOptions, SectorHash, TrackHash},
+    health::HealthReport,
+    platform::Platform,
+    track::TrackInfo,
+    types::{DiskCh, DiskImageMetadata, SectorMapEntry, SourceFormatInfo, StandardFormat},
+    DiskImage,
+};
+use serde::Serialize;
+use std::fmt::{Display, Formatter, Result};
+
+/// The version of the document structure produced by [DiskImage::export_info()]. This is
+/// incremented whenever a field is renamed or removed.
+pub const INFO_EXPORT_VERSION: u32 = 1;
+
+/// The document format produced by [DiskImage::export_info()].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum InfoFormat {
+    #[default]
+    Json,
+    Toml,
+}
+
+impl Display for InfoFormat {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            InfoFormat::Json => write!(f, "JSON"),
+            InfoFormat::Toml => write!(f, "TOML"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct InfoDocument<'a> {
+    info_version: u32,
+    fluxfox_version: &'static str,
+    source: Option<&'a SourceFormatInfo>,
+    geometry: DiskCh,
+    track_ct: usize,
+    standard_format: Option<StandardFormat>,
+    platforms: Vec<Platform>,
+    copy_protection: Option<CopyProtectionScheme>,
+    metadata: DiskImageMetadata,
+    health: HealthReport,
+    digest: &'a ContentDigest,
+    fingerprint: Fingerprint,
+    tracks: Vec<InfoTrack<'a>>,
+}
+
+#[derive(Serialize)]
+struct InfoTrack<'a> {
+    ch: DiskCh,
+    info: TrackInfo,
+    weak_bits: bool,
+    hash: Option<&'a TrackHash>,
+    sectors: Vec<InfoSector<'a>>,
+}
+
+#[derive(Serialize)]
+struct InfoSector<'a> {
+    #[serde(flatten)]
+    entry: SectorMapEntry,
+    hash:  Option<&'a SectorHash>,
+}
+
+impl DiskImage {
+    /// Export a machine-readable description of the disk image as a JSON or TOML document,
+    /// suitable for storing alongside the image in an archive or loading into a database.
+    /// See the [info_export](crate::info_export) module for the contents of the document.
+    pub fn export_info(&self, format: InfoFormat) -> String {
+        let manifest = self.hash_manifest(&HashManifestOptions::default());
+        let tracks = self
+            .iter_tracks()
+            .map(|(ch, track)| InfoTrack {
+                ch,
+                info: track.info(),
+                weak_bits: track.has_weak_bits(),
+                hash: manifest.track(ch),
+                sectors: track
+                    .sector_list()
+                    .into_iter()
+                    .map(|entry| InfoSector {
+                        entry,
+                        hash: manifest.sector(ch, entry.chsn),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let doc = InfoDocument {
+            info_version: INFO_EXPORT_VERSION,
+            fluxfox_version: env!("CARGO_PKG_VERSION"),
+            source: self.source_format_info(),
+            geometry: self.geometry(),
+            track_ct: self.track_ch_iter().count(),
+            standard_format: self.closest_format(true),
+            platforms: self.image_format().platforms.clone().unwrap_or_default(),
+            copy_protection: self.detect_copy_protection(),
+            metadata: self.image_metadata(),
+            health: self.health(),
+            digest: &manifest.image,
+            fingerprint: self.fingerprint(),
+            tracks,
+        };
+
+        // The core types serialize to plain structs, strings and numbers, so serialization
+        // cannot fail.
+        match format {
+            InfoFormat::Json => {
+                let mut out = serde_json::to_string_pretty(&doc).unwrap_or_default();
+                out.push('\n');
+                out
+            }
+            InfoFormat::Toml => toml::to_string(&doc).unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prelude::*,
+        test_util::{add_sector, add_track, empty_disk},
+        types::SectorAttributes,
+    };
+
+    fn test_disk() -> DiskImage {
+        let mut disk = empty_disk();
+        for c in 0..2 {
+            let track = add_track(&mut disk, DiskCh::new(c, 0));
+            for s in 1..=9 {
+                let attributes = SectorAttributes {
+                    data_error: c == 1 && s == 5,
+                    ..Default::default()
+                };
+                add_sector(track, DiskChsn::new(c, 0, s, 2), &[s; 512], attributes);
+            }
+        }
+        disk.set_image_metadata(&DiskImageMetadata {
+            title: Some("Test \"Disk\"".to_string()),
+            ..Default::default()
+        });
+        disk
+    }
+
+    #[test]
+    fn test_export_info_json() {
+        let disk = test_disk();
+        let json = disk.export_info(InfoFormat::Json);
+
+        assert!(json.starts_with("{\n  \"info_version\": 1,"));
+        assert!(json.contains("\"title\": \"Test \\\"Disk\\\"\""));
+        assert!(json.contains("\"dumper\": null"));
+
+        // The document embeds the serde representations of the core types.
+        let doc: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let tracks = doc["tracks"].as_array().unwrap();
+        assert_eq!(tracks.len(), 2);
+        let sector = &tracks[1]["sectors"][4];
+        assert_eq!(sector["attributes"]["data_error"], true);
+        let entry: SectorMapEntry = serde_json::from_value(sector.clone()).unwrap();
+        assert_eq!(entry.chsn, DiskChsn::new(1, 0, 5, 2));
+
+        let hash: SectorHash = serde_json::from_value(tracks[0]["sectors"][0]["hash"].clone()).unwrap();
+        assert_eq!(hash.digest, ContentDigest::of(&[1; 512]));
+        let image = disk.hash_manifest(&HashManifestOptions::default()).image;
+        assert_eq!(
+            serde_json::from_value::<ContentDigest>(doc["digest"].clone()).unwrap(),
+            image
+        );
+    }
+
+    #[test]
+    fn test_export_info_toml() {
+        let disk = test_disk();
+        let toml = disk.export_info(InfoFormat::Toml);
+
+        assert!(toml.starts_with("info_version = 1\n"));
+        assert!(!toml.contains("dumper"));
+
+        let doc: toml::Table = toml.parse().unwrap();
+        assert_eq!(doc["metadata"]["title"].as_str(), Some("Test \"Disk\""));
+        let tracks = doc["tracks"].as_array().unwrap();
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[1]["ch"]["c"].as_integer(), Some(1));
+        assert_eq!(tracks[1]["sectors"].as_array().unwrap().len(), 9);
+    }
+}
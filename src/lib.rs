@@ -42,30 +42,61 @@
 //!
 //! It is recommended to use the [`ImageBuilder`] interface to load or create a disk image.
 
+pub mod aging;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod batch;
 mod bit_ring;
 pub mod bitstream_codec;
 pub mod boot_sector;
+pub mod compressibility;
 mod containers;
 mod copy_protection;
 mod detect;
+pub mod diff;
 pub mod disk_lock;
 mod disk_schema;
 pub mod diskimage;
+pub mod fdc;
 mod file_parsers;
 pub mod file_system;
 pub mod flux;
+pub mod format_detection;
+#[cfg(all(feature = "hardware", not(target_arch = "wasm32")))]
+pub mod hardware;
+pub mod hash_manifest;
+pub mod health;
+pub mod image_analysis;
 pub mod image_builder;
 mod image_loader;
 mod image_writer;
+#[cfg(feature = "serde")]
+pub mod info_export;
 pub mod io;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod load_handle;
+pub mod load_telemetry;
+mod merge;
 mod platform;
 pub mod prelude;
 mod random;
 mod range_check;
+pub mod rotation;
 mod scripting;
+#[cfg(feature = "tar")]
+mod sector_tar;
 mod sector_view;
+#[cfg(all(feature = "server", not(target_arch = "wasm32")))]
+pub mod server;
 pub mod source_map;
+pub mod storage;
+pub mod test_corpus;
+#[cfg(test)]
+mod test_util;
+mod text_dump;
 pub mod track;
+pub mod track_alignment;
+pub mod track_length;
+pub mod track_mapping;
 pub mod track_schema;
 mod tree_map;
 pub mod types;
@@ -76,7 +107,15 @@ pub mod visualization;
 use std::{hash::RandomState, sync::Arc};
 use thiserror::Error;
 
-pub const MAXIMUM_SECTOR_SIZE: usize = 8192;
+/// The largest sector size in bytes, corresponding to a sector size code (N) of 7. Larger values of
+/// N are clamped to this size.
+pub const MAXIMUM_SECTOR_SIZE: usize = 16384;
+/// The largest sector size code (N) accepted when adding a sector. N=7 (16K) is the largest value
+/// that any floppy controller can reasonably be asked to format.
+pub const MAXIMUM_SECTOR_SIZE_CODE: u8 = 7;
+/// The maximum number of sectors accepted on a single track. This is a sanity limit, well above
+/// anything produced by a real controller, used to reject corrupt or malicious images.
+pub const MAXIMUM_SECTORS_PER_TRACK: usize = 256;
 pub const DEFAULT_SECTOR_SIZE: usize = 512;
 pub const ASCII_EOF: u8 = 0x1A;
 /// The maximum cylinder any drive can seek to or that we will ever see in an image.
@@ -91,6 +130,7 @@ pub type FoxHashMap<K, V, S = RandomState> = std::collections::HashMap<K, V, S>;
 type FoxHashSet<T, S = RandomState> = std::collections::HashSet<T, S>;
 
 /// The status of a disk image loading operation, for file parsers that support progress reporting.
+#[non_exhaustive]
 pub enum LoadingStatus {
     /// Emitted by file parsers that support progress updates. This is sent before any other task
     /// is performed, to allow the caller time to prepare and display a progress bar.
@@ -99,15 +139,62 @@ pub enum LoadingStatus {
     /// The value is a floating-point number between 0.0 and 1.0, where 1.0 represents full completion.
     /// Note: The value 1.0 is not guaranteed to be emitted.
     Progress(f64),
+    /// Emitted when the loading operation enters a new stage.
+    Stage(LoadingStage),
+    /// Emitted by file parsers that support progress updates as each track is read or decoded.
+    /// `index` counts from 0 up to `total`, the number of tracks expected.
+    Track { ch: DiskCh, index: usize, total: usize },
     /// Emitted by file parsers to inform the caller that the loading operation is complete.
     Complete,
     /// Emitted by file parsers to inform the caller that an error occurred during the loading operation.
     Error,
 }
 
+/// A stage of a disk image loading operation, reported by [LoadingStatus::Stage].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoadingStage {
+    /// The format of the disk image, or the container holding it, is being detected.
+    Detecting,
+    /// The disk image file is being parsed and its tracks read.
+    Reading,
+    /// Flux or bitstream tracks are being decoded.
+    Decoding,
+    /// The loaded disk image is being analyzed.
+    Analyzing,
+}
+
 pub type LoadingCallback = Arc<dyn Fn(LoadingStatus) + Send + Sync>;
 
+/// The status of a disk image saving operation, reported by [ImageWriter::write].
+#[non_exhaustive]
+pub enum SavingStatus {
+    /// Emitted when the saving operation enters a new stage.
+    Stage(SavingStage),
+    /// Emitted by file parsers that support progress updates as each track is encoded.
+    /// `index` counts from 0 up to `total`, the number of tracks to be written.
+    Track { ch: DiskCh, index: usize, total: usize },
+    /// Emitted to inform the caller that the saving operation is complete.
+    Complete,
+    /// Emitted to inform the caller that an error occurred during the saving operation.
+    Error,
+}
+
+/// A stage of a disk image saving operation, reported by [SavingStatus::Stage].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SavingStage {
+    /// The disk image is being encoded into the output format.
+    Encoding,
+    /// The encoded image is being written to its destination.
+    Writing,
+    /// The written image is being reloaded from its destination and compared against the source
+    /// image.
+    Verifying,
+}
+
+pub type SavingCallback = Arc<dyn Fn(SavingStatus) + Send + Sync>;
+
 #[derive(Clone, Debug, Error)]
+#[non_exhaustive]
 pub enum DiskImageError {
     #[error("An IO error occurred reading or writing the disk image: {0}")]
     IoError(String),
@@ -153,6 +240,20 @@ pub enum DiskImageError {
     PlatformMismatch,
     #[error("The disk image was not compatible with the requested format")]
     FormatMismatch,
+    #[error("Sector size code {0} exceeds the configured maximum")]
+    InvalidSectorSize(u8),
+    #[error("Sector {0} has no data but is not marked as missing its data address mark")]
+    EmptySectorData(types::DiskChsn),
+    #[error("Sector mask length {mask_len} does not match data length {data_len}")]
+    MaskLengthMismatch { data_len: usize, mask_len: usize },
+    #[error("Track sector count would exceed the configured maximum of {0}")]
+    SectorCountError(usize),
+    #[error("The operation was cancelled")]
+    Cancelled,
+    #[error("The written disk image failed verification ({} track and {} sector differences)", .0.tracks.len(), .0.sectors.len())]
+    VerifyError(Box<diff::ImageDiff>),
+    #[error("An error occurred communicating with imaging hardware: {0}")]
+    HardwareError(String),
 }
 
 // Manually implement `From<io::Error>` for `DiskImageError`
@@ -0,0 +1,258 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/load_handle.rs
+
+    A handle to a disk image being loaded on a background thread, for
+    frontends that must keep drawing while a large image is decoded.
+*/
+
+//! This module defines [LoadHandle], a handle to a disk image being loaded on a background
+//! thread, returned by [DiskImage::load_in_background].
+//!
+//! A GUI frontend can start a load, then [poll](LoadHandle::poll) the handle once per frame,
+//! displaying the current [stage](LoadHandle::stage) and [progress](LoadHandle::progress) until
+//! the result is ready. A load may be [cancelled](LoadHandle::cancel) at any time.
+//!
+//! Threads are not available on `wasm32` targets. There, use [DiskImage::load_async] instead.
+//!
+//! [DiskImage::load_async]: crate::DiskImage::load_async
+
+use crate::{
+    file_parsers::ParserReadOptions,
+    io::ReadSeek,
+    types::DiskSelection,
+    DiskImage,
+    DiskImageError,
+    LoadingCallback,
+    LoadingStage,
+    LoadingStatus,
+};
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+        Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+#[derive(Default)]
+struct LoadState {
+    progress: f64,
+    stage:    Option<LoadingStage>,
+}
+
+/// A handle to a disk image being loaded on a background thread. See the
+/// [module documentation](self).
+///
+/// Dropping the handle does not stop the load; the background thread runs to completion and its
+/// result is discarded.
+pub struct LoadHandle {
+    state: Arc<Mutex<LoadState>>,
+    cancelled: Arc<AtomicBool>,
+    receiver: Receiver<Result<DiskImage, DiskImageError>>,
+    thread: Option<JoinHandle<()>>,
+    finished: bool,
+}
+
+impl LoadHandle {
+    pub(crate) fn spawn<RS: ReadSeek + Send + 'static>(
+        mut image_io: RS,
+        image_path: Option<PathBuf>,
+        disk_selection: Option<DiskSelection>,
+        callback: Option<LoadingCallback>,
+    ) -> Self {
+        let state = Arc::new(Mutex::new(LoadState::default()));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+
+        // Record the progress of the load for polling, then pass it on to the caller's callback.
+        let thread_state = state.clone();
+        let tracker: LoadingCallback = Arc::new(move |status| {
+            if let Ok(mut state) = thread_state.lock() {
+                match status {
+                    LoadingStatus::Stage(stage) => state.stage = Some(stage),
+                    LoadingStatus::Progress(progress) => state.progress = progress.clamp(0.0, 1.0),
+                    LoadingStatus::Track { index, total, .. } if total > 0 => {
+                        state.progress = (index + 1) as f64 / total as f64;
+                    }
+                    LoadingStatus::Complete => state.progress = 1.0,
+                    _ => {}
+                }
+            }
+            if let Some(ref callback_fn) = callback {
+                callback_fn(status);
+            }
+        });
+
+        let options = ParserReadOptions::default().with_cancel_flag(cancelled.clone());
+        let thread = thread::spawn(move || {
+            let result = DiskImage::load_with_options(
+                &mut image_io,
+                image_path.as_deref(),
+                disk_selection,
+                &options,
+                Some(tracker),
+            );
+            // The receiver is gone if the handle was dropped.
+            _ = sender.send(result);
+        });
+
+        LoadHandle {
+            state,
+            cancelled,
+            receiver,
+            thread: Some(thread),
+            finished: false,
+        }
+    }
+
+    /// Return the progress of the load, from 0.0 to 1.0. Progress is only reported by file
+    /// parsers that support it, and remains at 0.0 for the rest until the load completes.
+    pub fn progress(&self) -> f64 {
+        self.state.lock().map(|state| state.progress).unwrap_or_default()
+    }
+
+    /// Return the current stage of the load, or `None` if it has not yet started.
+    pub fn stage(&self) -> Option<LoadingStage> {
+        self.state.lock().ok().and_then(|state| state.stage)
+    }
+
+    /// Cancel the load. The next call to [LoadHandle::poll] returns
+    /// [DiskImageError::Cancelled]. The background thread stops at the next stage of the load, or
+    /// at the next track for file parsers that report per-track progress.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Return true if the load has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Check whether the load has finished without blocking. Returns `None` while the load is in
+    /// progress, and the result of the load once it has finished or been cancelled. The result
+    /// is returned only once; later calls return `None`.
+    pub fn poll(&mut self) -> Option<Result<DiskImage, DiskImageError>> {
+        if self.finished {
+            return None;
+        }
+        if self.is_cancelled() {
+            self.finished = true;
+            return Some(Err(DiskImageError::Cancelled));
+        }
+
+        let result = match self.receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => {
+                log::error!("poll(): Load thread exited without a result");
+                Err(DiskImageError::SyncError(
+                    "Load thread exited without a result".to_string(),
+                ))
+            }
+        };
+        self.finished = true;
+        if let Some(thread) = self.thread.take() {
+            _ = thread.join();
+        }
+        Some(result)
+    }
+
+    /// Block until the load finishes, and return its result. Returns [DiskImageError::Cancelled]
+    /// if the load was cancelled, or if the result was already returned by [LoadHandle::poll].
+    pub fn wait(mut self) -> Result<DiskImage, DiskImageError> {
+        if self.finished || self.is_cancelled() {
+            return Err(DiskImageError::Cancelled);
+        }
+
+        let result = self.receiver.recv().unwrap_or_else(|_| {
+            log::error!("wait(): Load thread exited without a result");
+            Err(DiskImageError::SyncError(
+                "Load thread exited without a result".to_string(),
+            ))
+        });
+        if let Some(thread) = self.thread.take() {
+            _ = thread.join();
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        file_parsers::ParserWriteOptions,
+        io::Cursor,
+        prelude::*,
+        test_util::formatted_disk,
+        ImageFormatParser,
+    };
+
+    fn raw_image() -> Vec<u8> {
+        let mut disk = formatted_disk();
+        let mut out = Cursor::new(Vec::new());
+        DiskImageFileFormat::RawSectorImage
+            .save_image(&mut disk, &ParserWriteOptions::default(), &mut out)
+            .unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn test_load_in_background() {
+        let mut handle = DiskImage::load_in_background(Cursor::new(raw_image()), None, None, None);
+        let disk = loop {
+            if let Some(result) = handle.poll() {
+                break result.unwrap();
+            }
+            thread::yield_now();
+        };
+        assert_eq!(disk.geometry(), DiskCh::new(40, 2));
+        assert_eq!(handle.stage(), Some(LoadingStage::Analyzing));
+        assert_eq!(handle.progress(), 1.0);
+        assert!(handle.poll().is_none());
+
+        let handle = DiskImage::load_in_background(Cursor::new(vec![0x55; 100]), None, None, None);
+        assert!(handle.wait().is_err());
+    }
+
+    #[test]
+    fn test_cancel_load() {
+        let mut handle = DiskImage::load_in_background(Cursor::new(raw_image()), None, None, None);
+        handle.cancel();
+        assert!(matches!(handle.poll(), Some(Err(DiskImageError::Cancelled))));
+        assert!(handle.poll().is_none());
+
+        // A load with its cancel flag set stops with an error.
+        let options = ParserReadOptions::default().with_cancel_flag(Arc::new(AtomicBool::new(true)));
+        let result = DiskImage::load_with_options(&mut Cursor::new(raw_image()), None, None, &options, None);
+        assert!(matches!(result, Err(DiskImageError::Cancelled)));
+    }
+}
@@ -0,0 +1,162 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/load_telemetry.rs
+
+    Records how long each phase of loading a disk image took.
+*/
+
+//! Image-load telemetry.
+//!
+//! Images loaded with [DiskImage::load()] record how long each phase of the load took, available
+//! from [DiskImage::load_telemetry()]. This turns a report of a "slow load" into numbers that show
+//! whether the time went to format detection, the container parser, decoding flux, or scanning
+//! track structure.
+
+use crate::{types::DiskCh, DiskImage};
+use std::{
+    fmt::{Display, Formatter, Result},
+    time::Duration,
+};
+
+/// The time spent loading a single `BitStream` or `FluxStream` track.
+#[derive(Clone, Debug, Default)]
+pub struct TrackLoadTiming {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    /// The time spent decoding flux transitions into bitstreams. Always zero for `BitStream`
+    /// tracks.
+    pub decode: Duration,
+    /// The time spent scanning the track for its structure (markers, sector headers and data).
+    /// For `FluxStream` tracks, this is the time spent analyzing the decoded revolutions.
+    pub scan: Duration,
+}
+
+impl TrackLoadTiming {
+    /// Return the total time spent loading this track.
+    pub fn total(&self) -> Duration {
+        self.decode + self.scan
+    }
+}
+
+/// A breakdown of the time spent loading a disk image, returned by
+/// [DiskImage::load_telemetry()].
+#[derive(Clone, Debug, Default)]
+pub struct LoadTelemetry {
+    /// The time spent detecting the image format, including extracting it from an archive.
+    pub detect: Duration,
+    /// The time spent in the image format parser, excluding the per-track decode and scan times.
+    pub parse: Duration,
+    /// The time spent analyzing the loaded image, after all tracks were added.
+    pub post_process: Duration,
+    /// The time spent loading each `BitStream` or `FluxStream` track, in the order the tracks
+    /// were added. `MetaSector` tracks are not timed.
+    pub tracks: Vec<TrackLoadTiming>,
+}
+
+impl LoadTelemetry {
+    /// Return the total time spent decoding flux across all tracks.
+    pub fn decode(&self) -> Duration {
+        self.tracks.iter().map(|t| t.decode).sum()
+    }
+
+    /// Return the total time spent scanning track structure across all tracks.
+    pub fn scan(&self) -> Duration {
+        self.tracks.iter().map(|t| t.scan).sum()
+    }
+
+    /// Return the total time spent loading the image.
+    pub fn total(&self) -> Duration {
+        self.detect + self.parse + self.decode() + self.scan() + self.post_process
+    }
+
+    /// Return the track that took the longest to load, if any tracks were timed.
+    pub fn slowest_track(&self) -> Option<&TrackLoadTiming> {
+        self.tracks.iter().max_by_key(|t| t.total())
+    }
+
+    pub(crate) fn add_track(&mut self, ch: DiskCh, decode: Duration, scan: Duration) {
+        self.tracks.push(TrackLoadTiming { ch, decode, scan });
+    }
+
+    /// Record the phase times measured by the loader. `parse` is the total time spent in the
+    /// parser, from which the time spent on individual tracks is subtracted.
+    pub(crate) fn finish(&mut self, detect: Duration, parse: Duration, post_process: Duration) {
+        self.detect = detect;
+        self.parse = parse.saturating_sub(self.decode() + self.scan());
+        self.post_process = post_process;
+    }
+}
+
+impl Display for LoadTelemetry {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        writeln!(f, "Detect:       {:?}", self.detect)?;
+        writeln!(f, "Parse:        {:?}", self.parse)?;
+        writeln!(f, "Decode:       {:?} ({} tracks)", self.decode(), self.tracks.len())?;
+        writeln!(f, "Scan:         {:?}", self.scan())?;
+        writeln!(f, "Post-process: {:?}", self.post_process)?;
+        write!(f, "Total:        {:?}", self.total())?;
+        if let Some(slowest) = self.slowest_track() {
+            write!(f, "\nSlowest track: {} ({:?})", slowest.ch, slowest.total())?;
+        }
+        Ok(())
+    }
+}
+
+impl DiskImage {
+    /// Return a breakdown of the time spent loading this image, if it was loaded from an image
+    /// file. Images created by an [ImageBuilder](crate::ImageBuilder) have no load telemetry.
+    pub fn load_telemetry(&self) -> Option<&LoadTelemetry> {
+        self.load_telemetry.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, test_util::formatted_disk};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_load_telemetry() {
+        let mut disk = formatted_disk();
+        assert!(disk.load_telemetry().is_none());
+
+        let mut out = Cursor::new(Vec::new());
+        DiskImageFileFormat::F86Image
+            .save_image(&mut disk, &ParserWriteOptions::default(), &mut out)
+            .unwrap();
+        let loaded = DiskImage::load(&mut Cursor::new(out.into_inner()), None, None, None).unwrap();
+
+        let telemetry = loaded.load_telemetry().unwrap();
+        // Every track added by the parser is timed, including double-stepped tracks that are
+        // later removed by normalization.
+        assert!(telemetry.tracks.len() >= loaded.iter_tracks().count());
+        assert!(telemetry.tracks.iter().all(|t| t.decode.is_zero()));
+        assert!(telemetry.total() >= telemetry.scan());
+        assert!(telemetry.slowest_track().is_some());
+        assert!(telemetry.to_string().contains("Slowest track"));
+    }
+}
@@ -0,0 +1,250 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/merge.rs
+
+    Implements merging multiple dumps of the same disk into a single best-of
+    MetaSector image.
+*/
+use crate::{
+    prelude::{DiskCh, DiskChsn, DiskChsnQuery, RwScope},
+    track::Track,
+    types::{AddSectorParams, MetaSectorTrackParams, SectorAttributes},
+    DiskImage,
+    DiskImageError,
+};
+
+/// A single read of a sector from one merge source.
+struct SectorRead {
+    data: Vec<u8>,
+    weak_mask: Option<Vec<u8>>,
+    attributes: SectorAttributes,
+}
+
+impl SectorRead {
+    fn is_good(&self) -> bool {
+        !self.attributes.address_error && !self.attributes.data_error
+    }
+}
+
+/// Merge multiple dumps of the same disk into a single `MetaSector` resolution [DiskImage].
+///
+/// Each track of each image is a merge source. `FluxStream` tracks contribute each of their
+/// decoded revolutions as a separate source. For every sector ID found in any source:
+/// - The first read with a valid address and data CRC is preferred, otherwise the first read.
+/// - Bits that differ between reads sharing the chosen read's data CRC status, and any weak bits
+///   already present in those reads, become weak bits in the merged sector. Reads with a
+///   differing CRC status are not compared, so a bad dump does not mark weak bits in a good
+///   sector.
+///
+/// Duplicate sector IDs within a track are merged into a single sector.
+pub(crate) fn merge_images(images: &[&DiskImage]) -> Result<DiskImage, DiskImageError> {
+    let Some(first) = images.first()
+    else {
+        log::error!("merge_images(): No images to merge.");
+        return Err(DiskImageError::ParameterError);
+    };
+
+    let mut merged = DiskImage {
+        descriptor: first.descriptor.clone(),
+        standard_format: first.standard_format,
+        source_info: first.source_info.clone(),
+        ..Default::default()
+    };
+
+    for head in 0..2u8 {
+        let cylinders = images
+            .iter()
+            .map(|image| image.track_map[head as usize].len())
+            .max()
+            .unwrap_or(0);
+
+        for cylinder in 0..cylinders {
+            let ch = DiskCh::new(cylinder as u16, head);
+            let sources = merge_sources(images, ch);
+
+            // Every track is added, even when empty, to keep the track map contiguous.
+            let params = match sources.first() {
+                Some(track) => MetaSectorTrackParams {
+                    ch,
+                    encoding: track.encoding(),
+                    data_rate: track.info().data_rate,
+                },
+                None => MetaSectorTrackParams {
+                    ch,
+                    encoding: merged.descriptor.data_encoding,
+                    data_rate: merged.descriptor.data_rate,
+                },
+            };
+            let new_track = merged.add_track_metasector(&params)?;
+
+            let mut ids: Vec<DiskChsn> = Vec::new();
+            for entry in sources.iter().flat_map(|track| track.sector_list()) {
+                if !ids.contains(&entry.chsn) {
+                    ids.push(entry.chsn);
+                }
+            }
+
+            for id in ids {
+                let reads: Vec<SectorRead> = sources.iter().filter_map(|track| read_source(*track, id)).collect();
+                if let Some((read, weak_mask)) = merge_reads(id, &reads) {
+                    new_track.add_sector(&AddSectorParams {
+                        id_chsn: id,
+                        data: &read.data,
+                        weak_mask: weak_mask.as_deref(),
+                        attributes: read.attributes,
+                        ..Default::default()
+                    })?;
+                }
+            }
+        }
+    }
+
+    merged.post_load_process();
+    Ok(merged)
+}
+
+/// Collect the tracks to merge for the specified physical track, expanding `FluxStream` tracks
+/// into their decoded revolutions.
+fn merge_sources<'a>(images: &[&'a DiskImage], ch: DiskCh) -> Vec<&'a dyn Track> {
+    let mut sources: Vec<&dyn Track> = Vec::new();
+    for track in images.iter().filter_map(|image| image.track(ch)) {
+        match track.as_fluxstream_track() {
+            Some(flux_track) => {
+                sources.extend(flux_track.decoded_revolution_iter().map(|t| t as &dyn Track));
+            }
+            None => sources.push(track.as_ref()),
+        }
+    }
+    sources
+}
+
+fn read_source(track: &dyn Track, id: DiskChsn) -> Option<SectorRead> {
+    let result = track
+        .read_sector(DiskChsnQuery::from(id), None, None, RwScope::DataOnly, false)
+        .ok()
+        .filter(|result| !result.not_found)?;
+
+    // Reading a MetaSector randomizes its weak bits, so take the stored data and mask directly.
+    let (data, weak_mask) = match track.as_metasector_track().and_then(|t| t.raw_sector_data(id)) {
+        Some((data, mask)) => (data.to_vec(), Some(mask.to_vec())),
+        None => (result.read_buf[result.data_range.clone()].to_vec(), None),
+    };
+
+    Some(SectorRead {
+        data,
+        weak_mask,
+        attributes: SectorAttributes {
+            address_error: result.address_crc_error,
+            data_error: result.data_crc_error,
+            deleted_mark: result.deleted_mark,
+            no_dam: result.no_dam,
+        },
+    })
+}
+
+/// Select the best read and calculate its weak bit mask, if any bits are weak. A read of a
+/// different length than the best read conflicts with it past their common length, so those bytes
+/// of the best read are marked weak.
+fn merge_reads(id: DiskChsn, reads: &[SectorRead]) -> Option<(&SectorRead, Option<Vec<u8>>)> {
+    let best = reads.iter().find(|read| read.is_good()).or(reads.first())?;
+
+    let mut weak_mask = best.weak_mask.clone().unwrap_or_else(|| vec![0; best.data.len()]);
+    weak_mask.resize(best.data.len(), 0);
+    for read in reads
+        .iter()
+        .filter(|read| read.attributes.data_error == best.attributes.data_error)
+    {
+        let common_len = read.data.len().min(best.data.len());
+        if read.data.len() != best.data.len() {
+            log::warn!(
+                "merge_reads(): Reads of sector {} differ in length ({} vs {} bytes), marking {} bytes weak.",
+                id,
+                best.data.len(),
+                read.data.len(),
+                best.data.len() - common_len
+            );
+            weak_mask[common_len..].fill(0xFF);
+        }
+
+        for (i, mask_byte) in weak_mask.iter_mut().take(common_len).enumerate() {
+            *mask_byte |= read.data[i] ^ best.data[i];
+            if let Some(read_mask) = &read.weak_mask {
+                *mask_byte |= read_mask.get(i).copied().unwrap_or(0);
+            }
+        }
+    }
+
+    let has_weak = weak_mask.iter().any(|&b| b != 0);
+    Some((best, has_weak.then_some(weak_mask)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, test_util::single_track_disk, types::SectorAttributes};
+
+    const BAD_CRC: SectorAttributes = SectorAttributes {
+        address_error: false,
+        data_error: true,
+        deleted_mark: false,
+        no_dam: false,
+    };
+
+    #[test]
+    fn test_merge_prefers_good_crc() {
+        let bad = single_track_disk(&[(1, 0x00, BAD_CRC), (2, 0xF6, Default::default())]);
+        let good = single_track_disk(&[(1, 0xF6, Default::default()), (3, 0xF6, Default::default())]);
+
+        let merged = DiskImage::merge(&[&bad, &good]).unwrap();
+        let track = merged.track(DiskCh::new(0, 0)).unwrap();
+        assert_eq!(track.sector_ct(), 3);
+        assert!(!track.has_weak_bits());
+
+        let result = track
+            .read_sector(DiskChsn::new(0, 0, 1, 2).into(), None, None, RwScope::DataOnly, false)
+            .unwrap();
+        assert!(!result.data_crc_error);
+        assert!(result.read_buf[result.data_range].iter().all(|&b| b == 0xF6));
+    }
+
+    #[test]
+    fn test_merge_weak_bits() {
+        let dump1 = single_track_disk(&[(1, 0x0F, BAD_CRC)]);
+        let dump2 = single_track_disk(&[(1, 0x00, BAD_CRC)]);
+
+        let merged = DiskImage::merge(&[&dump1, &dump2]).unwrap();
+        let track = merged.track(DiskCh::new(0, 0)).unwrap();
+        assert!(track.has_weak_bits());
+
+        let (data, weak_mask) = track
+            .as_metasector_track()
+            .unwrap()
+            .raw_sector_data(DiskChsn::new(0, 0, 1, 2))
+            .unwrap();
+        assert!(data.iter().all(|&b| b == 0x0F));
+        assert!(weak_mask.iter().all(|&b| b == 0x0F));
+    }
+}
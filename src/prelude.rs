@@ -31,11 +31,14 @@ pub use crate::{
     file_parsers::{
         format_from_ext,
         supported_extensions,
+        FormatCaps,
         ImageFormatParser,
         ParserReadOptions,
         ParserWriteCompatibility,
         ParserWriteOptions,
+        RawFillPolicy,
     },
+    flux::synthesis::FluxSynthesisOptions,
     image_builder::ImageBuilder,
     image_writer::ImageWriter,
     platform::Platform,
@@ -46,8 +49,11 @@ pub use crate::{
         DiskChsn,
         DiskChsnQuery,
         DiskImageFileFormat,
+        DiskImageMetadata,
         RwScope,
         SectorMapEntry,
+        SourceFormatInfo,
+        SourceQuirks,
         StandardFormat,
         StandardFormatParam,
         TrackDataEncoding,
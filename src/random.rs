@@ -26,11 +26,14 @@
 
     src/random.rs
 
-    Provide a simple random bit generator.
+    Provide a simple random bit generator, and a generator for resolving weak
+    bits according to a WeakBitPolicy.
 */
 
 #![allow(dead_code)]
 
+use crate::types::{DiskCh, WeakBitPolicy};
+
 const RANDOM_BITS_SIZE: usize = 2048;
 
 const PSEUDO_RANDOM_BITS: [bool; RANDOM_BITS_SIZE] = generate_pseudo_random_bits(0x57A857FA, RANDOM_BITS_SIZE);
@@ -60,3 +63,98 @@ pub fn random_bit(index: usize) -> bool {
 pub fn random_bit_ref(index: usize) -> &'static bool {
     &PSEUDO_RANDOM_BITS[index & (RANDOM_BITS_SIZE - 1)]
 }
+
+/// A small seeded pseudo-random number generator (SplitMix64). Fast, and good enough for weak
+/// bits and simulated media defects - we only need reproducibility.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        SeededRng { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Return a value in the range [0.0, 1.0).
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Return a value in the range [0, n), or 0 if `n` is 0.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Produces the values of weak bits for sector reads according to a [WeakBitPolicy].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct WeakBitGenerator {
+    policy: WeakBitPolicy,
+    rng: SeededRng,
+    ones: bool,
+    /// The track and physical sector index of the last sector read.
+    last_read: Option<(DiskCh, usize)>,
+}
+
+impl WeakBitGenerator {
+    pub(crate) fn new(policy: WeakBitPolicy) -> Self {
+        let seed = match policy {
+            WeakBitPolicy::Seeded(seed) => seed,
+            _ => 0,
+        };
+        WeakBitGenerator {
+            policy,
+            rng: SeededRng::new(seed),
+            // Toggled by the first call to begin_read(), so the first read returns zeros.
+            ones: true,
+            last_read: None,
+        }
+    }
+
+    pub(crate) fn policy(&self) -> WeakBitPolicy {
+        self.policy
+    }
+
+    /// Signal the start of a read of the sector at physical `index` on track `ch`. The read takes
+    /// place on a new revolution of the disk unless the sector follows the last sector read on the
+    /// same track. Only affects [WeakBitPolicy::Alternating], which toggles on each revolution.
+    pub(crate) fn begin_read(&mut self, ch: DiskCh, index: usize) {
+        let same_revolution = self
+            .last_read
+            .is_some_and(|(last_ch, last_index)| last_ch == ch && index > last_index);
+        if !same_revolution {
+            self.ones = !self.ones;
+        }
+        self.last_read = Some((ch, index));
+    }
+
+    /// Return a byte of weak bit values. Only bits set in the weak mask should be used.
+    pub(crate) fn next_byte(&mut self) -> u8 {
+        match self.policy {
+            WeakBitPolicy::Random => rand::random(),
+            WeakBitPolicy::Seeded(_) => self.rng.next_u64() as u8,
+            WeakBitPolicy::Ones => 0xFF,
+            WeakBitPolicy::Zeros => 0x00,
+            WeakBitPolicy::Alternating => {
+                if self.ones {
+                    0xFF
+                }
+                else {
+                    0x00
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,304 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/rotation.rs
+
+    A model of the rotation of a disk in a drive, for emulators that need to
+    track the rotational position of the disk and its index pulses.
+*/
+
+//! This module defines [DiskRotation], a model of the rotation of a disk in a drive.
+//!
+//! A [DiskRotation] tracks the angular position of the disk relative to the index, the number of
+//! revolutions completed and the time elapsed, as the emulator advances it. The rotation rate is
+//! given by a [DiskRpm], which may be changed at any time to model a dual speed 300/360 RPM drive,
+//! and may vary periodically by an [RpmWobble] to model the speed variation of a real drive.
+//! Callbacks registered with [DiskRotation::add_index_callback] are invoked for each index pulse.
+//!
+//! An emulator typically keeps one [DiskRotation] per drive. The rotational position can be
+//! converted into a bitcell offset into a track with [DiskRotation::bit_position], to find the next
+//! ID field with [DiskImage::next_id_timed], or to read a sector starting from the current position
+//! of the disk with [DiskImage::read_sector_rotational].
+//!
+//! [DiskImage::next_id_timed]: crate::DiskImage::next_id_timed
+//! [DiskImage::read_sector_rotational]: crate::DiskImage::read_sector_rotational
+
+use crate::types::DiskRpm;
+use std::{
+    f64::consts::TAU,
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+};
+
+/// The duration of the index pulse, in seconds. Drives assert the index signal for a few
+/// milliseconds as the index hole passes the sensor.
+pub const INDEX_PULSE_TIME: f64 = 0.002;
+
+/// A callback invoked by a [DiskRotation] for each index pulse.
+pub type IndexCallback = Arc<dyn Fn(IndexPulse) + Send + Sync>;
+
+/// An index pulse, as reported to an [IndexCallback].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct IndexPulse {
+    /// The number of revolutions completed, including the one ending with this pulse.
+    pub revolution: u64,
+    /// The time of the index pulse, in seconds since the rotation model was created.
+    pub time: f64,
+}
+
+/// A periodic variation of the rotation rate of a drive.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RpmWobble {
+    /// The peak deviation from the nominal rotation rate, as a fraction of it.
+    pub amplitude: f64,
+    /// The period of the variation, in seconds.
+    pub period:    f64,
+}
+
+/// A model of the rotational position of a disk in a drive. See the [module documentation](self).
+#[derive(Clone)]
+pub struct DiskRotation {
+    rpm: DiskRpm,
+    wobble: Option<RpmWobble>,
+    angle: f64,
+    revolutions: u64,
+    elapsed: f64,
+    index_callbacks: Vec<IndexCallback>,
+}
+
+impl Debug for DiskRotation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiskRotation")
+            .field("rpm", &self.rpm)
+            .field("wobble", &self.wobble)
+            .field("angle", &self.angle)
+            .field("revolutions", &self.revolutions)
+            .field("elapsed", &self.elapsed)
+            .field("index_callbacks", &self.index_callbacks.len())
+            .finish()
+    }
+}
+
+impl Default for DiskRotation {
+    fn default() -> Self {
+        Self::new(DiskRpm::default())
+    }
+}
+
+impl DiskRotation {
+    /// Create a new [DiskRotation] rotating at `rpm`, positioned at the index.
+    pub fn new(rpm: DiskRpm) -> Self {
+        Self {
+            rpm,
+            wobble: None,
+            angle: 0.0,
+            revolutions: 0,
+            elapsed: 0.0,
+            index_callbacks: Vec::new(),
+        }
+    }
+
+    /// Vary the rotation rate periodically by `wobble`.
+    pub fn with_wobble(self, wobble: RpmWobble) -> Self {
+        Self {
+            wobble: Some(wobble),
+            ..self
+        }
+    }
+
+    /// Register a callback to be invoked for each index pulse.
+    pub fn add_index_callback(&mut self, callback: IndexCallback) {
+        self.index_callbacks.push(callback);
+    }
+
+    /// Return the nominal rotation rate.
+    pub fn rpm(&self) -> DiskRpm {
+        self.rpm
+    }
+
+    /// Change the nominal rotation rate, as a dual speed drive switching between 300 and 360 RPM
+    /// would. The rotational position of the disk is unchanged.
+    pub fn set_rpm(&mut self, rpm: DiskRpm) {
+        self.rpm = rpm;
+    }
+
+    /// Return the instantaneous rotation rate in RPM, including any wobble.
+    pub fn current_rpm(&self) -> f64 {
+        let nominal = f64::from(self.rpm);
+        match self.wobble {
+            Some(wobble) if wobble.period > 0.0 => {
+                nominal * (1.0 + wobble.amplitude * (TAU * self.elapsed / wobble.period).sin())
+            }
+            _ => nominal,
+        }
+    }
+
+    /// Return the time for one revolution at the instantaneous rotation rate, in seconds.
+    pub fn revolution_time(&self) -> f64 {
+        60.0 / self.current_rpm()
+    }
+
+    /// Return the angular position of the disk as a fraction of a revolution past the index, in
+    /// the range `0.0..1.0`.
+    pub fn angle(&self) -> f64 {
+        self.angle
+    }
+
+    /// Set the angular position of the disk as a fraction of a revolution past the index. Whole
+    /// revolutions are discarded. No index pulse is generated.
+    pub fn set_angle(&mut self, angle: f64) {
+        self.angle = angle.rem_euclid(1.0);
+    }
+
+    /// Return the number of revolutions completed.
+    pub fn revolutions(&self) -> u64 {
+        self.revolutions
+    }
+
+    /// Return the time elapsed since the model was created, in seconds.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Return true if the index hole is passing the index sensor, asserting the index signal.
+    pub fn index_pulse(&self) -> bool {
+        self.angle * self.revolution_time() < INDEX_PULSE_TIME
+    }
+
+    /// Return the bitcell offset from the index under the head, for a track of `bit_length`
+    /// bitcells.
+    pub fn bit_position(&self, bit_length: usize) -> usize {
+        ((self.angle * bit_length as f64) as usize).min(bit_length.saturating_sub(1))
+    }
+
+    /// Return the time until the disk reaches the angular position `angle`, in seconds, at the
+    /// instantaneous rotation rate.
+    pub fn time_to_angle(&self, angle: f64) -> f64 {
+        (angle - self.angle).rem_euclid(1.0) * self.revolution_time()
+    }
+
+    /// Advance the model by `seconds`, invoking the index callbacks for each index pulse passed.
+    pub fn advance(&mut self, seconds: f64) {
+        if seconds <= 0.0 {
+            return;
+        }
+        let rps = f64::from(self.rpm) / 60.0;
+        let revolutions = match self.wobble {
+            // Integrate the rotation rate over the interval.
+            Some(wobble) if wobble.period > 0.0 => {
+                let w = TAU / wobble.period;
+                let t0 = self.elapsed;
+                let t1 = self.elapsed + seconds;
+                rps * (seconds + wobble.amplitude / w * ((w * t0).cos() - (w * t1).cos()))
+            }
+            _ => rps * seconds,
+        };
+        self.rotate(revolutions, seconds);
+    }
+
+    /// Advance the model by the time it takes `bits` bitcells of a track of `bit_length` bitcells
+    /// to pass under the head, invoking the index callbacks for each index pulse passed.
+    pub fn advance_bits(&mut self, bits: usize, bit_length: usize) {
+        if bit_length == 0 {
+            return;
+        }
+        let revolutions = bits as f64 / bit_length as f64;
+        self.rotate(revolutions, revolutions * self.revolution_time());
+    }
+
+    fn rotate(&mut self, revolutions: f64, seconds: f64) {
+        let total = self.angle + revolutions;
+        let pulses = total.floor() as u64;
+        for k in 1..=pulses {
+            // Estimate the time of each pulse by assuming a constant rate over the interval.
+            let time = self.elapsed + (k as f64 - self.angle) / revolutions * seconds;
+            self.revolutions += 1;
+            let pulse = IndexPulse {
+                revolution: self.revolutions,
+                time,
+            };
+            for callback in &self.index_callbacks {
+                callback(pulse);
+            }
+        }
+        self.angle = total.fract();
+        self.elapsed += seconds;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_rotation() {
+        let pulses = Arc::new(Mutex::new(Vec::new()));
+        let mut rotation = DiskRotation::new(DiskRpm::Rpm300(1.0));
+        let sink = pulses.clone();
+        rotation.add_index_callback(Arc::new(move |pulse| sink.lock().unwrap().push(pulse)));
+        assert!(rotation.index_pulse());
+
+        rotation.advance(0.1);
+        assert!((rotation.angle() - 0.5).abs() < 1e-9);
+        assert!(!rotation.index_pulse());
+        assert_eq!(rotation.bit_position(100_000), 50_000);
+        assert!((rotation.time_to_angle(0.0) - 0.1).abs() < 1e-9);
+
+        // Two index pulses pass, at 0.2 and 0.4 seconds.
+        rotation.advance(0.35);
+        assert_eq!(rotation.revolutions(), 2);
+        let pulses = pulses.lock().unwrap().clone();
+        assert_eq!(pulses.len(), 2);
+        assert!((pulses[1].time - 0.4).abs() < 1e-9);
+
+        // Switching to 360 RPM preserves the position but shortens the revolution.
+        rotation.set_rpm(DiskRpm::Rpm360(1.0));
+        assert!((rotation.angle() - 0.25).abs() < 1e-9);
+        assert!((rotation.revolution_time() - 60.0 / 360.0).abs() < 1e-9);
+
+        rotation.advance_bits(25_000, 100_000);
+        assert!((rotation.angle() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_wobble() {
+        let wobble = RpmWobble {
+            amplitude: 0.02,
+            period:    0.5,
+        };
+        let mut rotation = DiskRotation::new(DiskRpm::Rpm300(1.0)).with_wobble(wobble);
+
+        // A quarter period in, the drive runs at its fastest.
+        rotation.advance(0.125);
+        assert!((rotation.current_rpm() - 306.0).abs() < 1e-6);
+
+        // Over a whole period, the variation averages out.
+        rotation.advance(0.375);
+        assert_eq!(rotation.revolutions(), 2);
+        assert!((rotation.angle() - 0.5).abs() < 1e-9);
+    }
+}
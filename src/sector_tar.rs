@@ -0,0 +1,327 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/sector_tar.rs
+
+    Implements export and import of a disk image as a tar archive containing
+    one file per sector, plus a text manifest describing tracks and sector
+    flags. This gives a diff-friendly representation of a disk image that is
+    easy to keep in version control or edit with external tools.
+
+    The manifest is a line-oriented text file named `manifest.txt`:
+
+        fluxfox-sector-tar 1
+        track <c> <h> <encoding> <data rate in bits per second>
+        sector <path> <c> <h> <s> <n> <flags> [<weak mask path> [<hole mask path>]]
+
+    `sector` lines belong to the preceding `track` line and are listed in
+    physical order. `flags` is a comma-separated list of `address_error`,
+    `data_error`, `deleted` and `no_dam`, or `-` if none are set. A mask path
+    of `-` means the sector has no such mask. Tracks missing from the
+    manifest are imported as empty tracks.
+*/
+use crate::{
+    io::{Cursor, Read},
+    prelude::{DiskCh, DiskChsn},
+    text_dump::{flags_to_string, parse, parse_encoding, parse_flags, read_sector_at},
+    types::{AddSectorParams, MetaSectorTrackParams, TrackDataRate},
+    DiskImage,
+    DiskImageError,
+    FoxHashMap,
+};
+
+const MANIFEST_NAME: &str = "manifest.txt";
+const MANIFEST_MAGIC: &str = "fluxfox-sector-tar 1";
+
+/// Export `disk` as a tar archive of per-sector files.
+pub(crate) fn export_sector_tar(disk: &DiskImage) -> Result<Vec<u8>, DiskImageError> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut manifest = format!("{}\n", MANIFEST_MAGIC);
+
+    for head in 0..2u8 {
+        for cylinder in 0..disk.track_map[head as usize].len() {
+            let ch = DiskCh::new(cylinder as u16, head);
+            let Some(track) = disk.track(ch)
+            else {
+                continue;
+            };
+
+            manifest.push_str(&format!(
+                "track {} {} {} {}\n",
+                ch.c(),
+                ch.h(),
+                track.encoding(),
+                u32::from(track.info().data_rate)
+            ));
+
+            for (index, entry) in track.sector_list().iter().enumerate() {
+                let chsn = entry.chsn;
+                let (data, weak_mask) = read_sector_at(track, index)?;
+                let hole_mask = track.as_metasector_track().and_then(|t| t.hole_mask_at(index));
+                let base = format!(
+                    "c{:02}_h{}/{:03}_c{}_h{}_s{}_n{}",
+                    ch.c(),
+                    ch.h(),
+                    index,
+                    chsn.c(),
+                    chsn.h(),
+                    chsn.s(),
+                    chsn.n()
+                );
+
+                let data_path = format!("{}.bin", base);
+                append_file(&mut builder, &data_path, &data)?;
+
+                let mut line = format!(
+                    "sector {} {} {} {} {} {}",
+                    data_path,
+                    chsn.c(),
+                    chsn.h(),
+                    chsn.s(),
+                    chsn.n(),
+                    flags_to_string(&entry.attributes)
+                );
+                let weak_mask = weak_mask.filter(|mask| mask.iter().any(|&b| b != 0));
+                let hole_mask = hole_mask.filter(|mask| mask.iter().any(|&b| b != 0));
+                if let Some(weak_mask) = &weak_mask {
+                    let weak_path = format!("{}.weak", base);
+                    append_file(&mut builder, &weak_path, weak_mask)?;
+                    line.push_str(&format!(" {}", weak_path));
+                }
+                else if hole_mask.is_some() {
+                    line.push_str(" -");
+                }
+                if let Some(hole_mask) = hole_mask {
+                    let hole_path = format!("{}.hole", base);
+                    append_file(&mut builder, &hole_path, hole_mask)?;
+                    line.push_str(&format!(" {}", hole_path));
+                }
+                manifest.push_str(&line);
+                manifest.push('\n');
+            }
+        }
+    }
+
+    append_file(&mut builder, MANIFEST_NAME, manifest.as_bytes())?;
+    builder.finish()?;
+    Ok(builder.into_inner()?)
+}
+
+/// Import a tar archive of per-sector files produced by [export_sector_tar] as a `MetaSector`
+/// resolution [DiskImage].
+pub(crate) fn import_sector_tar(tar_data: &[u8]) -> Result<DiskImage, DiskImageError> {
+    let mut files: FoxHashMap<String, Vec<u8>> = FoxHashMap::new();
+    let mut archive = tar::Archive::new(Cursor::new(tar_data));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().to_string();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        files.insert(path, data);
+    }
+
+    let manifest = files
+        .get(MANIFEST_NAME)
+        .ok_or_else(|| corrupt(format!("{} not found", MANIFEST_NAME)))?;
+    let manifest = String::from_utf8_lossy(manifest);
+
+    let mut lines = manifest.lines().filter(|line| !line.trim().is_empty());
+    if lines.next().map(str::trim) != Some(MANIFEST_MAGIC) {
+        return Err(corrupt("Manifest header not found".to_string()));
+    }
+
+    let mut disk = DiskImage::default();
+    let mut heads = 1;
+    let mut cylinders = 0;
+    let mut first_track: Option<MetaSectorTrackParams> = None;
+    let mut current_track: Option<DiskCh> = None;
+
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["track", c, h, encoding, rate] => {
+                let ch = DiskCh::new(parse(c)?, parse(h)?);
+                if ch.h() > 1 || (ch.c() as usize) < disk.track_map[ch.h() as usize].len() {
+                    return Err(corrupt(format!("Track {} is out of order", ch)));
+                }
+                let params = MetaSectorTrackParams {
+                    ch,
+                    encoding: parse_encoding(encoding)?,
+                    data_rate: TrackDataRate::from(parse::<u32>(rate)?),
+                };
+                // Fill any tracks skipped by the manifest with empty tracks, to keep the track map
+                // contiguous.
+                for c in disk.track_map[ch.h() as usize].len()..ch.c() as usize {
+                    disk.add_track_metasector(&MetaSectorTrackParams {
+                        ch: DiskCh::new(c as u16, ch.h()),
+                        ..params
+                    })?;
+                }
+                disk.add_track_metasector(&params)?;
+                first_track.get_or_insert(params);
+                heads = heads.max(ch.h() + 1);
+                cylinders = cylinders.max(ch.c() + 1);
+                current_track = Some(ch);
+            }
+            ["sector", path, c, h, s, n, flags, mask_paths @ ..] if mask_paths.len() <= 2 => {
+                let ch = current_track.ok_or_else(|| corrupt("Sector defined before track".to_string()))?;
+                let data = files
+                    .get(*path)
+                    .ok_or_else(|| corrupt(format!("Sector file {} not found", path)))?;
+                let mask = |index: usize| match mask_paths.get(index).filter(|&&path| path != "-") {
+                    Some(path) => files
+                        .get(*path)
+                        .map(|mask| Some(mask.as_slice()))
+                        .ok_or_else(|| corrupt(format!("Mask file {} not found", path))),
+                    None => Ok(None),
+                };
+                let weak_mask = mask(0)?;
+                let hole_mask = mask(1)?;
+
+                let track = disk.track_mut(ch).ok_or(DiskImageError::SeekError)?;
+                track.add_sector(&AddSectorParams {
+                    id_chsn: DiskChsn::new(parse(c)?, parse(h)?, parse(s)?, parse(n)?),
+                    data,
+                    weak_mask,
+                    hole_mask,
+                    attributes: parse_flags(flags)?,
+                    ..Default::default()
+                })?;
+            }
+            _ => return Err(corrupt(format!("Invalid manifest line: {}", line))),
+        }
+    }
+
+    let Some(first_track) = first_track
+    else {
+        return Err(corrupt("No tracks found".to_string()));
+    };
+    disk.descriptor.geometry = DiskCh::new(cylinders, heads);
+    disk.descriptor.data_encoding = first_track.encoding;
+    disk.descriptor.data_rate = first_track.data_rate;
+    disk.descriptor.density = first_track.data_rate.into();
+
+    disk.post_load_process();
+    Ok(disk)
+}
+
+fn append_file(builder: &mut tar::Builder<Vec<u8>>, path: &str, data: &[u8]) -> Result<(), DiskImageError> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, path, data)?;
+    Ok(())
+}
+
+fn corrupt(msg: String) -> DiskImageError {
+    log::error!("import_sector_tar(): {}", msg);
+    DiskImageError::ImageCorruptError(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        prelude::*,
+        test_util::{add_track, empty_disk},
+        types::{AddSectorParams, SectorAttributes},
+    };
+
+    #[test]
+    fn test_sector_tar_round_trip() {
+        let mut disk = empty_disk();
+
+        let layout = StandardFormat::PcFloppy360.layout();
+        let weak_mask = [0x0Fu8; 512];
+        for ch in layout.ch_iter() {
+            let track = add_track(&mut disk, ch);
+            for s in 1..=layout.s() {
+                let odd = s % 2 == 1;
+                track
+                    .add_sector(&AddSectorParams {
+                        id_chsn: DiskChsn::from((DiskChs::from((ch, s)), layout.n())),
+                        data: &[s; 512],
+                        weak_mask: odd.then_some(&weak_mask[..]),
+                        attributes: SectorAttributes {
+                            data_error: odd,
+                            deleted_mark: s == 2,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .unwrap();
+            }
+        }
+
+        let tar_data = disk.export_sector_tar().unwrap();
+        let imported = DiskImage::import_sector_tar(&tar_data).unwrap();
+
+        assert_eq!(imported.geometry(), DiskCh::new(40, 2));
+        let diff = disk.diff(&imported);
+        assert!(diff.is_empty(), "{:?}", diff);
+    }
+
+    #[test]
+    fn test_sector_tar_holes_and_gaps() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let manifest = "fluxfox-sector-tar 1\n\
+                        track 2 0 MFM 250000\n\
+                        sector s1.bin 2 0 1 2 data_error - s1.hole\n";
+        for (path, data) in [
+            ("manifest.txt", manifest.as_bytes()),
+            ("s1.bin", &[0xF6; 512][..]),
+            ("s1.hole", &[0xFF; 512][..]),
+        ] {
+            super::append_file(&mut builder, path, data).unwrap();
+        }
+        builder.finish().unwrap();
+        let tar_data = builder.into_inner().unwrap();
+
+        // Cylinders 0 and 1 are missing from the manifest and are imported as empty tracks.
+        let imported = DiskImage::import_sector_tar(&tar_data).unwrap();
+        assert_eq!(imported.track(DiskCh::new(0, 0)).unwrap().sector_ct(), 0);
+        let track = imported.track(DiskCh::new(2, 0)).unwrap();
+        let hole_mask = track.as_metasector_track().unwrap().hole_mask_at(0).unwrap();
+        assert!(hole_mask.iter().all(|&b| b == 0xFF));
+
+        // The hole mask survives a second round trip.
+        let reimported = DiskImage::import_sector_tar(&imported.export_sector_tar().unwrap()).unwrap();
+        let track = reimported.track(DiskCh::new(2, 0)).unwrap();
+        let hole_mask = track.as_metasector_track().unwrap().hole_mask_at(0).unwrap();
+        assert!(hole_mask.iter().all(|&b| b == 0xFF));
+    }
+
+    #[test]
+    fn test_sector_tar_missing_manifest() {
+        let mut builder = tar::Builder::new(Vec::new());
+        builder.finish().unwrap();
+        let tar_data = builder.into_inner().unwrap();
+        assert!(matches!(
+            DiskImage::import_sector_tar(&tar_data),
+            Err(DiskImageError::ImageCorruptError(_))
+        ));
+    }
+}
@@ -119,16 +119,16 @@ impl StandardSectorView {
         if DiskChs::from((self.track_cursor, self.sector_id_cursor)) != chs {
             log::trace!("seek_to_offset(): Seeking to CHS: {}", chs);
 
+            // Commit the current sector. This must be done before the track cursor is updated.
+            self.commit_sector()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+
             // Do we need to switch tracks?
             if chs.ch() != self.track_cursor {
                 // Update the track cursor
                 self.track_cursor = DiskCh::from(chs);
             }
 
-            // Commit the current sector
-            self.commit_sector()
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-
             // Read the specified sector on this track into the sector buffer.
             self.sector_id_cursor = chs.s();
             self.read_sector(self.sector_id_cursor)
@@ -194,6 +194,9 @@ impl StandardSectorView {
     }
 
     fn next_sector(&mut self) -> Result<(), DiskImageError> {
+        // Commit the sector if needed. This must be done before the cursors are advanced.
+        self.commit_sector()?;
+
         self.sector_id_cursor += 1;
         if self.sector_id_cursor > self.spt {
             // Standard sector ids are 1-indexed.
@@ -202,9 +205,6 @@ impl StandardSectorView {
             log::trace!("next_sector(): Seek to new track: {}", self.track_cursor);
         }
 
-        // Commit the sector if needed.
-        self.commit_sector()?;
-
         if !self.eod {
             self.read_sector(self.sector_id_cursor)?;
         }
@@ -300,7 +300,7 @@ impl Seek for StandardSectorView {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::prelude::*;
+    use crate::{prelude::*, test_util::formatted_disk};
     use std::sync::{Arc, RwLock};
 
     fn create_view() -> StandardSectorView {
@@ -311,12 +311,7 @@ mod tests {
 
     fn create_test_disk_image() -> Arc<RwLock<DiskImage>> {
         // Create a mock DiskImage for testing purposes
-        let disk = ImageBuilder::new()
-            .with_standard_format(StandardFormat::PcFloppy360)
-            .with_formatted(true)
-            .with_resolution(TrackDataResolution::BitStream)
-            .build()
-            .unwrap();
+        let disk = formatted_disk();
 
         DiskImage::into_arc(disk)
     }
@@ -0,0 +1,124 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+
+//! A client for the fluxfox remote disk protocol.
+
+use super::protocol::{read_frame, write_frame, Request, Response, ResponseStatus, SectorStatusFlags};
+use crate::{
+    io::{Read, Write},
+    types::{DiskCh, DiskChsnQuery},
+    DiskImageError,
+};
+
+use std::net::{TcpStream, ToSocketAddrs};
+
+/// Basic information about a disk image served by a [DiskServer](super::DiskServer).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RemoteDiskInfo {
+    pub heads: u8,
+    pub cylinders: u16,
+    pub read_only: bool,
+}
+
+/// A [DiskClient] provides sector-level access to a disk image served by a
+/// [DiskServer](super::DiskServer).
+pub struct DiskClient<S: Read + Write = TcpStream> {
+    stream: S,
+}
+
+impl DiskClient<TcpStream> {
+    /// Connect to a [DiskServer](super::DiskServer) at the specified address.
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, DiskImageError> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(Self { stream })
+    }
+}
+
+impl<S: Read + Write> DiskClient<S> {
+    /// Create a [DiskClient] over an existing stream.
+    pub fn from_stream(stream: S) -> Self {
+        Self { stream }
+    }
+
+    /// Query basic information about the served disk image.
+    pub fn info(&mut self) -> Result<RemoteDiskInfo, DiskImageError> {
+        let response = self.transact(&Request::Info)?;
+        if response.data.len() < 4 {
+            return Err(DiskImageError::IoError("Info response too short".to_string()));
+        }
+        Ok(RemoteDiskInfo {
+            heads: response.data[0],
+            cylinders: u16::from_le_bytes([response.data[1], response.data[2]]),
+            read_only: response.data[3] != 0,
+        })
+    }
+
+    /// Read the sector matching `id` from physical track `ch`. The returned [SectorStatusFlags]
+    /// report any error conditions the sector was read with.
+    pub fn read_sector(
+        &mut self,
+        ch: DiskCh,
+        id: DiskChsnQuery,
+    ) -> Result<(Vec<u8>, SectorStatusFlags), DiskImageError> {
+        let response = self.transact(&Request::ReadSector { ch, id })?;
+        Ok((response.data, response.flags))
+    }
+
+    /// Write `data` to the sector matching `id` on physical track `ch`.
+    pub fn write_sector(
+        &mut self,
+        ch: DiskCh,
+        id: DiskChsnQuery,
+        data: &[u8],
+        deleted: bool,
+    ) -> Result<SectorStatusFlags, DiskImageError> {
+        let response = self.transact(&Request::WriteSector {
+            ch,
+            id,
+            deleted,
+            data: data.to_vec(),
+        })?;
+        Ok(response.flags)
+    }
+
+    /// End the session. The server will close the connection.
+    pub fn close(mut self) -> Result<(), DiskImageError> {
+        write_frame(&mut self.stream, &Request::Close.to_bytes())
+    }
+
+    fn transact(&mut self, request: &Request) -> Result<Response, DiskImageError> {
+        write_frame(&mut self.stream, &request.to_bytes())?;
+        let payload = read_frame(&mut self.stream)?
+            .ok_or_else(|| DiskImageError::IoError("Remote disk server closed the connection".to_string()))?;
+        let response = Response::from_bytes(&payload)?;
+        match response.status {
+            ResponseStatus::Ok => Ok(response),
+            status => Err(status.into()),
+        }
+    }
+}
@@ -0,0 +1,236 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+
+//! A module implementing a simple remote disk service. A [DiskServer] serves a [DiskImage] over
+//! TCP, allowing an emulator running on another machine to read and write sectors of an image
+//! managed by fluxfox. A matching [DiskClient] is provided.
+//!
+//! The wire format is described in the [protocol] module.
+//!
+//! An [NbdExport] is also provided, which exports the linear sector view of a disk image as a
+//! Network Block Device so that it can be mounted directly by a host OS.
+//!
+//! This module requires the `server` feature and is not available on wasm32 targets.
+
+pub mod client;
+pub mod nbd;
+pub mod protocol;
+
+pub use client::DiskClient;
+pub use nbd::NbdExport;
+
+use crate::{
+    io::{Read, Write},
+    types::RwScope,
+    DiskImage,
+    DiskImageError,
+};
+use protocol::{read_frame, write_frame, Request, Response, ResponseStatus, SectorStatusFlags};
+
+use std::{
+    net::{TcpListener, ToSocketAddrs},
+    sync::{Arc, RwLock},
+    thread,
+};
+
+/// A [DiskServer] serves sector-level access to a shared [DiskImage].
+#[derive(Clone)]
+pub struct DiskServer {
+    disk: Arc<RwLock<DiskImage>>,
+    read_only: bool,
+}
+
+impl DiskServer {
+    /// Create a new [DiskServer] for the specified disk image.
+    pub fn new(disk: Arc<RwLock<DiskImage>>) -> Self {
+        Self { disk, read_only: false }
+    }
+
+    /// Set whether the disk image should be presented to clients as write-protected.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Listen on the specified address and serve clients until the listener fails.
+    /// Each client connection is handled on its own thread.
+    pub fn serve(&self, addr: impl ToSocketAddrs) -> Result<(), DiskImageError> {
+        let listener = TcpListener::bind(addr)?;
+        log::debug!("serve(): Listening on {:?}", listener.local_addr());
+
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let peer = stream.peer_addr().ok();
+            log::debug!("serve(): Accepted connection from {:?}", peer);
+
+            let server = self.clone();
+            thread::spawn(move || {
+                if let Err(e) = server.handle_stream(stream) {
+                    log::error!("serve(): Connection from {:?} failed: {}", peer, e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Handle requests on a single connection until the client closes it or sends a `Close`
+    /// request. This can be used with any stream, not just a `TcpStream`.
+    pub fn handle_stream<S: Read + Write>(&self, mut stream: S) -> Result<(), DiskImageError> {
+        while let Some(payload) = read_frame(&mut stream)? {
+            let response = match Request::from_bytes(&payload) {
+                Ok(Request::Close) => break,
+                Ok(request) => self.handle_request(request),
+                Err(e) => {
+                    log::warn!("handle_stream(): Malformed request: {}", e);
+                    Response::error(ResponseStatus::ParameterError)
+                }
+            };
+            write_frame(&mut stream, &response.to_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Process a single [Request] and return its [Response].
+    pub fn handle_request(&self, request: Request) -> Response {
+        match self.process_request(request) {
+            Ok(response) => response,
+            Err(e) => Response::error(ResponseStatus::from(&e)),
+        }
+    }
+
+    fn process_request(&self, request: Request) -> Result<Response, DiskImageError> {
+        match request {
+            Request::Close => Ok(Response::ok(SectorStatusFlags::empty(), Vec::new())),
+            Request::Info => {
+                let disk = self.lock_read()?;
+                let mut data = Vec::with_capacity(4);
+                data.push(disk.heads());
+                data.extend_from_slice(&(disk.track_ct(0) as u16).to_le_bytes());
+                data.push(self.read_only as u8);
+                Ok(Response::ok(SectorStatusFlags::empty(), data))
+            }
+            Request::ReadSector { ch, id } => {
+                let disk = self.lock_read()?;
+                let track = disk.track(ch).ok_or(DiskImageError::SeekError)?;
+                let rsr = track.read_sector(id, id.n(), None, RwScope::DataOnly, false)?;
+                if rsr.not_found {
+                    return Err(DiskImageError::IdError);
+                }
+                let flags = SectorStatusFlags::from(&rsr);
+                Ok(Response::ok(flags, rsr.data().to_vec()))
+            }
+            Request::WriteSector { ch, id, deleted, data } => {
+                if self.read_only {
+                    return Err(DiskImageError::WriteProtectError);
+                }
+                let mut disk = self.lock_write()?;
+                let wsr = disk.write_sector(ch, id, None, &data, RwScope::DataOnly, deleted, false)?;
+                if wsr.not_found {
+                    return Err(DiskImageError::IdError);
+                }
+                let mut flags = SectorStatusFlags::empty();
+                flags.set(SectorStatusFlags::ADDRESS_CRC_ERROR, wsr.address_crc_error);
+                flags.set(SectorStatusFlags::NO_DAM, wsr.no_dam);
+                Ok(Response::ok(flags, Vec::new()))
+            }
+        }
+    }
+
+    fn lock_read(&self) -> Result<std::sync::RwLockReadGuard<'_, DiskImage>, DiskImageError> {
+        self.disk.read().map_err(|e| DiskImageError::SyncError(e.to_string()))
+    }
+
+    fn lock_write(&self) -> Result<std::sync::RwLockWriteGuard<'_, DiskImage>, DiskImageError> {
+        self.disk.write().map_err(|e| DiskImageError::SyncError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::sector_disk,
+        types::{DiskCh, DiskChsnQuery, StandardFormat},
+    };
+    use std::net::TcpListener;
+
+    fn serve_once(server: DiskServer) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            server.handle_stream(stream).unwrap();
+        });
+        addr
+    }
+
+    pub(crate) fn test_disk() -> Arc<RwLock<DiskImage>> {
+        sector_disk(StandardFormat::PcFloppy360, |_| (vec![0; 512], Default::default())).into_arc()
+    }
+
+    #[test]
+    fn test_remote_read_write() {
+        let addr = serve_once(DiskServer::new(test_disk()));
+        let mut client = DiskClient::connect(addr).unwrap();
+
+        let info = client.info().unwrap();
+        assert_eq!(info.heads, 2);
+        assert_eq!(info.cylinders, 40);
+        assert!(!info.read_only);
+
+        let ch = DiskCh::new(5, 1);
+        let id = DiskChsnQuery::new(5, 1, 3, 2);
+        let write_data = vec![0xA5; 512];
+        client.write_sector(ch, id, &write_data, false).unwrap();
+
+        let (data, flags) = client.read_sector(ch, id).unwrap();
+        assert_eq!(data, write_data);
+        assert!(flags.is_empty());
+
+        // A query with no cylinder, head or size must reach the server as a wildcard.
+        let wildcard = DiskChsnQuery::new(None, None, 3, None);
+        let request = Request::ReadSector { ch, id: wildcard };
+        assert_eq!(Request::from_bytes(&request.to_bytes()).unwrap(), request);
+        let (data, _) = client.read_sector(ch, wildcard).unwrap();
+        assert_eq!(data, write_data);
+
+        let missing = DiskChsnQuery::new(5, 1, 12, 2);
+        assert!(matches!(client.read_sector(ch, missing), Err(DiskImageError::IdError)));
+        client.close().unwrap();
+    }
+
+    #[test]
+    fn test_remote_read_only() {
+        let addr = serve_once(DiskServer::new(test_disk()).with_read_only(true));
+        let mut client = DiskClient::connect(addr).unwrap();
+
+        assert!(client.info().unwrap().read_only);
+        let result = client.write_sector(DiskCh::new(0, 0), DiskChsnQuery::new(0, 0, 1, 2), &[0; 512], false);
+        assert!(matches!(result, Err(DiskImageError::WriteProtectError)));
+        client.close().unwrap();
+    }
+}
@@ -0,0 +1,476 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+
+//! An export of a [DiskImage] over the Network Block Device (NBD) protocol.
+//!
+//! An [NbdExport] presents the linear sector view of a disk image as a block device, in the
+//! manner of a Gotek USB floppy emulator. The export can be attached with a standard NBD client,
+//! such as `nbd-client` on Linux, allowing the host OS to mount a FAT12 floppy whose underlying
+//! image is a bitstream or flux format such as HFE or SCP:
+//!
+//! ```text
+//! nbd-client -N fluxfox 127.0.0.1 10809 /dev/nbd0
+//! mount /dev/nbd0 /mnt/floppy
+//! ```
+//!
+//! Only the fixed newstyle handshake is supported. The `NBD_OPT_EXPORT_NAME`, `NBD_OPT_GO`,
+//! `NBD_OPT_INFO`, `NBD_OPT_LIST` and `NBD_OPT_ABORT` options and the `READ`, `WRITE`, `FLUSH`
+//! and `DISC` commands are implemented. The export name sent by the client is ignored.
+
+use crate::{
+    disk_lock::NonTrackingDiskLock,
+    io::{Read, Seek, SeekFrom, Write},
+    sector_view::StandardSectorView,
+    DiskImage,
+    DiskImageError,
+    StandardFormat,
+};
+
+use std::{
+    net::{TcpListener, ToSocketAddrs},
+    sync::{Arc, RwLock},
+    thread,
+};
+
+/// The default TCP port for NBD servers.
+pub const NBD_DEFAULT_PORT: u16 = 10809;
+
+const NBD_MAGIC: u64 = 0x4e42_444d_4147_4943; // "NBDMAGIC"
+const NBD_OPTS_MAGIC: u64 = 0x4948_4156_454f_5054; // "IHAVEOPT"
+const NBD_REP_MAGIC: u64 = 0x0003_e889_0455_65a9;
+const NBD_REQUEST_MAGIC: u32 = 0x2560_9513;
+const NBD_SIMPLE_REPLY_MAGIC: u32 = 0x6744_6698;
+
+const NBD_FLAG_FIXED_NEWSTYLE: u16 = 1 << 0;
+const NBD_FLAG_NO_ZEROES: u16 = 1 << 1;
+const NBD_FLAG_C_NO_ZEROES: u32 = 1 << 1;
+
+const NBD_FLAG_HAS_FLAGS: u16 = 1 << 0;
+const NBD_FLAG_READ_ONLY: u16 = 1 << 1;
+const NBD_FLAG_SEND_FLUSH: u16 = 1 << 2;
+
+const NBD_OPT_EXPORT_NAME: u32 = 1;
+const NBD_OPT_ABORT: u32 = 2;
+const NBD_OPT_LIST: u32 = 3;
+const NBD_OPT_INFO: u32 = 6;
+const NBD_OPT_GO: u32 = 7;
+
+const NBD_REP_ACK: u32 = 1;
+const NBD_REP_SERVER: u32 = 2;
+const NBD_REP_INFO: u32 = 3;
+const NBD_REP_ERR_UNSUP: u32 = (1 << 31) + 1;
+const NBD_INFO_EXPORT: u16 = 0;
+
+const NBD_CMD_READ: u16 = 0;
+const NBD_CMD_WRITE: u16 = 1;
+const NBD_CMD_DISC: u16 = 2;
+const NBD_CMD_FLUSH: u16 = 3;
+
+const NBD_EPERM: u32 = 1;
+const NBD_EIO: u32 = 5;
+const NBD_EINVAL: u32 = 22;
+const NBD_ENOSPC: u32 = 28;
+
+/// The name advertised for the export in response to `NBD_OPT_LIST`.
+const EXPORT_NAME: &str = "fluxfox";
+
+/// Limit a single request to a sane size so that a misbehaving client can't exhaust memory.
+const MAX_REQUEST_LEN: u32 = 32 * 1024 * 1024;
+
+/// An [NbdExport] serves the linear sector view of a [DiskImage] to NBD clients.
+#[derive(Clone)]
+pub struct NbdExport {
+    disk: Arc<RwLock<DiskImage>>,
+    format: StandardFormat,
+    read_only: bool,
+}
+
+impl NbdExport {
+    /// Create a new [NbdExport] for the specified disk image.
+    ///
+    /// # Arguments
+    /// - `disk`: A reference-counted `RwLock` wrapping a `DiskImage` object.
+    /// - `format`: An optional `StandardFormat` describing the sector layout to export. If `None`,
+    ///   the closest standard format to the disk image will be used.
+    pub fn new(disk: Arc<RwLock<DiskImage>>, format: Option<StandardFormat>) -> Result<Self, DiskImageError> {
+        let format = match format {
+            Some(format) => format,
+            None => disk
+                .read()
+                .map_err(|e| DiskImageError::SyncError(e.to_string()))?
+                .closest_format(true)
+                .ok_or(DiskImageError::IncompatibleImage(
+                    "Could not determine a standard format for export".to_string(),
+                ))?,
+        };
+
+        Ok(Self {
+            disk,
+            format,
+            read_only: false,
+        })
+    }
+
+    /// Set whether the export should be presented to clients as read-only.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Return the size of the exported block device in bytes.
+    pub fn size(&self) -> u64 {
+        self.format.disk_size() as u64
+    }
+
+    /// Listen on the specified address and serve NBD clients until the listener fails.
+    /// Each client connection is handled on its own thread.
+    pub fn serve(&self, addr: impl ToSocketAddrs) -> Result<(), DiskImageError> {
+        let listener = TcpListener::bind(addr)?;
+        log::debug!("NbdExport::serve(): Listening on {:?}", listener.local_addr());
+
+        for stream in listener.incoming() {
+            let stream = stream?;
+            stream.set_nodelay(true)?;
+            let peer = stream.peer_addr().ok();
+            log::debug!("NbdExport::serve(): Accepted connection from {:?}", peer);
+
+            let export = self.clone();
+            thread::spawn(move || {
+                if let Err(e) = export.handle_stream(stream) {
+                    log::error!("NbdExport::serve(): Connection from {:?} failed: {}", peer, e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Perform the NBD handshake and then serve transmission requests on a single connection
+    /// until the client disconnects.
+    pub fn handle_stream<S: Read + Write>(&self, mut stream: S) -> Result<(), DiskImageError> {
+        if self.handshake(&mut stream)? {
+            self.transmission(&mut stream)?;
+        }
+        Ok(())
+    }
+
+    fn transmission_flags(&self) -> u16 {
+        let mut flags = NBD_FLAG_HAS_FLAGS | NBD_FLAG_SEND_FLUSH;
+        if self.read_only {
+            flags |= NBD_FLAG_READ_ONLY;
+        }
+        flags
+    }
+
+    /// Perform the fixed newstyle handshake and option haggling. Returns `true` if the client
+    /// entered the transmission phase, or `false` if it aborted.
+    fn handshake<S: Read + Write>(&self, stream: &mut S) -> Result<bool, DiskImageError> {
+        stream.write_all(&NBD_MAGIC.to_be_bytes())?;
+        stream.write_all(&NBD_OPTS_MAGIC.to_be_bytes())?;
+        stream.write_all(&(NBD_FLAG_FIXED_NEWSTYLE | NBD_FLAG_NO_ZEROES).to_be_bytes())?;
+        stream.flush()?;
+
+        let client_flags = read_u32(stream)?;
+        let no_zeroes = client_flags & NBD_FLAG_C_NO_ZEROES != 0;
+
+        loop {
+            if read_u64(stream)? != NBD_OPTS_MAGIC {
+                log::error!("NbdExport::handshake(): Bad option magic from client");
+                return Err(DiskImageError::IoError("Bad NBD option magic".to_string()));
+            }
+            let option = read_u32(stream)?;
+            let len = read_u32(stream)?;
+            if len > MAX_REQUEST_LEN {
+                return Err(DiskImageError::IoError("NBD option data too long".to_string()));
+            }
+            let mut data = vec![0u8; len as usize];
+            stream.read_exact(&mut data)?;
+
+            match option {
+                NBD_OPT_EXPORT_NAME => {
+                    // This option has no reply header; the export info is sent directly.
+                    stream.write_all(&self.size().to_be_bytes())?;
+                    stream.write_all(&self.transmission_flags().to_be_bytes())?;
+                    if !no_zeroes {
+                        stream.write_all(&[0u8; 124])?;
+                    }
+                    stream.flush()?;
+                    return Ok(true);
+                }
+                NBD_OPT_ABORT => {
+                    write_option_reply(stream, option, NBD_REP_ACK, &[])?;
+                    return Ok(false);
+                }
+                NBD_OPT_LIST => {
+                    let mut reply = Vec::with_capacity(4 + EXPORT_NAME.len());
+                    reply.extend_from_slice(&(EXPORT_NAME.len() as u32).to_be_bytes());
+                    reply.extend_from_slice(EXPORT_NAME.as_bytes());
+                    write_option_reply(stream, option, NBD_REP_SERVER, &reply)?;
+                    write_option_reply(stream, option, NBD_REP_ACK, &[])?;
+                }
+                NBD_OPT_INFO | NBD_OPT_GO => {
+                    let mut info = Vec::with_capacity(12);
+                    info.extend_from_slice(&NBD_INFO_EXPORT.to_be_bytes());
+                    info.extend_from_slice(&self.size().to_be_bytes());
+                    info.extend_from_slice(&self.transmission_flags().to_be_bytes());
+                    write_option_reply(stream, option, NBD_REP_INFO, &info)?;
+                    write_option_reply(stream, option, NBD_REP_ACK, &[])?;
+                    if option == NBD_OPT_GO {
+                        return Ok(true);
+                    }
+                }
+                _ => {
+                    log::debug!("NbdExport::handshake(): Unsupported option: {}", option);
+                    write_option_reply(stream, option, NBD_REP_ERR_UNSUP, &[])?;
+                }
+            }
+        }
+    }
+
+    fn transmission<S: Read + Write>(&self, stream: &mut S) -> Result<(), DiskImageError> {
+        let mut view = StandardSectorView::new(NonTrackingDiskLock::new(self.disk.clone()), self.format)?;
+        let size = self.size();
+
+        loop {
+            let magic = match read_u32(stream) {
+                Ok(magic) => magic,
+                // Treat a closed connection as an implicit disconnect.
+                Err(DiskImageError::IoError(_)) => break,
+                Err(e) => return Err(e),
+            };
+            if magic != NBD_REQUEST_MAGIC {
+                log::error!("NbdExport::transmission(): Bad request magic: {:08X}", magic);
+                return Err(DiskImageError::IoError("Bad NBD request magic".to_string()));
+            }
+            let _flags = read_u16(stream)?;
+            let command = read_u16(stream)?;
+            let handle = read_u64(stream)?;
+            let offset = read_u64(stream)?;
+            let len = read_u32(stream)?;
+
+            let in_bounds = offset.checked_add(len as u64).is_some_and(|end| end <= size);
+
+            match command {
+                NBD_CMD_READ => {
+                    if !in_bounds || len > MAX_REQUEST_LEN {
+                        write_simple_reply(stream, NBD_EINVAL, handle, &[])?;
+                        continue;
+                    }
+                    let mut buf = vec![0u8; len as usize];
+                    match view
+                        .seek(SeekFrom::Start(offset))
+                        .and_then(|_| view.read_exact(&mut buf))
+                    {
+                        Ok(_) => write_simple_reply(stream, 0, handle, &buf)?,
+                        Err(e) => {
+                            log::error!("NbdExport::transmission(): Read error at offset {}: {}", offset, e);
+                            write_simple_reply(stream, NBD_EIO, handle, &[])?;
+                        }
+                    }
+                }
+                NBD_CMD_WRITE => {
+                    if len > MAX_REQUEST_LEN {
+                        return Err(DiskImageError::IoError("NBD write request too long".to_string()));
+                    }
+                    // The write payload must be consumed even if we reject the request.
+                    let mut buf = vec![0u8; len as usize];
+                    stream.read_exact(&mut buf)?;
+
+                    let error = if self.read_only {
+                        NBD_EPERM
+                    }
+                    else if !in_bounds {
+                        NBD_ENOSPC
+                    }
+                    else {
+                        match view
+                            .seek(SeekFrom::Start(offset))
+                            .and_then(|_| view.write_all(&buf))
+                            .and_then(|_| view.flush())
+                        {
+                            Ok(_) => 0,
+                            Err(e) => {
+                                log::error!("NbdExport::transmission(): Write error at offset {}: {}", offset, e);
+                                NBD_EIO
+                            }
+                        }
+                    };
+                    write_simple_reply(stream, error, handle, &[])?;
+                }
+                NBD_CMD_FLUSH => {
+                    let error = if view.flush().is_ok() { 0 } else { NBD_EIO };
+                    write_simple_reply(stream, error, handle, &[])?;
+                }
+                NBD_CMD_DISC => {
+                    view.flush()?;
+                    break;
+                }
+                _ => {
+                    write_simple_reply(stream, NBD_EINVAL, handle, &[])?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_option_reply<W: Write>(stream: &mut W, option: u32, reply: u32, data: &[u8]) -> Result<(), DiskImageError> {
+    stream.write_all(&NBD_REP_MAGIC.to_be_bytes())?;
+    stream.write_all(&option.to_be_bytes())?;
+    stream.write_all(&reply.to_be_bytes())?;
+    stream.write_all(&(data.len() as u32).to_be_bytes())?;
+    stream.write_all(data)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn write_simple_reply<W: Write>(stream: &mut W, error: u32, handle: u64, data: &[u8]) -> Result<(), DiskImageError> {
+    stream.write_all(&NBD_SIMPLE_REPLY_MAGIC.to_be_bytes())?;
+    stream.write_all(&error.to_be_bytes())?;
+    stream.write_all(&handle.to_be_bytes())?;
+    stream.write_all(data)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn read_u16<R: Read>(stream: &mut R) -> Result<u16, DiskImageError> {
+    let mut buf = [0u8; 2];
+    stream.read_exact(&mut buf)?;
+    Ok(u16::from_be_bytes(buf))
+}
+
+fn read_u32<R: Read>(stream: &mut R) -> Result<u32, DiskImageError> {
+    let mut buf = [0u8; 4];
+    stream.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn read_u64<R: Read>(stream: &mut R) -> Result<u64, DiskImageError> {
+    let mut buf = [0u8; 8];
+    stream.read_exact(&mut buf)?;
+    Ok(u64::from_be_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::test_disk;
+    use std::net::{TcpListener, TcpStream};
+
+    fn connect(export: NbdExport) -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            export.handle_stream(stream).unwrap();
+        });
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        assert_eq!(read_u64(&mut stream).unwrap(), NBD_MAGIC);
+        assert_eq!(read_u64(&mut stream).unwrap(), NBD_OPTS_MAGIC);
+        let _ = read_u16(&mut stream).unwrap();
+        stream.write_all(&NBD_FLAG_C_NO_ZEROES.to_be_bytes()).unwrap();
+        stream
+    }
+
+    fn request(stream: &mut TcpStream, command: u16, handle: u64, offset: u64, len: u32, data: &[u8]) -> u32 {
+        stream.write_all(&NBD_REQUEST_MAGIC.to_be_bytes()).unwrap();
+        stream.write_all(&0u16.to_be_bytes()).unwrap();
+        stream.write_all(&command.to_be_bytes()).unwrap();
+        stream.write_all(&handle.to_be_bytes()).unwrap();
+        stream.write_all(&offset.to_be_bytes()).unwrap();
+        stream.write_all(&len.to_be_bytes()).unwrap();
+        stream.write_all(data).unwrap();
+
+        assert_eq!(read_u32(stream).unwrap(), NBD_SIMPLE_REPLY_MAGIC);
+        let error = read_u32(stream).unwrap();
+        assert_eq!(read_u64(stream).unwrap(), handle);
+        error
+    }
+
+    #[test]
+    fn test_nbd_export_name_read_write() {
+        let export = NbdExport::new(test_disk(), Some(StandardFormat::PcFloppy360)).unwrap();
+        let size = export.size();
+        let mut stream = connect(export);
+
+        stream.write_all(&NBD_OPTS_MAGIC.to_be_bytes()).unwrap();
+        stream.write_all(&NBD_OPT_EXPORT_NAME.to_be_bytes()).unwrap();
+        stream.write_all(&0u32.to_be_bytes()).unwrap();
+        assert_eq!(read_u64(&mut stream).unwrap(), size);
+        let flags = read_u16(&mut stream).unwrap();
+        assert_eq!(flags & NBD_FLAG_READ_ONLY, 0);
+
+        // Write across a track boundary (last sector of track 0, first sector of track 1).
+        let write_data: Vec<u8> = (0..1024).map(|i| (i % 251) as u8).collect();
+        assert_eq!(request(&mut stream, NBD_CMD_WRITE, 1, 8 * 512, 1024, &write_data), 0);
+
+        assert_eq!(request(&mut stream, NBD_CMD_READ, 2, 8 * 512, 1024, &[]), 0);
+        let mut read_data = vec![0u8; 1024];
+        stream.read_exact(&mut read_data).unwrap();
+        assert_eq!(read_data, write_data);
+
+        // Out of bounds reads are rejected.
+        assert_eq!(request(&mut stream, NBD_CMD_READ, 3, size, 512, &[]), NBD_EINVAL);
+
+        request(&mut stream, NBD_CMD_FLUSH, 4, 0, 0, &[]);
+        stream.write_all(&NBD_REQUEST_MAGIC.to_be_bytes()).unwrap();
+        stream.write_all(&[0, 0, 0, NBD_CMD_DISC as u8]).unwrap();
+        stream.write_all(&[0u8; 20]).unwrap();
+    }
+
+    #[test]
+    fn test_nbd_go_read_only() {
+        let export = NbdExport::new(test_disk(), Some(StandardFormat::PcFloppy360))
+            .unwrap()
+            .with_read_only(true);
+        let mut stream = connect(export);
+
+        stream.write_all(&NBD_OPTS_MAGIC.to_be_bytes()).unwrap();
+        stream.write_all(&NBD_OPT_GO.to_be_bytes()).unwrap();
+        stream.write_all(&6u32.to_be_bytes()).unwrap();
+        stream.write_all(&[0, 0, 0, 0, 0, 0]).unwrap();
+
+        // NBD_REP_INFO
+        assert_eq!(read_u64(&mut stream).unwrap(), NBD_REP_MAGIC);
+        assert_eq!(read_u32(&mut stream).unwrap(), NBD_OPT_GO);
+        assert_eq!(read_u32(&mut stream).unwrap(), NBD_REP_INFO);
+        assert_eq!(read_u32(&mut stream).unwrap(), 12);
+        assert_eq!(read_u16(&mut stream).unwrap(), NBD_INFO_EXPORT);
+        assert_eq!(
+            read_u64(&mut stream).unwrap(),
+            StandardFormat::PcFloppy360.disk_size() as u64
+        );
+        assert_ne!(read_u16(&mut stream).unwrap() & NBD_FLAG_READ_ONLY, 0);
+        // NBD_REP_ACK
+        assert_eq!(read_u64(&mut stream).unwrap(), NBD_REP_MAGIC);
+        assert_eq!(read_u32(&mut stream).unwrap(), NBD_OPT_GO);
+        assert_eq!(read_u32(&mut stream).unwrap(), NBD_REP_ACK);
+        assert_eq!(read_u32(&mut stream).unwrap(), 0);
+
+        assert_eq!(request(&mut stream, NBD_CMD_WRITE, 1, 0, 512, &[0xAA; 512]), NBD_EPERM);
+    }
+}
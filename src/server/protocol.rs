@@ -0,0 +1,307 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+
+//! Definitions for the fluxfox remote disk protocol.
+//!
+//! The protocol is a simple, synchronous request/response protocol. Every message in either
+//! direction is sent as a frame consisting of a little-endian `u32` payload length followed by
+//! the payload itself.
+//!
+//! A request payload begins with a single opcode byte, followed by opcode-specific parameters:
+//!
+//! | Opcode | Request        | Parameters                                                      |
+//! |--------|----------------|-----------------------------------------------------------------|
+//! | `0x00` | `Close`        | None                                                            |
+//! | `0x01` | `Info`         | None                                                            |
+//! | `0x02` | `ReadSector`   | `phys_c: u16, phys_h: u8, c: u16, h: u8, s: u8, n: u8`          |
+//! | `0x03` | `WriteSector`  | `phys_c: u16, phys_h: u8, c: u16, h: u8, s: u8, n: u8, deleted: u8, data: [u8]` |
+//!
+//! A `c` value of [ANY_CYLINDER], an `h` value of [ANY_HEAD] and an `n` value of
+//! [ANY_SECTOR_SIZE] match a sector with any cylinder, head or size in its ID.
+//!
+//! A response payload begins with a [ResponseStatus] byte and a [SectorStatusFlags] byte,
+//! followed by any response data. An `Info` request returns the head count (`u8`), cylinder count
+//! (`u16`) and a write-protect flag (`u8`). A `ReadSector` request returns the sector data.
+
+use crate::{
+    io::{ErrorKind, Read, Write},
+    types::{DiskCh, DiskChsnQuery, ReadSectorResult},
+    DiskImageError,
+    MAXIMUM_SECTOR_SIZE,
+};
+
+use bitflags::bitflags;
+
+/// The maximum size of a frame payload we will accept. This is enough to hold the largest
+/// sector we support along with request parameters.
+pub const MAX_FRAME_LEN: usize = MAXIMUM_SECTOR_SIZE + 64;
+
+/// A cylinder value that will match sectors with any cylinder ID.
+pub const ANY_CYLINDER: u16 = 0xFFFF;
+/// A head value that will match sectors with any head ID.
+pub const ANY_HEAD: u8 = 0xFF;
+/// A sector size value that will match sectors of any size.
+pub const ANY_SECTOR_SIZE: u8 = 0xFF;
+
+const OP_CLOSE: u8 = 0x00;
+const OP_INFO: u8 = 0x01;
+const OP_READ_SECTOR: u8 = 0x02;
+const OP_WRITE_SECTOR: u8 = 0x03;
+
+/// A request sent from a remote disk client to a [DiskServer](super::DiskServer).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Request {
+    /// End the session.
+    Close,
+    /// Request basic information about the served disk image.
+    Info,
+    /// Read the sector matching `id` from the physical track `ch`.
+    ReadSector { ch: DiskCh, id: DiskChsnQuery },
+    /// Write `data` to the sector matching `id` on the physical track `ch`.
+    WriteSector { ch: DiskCh, id: DiskChsnQuery, deleted: bool, data: Vec<u8> },
+}
+
+/// The status code returned at the start of every response.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResponseStatus {
+    Ok = 0x00,
+    SeekError = 0x01,
+    IdError = 0x02,
+    WriteProtectError = 0x03,
+    ParameterError = 0x04,
+    Error = 0xFF,
+}
+
+impl From<u8> for ResponseStatus {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => ResponseStatus::Ok,
+            0x01 => ResponseStatus::SeekError,
+            0x02 => ResponseStatus::IdError,
+            0x03 => ResponseStatus::WriteProtectError,
+            0x04 => ResponseStatus::ParameterError,
+            _ => ResponseStatus::Error,
+        }
+    }
+}
+
+impl From<&DiskImageError> for ResponseStatus {
+    fn from(err: &DiskImageError) -> Self {
+        match err {
+            DiskImageError::SeekError => ResponseStatus::SeekError,
+            DiskImageError::IdError => ResponseStatus::IdError,
+            DiskImageError::WriteProtectError => ResponseStatus::WriteProtectError,
+            DiskImageError::ParameterError => ResponseStatus::ParameterError,
+            _ => ResponseStatus::Error,
+        }
+    }
+}
+
+impl From<ResponseStatus> for DiskImageError {
+    fn from(status: ResponseStatus) -> Self {
+        match status {
+            ResponseStatus::SeekError => DiskImageError::SeekError,
+            ResponseStatus::IdError => DiskImageError::IdError,
+            ResponseStatus::WriteProtectError => DiskImageError::WriteProtectError,
+            ResponseStatus::ParameterError => DiskImageError::ParameterError,
+            _ => DiskImageError::IoError("Remote disk server reported an error".to_string()),
+        }
+    }
+}
+
+bitflags! {
+    /// Bit flags describing the condition of a sector returned in a response.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    #[rustfmt::skip]
+    pub struct SectorStatusFlags: u8 {
+        const ADDRESS_CRC_ERROR = 0b0000_0001; // The sector header had a CRC error
+        const DATA_CRC_ERROR    = 0b0000_0010; // The sector data had a CRC error
+        const DELETED_MARK      = 0b0000_0100; // The sector has a deleted data address mark
+        const NO_DAM            = 0b0000_1000; // The sector header had no corresponding data
+    }
+}
+
+impl From<&ReadSectorResult> for SectorStatusFlags {
+    fn from(rsr: &ReadSectorResult) -> Self {
+        let mut flags = SectorStatusFlags::empty();
+        flags.set(SectorStatusFlags::ADDRESS_CRC_ERROR, rsr.address_crc_error);
+        flags.set(SectorStatusFlags::DATA_CRC_ERROR, rsr.data_crc_error);
+        flags.set(SectorStatusFlags::DELETED_MARK, rsr.deleted_mark);
+        flags.set(SectorStatusFlags::NO_DAM, rsr.no_dam);
+        flags
+    }
+}
+
+/// A response sent from a [DiskServer](super::DiskServer) to a remote disk client.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub status: ResponseStatus,
+    pub flags:  SectorStatusFlags,
+    pub data:   Vec<u8>,
+}
+
+impl Response {
+    pub fn ok(flags: SectorStatusFlags, data: Vec<u8>) -> Self {
+        Self {
+            status: ResponseStatus::Ok,
+            flags,
+            data,
+        }
+    }
+
+    pub fn error(status: ResponseStatus) -> Self {
+        Self {
+            status,
+            flags: SectorStatusFlags::empty(),
+            data: Vec::new(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(2 + self.data.len());
+        buf.push(self.status as u8);
+        buf.push(self.flags.bits());
+        buf.extend_from_slice(&self.data);
+        buf
+    }
+
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, DiskImageError> {
+        if buf.len() < 2 {
+            return Err(DiskImageError::IoError("Response frame too short".to_string()));
+        }
+        Ok(Self {
+            status: ResponseStatus::from(buf[0]),
+            flags:  SectorStatusFlags::from_bits_truncate(buf[1]),
+            data:   buf[2..].to_vec(),
+        })
+    }
+}
+
+impl Request {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            Request::Close => buf.push(OP_CLOSE),
+            Request::Info => buf.push(OP_INFO),
+            Request::ReadSector { ch, id } => {
+                buf.push(OP_READ_SECTOR);
+                write_sector_address(&mut buf, *ch, id);
+            }
+            Request::WriteSector { ch, id, deleted, data } => {
+                buf.push(OP_WRITE_SECTOR);
+                write_sector_address(&mut buf, *ch, id);
+                buf.push(*deleted as u8);
+                buf.extend_from_slice(data);
+            }
+        }
+        buf
+    }
+
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, DiskImageError> {
+        match buf.first() {
+            Some(&OP_CLOSE) => Ok(Request::Close),
+            Some(&OP_INFO) => Ok(Request::Info),
+            Some(&OP_READ_SECTOR) => {
+                let (ch, id) = read_sector_address(&buf[1..])?;
+                Ok(Request::ReadSector { ch, id })
+            }
+            Some(&OP_WRITE_SECTOR) => {
+                let (ch, id) = read_sector_address(&buf[1..])?;
+                let deleted = *buf.get(9).ok_or(DiskImageError::ParameterError)? != 0;
+                Ok(Request::WriteSector {
+                    ch,
+                    id,
+                    deleted,
+                    data: buf[10..].to_vec(),
+                })
+            }
+            _ => Err(DiskImageError::ParameterError),
+        }
+    }
+}
+
+fn write_sector_address(buf: &mut Vec<u8>, ch: DiskCh, id: &DiskChsnQuery) {
+    buf.extend_from_slice(&ch.c().to_le_bytes());
+    buf.push(ch.h());
+    buf.extend_from_slice(&id.c().unwrap_or(ANY_CYLINDER).to_le_bytes());
+    buf.push(id.h().unwrap_or(ANY_HEAD));
+    buf.push(id.s());
+    buf.push(id.n().unwrap_or(ANY_SECTOR_SIZE));
+}
+
+fn read_sector_address(buf: &[u8]) -> Result<(DiskCh, DiskChsnQuery), DiskImageError> {
+    if buf.len() < 8 {
+        return Err(DiskImageError::ParameterError);
+    }
+    let ch = DiskCh::new(u16::from_le_bytes([buf[0], buf[1]]), buf[2]);
+    let c = match u16::from_le_bytes([buf[3], buf[4]]) {
+        ANY_CYLINDER => None,
+        c => Some(c),
+    };
+    let h = match buf[5] {
+        ANY_HEAD => None,
+        h => Some(h),
+    };
+    let n = match buf[7] {
+        ANY_SECTOR_SIZE => None,
+        n => Some(n),
+    };
+    let id = DiskChsnQuery::new(c, h, buf[6], n);
+    Ok((ch, id))
+}
+
+/// Read a single frame from `reader`. Returns `Ok(None)` if the stream was closed cleanly before
+/// a new frame was started.
+pub fn read_frame<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>, DiskImageError> {
+    let mut len_buf = [0u8; 4];
+    match reader.read_exact(&mut len_buf) {
+        Ok(_) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_le_bytes(len_buf) as usize;
+    if len > MAX_FRAME_LEN {
+        log::error!(
+            "read_frame(): Frame length {} exceeds maximum of {}",
+            len,
+            MAX_FRAME_LEN
+        );
+        return Err(DiskImageError::ParameterError);
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+/// Write `payload` to `writer` as a single frame.
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> Result<(), DiskImageError> {
+    writer.write_all(&(payload.len() as u32).to_le_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()?;
+    Ok(())
+}
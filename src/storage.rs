@@ -0,0 +1,800 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/storage.rs
+
+    Defines the DiskStorage trait for sector-level access to a disk image,
+    a copy-on-write overlay backend, and a delta file format for persisting
+    overlay writes.
+*/
+
+//! Sector-level storage backends.
+//!
+//! The [DiskStorage] trait describes sector-level access to a disk image - reading and writing
+//! sectors by physical track and sector ID - independently of how the image is stored. It is
+//! implemented by [DiskImage] itself, and by alternative backends such as [OverlayImage], so that
+//! code written against `&mut dyn DiskStorage` can operate on any of them.
+//!
+//! An [OverlayImage] layers a delta of written sectors over a shared, read-only base image. Writes
+//! go to the delta, and reads of written sectors return the delta's data, leaving the base image
+//! untouched. This is useful for emulators that need non-destructive writes, or for comparing the
+//! effect of different changes to the same image by creating several overlays of one base.
+//!
+//! An [OverlayDelta] records the sectors written to an overlay together with the [Fingerprint] of
+//! its base image, and can be saved to and loaded from a small delta file. This allows, for
+//! example, an emulator to persist a user's saves between sessions without ever modifying a
+//! pristine dump. Deltas against the same base can be merged, and a delta can be flattened into
+//! its base image to produce a standalone modified image.
+//!
+//! The delta file format is little-endian, and consists of a header followed by each sector:
+//!
+//! | Field     | Size | Description                          |
+//! |-----------|------|--------------------------------------|
+//! | id        | 8    | `FFDELTA` followed by a 0x1A byte    |
+//! | version   | 2    | The format version, currently 1      |
+//! | base      | 20   | The [Fingerprint] of the base image  |
+//! | sector_ct | 4    | The number of sectors that follow    |
+//!
+//! Each sector consists of a sector header followed by `data_len` bytes of sector data:
+//!
+//! | Field     | Size | Description                                     |
+//! |-----------|------|-------------------------------------------------|
+//! | c         | 2    | The physical cylinder of the track              |
+//! | h         | 1    | The physical head of the track                  |
+//! | id_c      | 2    | The cylinder ID of the sector                   |
+//! | id_h      | 1    | The head ID of the sector                       |
+//! | id_s      | 1    | The sector ID of the sector                     |
+//! | id_n      | 1    | The size code of the sector                     |
+//! | flags     | 1    | Bit 0 is set if the sector has a deleted mark   |
+//! | data_len  | 4    | The length of the sector data                   |
+
+use crate::{
+    hash_manifest::Fingerprint,
+    io::{ReadSeek, ReadWriteSeek},
+    types::{DiskCh, DiskChsn, DiskChsnQuery, ReadSectorResult, RwScope, WriteSectorResult},
+    DiskImage,
+    DiskImageError,
+    FoxHashMap,
+};
+use binrw::{binrw, BinRead, BinWrite};
+use std::sync::Arc;
+
+/// The identifier at the start of a delta file.
+pub const DELTA_FILE_ID: &[u8; 8] = b"FFDELTA\x1A";
+/// The version of the delta file format written by [OverlayDelta::write].
+pub const DELTA_FILE_VERSION: u16 = 1;
+
+const DELTA_FLAG_DELETED: u8 = 0x01;
+
+#[binrw]
+#[brw(little)]
+struct DeltaFileHeader {
+    id: [u8; 8],
+    version: u16,
+    base: [u8; 20],
+    sector_ct: u32,
+}
+
+#[binrw]
+#[brw(little)]
+struct DeltaSectorHeader {
+    c: u16,
+    h: u8,
+    id_c: u16,
+    id_h: u8,
+    id_s: u8,
+    id_n: u8,
+    flags: u8,
+    data_len: u32,
+}
+
+/// Sector-level access to a disk image.
+pub trait DiskStorage {
+    /// Return the number of heads of the disk.
+    fn heads(&self) -> u8;
+
+    /// Return the number of tracks on the specified head.
+    fn track_ct(&self, head: usize) -> usize;
+
+    /// Read the sector identified by `id` from the track at `phys_ch`.
+    /// See [DiskImage::read_sector] for a description of the parameters.
+    fn read_sector(
+        &self,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        n: Option<u8>,
+        offset: Option<usize>,
+        scope: RwScope,
+        debug: bool,
+    ) -> Result<ReadSectorResult, DiskImageError>;
+
+    /// Write `data` to the sector identified by `id` on the track at `phys_ch`.
+    /// See [DiskImage::write_sector] for a description of the parameters.
+    fn write_sector(
+        &mut self,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        offset: Option<usize>,
+        data: &[u8],
+        scope: RwScope,
+        deleted: bool,
+        debug: bool,
+    ) -> Result<WriteSectorResult, DiskImageError>;
+
+    /// Return the number of write operations performed on the storage.
+    fn writes(&self) -> u64;
+
+    /// Read the data of the sector identified by `id`, or return [DiskImageError::IdError] if the
+    /// sector could not be read.
+    fn read_sector_basic(
+        &self,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        offset: Option<usize>,
+    ) -> Result<Vec<u8>, DiskImageError> {
+        let rsr = self.read_sector(phys_ch, id, None, offset, RwScope::DataOnly, false)?;
+        if rsr.not_found || rsr.address_crc_error || rsr.no_dam {
+            return Err(DiskImageError::IdError);
+        }
+        Ok(rsr.read_buf[rsr.data_range].to_vec())
+    }
+
+    /// Write the data of the sector identified by `id`, or return [DiskImageError::IdError] if
+    /// the sector could not be written.
+    fn write_sector_basic(
+        &mut self,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        offset: Option<usize>,
+        data: &[u8],
+    ) -> Result<(), DiskImageError> {
+        let wsr = self.write_sector(phys_ch, id, offset, data, RwScope::DataOnly, false, false)?;
+        if wsr.not_found || wsr.address_crc_error || wsr.no_dam {
+            return Err(DiskImageError::IdError);
+        }
+        Ok(())
+    }
+}
+
+impl DiskStorage for DiskImage {
+    fn heads(&self) -> u8 {
+        DiskImage::heads(self)
+    }
+
+    fn track_ct(&self, head: usize) -> usize {
+        DiskImage::track_ct(self, head)
+    }
+
+    fn read_sector(
+        &self,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        n: Option<u8>,
+        offset: Option<usize>,
+        scope: RwScope,
+        debug: bool,
+    ) -> Result<ReadSectorResult, DiskImageError> {
+        DiskImage::read_sector(self, phys_ch, id, n, offset, scope, debug)
+    }
+
+    fn write_sector(
+        &mut self,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        offset: Option<usize>,
+        data: &[u8],
+        scope: RwScope,
+        deleted: bool,
+        debug: bool,
+    ) -> Result<WriteSectorResult, DiskImageError> {
+        DiskImage::write_sector(self, phys_ch, id, offset, data, scope, deleted, debug)
+    }
+
+    fn writes(&self) -> u64 {
+        self.shared
+            .as_ref()
+            .map(|shared| shared.lock().unwrap().writes)
+            .unwrap_or_default()
+    }
+}
+
+/// A sector written to an [OverlayImage].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OverlaySector {
+    /// The physical cylinder and head of the track containing the sector.
+    pub ch: DiskCh,
+    /// The ID of the sector in the base image.
+    pub id: DiskChsn,
+    /// The data written to the sector.
+    pub data: Vec<u8>,
+    /// Whether the sector was written with a deleted data address mark.
+    pub deleted: bool,
+}
+
+/// A copy-on-write view of a read-only base [DiskImage]. See the [module documentation](self).
+///
+/// Only sector data is overlaid: sector writes must use [RwScope::DataOnly], and reads of
+/// written sectors must use [RwScope::DataOnly] as well.
+pub struct OverlayImage {
+    base:   Arc<DiskImage>,
+    delta:  FoxHashMap<(DiskCh, DiskChsn), OverlaySector>,
+    writes: u64,
+}
+
+impl OverlayImage {
+    /// Create a new overlay with no written sectors over the specified base image.
+    pub fn new(base: Arc<DiskImage>) -> Self {
+        OverlayImage {
+            base,
+            delta: FoxHashMap::new(),
+            writes: 0,
+        }
+    }
+
+    /// Return the base image.
+    pub fn base(&self) -> &Arc<DiskImage> {
+        &self.base
+    }
+
+    /// Return true if any sector has been written to the overlay.
+    pub fn is_modified(&self) -> bool {
+        !self.delta.is_empty()
+    }
+
+    /// Return the sectors written to the overlay, in track and sector order.
+    pub fn written_sectors(&self) -> Vec<&OverlaySector> {
+        Self::sorted(&self.delta)
+    }
+
+    /// Create a new overlay over the specified base image, with the sectors recorded in `delta`
+    /// already written. This fails with [DiskImageError::IncompatibleImage] if the delta was not
+    /// recorded against `base`, or [DiskImageError::IdError] if a recorded sector cannot be
+    /// written to it.
+    pub fn with_delta(base: Arc<DiskImage>, delta: &OverlayDelta) -> Result<Self, DiskImageError> {
+        delta.check_base(&base)?;
+        let mut overlay = OverlayImage::new(base);
+        for sector in &delta.sectors {
+            let wsr = overlay.write_sector(
+                sector.ch,
+                DiskChsnQuery::from(sector.id),
+                None,
+                &sector.data,
+                RwScope::DataOnly,
+                sector.deleted,
+                false,
+            )?;
+            if wsr.not_found || wsr.address_crc_error || wsr.no_dam {
+                log::error!(
+                    "with_delta(): Could not write sector {} on track {}",
+                    sector.id,
+                    sector.ch
+                );
+                return Err(DiskImageError::IdError);
+            }
+        }
+        // Restoring a delta is not a write by the caller.
+        overlay.writes = 0;
+        Ok(overlay)
+    }
+
+    /// Return an [OverlayDelta] recording the sectors written to the overlay, which can be saved
+    /// with [OverlayDelta::write] and restored with [OverlayImage::with_delta].
+    pub fn delta(&self) -> OverlayDelta {
+        OverlayDelta {
+            base:    self.base.fingerprint(),
+            sectors: Self::sorted(&self.delta).into_iter().cloned().collect(),
+        }
+    }
+
+    /// Discard all writes, returning the overlay to the state of the base image.
+    pub fn discard(&mut self) {
+        self.delta.clear();
+        self.writes = 0;
+    }
+
+    /// Write each sector written to the overlay to `target`. The target is normally a copy of
+    /// the base image, or the base image itself once no other overlay shares it.
+    pub fn apply_to(&self, target: &mut DiskImage) -> Result<(), DiskImageError> {
+        Self::write_delta(&self.delta, target)
+    }
+
+    /// Apply the overlay to the base image and return it. This fails with
+    /// [DiskImageError::SyncError] if the base image is shared with another owner.
+    pub fn commit(self) -> Result<DiskImage, DiskImageError> {
+        let mut base = Arc::try_unwrap(self.base).map_err(|_| {
+            log::error!("commit(): Base image is shared with another owner");
+            DiskImageError::SyncError("Base image is shared with another owner".to_string())
+        })?;
+        Self::write_delta(&self.delta, &mut base)?;
+        Ok(base)
+    }
+
+    fn sorted(delta: &FoxHashMap<(DiskCh, DiskChsn), OverlaySector>) -> Vec<&OverlaySector> {
+        let mut sectors: Vec<&OverlaySector> = delta.values().collect();
+        sectors.sort_by_key(|sector| (sector.ch, sector.id.s(), sector.id.n()));
+        sectors
+    }
+
+    fn write_delta(
+        delta: &FoxHashMap<(DiskCh, DiskChsn), OverlaySector>,
+        target: &mut DiskImage,
+    ) -> Result<(), DiskImageError> {
+        for sector in Self::sorted(delta) {
+            let query = DiskChsnQuery::from(sector.id);
+            let wsr = target.write_sector(
+                sector.ch,
+                query,
+                None,
+                &sector.data,
+                RwScope::DataOnly,
+                sector.deleted,
+                false,
+            )?;
+            if wsr.not_found || wsr.address_crc_error || wsr.no_dam {
+                log::error!(
+                    "write_delta(): Could not write sector {} on track {}",
+                    sector.id,
+                    sector.ch
+                );
+                return Err(DiskImageError::IdError);
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the unique sector in the base image matching `id`, returning its ID.
+    fn resolve_id(&self, phys_ch: DiskCh, id: DiskChsnQuery) -> Result<Option<DiskChsn>, DiskImageError> {
+        let track = self.base.track(phys_ch).ok_or(DiskImageError::SeekError)?;
+        let mut matches = track.sector_list().into_iter().filter(|entry| id.matches(&entry.chsn));
+        match (matches.next(), matches.next()) {
+            (Some(entry), None) => Ok(Some(entry.chsn)),
+            (None, _) => Ok(None),
+            (Some(_), Some(_)) => {
+                log::error!(
+                    "write_sector(): Could not identify unique target sector for query {}",
+                    id
+                );
+                Err(DiskImageError::UniqueIdError)
+            }
+        }
+    }
+}
+
+impl DiskStorage for OverlayImage {
+    fn heads(&self) -> u8 {
+        self.base.heads()
+    }
+
+    fn track_ct(&self, head: usize) -> usize {
+        self.base.track_ct(head)
+    }
+
+    fn read_sector(
+        &self,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        n: Option<u8>,
+        offset: Option<usize>,
+        scope: RwScope,
+        debug: bool,
+    ) -> Result<ReadSectorResult, DiskImageError> {
+        let mut rsr = self.base.read_sector(phys_ch, id, n, offset, scope, debug)?;
+        let Some(sector) = rsr.id_chsn.and_then(|chsn| self.delta.get(&(phys_ch, chsn)))
+        else {
+            return Ok(rsr);
+        };
+
+        if !matches!(scope, RwScope::DataOnly) {
+            log::error!(
+                "read_sector(): Sector {} has been written; only RwScope::DataOnly reads are supported",
+                sector.id
+            );
+            return Err(DiskImageError::ParameterError);
+        }
+
+        // The read may be shorter or longer than the sector if a different N was requested.
+        let data = &mut rsr.read_buf[rsr.data_range.clone()];
+        let len = data.len().min(sector.data.len());
+        data[..len].copy_from_slice(&sector.data[..len]);
+        // Writing a sector records a new, valid data CRC.
+        rsr.data_crc_error = false;
+        rsr.data_crc = None;
+        rsr.deleted_mark = sector.deleted;
+        Ok(rsr)
+    }
+
+    fn write_sector(
+        &mut self,
+        phys_ch: DiskCh,
+        id: DiskChsnQuery,
+        offset: Option<usize>,
+        data: &[u8],
+        scope: RwScope,
+        deleted: bool,
+        debug: bool,
+    ) -> Result<WriteSectorResult, DiskImageError> {
+        if !matches!(scope, RwScope::DataOnly) {
+            log::error!("write_sector(): Only RwScope::DataOnly writes are supported by an overlay");
+            return Err(DiskImageError::ParameterError);
+        }
+
+        let Some(chsn) = self.resolve_id(phys_ch, id)?
+        else {
+            let rsr = self
+                .base
+                .read_sector(phys_ch, id, None, offset, RwScope::DataOnly, debug)?;
+            return Ok(WriteSectorResult {
+                not_found: true,
+                no_dam: false,
+                address_crc_error: false,
+                wrong_cylinder: rsr.wrong_cylinder,
+                bad_cylinder: rsr.bad_cylinder,
+                wrong_head: rsr.wrong_head,
+            });
+        };
+
+        let rsr = self.base.read_sector(
+            phys_ch,
+            DiskChsnQuery::from(chsn),
+            None,
+            offset,
+            RwScope::DataOnly,
+            true,
+        )?;
+        let result = WriteSectorResult {
+            not_found: false,
+            no_dam: rsr.no_dam,
+            address_crc_error: rsr.address_crc_error,
+            wrong_cylinder: rsr.wrong_cylinder,
+            bad_cylinder: rsr.bad_cylinder,
+            wrong_head: rsr.wrong_head,
+        };
+        if rsr.no_dam || rsr.address_crc_error {
+            log::debug!(
+                "write_sector(): Sector {} is unwritable due to no DAM or bad address CRC.",
+                chsn
+            );
+            return Ok(result);
+        }
+
+        if data.len() != chsn.n_size() {
+            log::error!(
+                "write_sector(): Data buffer size mismatch, expected: {} got: {}",
+                chsn.n_size(),
+                data.len()
+            );
+            return Err(DiskImageError::ParameterError);
+        }
+
+        self.delta.insert(
+            (phys_ch, chsn),
+            OverlaySector {
+                ch: phys_ch,
+                id: chsn,
+                data: data.to_vec(),
+                deleted,
+            },
+        );
+        self.writes += 1;
+        Ok(result)
+    }
+
+    fn writes(&self) -> u64 {
+        self.writes
+    }
+}
+
+/// The sectors written to an [OverlayImage], recorded against the [Fingerprint] of its base
+/// image. See the [module documentation](self) for a description of the delta file format.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OverlayDelta {
+    base:    Fingerprint,
+    sectors: Vec<OverlaySector>,
+}
+
+impl OverlayDelta {
+    /// Return the fingerprint of the base image the delta was recorded against.
+    pub fn base(&self) -> Fingerprint {
+        self.base
+    }
+
+    /// Return the sectors recorded in the delta, in track and sector order.
+    pub fn sectors(&self) -> &[OverlaySector] {
+        &self.sectors
+    }
+
+    /// Return true if the delta records no sectors.
+    pub fn is_empty(&self) -> bool {
+        self.sectors.is_empty()
+    }
+
+    /// Read a delta from a delta file.
+    pub fn read<RS: ReadSeek>(reader: &mut RS) -> Result<Self, DiskImageError> {
+        let header = DeltaFileHeader::read(reader)?;
+        if &header.id != DELTA_FILE_ID {
+            log::error!("read(): Delta file ID not found");
+            return Err(DiskImageError::UnknownFormat);
+        }
+        if header.version != DELTA_FILE_VERSION {
+            log::error!("read(): Unsupported delta file version: {}", header.version);
+            return Err(DiskImageError::UnsupportedFormat);
+        }
+
+        let mut sectors = Vec::new();
+        for _ in 0..header.sector_ct {
+            let sector_header = DeltaSectorHeader::read(reader)?;
+            let id = DiskChsn::new(
+                sector_header.id_c,
+                sector_header.id_h,
+                sector_header.id_s,
+                sector_header.id_n,
+            );
+            if sector_header.data_len as usize != id.n_size() {
+                log::error!(
+                    "read(): Sector {} data length {} does not match its size",
+                    id,
+                    sector_header.data_len
+                );
+                return Err(DiskImageError::ImageCorruptError(format!(
+                    "Sector {} has invalid data length {}",
+                    id, sector_header.data_len
+                )));
+            }
+            let mut data = vec![0; sector_header.data_len as usize];
+            reader.read_exact(&mut data)?;
+            sectors.push(OverlaySector {
+                ch: DiskCh::new(sector_header.c, sector_header.h),
+                id,
+                data,
+                deleted: sector_header.flags & DELTA_FLAG_DELETED != 0,
+            });
+        }
+
+        let mut delta = OverlayDelta {
+            base: Fingerprint(header.base),
+            sectors,
+        };
+        delta.sort();
+        Ok(delta)
+    }
+
+    /// Write the delta as a delta file.
+    pub fn write<WS: ReadWriteSeek>(&self, writer: &mut WS) -> Result<(), DiskImageError> {
+        DeltaFileHeader {
+            id: *DELTA_FILE_ID,
+            version: DELTA_FILE_VERSION,
+            base: self.base.0,
+            sector_ct: self.sectors.len() as u32,
+        }
+        .write(writer)?;
+
+        for sector in &self.sectors {
+            DeltaSectorHeader {
+                c: sector.ch.c(),
+                h: sector.ch.h(),
+                id_c: sector.id.c(),
+                id_h: sector.id.h(),
+                id_s: sector.id.s(),
+                id_n: sector.id.n(),
+                flags: if sector.deleted { DELTA_FLAG_DELETED } else { 0 },
+                data_len: sector.data.len() as u32,
+            }
+            .write(writer)?;
+            writer.write_all(&sector.data)?;
+        }
+        Ok(())
+    }
+
+    /// Merge `other` into this delta. Sectors recorded in both deltas take their data from
+    /// `other`, so deltas should be merged in the order they were recorded. This fails with
+    /// [DiskImageError::IncompatibleImage] if the deltas were recorded against different bases.
+    pub fn merge(&mut self, other: &OverlayDelta) -> Result<(), DiskImageError> {
+        if self.base != other.base {
+            log::error!(
+                "merge(): Delta base {} does not match delta base {}",
+                other.base,
+                self.base
+            );
+            return Err(DiskImageError::IncompatibleImage(
+                "Deltas were recorded against different base images".to_string(),
+            ));
+        }
+        for sector in &other.sectors {
+            match self
+                .sectors
+                .iter_mut()
+                .find(|existing| existing.ch == sector.ch && existing.id == sector.id)
+            {
+                Some(existing) => *existing = sector.clone(),
+                None => self.sectors.push(sector.clone()),
+            }
+        }
+        self.sort();
+        Ok(())
+    }
+
+    /// Write the sectors recorded in the delta to its base image, returning a standalone image.
+    /// This fails with [DiskImageError::IncompatibleImage] if the delta was not recorded against
+    /// `base`.
+    pub fn flatten(&self, mut base: DiskImage) -> Result<DiskImage, DiskImageError> {
+        self.check_base(&base)?;
+        let delta = self
+            .sectors
+            .iter()
+            .map(|sector| ((sector.ch, sector.id), sector.clone()))
+            .collect();
+        OverlayImage::write_delta(&delta, &mut base)?;
+        Ok(base)
+    }
+
+    fn check_base(&self, base: &DiskImage) -> Result<(), DiskImageError> {
+        let fingerprint = base.fingerprint();
+        if fingerprint != self.base {
+            log::error!(
+                "check_base(): Delta base {} does not match image fingerprint {}",
+                self.base,
+                fingerprint
+            );
+            return Err(DiskImageError::IncompatibleImage(
+                "Delta was recorded against a different base image".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn sort(&mut self) {
+        self.sectors
+            .sort_by_key(|sector| (sector.ch, sector.id.s(), sector.id.n()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        io::Cursor,
+        prelude::*,
+        test_util::{add_sector, add_track, empty_disk},
+    };
+
+    fn base_image() -> DiskImage {
+        let mut disk = empty_disk();
+        for c in 0..2 {
+            let track = add_track(&mut disk, DiskCh::new(c, 0));
+            for s in 1..=9 {
+                add_sector(track, DiskChsn::new(c, 0, s, 2), &[s; 512], Default::default());
+            }
+        }
+        disk
+    }
+
+    #[test]
+    fn test_overlay() {
+        let base = Arc::new(base_image());
+        let ch = DiskCh::new(1, 0);
+        let id = DiskChsnQuery::new(1, 0, 3, 2);
+
+        // Two overlays of the same base see only their own writes.
+        let mut a = OverlayImage::new(base.clone());
+        let mut b = OverlayImage::new(base.clone());
+        a.write_sector_basic(ch, id, None, &[0xAA; 512]).unwrap();
+        b.write_sector(ch, id, None, &[0xBB; 512], RwScope::DataOnly, true, false)
+            .unwrap();
+
+        assert_eq!(a.read_sector_basic(ch, id, None).unwrap(), vec![0xAA; 512]);
+        let rsr = DiskStorage::read_sector(&b, ch, id, None, None, RwScope::DataOnly, false).unwrap();
+        assert_eq!(rsr.read_buf, vec![0xBB; 512]);
+        assert!(rsr.deleted_mark);
+        assert_eq!(base.read_sector_basic(ch, id, None).unwrap(), vec![3; 512]);
+        assert_eq!((a.writes(), a.written_sectors().len()), (1, 1));
+
+        // Unmatched and mis-sized writes leave the overlay unchanged.
+        let missing = DiskChsnQuery::new(1, 0, 12, 2);
+        assert!(a.write_sector_basic(ch, missing, None, &[0; 512]).is_err());
+        assert!(a.write_sector_basic(ch, id, None, &[0; 256]).is_err());
+        assert_eq!(a.writes(), 1);
+
+        // Backends are interchangeable as trait objects.
+        let storage: Vec<&dyn DiskStorage> = vec![&a, &b, base.as_ref()];
+        let firsts: Vec<u8> = storage
+            .iter()
+            .map(|s| s.read_sector_basic(ch, id, None).unwrap()[0])
+            .collect();
+        assert_eq!(firsts, vec![0xAA, 0xBB, 3]);
+        drop(storage);
+
+        // The delta can be applied to a copy of the base, or committed to the base itself once
+        // no other owner shares it.
+        let mut copy = base_image();
+        a.apply_to(&mut copy).unwrap();
+        assert_eq!(copy.read_sector_basic(ch, id, None).unwrap(), vec![0xAA; 512]);
+        assert!(matches!(a.commit(), Err(DiskImageError::SyncError(_))));
+
+        b.discard();
+        assert!(!b.is_modified());
+        assert_eq!(b.writes(), 0);
+        b.write_sector_basic(ch, id, None, &[0xCC; 512]).unwrap();
+        drop(base);
+        let committed = b.commit().unwrap();
+        assert_eq!(committed.read_sector_basic(ch, id, None).unwrap(), vec![0xCC; 512]);
+    }
+
+    #[test]
+    fn test_overlay_delta() {
+        let base = Arc::new(base_image());
+        let ch = DiskCh::new(1, 0);
+        let id = DiskChsnQuery::new(1, 0, 3, 2);
+        let id2 = DiskChsnQuery::new(1, 0, 4, 2);
+
+        let mut session1 = OverlayImage::new(base.clone());
+        session1.write_sector_basic(ch, id, None, &[0xAA; 512]).unwrap();
+        let mut session2 = OverlayImage::new(base.clone());
+        session2.write_sector_basic(ch, id, None, &[0xBB; 512]).unwrap();
+        session2
+            .write_sector(ch, id2, None, &[0xCC; 512], RwScope::DataOnly, true, false)
+            .unwrap();
+
+        // A delta survives a round trip through a delta file, and restores the overlay.
+        let mut file = Cursor::new(Vec::new());
+        session2.delta().write(&mut file).unwrap();
+        file.set_position(0);
+        let delta2 = OverlayDelta::read(&mut file).unwrap();
+        assert_eq!(delta2, session2.delta());
+        assert_eq!(delta2.base(), base.fingerprint());
+        let restored = OverlayImage::with_delta(base.clone(), &delta2).unwrap();
+        assert_eq!(restored.writes(), 0);
+        assert_eq!(restored.read_sector_basic(ch, id2, None).unwrap(), vec![0xCC; 512]);
+
+        // Later deltas take precedence when merged.
+        let mut merged = session1.delta();
+        merged.merge(&delta2).unwrap();
+        assert_eq!(merged.sectors().len(), 2);
+        assert_eq!(merged.sectors()[0].data, vec![0xBB; 512]);
+
+        let flat = merged.flatten(base_image()).unwrap();
+        assert_eq!(flat.read_sector_basic(ch, id, None).unwrap(), vec![0xBB; 512]);
+        let rsr = flat.read_sector(ch, id2, None, None, RwScope::DataOnly, false).unwrap();
+        assert!(rsr.deleted_mark);
+
+        // A delta can't be applied to a different base.
+        let mut other = base_image();
+        other.write_sector_basic(ch, id, None, &[0; 512]).unwrap();
+        assert!(matches!(
+            merged.flatten(other),
+            Err(DiskImageError::IncompatibleImage(_))
+        ));
+        let mut foreign = OverlayDelta::default();
+        assert!(foreign.merge(&merged).is_err());
+
+        let mut bad_file = Cursor::new(b"NOTADELTA".repeat(8));
+        assert!(matches!(
+            OverlayDelta::read(&mut bad_file),
+            Err(DiskImageError::UnknownFormat)
+        ));
+    }
+}
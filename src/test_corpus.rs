@@ -0,0 +1,203 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/test_corpus.rs
+
+    Generates a canonical set of small test images in every writable format
+    from one synthetic source disk, for checking fluxfox integration.
+*/
+
+//! A canonical corpus of small test images for checking fluxfox integration.
+//!
+//! [TestCorpus::generate()] builds a single synthetic source disk - a [CORPUS_FORMAT] floppy whose
+//! every sector holds a pattern derived from its sector ID - and writes it out in every disk image
+//! file format that fluxfox can write it to. Downstream projects, such as emulators, can load each
+//! image in their own CI and check the sector data they read against [expected_sector_data()], or
+//! check a loaded [DiskImage] directly with [verify_image()].
+//!
+//! Since every image in the corpus contains the same sectors, each also has the same
+//! [DiskImage::fingerprint()], recorded in [TestCorpus::fingerprint].
+
+use crate::{hash_manifest::Fingerprint, io::Cursor, prelude::*, types::DiskChsnQuery, DiskImage, DiskImageError};
+use std::path::{Path, PathBuf};
+use strum::IntoEnumIterator;
+
+/// The [StandardFormat] of the corpus source disk. This is the smallest double-sided PC format,
+/// as not every writable format can store a single-sided disk.
+pub const CORPUS_FORMAT: StandardFormat = StandardFormat::PcFloppy320;
+/// The base name of the image files written by [TestCorpus::write_to_dir()].
+pub const CORPUS_FILE_STEM: &str = "fluxfox_corpus";
+
+/// Return the data expected in the sector with the specified ID. The first four bytes hold the
+/// sector's cylinder, head, sector and size IDs, so a misplaced sector can be identified by eye;
+/// the rest is a pattern seeded by the same IDs.
+pub fn expected_sector_data(id: DiskChsn) -> Vec<u8> {
+    let seed = (id.c() as usize) * 13 + (id.h() as usize) * 31 + (id.s() as usize) * 61;
+    let mut data: Vec<u8> = (0..id.n_size()).map(|i| (seed + i * 7) as u8).collect();
+    let header = [id.c() as u8, id.h(), id.s(), id.n()];
+    let len = header.len().min(data.len());
+    data[..len].copy_from_slice(&header[..len]);
+    data
+}
+
+/// Return the corpus source disk as a raw sector image: the [expected_sector_data()] of every
+/// sector of [CORPUS_FORMAT], in standard cylinder, head, sector order.
+pub fn source_data() -> Vec<u8> {
+    CORPUS_FORMAT
+        .layout()
+        .chsn_iter()
+        .flat_map(expected_sector_data)
+        .collect()
+}
+
+/// Build the corpus source disk by loading [source_data()] as a raw sector image, which produces a
+/// [CORPUS_FORMAT] bitstream image with every sector filled with its [expected_sector_data()].
+pub fn source_image() -> Result<DiskImage, DiskImageError> {
+    let path = PathBuf::from(format!("{}.img", CORPUS_FILE_STEM));
+    DiskImage::load(&mut Cursor::new(source_data()), Some(&path), None, None)
+}
+
+/// Check every sector of `image` against its [expected_sector_data()].
+/// # Returns
+/// The IDs of the sectors that could not be read or did not hold the expected data. An empty
+/// vector means the image matches the corpus.
+pub fn verify_image(image: &DiskImage) -> Vec<DiskChsn> {
+    CORPUS_FORMAT
+        .layout()
+        .chsn_iter()
+        .filter(|id| {
+            image
+                .read_sector_basic(id.ch(), DiskChsnQuery::from(*id), None)
+                .map_or(true, |data| data != expected_sector_data(*id))
+        })
+        .collect()
+}
+
+/// A single image of the corpus.
+#[derive(Clone, Debug)]
+pub struct CorpusImage {
+    /// The file format the image was written in.
+    pub format: DiskImageFileFormat,
+    /// The file name the image is written to by [TestCorpus::write_to_dir()].
+    pub file_name: String,
+    /// The image file data.
+    pub data: Vec<u8>,
+}
+
+/// The corpus of test images. See the [module documentation](self).
+#[derive(Clone, Debug)]
+pub struct TestCorpus {
+    /// The fingerprint shared by the source disk and every image in the corpus.
+    pub fingerprint: Fingerprint,
+    /// The images of the corpus, one per writable format.
+    pub images: Vec<CorpusImage>,
+}
+
+impl TestCorpus {
+    /// Build the corpus source disk and write it in each format that can store it without loss.
+    /// Flux formats are written with a single revolution per track to keep them small.
+    pub fn generate() -> Result<Self, DiskImageError> {
+        let mut source = source_image()?;
+        let options = ParserWriteOptions::default().with_flux_revolutions(1);
+
+        let mut images = Vec::new();
+        for format in DiskImageFileFormat::iter() {
+            if format.can_write(Some(&source)) != ParserWriteCompatibility::Ok {
+                continue;
+            }
+            let Some(extension) = format.extensions().first().copied()
+            else {
+                continue;
+            };
+
+            let mut buf = Cursor::new(Vec::new());
+            format.save_image(&mut source, &options, &mut buf)?;
+            images.push(CorpusImage {
+                format,
+                file_name: format!("{}.{}", CORPUS_FILE_STEM, extension),
+                data: buf.into_inner(),
+            });
+        }
+
+        Ok(TestCorpus {
+            fingerprint: source.fingerprint(),
+            images,
+        })
+    }
+
+    /// Return the image of the corpus written in the specified format, if that format is writable.
+    pub fn image(&self, format: DiskImageFileFormat) -> Option<&CorpusImage> {
+        self.images.iter().find(|image| image.format == format)
+    }
+
+    /// Write every image of the corpus to `dir`, which must exist.
+    /// # Returns
+    /// The paths of the files written.
+    pub fn write_to_dir(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, DiskImageError> {
+        self.images
+            .iter()
+            .map(|image| {
+                let path = dir.as_ref().join(&image.file_name);
+                std::fs::write(&path, &image.data)?;
+                Ok(path)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corpus() {
+        let corpus = TestCorpus::generate().unwrap();
+        assert!(corpus.image(DiskImageFileFormat::RawSectorImage).is_some());
+        assert!(corpus.image(DiskImageFileFormat::F86Image).is_some());
+        assert!(corpus.image(DiskImageFileFormat::SuperCardPro).is_some());
+
+        let raw = corpus.image(DiskImageFileFormat::RawSectorImage).unwrap();
+        assert_eq!(raw.data, source_data());
+        assert_eq!(&raw.data[..4], &[0, 0, 1, 2]);
+
+        for image in &corpus.images {
+            let path = PathBuf::from(&image.file_name);
+            let disk = DiskImage::load(&mut Cursor::new(&image.data), Some(&path), None, None).unwrap();
+            assert_eq!(disk.source_format(), Some(image.format), "{}", image.file_name);
+            assert!(verify_image(&disk).is_empty(), "{} has bad sectors", image.file_name);
+            assert_eq!(disk.fingerprint(), corpus.fingerprint, "{}", image.file_name);
+        }
+
+        // A single changed sector is reported.
+        let id = DiskChsn::new(3, 1, 4, 2);
+        let mut data = source_data();
+        let offset = CORPUS_FORMAT.layout().chsn_iter().position(|s| s == id).unwrap() * id.n_size();
+        data[offset + 100] ^= 0xFF;
+        let path = PathBuf::from("changed.img");
+        let disk = DiskImage::load(&mut Cursor::new(data), Some(&path), None, None).unwrap();
+        assert_eq!(verify_image(&disk), vec![id]);
+    }
+}
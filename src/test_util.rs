@@ -0,0 +1,123 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/test_util.rs
+
+    Disk image fixtures shared by the unit tests.
+*/
+
+//! Disk image fixtures shared by the unit tests.
+//!
+//! All fixtures are based on a [StandardFormat::PcFloppy360] disk.
+
+use crate::{
+    prelude::*,
+    track::DiskTrack,
+    types::{AddSectorParams, MetaSectorTrackParams, SectorAttributes},
+    ImageBuilder,
+};
+
+/// Build a 360K MetaSector disk with no tracks.
+pub(crate) fn empty_disk() -> DiskImage {
+    empty_format_disk(StandardFormat::PcFloppy360)
+}
+
+/// Build a MetaSector disk of the specified `format` with no tracks.
+pub(crate) fn empty_format_disk(format: StandardFormat) -> DiskImage {
+    ImageBuilder::new()
+        .with_standard_format(format)
+        .with_resolution(TrackDataResolution::MetaSector)
+        .build()
+        .unwrap()
+}
+
+/// Build a 360K BitStream disk with every track formatted.
+pub(crate) fn formatted_disk() -> DiskImage {
+    ImageBuilder::new()
+        .with_standard_format(StandardFormat::PcFloppy360)
+        .with_resolution(TrackDataResolution::BitStream)
+        .with_formatted(true)
+        .build()
+        .unwrap()
+}
+
+/// Add an empty MFM MetaSector track at `ch` to `disk`.
+pub(crate) fn add_track(disk: &mut DiskImage, ch: DiskCh) -> &mut DiskTrack {
+    add_format_track(disk, StandardFormat::PcFloppy360, ch)
+}
+
+/// Add an empty MetaSector track at `ch` to `disk`, with the encoding and data rate of `format`.
+pub(crate) fn add_format_track(disk: &mut DiskImage, format: StandardFormat, ch: DiskCh) -> &mut DiskTrack {
+    disk.add_track_metasector(&MetaSectorTrackParams {
+        ch,
+        encoding: format.encoding(),
+        data_rate: format.data_rate(),
+    })
+    .unwrap()
+}
+
+/// Add a sector with the specified ID, data and attributes to `track`.
+pub(crate) fn add_sector(track: &mut DiskTrack, id_chsn: DiskChsn, data: &[u8], attributes: SectorAttributes) {
+    track
+        .add_sector(&AddSectorParams {
+            id_chsn,
+            data,
+            attributes,
+            ..Default::default()
+        })
+        .unwrap();
+}
+
+/// Build a 360K MetaSector disk with a single track at cylinder 0, head 0, holding a 512-byte
+/// sector for each `(sector id, fill byte, attributes)` in `sectors`.
+pub(crate) fn single_track_disk(sectors: &[(u8, u8, SectorAttributes)]) -> DiskImage {
+    let mut disk = empty_disk();
+    let track = add_track(&mut disk, DiskCh::new(0, 0));
+    for (s, fill, attributes) in sectors {
+        add_sector(track, DiskChsn::new(0, 0, *s, 2), &[*fill; 512], *attributes);
+    }
+    disk
+}
+
+/// Build a MetaSector disk of the specified `format` with every sector of its layout, where
+/// `sector` returns the data and attributes of the sector at each [DiskChs].
+pub(crate) fn sector_disk(
+    format: StandardFormat,
+    mut sector: impl FnMut(DiskChs) -> (Vec<u8>, SectorAttributes),
+) -> DiskImage {
+    let mut disk = empty_format_disk(format);
+
+    let layout = format.layout();
+    for ch in layout.ch_iter() {
+        let track = add_format_track(&mut disk, format, ch);
+        for s in 1..=layout.s() {
+            let chs = DiskChs::from((ch, s));
+            let (data, attributes) = sector(chs);
+            add_sector(track, DiskChsn::from((chs, layout.n())), &data, attributes);
+        }
+    }
+    disk
+}
@@ -0,0 +1,504 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+    src/text_dump.rs
+
+    Implements export and import of a disk image as a deterministic text dump.
+    Each sector is written as a hex dump annotated with its ID and flags, so
+    that small changes to an image produce small, reviewable diffs when the
+    dump is kept in version control.
+
+    The dump is line-oriented:
+
+        fluxfox-text-dump 1
+        track <c> <h> <encoding> <data rate in bits per second>
+        sector <c> <h> <s> <n> <flags>
+        <offset>: <up to 16 hex bytes>  |<ascii>|
+        weak
+        <offset>: <up to 16 hex bytes>  |<ascii>|
+        hole
+        <offset>: <up to 16 hex bytes>  |<ascii>|
+
+    `sector` entries belong to the preceding `track` line and are listed in
+    physical order. Hex lines following a `sector` line hold the sector data;
+    hex lines following a `weak` or `hole` line hold the sector's weak bit or
+    hole mask. Tracks are listed by head, then cylinder; cylinders missing
+    from the dump are imported as empty tracks. The
+    ASCII column is informational and ignored on import, as are blank lines
+    and lines starting with `#`. `flags` is a comma-separated list of
+    `address_error`, `data_error`, `deleted` and `no_dam`, or `-` if none are
+    set.
+*/
+use crate::{
+    prelude::{DiskCh, DiskChsn, RwScope},
+    track::DiskTrack,
+    types::{AddSectorParams, MetaSectorTrackParams, SectorAttributes, TrackDataEncoding, TrackDataRate},
+    DiskImage,
+    DiskImageError,
+};
+use std::fmt::Write;
+
+const DUMP_MAGIC: &str = "fluxfox-text-dump 1";
+const BYTES_PER_LINE: usize = 16;
+
+/// Export `disk` as a text dump.
+pub(crate) fn export_text_dump(disk: &DiskImage) -> Result<String, DiskImageError> {
+    let mut dump = format!("{}\n", DUMP_MAGIC);
+
+    for head in 0..2u8 {
+        for cylinder in 0..disk.track_map[head as usize].len() {
+            let ch = DiskCh::new(cylinder as u16, head);
+            let Some(track) = disk.track(ch)
+            else {
+                continue;
+            };
+
+            _ = writeln!(
+                dump,
+                "\ntrack {} {} {} {}",
+                ch.c(),
+                ch.h(),
+                track.encoding(),
+                u32::from(track.info().data_rate)
+            );
+
+            for (index, entry) in track.sector_list().iter().enumerate() {
+                let chsn = entry.chsn;
+                let (data, weak_mask) = read_sector_at(track, index)?;
+
+                _ = writeln!(
+                    dump,
+                    "\nsector {} {} {} {} {}",
+                    chsn.c(),
+                    chsn.h(),
+                    chsn.s(),
+                    chsn.n(),
+                    flags_to_string(&entry.attributes)
+                );
+                write_hex(&mut dump, &data);
+                if let Some(weak_mask) = weak_mask.filter(|mask| has_bits(mask)) {
+                    dump.push_str("weak\n");
+                    write_hex(&mut dump, &weak_mask);
+                }
+                let hole_mask = track.as_metasector_track().and_then(|t| t.hole_mask_at(index));
+                if let Some(hole_mask) = hole_mask.filter(|mask| has_bits(mask)) {
+                    dump.push_str("hole\n");
+                    write_hex(&mut dump, hole_mask);
+                }
+            }
+        }
+    }
+
+    Ok(dump)
+}
+
+/// Import a text dump produced by [export_text_dump] as a `MetaSector` resolution [DiskImage].
+pub(crate) fn import_text_dump(text: &str) -> Result<DiskImage, DiskImageError> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    if lines.next() != Some(DUMP_MAGIC) {
+        return Err(corrupt("Dump header not found".to_string()));
+    }
+
+    let mut disk = DiskImage::default();
+    let mut heads = 1;
+    let mut cylinders = 0;
+    let mut first_track: Option<MetaSectorTrackParams> = None;
+    let mut current_track: Option<DiskCh> = None;
+    let mut pending: Option<PendingSector> = None;
+
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["track", c, h, encoding, rate] => {
+                if let Some(sector) = pending.take() {
+                    sector.add_to(&mut disk)?;
+                }
+                let ch = DiskCh::new(parse(c)?, parse(h)?);
+                if ch.h() > 1 || (ch.c() as usize) < disk.track_map[ch.h() as usize].len() {
+                    return Err(corrupt(format!("Track {} is out of order", ch)));
+                }
+                let params = MetaSectorTrackParams {
+                    ch,
+                    encoding: parse_encoding(encoding)?,
+                    data_rate: TrackDataRate::from(parse::<u32>(rate)?),
+                };
+                // Fill any cylinders missing from the dump with empty tracks.
+                for c in disk.track_map[ch.h() as usize].len()..ch.c() as usize {
+                    log::warn!(
+                        "import_text_dump(): Track {} missing from dump",
+                        DiskCh::new(c as u16, ch.h())
+                    );
+                    disk.add_track_metasector(&MetaSectorTrackParams {
+                        ch: DiskCh::new(c as u16, ch.h()),
+                        ..params
+                    })?;
+                }
+                disk.add_track_metasector(&params)?;
+                first_track.get_or_insert(params);
+                heads = heads.max(ch.h() + 1);
+                cylinders = cylinders.max(ch.c() + 1);
+                current_track = Some(ch);
+            }
+            ["sector", c, h, s, n, flags] => {
+                if let Some(sector) = pending.take() {
+                    sector.add_to(&mut disk)?;
+                }
+                let ch = current_track.ok_or_else(|| corrupt("Sector defined before track".to_string()))?;
+                pending = Some(PendingSector {
+                    ch,
+                    id_chsn: DiskChsn::new(parse(c)?, parse(h)?, parse(s)?, parse(n)?),
+                    attributes: parse_flags(flags)?,
+                    data: Vec::new(),
+                    weak_mask: None,
+                    hole_mask: None,
+                });
+            }
+            [mask @ ("weak" | "hole")] => {
+                let sector = pending
+                    .as_mut()
+                    .ok_or_else(|| corrupt(format!("{} mask defined before sector", mask)))?;
+                let buf = match *mask {
+                    "weak" if sector.hole_mask.is_none() => &mut sector.weak_mask,
+                    "hole" => &mut sector.hole_mask,
+                    _ => {
+                        return Err(corrupt(format!(
+                            "Weak mask after hole mask for sector {}",
+                            sector.id_chsn
+                        )))
+                    }
+                };
+                if buf.is_some() {
+                    return Err(corrupt(format!(
+                        "Duplicate {} mask for sector {}",
+                        mask, sector.id_chsn
+                    )));
+                }
+                *buf = Some(Vec::new());
+            }
+            _ => {
+                let sector = pending
+                    .as_mut()
+                    .ok_or_else(|| corrupt(format!("Invalid dump line: {}", line)))?;
+                // Hex lines belong to the last section opened for the sector.
+                let buf = match (&mut sector.weak_mask, &mut sector.hole_mask) {
+                    (_, Some(hole_mask)) => hole_mask,
+                    (Some(weak_mask), None) => weak_mask,
+                    (None, None) => &mut sector.data,
+                };
+                parse_hex_line(line, buf)?;
+            }
+        }
+    }
+    if let Some(sector) = pending.take() {
+        sector.add_to(&mut disk)?;
+    }
+
+    let Some(first_track) = first_track
+    else {
+        return Err(corrupt("No tracks found".to_string()));
+    };
+    disk.descriptor.geometry = DiskCh::new(cylinders, heads);
+    disk.descriptor.data_encoding = first_track.encoding;
+    disk.descriptor.data_rate = first_track.data_rate;
+    disk.descriptor.density = first_track.data_rate.into();
+
+    disk.post_load_process();
+    Ok(disk)
+}
+
+/// A sector whose hex lines are still being read.
+struct PendingSector {
+    ch: DiskCh,
+    id_chsn: DiskChsn,
+    attributes: SectorAttributes,
+    data: Vec<u8>,
+    weak_mask: Option<Vec<u8>>,
+    hole_mask: Option<Vec<u8>>,
+}
+
+impl PendingSector {
+    fn add_to(self, disk: &mut DiskImage) -> Result<(), DiskImageError> {
+        let track = disk.track_mut(self.ch).ok_or(DiskImageError::SeekError)?;
+        track.add_sector(&AddSectorParams {
+            id_chsn: self.id_chsn,
+            data: &self.data,
+            weak_mask: self.weak_mask.as_deref(),
+            hole_mask: self.hole_mask.as_deref(),
+            attributes: self.attributes,
+            ..Default::default()
+        })?;
+        Ok(())
+    }
+}
+
+/// Append `data` to `dump` as hex lines of [BYTES_PER_LINE] bytes with an ASCII column.
+fn write_hex(dump: &mut String, data: &[u8]) {
+    for (i, chunk) in data.chunks(BYTES_PER_LINE).enumerate() {
+        _ = write!(dump, "{:04X}:", i * BYTES_PER_LINE);
+        for byte in chunk {
+            _ = write!(dump, " {:02X}", byte);
+        }
+        let padding = (BYTES_PER_LINE - chunk.len()) * 3;
+        _ = write!(dump, "{:padding$}  |", "");
+        dump.extend(chunk.iter().map(|&b| match b {
+            0x20..=0x7E => b as char,
+            _ => '.',
+        }));
+        dump.push_str("|\n");
+    }
+}
+
+fn has_bits(mask: &[u8]) -> bool {
+    mask.iter().any(|&b| b != 0)
+}
+
+/// Parse a hex line written by [write_hex] and append its bytes to `buf`. The line's offset must
+/// match the current length of `buf`, so that missing or reordered lines are detected.
+fn parse_hex_line(line: &str, buf: &mut Vec<u8>) -> Result<(), DiskImageError> {
+    let (offset, rest) = line
+        .split_once(':')
+        .ok_or_else(|| corrupt(format!("Invalid dump line: {}", line)))?;
+    let offset = usize::from_str_radix(offset, 16).map_err(|_| corrupt(format!("Invalid hex offset: {}", offset)))?;
+    if offset != buf.len() {
+        return Err(corrupt(format!(
+            "Expected offset {:04X}, found {:04X}",
+            buf.len(),
+            offset
+        )));
+    }
+
+    let hex = rest.split_once('|').map_or(rest, |(hex, _)| hex);
+    for byte in hex.split_whitespace() {
+        buf.push(u8::from_str_radix(byte, 16).map_err(|_| corrupt(format!("Invalid hex byte: {}", byte)))?);
+    }
+    Ok(())
+}
+
+/// Read the data and weak bit mask, if available, of the sector at physical `index` on `track`.
+pub(crate) fn read_sector_at(track: &DiskTrack, index: usize) -> Result<(Vec<u8>, Option<Vec<u8>>), DiskImageError> {
+    // Reading a MetaSector randomizes its weak bits, so take the stored data and mask directly.
+    // This also lets us distinguish between sectors with duplicate IDs.
+    if let Some((data, weak_mask)) = track.as_metasector_track().and_then(|t| t.raw_sector_data_at(index)) {
+        return Ok((data.to_vec(), Some(weak_mask.to_vec())));
+    }
+
+    let result = track.read_sector_at(index, RwScope::DataOnly)?;
+    Ok((result.read_buf[result.data_range].to_vec(), None))
+}
+
+pub(crate) fn flags_to_string(attributes: &SectorAttributes) -> String {
+    let flags: Vec<&str> = [
+        (attributes.address_error, "address_error"),
+        (attributes.data_error, "data_error"),
+        (attributes.deleted_mark, "deleted"),
+        (attributes.no_dam, "no_dam"),
+    ]
+    .iter()
+    .filter_map(|&(set, name)| set.then_some(name))
+    .collect();
+
+    if flags.is_empty() {
+        "-".to_string()
+    }
+    else {
+        flags.join(",")
+    }
+}
+
+pub(crate) fn parse_flags(flags: &str) -> Result<SectorAttributes, DiskImageError> {
+    let mut attributes = SectorAttributes::default();
+    for flag in flags.split(',').filter(|&flag| flag != "-") {
+        match flag {
+            "address_error" => attributes.address_error = true,
+            "data_error" => attributes.data_error = true,
+            "deleted" => attributes.deleted_mark = true,
+            "no_dam" => attributes.no_dam = true,
+            _ => return Err(invalid("parse_flags", format!("Unknown sector flag: {}", flag))),
+        }
+    }
+    Ok(attributes)
+}
+
+pub(crate) fn parse_encoding(encoding: &str) -> Result<TrackDataEncoding, DiskImageError> {
+    match encoding {
+        "FM" => Ok(TrackDataEncoding::Fm),
+        "MFM" => Ok(TrackDataEncoding::Mfm),
+        "GCR" => Ok(TrackDataEncoding::Gcr),
+        _ => Err(invalid(
+            "parse_encoding",
+            format!("Unknown track encoding: {}", encoding),
+        )),
+    }
+}
+
+pub(crate) fn parse<T: std::str::FromStr>(value: &str) -> Result<T, DiskImageError> {
+    value
+        .parse()
+        .map_err(|_| invalid("parse", format!("Invalid numeric value: {}", value)))
+}
+
+fn corrupt(msg: String) -> DiskImageError {
+    invalid("import_text_dump", msg)
+}
+
+fn invalid(function: &str, msg: String) -> DiskImageError {
+    log::error!("{}(): {}", function, msg);
+    DiskImageError::ImageCorruptError(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        diff::{DiffSide, TrackDifference},
+        prelude::*,
+        test_util::{add_track, empty_disk},
+        types::{AddSectorParams, SectorAttributes},
+    };
+
+    fn test_disk() -> DiskImage {
+        let mut disk = empty_disk();
+        let layout = StandardFormat::PcFloppy360.layout();
+        let weak_mask = [0xF0u8; 512];
+        let hole_mask: Vec<u8> = (0..512)
+            .map(|i| {
+                if i < 32 {
+                    0xFF
+                }
+                else {
+                    0
+                }
+            })
+            .collect();
+        for ch in layout.ch_iter() {
+            let track = add_track(&mut disk, ch);
+            for s in 1..=layout.s() {
+                let odd = s % 2 == 1;
+                let data: Vec<u8> = (0..512).map(|i| (i as u8).wrapping_add(s)).collect();
+                track
+                    .add_sector(&AddSectorParams {
+                        id_chsn: DiskChsn::from((DiskChs::from((ch, s)), layout.n())),
+                        data: &data,
+                        weak_mask: odd.then_some(&weak_mask[..]),
+                        hole_mask: (s == 3).then_some(&hole_mask[..]),
+                        attributes: SectorAttributes {
+                            data_error: odd,
+                            no_dam: s == 4,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .unwrap();
+            }
+        }
+        disk
+    }
+
+    #[test]
+    fn test_text_dump_round_trip() {
+        let disk = test_disk();
+        let dump = disk.export_text_dump().unwrap();
+        assert!(dump.contains("sector 0 0 1 2 data_error\n0000: 01 02 03"));
+        assert_eq!(dump, disk.export_text_dump().unwrap());
+
+        let imported = DiskImage::import_text_dump(&dump).unwrap();
+        assert_eq!(imported.geometry(), DiskCh::new(40, 2));
+        let diff = disk.diff(&imported);
+        assert!(diff.is_empty(), "{:?}", diff);
+        let hole_regions = imported
+            .track(DiskCh::new(1, 1))
+            .unwrap()
+            .hole_regions(DiskChsn::new(1, 1, 3, 2).into())
+            .unwrap();
+        assert_eq!(hole_regions, vec![0..32]);
+        assert_eq!(imported.export_text_dump().unwrap(), dump);
+
+        // Rewriting one sector should only change that sector's hex lines.
+        let mut modified = imported;
+        modified
+            .write_sector_basic(DiskCh::new(0, 0), DiskChsn::new(0, 0, 2, 2).into(), None, &[0xAA; 512])
+            .unwrap();
+        let modified_dump = modified.export_text_dump().unwrap();
+        let changed = dump.lines().zip(modified_dump.lines()).filter(|(a, b)| a != b).count();
+        assert_eq!(dump.lines().count(), modified_dump.lines().count());
+        assert_eq!(changed, 32);
+    }
+
+    #[test]
+    fn test_text_dump_sparse() {
+        // Cylinder 1 of head 0 is missing from the dump, and head 1 has fewer tracks than head 0.
+        let mut disk = test_disk();
+        let dump = disk.export_text_dump().unwrap();
+        let sparse: String = dump
+            .split("\ntrack ")
+            .filter(|track| !track.starts_with("1 0 ") && !track.starts_with("39 1 "))
+            .collect::<Vec<_>>()
+            .join("\ntrack ");
+
+        let imported = DiskImage::import_text_dump(&sparse).unwrap();
+        assert_eq!(imported.geometry(), DiskCh::new(40, 2));
+        assert!(imported.track(DiskCh::new(1, 0)).unwrap().sector_list().is_empty());
+        assert!(imported.track(DiskCh::new(39, 1)).is_none());
+
+        // The missing cylinder is imported as an empty track, so the only differences from the
+        // original image are the sectors of the dropped tracks.
+        for c in [1, 39] {
+            let h = if c == 1 { 0 } else { 1 };
+            let track = disk.track_mut(DiskCh::new(c, h)).unwrap();
+            for s in 1..=9 {
+                track.remove_sector(DiskChsn::new(c, h, s, 2).into()).unwrap();
+            }
+        }
+        let diff = disk.diff(&imported);
+        assert!(diff.sectors.is_empty(), "{:?}", diff);
+        assert_eq!(diff.tracks.len(), 1);
+        assert_eq!(diff.tracks[0].ch, DiskCh::new(39, 1));
+        assert_eq!(
+            diff.tracks[0].differences,
+            vec![TrackDifference::OnlyIn(DiffSide::Left)]
+        );
+    }
+
+    #[test]
+    fn test_text_dump_invalid() {
+        assert!(matches!(
+            DiskImage::import_text_dump("not a dump"),
+            Err(DiskImageError::ImageCorruptError(_))
+        ));
+
+        let dump = test_disk().export_text_dump().unwrap();
+        let truncated: String = dump
+            .lines()
+            .filter(|line| !line.starts_with("0010:"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        assert!(matches!(
+            DiskImage::import_text_dump(&truncated),
+            Err(DiskImageError::ImageCorruptError(_))
+        ));
+    }
+}
@@ -29,13 +29,22 @@
     Implements the Bitstream track type and the Track trait for same.
 
 */
-use super::{Track, TrackAnalysis, TrackInfo, TrackSectorScanResult};
+use super::{
+    gap_analysis::{measure_gaps, TrackGapReport},
+    memory::{bitvec_bytes, metadata_bytes, TrackMemoryUsage},
+    RepairOptions,
+    RepairSummary,
+    Track,
+    TrackAnalysis,
+    TrackInfo,
+    TrackSectorScanResult,
+};
 use crate::{
     bitstream_codec::{fm::FmCodec, gcr::GcrCodec, mfm::MfmCodec, EncodingVariant, TrackCodec, TrackDataStream},
-    io::SeekFrom,
     source_map::SourceMap,
     track_schema::{
-        system34::{System34Element, System34Marker, System34Schema, System34Standard},
+        system34::{System34Element, System34FormatResult, System34Marker, System34Schema, System34Standard},
+        DecodedElements,
         TrackElement,
         TrackElementInstance,
         TrackMetadata,
@@ -52,13 +61,16 @@ use crate::{
         DiskRpm,
         ReadSectorResult,
         ReadTrackResult,
+        RecoveryOptions,
         RwScope,
         ScanSectorResult,
         SharedDiskContext,
+        SizeMismatchPolicy,
         TrackDataEncoding,
         TrackDataRate,
         TrackDataResolution,
         TrackDensity,
+        TrackFormatting,
         WriteSectorResult,
     },
     DiskImageError,
@@ -69,6 +81,7 @@ use bit_vec::BitVec;
 use sha1_smol::Digest;
 use std::{
     any::Any,
+    ops::Range,
     sync::{Arc, Mutex},
 };
 use strum::IntoEnumIterator;
@@ -139,6 +152,10 @@ impl Track for BitStreamTrack {
         Some(&self.metadata)
     }
 
+    fn decoded_elements(&self) -> DecodedElements<'_> {
+        DecodedElements::new(self.schema, &self.data, &self.metadata)
+    }
+
     fn sector_ct(&self) -> usize {
         let mut sector_ct = 0;
         for item in &self.metadata.items {
@@ -201,6 +218,18 @@ impl Track for BitStreamTrack {
         Err(DiskImageError::UnsupportedFormat)
     }
 
+    fn remove_sector(&mut self, _id: DiskChsnQuery) -> Result<DiskChsn, DiskImageError> {
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
+    fn insert_sector_at(&mut self, _index: usize, _sd: &AddSectorParams) -> Result<(), DiskImageError> {
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
+    fn reorder_sectors(&mut self, _order: &[usize]) -> Result<(), DiskImageError> {
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
     /// Read the sector data from the sector identified by 'chs'. The data is returned within a
     /// [ReadSectorResult] struct which also sets some convenience metadata flags which are needed
     /// when handling `MetaSector` resolution images.
@@ -224,6 +253,7 @@ impl Track for BitStreamTrack {
         let mut result_data_range = 0..0;
         let mut result_chsn = None;
 
+        let mut not_found = false;
         let mut wrong_cylinder = false;
         let mut bad_cylinder = false;
         let mut wrong_head = false;
@@ -279,18 +309,48 @@ impl Track for BitStreamTrack {
                 let instance = self.element(ei).unwrap();
                 // Get the size and range of the sector data element.
                 let element_size = instance.element.size();
-                let scope_range = instance.element.range(scope).unwrap_or(0..element_size);
-                let scope_overhead = element_size - scope_range.len();
+                let mut scope_range = instance.element.range(scope).unwrap_or(0..element_size);
+                let data_only_range = instance.element.range(RwScope::DataOnly).unwrap_or(0..element_size);
+                // The whole element is always decoded, so size the buffer by the overhead of the
+                // address mark and CRC around the sector data, regardless of scope.
+                let scope_overhead = element_size - data_only_range.len();
 
                 // Normally we read the contents of the sector determined by N in the sector header.
                 // The read operation however can override the value of N if the `n` parameter
-                // is Some.
-                let data_len = if let Some(n_value) = n {
-                    DiskChsn::n_to_bytes(n_value) + scope_overhead
+                // is Some, in which case the size mismatch policy determines how much we read.
+                let policy = self.size_mismatch_policy();
+                let stored_len = sector_chsn.n_size();
+                let mut read_len = stored_len;
+                let mut size_mismatch = false;
+                if let Some(n_value) = n.filter(|&n_value| n_value != sector_chsn.n()) {
+                    let requested_len = DiskChsn::n_to_bytes(n_value);
+                    match policy {
+                        SizeMismatchPolicy::Unchecked => read_len = requested_len,
+                        SizeMismatchPolicy::NotFound => {
+                            log::debug!(
+                                "read_sector(): Requested N {} does not match sector {}, reporting not found.",
+                                n_value,
+                                sector_chsn
+                            );
+                            return Ok(ReadSectorResult::default());
+                        }
+                        SizeMismatchPolicy::Truncate | SizeMismatchPolicy::Controller => {
+                            read_len = match policy {
+                                SizeMismatchPolicy::Truncate => requested_len.min(stored_len),
+                                _ => requested_len,
+                            };
+                            size_mismatch = true;
+                            // Move the end of the data, and anything following it, to match the
+                            // length read.
+                            let resize = |i: usize| match i >= data_only_range.end {
+                                true => i - data_only_range.len() + read_len,
+                                false => i,
+                            };
+                            scope_range = resize(scope_range.start)..resize(scope_range.end);
+                        }
+                    }
                 }
-                else {
-                    sector_chsn.n_size() + scope_overhead
-                };
+                let data_len = read_len + scope_overhead;
                 log::debug!(
                     "read_sector(): Allocating {} bytes for sector {} data element of size {} at offset: {:05X}",
                     data_len,
@@ -304,7 +364,7 @@ impl Track for BitStreamTrack {
                 let crc = crc_opt.unwrap();
 
                 // Sanity check: Read CRC matches metadata?
-                if crc.is_error() != data_error {
+                if !size_mismatch && read_len == stored_len && crc.is_error() != data_error {
                     log::warn!(
                         "read_sector(): CRC data/metadata mismatch for sector {}: calculated: {} metadata: {}",
                         sector_chsn,
@@ -313,9 +373,14 @@ impl Track for BitStreamTrack {
                     );
                 }
                 result_address_error = address_error;
-                result_data_error = data_error;
+                result_data_error = match policy {
+                    _ if !size_mismatch => data_error,
+                    SizeMismatchPolicy::Controller => crc.is_error(),
+                    _ => true,
+                };
                 result_deleted_mark = deleted_mark;
-                result_data_range = scope_range;
+                // An unchecked read shorter than the sector may end before the stored data range.
+                result_data_range = scope_range.start.min(data_len)..scope_range.end.min(data_len);
                 // Move crc into Option for return
                 data_crc = Some(crc);
 
@@ -348,6 +413,7 @@ impl Track for BitStreamTrack {
                     wh
                 );
 
+                not_found = true;
                 wrong_cylinder = wc;
                 bad_cylinder = bc;
                 wrong_head = wh;
@@ -362,7 +428,7 @@ impl Track for BitStreamTrack {
             read_buf: read_vec,
             data_range: result_data_range,
             deleted_mark: result_deleted_mark,
-            not_found: false,
+            not_found,
             no_dam: false,
             address_crc_error: result_address_error,
             address_crc: None,
@@ -371,6 +437,7 @@ impl Track for BitStreamTrack {
             wrong_cylinder,
             bad_cylinder,
             wrong_head,
+            hole: false,
         })
     }
 
@@ -616,7 +683,25 @@ impl Track for BitStreamTrack {
         Ok(())
     }
 
-    fn hash(&mut self) -> Digest {
+    fn repair(&mut self, options: &RepairOptions) -> Result<RepairSummary, DiskImageError> {
+        if options.renumber_sectors || options.normalize_sector_sizes {
+            log::error!("repair(): Only CRC repair is supported on BitStream tracks.");
+            return Err(DiskImageError::UnsupportedFormat);
+        }
+
+        let mut summary = RepairSummary::default();
+        if options.fix_data_crcs {
+            for entry in self.sector_list() {
+                if entry.attributes.data_error && !entry.attributes.no_dam {
+                    self.recalculate_sector_crc(DiskChsnQuery::from(entry.chsn), None)?;
+                    summary.fixed_crcs.push(entry.chsn);
+                }
+            }
+        }
+        Ok(summary)
+    }
+
+    fn hash(&self) -> Digest {
         let mut hasher = sha1_smol::Sha1::new();
         hasher.update(&self.data.data_copied());
         hasher.digest()
@@ -628,7 +713,7 @@ impl Track for BitStreamTrack {
     /// The data returned is only the actual sector data. The address marks and CRCs are not included
     /// in the data.
     /// This function is intended for use in implementing the Read Track FDC command.
-    fn read_all_sectors(&mut self, _ch: DiskCh, n: u8, eot: u8) -> Result<ReadTrackResult, DiskImageError> {
+    fn read_all_sectors(&self, _ch: DiskCh, n: u8, eot: u8) -> Result<ReadTrackResult, DiskImageError> {
         let mut track_read_vec = Vec::with_capacity(512 * 9);
         let sector_data_len = DiskChsn::n_to_bytes(n);
         let mut sector_read_vec = vec![0u8; sector_data_len];
@@ -794,10 +879,75 @@ impl Track for BitStreamTrack {
         })
     }
 
+    fn read_raw_bits(&self, range: Range<usize>) -> Result<BitVec, DiskImageError> {
+        if range.is_empty() || range.end > self.data.len() {
+            log::error!(
+                "read_raw_bits(): Range {:?} is invalid for track of {} bits",
+                range,
+                self.data.len()
+            );
+            return Err(DiskImageError::ParameterError);
+        }
+        let bits = self.data.data();
+        Ok(range.map(|i| bits[i]).collect())
+    }
+
+    fn write_raw_bits(&mut self, offset: usize, bits: &BitVec) -> Result<(), DiskImageError> {
+        if bits.is_empty() || offset + bits.len() > self.data.len() {
+            log::error!(
+                "write_raw_bits(): Writing {} bits at offset {} exceeds track of {} bits",
+                bits.len(),
+                offset,
+                self.data.len()
+            );
+            return Err(DiskImageError::ParameterError);
+        }
+
+        let data = self.data.data_mut();
+        for (i, bit) in bits.iter().enumerate() {
+            data.set(offset + i, bit);
+        }
+
+        // Marks may have been created or destroyed, so re-parse the track with its current schema.
+        self.rescan(self.schema)
+    }
+
     fn has_weak_bits(&self) -> bool {
         self.data.has_weak_bits()
     }
 
+    fn formatting(&self) -> TrackFormatting {
+        if !self.metadata.items.is_empty() {
+            return TrackFormatting::Formatted;
+        }
+
+        // A track with no transitions, or only a regular clock pattern (as written by
+        // DiskImage::add_empty_track()), is blank. Anything else, including weak bits, is noise.
+        let bits = self.data.data();
+        let regular = bits.len() < 2 || bits.iter().enumerate().all(|(i, bit)| bit == bits[i % 2]);
+        TrackFormatting::Unformatted {
+            noise: !regular || self.data.weak_mask().any(),
+        }
+    }
+
+    fn set_hole_region(&mut self, _id: DiskChsnQuery, _range: Range<usize>) -> Result<(), DiskImageError> {
+        log::error!("set_hole_region(): Holes are not supported on BitStream tracks.");
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
+    fn clear_hole_region(&mut self, _id: DiskChsnQuery, _range: Option<Range<usize>>) -> Result<(), DiskImageError> {
+        log::error!("clear_hole_region(): Holes are not supported on BitStream tracks.");
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
+    fn hole_regions(&self, id: DiskChsnQuery) -> Result<Vec<Range<usize>>, DiskImageError> {
+        // Holes are not modeled, so any matching sector has none.
+        if self.scan_sector(id, None)?.not_found {
+            return Err(DiskImageError::IdError);
+        }
+        Ok(Vec::new())
+    }
+
     fn format(
         &mut self,
         standard: System34Standard,
@@ -806,43 +956,24 @@ impl Track for BitStreamTrack {
         gap3: usize,
     ) -> Result<(), DiskImageError> {
         let bitcell_ct = self.data.len();
-        let format_result =
-            System34Schema::format_track_as_bytes(standard, bitcell_ct, format_buffer, fill_pattern, gap3)?;
-
-        let new_bit_vec = self
-            .data
-            .encode(&format_result.track_bytes, false, EncodingVariant::Data);
-        log::trace!(
-            "New bitstream size: {} from {} bytes",
-            new_bit_vec.len(),
-            format_result.track_bytes.len()
-        );
-        self.data.replace(new_bit_vec);
-
-        System34Schema::set_track_markers(&mut self.data, format_result.markers)?;
-
-        // Scan the new track data for markers and create a clock map.
-        let markers = System34Schema::scan_markers(&self.data);
-        if markers.is_empty() {
-            log::error!("TrackData::format(): No markers found in track data post-format.");
-        }
-        else {
-            log::trace!("TrackData::format(): Found {} markers in track data.", markers.len());
-        }
-        System34Schema::create_clock_map(&markers, self.data.clock_map_mut());
-
-        let new_metadata = TrackMetadata::new(
-            System34Schema::scan_metadata(&mut self.data, markers),
-            TrackSchema::System34,
-        );
+        let format_result = System34Schema::format_track_as_bytes(
+            standard,
+            self.encoding,
+            bitcell_ct,
+            format_buffer,
+            fill_pattern,
+            gap3,
+        )?;
+        self.apply_format_result(format_result)
+    }
 
-        let data_ranges = new_metadata.data_ranges();
-        if !data_ranges.is_empty() {
-            self.data.set_data_ranges(data_ranges);
+    fn write_track(&mut self, data: &[u8]) -> Result<(), DiskImageError> {
+        if !matches!(self.encoding, TrackDataEncoding::Mfm) {
+            log::error!("write_track(): Only MFM tracks can be written.");
+            return Err(DiskImageError::UnsupportedFormat);
         }
-
-        self.metadata = new_metadata;
-        Ok(())
+        let write_result = System34Schema::write_track_as_bytes(self.data.len(), data)?;
+        self.apply_format_result(write_result)
     }
 
     fn analysis(&self) -> Result<TrackAnalysis, DiskImageError> {
@@ -850,6 +981,13 @@ impl Track for BitStreamTrack {
         Ok(schema.analyze_elements(&self.metadata))
     }
 
+    fn gap_report(&self) -> Option<TrackGapReport> {
+        match self.schema {
+            Some(TrackSchema::System34) => Some(measure_gaps(self.ch, self.encoding, &self.data, &self.metadata)),
+            _ => None,
+        }
+    }
+
     fn stream(&self) -> Option<&TrackDataStream> {
         Some(&self.data)
     }
@@ -861,6 +999,17 @@ impl Track for BitStreamTrack {
     fn element_map(&self) -> Option<&SourceMap> {
         Some(&self.metadata.element_map)
     }
+
+    fn memory_usage(&self) -> TrackMemoryUsage {
+        TrackMemoryUsage {
+            bitcells: bitvec_bytes(self.data.data())
+                + bitvec_bytes(self.data.clock_map())
+                + bitvec_bytes(self.data.error_map())
+                + bitvec_bytes(self.data.weak_mask()),
+            metadata: metadata_bytes(&self.metadata),
+            ..TrackMemoryUsage::new(self.ch)
+        }
+    }
 }
 
 impl BitStreamTrack {
@@ -1042,7 +1191,12 @@ impl BitStreamTrack {
         }
 
         if let Some(schema) = track_schema {
-            track_metadata = TrackMetadata::new(schema.scan_for_elements(&mut data_stream, track_markers), schema);
+            let recovery = shared
+                .as_ref()
+                .map(|shared| shared.lock().unwrap().recovery)
+                .unwrap_or_default();
+            let (elements, recoveries) = schema.scan_for_elements(&mut data_stream, track_markers, &recovery);
+            track_metadata = TrackMetadata::new(elements, schema).with_recoveries(recoveries);
         }
 
         let sector_ids = track_metadata.sector_ids();
@@ -1122,7 +1276,9 @@ impl BitStreamTrack {
                 log::warn!("Schema {:?} failed to detect track markers.", schema);
             }
 
-            self.metadata = TrackMetadata::new(schema.scan_for_elements(&mut self.data, track_markers), schema);
+            let recovery = self.recovery_options();
+            let (elements, recoveries) = schema.scan_for_elements(&mut self.data, track_markers, &recovery);
+            self.metadata = TrackMetadata::new(elements, schema).with_recoveries(recoveries);
             let sector_ids = self.metadata.valid_sector_ids();
             if sector_ids.is_empty() {
                 log::debug!(
@@ -1144,6 +1300,61 @@ impl BitStreamTrack {
         Ok(())
     }
 
+    /// Rescan the track for elements with its current schema, such as after the [RecoveryOptions]
+    /// of the disk image have changed. Unlike [BitStreamTrack::rescan], no schema detection is
+    /// performed.
+    pub(crate) fn rescan_elements(&mut self) {
+        if let Some(schema) = self.schema {
+            let markers = schema.scan_for_markers(&self.data);
+            schema.create_clock_map(&markers, self.data.clock_map_mut());
+            let recovery = self.recovery_options();
+            let (elements, recoveries) = schema.scan_for_elements(&mut self.data, markers, &recovery);
+            self.metadata = TrackMetadata::new(elements, schema).with_recoveries(recoveries);
+            let data_ranges = self.metadata.data_ranges();
+            if !data_ranges.is_empty() {
+                self.data.set_data_ranges(data_ranges);
+            }
+        }
+    }
+
+    /// Replace the track data with formatted track bytes, set the address markers and rescan the
+    /// track for metadata.
+    fn apply_format_result(&mut self, format_result: System34FormatResult) -> Result<(), DiskImageError> {
+        let new_bit_vec = self
+            .data
+            .encode(&format_result.track_bytes, false, EncodingVariant::Data);
+        log::trace!(
+            "New bitstream size: {} from {} bytes",
+            new_bit_vec.len(),
+            format_result.track_bytes.len()
+        );
+        self.data.replace(new_bit_vec);
+
+        System34Schema::set_track_markers(&mut self.data, format_result.markers)?;
+
+        // Scan the new track data for markers and create a clock map.
+        let markers = System34Schema::scan_markers(&self.data);
+        if markers.is_empty() {
+            log::error!("apply_format_result(): No markers found in track data post-format.");
+        }
+        else {
+            log::trace!("apply_format_result(): Found {} markers in track data.", markers.len());
+        }
+        System34Schema::create_clock_map(&markers, self.data.clock_map_mut());
+
+        let recovery = self.recovery_options();
+        let (elements, recoveries) = System34Schema::scan_metadata(&mut self.data, markers, &recovery);
+        let new_metadata = TrackMetadata::new(elements, TrackSchema::System34).with_recoveries(recoveries);
+
+        let data_ranges = new_metadata.data_ranges();
+        if !data_ranges.is_empty() {
+            self.data.set_data_ranges(data_ranges);
+        }
+
+        self.metadata = new_metadata;
+        Ok(())
+    }
+
     #[allow(dead_code)]
     fn elements(&self) -> &[TrackElementInstance] {
         &self.metadata.items
@@ -1164,6 +1375,22 @@ impl BitStreamTrack {
         self.metadata.items.get_mut(idx)
     }
 
+    /// Return the [SizeMismatchPolicy] of the disk image the track belongs to.
+    fn size_mismatch_policy(&self) -> SizeMismatchPolicy {
+        self.shared
+            .as_ref()
+            .map(|shared| shared.lock().unwrap().size_mismatch)
+            .unwrap_or_default()
+    }
+
+    /// Return the [RecoveryOptions] of the disk image the track belongs to.
+    fn recovery_options(&self) -> RecoveryOptions {
+        self.shared
+            .as_ref()
+            .map(|shared| shared.lock().unwrap().recovery)
+            .unwrap_or_default()
+    }
+
     pub(crate) fn add_write(&mut self, _bytes: usize) {
         if let Some(shared) = &self.shared {
             let mut write_count = shared.lock().unwrap().writes;
@@ -1172,11 +1399,10 @@ impl BitStreamTrack {
         }
     }
 
-    fn read_exact_at(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), DiskImageError> {
-        self.data
-            .seek(SeekFrom::Start(offset as u64))
-            .map_err(|_| DiskImageError::SeekError)?;
-        self.data.read_exact(buf)?;
+    fn read_exact_at(&self, offset: usize, buf: &mut [u8]) -> Result<(), DiskImageError> {
+        if self.data.read_decoded_buf(buf, offset) < buf.len() {
+            return Err(DiskImageError::BitstreamError);
+        }
         Ok(())
     }
 
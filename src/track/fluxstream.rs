@@ -32,10 +32,19 @@
 
 use std::{
     any::Any,
-    sync::{Arc, Mutex},
+    ops::Range,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
 };
 
-use super::{Track, TrackAnalysis, TrackInfo};
+use super::{
+    memory::{bitvec_bytes, slice_bytes, TrackMemoryUsage},
+    RepairOptions,
+    RepairSummary,
+    Track,
+    TrackAnalysis,
+    TrackInfo,
+};
 use crate::{
     bitstream_codec::TrackDataStream,
     flux::{
@@ -44,8 +53,8 @@ use crate::{
         pll::{Pll, PllPreset},
     },
     format_us,
-    track::bitstream::BitStreamTrack,
-    track_schema::{system34::System34Standard, TrackMetadata, TrackSchema},
+    track::{bitstream::BitStreamTrack, gap_analysis::TrackGapReport},
+    track_schema::{system34::System34Standard, DecodedElements, TrackMetadata, TrackSchema},
     types::{
         chs::DiskChsnQuery,
         AddSectorParams,
@@ -63,6 +72,7 @@ use crate::{
         TrackDataRate,
         TrackDataResolution,
         TrackDensity,
+        TrackFormatting,
         WriteSectorResult,
     },
     DiskImageError,
@@ -73,7 +83,13 @@ use crate::{
     flux::{pll::PllMarkerEntry, FluxRevolutionType},
     source_map::SourceMap,
 };
+use bit_vec::BitVec;
 use sha1_smol::Digest;
+use web_time::Instant;
+
+/// The maximum deviation of a double-density revolution's transition bands from their ideal
+/// positions, as a fraction of a bitcell, before the bands are normalized prior to decoding.
+const DD_BAND_SKEW_TOLERANCE: f64 = 0.1;
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -135,6 +151,35 @@ impl<'a> Iterator for RawFluxIterator<'a> {
     }
 }
 
+/// The flux track and decoding hints of a [FluxStreamTrack] whose decoding has been deferred by a
+/// lazy [DecodePolicy](crate::types::DecodePolicy). It is shared between clones of the track, so
+/// a track is only ever decoded once, whether on access or in the background.
+struct DeferredDecode {
+    clock: Option<f64>,
+    rpm: Option<DiskRpm>,
+    source: Mutex<Option<FluxStreamTrack>>,
+    decoded: OnceLock<FluxStreamTrack>,
+}
+
+impl DeferredDecode {
+    /// Return the decoded track, decoding it now if it has not yet been decoded.
+    fn get(&self) -> &FluxStreamTrack {
+        self.decoded.get_or_init(|| {
+            let mut track = self.source.lock().unwrap().take().unwrap_or_default();
+            log::debug!("DeferredDecode::get(): Decoding deferred track {}", track.ch);
+            if let Err(e) = track.decode(self.clock, self.rpm) {
+                log::error!("DeferredDecode::get(): Error decoding track {}: {:?}", track.ch, e);
+            }
+            track
+        })
+    }
+
+    fn into_decoded(self) -> FluxStreamTrack {
+        self.get();
+        self.decoded.into_inner().unwrap_or_default()
+    }
+}
+
 #[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FluxStreamTrack {
@@ -154,6 +199,60 @@ pub struct FluxStreamTrack {
 
     #[cfg_attr(feature = "serde", serde(skip))]
     shared: Option<Arc<Mutex<SharedDiskContext>>>,
+
+    // Set if decoding of this track has been deferred. The other fields are then empty, and
+    // accessors forward to the decoded track.
+    #[cfg_attr(feature = "serde", serde(with = "deferred_serde"))]
+    deferred: Option<Arc<DeferredDecode>>,
+}
+
+#[cfg(feature = "serde")]
+mod deferred_serde {
+    use super::*;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    // A deferred track is stored as its decoding hints, its flux track, and whether the flux
+    // track has been decoded.
+    type Stored = Option<(Option<f64>, Option<DiskRpm>, FluxStreamTrack, bool)>;
+
+    pub(super) fn serialize<S: Serializer>(
+        deferred: &Option<Arc<DeferredDecode>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let stored: Stored = deferred.as_ref().map(|deferred| {
+            let source = deferred.source.lock().unwrap().clone();
+            match source {
+                Some(track) => (deferred.clock, deferred.rpm, track, false),
+                None => (deferred.clock, deferred.rpm, deferred.get().clone(), true),
+            }
+        });
+        stored.serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Arc<DeferredDecode>>, D::Error> {
+        let stored = Stored::deserialize(deserializer)?;
+        Ok(stored.map(|(clock, rpm, track, decoded)| {
+            let deferred = if decoded {
+                DeferredDecode {
+                    clock,
+                    rpm,
+                    source: Mutex::new(None),
+                    decoded: OnceLock::from(track),
+                }
+            }
+            else {
+                DeferredDecode {
+                    clock,
+                    rpm,
+                    source: Mutex::new(Some(track)),
+                    decoded: OnceLock::new(),
+                }
+            };
+            Arc::new(deferred)
+        }))
+    }
 }
 
 #[cfg_attr(feature = "serde", typetag::serde)]
@@ -183,14 +282,18 @@ impl Track for FluxStreamTrack {
     }
 
     fn set_ch(&mut self, new_ch: DiskCh) {
+        self.materialize();
         self.ch = new_ch;
     }
 
     fn encoding(&self) -> TrackDataEncoding {
-        self.encoding
+        self.decoded().encoding
     }
 
     fn info(&self) -> TrackInfo {
+        if self.deferred.is_some() {
+            return self.decoded().info();
+        }
         if let Some(resolved) = self.get_bitstream() {
             let mut ti = resolved.info();
 
@@ -229,6 +332,13 @@ impl Track for FluxStreamTrack {
         None
     }
 
+    fn decoded_elements(&self) -> DecodedElements<'_> {
+        if let Some(resolved) = self.get_bitstream() {
+            return resolved.decoded_elements();
+        }
+        DecodedElements::empty()
+    }
+
     fn sector_ct(&self) -> usize {
         if let Some(resolved) = self.get_bitstream() {
             return resolved.sector_ct();
@@ -254,6 +364,18 @@ impl Track for FluxStreamTrack {
         Err(DiskImageError::UnsupportedFormat)
     }
 
+    fn remove_sector(&mut self, _id: DiskChsnQuery) -> Result<DiskChsn, DiskImageError> {
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
+    fn insert_sector_at(&mut self, _index: usize, _sd: &AddSectorParams) -> Result<(), DiskImageError> {
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
+    fn reorder_sectors(&mut self, _order: &[usize]) -> Result<(), DiskImageError> {
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
     /// Read the sector data from the sector identified by 'chs'. The data is returned within a
     /// ReadSectorResult struct which also sets some convenience metadata flags where are needed
     /// when handling MetaSector images.
@@ -274,6 +396,34 @@ impl Track for FluxStreamTrack {
         Err(DiskImageError::ResolveError)
     }
 
+    fn revolutions(&self) -> usize {
+        self.decoded().decoded_revolutions.len()
+    }
+
+    fn read_sector_rev(
+        &self,
+        id: DiskChsnQuery,
+        revolution: usize,
+        n: Option<u8>,
+        scope: RwScope,
+    ) -> Result<ReadSectorResult, DiskImageError> {
+        if self.deferred.is_some() {
+            return self.decoded().read_sector_rev(id, revolution, n, scope);
+        }
+        match self.decoded_revolutions.get(revolution) {
+            Some(Some(track)) => track.read_sector(id, n, None, scope, false),
+            Some(None) => Err(DiskImageError::ResolveError),
+            None => {
+                log::error!(
+                    "read_sector_rev(): Revolution {} out of range ({} revolutions)",
+                    revolution,
+                    self.decoded_revolutions.len()
+                );
+                Err(DiskImageError::ParameterError)
+            }
+        }
+    }
+
     fn scan_sector(&self, id: DiskChsnQuery, offset: Option<usize>) -> Result<ScanSectorResult, DiskImageError> {
         if let Some(resolved) = self.get_bitstream() {
             return Ok(resolved.scan_sector_element(id, offset.unwrap_or(0))?.into());
@@ -290,6 +440,7 @@ impl Track for FluxStreamTrack {
         write_deleted: bool,
         debug: bool,
     ) -> Result<WriteSectorResult, DiskImageError> {
+        self.materialize();
         let old_dirty = self.dirty;
         self.dirty = true;
         if let Some(resolved) = self.get_bitstream_mut() {
@@ -306,8 +457,15 @@ impl Track for FluxStreamTrack {
         Err(DiskImageError::ResolveError)
     }
 
-    fn hash(&mut self) -> Digest {
+    fn repair(&mut self, options: &RepairOptions) -> Result<RepairSummary, DiskImageError> {
         if let Some(resolved) = self.get_bitstream_mut() {
+            return resolved.repair(options);
+        }
+        Err(DiskImageError::ResolveError)
+    }
+
+    fn hash(&self) -> Digest {
+        if let Some(resolved) = self.get_bitstream() {
             return resolved.hash();
         }
 
@@ -320,8 +478,8 @@ impl Track for FluxStreamTrack {
     /// Unlike read_sectors, the data returned is only the actual sector data. The address marks and
     /// CRCs are not included in the data.
     /// This function is intended for use in implementing the Read Track FDC command.
-    fn read_all_sectors(&mut self, _ch: DiskCh, n: u8, eot: u8) -> Result<ReadTrackResult, DiskImageError> {
-        if let Some(resolved) = self.get_bitstream_mut() {
+    fn read_all_sectors(&self, _ch: DiskCh, n: u8, eot: u8) -> Result<ReadTrackResult, DiskImageError> {
+        if let Some(resolved) = self.get_bitstream() {
             return resolved.read_all_sectors(_ch, n, eot);
         }
 
@@ -349,6 +507,24 @@ impl Track for FluxStreamTrack {
         Err(DiskImageError::ResolveError)
     }
 
+    fn read_raw_bits(&self, range: Range<usize>) -> Result<BitVec, DiskImageError> {
+        if let Some(resolved) = self.get_bitstream() {
+            return resolved.read_raw_bits(range);
+        }
+        Err(DiskImageError::ResolveError)
+    }
+
+    fn write_raw_bits(&mut self, offset: usize, bits: &BitVec) -> Result<(), DiskImageError> {
+        self.materialize();
+        let old_dirty = self.dirty;
+        self.dirty = true;
+        if let Some(resolved) = self.get_bitstream_mut() {
+            return resolved.write_raw_bits(offset, bits);
+        }
+        self.dirty = old_dirty;
+        Err(DiskImageError::ResolveError)
+    }
+
     fn has_weak_bits(&self) -> bool {
         if let Some(resolved) = self.get_bitstream() {
             return resolved.has_weak_bits();
@@ -356,6 +532,36 @@ impl Track for FluxStreamTrack {
         false
     }
 
+    fn formatting(&self) -> TrackFormatting {
+        if self.deferred.is_some() {
+            return self.decoded().formatting();
+        }
+        match self.get_bitstream() {
+            Some(resolved) if !resolved.formatting().is_unformatted() => TrackFormatting::Formatted,
+            _ => TrackFormatting::Unformatted {
+                noise: self.revolutions.iter().any(|r| r.ft_ct() > 0),
+            },
+        }
+    }
+
+    fn set_hole_region(&mut self, _id: DiskChsnQuery, _range: Range<usize>) -> Result<(), DiskImageError> {
+        log::error!("set_hole_region(): Holes are not supported on FluxStream tracks.");
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
+    fn clear_hole_region(&mut self, _id: DiskChsnQuery, _range: Option<Range<usize>>) -> Result<(), DiskImageError> {
+        log::error!("clear_hole_region(): Holes are not supported on FluxStream tracks.");
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
+    fn hole_regions(&self, id: DiskChsnQuery) -> Result<Vec<Range<usize>>, DiskImageError> {
+        // Holes are not modeled, so any matching sector has none.
+        if self.scan_sector(id, None)?.not_found {
+            return Err(DiskImageError::IdError);
+        }
+        Ok(Vec::new())
+    }
+
     fn format(
         &mut self,
         standard: System34Standard,
@@ -363,6 +569,7 @@ impl Track for FluxStreamTrack {
         fill_pattern: &[u8],
         gap3: usize,
     ) -> Result<(), DiskImageError> {
+        self.materialize();
         let old_dirty = self.dirty;
         self.dirty = true;
         if let Some(resolved) = self.get_bitstream_mut() {
@@ -372,6 +579,17 @@ impl Track for FluxStreamTrack {
         Err(DiskImageError::ResolveError)
     }
 
+    fn write_track(&mut self, data: &[u8]) -> Result<(), DiskImageError> {
+        self.materialize();
+        let old_dirty = self.dirty;
+        self.dirty = true;
+        if let Some(resolved) = self.get_bitstream_mut() {
+            return resolved.write_track(data);
+        }
+        self.dirty = old_dirty;
+        Err(DiskImageError::ResolveError)
+    }
+
     fn analysis(&self) -> Result<TrackAnalysis, DiskImageError> {
         if let Some(resolved) = self.get_bitstream() {
             return resolved.analysis();
@@ -379,6 +597,13 @@ impl Track for FluxStreamTrack {
         Err(DiskImageError::ResolveError)
     }
 
+    fn gap_report(&self) -> Option<TrackGapReport> {
+        if let Some(resolved) = self.get_bitstream() {
+            return resolved.gap_report();
+        }
+        None
+    }
+
     fn stream(&self) -> Option<&TrackDataStream> {
         if let Some(resolved) = self.get_bitstream() {
             return resolved.stream();
@@ -399,6 +624,33 @@ impl Track for FluxStreamTrack {
         }
         None
     }
+
+    fn memory_usage(&self) -> TrackMemoryUsage {
+        if let Some(deferred) = &self.deferred {
+            // Measure the flux of a deferred track without decoding it.
+            let source_usage = deferred
+                .source
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|track| track.memory_usage());
+            return source_usage.unwrap_or_else(|| deferred.get().memory_usage());
+        }
+
+        let mut usage = TrackMemoryUsage::new(self.ch);
+        for revolution in &self.revolutions {
+            usage.flux += slice_bytes(&revolution.flux_deltas)
+                + slice_bytes(&revolution.transitions)
+                + slice_bytes(&revolution.markers)
+                + slice_bytes(&revolution.pll_stats);
+            usage.bitcells += bitvec_bytes(&revolution.bitstream) + bitvec_bytes(&revolution.biterrors);
+        }
+        self.decoded_revolutions
+            .iter()
+            .flatten()
+            .chain(self.resolved.iter())
+            .fold(usage, |usage, track| usage + track.memory_usage())
+    }
 }
 
 impl Default for FluxStreamTrack {
@@ -422,19 +674,127 @@ impl FluxStreamTrack {
             dirty: false,
             resolved: None,
             shared: None,
+            deferred: None,
+        }
+    }
+
+    /// Create a track whose decoding is deferred until it is first accessed. `track` must already
+    /// have its `ch` and shared context set.
+    pub(crate) fn new_deferred(track: FluxStreamTrack, clock_hint: Option<f64>, rpm_hint: Option<DiskRpm>) -> Self {
+        FluxStreamTrack {
+            ch: track.ch,
+            shared: track.shared.clone(),
+            deferred: Some(Arc::new(DeferredDecode {
+                clock: clock_hint,
+                rpm: rpm_hint,
+                source: Mutex::new(Some(track)),
+                decoded: OnceLock::new(),
+            })),
+            ..FluxStreamTrack::new()
+        }
+    }
+
+    /// Return true if the track has been decoded. A track is not decoded only if its decoding has
+    /// been deferred by a lazy [DecodePolicy](crate::types::DecodePolicy) and it has not yet been
+    /// accessed or prefetched.
+    pub fn is_decoded(&self) -> bool {
+        match &self.deferred {
+            Some(deferred) => deferred.decoded.get().is_some(),
+            None => true,
+        }
+    }
+
+    /// Return the decoded track. This is the track itself, unless its decoding has been deferred,
+    /// in which case the track is decoded now if it has not been already.
+    fn decoded(&self) -> &FluxStreamTrack {
+        match &self.deferred {
+            Some(deferred) => deferred.get(),
+            None => self,
+        }
+    }
+
+    /// Replace a track whose decoding has been deferred with its decoded track, decoding it now if
+    /// it has not been already. This must be done before the track is modified.
+    pub(crate) fn materialize(&mut self) {
+        if let Some(deferred) = self.deferred.take() {
+            *self = match Arc::try_unwrap(deferred) {
+                Ok(deferred) => deferred.into_decoded(),
+                Err(deferred) => deferred.get().clone(),
+            };
+        }
+    }
+
+    /// Start decoding a track whose decoding has been deferred, if it has not been decoded
+    /// already. If the `rayon` feature is enabled (except on wasm32) the track is decoded in the
+    /// background, otherwise it is decoded before returning.
+    pub(crate) fn prefetch(&self) {
+        let Some(deferred) = &self.deferred
+        else {
+            return;
+        };
+        if deferred.decoded.get().is_some() {
+            return;
+        }
+        #[cfg(all(feature = "rayon", not(target_arch = "wasm32")))]
+        {
+            let deferred = deferred.clone();
+            rayon::spawn(move || _ = deferred.get());
+        }
+        #[cfg(not(all(feature = "rayon", not(target_arch = "wasm32"))))]
+        deferred.get();
+    }
+
+    /// Drop the bitstreams decoded from every revolution but the best one. The flux of every
+    /// revolution is kept, but only the best revolution can be read with [Track::read_sector_rev].
+    pub(crate) fn retain_best_revolution(&mut self) {
+        self.materialize();
+        let best_revolution = self.best_revolution;
+        for (i, decoded) in self.decoded_revolutions.iter_mut().enumerate() {
+            if i != best_revolution {
+                *decoded = None;
+            }
         }
     }
 
+    /// Take the bitstream track resolved from the track's flux, leaving the track unresolved.
+    /// Returns `None` if no revolution was decoded.
+    pub(crate) fn take_bitstream(&mut self) -> Option<BitStreamTrack> {
+        self.materialize();
+        self.resolved.take().or_else(|| {
+            self.decoded_revolutions
+                .get_mut(self.best_revolution)
+                .and_then(Option::take)
+        })
+    }
+
+    /// Synthesize additional revolutions, decode all revolutions using the provided clock and
+    /// rotation rate hints, and select the best revolution. Returns the time taken to decode the
+    /// revolutions and to analyze them.
+    pub(crate) fn decode(
+        &mut self,
+        clock_hint: Option<f64>,
+        rpm_hint: Option<DiskRpm>,
+    ) -> Result<(Duration, Duration), DiskImageError> {
+        let decode_start = Instant::now();
+        self.synthesize_revolutions(); // Create synthetic revolutions to increase chances of successful decoding.
+        self.decode_revolutions(clock_hint, rpm_hint)?;
+        let decode_time = decode_start.elapsed();
+        let scan_start = Instant::now();
+        self.analyze_revolutions();
+        Ok((decode_time, scan_start.elapsed()))
+    }
+
     pub fn density(&self) -> TrackDensity {
-        self.density
+        self.decoded().density
     }
 
     pub fn set_density(&mut self, density: TrackDensity) {
+        self.materialize();
         self.density = density;
     }
 
     pub fn is_empty(&self) -> bool {
-        self.revolutions.is_empty()
+        self.decoded().revolutions.is_empty()
     }
 
     #[allow(dead_code)]
@@ -452,31 +812,39 @@ impl FluxStreamTrack {
     }
 
     pub fn set_revolution(&mut self, index: usize) {
+        self.materialize();
         if index < self.revolutions.len() {
             self.best_revolution = index;
         }
     }
 
     pub fn revolution_ct(&self) -> usize {
-        self.revolutions.len()
+        self.decoded().revolutions.len()
     }
 
     pub fn revolution(&self, index: usize) -> Option<&FluxRevolution> {
-        self.revolutions.get(index)
+        self.decoded().revolutions.get(index)
     }
 
     pub fn revolution_mut(&mut self, index: usize) -> Option<&mut FluxRevolution> {
+        self.materialize();
         self.revolutions.get_mut(index)
     }
 
     pub fn revolution_iter(&self) -> impl Iterator<Item = &FluxRevolution> {
-        self.revolutions.iter()
+        self.decoded().revolutions.iter()
     }
 
     pub fn revolution_iter_mut(&mut self) -> impl Iterator<Item = &mut FluxRevolution> {
+        self.materialize();
         self.revolutions.iter_mut()
     }
 
+    /// Iterate over the successfully decoded revolutions of the track, as `BitStreamTrack`s.
+    pub(crate) fn decoded_revolution_iter(&self) -> impl Iterator<Item = &BitStreamTrack> {
+        self.decoded().decoded_revolutions.iter().flatten()
+    }
+
     /// Decode all revolutions in the track. Use 'base_clock' to set the base clock for the PLL,
     /// if provided. If not provided, the base clock is estimated based on the flux transition
     /// count, but this can be ambiguous. If no base clock is provided, and we cannot guess, we
@@ -487,6 +855,10 @@ impl FluxStreamTrack {
         rpm_hint: Option<DiskRpm>,
     ) -> Result<(), DiskImageError> {
         self.decoded_revolutions = Vec::new();
+        let normalize_dd_bands = self
+            .shared
+            .as_ref()
+            .is_some_and(|shared| shared.lock().unwrap().normalize_dd_bands);
 
         for (i, revolution) in self.revolutions.iter_mut().enumerate() {
             self.decoded_revolutions.push(None);
@@ -585,6 +957,22 @@ impl FluxStreamTrack {
                 }
             };
 
+            // Double-density media read in a high-density drive often shows narrowed short pulses
+            // and shifted transition bands that the PLL struggles to track. If enabled, pull the
+            // bands back to their ideal positions in a copy of the flux that only the PLL sees,
+            // so that the captured flux is preserved.
+            let mut captured_deltas = None;
+            if normalize_dd_bands && base_clock >= 1.5e-6 {
+                if let Some((deltas, centers)) = revolution.normalized_deltas(base_clock, DD_BAND_SKEW_TOLERANCE) {
+                    log::debug!(
+                        "decode_revolutions(): Revolution {}: Normalized skewed DD flux bands: {:?}",
+                        i,
+                        centers
+                    );
+                    captured_deltas = Some(std::mem::replace(&mut revolution.flux_deltas, deltas));
+                }
+            }
+
             // Create PLL and decode revolution.
             let mut pll = Pll::from_preset(PllPreset::Aggressive);
 
@@ -629,6 +1017,9 @@ impl FluxStreamTrack {
             );
 
             let flux_stats = revolution.decode_direct(&mut pll);
+            if let Some(deltas) = captured_deltas {
+                revolution.flux_deltas = deltas;
+            }
 
             let (bitstream_data, bitcell_ct) = revolution.bitstream_data();
             let params = BitStreamTrackParams {
@@ -659,6 +1050,7 @@ impl FluxStreamTrack {
     }
 
     pub fn synthesize_revolutions(&mut self) {
+        self.materialize();
         let synthetic_revs: Vec<FluxRevolution> = self
             .revolutions
             .windows(2) // Create pairs of successive elements
@@ -669,6 +1061,7 @@ impl FluxStreamTrack {
     }
 
     pub fn analyze_revolutions(&mut self) {
+        self.materialize();
         let mut best_revolution = 0;
         let mut best_score = 0;
 
@@ -715,40 +1108,67 @@ impl FluxStreamTrack {
         self.encoding = rev_ref.encoding;
     }
 
+    /// Return a measure of confidence in the decoded track, from 0.0 to 1.0, as the fraction of
+    /// revolutions that decoded to the same set of error-free sectors as the best revolution.
+    /// Returns None if fewer than two revolutions were decoded, as there is nothing to compare.
+    pub fn decode_confidence(&self) -> Option<f64> {
+        if self.deferred.is_some() {
+            return self.decoded().decode_confidence();
+        }
+        if self.decoded_revolutions.len() < 2 {
+            return None;
+        }
+
+        let good_sectors = |track: &BitStreamTrack| {
+            track
+                .sector_list()
+                .iter()
+                .filter(|s| !s.attributes.data_error && !s.attributes.address_error)
+                .map(|s| s.chsn)
+                .collect::<Vec<_>>()
+        };
+
+        let best = good_sectors(self.decoded_revolutions.get(self.best_revolution)?.as_ref()?);
+        let agree_ct = self
+            .decoded_revolutions
+            .iter()
+            .flatten()
+            .filter(|track| good_sectors(track) == best)
+            .count();
+
+        Some(agree_ct as f64 / self.decoded_revolutions.len() as f64)
+    }
+
     /// Retrieve the flux deltas for the best revolution.
     pub fn flux_deltas(&self) -> &[f64] {
-        self.revolutions[self.best_revolution].flux_deltas.as_slice()
+        let track = self.decoded();
+        track.revolutions[track.best_revolution].flux_deltas.as_slice()
     }
 
     pub fn flux_deltas_us(&self) -> Vec<f32> {
-        self.revolutions[self.best_revolution]
-            .flux_deltas
+        self.flux_deltas()
             .iter()
             .map(|&f| (f * 1_000_000.0) as f32)
             .collect::<Vec<f32>>()
     }
 
     pub fn flux_deltas_revolution(&self, rev: usize) -> Option<&[f64]> {
-        if rev < self.revolutions.len() {
-            return Some(self.revolutions[rev].flux_deltas.as_slice());
-        }
-        None
+        self.revolution(rev).map(|r| r.flux_deltas.as_slice())
     }
 
     pub fn pll_markers(&self) -> &[PllMarkerEntry] {
-        &self.revolutions[self.best_revolution].markers
+        let track = self.decoded();
+        &track.revolutions[track.best_revolution].markers
     }
 
     pub fn pll_markers_revolution(&self, rev: usize) -> Option<&[PllMarkerEntry]> {
-        if rev < self.revolutions.len() {
-            Some(self.revolutions[rev].markers.as_slice())
-        }
-        else {
-            None
-        }
+        self.revolution(rev).map(|r| r.markers.as_slice())
     }
 
     fn get_bitstream(&self) -> Option<&BitStreamTrack> {
+        if self.deferred.is_some() {
+            return self.decoded().get_bitstream();
+        }
         if let Some(resolved) = &self.resolved {
             return Some(resolved);
         }
@@ -767,6 +1187,7 @@ impl FluxStreamTrack {
     }
 
     fn get_bitstream_mut(&mut self) -> Option<&mut BitStreamTrack> {
+        self.materialize();
         if let Some(resolved) = &mut self.resolved {
             return Some(resolved);
         }
@@ -789,7 +1210,7 @@ impl FluxStreamTrack {
     }
 
     pub fn raw_flux_iter(&self) -> RawFluxIterator {
-        RawFluxIterator::new(&self.revolutions)
+        RawFluxIterator::new(&self.decoded().revolutions)
     }
 }
 
@@ -834,4 +1255,52 @@ mod tests {
         let collected: Vec<f64> = iter.collect();
         assert_eq!(collected, vec![0.002, 0.004, 0.006, 0.004, 0.006, 0.002]);
     }
+
+    #[test]
+    fn test_read_sector_rev() {
+        use crate::{bitstream_codec::mfm::MFM_BYTE_LEN, test_util::formatted_disk};
+
+        let disk = formatted_disk();
+        let ch = DiskCh::new(0, 0);
+        let rev = disk.track(ch).unwrap().as_bitstream_track().unwrap().clone();
+        let id = DiskChsnQuery::new(0, 0, 1, 2);
+
+        // Build a flux track with two decoded revolutions, the second with a corrupted byte in
+        // the first sector's data.
+        let mut unstable = rev.clone();
+        let data_start = unstable
+            .decoded_elements()
+            .find(|e| e.instance.element().is_sector_data())
+            .unwrap()
+            .instance
+            .range()
+            .start;
+        unstable.data.write_encoded_buf(&[0xA5], data_start + 8 * MFM_BYTE_LEN);
+
+        let mut track = FluxStreamTrack::new();
+        track.decoded_revolutions = vec![Some(rev), Some(unstable), None];
+        assert_eq!(track.revolutions(), 3);
+
+        let rsr0 = track.read_sector_rev(id, 0, None, RwScope::DataOnly).unwrap();
+        let rsr1 = track.read_sector_rev(id, 1, None, RwScope::DataOnly).unwrap();
+        assert_eq!(rsr0.read_buf[rsr0.data_range.clone()].len(), 512);
+        assert_ne!(
+            rsr0.read_buf[rsr0.data_range.clone()],
+            rsr1.read_buf[rsr1.data_range.clone()]
+        );
+        assert!(matches!(
+            track.read_sector_rev(id, 2, None, RwScope::DataOnly),
+            Err(DiskImageError::ResolveError)
+        ));
+        assert!(matches!(
+            track.read_sector_rev(id, 3, None, RwScope::DataOnly),
+            Err(DiskImageError::ParameterError)
+        ));
+
+        // Tracks without multiple revolutions have a single revolution.
+        let bitstream = disk.track(ch).unwrap();
+        assert_eq!(bitstream.revolutions(), 1);
+        assert!(bitstream.read_sector_rev(id, 0, None, RwScope::DataOnly).is_ok());
+        assert!(bitstream.read_sector_rev(id, 1, None, RwScope::DataOnly).is_err());
+    }
 }
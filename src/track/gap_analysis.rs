@@ -0,0 +1,372 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+    src/track/gap_analysis.rs
+
+    Measure the gaps between the address fields of a System34 track and
+    check them against the minimum lengths required for reliable writes
+    by a real floppy disk controller.
+*/
+
+use crate::{
+    bitstream_codec::{mfm::MFM_BYTE_LEN, TrackDataStream},
+    track_schema::{
+        system34::{System34Element, System34Marker},
+        TrackElement,
+        TrackElementInstance,
+        TrackMetadata,
+    },
+    types::{DiskCh, DiskChsn, TrackDataEncoding},
+};
+
+/// The measured gaps following the sector header and the sector data of a single sector.
+/// Gap lengths are given in bytes and exclude the sync field preceding the next address mark.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SectorGaps {
+    /// The ID of the sector.
+    pub chsn: DiskChsn,
+    /// The length of the sync field preceding the sector header, in bytes.
+    pub header_sync: usize,
+    /// The length of GAP2, between the sector header and the data address mark, or `None` if the
+    /// sector has no data field.
+    pub gap2: Option<usize>,
+    /// The most common byte in GAP2, or `None` if the sector has no data field or GAP2 is empty.
+    pub gap2_filler: Option<u8>,
+    /// The length of the sync field preceding the data address mark, in bytes, or `None` if the
+    /// sector has no data field.
+    pub data_sync: Option<usize>,
+    /// The length of GAP3, between the end of the sector data and the next address mark, or
+    /// `None` if the sector has no data field.
+    pub gap3: Option<usize>,
+    /// The most common byte in GAP3, or `None` if the sector has no data field or GAP3 is empty.
+    pub gap3_filler: Option<u8>,
+}
+
+/// The measured length and content of a gap. The length is given in bytes and excludes the sync
+/// field preceding the next address mark, which is measured separately.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GapStats {
+    /// The length of the gap, in bytes.
+    pub length: usize,
+    /// The most common byte in the gap, or `None` if the gap is empty.
+    pub filler: Option<u8>,
+    /// The length of the sync field following the gap, in bytes.
+    pub sync:   usize,
+}
+
+/// A condition found by gap analysis that may prevent a sector from being reliably written by a
+/// floppy disk controller.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GapWarning {
+    /// GAP2 is shorter than the gap the controller writes before the data field. Writing the
+    /// sector will shift the data field later, into GAP3.
+    Gap2TooShort { chsn: DiskChsn, length: usize, minimum: usize },
+    /// GAP3 is too short to absorb speed variation at the end of a sector write, so writing the
+    /// sector risks overwriting the following address mark.
+    Gap3TooShort { chsn: DiskChsn, length: usize, minimum: usize },
+}
+
+/// The result of measuring the gaps of a single track. Returned by [Track::gap_report].
+///
+/// [Track::gap_report]: crate::track::Track::gap_report
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackGapReport {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    /// GAP4A, between the index and the index address mark, or `None` if the track has no index
+    /// address mark.
+    pub gap4a: Option<GapStats>,
+    /// GAP1, between the index address mark and the first sector header. If the track has no
+    /// index address mark, GAP1 is measured from the index.
+    pub gap1: Option<GapStats>,
+    /// GAP4B, between the end of the last sector and the index, or `None` if the track has no
+    /// sectors.
+    pub gap4b: Option<GapStats>,
+    /// The measured gaps of each sector, in physical order.
+    pub sectors: Vec<SectorGaps>,
+    /// Any gaps found to be too short for reliable writes.
+    pub warnings: Vec<GapWarning>,
+}
+
+impl TrackGapReport {
+    /// Returns `true` if no gaps were found to be too short for reliable writes.
+    pub fn is_write_safe(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Return the shortest and longest GAP3 lengths on the track, excluding the last sector, whose
+    /// GAP3 runs on through GAP4B. Returns `None` if no other sector has a GAP3. A formatting
+    /// controller writes the same GAP3 after every sector, so a spread here suggests the track was
+    /// mastered or modified by other means.
+    pub fn gap3_range(&self) -> Option<(usize, usize)> {
+        let (_, sectors) = self.sectors.split_last()?;
+        let gaps = sectors.iter().filter_map(|s| s.gap3);
+        Some((gaps.clone().min()?, gaps.max()?))
+    }
+}
+
+/// Return the minimum GAP2 length written by a floppy disk controller for the given encoding.
+pub fn min_gap2(encoding: TrackDataEncoding) -> usize {
+    match encoding {
+        TrackDataEncoding::Fm => 11,
+        _ => 22,
+    }
+}
+
+/// Return the minimum GAP3 length required for reliable writes of sectors of size `n` with the
+/// given encoding. These are the read/write gap lengths (GPL) recommended by the µPD765 data
+/// sheet for standard media. Sector sizes beyond the table use the largest listed value.
+pub fn min_gap3(encoding: TrackDataEncoding, n: u8) -> usize {
+    match (encoding, n) {
+        (TrackDataEncoding::Fm, 0) => 0x07,
+        (TrackDataEncoding::Fm, 1) => 0x0E,
+        (TrackDataEncoding::Fm, 2) => 0x1B,
+        (TrackDataEncoding::Fm, 3) => 0x47,
+        (TrackDataEncoding::Fm, _) => 0xC8,
+        (_, 0 | 1) => 0x0E,
+        (_, 2) => 0x1B,
+        (_, 3) => 0x35,
+        (_, 4) => 0x99,
+        (_, _) => 0xC8,
+    }
+}
+
+/// Measure the gap between the bit offsets `start` and `end` of `stream`, in bytes, excluding the
+/// run of 0x00 sync bytes immediately before `end`. If `end` is less than `start`, the gap wraps
+/// around the index. Bytes are decoded backwards from `end` so that they stay aligned to the
+/// address mark that follows the gap.
+fn measure_gap(stream: &TrackDataStream, start: usize, end: usize) -> GapStats {
+    let len = stream.len();
+    let distance = if end >= start { end - start } else { len - start + end };
+    let total = distance / MFM_BYTE_LEN;
+
+    let mut sync_ct = 0;
+    let mut in_sync = true;
+    let mut byte_cts = [0usize; 256];
+    for k in 1..=total {
+        let offset = (end + len - k * MFM_BYTE_LEN) % len;
+        // Don't read past the end of the track. Any sync field is interrupted by the index.
+        if offset + MFM_BYTE_LEN + 2 > len {
+            in_sync = false;
+            continue;
+        }
+        match stream.read_decoded_u8(offset) {
+            Some(0x00) if in_sync => sync_ct += 1,
+            Some(byte) => {
+                in_sync = false;
+                byte_cts[byte as usize] += 1;
+            }
+            None => break,
+        }
+    }
+
+    let filler = (0..=255u8)
+        .max_by_key(|&byte| byte_cts[byte as usize])
+        .filter(|&byte| byte_cts[byte as usize] > 0);
+    GapStats {
+        length: total - sync_ct,
+        filler,
+        sync: sync_ct,
+    }
+}
+
+/// Measure the GAP2 and GAP3 lengths of each sector of a System34 track, and check them against
+/// the minimum lengths for the track's encoding.
+pub(crate) fn measure_gaps(
+    ch: DiskCh,
+    encoding: TrackDataEncoding,
+    stream: &TrackDataStream,
+    metadata: &TrackMetadata,
+) -> TrackGapReport {
+    let items = metadata.elements();
+    let mut report = TrackGapReport {
+        ch,
+        gap4a: None,
+        gap1: None,
+        gap4b: None,
+        sectors: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    // Address marks that a sector write must not overrun.
+    let address_marks: Vec<usize> = items
+        .iter()
+        .filter(|item| {
+            matches!(
+                item.element,
+                TrackElement::System34(System34Element::Marker(..) | System34Element::SectorHeader { .. })
+            )
+        })
+        .map(|item| item.start)
+        .collect();
+
+    // Element ranges may not cover the CRC, so find the end of each field from its size.
+    let field_end = |item: &TrackElementInstance| item.start + item.element.size() * MFM_BYTE_LEN;
+
+    let iam = items.iter().find(|item| {
+        matches!(
+            item.element,
+            TrackElement::System34(System34Element::Marker(System34Marker::Iam, _))
+        )
+    });
+    let first_header = items.iter().find(|item| item.element.is_sector_header());
+    if let Some(iam) = iam {
+        report.gap4a = Some(measure_gap(stream, 0, iam.start));
+    }
+    if let Some(header) = first_header {
+        let gap1_start = iam.map(field_end).filter(|&end| end <= header.start).unwrap_or(0);
+        report.gap1 = Some(measure_gap(stream, gap1_start, header.start));
+    }
+    if let Some(last) = items.iter().rev().find(|item| item.element.is_sector_data()) {
+        report.gap4b = Some(measure_gap(stream, field_end(last).min(stream.len()), stream.len()));
+    }
+
+    for (i, header) in items.iter().enumerate() {
+        let chsn = match header.element {
+            TrackElement::System34(System34Element::SectorHeader { chsn, .. }) => chsn,
+            _ => continue,
+        };
+
+        // The data field belongs to this header if no other header comes before it.
+        let data = items[i + 1..]
+            .iter()
+            .take_while(|item| !item.element.is_sector_header())
+            .find(|item| item.element.is_sector_data());
+
+        // The sync field before the header is the tail of whatever gap precedes it.
+        let header_sync = measure_gap(stream, header.start.saturating_sub(32 * MFM_BYTE_LEN), header.start).sync;
+
+        let (gap2, gap3) = match data {
+            Some(data) => {
+                let gap2 = measure_gap(stream, field_end(header), data.start);
+                // The next address mark after the data, wrapping around the index if needed.
+                let next_mark = address_marks
+                    .iter()
+                    .copied()
+                    .find(|&start| start >= field_end(data))
+                    .or_else(|| address_marks.first().copied());
+                let gap3 = next_mark.map(|start| measure_gap(stream, field_end(data), start));
+                (Some(gap2), gap3)
+            }
+            None => (None, None),
+        };
+        let gap2_filler = gap2.and_then(|gap| gap.filler);
+        let data_sync = gap2.map(|gap| gap.sync);
+        let (gap2, gap3_filler, gap3) = (
+            gap2.map(|gap| gap.length),
+            gap3.and_then(|gap| gap.filler),
+            gap3.map(|gap| gap.length),
+        );
+
+        if let Some(length) = gap2 {
+            let minimum = min_gap2(encoding);
+            if length < minimum {
+                report.warnings.push(GapWarning::Gap2TooShort { chsn, length, minimum });
+            }
+        }
+        if let Some(length) = gap3 {
+            let minimum = min_gap3(encoding, chsn.n());
+            if length < minimum {
+                report.warnings.push(GapWarning::Gap3TooShort { chsn, length, minimum });
+            }
+        }
+        report.sectors.push(SectorGaps {
+            chsn,
+            header_sync,
+            gap2,
+            gap2_filler,
+            data_sync,
+            gap3,
+            gap3_filler,
+        });
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::formatted_disk, track_schema::system34::System34Standard, types::StandardFormat};
+
+    #[test]
+    fn test_gap_report() {
+        let format = StandardFormat::PcFloppy360;
+        let mut disk = formatted_disk();
+
+        let reports = disk.gap_reports();
+        assert_eq!(reports.len(), format.layout().ch_iter().count());
+        let report = &reports[0];
+        assert!(report.is_write_safe());
+        assert_eq!(report.sectors.len(), 9);
+        for sector in &report.sectors[..8] {
+            assert_eq!(sector.gap2, Some(System34Standard::Ibm.gap2()));
+            assert_eq!(sector.gap3, Some(format.gap3()));
+            assert_eq!(sector.header_sync, 12);
+            assert_eq!(sector.data_sync, Some(12));
+            assert_eq!(sector.gap2_filler, Some(0x4E));
+            assert_eq!(sector.gap3_filler, Some(0x4E));
+        }
+        // The image was formatted without an index address mark.
+        assert_eq!(report.gap4a, None);
+        assert_eq!(
+            report.gap1.map(|gap| (gap.length, gap.sync)),
+            Some((System34Standard::Iso.gap1(), 12))
+        );
+        assert_eq!(report.gap4b.and_then(|gap| gap.filler), Some(0x4E));
+        assert_eq!(report.gap3_range(), Some((format.gap3(), format.gap3())));
+
+        // Reformat a track with a GAP3 too short for the controller to write to.
+        let ch = DiskCh::new(1, 0);
+        let chsn_list: Vec<DiskChsn> = (1..=9).map(|s| DiskChsn::new(1, 0, s, 2)).collect();
+        disk.track_mut(ch)
+            .unwrap()
+            .format(System34Standard::Ibm, chsn_list, &[0xF6], 8)
+            .unwrap();
+
+        let report = disk.track(ch).unwrap().gap_report().unwrap();
+        assert!(!report.is_write_safe());
+        assert_eq!(report.sectors[0].gap3, Some(8));
+        assert_eq!(
+            report.gap4a,
+            Some(GapStats {
+                length: 80,
+                filler: Some(0x4E),
+                sync:   12,
+            })
+        );
+        assert_eq!(report.gap1.map(|gap| gap.length), Some(System34Standard::Ibm.gap1()));
+        assert_eq!(report.gap3_range(), Some((8, 8)));
+        assert!(report.warnings.contains(&GapWarning::Gap3TooShort {
+            chsn:    DiskChsn::new(1, 0, 1, 2),
+            length:  8,
+            minimum: min_gap3(TrackDataEncoding::Mfm, 2),
+        }));
+    }
+}
@@ -0,0 +1,242 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+    src/track/interleave.rs
+
+    Determine the logical interleave of a track and the head and cylinder
+    skew between tracks from the physical order of their sectors.
+*/
+
+use crate::{
+    types::{DiskCh, SectorMapEntry},
+    FoxHashMap,
+};
+use std::collections::BTreeMap;
+
+/// The logical interleave of a single track, as determined from the physical order of its
+/// sectors. Returned by [Track::interleave].
+///
+/// [Track::interleave]: crate::track::Track::interleave
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackInterleave {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    /// The interleave factor: the number of physical sector positions from each logical sector to
+    /// the next. A factor of 1 indicates no interleave.
+    pub factor: usize,
+    /// Whether the physical order of the sectors is exactly the order produced by
+    /// [interleaved_order] for this factor and `first_position`.
+    pub consistent: bool,
+    /// The physical position of the lowest numbered sector, counted from the index.
+    pub first_position: usize,
+    /// The sector IDs of the track in physical order. Duplicate sector IDs are listed once.
+    pub order: Vec<u8>,
+}
+
+/// The rotational skew of the lowest numbered sector from one track to another, in physical
+/// sector positions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TrackSkew {
+    /// The track skewed from.
+    pub from: DiskCh,
+    /// The track skewed to.
+    pub to: DiskCh,
+    /// The number of physical sector positions the lowest numbered sector of `to` follows the
+    /// lowest numbered sector of `from`.
+    pub sectors: usize,
+}
+
+/// The interleave of every track of a disk image, and the skew between adjacent heads and
+/// cylinders. Returned by [DiskImage::skew_map].
+///
+/// [DiskImage::skew_map]: crate::DiskImage::skew_map
+#[derive(Clone, Debug, Default)]
+pub struct SkewMap {
+    /// The interleave of each track with at least two sectors, in track order.
+    pub tracks: Vec<TrackInterleave>,
+    /// The skew from head 0 to head 1 of each cylinder.
+    pub head_skews: Vec<TrackSkew>,
+    /// The skew from each track to the same head of the next cylinder.
+    pub cylinder_skews: Vec<TrackSkew>,
+}
+
+impl SkewMap {
+    pub(crate) fn new(tracks: Vec<TrackInterleave>) -> Self {
+        let by_ch: FoxHashMap<DiskCh, &TrackInterleave> = tracks.iter().map(|t| (t.ch, t)).collect();
+
+        let skew = |from: &TrackInterleave, to: DiskCh| -> Option<TrackSkew> {
+            let to_track = by_ch.get(&to)?;
+            // Skew is only meaningful between tracks with the same number of sectors.
+            let n = from.order.len();
+            (to_track.order.len() == n).then(|| TrackSkew {
+                from: from.ch,
+                to,
+                sectors: (to_track.first_position + n - from.first_position) % n,
+            })
+        };
+
+        let mut head_skews = Vec::new();
+        let mut cylinder_skews = Vec::new();
+        for track in &tracks {
+            if track.ch.h() == 0 {
+                head_skews.extend(skew(track, DiskCh::new(track.ch.c(), 1)));
+            }
+            cylinder_skews.extend(skew(track, DiskCh::new(track.ch.c() + 1, track.ch.h())));
+        }
+
+        SkewMap {
+            tracks,
+            head_skews,
+            cylinder_skews,
+        }
+    }
+
+    /// Return the most common interleave factor of all tracks, or `None` if no track has an
+    /// interleave.
+    pub fn interleave(&self) -> Option<usize> {
+        most_common(self.tracks.iter().map(|t| t.factor))
+    }
+
+    /// Return the most common head skew, or `None` if no cylinder has two comparable heads.
+    pub fn head_skew(&self) -> Option<usize> {
+        most_common(self.head_skews.iter().map(|s| s.sectors))
+    }
+
+    /// Return the most common cylinder skew, or `None` if no two adjacent cylinders are
+    /// comparable.
+    pub fn cylinder_skew(&self) -> Option<usize> {
+        most_common(self.cylinder_skews.iter().map(|s| s.sectors))
+    }
+}
+
+/// Return the most common value, preferring the lowest value on a tie.
+fn most_common(values: impl Iterator<Item = usize>) -> Option<usize> {
+    let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+    for value in values {
+        *counts.entry(value).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+        .map(|(value, _)| value)
+}
+
+/// Return the physical order of `sector_ct` sectors numbered from `first_id`, laid out with an
+/// interleave of `factor` and with the first sector at physical position `first_position`. When
+/// a position is already taken, the next free position is used, as formatting tools do.
+pub fn interleaved_order(first_id: u8, sector_ct: usize, factor: usize, first_position: usize) -> Vec<u8> {
+    let mut order: Vec<Option<u8>> = vec![None; sector_ct];
+    let mut position = first_position;
+    for i in 0..sector_ct {
+        while order[position % sector_ct].is_some() {
+            position += 1;
+        }
+        order[position % sector_ct] = Some(first_id.wrapping_add(i as u8));
+        position += factor.max(1);
+    }
+    order.into_iter().flatten().collect()
+}
+
+/// Determine the interleave of a track from its sector list, in physical order. Returns `None` if
+/// the track has fewer than two sectors, or no two sectors with consecutive IDs.
+pub(crate) fn measure_interleave(ch: DiskCh, sectors: &[SectorMapEntry]) -> Option<TrackInterleave> {
+    let order: Vec<u8> = sectors
+        .iter()
+        .filter(|s| !s.orphan && s.duplicate_idx == 0)
+        .map(|s| s.chsn.s())
+        .collect();
+    let n = order.len();
+    if n < 2 {
+        return None;
+    }
+
+    let positions: BTreeMap<u8, usize> = order.iter().enumerate().map(|(pos, &id)| (id, pos)).collect();
+    let distances = positions.iter().filter_map(|(&id, &pos)| {
+        let next = positions.get(&id.checked_add(1)?)?;
+        Some((next + n - pos) % n)
+    });
+    let factor = most_common(distances)?;
+
+    let (&first_id, &first_position) = positions.iter().next()?;
+    let consistent = interleaved_order(first_id, n, factor, first_position) == order;
+
+    Some(TrackInterleave {
+        ch,
+        factor,
+        consistent,
+        first_position,
+        order,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prelude::*,
+        test_util::{add_sector, add_track, empty_disk},
+    };
+
+    #[test]
+    fn test_interleaved_order() {
+        assert_eq!(interleaved_order(1, 9, 1, 0), vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(interleaved_order(1, 9, 2, 0), vec![1, 6, 2, 7, 3, 8, 4, 9, 5]);
+        assert_eq!(interleaved_order(1, 8, 2, 0), vec![1, 5, 2, 6, 3, 7, 4, 8]);
+        assert_eq!(interleaved_order(1, 9, 1, 3), vec![7, 8, 9, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_skew_map() {
+        let mut disk = empty_disk();
+
+        // Format with a 3:1 interleave, a head skew of 2 and a cylinder skew of 4.
+        let layout = StandardFormat::PcFloppy360.layout();
+        for ch in layout.ch_iter() {
+            let skew = (ch.c() as usize * 4 + ch.h() as usize * 2) % 9;
+            let track = add_track(&mut disk, ch);
+            for s in interleaved_order(1, 9, 3, skew) {
+                add_sector(
+                    track,
+                    DiskChsn::new(ch.c(), ch.h(), s, 2),
+                    &[s; 512],
+                    Default::default(),
+                );
+            }
+        }
+
+        let interleave = disk.track(DiskCh::new(1, 1)).unwrap().interleave().unwrap();
+        assert_eq!(interleave.factor, 3);
+        assert!(interleave.consistent);
+        assert_eq!(interleave.first_position, 6);
+
+        let map = disk.skew_map();
+        assert_eq!(map.tracks.len(), 80);
+        assert_eq!(map.interleave(), Some(3));
+        assert_eq!(map.head_skew(), Some(2));
+        assert_eq!(map.cylinder_skew(), Some(4));
+        assert_eq!(map.head_skews.len(), 40);
+        assert_eq!(map.cylinder_skews.len(), 78);
+    }
+}
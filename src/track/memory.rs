@@ -0,0 +1,133 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+    src/track/memory.rs
+
+    Account for the memory used by the representations of a track's data.
+*/
+
+use crate::{track_schema::TrackMetadata, types::DiskCh};
+use bit_vec::BitVec;
+use std::{iter::Sum, ops::Add};
+
+/// The approximate number of bytes of heap memory used by each representation of a track's data.
+/// Returned by [Track::memory_usage].
+///
+/// The counts include only the buffers holding the track's data, not the fixed size of the track
+/// structures themselves, and so are a lower bound.
+///
+/// [Track::memory_usage]: crate::track::Track::memory_usage
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrackMemoryUsage {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    /// Flux transition timings, and the transitions and PLL markers and statistics decoded from
+    /// them.
+    pub flux: usize,
+    /// Bitcells, including the clock, error and weak bit maps of each bitstream. For `FluxStream`
+    /// resolution tracks, this includes the bitstream decoded from every revolution.
+    pub bitcells: usize,
+    /// Sector data, and the weak and hole masks of each sector. Only `MetaSector` resolution tracks
+    /// store sector data separately from their bitcells.
+    pub sectors: usize,
+    /// Track element metadata produced by scanning the track.
+    pub metadata: usize,
+}
+
+impl TrackMemoryUsage {
+    pub(crate) fn new(ch: DiskCh) -> Self {
+        TrackMemoryUsage {
+            ch,
+            ..Default::default()
+        }
+    }
+
+    /// Return the total number of bytes used by the track.
+    pub fn total(&self) -> usize {
+        self.flux + self.bitcells + self.sectors + self.metadata
+    }
+}
+
+impl Add for TrackMemoryUsage {
+    type Output = TrackMemoryUsage;
+
+    /// Add the byte counts of two tracks. The `ch` of the left-hand side is kept.
+    fn add(self, rhs: TrackMemoryUsage) -> TrackMemoryUsage {
+        TrackMemoryUsage {
+            ch: self.ch,
+            flux: self.flux + rhs.flux,
+            bitcells: self.bitcells + rhs.bitcells,
+            sectors: self.sectors + rhs.sectors,
+            metadata: self.metadata + rhs.metadata,
+        }
+    }
+}
+
+impl<'a> Sum<&'a TrackMemoryUsage> for TrackMemoryUsage {
+    fn sum<I: Iterator<Item = &'a TrackMemoryUsage>>(iter: I) -> Self {
+        iter.fold(TrackMemoryUsage::default(), |acc, usage| acc + *usage)
+    }
+}
+
+/// The approximate memory used by every track of a disk image. Returned by
+/// [DiskImage::memory_usage].
+///
+/// [DiskImage::memory_usage]: crate::DiskImage::memory_usage
+#[derive(Clone, Debug, Default)]
+pub struct MemoryUsage {
+    /// The memory used by each track, in track order.
+    pub tracks: Vec<TrackMemoryUsage>,
+}
+
+impl MemoryUsage {
+    /// Return the memory used by all tracks, by representation. The `ch` of the result is not
+    /// meaningful.
+    pub fn sum(&self) -> TrackMemoryUsage {
+        self.tracks.iter().sum()
+    }
+
+    /// Return the total number of bytes used by all tracks.
+    pub fn total(&self) -> usize {
+        self.sum().total()
+    }
+}
+
+/// Return the number of bytes used by the bits of a `BitVec`.
+pub(crate) fn bitvec_bytes(bits: &BitVec) -> usize {
+    bits.len().div_ceil(8)
+}
+
+/// Return the number of bytes used by the elements of a slice.
+pub(crate) fn slice_bytes<T>(items: &[T]) -> usize {
+    std::mem::size_of_val(items)
+}
+
+/// Return the number of bytes used by the element lists of track metadata.
+pub(crate) fn metadata_bytes(metadata: &TrackMetadata) -> usize {
+    slice_bytes(&metadata.items)
+        + slice_bytes(&metadata.sector_ids)
+        + slice_bytes(&metadata.valid_sector_ids)
+        + slice_bytes(&metadata.recoveries)
+}
@@ -29,33 +29,66 @@
     Implements the MetaSector track type and the Track trait for same.
 
 */
-use super::{Track, TrackAnalysis, TrackInfo};
+use super::{
+    memory::{metadata_bytes, TrackMemoryUsage},
+    RepairOptions,
+    RepairSummary,
+    Track,
+    TrackAnalysis,
+    TrackInfo,
+};
 
 use crate::types::{
     AddSectorParams,
+    IntegrityCheck,
+    IntegrityField,
+    ReadSectorRef,
     ReadSectorResult,
     ReadTrackResult,
     RwScope,
     ScanSectorResult,
     SectorAttributes,
     SharedDiskContext,
+    SizeMismatchPolicy,
+    StandardFormat,
     WriteSectorResult,
 };
 
-use crate::track_schema::{system34::System34Standard, TrackMetadata, TrackSchema};
+use crate::track_schema::{
+    system34::{System34Schema, System34Standard, DEFAULT_TRACK_SIZE_BYTES, IBM_GAP3_DEFAULT},
+    TrackMetadata,
+    TrackSchema,
+};
 
 use crate::{
-    bitstream_codec::TrackDataStream,
-    types::{chs::DiskChsnQuery, DiskCh, DiskChs, DiskChsn, TrackDataEncoding, TrackDataRate, TrackDataResolution},
+    bitstream_codec::{mfm::MFM_BYTE_LEN, TrackDataStream},
+    random::WeakBitGenerator,
+    source_map::SourceMap,
+    types::{
+        chs::DiskChsnQuery,
+        DiskCh,
+        DiskChs,
+        DiskChsn,
+        TrackDataEncoding,
+        TrackDataRate,
+        TrackDataResolution,
+        TrackDensity,
+        TrackFormatting,
+    },
+    util::crc_ibm_3740,
     DiskImageError,
     FoxHashSet,
     SectorMapEntry,
 };
+use bit_vec::BitVec;
 use sha1_smol::Digest;
 use std::{
     any::Any,
-    sync::{Arc, Mutex},
+    borrow::Cow,
+    ops::Range,
+    sync::{Arc, Mutex, OnceLock},
 };
+use strum::IntoEnumIterator;
 
 struct SectorMatch<'a> {
     pub(crate) sectors: Vec<&'a MetaSector>,
@@ -95,6 +128,26 @@ impl<'a> SectorMatchMut<'a> {
     }
 }
 
+/// The result of adding an alternate read of a sector with
+/// [MetaSectorTrack::add_alternate_sector].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlternateSectorSummary {
+    /// Whether the alternate was merged into an existing sector. If `false`, no sector with the
+    /// same ID existed and the alternate was added as a new sector.
+    pub merged: bool,
+    /// The number of bits of the alternate that differed from the existing sector data.
+    pub differing_bits: usize,
+    /// The number of bytes at the end of the existing sector data that the alternate did not
+    /// cover. These bytes are marked weak.
+    pub uncovered_bytes: usize,
+    /// The number of bytes of the alternate past the end of the existing sector data. These
+    /// bytes are discarded.
+    pub excess_bytes: usize,
+    /// The byte ranges of the sector data marked weak after the alternate was added.
+    pub weak_regions: Vec<Range<usize>>,
+}
+
 #[derive(Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct MetaMask {
@@ -120,15 +173,34 @@ impl MetaMask {
     }
     #[allow(dead_code)]
     fn or_mask(&mut self, source_mask: &MetaMask) {
-        for (i, &m) in source_mask.iter().enumerate() {
-            self.mask[i] |= m;
+        for (dst, &m) in self.mask.iter_mut().zip(source_mask.iter()) {
+            *dst |= m;
         }
         self.has_bits = self.mask.iter().any(|&x| x != 0);
     }
     fn or_slice(&mut self, source_mask: &[u8]) {
-        for (i, &m) in source_mask.iter().enumerate() {
-            self.mask[i] |= m;
+        for (dst, &m) in self.mask.iter_mut().zip(source_mask.iter()) {
+            *dst |= m;
+        }
+        self.has_bits = self.mask.iter().any(|&x| x != 0);
+    }
+    fn set_range(&mut self, range: Range<usize>, value: u8) {
+        self.mask[range].fill(value);
+        self.has_bits = self.mask.iter().any(|&x| x != 0);
+    }
+    /// Return the ranges of contiguous non-zero bytes in the mask.
+    fn ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (i, _) in self.mask.iter().enumerate().filter(|(_, &b)| b != 0) {
+            match ranges.last_mut() {
+                Some(last) if last.end == i => last.end = i + 1,
+                _ => ranges.push(i..i + 1),
+            }
         }
+        ranges
+    }
+    fn resize(&mut self, len: usize) {
+        self.mask.resize(len, 0);
         self.has_bits = self.mask.iter().any(|&x| x != 0);
     }
     #[allow(dead_code)]
@@ -143,6 +215,10 @@ impl MetaMask {
     fn has_bits(&self) -> bool {
         self.has_bits
     }
+    /// Return the mask byte at `index`, or 0 (unmasked) if the index is beyond the end of the mask.
+    fn get(&self, index: usize) -> u8 {
+        self.mask.get(index).copied().unwrap_or(0)
+    }
     fn iter(&self) -> std::slice::Iter<u8> {
         self.mask.iter()
     }
@@ -166,18 +242,21 @@ pub(crate) struct MetaSector {
 }
 
 impl MetaSector {
-    pub fn read_data(&self) -> Vec<u8> {
+    /// Read the sector data, resolving weak and hole bits with `weak_bits`. The caller signals
+    /// the start of the read with [WeakBitGenerator::begin_read].
+    pub fn read_data(&self, weak_bits: &mut WeakBitGenerator) -> Vec<u8> {
         if self.no_dam {
             return Vec::new();
         }
         let mut data = self.data.clone();
-        for (i, (weak_byte, hole_byte)) in self.weak_mask.iter().zip(self.hole_mask.iter()).enumerate() {
-            let mask_byte = weak_byte | hole_byte;
+        // Masks are fitted to the data length when the sector is added, but don't rely on it.
+        for (i, data_byte) in data.iter_mut().enumerate() {
+            let mask_byte = self.weak_mask.get(i) | self.hole_mask.get(i);
             if mask_byte == 0 {
                 continue;
             }
-            let rand_byte = rand::random::<u8>();
-            data[i] = data[i] & !mask_byte | rand_byte & mask_byte;
+            let weak_byte = weak_bits.next_byte();
+            *data_byte = *data_byte & !mask_byte | weak_byte & mask_byte;
         }
         data
     }
@@ -185,17 +264,50 @@ impl MetaSector {
 
 #[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "MetaSectorTrackFields"))]
 pub struct MetaSectorTrack {
     pub(crate) ch: DiskCh,
     pub(crate) encoding: TrackDataEncoding,
     pub(crate) schema: Option<TrackSchema>,
     pub(crate) data_rate: TrackDataRate,
     pub(crate) sectors: Vec<MetaSector>,
+    /// Metadata synthesized from the sectors, as if the track were formatted with the standard
+    /// layout. Built when first requested and discarded by [MetaSectorTrack::update_metadata]
+    /// whenever the sectors change, so a run of sector writes only rebuilds it once.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) metadata: OnceLock<TrackMetadata>,
 
     #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) shared: Arc<Mutex<SharedDiskContext>>,
 }
 
+/// The serialized fields of a [MetaSectorTrack]. A deserialized track is built from these so that
+/// it shares no state with the serialized track.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct MetaSectorTrackFields {
+    ch: DiskCh,
+    encoding: TrackDataEncoding,
+    schema: Option<TrackSchema>,
+    data_rate: TrackDataRate,
+    sectors: Vec<MetaSector>,
+}
+
+#[cfg(feature = "serde")]
+impl From<MetaSectorTrackFields> for MetaSectorTrack {
+    fn from(fields: MetaSectorTrackFields) -> Self {
+        MetaSectorTrack {
+            ch: fields.ch,
+            encoding: fields.encoding,
+            schema: fields.schema,
+            data_rate: fields.data_rate,
+            sectors: fields.sectors,
+            metadata: OnceLock::new(),
+            shared: Arc::default(),
+        }
+    }
+}
+
 #[cfg_attr(feature = "serde", typetag::serde)]
 impl Track for MetaSectorTrack {
     fn resolution(&self) -> TrackDataResolution {
@@ -244,7 +356,7 @@ impl Track for MetaSectorTrack {
     }
 
     fn metadata(&self) -> Option<&TrackMetadata> {
-        None
+        Some(self.synthesized_metadata())
     }
 
     fn sector_ct(&self) -> usize {
@@ -266,7 +378,8 @@ impl Track for MetaSectorTrack {
     }
 
     fn sector_list(&self) -> Vec<SectorMapEntry> {
-        self.sectors
+        let mut sector_list: Vec<SectorMapEntry> = self
+            .sectors
             .iter()
             .map(|s| SectorMapEntry {
                 chsn: s.id_chsn,
@@ -274,57 +387,75 @@ impl Track for MetaSectorTrack {
                     address_error: s.address_error,
                     data_error: s.data_error,
                     deleted_mark: s.deleted_mark,
-                    no_dam: false,
+                    no_dam: s.no_dam,
                 },
+                ..Default::default()
             })
-            .collect()
+            .collect();
+        SectorMapEntry::mark_duplicates(&mut sector_list);
+        sector_list
     }
 
     fn add_sector(&mut self, params: &AddSectorParams) -> Result<(), DiskImageError> {
-        // Create an empty weak bit mask if none is provided.
-        let weak_mask = match &params.weak_mask {
-            Some(weak_buf) => MetaMask::from(weak_buf),
-            None => MetaMask::empty(params.data.len()),
-        };
-
-        let hole_mask = match &params.hole_mask {
-            Some(hole_buf) => MetaMask::from(hole_buf),
-            None => MetaMask::empty(params.data.len()),
-        };
-
-        let new_sector = MetaSector {
-            id_chsn: params.id_chsn,
-            address_error: params.attributes.address_error,
-            data_error: params.attributes.data_error,
-            deleted_mark: params.attributes.deleted_mark,
-            no_dam: params.attributes.no_dam,
-            data: params.data.to_vec(),
-            weak_mask,
-            hole_mask,
-        };
-
         if params.alternate {
-            // Look for existing sector.
-            let existing_sector = self.sectors.iter_mut().find(|s| s.id_chsn == params.id_chsn);
+            return self.add_alternate_sector(params).map(|_| ());
+        }
 
-            if let Some(es) = existing_sector {
-                // Update the existing sector.
-                let mut xor_vec: Vec<u8> = Vec::with_capacity(es.data.len());
+        let new_sector = self.new_sector(params, true)?;
+        self.sectors.push(new_sector);
+        self.update_metadata();
 
-                // Calculate a bitmap representing the difference between the new sector data and the
-                // existing sector data.
-                for (i, (ns_byte, es_byte)) in new_sector.data.iter().zip(es.data.iter()).enumerate() {
-                    xor_vec[i] = ns_byte ^ es_byte;
-                }
+        Ok(())
+    }
 
-                // Update the weak bit mask for the existing sector and return.
-                es.weak_mask.or_slice(&xor_vec);
-                return Ok(());
+    fn remove_sector(&mut self, id: DiskChsnQuery) -> Result<DiskChsn, DiskImageError> {
+        match self.sectors.iter().position(|s| id.matches(&s.id_chsn)) {
+            Some(index) => {
+                let removed = self.sectors.remove(index);
+                self.update_metadata();
+                self.add_write(0);
+                Ok(removed.id_chsn)
             }
+            None => Err(DiskImageError::IdError),
         }
+    }
 
-        self.sectors.push(new_sector);
+    fn insert_sector_at(&mut self, index: usize, params: &AddSectorParams) -> Result<(), DiskImageError> {
+        if index > self.sectors.len() {
+            log::error!(
+                "insert_sector_at(): Index {} is past the end of track {} ({} sectors)",
+                index,
+                self.ch,
+                self.sectors.len()
+            );
+            return Err(DiskImageError::ParameterError);
+        }
+        let new_sector = self.new_sector(params, true)?;
+        self.sectors.insert(index, new_sector);
+        self.update_metadata();
+        self.add_write(0);
+        Ok(())
+    }
+
+    fn reorder_sectors(&mut self, order: &[usize]) -> Result<(), DiskImageError> {
+        let mut seen = vec![false; self.sectors.len()];
+        if order.len() != self.sectors.len()
+            || !order
+                .iter()
+                .all(|&i| i < seen.len() && !std::mem::replace(&mut seen[i], true))
+        {
+            log::error!(
+                "reorder_sectors(): Order is not a permutation of the sectors on track {}",
+                self.ch
+            );
+            return Err(DiskImageError::ParameterError);
+        }
 
+        let mut old_sectors: Vec<Option<MetaSector>> =
+            std::mem::take(&mut self.sectors).into_iter().map(Some).collect();
+        self.sectors = order.iter().filter_map(|&i| old_sectors[i].take()).collect();
+        self.update_metadata();
+        self.add_write(0);
         Ok(())
     }
 
@@ -337,15 +468,13 @@ impl Track for MetaSectorTrack {
     fn read_sector(
         &self,
         id: DiskChsnQuery,
-        _n: Option<u8>,
+        n: Option<u8>,
         _offset: Option<usize>,
         scope: RwScope,
         debug: bool,
     ) -> Result<ReadSectorResult, DiskImageError> {
         match scope {
-            // Add 4 bytes for address mark and 2 bytes for CRC.
-            RwScope::EntireElement => unimplemented!("DataElement scope not supported for MetaSector"),
-            RwScope::DataOnly => {}
+            RwScope::EntireElement | RwScope::DataOnly => {}
             _ => return Err(DiskImageError::ParameterError),
         };
 
@@ -370,24 +499,62 @@ impl Track for MetaSectorTrack {
                     sm.sizes.len()
                 );
             }
-            let s = sm.sectors[0];
+            self.read_matched_sector(sm.sectors[0], &sm, n, scope)
+        }
+    }
 
-            // TODO: MetaSector doesn't have stored CRC, but we can calculate the read CRC
-            Ok(ReadSectorResult {
-                id_chsn: Some(s.id_chsn),
-                data_range: 0..s.data.len(),
-                read_buf: s.read_data(), // Calling read_data applies the weak bit and hole masks.
-                deleted_mark: s.deleted_mark,
-                not_found: false,
-                no_dam: false,
-                address_crc_error: s.address_error,
-                data_crc_error: s.data_error,
-                wrong_cylinder: sm.wrong_cylinder,
-                bad_cylinder: sm.bad_cylinder,
-                wrong_head: sm.wrong_head,
-                ..ReadSectorResult::default()
-            })
+    fn read_sector_at(&self, index: usize, scope: RwScope) -> Result<ReadSectorResult, DiskImageError> {
+        match scope {
+            RwScope::EntireElement | RwScope::DataOnly => {}
+            _ => return Err(DiskImageError::ParameterError),
+        };
+
+        let s = self.sectors.get(index).ok_or(DiskImageError::ParameterError)?;
+        let sm = SectorMatch {
+            sectors: vec![s],
+            sizes: vec![s.id_chsn.n()],
+            wrong_cylinder: false,
+            bad_cylinder: s.id_chsn.c() == 0xFF,
+            wrong_head: false,
+        };
+        self.read_matched_sector(s, &sm, None, scope)
+    }
+
+    fn read_sector_ref(&self, id: DiskChsnQuery, n: Option<u8>) -> Result<ReadSectorRef<'_>, DiskImageError> {
+        let sm = self.match_sectors(id, false);
+        let Some(&s) = sm.sectors.first()
+        else {
+            return Ok(self.read_sector(id, n, None, RwScope::DataOnly, false)?.into());
+        };
+        // Sector data can only be borrowed if it is read unmodified.
+        let size_mismatch = n.is_some_and(|n| n != s.id_chsn.n());
+        if size_mismatch || s.no_dam || s.weak_mask.has_bits() || s.hole_mask.has_bits() {
+            return Ok(self.read_sector(id, n, None, RwScope::DataOnly, false)?.into());
         }
+
+        if sm.len() > 1 {
+            log::warn!(
+                "read_sector_ref(): Found {} sector ids matching id query: {} (with {} different sizes). Using first.",
+                sm.len(),
+                id,
+                sm.sizes.len()
+            );
+        }
+        // Every read advances the weak bit generator, as read_sector() does.
+        self.begin_read(s);
+        Ok(ReadSectorRef {
+            id_chsn: Some(s.id_chsn),
+            not_found: false,
+            no_dam: false,
+            deleted_mark: s.deleted_mark,
+            address_crc_error: s.address_error,
+            data_crc_error: s.data_error,
+            wrong_cylinder: sm.wrong_cylinder,
+            bad_cylinder: sm.bad_cylinder,
+            wrong_head: sm.wrong_head,
+            hole: false,
+            data: Cow::Borrowed(&s.data),
+        })
     }
 
     fn scan_sector(&self, id: DiskChsnQuery, _offset: Option<usize>) -> Result<ScanSectorResult, DiskImageError> {
@@ -418,7 +585,7 @@ impl Track for MetaSectorTrack {
             Ok(ScanSectorResult {
                 deleted_mark: s.deleted_mark,
                 not_found: false,
-                no_dam: false,
+                no_dam: s.no_dam,
                 address_error: s.address_error,
                 data_error: s.data_error,
                 wrong_cylinder: sm.wrong_cylinder,
@@ -450,7 +617,7 @@ impl Track for MetaSectorTrack {
         else if sm.len() == 0 {
             log::debug!("write_sector(): No sector found for id query: {}", id);
             return Ok(WriteSectorResult {
-                not_found: false,
+                not_found: true,
                 no_dam: false,
                 address_crc_error: false,
                 wrong_cylinder: sm.wrong_cylinder,
@@ -483,14 +650,17 @@ impl Track for MetaSectorTrack {
 
         sm.shared.lock().unwrap().writes += 1;
 
-        Ok(WriteSectorResult {
+        let result = WriteSectorResult {
             not_found: false,
             no_dam: sm.sectors[0].no_dam,
             address_crc_error: sm.sectors[0].address_error,
             wrong_cylinder: sm.wrong_cylinder,
             bad_cylinder: sm.bad_cylinder,
             wrong_head: sm.wrong_head,
-        })
+        };
+        // Writing may have changed the sector's data mark.
+        self.update_metadata();
+        Ok(result)
     }
 
     fn recalculate_sector_crc(&mut self, id: DiskChsnQuery, offset: Option<usize>) -> Result<(), DiskImageError> {
@@ -503,7 +673,63 @@ impl Track for MetaSectorTrack {
         Ok(())
     }
 
-    fn hash(&mut self) -> Digest {
+    fn repair(&mut self, options: &RepairOptions) -> Result<RepairSummary, DiskImageError> {
+        let mut summary = RepairSummary::default();
+
+        if options.fix_data_crcs {
+            // MetaSector tracks store no CRC, so fixing a CRC is simply clearing the error flag.
+            for sector in self.sectors.iter_mut().filter(|s| s.data_error && !s.no_dam) {
+                sector.data_error = false;
+                summary.fixed_crcs.push(sector.id_chsn);
+            }
+        }
+
+        if options.renumber_sectors {
+            if let Some(first_id) = self.sectors.iter().map(|s| s.id_chsn.s()).min() {
+                for (i, sector) in self.sectors.iter_mut().enumerate() {
+                    let new_id = first_id.wrapping_add(i as u8);
+                    if sector.id_chsn.s() != new_id {
+                        let old_chsn = sector.id_chsn;
+                        sector.id_chsn.set_s(new_id);
+                        summary.renumbered.push((old_chsn, sector.id_chsn));
+                    }
+                }
+            }
+        }
+
+        if options.normalize_sector_sizes {
+            // Find the most common sector size on the track. Ties resolve to the smaller size.
+            let mut size_counts = [0usize; 256];
+            for sector in &self.sectors {
+                size_counts[sector.id_chsn.n() as usize] += 1;
+            }
+            let common_n = (0..=255u8)
+                .max_by_key(|&n| (size_counts[n as usize], u8::MAX - n))
+                .unwrap_or(0);
+            let common_size = DiskChsn::n_to_bytes(common_n);
+
+            for sector in self.sectors.iter_mut() {
+                if sector.id_chsn.n() != common_n || (!sector.no_dam && sector.data.len() != common_size) {
+                    let old_chsn = sector.id_chsn;
+                    sector.id_chsn.set_n(common_n);
+                    if !sector.no_dam {
+                        sector.data.resize(common_size, 0);
+                        sector.weak_mask.resize(common_size);
+                        sector.hole_mask.resize(common_size);
+                    }
+                    summary.resized.push((old_chsn, sector.id_chsn));
+                }
+            }
+        }
+
+        if !summary.is_empty() {
+            self.update_metadata();
+            self.add_write(0);
+        }
+        Ok(summary)
+    }
+
+    fn hash(&self) -> Digest {
         let mut hasher = sha1_smol::Sha1::new();
         let rtr = self.read_all_sectors(self.ch, 0xFF, 0xFF).unwrap();
         hasher.update(&rtr.read_buf);
@@ -516,7 +742,7 @@ impl Track for MetaSectorTrack {
     /// Unlike read_sectors, the data returned is only the actual sector data. The address marks and
     /// CRCs are not included in the data.
     /// This function is intended for use in implementing the Read Track FDC command.
-    fn read_all_sectors(&mut self, _ch: DiskCh, n: u8, track_len: u8) -> Result<ReadTrackResult, DiskImageError> {
+    fn read_all_sectors(&self, _ch: DiskCh, n: u8, track_len: u8) -> Result<ReadTrackResult, DiskImageError> {
         let track_len = track_len as u16;
         let sector_data_len = DiskChsn::n_to_bytes(n);
         let mut track_read_vec = Vec::with_capacity(sector_data_len * self.sectors.len());
@@ -527,7 +753,7 @@ impl Track for MetaSectorTrack {
         let mut not_found = true;
         let mut sectors_read = 0;
 
-        for s in &self.sectors {
+        for (index, s) in self.sectors.iter().enumerate() {
             log::trace!("read_all_sectors(): Found sector_id: {}", s.id_chsn,);
             not_found = false;
 
@@ -544,7 +770,12 @@ impl Track for MetaSectorTrack {
                 break;
             }
 
-            track_read_vec.extend(&s.read_data());
+            // The whole track is read in a single revolution.
+            {
+                let weak_bits = &mut self.shared.lock().unwrap().weak_bits;
+                weak_bits.begin_read(self.ch, index);
+                track_read_vec.extend(&s.read_data(weak_bits));
+            }
             sectors_read = sectors_read.saturating_add(1);
 
             if s.address_error {
@@ -607,18 +838,109 @@ impl Track for MetaSectorTrack {
         Err(DiskImageError::UnsupportedFormat)
     }
 
+    fn read_raw_bits(&self, _range: Range<usize>) -> Result<BitVec, DiskImageError> {
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
+    fn write_raw_bits(&mut self, _offset: usize, _bits: &BitVec) -> Result<(), DiskImageError> {
+        Err(DiskImageError::UnsupportedFormat)
+    }
+
     fn has_weak_bits(&self) -> bool {
         self.sectors.iter().any(|s| s.weak_mask.has_bits())
     }
 
+    fn formatting(&self) -> TrackFormatting {
+        // Sector-based images have no way to represent noise on an unformatted track.
+        if self.sectors.is_empty() {
+            TrackFormatting::Unformatted { noise: false }
+        }
+        else {
+            TrackFormatting::Formatted
+        }
+    }
+
+    fn set_hole_region(&mut self, id: DiskChsnQuery, range: Range<usize>) -> Result<(), DiskImageError> {
+        let sector = self.first_sector_mut(id)?;
+        if range.start > range.end || range.end > sector.data.len() {
+            log::error!(
+                "set_hole_region(): Range {:?} exceeds sector data length {}",
+                range,
+                sector.data.len()
+            );
+            return Err(DiskImageError::ParameterError);
+        }
+        sector.hole_mask.set_range(range, 0xFF);
+        self.add_write(0);
+        Ok(())
+    }
+
+    fn clear_hole_region(&mut self, id: DiskChsnQuery, range: Option<Range<usize>>) -> Result<(), DiskImageError> {
+        let sector = self.first_sector_mut(id)?;
+        let range = range.unwrap_or(0..sector.data.len());
+        if range.start > range.end || range.end > sector.data.len() {
+            log::error!(
+                "clear_hole_region(): Range {:?} exceeds sector data length {}",
+                range,
+                sector.data.len()
+            );
+            return Err(DiskImageError::ParameterError);
+        }
+        sector.hole_mask.set_range(range, 0);
+        self.add_write(0);
+        Ok(())
+    }
+
+    fn hole_regions(&self, id: DiskChsnQuery) -> Result<Vec<Range<usize>>, DiskImageError> {
+        let sm = self.match_sectors(id, false);
+        match sm.sectors.first() {
+            Some(sector) => Ok(sector.hole_mask.ranges()),
+            None => Err(DiskImageError::IdError),
+        }
+    }
+
     fn format(
         &mut self,
         _standard: System34Standard,
-        _format_buffer: Vec<DiskChsn>,
-        _fill_pattern: &[u8],
+        format_buffer: Vec<DiskChsn>,
+        fill_pattern: &[u8],
         _gap3: usize,
     ) -> Result<(), DiskImageError> {
-        // TODO: Implement format for MetaSectorTrack
+        if fill_pattern.is_empty() {
+            log::error!("format(): Fill pattern cannot be empty.");
+            return Err(DiskImageError::ParameterError);
+        }
+
+        // A MetaSector track has no gaps or address marks to lay out, so formatting just replaces
+        // the sectors. Keep the old sectors to restore if any new sector is rejected.
+        let old_sectors = std::mem::take(&mut self.sectors);
+        let mut pat_cursor = 0;
+
+        for id_chsn in format_buffer {
+            // Continue the fill pattern across sectors, as when formatting a bitstream track.
+            let data: Vec<u8> = (0..id_chsn.n_size())
+                .map(|i| fill_pattern[(pat_cursor + i) % fill_pattern.len()])
+                .collect();
+            pat_cursor = (pat_cursor + data.len()) % fill_pattern.len();
+
+            if let Err(e) = self.add_sector(&AddSectorParams {
+                id_chsn,
+                data: &data,
+                ..Default::default()
+            }) {
+                log::error!("format(): Failed to format sector {}: {}", id_chsn, e);
+                self.sectors = old_sectors;
+                self.update_metadata();
+                return Err(e);
+            }
+        }
+        // An empty format buffer adds no sectors, so the metadata of the old sectors must be
+        // cleared here.
+        self.update_metadata();
+        Ok(())
+    }
+
+    fn write_track(&mut self, _data: &[u8]) -> Result<(), DiskImageError> {
         Err(DiskImageError::UnsupportedFormat)
     }
 
@@ -663,6 +985,22 @@ impl Track for MetaSectorTrack {
     fn stream_mut(&mut self) -> Option<&mut TrackDataStream> {
         None
     }
+
+    fn element_map(&self) -> Option<&SourceMap> {
+        Some(&self.synthesized_metadata().element_map)
+    }
+
+    fn memory_usage(&self) -> TrackMemoryUsage {
+        TrackMemoryUsage {
+            sectors: self
+                .sectors
+                .iter()
+                .map(|s| s.data.len() + s.weak_mask.mask.len() + s.hole_mask.mask.len())
+                .sum(),
+            metadata: self.metadata.get().map_or(0, metadata_bytes),
+            ..TrackMemoryUsage::new(self.ch)
+        }
+    }
 }
 
 impl MetaSectorTrack {
@@ -671,6 +1009,398 @@ impl MetaSectorTrack {
         self.shared.lock().unwrap().writes += 1;
     }
 
+    /// Discard the track's metadata after its sectors have changed. It is synthesized again from
+    /// the sectors the next time it is requested.
+    pub(crate) fn update_metadata(&mut self) {
+        self.metadata = OnceLock::new();
+    }
+
+    /// Return the track's metadata, synthesizing it if the sectors have changed. A MetaSector
+    /// track records no gaps or address marks, so element positions are synthesized from the
+    /// standard layout, using the GAP3 of the [StandardFormat] matching the track's sector count
+    /// and size, if any.
+    fn synthesized_metadata(&self) -> &TrackMetadata {
+        self.metadata.get_or_init(|| {
+            let sectors = self.sector_list();
+            let gap3 = sectors
+                .first()
+                .and_then(|first| {
+                    StandardFormat::iter().find(|format| {
+                        let layout = format.layout();
+                        layout.s() as usize == sectors.len() && layout.n() == first.chsn.n()
+                    })
+                })
+                .map(|format| format.gap3())
+                .unwrap_or(IBM_GAP3_DEFAULT);
+            let bitcell_ct = TrackDensity::from(self.data_rate)
+                .bitcells(None)
+                .unwrap_or(DEFAULT_TRACK_SIZE_BYTES * MFM_BYTE_LEN);
+
+            let elements =
+                System34Schema::synthesize_elements(System34Standard::Ibm, self.encoding, &sectors, gap3, bitcell_ct);
+            TrackMetadata::new(elements, TrackSchema::System34)
+        })
+    }
+
+    /// Read the sector `s`, found by the sector match `sm`, with an optional override size `n`.
+    fn read_matched_sector(
+        &self,
+        s: &MetaSector,
+        sm: &SectorMatch,
+        n: Option<u8>,
+        scope: RwScope,
+    ) -> Result<ReadSectorResult, DiskImageError> {
+        // If the requested N differs from the sector's, the size mismatch policy determines
+        // how much sector data to read.
+        let policy = self.shared.lock().unwrap().size_mismatch;
+        let read_len = match (n.filter(|&n| n != s.id_chsn.n()).map(DiskChsn::n_to_bytes), policy) {
+            (Some(_), SizeMismatchPolicy::NotFound) => {
+                log::debug!(
+                    "read_sector(): Requested N does not match sector {}, reporting not found.",
+                    s.id_chsn
+                );
+                return Ok(ReadSectorResult::default());
+            }
+            (Some(len), SizeMismatchPolicy::Truncate) => Some(len.min(s.data.len())),
+            (Some(len), SizeMismatchPolicy::Controller) => Some(len),
+            _ => None,
+        };
+
+        if matches!(scope, RwScope::EntireElement) {
+            return Ok(self.read_sector_element(s, sm, read_len));
+        }
+
+        // A sector without a data address mark has no data to read.
+        if s.no_dam {
+            return Ok(ReadSectorResult {
+                id_chsn: Some(s.id_chsn),
+                not_found: false,
+                no_dam: true,
+                address_crc_error: s.address_error,
+                wrong_cylinder: sm.wrong_cylinder,
+                bad_cylinder: sm.bad_cylinder,
+                wrong_head: sm.wrong_head,
+                ..ReadSectorResult::default()
+            });
+        }
+
+        let read_buf = self.read_sized_data(s, read_len);
+        Ok(ReadSectorResult {
+            id_chsn: Some(s.id_chsn),
+            data_range: 0..read_buf.len(),
+            read_buf,
+            deleted_mark: s.deleted_mark,
+            not_found: false,
+            no_dam: false,
+            address_crc_error: s.address_error,
+            data_crc_error: s.data_error || read_len.is_some(),
+            wrong_cylinder: sm.wrong_cylinder,
+            bad_cylinder: sm.bad_cylinder,
+            wrong_head: sm.wrong_head,
+            hole: s.hole_mask.has_bits(),
+            ..ReadSectorResult::default()
+        })
+    }
+
+    /// Read a sector as a complete data element, as it would be read from a bitstream track with
+    /// [RwScope::EntireElement]. A MetaSector track stores no address marks or CRCs, so the data
+    /// address mark is synthesized from the sector's deleted flag, and the ID and data CRCs are
+    /// calculated from the stored sector metadata. Sectors marked with a CRC error get a recorded
+    /// CRC that does not match the calculated one.
+    ///
+    /// If `read_len` is specified, that many bytes of sector data are read as described by
+    /// [MetaSectorTrack::read_sized_data], and the data CRC is reported as bad.
+    fn read_sector_element(&self, s: &MetaSector, sm: &SectorMatch, read_len: Option<usize>) -> ReadSectorResult {
+        let data_error = s.data_error || read_len.is_some();
+        let mut result = ReadSectorResult {
+            id_chsn: Some(s.id_chsn),
+            not_found: false,
+            deleted_mark: s.deleted_mark,
+            address_crc_error: s.address_error,
+            data_crc_error: data_error,
+            wrong_cylinder: sm.wrong_cylinder,
+            bad_cylinder: sm.bad_cylinder,
+            wrong_head: sm.wrong_head,
+            hole: s.hole_mask.has_bits(),
+            ..ReadSectorResult::default()
+        };
+
+        let sync = self.sync_bytes();
+        let element_crc = |element: &[u8], error: bool| {
+            let calculated = crc_ibm_3740(element, None);
+            let recorded = if error { !calculated } else { calculated };
+            (
+                recorded,
+                IntegrityCheck::Crc16(IntegrityField::new(recorded, calculated)),
+            )
+        };
+
+        let mut header = sync.to_vec();
+        header.extend_from_slice(&[0xFE, s.id_chsn.c() as u8, s.id_chsn.h(), s.id_chsn.s(), s.id_chsn.n()]);
+        result.address_crc = Some(element_crc(&header, s.address_error).1);
+
+        if s.no_dam {
+            result.no_dam = true;
+            return result;
+        }
+
+        let mut element = self.data_mark(s);
+        element.extend_from_slice(&self.read_sized_data(s, read_len));
+        let (recorded, data_crc) = element_crc(&element, data_error);
+        element.extend_from_slice(&recorded.to_be_bytes());
+
+        result.data_range = 0..element.len();
+        result.data_crc = Some(data_crc);
+        result.read_buf = element;
+        result
+    }
+
+    /// FM address marks are a single byte with a missing clock, MFM marks are preceded by
+    /// three A1 sync bytes which are included in the CRC.
+    fn sync_bytes(&self) -> &'static [u8] {
+        match self.encoding {
+            TrackDataEncoding::Mfm => &[0xA1; 3],
+            _ => &[],
+        }
+    }
+
+    /// Return the data address mark of a sector, preceded by any sync bytes.
+    fn data_mark(&self, s: &MetaSector) -> Vec<u8> {
+        let mut mark = self.sync_bytes().to_vec();
+        mark.push(if s.deleted_mark { 0xF8 } else { 0xFB });
+        mark
+    }
+
+    /// Signal the weak bit generator that `s`, one of the track's sectors, is about to be read.
+    fn begin_read(&self, s: &MetaSector) {
+        let index = self
+            .sectors
+            .iter()
+            .position(|sector| std::ptr::eq(sector, s))
+            .unwrap_or_default();
+        self.shared.lock().unwrap().weak_bits.begin_read(self.ch, index);
+    }
+
+    /// Read the data of a sector, applying its weak bit and hole masks. If `read_len` is
+    /// specified, the data is truncated or extended to that length. As a floppy disk controller
+    /// would, reads past the end of the sector data continue into the data CRC and then the gap
+    /// following the sector.
+    fn read_sized_data(&self, s: &MetaSector, read_len: Option<usize>) -> Vec<u8> {
+        self.begin_read(s);
+        let mut data = s.read_data(&mut self.shared.lock().unwrap().weak_bits);
+        let Some(read_len) = read_len
+        else {
+            return data;
+        };
+
+        if read_len > data.len() {
+            let mut element = self.data_mark(s);
+            element.extend_from_slice(&data);
+            let calculated = crc_ibm_3740(&element, None);
+            let recorded = if s.data_error { !calculated } else { calculated };
+            let gap_byte = match self.encoding {
+                TrackDataEncoding::Mfm => 0x4E,
+                _ => 0xFF,
+            };
+            data.extend_from_slice(&recorded.to_be_bytes());
+            data.resize(read_len, gap_byte);
+        }
+        data.truncate(read_len);
+        data
+    }
+
+    /// Validate `params` and build a new [MetaSector] from them. If `check_count` is set, reject
+    /// the sector if the track is already at the maximum sector count.
+    fn new_sector(&self, params: &AddSectorParams, check_count: bool) -> Result<MetaSector, DiskImageError> {
+        let limits = self.shared.lock().unwrap().sector_limits;
+        if let Err(e) = params.validate(&limits) {
+            log::error!("new_sector(): Rejecting sector {}: {}", params.id_chsn, e);
+            return Err(e);
+        }
+        if check_count && self.sectors.len() >= limits.max_sectors {
+            log::error!("new_sector(): Track {} exceeds {} sectors", self.ch, limits.max_sectors);
+            return Err(DiskImageError::SectorCountError(limits.max_sectors));
+        }
+
+        // Create an empty weak bit mask if none is provided.
+        let weak_mask = match &params.weak_mask {
+            Some(weak_buf) => MetaMask::from(&params.fit_mask(weak_buf)?),
+            None => MetaMask::empty(params.data.len()),
+        };
+
+        let hole_mask = match &params.hole_mask {
+            Some(hole_buf) => MetaMask::from(&params.fit_mask(hole_buf)?),
+            None => MetaMask::empty(params.data.len()),
+        };
+
+        Ok(MetaSector {
+            id_chsn: params.id_chsn,
+            address_error: params.attributes.address_error,
+            data_error: params.attributes.data_error,
+            deleted_mark: params.attributes.deleted_mark,
+            no_dam: params.attributes.no_dam,
+            data: params.data.to_vec(),
+            weak_mask,
+            hole_mask,
+        })
+    }
+
+    /// Add an alternate read of a sector. If a sector with the same ID exists, the bits that
+    /// differ between the alternate and the existing sector data, and any weak bits of the
+    /// alternate, are marked weak in the existing sector; the existing data, length and attributes
+    /// are kept. If the alternate is shorter than the existing sector, the bytes it does not cover
+    /// are marked weak. Otherwise, the alternate is added as a new sector.
+    ///
+    /// Repeated alternates accumulate, so adding every available read of a sector builds up a
+    /// weak bit mask covering every bit that was not read consistently.
+    /// # Returns
+    /// An [AlternateSectorSummary] describing the sector's weak bits after the alternate was added.
+    pub fn add_alternate_sector(&mut self, params: &AddSectorParams) -> Result<AlternateSectorSummary, DiskImageError> {
+        let Some(index) = self.sectors.iter().position(|s| s.id_chsn == params.id_chsn)
+        else {
+            let new_sector = self.new_sector(params, true)?;
+            let weak_regions = new_sector.weak_mask.ranges();
+            self.sectors.push(new_sector);
+            self.update_metadata();
+            return Ok(AlternateSectorSummary {
+                merged: false,
+                weak_regions,
+                ..Default::default()
+            });
+        };
+
+        let alternate = self.new_sector(params, false)?;
+        let existing = &mut self.sectors[index];
+        let common_len = existing.data.len().min(alternate.data.len());
+
+        let mut diff_mask = vec![0xFF; existing.data.len()];
+        let mut differing_bits = 0;
+        for (i, mask_byte) in diff_mask.iter_mut().enumerate().take(common_len) {
+            let diff = existing.data[i] ^ alternate.data[i];
+            differing_bits += diff.count_ones() as usize;
+            *mask_byte = diff | alternate.weak_mask.get(i);
+        }
+        // The mask may not span the sector data if it was deserialized or built by an older
+        // version; size it to the data so the whole diff mask applies.
+        existing.weak_mask.resize(existing.data.len());
+        existing.weak_mask.or_slice(&diff_mask);
+
+        Ok(AlternateSectorSummary {
+            merged: true,
+            differing_bits,
+            uncovered_bytes: existing.data.len() - common_len,
+            excess_bytes: alternate.data.len() - common_len,
+            weak_regions: existing.weak_mask.ranges(),
+        })
+    }
+
+    /// Return the unmasked data and weak bit mask of the sector at physical `index` on the track.
+    /// Unlike `read_sector`, weak bits are not randomized, making this suitable for comparisons.
+    pub(crate) fn raw_sector_data_at(&self, index: usize) -> Option<(&[u8], &[u8])> {
+        self.sectors.get(index).map(|s| (s.data.as_slice(), s.weak_mask.mask()))
+    }
+
+    /// Return the unmasked data and weak bit mask of the first sector matching `id`.
+    /// Unlike `read_sector`, weak bits are not randomized, making this suitable for comparisons.
+    pub(crate) fn raw_sector_data(&self, id: DiskChsn) -> Option<(&[u8], &[u8])> {
+        self.sectors
+            .iter()
+            .find(|s| s.id_chsn == id)
+            .map(|s| (s.data.as_slice(), s.weak_mask.mask()))
+    }
+
+    /// Return the hole mask of the data of the sector at `index`. Set bits lie within a hole.
+    pub(crate) fn hole_mask_at(&self, index: usize) -> Option<&[u8]> {
+        self.sectors.get(index).map(|s| s.hole_mask.mask())
+    }
+
+    /// Return the combined weak and hole mask of the data of the sector at `index`. Set bits read
+    /// back as random data.
+    pub(crate) fn read_mask_at(&self, index: usize) -> Option<Vec<u8>> {
+        self.sectors.get(index).map(|s| {
+            (0..s.data.len())
+                .map(|i| s.weak_mask.get(i) | s.hole_mask.get(i))
+                .collect()
+        })
+    }
+
+    /// Return the length of the stored data of the sector at `index`.
+    pub(crate) fn data_len_at(&self, index: usize) -> Option<usize> {
+        self.sectors.get(index).map(|s| s.data.len())
+    }
+
+    /// Return the byte ranges of the data of the sector at `index` that are marked weak.
+    pub(crate) fn weak_regions_at(&self, index: usize) -> Vec<Range<usize>> {
+        self.sectors
+            .get(index)
+            .map(|s| s.weak_mask.ranges())
+            .unwrap_or_default()
+    }
+
+    /// Mark the bytes within `range` of the data of the sector at `index` as weak. The range is
+    /// clipped to the sector data.
+    pub(crate) fn set_weak_region_at(&mut self, index: usize, range: Range<usize>) -> Result<(), DiskImageError> {
+        let sector = self.sector_at_mut(index)?;
+        let len = sector.data.len();
+        sector.weak_mask.resize(len);
+        sector
+            .weak_mask
+            .set_range(range.start.min(len)..range.end.min(len), 0xFF);
+        self.add_write(0);
+        Ok(())
+    }
+
+    /// Mark the bytes within `range` of the data of the sector at `index` as a hole, as if the
+    /// media had dropped out, and flag the sector data as failing its CRC. The range is clipped
+    /// to the sector data.
+    pub(crate) fn set_dropout_at(&mut self, index: usize, range: Range<usize>) -> Result<(), DiskImageError> {
+        let sector = self.sector_at_mut(index)?;
+        let len = sector.data.len();
+        sector.hole_mask.resize(len);
+        sector
+            .hole_mask
+            .set_range(range.start.min(len)..range.end.min(len), 0xFF);
+        sector.data_error = true;
+        self.update_metadata();
+        self.add_write(0);
+        Ok(())
+    }
+
+    /// XOR the data byte at `offset` of the sector at `index` with `xor`, and flag the sector data
+    /// as failing its CRC.
+    pub(crate) fn corrupt_data_at(&mut self, index: usize, offset: usize, xor: u8) -> Result<(), DiskImageError> {
+        let sector = self.sector_at_mut(index)?;
+        let Some(byte) = sector.data.get_mut(offset)
+        else {
+            log::error!("corrupt_data_at(): Offset {} exceeds sector data length", offset);
+            return Err(DiskImageError::ParameterError);
+        };
+        *byte ^= xor;
+        sector.data_error = true;
+        self.update_metadata();
+        self.add_write(0);
+        Ok(())
+    }
+
+    fn sector_at_mut(&mut self, index: usize) -> Result<&mut MetaSector, DiskImageError> {
+        let sector_ct = self.sectors.len();
+        self.sectors.get_mut(index).ok_or_else(|| {
+            log::error!(
+                "sector_at_mut(): Sector index {} out of range ({} sectors)",
+                index,
+                sector_ct
+            );
+            DiskImageError::ParameterError
+        })
+    }
+
+    fn first_sector_mut(&mut self, id: DiskChsnQuery) -> Result<&mut MetaSector, DiskImageError> {
+        self.sectors.iter_mut().find(|s| id.matches(&s.id_chsn)).ok_or_else(|| {
+            log::error!("first_sector_mut(): No sector found for id query: {}", id);
+            DiskImageError::IdError
+        })
+    }
+
     fn match_sectors(&self, id: DiskChsnQuery, _debug: bool) -> SectorMatch {
         let mut wrong_cylinder = false;
         let mut bad_cylinder = false;
@@ -738,3 +1468,758 @@ impl MetaSectorTrack {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        prelude::*,
+        test_util::{add_track, empty_disk, formatted_disk},
+        track::{DiskTrack, RepairOptions, Track},
+        track_schema::{
+            system34::{System34Element, System34Marker, System34Standard},
+            GenericTrackElement,
+            TrackElement,
+        },
+        types::{
+            AddSectorParams,
+            MaskLengthPolicy,
+            ReadSectorResult,
+            SectorAttributes,
+            SectorLimits,
+            SizeMismatchPolicy,
+            WeakBitPolicy,
+        },
+    };
+
+    fn test_disk() -> DiskImage {
+        let mut disk = empty_disk();
+        add_track(&mut disk, DiskCh::new(0, 0));
+        disk
+    }
+
+    fn params(n: u8, data: &[u8]) -> AddSectorParams<'_> {
+        AddSectorParams {
+            id_chsn: DiskChsn::new(0, 0, 1, n),
+            data,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let mut disk = test_disk();
+        let ch = DiskCh::new(0, 0);
+        let track = disk.track_mut(ch).unwrap();
+        track.add_sector(&params(2, &[0xAA; 512])).unwrap();
+
+        // Format with mixed sector sizes and a fill pattern that continues across sectors.
+        let format_buffer = vec![
+            DiskChsn::new(0, 0, 3, 1),
+            DiskChsn::new(0, 0, 2, 3),
+            DiskChsn::new(0, 0, 1, 2),
+        ];
+        track
+            .format(System34Standard::Ibm, format_buffer.clone(), &[0x01, 0x02, 0x03], 0x50)
+            .unwrap();
+
+        let ids: Vec<DiskChsn> = track.sector_list().iter().map(|s| s.chsn).collect();
+        assert_eq!(ids, format_buffer);
+        let rsr = track
+            .read_sector(DiskChsnQuery::new(0, 0, 2, 3), None, None, RwScope::DataOnly, false)
+            .unwrap();
+        assert_eq!(rsr.read_buf.len(), 1024);
+        // The first sector used 256 bytes of the pattern, leaving the cursor at 0x02.
+        assert_eq!(&rsr.read_buf[..4], &[0x02, 0x03, 0x01, 0x02]);
+
+        // A rejected sector leaves the track unchanged.
+        assert!(track
+            .format(System34Standard::Ibm, vec![DiskChsn::new(0, 0, 1, 8)], &[0xF6], 0x50)
+            .is_err());
+        assert_eq!(track.sector_list().len(), 3);
+        assert!(track.format(System34Standard::Ibm, Vec::new(), &[], 0x50).is_err());
+
+        // Formatting with no sectors leaves no sector elements behind.
+        assert_eq!(track.metadata().unwrap().sector_ct(), 3);
+        track.format(System34Standard::Ibm, Vec::new(), &[0xF6], 0x50).unwrap();
+        assert!(track.sector_list().is_empty());
+        assert_eq!(track.metadata().unwrap().sector_ct(), 0);
+    }
+
+    #[test]
+    fn test_read_entire_element() {
+        // Read the same sector from a formatted bitstream track for comparison.
+        let bitstream_disk = formatted_disk();
+        let bitstream_track = bitstream_disk.track(DiskCh::new(0, 0)).unwrap();
+        let id = DiskChsnQuery::new(0, 0, 1, 2);
+        let data = bitstream_track
+            .read_sector(id, None, None, RwScope::DataOnly, false)
+            .unwrap();
+        let element = bitstream_track
+            .read_sector(id, None, None, RwScope::EntireElement, false)
+            .unwrap();
+
+        let mut disk = test_disk();
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+        track.add_sector(&params(2, &data.read_buf[data.data_range])).unwrap();
+        track
+            .add_sector(&AddSectorParams {
+                id_chsn: DiskChsn::new(0, 0, 2, 2),
+                data: &[0x55; 512],
+                attributes: SectorAttributes {
+                    data_error: true,
+                    deleted_mark: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .unwrap();
+
+        // The synthesized element matches the bitstream element byte for byte.
+        let rsr = track
+            .read_sector(id, None, None, RwScope::EntireElement, false)
+            .unwrap();
+        assert_eq!(rsr.read_buf, element.read_buf);
+        assert_eq!(rsr.data_range, element.data_range);
+        assert!(rsr.data_crc.unwrap().is_valid());
+        assert!(rsr.address_crc.unwrap().is_valid());
+
+        // A sector with a data CRC error has a deleted mark and mismatched CRC.
+        let rsr = track
+            .read_sector(
+                DiskChsnQuery::new(0, 0, 2, 2),
+                None,
+                None,
+                RwScope::EntireElement,
+                false,
+            )
+            .unwrap();
+        assert_eq!(rsr.read_buf.len(), 4 + 512 + 2);
+        assert_eq!(&rsr.read_buf[..4], &[0xA1, 0xA1, 0xA1, 0xF8]);
+        assert!(rsr.data_crc_error);
+        assert!(!rsr.data_crc.unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_remove_insert_reorder() {
+        let mut disk = test_disk();
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+        for s in 1..=4 {
+            track
+                .add_sector(&AddSectorParams {
+                    id_chsn: DiskChsn::new(0, 0, s, 2),
+                    data: &[s; 512],
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let ids = |track: &DiskTrack| track.sector_list().iter().map(|s| s.chsn.s()).collect::<Vec<_>>();
+
+        // Remove a phantom sector.
+        let removed = track.remove_sector(DiskChsnQuery::new(0, 0, 3, None)).unwrap();
+        assert_eq!(removed, DiskChsn::new(0, 0, 3, 2));
+        assert_eq!(ids(track), vec![1, 2, 4]);
+        assert!(matches!(
+            track.remove_sector(DiskChsnQuery::new(0, 0, 3, None)),
+            Err(DiskImageError::IdError)
+        ));
+
+        // Insert a sector at the front, then append one at the end.
+        track
+            .insert_sector_at(
+                0,
+                &AddSectorParams {
+                    id_chsn: DiskChsn::new(0, 0, 9, 2),
+                    data: &[9; 512],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        track
+            .insert_sector_at(
+                4,
+                &AddSectorParams {
+                    id_chsn: DiskChsn::new(0, 0, 5, 2),
+                    data: &[5; 512],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(ids(track), vec![9, 1, 2, 4, 5]);
+        assert!(matches!(
+            track.insert_sector_at(6, &params(2, &[0; 512])),
+            Err(DiskImageError::ParameterError)
+        ));
+
+        // Reconstruct a 2:1 interleave.
+        track.reorder_sectors(&[1, 3, 0, 2, 4]).unwrap();
+        assert_eq!(ids(track), vec![1, 4, 9, 2, 5]);
+        let rsr = track
+            .read_sector(DiskChsnQuery::new(0, 0, 4, 2), None, None, RwScope::DataOnly, false)
+            .unwrap();
+        assert_eq!(rsr.read_buf, vec![4; 512]);
+
+        // Orders that are not a permutation are rejected and leave the track unchanged.
+        for order in [&[0, 1, 2, 3][..], &[0, 1, 2, 3, 3], &[0, 1, 2, 3, 5]] {
+            assert!(matches!(
+                track.reorder_sectors(order),
+                Err(DiskImageError::ParameterError)
+            ));
+        }
+        assert_eq!(ids(track), vec![1, 4, 9, 2, 5]);
+    }
+
+    #[test]
+    fn test_add_sector_validation() {
+        let mut disk = test_disk();
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+        let data = vec![0u8; 512];
+
+        assert!(matches!(
+            track.add_sector(&params(8, &data)),
+            Err(DiskImageError::InvalidSectorSize(8))
+        ));
+        assert!(matches!(
+            track.add_sector(&params(2, &[])),
+            Err(DiskImageError::EmptySectorData(_))
+        ));
+        assert!(matches!(
+            track.add_sector(&AddSectorParams {
+                weak_mask: Some(&data[..256]),
+                ..params(2, &data)
+            }),
+            Err(DiskImageError::MaskLengthMismatch {
+                data_len: 512,
+                mask_len: 256,
+            })
+        ));
+
+        // A sector with no DAM may legitimately have no data.
+        assert!(track
+            .add_sector(&AddSectorParams {
+                attributes: SectorAttributes {
+                    no_dam: true,
+                    ..Default::default()
+                },
+                ..params(2, &[])
+            })
+            .is_ok());
+        assert!(track.add_sector(&params(2, &data)).is_ok());
+    }
+
+    #[test]
+    fn test_mask_length_policy() {
+        let data = vec![0x55u8; 512];
+        let short = vec![0xFFu8; 256];
+        let long = vec![0xFFu8; 1024];
+        fn with_mask<'a>(data: &'a [u8], mask: &'a [u8], mask_policy: MaskLengthPolicy) -> AddSectorParams<'a> {
+            AddSectorParams {
+                weak_mask: Some(mask),
+                mask_policy,
+                ..params(2, data)
+            }
+        }
+
+        let fitted = with_mask(&data, &short, MaskLengthPolicy::Pad)
+            .fit_mask(&short)
+            .unwrap();
+        assert_eq!(fitted.len(), 512);
+        assert!(fitted[..256].iter().all(|&b| b == 0xFF));
+        assert!(fitted[256..].iter().all(|&b| b == 0));
+        assert!(with_mask(&data, &long, MaskLengthPolicy::Pad).fit_mask(&long).is_err());
+
+        assert_eq!(
+            with_mask(&data, &long, MaskLengthPolicy::Truncate)
+                .fit_mask(&long)
+                .unwrap()
+                .len(),
+            512
+        );
+        assert!(with_mask(&data, &short, MaskLengthPolicy::Truncate)
+            .fit_mask(&short)
+            .is_err());
+
+        assert_eq!(
+            with_mask(&data, &short, MaskLengthPolicy::Fit)
+                .fit_mask(&short)
+                .unwrap()
+                .len(),
+            512
+        );
+        assert_eq!(
+            with_mask(&data, &long, MaskLengthPolicy::Fit)
+                .fit_mask(&long)
+                .unwrap()
+                .len(),
+            512
+        );
+
+        // Only the unpadded portion of the mask should randomize data on read.
+        let mut disk = test_disk();
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+        track
+            .add_sector(&with_mask(&data, &short, MaskLengthPolicy::Pad))
+            .unwrap();
+        let result = track
+            .read_sector(DiskChsn::new(0, 0, 1, 2).into(), None, None, RwScope::DataOnly, false)
+            .unwrap();
+        let read = &result.read_buf[result.data_range];
+        assert_eq!(read.len(), 512);
+        assert!(read[256..].iter().all(|&b| b == 0x55));
+    }
+
+    #[test]
+    fn test_add_sector_count_limit() {
+        let mut disk = test_disk();
+        disk.set_sector_limits(SectorLimits {
+            max_sectors: 2,
+            ..Default::default()
+        });
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+        let data = vec![0u8; 512];
+
+        assert!(track.add_sector(&params(2, &data)).is_ok());
+        assert!(track.add_sector(&params(2, &data)).is_ok());
+        assert!(matches!(
+            track.add_sector(&params(2, &data)),
+            Err(DiskImageError::SectorCountError(2))
+        ));
+    }
+
+    #[test]
+    fn test_repair() {
+        let mut disk = test_disk();
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+        let bad_crc = SectorAttributes {
+            data_error: true,
+            ..Default::default()
+        };
+
+        for (s, n, attributes) in [(1, 2, bad_crc), (3, 2, Default::default()), (4, 3, Default::default())] {
+            let data = vec![s; DiskChsn::n_to_bytes(n)];
+            track
+                .add_sector(&AddSectorParams {
+                    id_chsn: DiskChsn::new(0, 0, s, n),
+                    data: &data,
+                    attributes,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let summary = track
+            .repair(&RepairOptions {
+                fix_data_crcs: true,
+                renumber_sectors: true,
+                normalize_sector_sizes: true,
+            })
+            .unwrap();
+
+        assert_eq!(summary.fixed_crcs, vec![DiskChsn::new(0, 0, 1, 2)]);
+        assert_eq!(
+            summary.renumbered,
+            vec![
+                (DiskChsn::new(0, 0, 3, 2), DiskChsn::new(0, 0, 2, 2)),
+                (DiskChsn::new(0, 0, 4, 3), DiskChsn::new(0, 0, 3, 3)),
+            ]
+        );
+        assert_eq!(
+            summary.resized,
+            vec![(DiskChsn::new(0, 0, 3, 3), DiskChsn::new(0, 0, 3, 2))]
+        );
+
+        let ids: Vec<DiskChsn> = track.sector_list().iter().map(|s| s.chsn).collect();
+        assert_eq!(ids, (1..=3).map(|s| DiskChsn::new(0, 0, s, 2)).collect::<Vec<_>>());
+        assert!(track.sector_list().iter().all(|s| !s.attributes.data_error));
+
+        let result = track
+            .read_sector(DiskChsn::new(0, 0, 3, 2).into(), None, None, RwScope::DataOnly, false)
+            .unwrap();
+        assert_eq!(result.read_buf[result.data_range], vec![4u8; 512]);
+
+        // A second pass has nothing left to do.
+        assert!(track
+            .repair(&RepairOptions {
+                fix_data_crcs: true,
+                renumber_sectors: true,
+                normalize_sector_sizes: true,
+            })
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_hole_regions() {
+        let mut disk = test_disk();
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+        let data = vec![0x55u8; 512];
+        track.add_sector(&params(2, &data)).unwrap();
+        let id: DiskChsnQuery = DiskChsn::new(0, 0, 1, 2).into();
+
+        assert!(track.hole_regions(id).unwrap().is_empty());
+        let result = track.read_sector(id, None, None, RwScope::DataOnly, false).unwrap();
+        assert!(!result.hole);
+
+        track.set_hole_region(id, 16..32).unwrap();
+        track.set_hole_region(id, 100..110).unwrap();
+        assert_eq!(track.hole_regions(id).unwrap(), vec![16..32, 100..110]);
+
+        let result = track.read_sector(id, None, None, RwScope::DataOnly, false).unwrap();
+        assert!(result.hole);
+        assert!(result.read_buf[..16].iter().all(|&b| b == 0x55));
+
+        track.clear_hole_region(id, Some(16..24)).unwrap();
+        assert_eq!(track.hole_regions(id).unwrap(), vec![24..32, 100..110]);
+        track.clear_hole_region(id, None).unwrap();
+        assert!(track.hole_regions(id).unwrap().is_empty());
+
+        assert!(matches!(
+            track.set_hole_region(id, 500..513),
+            Err(DiskImageError::ParameterError)
+        ));
+        assert!(matches!(
+            track.hole_regions(DiskChsn::new(0, 0, 2, 2).into()),
+            Err(DiskImageError::IdError)
+        ));
+    }
+
+    #[test]
+    fn test_weak_bit_policy() {
+        let mut disk = test_disk();
+        assert_eq!(disk.weak_bit_policy(), WeakBitPolicy::Random);
+        let data = vec![0x55u8; 512];
+        let weak_mask = vec![0x0Fu8; 512];
+        for s in 1..=2 {
+            disk.track_mut(DiskCh::new(0, 0))
+                .unwrap()
+                .add_sector(&AddSectorParams {
+                    id_chsn: DiskChsn::new(0, 0, s, 2),
+                    weak_mask: Some(&weak_mask),
+                    ..params(2, &data)
+                })
+                .unwrap();
+        }
+
+        fn read_s(disk: &DiskImage, s: u8) -> Vec<u8> {
+            disk.track(DiskCh::new(0, 0))
+                .unwrap()
+                .read_sector(DiskChsn::new(0, 0, s, 2).into(), None, None, RwScope::DataOnly, false)
+                .unwrap()
+                .read_buf
+        }
+        fn read(disk: &DiskImage) -> Vec<u8> {
+            read_s(disk, 1)
+        }
+
+        disk.set_weak_bit_policy(WeakBitPolicy::Ones);
+        assert!(read(&disk).iter().all(|&b| b == 0x5F));
+        disk.set_weak_bit_policy(WeakBitPolicy::Zeros);
+        assert!(read(&disk).iter().all(|&b| b == 0x50));
+
+        disk.set_weak_bit_policy(WeakBitPolicy::Alternating);
+        assert!(read(&disk).iter().all(|&b| b == 0x50));
+        assert!(read(&disk).iter().all(|&b| b == 0x5F));
+        assert!(read(&disk).iter().all(|&b| b == 0x50));
+        // Sector 2 follows sector 1 on the same revolution, while going back to sector 1 takes
+        // another revolution.
+        assert!(read_s(&disk, 2).iter().all(|&b| b == 0x50));
+        assert!(read(&disk).iter().all(|&b| b == 0x5F));
+        // A whole track is read in one revolution.
+        let track_read = disk
+            .track(DiskCh::new(0, 0))
+            .unwrap()
+            .read_all_sectors(DiskCh::new(0, 0), 2, 2)
+            .unwrap();
+        assert_eq!(track_read.read_buf.len(), 1024);
+        assert!(track_read.read_buf.iter().all(|&b| b == 0x50));
+
+        disk.set_weak_bit_policy(WeakBitPolicy::Seeded(1234));
+        assert_eq!(disk.weak_bit_policy(), WeakBitPolicy::Seeded(1234));
+        let first = (read(&disk), read(&disk));
+        assert_ne!(first.0, first.1);
+        assert!(first.0.iter().all(|&b| b & 0xF0 == 0x50));
+        disk.set_weak_bit_policy(WeakBitPolicy::Seeded(1234));
+        assert_eq!((read(&disk), read(&disk)), first);
+    }
+
+    #[test]
+    fn test_read_sector_ref() {
+        let mut disk = test_disk();
+        disk.set_weak_bit_policy(WeakBitPolicy::Ones);
+        let data = vec![0x55u8; 512];
+        let weak_mask = vec![0x0Fu8; 512];
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+        track
+            .add_sector(&AddSectorParams {
+                attributes: SectorAttributes {
+                    deleted_mark: true,
+                    ..Default::default()
+                },
+                ..params(2, &data)
+            })
+            .unwrap();
+        track
+            .add_sector(&AddSectorParams {
+                id_chsn: DiskChsn::new(0, 0, 2, 2),
+                weak_mask: Some(&weak_mask),
+                ..params(2, &data)
+            })
+            .unwrap();
+
+        let ch = DiskCh::new(0, 0);
+        let plain = disk
+            .read_sector_ref(ch, DiskChsn::new(0, 0, 1, 2).into(), None)
+            .unwrap();
+        assert!(plain.is_borrowed());
+        assert!(plain.deleted_mark && !plain.data_crc_error);
+        assert_eq!(plain.data, data.as_slice());
+
+        // Weak bits and size overrides require a copy of the data.
+        let weak = disk
+            .read_sector_ref(ch, DiskChsn::new(0, 0, 2, 2).into(), None)
+            .unwrap();
+        assert!(!weak.is_borrowed());
+        assert!(weak.data.iter().all(|&b| b == 0x5F));
+        let resized = disk
+            .read_sector_ref(ch, DiskChsn::new(0, 0, 1, 2).into(), Some(1))
+            .unwrap();
+        assert!(!resized.is_borrowed());
+        assert_eq!(resized.data.len(), 512);
+
+        let missing = disk
+            .read_sector_ref(ch, DiskChsn::new(0, 0, 3, 2).into(), None)
+            .unwrap();
+        assert!(missing.not_found && missing.data.is_empty());
+    }
+
+    #[test]
+    fn test_size_mismatch_policy() {
+        let mut disk = test_disk();
+        let data: Vec<u8> = (0..512).map(|i| i as u8).collect();
+        disk.track_mut(DiskCh::new(0, 0))
+            .unwrap()
+            .add_sector(&params(2, &data))
+            .unwrap();
+
+        fn read(disk: &DiskImage, n: u8, scope: RwScope) -> ReadSectorResult {
+            disk.track(DiskCh::new(0, 0))
+                .unwrap()
+                .read_sector(DiskChsnQuery::new(0, 0, 1, None), Some(n), None, scope, false)
+                .unwrap()
+        }
+
+        // The stored data is returned regardless of N by default.
+        assert_eq!(disk.size_mismatch_policy(), SizeMismatchPolicy::Unchecked);
+        let rsr = read(&disk, 1, RwScope::DataOnly);
+        assert_eq!(rsr.read_buf, data);
+        assert!(!rsr.data_crc_error);
+
+        disk.set_size_mismatch_policy(SizeMismatchPolicy::Truncate);
+        let rsr = read(&disk, 1, RwScope::DataOnly);
+        assert_eq!(rsr.read_buf, &data[..256]);
+        assert!(rsr.data_crc_error);
+        assert_eq!(read(&disk, 3, RwScope::DataOnly).read_buf, data);
+        assert!(!read(&disk, 2, RwScope::DataOnly).data_crc_error);
+
+        // A long controller read continues into the data CRC, then the gap.
+        disk.set_size_mismatch_policy(SizeMismatchPolicy::Controller);
+        let element = read(&disk, 2, RwScope::EntireElement).read_buf;
+        let crc = &element[element.len() - 2..];
+        let rsr = read(&disk, 3, RwScope::DataOnly);
+        assert_eq!(rsr.read_buf.len(), 1024);
+        assert_eq!(&rsr.read_buf[..512], &data[..]);
+        assert_eq!(&rsr.read_buf[512..514], crc);
+        assert!(rsr.read_buf[514..].iter().all(|&b| b == 0x4E));
+        assert!(rsr.data_crc_error);
+        let rsr = read(&disk, 1, RwScope::EntireElement);
+        assert_eq!(rsr.read_buf.len(), 4 + 256 + 2);
+        assert!(rsr.data_crc_error);
+        assert!(rsr.data_crc.unwrap().is_error());
+
+        disk.set_size_mismatch_policy(SizeMismatchPolicy::NotFound);
+        assert!(read(&disk, 1, RwScope::DataOnly).not_found);
+        assert!(!read(&disk, 2, RwScope::DataOnly).not_found);
+    }
+
+    #[test]
+    fn test_sector_list_duplicates() {
+        let mut disk = test_disk();
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+
+        for (s, fill) in [(1, 0x11), (2, 0x22), (1, 0x33)] {
+            track
+                .add_sector(&AddSectorParams {
+                    id_chsn: DiskChsn::new(0, 0, s, 2),
+                    data: &[fill; 512],
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let sector_list = track.sector_list();
+        let duplicates: Vec<_> = sector_list
+            .iter()
+            .map(|e| (e.chsn.s(), e.duplicate_idx, e.duplicate_ct, e.is_read_target()))
+            .collect();
+        assert_eq!(duplicates, vec![(1, 0, 2, true), (2, 0, 1, true), (1, 1, 2, false)]);
+        assert!(sector_list[0].is_duplicate() && !sector_list[1].is_duplicate());
+
+        // A read returns the read target.
+        let result = track
+            .read_sector(DiskChsn::new(0, 0, 1, 2).into(), None, None, RwScope::DataOnly, false)
+            .unwrap();
+        assert!(result.read_buf.iter().all(|&b| b == 0x11));
+        assert_eq!(track.sector_list_with_orphans().len(), 3);
+    }
+
+    #[test]
+    fn test_add_alternate_sector() {
+        let mut disk = test_disk();
+        let track = disk
+            .track_mut(DiskCh::new(0, 0))
+            .unwrap()
+            .as_metasector_track_mut()
+            .unwrap();
+        let id = DiskChsn::new(0, 0, 1, 2);
+        fn alternate(data: &[u8]) -> AddSectorParams<'_> {
+            AddSectorParams {
+                id_chsn: DiskChsn::new(0, 0, 1, 2),
+                data,
+                alternate: true,
+                mask_policy: MaskLengthPolicy::Fit,
+                ..Default::default()
+            }
+        }
+
+        // The first alternate of a sector adds it.
+        let summary = track.add_alternate_sector(&alternate(&[0x00; 512])).unwrap();
+        assert!(!summary.merged);
+        assert!(summary.weak_regions.is_empty());
+
+        let mut data = vec![0x00; 512];
+        data[10] = 0x81;
+        let summary = track.add_alternate_sector(&alternate(&data)).unwrap();
+        assert!(summary.merged);
+        assert_eq!(summary.differing_bits, 2);
+        assert_eq!(summary.weak_regions, vec![10..11]);
+
+        // Repeated alternates accumulate, and a short alternate marks the uncovered bytes weak.
+        data[10] = 0x00;
+        data[20] = 0x01;
+        let summary = track.add_alternate_sector(&alternate(&data[..500])).unwrap();
+        assert_eq!(summary.differing_bits, 1);
+        assert_eq!(summary.uncovered_bytes, 12);
+        assert_eq!(summary.weak_regions, vec![10..11, 20..21, 500..512]);
+
+        // Excess bytes are discarded and the sector keeps its length and data.
+        let summary = track.add_alternate_sector(&alternate(&[0x00; 1024])).unwrap();
+        assert_eq!(summary.excess_bytes, 512);
+        assert_eq!(summary.differing_bits, 0);
+        assert_eq!(track.sector_ct(), 1);
+        let (stored, mask) = track.raw_sector_data(id).unwrap();
+        assert_eq!(stored, &[0x00; 512][..]);
+        assert_eq!(mask[10], 0x81);
+        assert_eq!(mask[20], 0x01);
+
+        // Adding through the Track trait takes the same path.
+        data[30] = 0x10;
+        track.add_sector(&alternate(&data)).unwrap();
+        assert_eq!(track.raw_sector_data(id).unwrap().1[30], 0x10);
+    }
+
+    #[test]
+    fn test_synthesized_metadata() {
+        let mut disk = test_disk();
+        let track = disk.track_mut(DiskCh::new(0, 0)).unwrap();
+        assert!(track.metadata().unwrap().elements().is_empty());
+
+        for s in 1..=9 {
+            let attributes = SectorAttributes {
+                deleted_mark: s == 3,
+                no_dam: s == 5,
+                ..Default::default()
+            };
+            let data = if attributes.no_dam { vec![] } else { vec![s; 512] };
+            track
+                .add_sector(&AddSectorParams {
+                    id_chsn: DiskChsn::new(0, 0, s, 2),
+                    data: &data,
+                    attributes,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let metadata = track.metadata().unwrap();
+        assert_eq!(metadata.sector_ids().len(), 9);
+        let data_ids: Vec<u8> = metadata.sector_list().iter().map(|e| e.chsn.s()).collect();
+        assert_eq!(data_ids, vec![1, 2, 3, 4, 6, 7, 8, 9]);
+        assert!(metadata.sector_list()[2].attributes.deleted_mark);
+        assert!(metadata.elements().windows(2).all(|w| w[0].start <= w[1].start));
+
+        // The first DAM follows GAP4A, sync, IAM, GAP1, sync, the sector ID, GAP2 and sync.
+        let first_dam = metadata
+            .elements()
+            .iter()
+            .find(|e| {
+                matches!(
+                    e.element(),
+                    TrackElement::System34(System34Element::Marker(System34Marker::Dam, _))
+                )
+            })
+            .unwrap();
+        assert_eq!(first_dam.start, (80 + 12 + 4 + 50 + 12 + 10 + 22 + 12) * 16);
+
+        // The sector without a DAM has a header flagged as missing its data, and GAP4B fills the
+        // rest of the track.
+        let no_dam_header = metadata
+            .elements()
+            .iter()
+            .find(|e| e.element().is_sector_header() && e.element().chsn().map(|c| c.s()) == Some(5))
+            .unwrap();
+        assert!(matches!(
+            no_dam_header.element(),
+            TrackElement::System34(System34Element::SectorHeader { data_missing: true, .. })
+        ));
+        let last = metadata.elements().last().unwrap();
+        assert!(matches!(last.element(), TrackElement::System34(System34Element::Gap4b)));
+        assert_eq!(last.range().end, 100_000);
+        assert!(track.element_map().is_some());
+
+        // Metadata follows changes to the sectors.
+        track
+            .write_sector(
+                DiskChsn::new(0, 0, 1, 2).into(),
+                None,
+                &[0; 512],
+                RwScope::DataOnly,
+                true,
+                false,
+            )
+            .unwrap();
+        let first_data = track.metadata().unwrap().sector_list()[0];
+        assert!(first_data.attributes.deleted_mark);
+        track.remove_sector(DiskChsn::new(0, 0, 9, 2).into()).unwrap();
+        let generic: Vec<GenericTrackElement> = track
+            .metadata()
+            .unwrap()
+            .elements()
+            .iter()
+            .filter(|e| e.element().is_sector_data())
+            .map(|e| e.element().into())
+            .collect();
+        assert_eq!(generic.len(), 7);
+        assert_eq!(generic[0], GenericTrackElement::SectorDeletedData);
+
+        // Metadata is not serialized, but rebuilt when the track is deserialized.
+        #[cfg(feature = "serde")]
+        {
+            let meta_track = track.as_metasector_track().unwrap();
+            let json = serde_json::to_string(meta_track).unwrap();
+            let restored: super::MetaSectorTrack = serde_json::from_str(&json).unwrap();
+            let (restored, original) = (restored.metadata().unwrap(), meta_track.metadata().unwrap());
+            assert_eq!(restored.sector_ids().len(), 8);
+            assert_eq!(restored.elements().len(), original.elements().len());
+            assert_eq!(restored.sector_list()[0].chsn, original.sector_list()[0].chsn);
+        }
+    }
+}
@@ -31,6 +31,9 @@
 */
 pub mod bitstream;
 pub mod fluxstream;
+pub mod gap_analysis;
+pub mod interleave;
+pub mod memory;
 pub mod metasector;
 //mod sector_iterator;
 
@@ -40,9 +43,12 @@ use crate::{
     track::{
         bitstream::BitStreamTrack,
         fluxstream::{FluxStreamTrack, FluxTrackInfo},
+        gap_analysis::TrackGapReport,
+        interleave::{measure_interleave, TrackInterleave},
+        memory::TrackMemoryUsage,
         metasector::MetaSectorTrack,
     },
-    track_schema::{system34::System34Standard, TrackMetadata, TrackSchema},
+    track_schema::{system34::System34Standard, DecodedElements, TrackMetadata, TrackSchema},
     types::{
         chs::DiskChsnQuery,
         AddSectorParams,
@@ -50,6 +56,7 @@ use crate::{
         DiskChs,
         DiskChsn,
         DiskRpm,
+        ReadSectorRef,
         ReadSectorResult,
         ReadTrackResult,
         RwScope,
@@ -58,19 +65,22 @@ use crate::{
         TrackDataRate,
         TrackDataResolution,
         TrackDensity,
+        TrackFormatting,
         WriteSectorResult,
     },
     DiskImageError,
     SectorIdQuery,
     SectorMapEntry,
 };
+use bit_vec::BitVec;
 use dyn_clone::{clone_trait_object, DynClone};
 use sha1_smol::Digest;
-use std::any::Any;
+use std::{any::Any, ops::Range};
 
 /// A struct containing information about a track's encoding, data rate, density, RPM, bit length,
 /// and sector count.
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TrackInfo {
     /// The resolution of the track as a `TrackDataResolution` enum.
     pub resolution: TrackDataResolution,
@@ -159,7 +169,8 @@ impl From<TrackSectorScanResult> for ScanSectorResult {
 }
 
 /// A structure containing information about a track's consistency vs a standard track.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TrackAnalysis {
     /// A boolean flag indicating whether the track contains sectors with bad data CRCs.
     pub data_error: bool,
@@ -199,6 +210,40 @@ impl TrackAnalysis {
     }
 }
 
+/// Options controlling which repair operations [Track::repair] performs. Operations are applied
+/// in the order of the fields below.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RepairOptions {
+    /// Recompute data CRCs for sectors with data CRC errors, so that they read without error.
+    /// The sector data itself is left unchanged.
+    pub fix_data_crcs: bool,
+    /// Renumber the sector IDs of a track with nonconsecutive sectors so that they ascend
+    /// consecutively in physical order, starting with the lowest sector ID present.
+    pub renumber_sectors: bool,
+    /// Resize all sectors to the most common sector size on the track, truncating or
+    /// zero-padding sector data as needed.
+    pub normalize_sector_sizes: bool,
+}
+
+/// A summary of the changes made by [Track::repair].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RepairSummary {
+    /// The sectors whose data CRC was fixed.
+    pub fixed_crcs: Vec<DiskChsn>,
+    /// The sectors that were renumbered, as (old ID, new ID) pairs.
+    pub renumbered: Vec<(DiskChsn, DiskChsn)>,
+    /// The sectors that were resized, as (old ID, new ID) pairs.
+    pub resized:    Vec<(DiskChsn, DiskChsn)>,
+}
+
+impl RepairSummary {
+    /// Returns `true` if no changes were made.
+    pub fn is_empty(&self) -> bool {
+        self.fixed_crcs.is_empty() && self.renumbered.is_empty() && self.resized.is_empty()
+    }
+}
+
 #[cfg_attr(feature = "serde", typetag::serde)]
 pub trait Track: DynClone + Any + Send + Sync {
     /// Return the resolution of the track as a `DiskDataResolution`.
@@ -258,6 +303,15 @@ pub trait Track: DynClone + Any + Send + Sync {
     /// been scanned for metadata or no metadata was found.
     fn metadata(&self) -> Option<&TrackMetadata>;
 
+    /// Return an iterator over the track's metadata elements, producing a [DecodedElement] with
+    /// the sector ID and the recorded and calculated CRC of each element where applicable.
+    /// Tracks without element metadata produce no elements.
+    ///
+    /// [DecodedElement]: crate::track_schema::DecodedElement
+    fn decoded_elements(&self) -> DecodedElements<'_> {
+        DecodedElements::empty()
+    }
+
     /// Return a count of the sectors on the track.
     fn sector_ct(&self) -> usize;
 
@@ -275,9 +329,26 @@ pub trait Track: DynClone + Any + Send + Sync {
     //fn sector_iter(&self) -> SectorIterator<'a, T>;
 
     /// TODO: Rename SectorMapEntry - it's not a map, it's a list.
-    /// Returns a vector of `SectorMapEntry` structs representing the sectors on the track.
+    /// Returns a vector of `SectorMapEntry` structs representing the sectors on the track, in track
+    /// order. Sectors sharing an ID are numbered by their `duplicate_idx`; reading that ID returns
+    /// the first of them. Orphaned data fields are not included.
     fn sector_list(&self) -> Vec<SectorMapEntry>;
 
+    /// Returns a vector of `SectorMapEntry` structs representing the sectors on the track, as
+    /// [Track::sector_list()], with any orphaned data fields (data address marks that do not
+    /// follow a sector header) inserted in track order.
+    fn sector_list_with_orphans(&self) -> Vec<SectorMapEntry> {
+        let mut sector_list = self.sector_list();
+        if let Some(metadata) = self.metadata() {
+            let orphans = metadata.orphan_data_list();
+            if !orphans.is_empty() {
+                sector_list.extend(orphans);
+                sector_list.sort_by_key(|entry| entry.data_offset);
+            }
+        }
+        sector_list
+    }
+
     /// Adds a new sector to a track in the disk image, essentially 'formatting' a new sector,
     /// This function is only valid for tracks with `MetaSector` resolution.
     ///
@@ -293,6 +364,38 @@ pub trait Track: DynClone + Any + Send + Sync {
     /// - `Err(DiskImageError::UnsupportedFormat)` if the track data is not of `MetaSector` resolution.
     fn add_sector(&mut self, sd: &AddSectorParams) -> Result<(), DiskImageError>;
 
+    /// Remove the first sector matching `id` from the track.
+    /// This function is only valid for tracks with `MetaSector` resolution.
+    ///
+    /// # Returns
+    /// - `Ok(DiskChsn)` with the ID of the removed sector.
+    /// - `Err(DiskImageError::IdError)` if no sector matches `id`.
+    /// - `Err(DiskImageError::UnsupportedFormat)` if the track data is not of `MetaSector` resolution.
+    fn remove_sector(&mut self, id: DiskChsnQuery) -> Result<DiskChsn, DiskImageError>;
+
+    /// Insert a new sector at physical position `index` on the track, shifting all following
+    /// sectors along. An `index` equal to the sector count appends the sector. The `alternate`
+    /// flag of `sd` is ignored; the sector is always inserted as a new sector.
+    /// This function is only valid for tracks with `MetaSector` resolution.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the sector was inserted.
+    /// - `Err(DiskImageError::ParameterError)` if `index` is greater than the sector count.
+    /// - `Err(DiskImageError::UnsupportedFormat)` if the track data is not of `MetaSector` resolution.
+    /// - Any error returned by [Track::add_sector] for an invalid sector.
+    fn insert_sector_at(&mut self, index: usize, sd: &AddSectorParams) -> Result<(), DiskImageError>;
+
+    /// Reorder the sectors on the track. `order` lists the current physical index of each sector
+    /// in its new position, so that sector `i` after reordering is the sector previously at
+    /// `order[i]`. `order` must be a permutation of all sector indices.
+    /// This function is only valid for tracks with `MetaSector` resolution.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the sectors were reordered.
+    /// - `Err(DiskImageError::ParameterError)` if `order` is not a permutation of the sector indices.
+    /// - `Err(DiskImageError::UnsupportedFormat)` if the track data is not of `MetaSector` resolution.
+    fn reorder_sectors(&mut self, order: &[usize]) -> Result<(), DiskImageError>;
+
     /// Attempts to read the sector data from the sector identified by `id`.
     ///
     /// # Arguments
@@ -322,6 +425,82 @@ pub trait Track: DynClone + Any + Send + Sync {
         debug: bool,
     ) -> Result<ReadSectorResult, DiskImageError>;
 
+    /// Read the sector at position `index` of [Track::sector_list()]. Unlike [Track::read_sector],
+    /// which reads the first sector matching an ID, this reads the specified sector when a track
+    /// contains several sectors with the same ID.
+    ///
+    /// # Returns
+    /// A Result containing either
+    /// - [ReadSectorResult] struct with the sector data as [Track::read_sector] returns it.
+    /// - [DiskImageError::ParameterError] if `index` is out of range.
+    fn read_sector_at(&self, index: usize, scope: RwScope) -> Result<ReadSectorResult, DiskImageError> {
+        let sector_list = self.sector_list();
+        let entry = sector_list.get(index).ok_or(DiskImageError::ParameterError)?;
+        // Start the search just past the data element of the previous sector with the same ID, so
+        // that the scan finds this sector's header next.
+        let offset = sector_list[..index]
+            .iter()
+            .rev()
+            .find(|e| e.chsn == entry.chsn)
+            .and_then(|e| e.data_offset)
+            .map(|offset| offset + 1);
+        self.read_sector(DiskChsnQuery::from(entry.chsn), None, offset, scope, false)
+    }
+
+    /// Read the data of the sector identified by `id`, as [Track::read_sector] does with
+    /// [RwScope::DataOnly], but borrow the sector data from the track instead of copying it where
+    /// possible. This avoids an allocation per read in hot loops such as emulators.
+    ///
+    /// Only `MetaSector` resolution tracks store sector data that can be borrowed, and only when
+    /// the sector has no weak bits or holes and `n` does not override the sector's size. In all
+    /// other cases the sector data is owned.
+    ///
+    /// # Arguments
+    /// - `id`: The sector ID to read as a `SectorIdQuery`.
+    /// - `n`: An optional override value for the sector's size parameter.
+    ///
+    /// # Returns
+    /// A Result containing either
+    /// - [ReadSectorRef] struct which provides various result flags and the sector data.
+    /// - [DiskImageError] if an error occurred while reading the sector.
+    fn read_sector_ref(&self, id: SectorIdQuery, n: Option<u8>) -> Result<ReadSectorRef<'_>, DiskImageError> {
+        Ok(self.read_sector(id, n, None, RwScope::DataOnly, false)?.into())
+    }
+
+    /// Return the number of captured revolutions of the track that can be read independently
+    /// with [Track::read_sector_rev]. Tracks that do not store multiple revolutions return 1.
+    fn revolutions(&self) -> usize {
+        1
+    }
+
+    /// Read the sector identified by `id` from a single captured revolution of the track,
+    /// rather than from the track's resolved view. This allows weak or unstable sectors to be
+    /// compared across revolutions.
+    ///
+    /// # Arguments
+    /// - `id`: The sector ID to read as a `SectorIdQuery`.
+    /// - `revolution`: The index of the revolution to read, less than [Track::revolutions].
+    /// - `n`: An optional override value for the sector's size parameter.
+    /// - `scope`: The scope of the read operation as a `RwScope` enum.
+    ///
+    /// # Returns
+    /// A Result containing either
+    /// - [ReadSectorResult] struct with the sector data as read from the specified revolution.
+    /// - [DiskImageError::ParameterError] if `revolution` is out of range.
+    /// - [DiskImageError::ResolveError] if the revolution could not be decoded.
+    fn read_sector_rev(
+        &self,
+        id: SectorIdQuery,
+        revolution: usize,
+        n: Option<u8>,
+        scope: RwScope,
+    ) -> Result<ReadSectorResult, DiskImageError> {
+        if revolution != 0 {
+            return Err(DiskImageError::ParameterError);
+        }
+        self.read_sector(id, n, None, scope, false)
+    }
+
     fn scan_sector(&self, id: SectorIdQuery, offset: Option<usize>) -> Result<ScanSectorResult, DiskImageError>;
 
     fn write_sector(
@@ -338,9 +517,17 @@ pub trait Track: DynClone + Any + Send + Sync {
     /// offset.
     fn recalculate_sector_crc(&mut self, id: DiskChsnQuery, offset: Option<usize>) -> Result<(), DiskImageError>;
 
+    /// Perform the repair operations selected by `options` on the track.
+    /// Not all track resolutions support all operations.
+    /// # Returns
+    /// - `Ok(RepairSummary)` describing the changes made.
+    /// - `Err(DiskImageError::UnsupportedFormat)` if a requested operation is not supported by
+    ///   the track resolution.
+    fn repair(&mut self, options: &RepairOptions) -> Result<RepairSummary, DiskImageError>;
+
     /// Return a hash that uniquely identifies the track data. Intended for use in identifying
     /// duplicate tracks.
-    fn hash(&mut self) -> Digest;
+    fn hash(&self) -> Digest;
 
     /// Read all sectors from the track. The data is returned within a `ReadSectorResult` struct
     /// which also sets some convenience metadata flags which are needed when handling `MetaSector`
@@ -348,7 +535,7 @@ pub trait Track: DynClone + Any + Send + Sync {
     /// Unlike `read_sector`, the data returned is only the actual sector data. The address marks
     /// and CRCs are not included in the data.
     /// This function is intended for use in implementing the µPD765 FDC's "Read Track" command.
-    fn read_all_sectors(&mut self, ch: DiskCh, n: u8, track_len: u8) -> Result<ReadTrackResult, DiskImageError>;
+    fn read_all_sectors(&self, ch: DiskCh, n: u8, track_len: u8) -> Result<ReadTrackResult, DiskImageError>;
 
     fn next_id(&self, chs: DiskChs) -> Option<DiskChsn>;
 
@@ -376,9 +563,51 @@ pub trait Track: DynClone + Any + Send + Sync {
     /// - `Err(DiskImageError)` if an error occurred while reading the track.
     fn read_raw(&self, overdump: Option<usize>) -> Result<ReadTrackResult, DiskImageError>;
 
+    /// Read the raw bitcells of the track within `range`, including clock bits. This is only
+    /// valid for tracks with a bitstream, including resolved flux tracks.
+    /// # Returns
+    /// - `Ok(BitVec)` containing the bitcells in `range`.
+    /// - `Err(DiskImageError::ParameterError)` if `range` is empty or extends past the end of the track.
+    /// - `Err(DiskImageError::UnsupportedFormat)` if the track has no bitstream.
+    fn read_raw_bits(&self, range: Range<usize>) -> Result<BitVec, DiskImageError>;
+
+    /// Overwrite the raw bitcells of the track starting at bitcell `offset` with `bits`, for
+    /// example to change a sync mark or a gap byte. No encoding is performed; `bits` must
+    /// include clock bits. The track is then rescanned for markers and metadata with its current
+    /// schema, so that sector lists and reads reflect the modified bitcells.
+    /// # Returns
+    /// - `Ok(())` if the bitcells were written.
+    /// - `Err(DiskImageError::ParameterError)` if `bits` is empty or would extend past the end of the track.
+    /// - `Err(DiskImageError::UnsupportedFormat)` if the track has no bitstream.
+    fn write_raw_bits(&mut self, offset: usize, bits: &BitVec) -> Result<(), DiskImageError>;
+
     /// Return a boolean value indicating whether the track has bits set in its weak bit mask.
     fn has_weak_bits(&self) -> bool;
 
+    /// Return whether the track is formatted, distinguishing an unformatted track from a
+    /// formatted track whose sectors could not be decoded. See [TrackFormatting].
+    fn formatting(&self) -> TrackFormatting;
+
+    /// Mark the bytes within `range` of the data of the first sector matching `id` as a hole,
+    /// modeling physical damage to the disk surface. Bytes within a hole read back as random data,
+    /// and reads of the sector will set the `hole` flag in the [ReadSectorResult].
+    /// # Returns
+    /// - `Err(DiskImageError::IdError)` if no sector matches `id`.
+    /// - `Err(DiskImageError::ParameterError)` if `range` extends beyond the sector data.
+    /// - `Err(DiskImageError::UnsupportedFormat)` if the track resolution does not model holes.
+    fn set_hole_region(&mut self, id: DiskChsnQuery, range: Range<usize>) -> Result<(), DiskImageError>;
+
+    /// Clear any hole within `range` of the data of the first sector matching `id`.
+    /// If `range` is `None`, all holes in the sector are cleared.
+    /// Errors are as for [Track::set_hole_region].
+    fn clear_hole_region(&mut self, id: DiskChsnQuery, range: Option<Range<usize>>) -> Result<(), DiskImageError>;
+
+    /// Return the byte ranges of the data of the first sector matching `id` that lie within a hole.
+    /// Track resolutions that do not model holes return an empty list.
+    /// # Returns
+    /// - `Err(DiskImageError::IdError)` if no sector matches `id`.
+    fn hole_regions(&self, id: DiskChsnQuery) -> Result<Vec<Range<usize>>, DiskImageError>;
+
     /// Format the track with the specified parameters.
     /// # Arguments
     /// - `standard`: The disk structure standard to use when formatting the track.
@@ -393,6 +622,18 @@ pub trait Track: DynClone + Any + Send + Sync {
         gap3: usize,
     ) -> Result<(), DiskImageError>;
 
+    /// Write the entire track from the data of a write track command, as sent to a WD177x-style
+    /// floppy disk controller by an emulator. Writing starts at the index, and address fields
+    /// are recognized from the sync and mark bytes as the data is written. See
+    /// [System34Schema::write_track_as_bytes] for the special bytes that are interpreted.
+    /// # Returns
+    /// - `Ok(())` if the track was successfully written.
+    /// - `Err(DiskImageError::UnsupportedFormat)` if the track resolution or encoding does not
+    ///   support writing raw track data.
+    ///
+    /// [System34Schema::write_track_as_bytes]: crate::track_schema::system34::System34Schema::write_track_as_bytes
+    fn write_track(&mut self, data: &[u8]) -> Result<(), DiskImageError>;
+
     /// Retrieve information about a track's consistency vs a standard track.
     /// Returns a `TrackAnalysis` struct containing information about the track's formatting,
     /// such as bad CRCs, deleted data, and overlapping sectors.
@@ -401,6 +642,25 @@ pub trait Track: DynClone + Any + Send + Sync {
     /// - `Err(DiskImageError)` if an error occurred while checking the analyzing the track
     fn analysis(&self) -> Result<TrackAnalysis, DiskImageError>;
 
+    /// Measure the gaps between the address fields of the track and check them against the
+    /// minimum lengths a floppy disk controller needs to write sectors reliably. This is useful
+    /// when an image is intended to be written back to a real disk.
+    /// # Returns
+    /// A [TrackGapReport], or `None` if the track has no element metadata or is not a System34
+    /// track.
+    fn gap_report(&self) -> Option<TrackGapReport> {
+        None
+    }
+
+    /// Determine the logical interleave of the track from the physical order of its sectors,
+    /// so that a formatting tool can reproduce it. See [interleave::interleaved_order].
+    /// # Returns
+    /// A [TrackInterleave], or `None` if the track has fewer than two sectors or no two sectors
+    /// with consecutive IDs.
+    fn interleave(&self) -> Option<TrackInterleave> {
+        measure_interleave(self.ch(), &self.sector_list())
+    }
+
     /// Return a reference to the underlying `TrackDataStream`.
     fn stream(&self) -> Option<&TrackDataStream>;
 
@@ -412,8 +672,40 @@ pub trait Track: DynClone + Any + Send + Sync {
     fn element_map(&self) -> Option<&SourceMap> {
         None
     }
+
+    /// Return the approximate memory used by each representation of the track's data. The default
+    /// implementation reports no memory used, for tracks that don't account for their buffers.
+    fn memory_usage(&self) -> TrackMemoryUsage {
+        TrackMemoryUsage::new(self.ch())
+    }
 }
 
 clone_trait_object!(Track);
 
 pub type DiskTrack = Box<dyn Track>;
+
+/// A reference to a single sector on a track, as yielded by [crate::DiskImage::sectors()].
+#[derive(Clone)]
+pub struct SectorRef<'a> {
+    /// The physical cylinder and head of the track containing the sector.
+    pub ch:    DiskCh,
+    /// The index of the sector in the track's [Track::sector_list()].
+    pub index: usize,
+    /// The sector's ID and attributes.
+    pub entry: SectorMapEntry,
+    /// The track containing the sector.
+    pub track: &'a dyn Track,
+}
+
+impl SectorRef<'_> {
+    /// Return the sector's ID.
+    pub fn id(&self) -> DiskChsn {
+        self.entry.chsn
+    }
+
+    /// Read the sector's data with the specified [RwScope]. The sector is read by its position on
+    /// the track, so each of several sectors sharing the same ID reads its own data.
+    pub fn read(&self, scope: RwScope) -> Result<ReadSectorResult, DiskImageError> {
+        self.track.read_sector_at(self.index, scope)
+    }
+}
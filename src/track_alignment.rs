@@ -0,0 +1,429 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/track_alignment.rs
+
+    Cross-correlates the bitstreams of the same track from two dumps to find
+    their rotational offset and how similar they are.
+*/
+
+//! Track alignment between two dumps of a disk.
+//!
+//! Two dumps of the same track rarely start at the same point of the disk's rotation, and may
+//! differ slightly in length due to drive speed variance. [align_tracks()] finds the rotational
+//! offset that best lines up two track bitstreams and reports the fraction of bits that match at
+//! that offset. [DiskImage::align()] does this for every track of two images, which answers the
+//! question of whether two dumps are actually of the same disk.
+//!
+//! Only tracks with a bitstream (`BitStream` and `FluxStream` resolution) can be aligned.
+
+use crate::{track::Track, types::DiskCh, DiskImage};
+use bit_vec::BitVec;
+use std::fmt::{Display, Formatter, Result};
+
+/// The number of bits compared as a unit when measuring similarity. The offset is re-centered
+/// after each block to follow speed variance within the track.
+const BLOCK_BITS: usize = 512;
+const BLOCK_WORDS: usize = BLOCK_BITS / 64;
+/// The number of bits either side of the expected position searched for each block.
+const BLOCK_SLACK: isize = 8;
+/// The size and count of the windows used to find candidate offsets on tracks with no markers.
+const WINDOW_WORDS: usize = 4;
+const WINDOW_CT: usize = 4;
+/// The number of candidate offsets taken from the window search.
+const WINDOW_CANDIDATES: usize = 8;
+
+/// The default minimum mean similarity for two images to be considered dumps of the same disk.
+pub const SAME_DISK_THRESHOLD: f64 = 0.95;
+
+/// The result of aligning one track against another.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrackAlignment {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    /// The length of the left track, in bits.
+    pub left_len: usize,
+    /// The length of the right track, in bits.
+    pub right_len: usize,
+    /// The bit index in the right track corresponding to bit 0 of the left track.
+    pub offset: usize,
+    /// The number of bits compared. Bits that are weak in either track are not compared.
+    pub compared_bits: usize,
+    /// The number of compared bits that matched.
+    pub matching_bits: usize,
+}
+
+impl TrackAlignment {
+    /// Return the fraction of compared bits that matched, from 0.0 to 1.0.
+    pub fn similarity(&self) -> f64 {
+        if self.compared_bits == 0 {
+            return 0.0;
+        }
+        self.matching_bits as f64 / self.compared_bits as f64
+    }
+
+    /// Return the rotational offset of the right track relative to the left track, in degrees.
+    pub fn offset_degrees(&self) -> f64 {
+        if self.right_len == 0 {
+            return 0.0;
+        }
+        self.offset as f64 * 360.0 / self.right_len as f64
+    }
+}
+
+impl Display for TrackAlignment {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(
+            f,
+            "{}: offset {} bits ({:.1}°), {:.2}% similar",
+            self.ch,
+            self.offset,
+            self.offset_degrees(),
+            self.similarity() * 100.0
+        )
+    }
+}
+
+/// The result of aligning every track of one [DiskImage] against another, as returned by
+/// [DiskImage::align()].
+#[derive(Clone, Debug, Default)]
+pub struct ImageAlignment {
+    /// The alignment of each track present with a bitstream in both images.
+    pub tracks:    Vec<TrackAlignment>,
+    /// Tracks present in only one image, or that could not be aligned because either track has
+    /// no bitstream.
+    pub unaligned: Vec<DiskCh>,
+}
+
+impl ImageAlignment {
+    /// Return the mean similarity of all aligned tracks, or 0.0 if no tracks were aligned.
+    pub fn mean_similarity(&self) -> f64 {
+        if self.tracks.is_empty() {
+            return 0.0;
+        }
+        self.tracks.iter().map(|t| t.similarity()).sum::<f64>() / self.tracks.len() as f64
+    }
+
+    /// Return the alignment of the track with the lowest similarity.
+    pub fn worst(&self) -> Option<&TrackAlignment> {
+        self.tracks
+            .iter()
+            .min_by(|a, b| a.similarity().total_cmp(&b.similarity()))
+    }
+
+    /// Returns `true` if any tracks were aligned and their mean similarity is at least
+    /// `threshold`. [SAME_DISK_THRESHOLD] is a reasonable default.
+    pub fn is_same_disk(&self, threshold: f64) -> bool {
+        !self.tracks.is_empty() && self.mean_similarity() >= threshold
+    }
+}
+
+impl Display for ImageAlignment {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        for track in &self.tracks {
+            writeln!(f, "{}", track)?;
+        }
+        for ch in &self.unaligned {
+            writeln!(f, "{}: not aligned", ch)?;
+        }
+        write!(f, "Mean similarity: {:.2}%", self.mean_similarity() * 100.0)
+    }
+}
+
+/// A track bitstream packed into 64-bit words for fast comparison. The bitstream is packed
+/// twice over so that a word can be read at any position without handling wraparound.
+struct PackedTrack {
+    len: usize,
+    bits: Vec<u64>,
+    /// Bits that should not be compared, i.e. weak bits.
+    masked: Vec<u64>,
+    has_weak: bool,
+}
+
+impl PackedTrack {
+    fn new(bits: &BitVec, weak: &BitVec) -> Self {
+        let len = bits.len();
+        let word_ct = (2 * len).div_ceil(64) + 1;
+        let mut packed = PackedTrack {
+            len,
+            bits: vec![0; word_ct],
+            masked: vec![0; word_ct],
+            has_weak: weak.any(),
+        };
+        for i in 0..(word_ct * 64) {
+            let bit = 1u64 << (63 - (i % 64));
+            if bits[i % len] {
+                packed.bits[i / 64] |= bit;
+            }
+            if packed.has_weak && weak.get(i % len).unwrap_or(false) {
+                packed.masked[i / 64] |= bit;
+            }
+        }
+        packed
+    }
+
+    /// Read 64 bits starting at bit `pos`, which must be less than `len`.
+    #[inline]
+    fn word(words: &[u64], pos: usize) -> u64 {
+        let (w, shift) = (pos / 64, pos % 64);
+        if shift == 0 {
+            words[w]
+        }
+        else {
+            (words[w] << shift) | (words[w + 1] >> (64 - shift))
+        }
+    }
+
+    /// Compare `words` words of `self` starting at `pos` against `other` starting at
+    /// `other_pos`, returning the number of (compared, matching) bits.
+    #[inline]
+    fn compare(&self, pos: usize, other: &PackedTrack, other_pos: usize, words: usize) -> (usize, usize) {
+        // Both tracks are packed twice over, so reading up to one track length past a position
+        // within the track needs no wraparound.
+        let (pos, other_pos) = (pos % self.len, other_pos % other.len);
+        let (mut compared, mut matching) = (0, 0);
+        for i in 0..words {
+            let (p, q) = (pos + i * 64, other_pos + i * 64);
+            let valid = match self.has_weak || other.has_weak {
+                true => !(Self::word(&self.masked, p) | Self::word(&other.masked, q)),
+                false => u64::MAX,
+            };
+            let same = !(Self::word(&self.bits, p) ^ Self::word(&other.bits, q)) & valid;
+            compared += valid.count_ones() as usize;
+            matching += same.count_ones() as usize;
+        }
+        (compared, matching)
+    }
+}
+
+/// Align the bitstream of `right` against the bitstream of `left`, returning the rotational
+/// offset with the highest similarity. Returns `None` if either track has no bitstream, or is
+/// too short to align.
+///
+/// Candidate offsets are taken from the positions of address markers when the tracks have them,
+/// and otherwise from a search of every offset using a few short windows of the left track. Each
+/// candidate is then measured over the whole track, in blocks that may each shift by a few bits
+/// to follow speed variance between the dumps.
+pub fn align_tracks(left: &dyn Track, right: &dyn Track) -> Option<TrackAlignment> {
+    let (left_stream, right_stream) = (left.stream()?, right.stream()?);
+    if left_stream.len() < BLOCK_BITS || right_stream.len() < BLOCK_BITS {
+        return None;
+    }
+    let lp = PackedTrack::new(left_stream.data(), left_stream.weak_mask());
+    let rp = PackedTrack::new(right_stream.data(), right_stream.weak_mask());
+
+    let mut candidates = marker_candidates(left, right, rp.len);
+    if candidates.is_empty() {
+        candidates = window_candidates(&lp, &rp);
+    }
+
+    let (offset, compared_bits, matching_bits) = candidates
+        .into_iter()
+        .map(|offset| {
+            let (compared, matching) = measure(&lp, &rp, offset);
+            (offset, compared, matching)
+        })
+        .max_by_key(|&(offset, _, matching)| (matching, std::cmp::Reverse(offset)))?;
+
+    Some(TrackAlignment {
+        ch: left.ch(),
+        left_len: lp.len,
+        right_len: rp.len,
+        offset,
+        compared_bits,
+        matching_bits,
+    })
+}
+
+/// Return the offsets that line up the first marker of `left` with each marker of `right`.
+fn marker_candidates(left: &dyn Track, right: &dyn Track, right_len: usize) -> Vec<usize> {
+    let markers = |track: &dyn Track| -> Vec<usize> {
+        track
+            .metadata()
+            .map(|m| {
+                m.elements()
+                    .iter()
+                    .filter(|item| item.element.is_marker())
+                    .map(|item| item.start)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let Some(&first) = markers(left).first()
+    else {
+        return Vec::new();
+    };
+    let scaled = scale(first, left.stream().map(|s| s.len()).unwrap_or(1), right_len);
+    markers(right)
+        .into_iter()
+        .map(|pos| (pos + right_len - scaled % right_len) % right_len)
+        .collect()
+}
+
+/// Search every offset using a few windows of the left track, returning the best scoring.
+fn window_candidates(left: &PackedTrack, right: &PackedTrack) -> Vec<usize> {
+    let windows: Vec<(usize, usize)> = (0..WINDOW_CT)
+        .map(|i| {
+            let pos = i * left.len / WINDOW_CT;
+            (pos, scale(pos, left.len, right.len))
+        })
+        .collect();
+
+    let mut scores: Vec<(usize, usize)> = (0..right.len)
+        .map(|offset| {
+            let matching = windows
+                .iter()
+                .map(|&(pos, scaled)| left.compare(pos, right, scaled + offset, WINDOW_WORDS).1)
+                .sum();
+            (matching, offset)
+        })
+        .collect();
+    scores.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scores
+        .into_iter()
+        .take(WINDOW_CANDIDATES)
+        .map(|(_, offset)| offset)
+        .collect()
+}
+
+/// Measure the similarity of `left` against `right` at `offset`, returning the number of
+/// (compared, matching) bits.
+fn measure(left: &PackedTrack, right: &PackedTrack, offset: usize) -> (usize, usize) {
+    let (mut compared, mut matching) = (0, 0);
+    let mut drift: isize = 0;
+    for block in 0..(left.len / BLOCK_BITS) {
+        let pos = block * BLOCK_BITS;
+        let expected = (scale(pos, left.len, right.len) + offset) as isize + drift;
+
+        let (best_shift, (block_compared, block_matching)) = (-BLOCK_SLACK..=BLOCK_SLACK)
+            .map(|shift| {
+                let right_pos = (expected + shift).rem_euclid(right.len as isize) as usize;
+                (shift, left.compare(pos, right, right_pos, BLOCK_WORDS))
+            })
+            .max_by_key(|&(shift, (_, matching))| (matching, std::cmp::Reverse(shift.abs())))
+            .unwrap_or_default();
+
+        drift += best_shift;
+        compared += block_compared;
+        matching += block_matching;
+    }
+    (compared, matching)
+}
+
+/// Scale a bit position in a track of `from_len` bits to a track of `to_len` bits.
+fn scale(pos: usize, from_len: usize, to_len: usize) -> usize {
+    (pos as u64 * to_len as u64 / from_len.max(1) as u64) as usize
+}
+
+impl DiskImage {
+    /// Align the bitstream of every track of `other` against the same track of this image. See
+    /// [align_tracks()].
+    pub fn align(&self, other: &DiskImage) -> ImageAlignment {
+        let mut alignment = ImageAlignment::default();
+
+        for head in 0..2u8 {
+            let cylinders = self.track_map[head as usize]
+                .len()
+                .max(other.track_map[head as usize].len());
+
+            for cylinder in 0..cylinders {
+                let ch = DiskCh::new(cylinder as u16, head);
+                let result = match (self.track(ch), other.track(ch)) {
+                    (Some(left), Some(right)) => align_tracks(left.as_ref(), right.as_ref()),
+                    _ => None,
+                };
+                match result {
+                    Some(track) => alignment.tracks.push(track),
+                    None => alignment.unaligned.push(ch),
+                }
+            }
+        }
+        alignment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, random::SeededRng, test_util::formatted_disk};
+
+    /// Replace the bits of a track and rescan it.
+    fn replace_bits(disk: &mut DiskImage, ch: DiskCh, f: impl FnOnce(&BitVec) -> BitVec) {
+        let track = disk.track_mut(ch).unwrap();
+        let bits = f(track.stream().unwrap().data());
+        track.stream_mut().unwrap().replace(bits);
+        track.as_bitstream_track_mut().unwrap().rescan(None).unwrap();
+    }
+
+    #[test]
+    fn test_align_tracks() {
+        let left = formatted_disk();
+        let mut right = formatted_disk();
+        let ch = DiskCh::new(2, 0);
+
+        // Rotate the right track, and insert a few bits partway through to simulate a slightly
+        // slower drive.
+        let rotation = 12345;
+        replace_bits(&mut right, ch, |bits| {
+            let len = bits.len();
+            let mut rotated = BitVec::new();
+            for i in 0..len {
+                rotated.push(bits[(i + len - rotation) % len]);
+                if i == len / 2 {
+                    (0..4).for_each(|_| rotated.push(false));
+                }
+            }
+            rotated
+        });
+
+        let alignment = align_tracks(left.track(ch).unwrap().as_ref(), right.track(ch).unwrap().as_ref()).unwrap();
+        assert_eq!(alignment.right_len, alignment.left_len + 4);
+        assert!(alignment.offset.abs_diff(rotation) <= 1, "{}", alignment);
+        assert!(alignment.similarity() > 0.99, "{}", alignment);
+
+        let image_alignment = left.align(&right);
+        assert!(image_alignment.unaligned.is_empty());
+        assert_eq!(image_alignment.tracks.len(), 80);
+        assert!(image_alignment.is_same_disk(SAME_DISK_THRESHOLD));
+        assert_eq!(image_alignment.worst().unwrap().ch, ch);
+    }
+
+    #[test]
+    fn test_align_different_tracks() {
+        let left = formatted_disk();
+        let mut right = formatted_disk();
+        let ch = DiskCh::new(0, 1);
+
+        let mut rng = SeededRng::new(1);
+        replace_bits(&mut right, ch, |bits| {
+            (0..bits.len()).map(|_| rng.below(2) == 1).collect()
+        });
+
+        let alignment = align_tracks(left.track(ch).unwrap().as_ref(), right.track(ch).unwrap().as_ref()).unwrap();
+        assert!(alignment.similarity() < 0.75, "{}", alignment);
+    }
+}
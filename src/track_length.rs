@@ -0,0 +1,445 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/track_length.rs
+
+    Measures the bitcell length of each track against its nominal length, and
+    resizes track bitstreams to a target length for fixed-length formats.
+*/
+
+//! Track length measurement and normalization.
+//!
+//! The number of bitcells on a track depends on the data rate and the rotation rate of the drive
+//! that wrote it, so real tracks are rarely exactly their nominal length. Some copy protection
+//! schemes go further and write deliberately long or short tracks, by writing at a slightly
+//! different data rate. [DiskImage::track_lengths()] measures the exact bitcell count of every
+//! track against its nominal length, so that such tracks can be found.
+//!
+//! Some image formats store every track at a fixed length. [DiskImage::normalize_track_lengths()]
+//! resizes track bitstreams to a target bitcell count by growing or shrinking the largest gap on
+//! each track, which leaves the sectors on the track intact, and reports which tracks were
+//! altered.
+//!
+//! Only tracks with a bitstream can be measured, and only `BitStream` resolution tracks can be
+//! resized.
+
+use crate::{
+    bitstream_codec::{fm::FmCodec, mfm::MfmCodec, TrackDataStream},
+    track::{bitstream::BitStreamTrack, Track},
+    types::{DiskCh, TrackDataEncoding, TrackDensity},
+    DiskImage,
+    DiskImageError,
+};
+use bit_vec::BitVec;
+use std::fmt::{self, Display, Formatter};
+
+/// The default tolerance for classifying a track as long or short, as a fraction of its nominal
+/// length. Drive speed variance alone is usually within 1-2%.
+pub const LENGTH_TOLERANCE: f64 = 0.03;
+
+/// The period of the pattern repeated to lengthen a gap, in bitcells. Gap bytes are repeated, and
+/// one encoded byte is 16 bitcells in both FM and MFM.
+const GAP_PERIOD: usize = 16;
+
+/// The number of bitcells left untouched at either end of a gap when shrinking it, so that the
+/// sync field before the following marker survives.
+const GAP_MARGIN: usize = 16 * 16;
+
+/// The measured length of a track.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackLength {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    /// The length of the track, in bitcells.
+    pub bit_length: usize,
+    /// The nominal length of the track for its density and rotation rate, in bitcells, if known.
+    pub nominal: Option<usize>,
+}
+
+impl TrackLength {
+    /// Return the deviation of the track length from its nominal length, as a fraction of the
+    /// nominal length. A positive value indicates a long track. Returns `None` if the nominal
+    /// length is not known.
+    pub fn deviation(&self) -> Option<f64> {
+        self.nominal
+            .filter(|&nominal| nominal > 0)
+            .map(|nominal| (self.bit_length as f64 - nominal as f64) / nominal as f64)
+    }
+
+    /// Returns `true` if the track is longer than its nominal length by more than `tolerance`.
+    /// [LENGTH_TOLERANCE] is a reasonable default.
+    pub fn is_long(&self, tolerance: f64) -> bool {
+        self.deviation().is_some_and(|d| d > tolerance)
+    }
+
+    /// Returns `true` if the track is shorter than its nominal length by more than `tolerance`.
+    /// [LENGTH_TOLERANCE] is a reasonable default.
+    pub fn is_short(&self, tolerance: f64) -> bool {
+        self.deviation().is_some_and(|d| d < -tolerance)
+    }
+}
+
+impl Display for TrackLength {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: {} bitcells", self.ch, self.bit_length)?;
+        if let (Some(nominal), Some(deviation)) = (self.nominal, self.deviation()) {
+            write!(f, " (nominal {}, {:+.2}%)", nominal, deviation * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// The measured length of every track of a [DiskImage], as returned by
+/// [DiskImage::track_lengths()].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackLengthReport {
+    /// The length of each track with a bitstream.
+    pub tracks: Vec<TrackLength>,
+    /// Tracks that could not be measured because they have no bitstream.
+    pub unmeasured: Vec<DiskCh>,
+}
+
+impl TrackLengthReport {
+    /// Return the tracks longer than their nominal length by more than `tolerance`.
+    pub fn long_tracks(&self, tolerance: f64) -> impl Iterator<Item = &TrackLength> {
+        self.tracks.iter().filter(move |t| t.is_long(tolerance))
+    }
+
+    /// Return the tracks shorter than their nominal length by more than `tolerance`.
+    pub fn short_tracks(&self, tolerance: f64) -> impl Iterator<Item = &TrackLength> {
+        self.tracks.iter().filter(move |t| t.is_short(tolerance))
+    }
+
+    /// Return the length of the longest track, in bitcells.
+    pub fn max_bit_length(&self) -> Option<usize> {
+        self.tracks.iter().map(|t| t.bit_length).max()
+    }
+
+    /// Return the length of the shortest track, in bitcells.
+    pub fn min_bit_length(&self) -> Option<usize> {
+        self.tracks.iter().map(|t| t.bit_length).min()
+    }
+}
+
+impl Display for TrackLengthReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for track in &self.tracks {
+            writeln!(f, "{}", track)?;
+        }
+        for ch in &self.unmeasured {
+            writeln!(f, "{}: not measured", ch)?;
+        }
+        Ok(())
+    }
+}
+
+/// A track resized by [DiskImage::normalize_track_lengths()].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResizedTrack {
+    /// The physical cylinder and head of the track.
+    pub ch: DiskCh,
+    /// The length of the track before resizing, in bitcells.
+    pub old_length: usize,
+    /// The length of the track after resizing, in bitcells.
+    pub new_length: usize,
+    /// The bitcell offset at which bitcells were inserted or removed, in the original track.
+    pub offset: usize,
+}
+
+impl Display for ResizedTrack {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {} bitcells at offset {}",
+            self.ch, self.old_length, self.new_length, self.offset
+        )
+    }
+}
+
+/// The result of [DiskImage::normalize_track_lengths()].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NormalizeReport {
+    /// The tracks that were resized.
+    pub resized:   Vec<ResizedTrack>,
+    /// The tracks that were already the target length.
+    pub unchanged: Vec<DiskCh>,
+    /// Tracks that could not be resized, because they have no bitstream, their target length is
+    /// not known, or they have no gap large enough to shrink.
+    pub skipped:   Vec<DiskCh>,
+}
+
+impl NormalizeReport {
+    /// Returns `true` if any track was resized.
+    pub fn is_altered(&self) -> bool {
+        !self.resized.is_empty()
+    }
+}
+
+impl Display for NormalizeReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for track in &self.resized {
+            writeln!(f, "{}", track)?;
+        }
+        for ch in &self.skipped {
+            writeln!(f, "{}: skipped", ch)?;
+        }
+        write!(
+            f,
+            "{} resized, {} unchanged, {} skipped",
+            self.resized.len(),
+            self.unchanged.len(),
+            self.skipped.len()
+        )
+    }
+}
+
+/// Return the nominal length of `track` in bitcells, from its density and rotation rate.
+fn nominal_length(track: &dyn Track) -> Option<usize> {
+    let info = track.info();
+    info.density
+        .unwrap_or_else(|| TrackDensity::from(info.data_rate))
+        .bitcells(info.rpm)
+}
+
+/// Find the largest gap on `track` that is not covered by a metadata element, returning its start
+/// offset and length in bitcells. The gap may wrap around the index. A track with no elements is
+/// one gap starting at the index.
+fn largest_gap(track: &BitStreamTrack, len: usize) -> (usize, usize) {
+    let mut spans: Vec<(usize, usize)> = track
+        .metadata
+        .elements()
+        .iter()
+        .map(|item| (item.start.min(len), item.end.min(len)))
+        .collect();
+    if spans.is_empty() {
+        return (0, len);
+    }
+    spans.sort_unstable();
+
+    let mut best = (0, 0);
+    let mut covered_to = spans[0].1;
+    for &(start, end) in &spans[1..] {
+        if start > covered_to && start - covered_to > best.1 {
+            best = (covered_to, start - covered_to);
+        }
+        covered_to = covered_to.max(end);
+    }
+    // The gap from the last element around the index to the first element.
+    let wrap = len - covered_to.min(len) + spans[0].0;
+    if wrap > best.1 {
+        best = (covered_to % len.max(1), wrap);
+    }
+    best
+}
+
+/// Resize `bits` to `target` bitcells at `offset`, inserting copies of the `GAP_PERIOD` bitcells
+/// preceding `offset` or removing bitcells starting at `offset`. `offset` may wrap around the
+/// index, in which case bitcells are removed from both ends of the track.
+fn resize_bits(bits: &BitVec, offset: usize, target: usize) -> BitVec {
+    let len = bits.len();
+    if target >= len {
+        let insert = target - len;
+        let pattern_start = offset + len - GAP_PERIOD.min(len);
+        let mut resized = BitVec::with_capacity(target);
+        resized.extend(bits.iter().take(offset));
+        resized.extend((0..insert).map(|i| bits[(pattern_start + i % GAP_PERIOD.min(len)) % len]));
+        resized.extend(bits.iter().skip(offset));
+        resized
+    }
+    else {
+        let end = offset + (len - target);
+        bits.iter()
+            .enumerate()
+            .filter(|&(i, _)| !(offset..end).contains(&i) && !(offset..end).contains(&(i + len)))
+            .map(|(_, bit)| bit)
+            .collect()
+    }
+}
+
+/// Resize the bitstream of `track` to `target` bitcells within its largest gap, and rescan it.
+/// Returns the offset of the change, or `None` if the largest gap is too small to shrink.
+/// Returns [DiskImageError::BitstreamError] if the track bitstream is empty.
+fn resize_track(track: &mut BitStreamTrack, target: usize) -> Result<Option<usize>, DiskImageError> {
+    let len = track.data.len();
+    if len == 0 {
+        return Err(DiskImageError::BitstreamError);
+    }
+    let (gap_start, gap_len) = largest_gap(track, len);
+
+    let offset = match target.cmp(&len) {
+        std::cmp::Ordering::Less => {
+            let remove = len - target;
+            if gap_len < remove + 2 * GAP_MARGIN {
+                return Ok(None);
+            }
+            // Remove from the middle of the gap, aligned to the period of the gap pattern.
+            gap_start + ((gap_len - remove) / 2) / GAP_PERIOD * GAP_PERIOD
+        }
+        _ => {
+            // Insert at the middle of the gap, after a whole number of gap pattern periods.
+            gap_start + (gap_len / 2).max(GAP_PERIOD) / GAP_PERIOD * GAP_PERIOD
+        }
+    } % len;
+
+    let bits = resize_bits(track.data.data(), offset, target);
+    let weak = resize_bits(track.data.weak_mask(), offset, target);
+    // Inserted bitcells copy the weak mask of the gap pattern they copy, which is what we want.
+    let data: TrackDataStream = match track.encoding {
+        TrackDataEncoding::Mfm => Box::new(MfmCodec::new(bits, None, Some(weak))),
+        TrackDataEncoding::Fm => Box::new(FmCodec::new(bits, None, Some(weak))),
+        _ => return Ok(None),
+    };
+    track.data = data;
+    track.rescan(track.schema)?;
+    Ok(Some(offset))
+}
+
+impl DiskImage {
+    /// Measure the length of every track in the image against its nominal length. See the
+    /// [module documentation](crate::track_length).
+    pub fn track_lengths(&self) -> TrackLengthReport {
+        let mut report = TrackLengthReport::default();
+        for (ch, track) in self.iter_tracks() {
+            match track.stream() {
+                Some(stream) => report.tracks.push(TrackLength {
+                    ch,
+                    bit_length: stream.len(),
+                    nominal: nominal_length(track),
+                }),
+                None => report.unmeasured.push(ch),
+            }
+        }
+        report
+    }
+
+    /// Resize the bitstream of every `BitStream` resolution track to `target` bitcells, or to its
+    /// nominal length if `target` is `None`, by growing or shrinking the largest gap on the track.
+    /// Sectors are left intact, but any protection that depends on the length of a track will not
+    /// survive.
+    ///
+    /// Returns a [NormalizeReport] listing which tracks were resized.
+    pub fn normalize_track_lengths(&mut self, target: Option<usize>) -> Result<NormalizeReport, DiskImageError> {
+        let mut report = NormalizeReport::default();
+        for (ch, track) in self.iter_tracks_mut() {
+            let target = target.or_else(|| nominal_length(track));
+            let (Some(target), Some(bitstream)) = (target, track.as_bitstream_track_mut())
+            else {
+                report.skipped.push(ch);
+                continue;
+            };
+
+            let old_length = bitstream.data.len();
+            if old_length == target {
+                report.unchanged.push(ch);
+                continue;
+            }
+            if old_length == 0 || target == 0 {
+                report.skipped.push(ch);
+                continue;
+            }
+
+            match resize_track(bitstream, target)? {
+                Some(offset) => report.resized.push(ResizedTrack {
+                    ch,
+                    old_length,
+                    new_length: bitstream.data.len(),
+                    offset,
+                }),
+                None => {
+                    log::warn!(
+                        "normalize_track_lengths(): Track {} has no gap large enough to remove {} bitcells",
+                        ch,
+                        old_length - target
+                    );
+                    report.skipped.push(ch);
+                }
+            }
+        }
+        if report.is_altered() {
+            self.update_analysis();
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_util::formatted_disk};
+
+    fn read_sectors(disk: &DiskImage, ch: DiskCh) -> Vec<Vec<u8>> {
+        let track = disk.track(ch).unwrap();
+        track
+            .sector_list()
+            .iter()
+            .map(|s| {
+                let result = track
+                    .read_sector(s.chsn.into(), None, None, RwScope::DataOnly, false)
+                    .unwrap();
+                assert!(!result.address_crc_error && !result.data_crc_error);
+                result.read_buf[result.data_range].to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_track_lengths() {
+        let mut disk = formatted_disk();
+        let ch = DiskCh::new(3, 1);
+        let nominal = disk.track_lengths().tracks[0].nominal.unwrap();
+        let sectors = read_sectors(&disk, ch);
+
+        // Lengthen every track by 5%, as a mastering tool running a slow drive might.
+        let long = nominal + nominal / 20;
+        let report = disk.normalize_track_lengths(Some(long)).unwrap();
+        assert_eq!(report.resized.len(), 80);
+
+        let lengths = disk.track_lengths();
+        assert!(lengths.tracks.iter().all(|t| t.bit_length == long));
+        assert_eq!(lengths.long_tracks(LENGTH_TOLERANCE).count(), 80);
+        assert_eq!(read_sectors(&disk, ch), sectors);
+
+        // Normalize back to the nominal length, and then shorten each track. Lengths are kept even,
+        // since an odd bitcell count would flip the MFM clock phase at the index.
+        let report = disk.normalize_track_lengths(None).unwrap();
+        assert_eq!(report.resized.len(), 80);
+        assert_eq!(disk.track_lengths().long_tracks(LENGTH_TOLERANCE).count(), 0);
+        assert_eq!(read_sectors(&disk, ch), sectors);
+
+        let report = disk.normalize_track_lengths(Some(nominal - 1000)).unwrap();
+        assert!(report.resized.iter().all(|t| t.new_length == nominal - 1000));
+        assert_eq!(read_sectors(&disk, ch), sectors);
+
+        // Shrinking past the available gap leaves tracks alone.
+        let report = disk.normalize_track_lengths(Some(nominal / 2)).unwrap();
+        assert!(!report.is_altered());
+        assert_eq!(report.skipped.len(), 80);
+    }
+}
@@ -0,0 +1,349 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+
+    src/track_mapping.rs
+
+    Maps the physical cylinders a disk image was captured from to the logical
+    tracks recorded on the disk.
+*/
+
+//! A mapping between the physical cylinders a [DiskImage] was captured from and the logical
+//! tracks recorded on the disk.
+//!
+//! Disk images store each track at the cylinder the drive was stepped to when it was read. This
+//! is not always the track number recorded in its sector IDs. A 40-track disk captured in an
+//! 80-track drive stores logical track 1 at physical cylinder 2, and some disks are recorded
+//! starting one cylinder in from where the drive expects track 0. A [TrackMapping] describes
+//! these relationships so that the tracks of an image can be resolved to logical tracks with
+//! [DiskImage::resolve_physical()], and logical tracks found in the image with
+//! [DiskImage::resolve_logical()].
+
+use crate::{prelude::DiskCh, DiskImage, FoxHashMap};
+use std::fmt::{Display, Formatter, Result};
+
+/// How the sectors of a disk are located relative to the index hole.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sectoring {
+    /// Sectors are located by address marks written to the track. The disk has a single index
+    /// hole.
+    #[default]
+    Soft,
+    /// Sectors are located by holes punched in the disk, one per sector. The disk has `holes`
+    /// sector holes plus an additional index hole.
+    Hard { holes: u8 },
+}
+
+impl Display for Sectoring {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Sectoring::Soft => write!(f, "Soft-sectored"),
+            Sectoring::Hard { holes } => write!(f, "Hard-sectored ({} holes)", holes),
+        }
+    }
+}
+
+impl Sectoring {
+    /// Infer the [Sectoring] of a disk from the times between successive index pulses of a flux
+    /// capture, in seconds.
+    ///
+    /// A soft-sectored disk pulses once per revolution. A hard-sectored disk pulses at every
+    /// sector hole, with the index hole midway between two sector holes splitting one interval
+    /// in two. The sector holes are counted between two such split intervals.
+    pub fn from_index_times(index_times: &[f64]) -> Sectoring {
+        // No drive spins faster than 600RPM, so any slower pulse is a full revolution.
+        const MIN_REVOLUTION_TIME: f64 = 0.1;
+
+        if index_times.len() < 2 {
+            return Sectoring::Soft;
+        }
+        let mut sorted = index_times.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let median = sorted[sorted.len() / 2];
+        if median >= MIN_REVOLUTION_TIME {
+            return Sectoring::Soft;
+        }
+
+        // Find the first of each pair of short intervals around the index hole.
+        let is_short = |i: usize| index_times[i] < median * 0.75;
+        let index_holes: Vec<usize> = (0..index_times.len())
+            .filter(|&i| is_short(i) && (i == 0 || !is_short(i - 1)))
+            .collect();
+
+        let holes = match index_holes.as_slice() {
+            // A revolution holds one interval per sector hole, plus one for the split interval.
+            [first, second, ..] => second - first - 1,
+            // Without two index holes we can only estimate the count for a 300RPM drive.
+            _ => (0.2 / median).round() as usize,
+        };
+        Sectoring::Hard {
+            holes: holes.min(u8::MAX as usize) as u8,
+        }
+    }
+}
+
+/// A [TrackMapping] describes how the physical cylinders a [DiskImage] stores its tracks at
+/// correspond to the logical tracks recorded on the disk.
+///
+/// A physical cylinder `p` maps to the logical track `(p - cylinder_offset) / step`, if that
+/// division is exact.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackMapping {
+    /// The number of physical cylinders stepped per logical track. This is 2 for a 40-track disk
+    /// in an 80-track drive, and 1 otherwise.
+    pub step: u8,
+    /// The physical cylinder containing logical track 0.
+    pub cylinder_offset: i16,
+    /// Whether the disk is soft or hard-sectored.
+    pub sectoring: Sectoring,
+}
+
+impl Default for TrackMapping {
+    fn default() -> Self {
+        TrackMapping {
+            step: 1,
+            cylinder_offset: 0,
+            sectoring: Sectoring::Soft,
+        }
+    }
+}
+
+impl TrackMapping {
+    /// Return a mapping for a 40-track disk read in an 80-track drive.
+    pub fn double_step() -> Self {
+        TrackMapping {
+            step: 2,
+            ..Default::default()
+        }
+    }
+
+    /// Return a copy of this mapping with the specified cylinder offset.
+    pub fn with_offset(self, cylinder_offset: i16) -> Self {
+        TrackMapping {
+            cylinder_offset,
+            ..self
+        }
+    }
+
+    /// Return a copy of this mapping with the specified [Sectoring].
+    pub fn with_sectoring(self, sectoring: Sectoring) -> Self {
+        TrackMapping { sectoring, ..self }
+    }
+
+    /// Return true if this mapping maps each physical cylinder directly to the image track of the
+    /// same number.
+    pub fn is_identity(&self) -> bool {
+        self.step <= 1 && self.cylinder_offset == 0
+    }
+
+    /// Return the logical cylinder at physical cylinder `c`, or None if the physical cylinder
+    /// lies before logical track 0 or between two logical tracks.
+    pub fn logical_cylinder(&self, c: u16) -> Option<u16> {
+        let step = self.step.max(1) as i32;
+        let relative = c as i32 - self.cylinder_offset as i32;
+        if relative < 0 || relative % step != 0 {
+            return None;
+        }
+        u16::try_from(relative / step).ok()
+    }
+
+    /// Return the physical cylinder containing logical cylinder `c`, or None if it cannot be
+    /// reached by the drive.
+    pub fn physical_cylinder(&self, c: u16) -> Option<u16> {
+        let physical = c as i32 * self.step.max(1) as i32 + self.cylinder_offset as i32;
+        u16::try_from(physical).ok()
+    }
+}
+
+impl Display for TrackMapping {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "step: {}, cylinder offset: {}, {}",
+            self.step, self.cylinder_offset, self.sectoring
+        )
+    }
+}
+
+impl DiskImage {
+    /// Return the [TrackMapping] of this image.
+    pub fn track_mapping(&self) -> TrackMapping {
+        self.track_mapping
+    }
+
+    /// Set the [TrackMapping] of this image.
+    pub fn set_track_mapping(&mut self, mapping: TrackMapping) {
+        self.track_mapping = mapping;
+    }
+
+    /// Resolve the image track at physical cylinder and head `ch` to the logical track recorded
+    /// there, according to the image's [TrackMapping]. Returns None if the image has no track at
+    /// `ch`, or the track lies before logical track 0 or between two logical tracks.
+    pub fn resolve_physical(&self, ch: DiskCh) -> Option<DiskCh> {
+        self.track(ch)?;
+        Some(DiskCh::new(self.track_mapping.logical_cylinder(ch.c())?, ch.h()))
+    }
+
+    /// Resolve logical track `ch` to the physical cylinder and head of the image track it is
+    /// stored at, according to the image's [TrackMapping]. Returns None if the image has no
+    /// track at that position.
+    pub fn resolve_logical(&self, ch: DiskCh) -> Option<DiskCh> {
+        let physical = DiskCh::new(self.track_mapping.physical_cylinder(ch.c())?, ch.h());
+        self.track(physical).map(|_| physical)
+    }
+
+    /// Infer a [TrackMapping] for this image by comparing the cylinder numbers in each track's
+    /// sector IDs with the physical cylinder the track is stored at.
+    ///
+    /// For example, a 40-track disk captured in an 80-track drive stores the sectors with
+    /// cylinder ID 1 at cylinder 2, and is inferred to be double-stepped. If no mapping accounts
+    /// for more than half of the formatted tracks, the identity mapping is returned. The
+    /// [Sectoring] of the current mapping is retained.
+    pub fn infer_track_mapping(&self) -> TrackMapping {
+        let mut candidates: FoxHashMap<(u8, i16), usize> = FoxHashMap::new();
+        let mut formatted_tracks = 0;
+
+        for track in self.track_iter() {
+            // Use the most common cylinder ID on the track, to tolerate the odd bad or
+            // deliberately mismatched sector ID.
+            let mut ids: FoxHashMap<u16, usize> = FoxHashMap::new();
+            for sector in track.sector_list() {
+                *ids.entry(sector.chsn.c()).or_default() += 1;
+            }
+            let Some((&id_c, _)) = ids.iter().max_by_key(|(c, count)| (**count, std::cmp::Reverse(**c)))
+            else {
+                continue;
+            };
+            formatted_tracks += 1;
+
+            let c = track.ch().c() as i32;
+            for step in [1u8, 2] {
+                let offset = c - id_c as i32 * step as i32;
+                if let Ok(offset) = i16::try_from(offset) {
+                    *candidates.entry((step, offset)).or_default() += 1;
+                }
+            }
+        }
+
+        let best = candidates
+            .into_iter()
+            // Prefer the most tracks, then single stepping, then the smallest offset.
+            .max_by_key(|((step, offset), count)| (*count, std::cmp::Reverse(*step), std::cmp::Reverse(offset.abs())));
+
+        match best {
+            Some(((step, cylinder_offset), count)) if count * 2 > formatted_tracks => TrackMapping {
+                step,
+                cylinder_offset,
+                sectoring: self.track_mapping.sectoring,
+            },
+            _ => TrackMapping {
+                sectoring: self.track_mapping.sectoring,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prelude::*,
+        test_util::{add_sector, add_track, empty_disk},
+    };
+
+    // Build an image with `cylinders` tracks on head 0, where track `c` contains a single
+    // sector with the cylinder ID returned by `id_c`, or no sectors if `id_c` returns None.
+    fn test_disk(cylinders: u16, id_c: impl Fn(u16) -> Option<u16>) -> DiskImage {
+        let mut disk = empty_disk();
+        for c in 0..cylinders {
+            let track = add_track(&mut disk, DiskCh::new(c, 0));
+            if let Some(id_c) = id_c(c) {
+                add_sector(track, DiskChsn::new(id_c, 0, 1, 2), &[0xF6; 512], Default::default());
+            }
+        }
+        disk
+    }
+
+    #[test]
+    fn test_track_mapping() {
+        let mapping = TrackMapping::double_step().with_offset(1);
+        assert_eq!(mapping.logical_cylinder(0), None);
+        assert_eq!(mapping.logical_cylinder(1), Some(0));
+        assert_eq!(mapping.logical_cylinder(2), None);
+        assert_eq!(mapping.logical_cylinder(5), Some(2));
+        assert_eq!(mapping.physical_cylinder(2), Some(5));
+
+        let disk = test_disk(40, Some);
+        assert_eq!(disk.infer_track_mapping(), TrackMapping::default());
+        assert_eq!(disk.resolve_physical(DiskCh::new(39, 0)), Some(DiskCh::new(39, 0)));
+        assert_eq!(disk.resolve_logical(DiskCh::new(39, 0)), Some(DiskCh::new(39, 0)));
+
+        // The mapping inferred from an image is the one its tracks resolve through.
+        let mut disk = test_disk(80, |c| (c % 2 == 0).then_some(c / 2));
+        disk.set_track_mapping(disk.infer_track_mapping());
+        assert_eq!(disk.resolve_physical(DiskCh::new(78, 0)), Some(DiskCh::new(39, 0)));
+        assert_eq!(disk.resolve_physical(DiskCh::new(79, 0)), None);
+        assert_eq!(disk.resolve_physical(DiskCh::new(80, 0)), None);
+        assert_eq!(disk.resolve_logical(DiskCh::new(39, 0)), Some(DiskCh::new(78, 0)));
+        assert_eq!(disk.resolve_logical(DiskCh::new(40, 0)), None);
+        for c in 0..40 {
+            let physical = disk.resolve_logical(DiskCh::new(c, 0)).unwrap();
+            let track = disk.track(physical).unwrap();
+            assert_eq!(track.sector_list()[0].chsn.c(), c);
+        }
+    }
+
+    #[test]
+    fn test_infer_track_mapping() {
+        // A 40-track disk captured in an 80-track drive, with the odd cylinders unformatted.
+        let disk = test_disk(80, |c| (c % 2 == 0).then_some(c / 2));
+        assert_eq!(disk.infer_track_mapping(), TrackMapping::double_step());
+
+        // A disk recorded one cylinder in from track 0.
+        let disk = test_disk(41, |c| c.checked_sub(1));
+        assert_eq!(disk.infer_track_mapping(), TrackMapping::default().with_offset(1));
+    }
+
+    #[test]
+    fn test_sectoring_from_index_times() {
+        assert_eq!(Sectoring::from_index_times(&[0.2; 3]), Sectoring::Soft);
+
+        // Two revolutions of a 10-hole disk at 300RPM, with the index hole splitting a sector
+        // interval after the second sector hole.
+        let revolution = [[0.02; 2].as_slice(), &[0.01; 2], &[0.02; 7]].concat();
+        let index_times = revolution.repeat(2);
+        assert_eq!(Sectoring::from_index_times(&index_times), Sectoring::Hard { holes: 10 });
+
+        // A capture too short to contain two index holes is estimated from the sector interval.
+        assert_eq!(Sectoring::from_index_times(&[0.02; 8]), Sectoring::Hard { holes: 10 });
+
+        let mut disk = test_disk(80, |c| (c % 2 == 0).then_some(c / 2));
+        disk.set_track_mapping(TrackMapping::default().with_sectoring(Sectoring::Hard { holes: 10 }));
+        assert_eq!(disk.infer_track_mapping().sectoring, Sectoring::Hard { holes: 10 });
+    }
+}
@@ -39,10 +39,11 @@ use crate::{
         TrackMarker,
         TrackMarkerItem,
         TrackMetadata,
+        TrackRecovery,
         TrackSchema,
         TrackSchemaParser,
     },
-    types::IntegrityCheck,
+    types::{IntegrityCheck, RecoveryOptions},
     SectorIdQuery,
 };
 use bit_vec::BitVec;
@@ -191,13 +192,14 @@ impl TrackSchemaParser for TrackSchema {
         &self,
         track: &mut TrackDataStream,
         markers: Vec<TrackMarkerItem>,
-    ) -> Vec<TrackElementInstance> {
+        recovery: &RecoveryOptions,
+    ) -> (Vec<TrackElementInstance>, Vec<TrackRecovery>) {
         #[allow(clippy::match_single_binding)]
         #[allow(unreachable_patterns)]
         match self {
-            TrackSchema::System34 => System34Schema::scan_metadata(track, markers),
+            TrackSchema::System34 => System34Schema::scan_metadata(track, markers, recovery),
             #[cfg(feature = "amiga")]
-            TrackSchema::Amiga => AmigaSchema::scan_for_elements(track, markers),
+            TrackSchema::Amiga => (AmigaSchema::scan_for_elements(track, markers), Vec::new()),
             _ => {
                 panic!("{}", SCHEMA_ERR)
             }
@@ -59,7 +59,8 @@ use crate::{
     bitstream_codec::{mfm::MFM_BYTE_LEN, TrackDataStream},
     track::{TrackAnalysis, TrackSectorScanResult},
     track_schema::system34::{System34Element, System34Marker, System34Variant},
-    types::{chs::DiskChsn, IntegrityCheck, Platform, RwScope, SectorAttributes},
+    types::{chs::DiskChsn, IdMark, IntegrityCheck, Platform, RecoveryOptions, RwScope, SectorAttributes},
+    FoxHashSet,
     SectorId,
     SectorIdQuery,
     SectorMapEntry,
@@ -132,6 +133,35 @@ pub struct TrackMetadata {
     pub(crate) sector_ids: Vec<SectorId>,
     pub(crate) valid_sector_ids: Vec<SectorId>,
     pub(crate) element_map: SourceMap,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) recoveries: Vec<TrackRecovery>,
+}
+
+/// A recovery heuristic applied by a track schema parser while scanning a track, as enabled by
+/// [RecoveryOptions].
+///
+/// [RecoveryOptions]: crate::types::RecoveryOptions
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RecoveryAction {
+    /// A spurious bitcell at the specified bit index was skipped to make the CRC of the element
+    /// valid.
+    Resync { slip: usize },
+    /// Sector data was found `distance` bytes after its sector header, beyond the distance a floppy
+    /// disk controller would search for it.
+    DistantDam { distance: usize },
+    /// Sector data without a sector header was given an ID inferred from its neighbors.
+    InferredId,
+}
+
+/// A `TrackRecovery` records a [RecoveryAction] applied to the element starting at `start`,
+/// belonging to the sector with the ID `chsn`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackRecovery {
+    pub chsn:   DiskChsn,
+    pub start:  usize,
+    pub action: RecoveryAction,
 }
 
 impl TrackMetadata {
@@ -142,14 +172,27 @@ impl TrackMetadata {
             valid_sector_ids: Self::find_valid_sector_ids(&items),
             element_map: schema.build_element_map(&items),
             items,
+            recoveries: Vec::new(),
         }
     }
 
+    /// Attach the recovery actions performed while scanning the track.
+    pub(crate) fn with_recoveries(mut self, recoveries: Vec<TrackRecovery>) -> Self {
+        self.recoveries = recoveries;
+        self
+    }
+
     /// Clear all metadata items from the collection.
     pub(crate) fn clear(&mut self) {
         self.items.clear();
         self.sector_ids.clear();
         self.valid_sector_ids.clear();
+        self.recoveries.clear();
+    }
+
+    /// Return the recovery actions performed while scanning the track, in track order.
+    pub fn recoveries(&self) -> &[TrackRecovery] {
+        &self.recoveries
     }
 
     /// Return a vector of metadata items contained in the collection as `TrackElementInstance`s.
@@ -265,6 +308,39 @@ impl TrackMetadata {
         markers
     }
 
+    /// Return the ID address marks of the sector headers contained in the metadata, in track
+    /// order, including sector headers with a bad CRC.
+    pub fn id_marks(&self) -> Vec<IdMark> {
+        let mut id_marks = Vec::new();
+
+        for item in &self.items {
+            match item.element {
+                TrackElement::System34(System34Element::SectorHeader {
+                    chsn, address_error, ..
+                }) => {
+                    id_marks.push(IdMark {
+                        chsn,
+                        address_error,
+                        bit_offset: item.start,
+                    });
+                }
+                #[cfg(feature = "amiga")]
+                TrackElement::Amiga(AmigaElement::SectorHeader {
+                    chsn, address_error, ..
+                }) => {
+                    id_marks.push(IdMark {
+                        chsn,
+                        address_error,
+                        bit_offset: item.start,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        id_marks
+    }
+
     /// Return a vector of [SectorMapEntry]s representing the sectors contained in the metadata
     pub fn sector_list(&self) -> Vec<SectorMapEntry> {
         let mut sector_list = Vec::new();
@@ -286,6 +362,8 @@ impl TrackMetadata {
                             deleted_mark: deleted,
                             no_dam: false,
                         },
+                        data_offset: Some(item.start),
+                        ..Default::default()
                     });
                 }
                 #[cfg(feature = "amiga")]
@@ -302,15 +380,52 @@ impl TrackMetadata {
                             deleted_mark: false, // Amiga sectors can't be deleted
                             no_dam: false, // Can Amiga sectors be missing data? There is no DAM marker to check for.
                         },
+                        data_offset: Some(item.start),
+                        ..Default::default()
                     });
                 }
                 _ => {}
             }
         }
 
+        SectorMapEntry::mark_duplicates(&mut sector_list);
         sector_list
     }
 
+    /// Return a vector of [SectorMapEntry]s representing the orphaned data fields in the metadata
+    /// collection - data address marks that do not follow a sector header, and so cannot be read.
+    pub fn orphan_data_list(&self) -> Vec<SectorMapEntry> {
+        let mut orphan_list = Vec::new();
+        // A data address mark is an orphan if no sector data was associated with it.
+        let data_starts: FoxHashSet<usize> = self
+            .items
+            .iter()
+            .filter(|item| matches!(item.element, TrackElement::System34(System34Element::SectorData { .. })))
+            .map(|item| item.start)
+            .collect();
+
+        for item in &self.items {
+            if let TrackElement::System34(System34Element::Marker(marker, _)) = item.element {
+                match marker {
+                    System34Marker::Dam | System34Marker::Ddam if !data_starts.contains(&item.start) => {
+                        orphan_list.push(SectorMapEntry {
+                            attributes: SectorAttributes {
+                                deleted_mark: matches!(marker, System34Marker::Ddam),
+                                ..Default::default()
+                            },
+                            data_offset: Some(item.start),
+                            orphan: true,
+                            ..Default::default()
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        orphan_list
+    }
+
     /// Return a reference to a slice of the [SectorId]s represented in the metadata collection.
     /// Note that the number of Sector IDs may not match the number of sectors returned by
     /// sector_list(), as not all sector headers may correspond to valid sector data, especially
@@ -449,6 +564,11 @@ pub struct TrackElementInstance {
 }
 
 impl TrackElementInstance {
+    /// Return the [TrackElement] this instance represents.
+    pub fn element(&self) -> TrackElement {
+        self.element
+    }
+
     pub fn contains(&self, bit_index: usize) -> bool {
         self.start <= bit_index && self.end >= bit_index
     }
@@ -462,6 +582,76 @@ impl TrackElementInstance {
     }
 }
 
+/// A [DecodedElement] is a [TrackElementInstance] along with the values decoded from the track
+/// data it covers, so that callers do not need to decode the element themselves.
+#[derive(Copy, Clone, Debug)]
+pub struct DecodedElement {
+    /// The element instance, specifying the element type and its position on the track.
+    pub instance: TrackElementInstance,
+    /// The sector ID read from the sector header, for sector header and sector data elements.
+    pub chsn: Option<DiskChsn>,
+    /// The recorded and calculated CRC or checksum of the element, for elements that have one.
+    pub integrity: Option<IntegrityCheck>,
+}
+
+/// An iterator over the elements of a track's [TrackMetadata], producing a [DecodedElement] for
+/// each [TrackElementInstance]. Elements are decoded from the track data as the iterator advances.
+///
+/// Returned by [Track::decoded_elements](crate::track::Track::decoded_elements).
+pub struct DecodedElements<'a> {
+    schema: Option<TrackSchema>,
+    stream: Option<&'a TrackDataStream>,
+    items:  std::slice::Iter<'a, TrackElementInstance>,
+}
+
+impl<'a> DecodedElements<'a> {
+    pub(crate) fn new(schema: Option<TrackSchema>, stream: &'a TrackDataStream, metadata: &'a TrackMetadata) -> Self {
+        DecodedElements {
+            schema,
+            stream: Some(stream),
+            items: metadata.items.iter(),
+        }
+    }
+
+    /// Create an iterator that produces no elements, for tracks without element metadata.
+    pub(crate) fn empty() -> Self {
+        DecodedElements {
+            schema: None,
+            stream: None,
+            items:  [].iter(),
+        }
+    }
+}
+
+impl Iterator for DecodedElements<'_> {
+    type Item = DecodedElement;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let instance = *self.items.next()?;
+        let chsn = instance.element.chsn();
+
+        // Only sector headers and sector data carry an integrity check.
+        let integrity = match (self.schema, self.stream, chsn) {
+            (Some(schema), Some(stream), Some(_)) => {
+                let mut buf = vec![0u8; instance.element.size()];
+                let (_, check) = schema.decode_element(stream, &instance, RwScope::EntireElement, &mut buf);
+                check
+            }
+            _ => None,
+        };
+
+        Some(DecodedElement {
+            instance,
+            chsn,
+            integrity,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
 /// A [TrackMarker] represents an encoding marker found in a track, such as an address marker or
 /// data marker. Markers are used by FM and MFM encodings, utilizing unique clock bit patterns to
 /// create an out-of-band signal for synchronization.
@@ -752,14 +942,18 @@ pub(crate) trait TrackSchemaParser: Send + Sync {
     /// # Arguments
     /// * `track` - The [TrackDataStream] to scan for metadata.
     /// * `markers` - A vector of [TrackMarkerItem]s representing the markers found in the track.
+    /// * `recovery` - The [RecoveryOptions] to apply to damaged elements. Schemas that do not
+    ///   support recovery ignore this argument.
     /// # Returns
-    /// A vector of [TrackElementInstance] instances representing the metadata found in the track.
-    /// If no metadata is found, an empty vector is returned.
+    /// A tuple of a vector of [TrackElementInstance] instances representing the metadata found in
+    /// the track, and a vector of [TrackRecovery] items recording any recovery performed.
+    /// If no metadata is found, empty vectors are returned.
     fn scan_for_elements(
         &self,
         track: &mut TrackDataStream,
         markers: Vec<TrackMarkerItem>,
-    ) -> Vec<TrackElementInstance>;
+        recovery: &RecoveryOptions,
+    ) -> (Vec<TrackElementInstance>, Vec<TrackRecovery>);
 
     /// Create a clock map from the specified markers. A clock map enables random access into an encoded
     /// bitstream containing both clock and data bits.
@@ -29,12 +29,19 @@
 //! track schema, used by IBM PCs and compatibles and Macintosh 1.44MB HD disks.
 //!
 //! The System34 track schema supports both MFM and FM track encodings.
+//!
+//! FM tracks follow the IBM 3740 single density layout. FM address marks are not preceded by
+//! 0xA1 sync bytes, but are identified by a missing clock pattern on the mark byte itself (0xC7,
+//! or 0xD7 for the IAM). To give address marks the same layout in both encodings, the marker
+//! element of an FM address mark spans the last three 0x00 sync bytes before the mark byte.
+//! These sync bytes are not included in the CRC of FM sector headers and data.
 
 use core::ops::Range;
 use std::fmt::{Display, Formatter};
 
 use crate::{
     bitstream_codec::{
+        fm::{FM_MARKER_CLOCK_PATTERN, FM_MARKER_LEN},
         mfm::{MfmCodec, MFM_BYTE_LEN, MFM_MARKER_LEN},
         MarkerEncoding,
         TrackDataStream,
@@ -46,17 +53,21 @@ use crate::{
     track::{TrackAnalysis, TrackSectorScanResult},
     track_schema::{
         GenericTrackElement,
+        RecoveryAction,
         TrackElement,
         TrackElementInstance,
         TrackMarker,
         TrackMarkerItem,
         TrackMetadata,
+        TrackRecovery,
     },
-    types::{chs::DiskChsn, IntegrityCheck, IntegrityField},
-    util::crc_ibm_3740,
+    types::{chs::DiskChsn, IntegrityCheck, IntegrityField, RecoveryOptions},
+    util::{check_sector_crc, crc_ibm_3740, sector_crc, SectorCrcOptions},
     DiskImageError,
     FoxHashSet,
     SectorIdQuery,
+    SectorMapEntry,
+    MAXIMUM_SECTOR_SIZE_CODE,
 };
 use bit_vec::BitVec;
 
@@ -75,6 +86,20 @@ pub const SYNC_LEN: usize = 12;
 pub const PERPENDICULAR_GAP1: usize = 50;
 pub const PERPENDICULAR_GAP2: usize = 41;
 
+pub const FM_GAP_BYTE: u8 = 0xFF;
+pub const FM_GAP4A: usize = 40;
+pub const FM_GAP1: usize = 26;
+pub const FM_GAP2: usize = 11;
+pub const ISO_FM_GAP1: usize = 16;
+pub const FM_SYNC_LEN: usize = 6;
+/// The number of sync bytes included at the start of an FM marker element.
+pub const FM_MARKER_SYNC_LEN: usize = 3;
+
+/// The number of bytes after the end of a sector header within which a floppy disk controller
+/// finds the data address mark byte.
+pub const DAM_SEARCH_WINDOW_MFM: usize = 43;
+pub const DAM_SEARCH_WINDOW_FM: usize = 30;
+
 // Pre-encoded markers for IAM, IDAM, DAM and DDAM.
 pub const IAM_MARKER: u64 = 0x5224_5224_5224_5552;
 pub const IDAM_MARKER: u64 = 0x4489_4489_4489_5554;
@@ -88,13 +113,22 @@ pub const MARKER_MASK: u64 = 0xFFFF_FFFF_FFFF_0000;
 pub const FM_MARKER_CLOCK: u64 = 0xAAAA_AAAA_AAAA_0000;
 pub const MFM_MARKER_CLOCK: u64 = 0x0220_0220_0220_0000;
 
-pub const IAM_MARKER_FM: u64 = 0xFAAE_FAAE_FAAE_FFFA;
+// Pre-encoded FM markers, as three sync bytes followed by the mark byte with missing clock bits.
+pub const IAM_MARKER_FM: u64 = 0xAAAA_AAAA_AAAA_F77A;
+pub const IDAM_MARKER_FM: u64 = 0xAAAA_AAAA_AAAA_F57E;
+pub const DAM_MARKER_FM: u64 = 0xAAAA_AAAA_AAAA_F56F;
+pub const DDAM_MARKER_FM: u64 = 0xAAAA_AAAA_AAAA_F56A;
 
 pub const IAM_MARKER_BYTES: [u8; 4] = [0xC2, 0xC2, 0xC2, 0xFC];
 pub const IDAM_MARKER_BYTES: [u8; 4] = [0xA1, 0xA1, 0xA1, 0xFE];
 pub const DAM_MARKER_BYTES: [u8; 4] = [0xA1, 0xA1, 0xA1, 0xFB];
 pub const DDAM_MARKER_BYTES: [u8; 4] = [0xA1, 0xA1, 0xA1, 0xF8];
 
+pub const IAM_MARKER_BYTES_FM: [u8; 4] = [0x00, 0x00, 0x00, 0xFC];
+pub const IDAM_MARKER_BYTES_FM: [u8; 4] = [0x00, 0x00, 0x00, 0xFE];
+pub const DAM_MARKER_BYTES_FM: [u8; 4] = [0x00, 0x00, 0x00, 0xFB];
+pub const DDAM_MARKER_BYTES_FM: [u8; 4] = [0x00, 0x00, 0x00, 0xF8];
+
 pub enum System34Variant {
     Ibm3740,
     Ibm,
@@ -129,6 +163,14 @@ pub enum System34Standard {
 }
 
 impl System34Standard {
+    pub fn gap1(&self) -> usize {
+        match self {
+            System34Standard::Ibm => IBM_GAP1,
+            System34Standard::Perpendicular => PERPENDICULAR_GAP1,
+            System34Standard::Iso => ISO_GAP1,
+        }
+    }
+
     pub fn gap2(&self) -> usize {
         match self {
             System34Standard::Ibm => IBM_GAP2,
@@ -136,6 +178,24 @@ impl System34Standard {
             System34Standard::Iso => ISO_GAP2,
         }
     }
+
+    /// Return the GAP1 length for a track with the specified encoding. FM tracks use the shorter
+    /// gaps of the IBM 3740 format.
+    pub fn encoded_gap1(&self, encoding: TrackDataEncoding) -> usize {
+        match (encoding, self) {
+            (TrackDataEncoding::Fm, System34Standard::Iso) => ISO_FM_GAP1,
+            (TrackDataEncoding::Fm, _) => FM_GAP1,
+            _ => self.gap1(),
+        }
+    }
+
+    /// Return the GAP2 length for a track with the specified encoding.
+    pub fn encoded_gap2(&self, encoding: TrackDataEncoding) -> usize {
+        match encoding {
+            TrackDataEncoding::Fm => FM_GAP2,
+            _ => self.gap2(),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -158,14 +218,46 @@ impl From<System34Marker> for u64 {
     }
 }
 
+impl System34Marker {
+    /// Return the encoded marker, including its preceding sync bytes, for a track with the
+    /// specified encoding.
+    pub fn encoded(&self, encoding: TrackDataEncoding) -> u64 {
+        match encoding {
+            TrackDataEncoding::Fm => match self {
+                System34Marker::Iam => IAM_MARKER_FM,
+                System34Marker::Idam => IDAM_MARKER_FM,
+                System34Marker::Dam => DAM_MARKER_FM,
+                System34Marker::Ddam => DDAM_MARKER_FM,
+            },
+            _ => u64::from(*self),
+        }
+    }
+
+    /// Return the decoded bytes of the marker, including its preceding sync bytes, for a track
+    /// with the specified encoding.
+    pub fn bytes(&self, encoding: TrackDataEncoding) -> [u8; 4] {
+        match (encoding, self) {
+            (TrackDataEncoding::Fm, System34Marker::Iam) => IAM_MARKER_BYTES_FM,
+            (TrackDataEncoding::Fm, System34Marker::Idam) => IDAM_MARKER_BYTES_FM,
+            (TrackDataEncoding::Fm, System34Marker::Dam) => DAM_MARKER_BYTES_FM,
+            (TrackDataEncoding::Fm, System34Marker::Ddam) => DDAM_MARKER_BYTES_FM,
+            (_, System34Marker::Iam) => IAM_MARKER_BYTES,
+            (_, System34Marker::Idam) => IDAM_MARKER_BYTES,
+            (_, System34Marker::Dam) => DAM_MARKER_BYTES,
+            (_, System34Marker::Ddam) => DDAM_MARKER_BYTES,
+        }
+    }
+}
+
 impl TryInto<System34Marker> for u16 {
     type Error = ();
 
     fn try_into(self) -> Result<System34Marker, Self::Error> {
         match self {
+            0x5552 | 0xF77A => Ok(System34Marker::Iam),
             0x5554 | 0xF57E => Ok(System34Marker::Idam),
             0x5545 | 0xF56F => Ok(System34Marker::Dam),
-            0x554A => Ok(System34Marker::Ddam),
+            0x554A | 0xF56A => Ok(System34Marker::Ddam),
             _ => {
                 log::error!("Invalid System34 marker: {:04X}", self);
                 Err(())
@@ -286,7 +378,7 @@ impl System34Element {
 }
 
 // TODO: get rid of this duplicate sector id type. Use DiskChsn?
-#[derive(Default)]
+#[derive(Copy, Clone, Default)]
 pub struct SectorId {
     pub c: u8,
     pub h: u8,
@@ -298,7 +390,11 @@ pub struct SectorId {
 
 impl SectorId {
     pub fn sector_size_in_bytes(&self) -> usize {
-        std::cmp::min(8192, 128usize.overflowing_shl(self.b as u32).0)
+        DiskChsn::n_to_bytes(self.b)
+    }
+
+    pub fn chsn(&self) -> DiskChsn {
+        DiskChsn::new(self.c as u16, self.h, self.s, self.b)
     }
 }
 
@@ -330,8 +426,11 @@ impl System34Schema {
         marker & Self::MFM_MARKER_CLOCK_MASK | Self::MFM_MARKER_CLOCK
     }
 
+    /// Format a track with the standard layout for the specified encoding, returning the decoded
+    /// track bytes and the byte offsets of the address markers to be written over them.
     pub fn format_track_as_bytes(
         standard: System34Standard,
+        encoding: TrackDataEncoding,
         bitcell_ct: usize,
         format_buffer: Vec<DiskChsn>,
         fill_pattern: &[u8],
@@ -351,24 +450,32 @@ impl System34Schema {
         let mut track_bytes: Vec<u8> = Vec::with_capacity(track_byte_ct);
         let mut markers = Vec::new();
 
+        let gap_byte = Self::gap_byte(encoding);
+        let sync = vec![SYNC_BYTE; Self::sync_len(encoding)];
+        // FM sync bytes that precede the mark byte are not included in the CRC.
+        let crc_start = Self::crc_start(encoding);
+
         if matches!(standard, System34Standard::Ibm | System34Standard::Perpendicular) {
             // Write out GAP0, sync,IAM marker, and GAP1.
-            track_bytes.extend_from_slice(&[GAP_BYTE; IBM_GAP4A]); // GAP0
-            track_bytes.extend_from_slice(&[SYNC_BYTE; SYNC_LEN]); // Sync
+            track_bytes.extend_from_slice(&vec![gap_byte; Self::gap4a(encoding)]); // GAP0
+            track_bytes.extend_from_slice(&sync); // Sync
             markers.push((System34Marker::Iam, track_bytes.len()));
+            track_bytes.extend_from_slice(&System34Marker::Iam.bytes(encoding)); // IAM
+            let gap1 = vec![gap_byte; standard.encoded_gap1(encoding)];
+            track_bytes.extend_from_slice(&gap1); // GAP1
         }
         else {
             // Just write Gap1 for ISO standard, there is no IAM marker.
-            track_bytes.extend_from_slice(&[GAP_BYTE; ISO_GAP1]);
+            track_bytes.extend_from_slice(&vec![gap_byte; standard.encoded_gap1(encoding)]);
         }
 
         let mut pat_cursor = 0;
 
         for sector in format_buffer {
-            track_bytes.extend_from_slice(&[SYNC_BYTE; SYNC_LEN]); // Write initial sync.
+            track_bytes.extend_from_slice(&sync); // Write initial sync.
             markers.push((System34Marker::Idam, track_bytes.len()));
-            let idam_crc_offset = track_bytes.len();
-            track_bytes.extend_from_slice(IDAM_MARKER_BYTES.as_ref()); // Write IDAM marker.
+            let idam_crc_offset = track_bytes.len() + crc_start;
+            track_bytes.extend_from_slice(&System34Marker::Idam.bytes(encoding)); // Write IDAM marker.
 
             // Write CHSN bytes.
             track_bytes.push(sector.c() as u8);
@@ -382,15 +489,15 @@ impl System34Schema {
             track_bytes.extend_from_slice(&crc16.to_be_bytes());
 
             // Write GAP2.
-            track_bytes.extend_from_slice(&vec![GAP_BYTE; standard.gap2()]);
+            track_bytes.extend_from_slice(&vec![gap_byte; standard.encoded_gap2(encoding)]);
 
             // Write SYNC.
-            track_bytes.extend_from_slice(&[SYNC_BYTE; SYNC_LEN]);
+            track_bytes.extend_from_slice(&sync);
 
             // Write DAM marker.
             markers.push((System34Marker::Dam, track_bytes.len()));
-            let dam_crc_offset = track_bytes.len();
-            track_bytes.extend_from_slice(DAM_MARKER_BYTES.as_ref());
+            let dam_crc_offset = track_bytes.len() + crc_start;
+            track_bytes.extend_from_slice(&System34Marker::Dam.bytes(encoding));
 
             // Write sector data using provided pattern buffer.
             if fill_pattern.len() == 1 {
@@ -424,12 +531,12 @@ impl System34Schema {
             track_bytes.extend_from_slice(&crc16.to_be_bytes());
 
             // Write GAP3.
-            track_bytes.extend_from_slice(&vec![GAP_BYTE; gap3]);
+            track_bytes.extend_from_slice(&vec![gap_byte; gap3]);
         }
 
         // Fill rest of track with GAP4B.
         if track_bytes.len() < track_byte_ct {
-            track_bytes.extend_from_slice(&vec![GAP_BYTE; track_byte_ct - track_bytes.len()]);
+            track_bytes.extend_from_slice(&vec![gap_byte; track_byte_ct - track_bytes.len()]);
         }
 
         if track_bytes.len() > track_byte_ct {
@@ -450,12 +557,93 @@ impl System34Schema {
         Ok(System34FormatResult { track_bytes, markers })
     }
 
+    /// Convert the data of a write track command, as sent to a WD177x-style floppy disk controller,
+    /// into track bytes and markers. Writing starts at the index. The following bytes have special
+    /// meaning:
+    /// - `0xF5` writes an 0xA1 sync byte with a missing clock bit, and presets the CRC.
+    /// - `0xF6` writes an 0xC2 sync byte with a missing clock bit.
+    /// - `0xF7` writes the two CRC bytes.
+    ///
+    /// All other bytes are written as-is. Address marks are recognized from three sync bytes
+    /// followed by a mark byte. Data beyond the index is discarded, and a short write leaves the
+    /// rest of the track filled with gap bytes.
+    pub fn write_track_as_bytes(bitcell_ct: usize, data: &[u8]) -> Result<System34FormatResult, DiskImageError> {
+        let track_byte_ct = bitcell_ct.div_ceil(MFM_BYTE_LEN);
+        let mut track_bytes: Vec<u8> = Vec::with_capacity(track_byte_ct);
+        let mut markers = Vec::new();
+
+        // The CRC preset by a sync byte covers the three 0xA1 bytes that precede any mark.
+        let crc_preset = crc_ibm_3740(&[0xA1; 3], None);
+        let mut crc = crc_preset;
+        let mut a1_run = 0;
+        let mut c2_run = 0;
+
+        for &byte in data {
+            match byte {
+                0xF5 => {
+                    track_bytes.push(0xA1);
+                    crc = crc_preset;
+                    a1_run += 1;
+                    c2_run = 0;
+                    continue;
+                }
+                0xF6 => {
+                    track_bytes.push(0xC2);
+                    c2_run += 1;
+                    a1_run = 0;
+                    continue;
+                }
+                0xF7 => {
+                    track_bytes.extend_from_slice(&crc.to_be_bytes());
+                }
+                _ => {
+                    let marker = match byte {
+                        0xFE if a1_run >= 3 => Some(System34Marker::Idam),
+                        0xFB if a1_run >= 3 => Some(System34Marker::Dam),
+                        0xF8 if a1_run >= 3 => Some(System34Marker::Ddam),
+                        0xFC if c2_run >= 3 => Some(System34Marker::Iam),
+                        _ => None,
+                    };
+                    if let Some(marker) = marker {
+                        markers.push((marker, track_bytes.len() - 3));
+                    }
+                    track_bytes.push(byte);
+                    crc = crc_ibm_3740(&[byte], Some(crc));
+                }
+            }
+            a1_run = 0;
+            c2_run = 0;
+        }
+
+        if track_bytes.len() > track_byte_ct {
+            log::warn!(
+                "write_track_as_bytes(): Write passed index. Truncating track to {} bytes",
+                track_byte_ct
+            );
+            track_bytes.truncate(track_byte_ct);
+            // Drop any marker that was cut off by the index.
+            markers.retain(|(_, offset)| offset + 4 <= track_byte_ct);
+        }
+        else {
+            track_bytes.resize(track_byte_ct, GAP_BYTE);
+        }
+
+        log::trace!(
+            "write_track_as_bytes(): Wrote {} markers to track of size {} bytes",
+            markers.len(),
+            track_bytes.len(),
+        );
+
+        Ok(System34FormatResult { track_bytes, markers })
+    }
+
     pub(crate) fn set_track_markers(
         codec: &mut TrackDataStream,
         markers: Vec<(System34Marker, usize)>,
     ) -> Result<(), DiskImageError> {
+        let encoding = codec.encoding();
         for (marker, offset) in markers {
-            let marker_u64 = u64::from(marker);
+            let marker_u64 = marker.encoded(encoding);
 
             let marker_bit_index = offset * MFM_BYTE_LEN;
 
@@ -467,6 +655,41 @@ impl System34Schema {
 
         Ok(())
     }
+
+    /// Return the gap byte written by a formatting controller for the specified encoding.
+    pub fn gap_byte(encoding: TrackDataEncoding) -> u8 {
+        match encoding {
+            TrackDataEncoding::Fm => FM_GAP_BYTE,
+            _ => GAP_BYTE,
+        }
+    }
+
+    /// Return the length of GAP4A (GAP0) for the specified encoding.
+    pub fn gap4a(encoding: TrackDataEncoding) -> usize {
+        match encoding {
+            TrackDataEncoding::Fm => FM_GAP4A,
+            _ => IBM_GAP4A,
+        }
+    }
+
+    /// Return the number of sync bytes preceding a marker element for the specified encoding.
+    /// FM marker elements contain the last [FM_MARKER_SYNC_LEN] bytes of the sync field.
+    pub fn sync_len(encoding: TrackDataEncoding) -> usize {
+        match encoding {
+            TrackDataEncoding::Fm => FM_SYNC_LEN - FM_MARKER_SYNC_LEN,
+            _ => SYNC_LEN,
+        }
+    }
+
+    /// Return the byte offset into a marker element at which the CRC of the field it starts
+    /// begins. The 0xA1 sync bytes of an MFM marker are included in the CRC; the 0x00 sync bytes
+    /// of an FM marker are not.
+    pub fn crc_start(encoding: TrackDataEncoding) -> usize {
+        match encoding {
+            TrackDataEncoding::Fm => FM_MARKER_SYNC_LEN,
+            _ => 0,
+        }
+    }
 }
 
 // Quasi-trait impl of TrackSchema - called by enum dispatch
@@ -578,13 +801,23 @@ impl System34Schema {
                 }
             }
             TrackDataEncoding::Fm => {
+                // Match the clock pattern of the IDAM, DAM and DDAM marks, and any mark byte.
                 let marker = MarkerEncoding {
-                    bits: ANY_MARKER,
+                    bits: FM_MARKER_CLOCK_PATTERN,
                     mask: MARKER_MASK,
                     ..MarkerEncoding::default()
                 };
+                // The IAM has a clock pattern of its own, so search for it separately, only up
+                // to the next mark found.
+                let iam = MarkerEncoding {
+                    bits: IAM_MARKER_FM,
+                    ..MarkerEncoding::default()
+                };
 
-                if let Some((index, marker_u16)) = stream.find_marker(&marker, offset, None) {
+                let mark = stream.find_marker(&marker, offset, None);
+                let iam_limit = mark.map(|(index, _)| index + FM_MARKER_LEN);
+                let found = stream.find_marker(&iam, offset, iam_limit).or(mark);
+                if let Some((index, marker_u16)) = found {
                     if let Ok(marker) = marker_u16.try_into() {
                         return Some((TrackMarker::System34(marker), index));
                     }
@@ -610,7 +843,7 @@ impl System34Schema {
     ) -> Option<(usize, u16)> {
         if let TrackMarker::System34(marker) = marker {
             let marker = MarkerEncoding {
-                bits: u64::from(marker),
+                bits: marker.encoded(stream.encoding()),
                 ..MarkerEncoding::default()
             };
             return stream.find_marker(&marker, index, limit);
@@ -720,17 +953,18 @@ impl System34Schema {
     ) -> (Range<usize>, Option<IntegrityCheck>) {
         // Read the element into the buffer
         stream.read_decoded_buf(buf, element.start);
+        let crc_start = Self::crc_start(stream.encoding()).min(buf.len());
 
         match element.element {
             TrackElement::System34(System34Element::SectorHeader { .. }) => {
                 // Calculate the CRC16 of the sector header
-                let (recorded_crc, calculated_crc) = Self::crc16_bytes(buf);
+                let (recorded_crc, calculated_crc) = Self::crc16_bytes(&buf[crc_start..]);
                 let check = IntegrityCheck::Crc16(IntegrityField::new(recorded_crc, calculated_crc));
                 (element.element.range(scope).unwrap_or_default(), Some(check))
             }
             TrackElement::System34(System34Element::SectorData { data_error, .. }) => {
                 // Calculate the CRC16 of the data.
-                let (recorded_crc, calculated_crc) = Self::crc16_bytes(buf);
+                let (recorded_crc, calculated_crc) = Self::crc16_bytes(&buf[crc_start..]);
                 let check = IntegrityCheck::Crc16(IntegrityField::new(recorded_crc, calculated_crc));
 
                 if data_error != check.is_error() {
@@ -845,17 +1079,29 @@ impl System34Schema {
     /// as Sector ID values and CRCs. This is done in a second pass after the markers have been
     /// found by scan_track_markers() and a clock phase map created for the track - required for the
     /// proper functioning of the Read and Seek traits on MfmCodec.
+    ///
+    /// Damaged elements are recovered as enabled by `recovery`, and the recovery actions taken are
+    /// returned along with the elements found.
     pub(crate) fn scan_metadata(
         stream: &mut TrackDataStream,
         markers: Vec<TrackMarkerItem>,
-    ) -> Vec<TrackElementInstance> {
+        recovery: &RecoveryOptions,
+    ) -> (Vec<TrackElementInstance>, Vec<TrackRecovery>) {
         let mut elements = Vec::new();
+        let mut recoveries = Vec::new();
         let mut last_marker_opt: Option<System34Marker> = None;
         let mut last_sector_id = SectorId::default();
+        // The last sector ID read with a valid CRC, or inferred, used to infer the ID of sector
+        // data without a sector header.
+        let mut prev_sector_id: Option<SectorId> = None;
         let mut last_element_offset = 0;
+        let encoding = stream.encoding();
+        let crc_start = Self::crc_start(encoding);
 
-        for marker in &markers {
+        for (marker_idx, marker) in markers.iter().enumerate() {
             let element_offset = marker.start;
+            // Clock phase corrections made by resynchronizing an element extend to the next marker.
+            let next_marker_offset = markers.get(marker_idx + 1).map_or(stream.len(), |m| m.start);
 
             if let TrackMarker::System34(sys34_marker) = marker.elem_type {
                 match (last_marker_opt, sys34_marker) {
@@ -866,12 +1112,7 @@ impl System34Schema {
                         // Push a Sector Header metadata item spanning from last IDAM to this IDAM.
                         let metadata = TrackElementInstance {
                             element: TrackElement::System34(System34Element::SectorHeader {
-                                chsn: DiskChsn::from((
-                                    last_sector_id.c as u16,
-                                    last_sector_id.h,
-                                    last_sector_id.s,
-                                    last_sector_id.b,
-                                )),
+                                chsn: last_sector_id.chsn(),
                                 address_error: !last_sector_id.crc_valid,
                                 data_missing: true, // Flag data as missing.
                             }),
@@ -883,42 +1124,53 @@ impl System34Schema {
                     }
                     (_, System34Marker::Idam) => {
                         // Encountered a sector ID address mark (sector header), after any element.
-                        let mut sector_header = [0; 8];
-
-                        // TODO: Don't unwrap in a library unless provably safe.
-                        //       Consider removing option return type from read_decoded_byte.
-                        sector_header[0] = stream.read_decoded_u8(marker.start + mfm_offset!(0)).unwrap();
-                        sector_header[1] = stream.read_decoded_u8(marker.start + mfm_offset!(1)).unwrap();
-                        sector_header[2] = stream.read_decoded_u8(marker.start + mfm_offset!(2)).unwrap();
-                        sector_header[3] = stream.read_decoded_u8(marker.start + mfm_offset!(3)).unwrap();
-
-                        log::trace!("Idam marker read: {:02X?}", &sector_header[0..4]);
-                        sector_header[4] = stream.read_decoded_u8(marker.start + mfm_offset!(4)).unwrap(); // Cylinder
-                        sector_header[5] = stream.read_decoded_u8(marker.start + mfm_offset!(5)).unwrap(); // Head
-                        sector_header[6] = stream.read_decoded_u8(marker.start + mfm_offset!(6)).unwrap(); // Sector
-                        sector_header[7] = stream.read_decoded_u8(marker.start + mfm_offset!(7)).unwrap(); // Sector size (b)
-                        let crc_byte0 = stream.read_decoded_u8(marker.start + mfm_offset!(8)).unwrap_or(0xAA);
-                        let crc_byte1 = stream.read_decoded_u8(marker.start + mfm_offset!(9)).unwrap_or(0xAA);
-
-                        let crc = u16::from_be_bytes([crc_byte0, crc_byte1]);
-                        let calculated_crc = crc_ibm_3740(&sector_header[0..8], None);
-
-                        let sector_id = SectorId {
-                            c: sector_header[4],
-                            h: sector_header[5],
-                            s: sector_header[6],
-                            b: sector_header[7],
-                            crc,
-                            crc_valid: crc == calculated_crc,
-                        };
+                        let mut sector_id = Self::read_sector_id(stream, marker.start, crc_start);
+
+                        if !sector_id.crc_valid && recovery.resync {
+                            if let Some(slip) = Self::resync_element(
+                                stream,
+                                marker.start,
+                                marker.start + crc_start * MFM_BYTE_LEN,
+                                marker.start + mfm_offset!(8),
+                                next_marker_offset,
+                            ) {
+                                sector_id = Self::read_sector_id(stream, marker.start, crc_start);
+                                recoveries.push(TrackRecovery {
+                                    chsn:   sector_id.chsn(),
+                                    start:  element_offset,
+                                    action: RecoveryAction::Resync { slip },
+                                });
+                            }
+                        }
+
+                        if sector_id.crc_valid {
+                            prev_sector_id = Some(sector_id);
+                        }
+                        last_sector_id = sector_id;
+                    }
+                    (Some(System34Marker::Idam), System34Marker::Dam | System34Marker::Ddam)
+                        if !recovery.distant_dams
+                            && Self::dam_distance(last_element_offset, element_offset)
+                                > Self::dam_search_window(encoding) =>
+                    {
+                        // The DAM is further from the sector header than a controller would search,
+                        // so the sector header has no data.
                         log::trace!(
-                            "Sector ID: {} Size: {} crc: {:04X} calculated CRC: {:04X}",
-                            sector_id,
-                            sector_id.sector_size_in_bytes(),
-                            crc,
-                            calculated_crc
+                            "Data marker at offset: {} is beyond the search window of sector header {}",
+                            element_offset,
+                            last_sector_id
                         );
-                        last_sector_id = sector_id;
+                        let metadata = TrackElementInstance {
+                            element: TrackElement::System34(System34Element::SectorHeader {
+                                chsn: last_sector_id.chsn(),
+                                address_error: !last_sector_id.crc_valid,
+                                data_missing: true, // Flag data as missing.
+                            }),
+                            start: last_element_offset,
+                            end: element_offset,
+                            chsn: None,
+                        };
+                        elements.push(metadata)
                     }
                     (Some(System34Marker::Idam), System34Marker::Dam | System34Marker::Ddam) => {
                         // Encountered a DAM or DDAM after a sector header (IDAM). This is the sector data.
@@ -940,78 +1192,96 @@ impl System34Schema {
                             data_end
                         );
 
-                        let mut dam_header = [0; 4];
-                        dam_header[0] = stream.read_decoded_u8(marker.start + mfm_offset!(0)).unwrap();
-                        dam_header[1] = stream.read_decoded_u8(marker.start + mfm_offset!(1)).unwrap();
-                        dam_header[2] = stream.read_decoded_u8(marker.start + mfm_offset!(2)).unwrap();
-                        dam_header[3] = stream.read_decoded_u8(marker.start + mfm_offset!(3)).unwrap();
-
-                        //log::debug!("DAM header verification: {:02X?}", dam_header);
+                        let distance = Self::dam_distance(last_element_offset, element_offset);
+                        if distance > Self::dam_search_window(encoding) {
+                            recoveries.push(TrackRecovery {
+                                chsn:   last_sector_id.chsn(),
+                                start:  element_offset,
+                                action: RecoveryAction::DistantDam { distance },
+                            });
+                        }
 
-                        let (data_crc, calculated_crc) = System34Schema::crc16(stream, element_offset, data_end);
+                        let crc_from = element_offset + crc_start * MFM_BYTE_LEN;
+                        let (data_crc, calculated_crc) = System34Schema::crc16(stream, crc_from, data_end);
                         log::trace!("Data CRC16: {:04X} Calculated: {:04X}", data_crc, calculated_crc);
 
-                        let crc_correct = data_crc == calculated_crc;
+                        let mut crc_correct = data_crc == calculated_crc;
+                        if !crc_correct && recovery.resync {
+                            if let Some(slip) =
+                                Self::resync_element(stream, element_offset, crc_from, data_end, next_marker_offset)
+                            {
+                                crc_correct = true;
+                                recoveries.push(TrackRecovery {
+                                    chsn:   last_sector_id.chsn(),
+                                    start:  element_offset,
+                                    action: RecoveryAction::Resync { slip },
+                                });
+                            }
+                        }
                         if !crc_correct {
                             log::warn!("Data CRC error detected at offset: {}", element_offset);
                         }
 
-                        // Push a Sector Header metadata item spanning from IDAM to DAM.
-                        let data_metadata = TrackElementInstance {
-                            element: TrackElement::System34(System34Element::SectorHeader {
-                                chsn: DiskChsn::from((
-                                    last_sector_id.c as u16,
-                                    last_sector_id.h,
-                                    last_sector_id.s,
-                                    last_sector_id.b,
-                                )),
-                                address_error: !last_sector_id.crc_valid,
-                                data_missing: false,
-                            }),
-                            start: last_element_offset,
-                            end: element_offset,
-                            chsn: None,
-                        };
-                        elements.push(data_metadata);
-
-                        let element = match sys34_marker {
-                            System34Marker::Dam => System34Element::SectorData {
-                                chsn: DiskChsn::new(
-                                    last_sector_id.c as u16,
-                                    last_sector_id.h,
-                                    last_sector_id.s,
-                                    last_sector_id.b,
-                                ),
-                                address_error: !last_sector_id.crc_valid,
-                                data_error: !crc_correct,
-                                deleted: false,
-                            },
-                            System34Marker::Ddam => System34Element::SectorData {
-                                chsn: DiskChsn::new(
-                                    last_sector_id.c as u16,
-                                    last_sector_id.h,
-                                    last_sector_id.s,
-                                    last_sector_id.b,
-                                ),
-                                address_error: !last_sector_id.crc_valid,
-                                data_error: !crc_correct,
-                                deleted: true,
-                            },
-                            _ => unreachable!(),
-                        };
+                        Self::push_sector_elements(
+                            &mut elements,
+                            sys34_marker,
+                            &last_sector_id,
+                            last_element_offset,
+                            element_offset,
+                            data_end,
+                            !crc_correct,
+                        );
+                    }
+                    (_, System34Marker::Dam | System34Marker::Ddam) if recovery.orphan_dams => {
+                        // Encountered a DAM or DDAM without a sector header. Infer the sector ID from
+                        // the neighboring sector headers.
+                        if let Some(mut sector_id) = Self::infer_sector_id(
+                            stream,
+                            prev_sector_id.as_ref(),
+                            &markers[marker_idx + 1..],
+                            crc_start,
+                        ) {
+                            let crc_from = element_offset + crc_start * MFM_BYTE_LEN;
+                            // Try the size of the neighboring sector first, then any size that
+                            // fits before the next marker, for a valid CRC.
+                            let mut crc_correct = false;
+                            for b in std::iter::once(sector_id.b).chain(0..=MAXIMUM_SECTOR_SIZE_CODE) {
+                                let data_end = element_offset + MFM_MARKER_LEN + DiskChsn::n_to_bytes(b) * MFM_BYTE_LEN;
+                                if data_end + 2 * MFM_BYTE_LEN > next_marker_offset {
+                                    continue;
+                                }
+                                let (data_crc, calculated_crc) = System34Schema::crc16(stream, crc_from, data_end);
+                                if data_crc == calculated_crc {
+                                    sector_id.b = b;
+                                    crc_correct = true;
+                                    break;
+                                }
+                            }
 
-                        let data_metadata = TrackElementInstance {
-                            element: TrackElement::System34(element),
-                            start: element_offset,
-                            end: data_end,
-                            chsn: Some(DiskChsn::new(
-                                last_sector_id.c as u16,
-                                last_sector_id.h,
-                                last_sector_id.s,
-                                last_sector_id.b,
-                            )),
-                        };
-                        elements.push(data_metadata);
+                            log::debug!(
+                                "Inferred sector ID {} for data marker at offset: {}",
+                                sector_id,
+                                element_offset
+                            );
+                            let data_end =
+                                element_offset + MFM_MARKER_LEN + sector_id.sector_size_in_bytes() * MFM_BYTE_LEN;
+                            Self::push_sector_elements(
+                                &mut elements,
+                                sys34_marker,
+                                &sector_id,
+                                element_offset,
+                                element_offset,
+                                data_end,
+                                !crc_correct,
+                            );
+                            recoveries.push(TrackRecovery {
+                                chsn:   sector_id.chsn(),
+                                start:  element_offset,
+                                action: RecoveryAction::InferredId,
+                            });
+                            prev_sector_id = Some(sector_id);
+                            last_sector_id = sector_id;
+                        }
                     }
                     _ => {}
                 }
@@ -1021,12 +1291,7 @@ impl System34Schema {
                     element: TrackElement::System34(System34Element::Marker(sys34_marker, None)),
                     start: marker.start,
                     end: marker.start + 4 * MFM_BYTE_LEN,
-                    chsn: Some(DiskChsn::new(
-                        last_sector_id.c as u16,
-                        last_sector_id.h,
-                        last_sector_id.s,
-                        last_sector_id.b,
-                    )),
+                    chsn: Some(last_sector_id.chsn()),
                 };
                 elements.push(marker_metadata);
 
@@ -1042,12 +1307,7 @@ impl System34Schema {
 
             let data_metadata = TrackElementInstance {
                 element: TrackElement::System34(System34Element::SectorHeader {
-                    chsn: DiskChsn::from((
-                        last_sector_id.c as u16,
-                        last_sector_id.h,
-                        last_sector_id.s,
-                        last_sector_id.b,
-                    )),
+                    chsn: last_sector_id.chsn(),
                     address_error: last_sector_id.crc_valid,
                     data_missing: true, // Flag data as missing.
                 }),
@@ -1060,7 +1320,210 @@ impl System34Schema {
 
         // Sort elements by start offset.
         elements.sort_by(|a, b| a.start.cmp(&b.start));
-        elements
+        (elements, recoveries)
+    }
+
+    /// Read the sector ID of the sector header whose marker starts at `marker_start`, checking its
+    /// CRC.
+    fn read_sector_id(stream: &TrackDataStream, marker_start: usize, crc_start: usize) -> SectorId {
+        let mut sector_header = [0; 8];
+
+        // TODO: Don't unwrap in a library unless provably safe.
+        //       Consider removing option return type from read_decoded_byte.
+        sector_header[0] = stream.read_decoded_u8(marker_start + mfm_offset!(0)).unwrap();
+        sector_header[1] = stream.read_decoded_u8(marker_start + mfm_offset!(1)).unwrap();
+        sector_header[2] = stream.read_decoded_u8(marker_start + mfm_offset!(2)).unwrap();
+        sector_header[3] = stream.read_decoded_u8(marker_start + mfm_offset!(3)).unwrap();
+
+        log::trace!("Idam marker read: {:02X?}", &sector_header[0..4]);
+        sector_header[4] = stream.read_decoded_u8(marker_start + mfm_offset!(4)).unwrap(); // Cylinder
+        sector_header[5] = stream.read_decoded_u8(marker_start + mfm_offset!(5)).unwrap(); // Head
+        sector_header[6] = stream.read_decoded_u8(marker_start + mfm_offset!(6)).unwrap(); // Sector
+        sector_header[7] = stream.read_decoded_u8(marker_start + mfm_offset!(7)).unwrap(); // Sector size (b)
+        let crc_byte0 = stream.read_decoded_u8(marker_start + mfm_offset!(8)).unwrap_or(0xAA);
+        let crc_byte1 = stream.read_decoded_u8(marker_start + mfm_offset!(9)).unwrap_or(0xAA);
+
+        let crc = u16::from_be_bytes([crc_byte0, crc_byte1]);
+        let calculated_crc = sector_crc(&sector_header[crc_start..], &SectorCrcOptions::default());
+
+        let sector_id = SectorId {
+            c: sector_header[4],
+            h: sector_header[5],
+            s: sector_header[6],
+            b: sector_header[7],
+            crc,
+            crc_valid: crc == calculated_crc,
+        };
+        log::trace!(
+            "Sector ID: {} Size: {} crc: {:04X} calculated CRC: {:04X}",
+            sector_id,
+            sector_id.sector_size_in_bytes(),
+            crc,
+            calculated_crc
+        );
+        sector_id
+    }
+
+    /// Push the sector header and sector data elements of a sector. The sector header spans from
+    /// `header_start` to the data address mark at `data_start`.
+    fn push_sector_elements(
+        elements: &mut Vec<TrackElementInstance>,
+        marker: System34Marker,
+        sector_id: &SectorId,
+        header_start: usize,
+        data_start: usize,
+        data_end: usize,
+        data_error: bool,
+    ) {
+        // Push a Sector Header metadata item spanning from IDAM to DAM.
+        elements.push(TrackElementInstance {
+            element: TrackElement::System34(System34Element::SectorHeader {
+                chsn: sector_id.chsn(),
+                address_error: !sector_id.crc_valid,
+                data_missing: false,
+            }),
+            start: header_start,
+            end: data_start,
+            chsn: None,
+        });
+
+        elements.push(TrackElementInstance {
+            element: TrackElement::System34(System34Element::SectorData {
+                chsn: sector_id.chsn(),
+                address_error: !sector_id.crc_valid,
+                data_error,
+                deleted: matches!(marker, System34Marker::Ddam),
+            }),
+            start: data_start,
+            end: data_end,
+            chsn: Some(sector_id.chsn()),
+        });
+    }
+
+    /// Return the distance in bytes from the end of the sector header whose marker starts at
+    /// `header_start` to the mark byte of the data address mark starting at `data_start`.
+    fn dam_distance(header_start: usize, data_start: usize) -> usize {
+        (data_start.saturating_sub(header_start + mfm_offset!(10)) / MFM_BYTE_LEN) + 3
+    }
+
+    /// Return the distance in bytes after a sector header within which a floppy disk controller
+    /// searches for the data address mark, as for the NEC µPD765.
+    pub fn dam_search_window(encoding: TrackDataEncoding) -> usize {
+        match encoding {
+            TrackDataEncoding::Fm => DAM_SEARCH_WINDOW_FM,
+            _ => DAM_SEARCH_WINDOW_MFM,
+        }
+    }
+
+    /// Infer the sector ID of sector data without a sector header: the sector following the last
+    /// sector ID read, or failing that, the sector preceding the next sector header on the track.
+    fn infer_sector_id(
+        stream: &TrackDataStream,
+        prev_sector_id: Option<&SectorId>,
+        next_markers: &[TrackMarkerItem],
+        crc_start: usize,
+    ) -> Option<SectorId> {
+        let (neighbor, s) = match prev_sector_id {
+            Some(prev) => (*prev, prev.s.wrapping_add(1)),
+            None => {
+                let next_idam = next_markers
+                    .iter()
+                    .find(|m| matches!(m.elem_type, TrackMarker::System34(System34Marker::Idam)))?;
+                let next = Self::read_sector_id(stream, next_idam.start, crc_start);
+                if !next.crc_valid {
+                    return None;
+                }
+                (next, next.s.wrapping_sub(1))
+            }
+        };
+        Some(SectorId {
+            s,
+            crc_valid: true,
+            ..neighbor
+        })
+    }
+
+    /// Attempt to resynchronize the element whose marker starts at `marker_start` and whose CRC
+    /// covers `crc_from..crc_end` (excluding the CRC itself) by skipping a single spurious bitcell,
+    /// as a PLL slip would produce. On success the clock map is adjusted to skip the bitcell up to
+    /// `limit`, and its bit index is returned.
+    fn resync_element(
+        stream: &mut TrackDataStream,
+        marker_start: usize,
+        crc_from: usize,
+        crc_end: usize,
+        limit: usize,
+    ) -> Option<usize> {
+        let slip = Self::find_slip(stream, marker_start + MFM_MARKER_LEN, crc_from, crc_end)?;
+        Self::invert_clock_phase(stream, slip, limit);
+
+        let (crc, calculated_crc) = System34Schema::crc16(stream, crc_from, crc_end);
+        if crc == calculated_crc {
+            log::debug!(
+                "resync_element(): Resynchronized element at {} by skipping bitcell {}",
+                marker_start,
+                slip
+            );
+            Some(slip)
+        }
+        else {
+            // Inverting the phase again restores the original clock map.
+            Self::invert_clock_phase(stream, slip, limit);
+            None
+        }
+    }
+
+    /// Find a single spurious bitcell between `check_from` and `crc_end` whose removal makes the
+    /// CRC over `crc_from..crc_end` valid.
+    ///
+    /// Before a slip the clock bits follow the encoding rules in the original clock phase, and after
+    /// it in the opposite phase, so only bitcells between the last violation of the opposite phase
+    /// and the first violation of the original phase are tried.
+    fn find_slip(stream: &TrackDataStream, check_from: usize, crc_from: usize, crc_end: usize) -> Option<usize> {
+        let bits = stream.data();
+        let byte_ct = (crc_end - crc_from) / MFM_BYTE_LEN + 2;
+        let field_end = crc_from + byte_ct * MFM_BYTE_LEN;
+        if check_from == 0 || field_end + 2 >= bits.len() {
+            return None;
+        }
+
+        let fm = matches!(stream.encoding(), TrackDataEncoding::Fm);
+        let clock_ok = |prev_data: bool, clock: bool, data: bool| clock == (fm || !(prev_data || data));
+
+        let violation = (check_from..field_end)
+            .step_by(2)
+            .find(|&ci| !clock_ok(bits[ci - 1], bits[ci], bits[ci + 1]))?;
+        let first_candidate = (check_from..violation)
+            .step_by(2)
+            .rev()
+            .find(|&ci| !clock_ok(bits[ci], bits[ci + 1], bits[ci + 2]))
+            .map_or(check_from, |ci| ci + 2);
+
+        let mut buf = vec![0; byte_ct];
+        (first_candidate..=violation).rev().step_by(2).find(|&slip| {
+            for (i, byte) in buf.iter_mut().enumerate() {
+                *byte = 0;
+                for bit in 0..8 {
+                    let mut data_idx = crc_from + (i * 8 + bit) * 2 + 1;
+                    if data_idx > slip {
+                        data_idx += 1;
+                    }
+                    *byte = (*byte << 1) | bits[data_idx] as u8;
+                }
+            }
+            let (crc, calculated_crc) = Self::crc16_bytes(&buf);
+            crc == calculated_crc
+        })
+    }
+
+    /// Invert the clock phase of the clock map from `slip` up to the bitcell before `limit`,
+    /// which is left clear to allow syncing to the marker at `limit`.
+    fn invert_clock_phase(stream: &mut TrackDataStream, slip: usize, limit: usize) {
+        let clock_map = stream.clock_map_mut();
+        for bi in slip..limit.saturating_sub(1).min(clock_map.len()) {
+            let bit = clock_map[bi];
+            clock_map.set(bi, !bit);
+        }
     }
 
     /// Use the list of track markers to create a clock phase map for the track. This a requirement
@@ -1118,18 +1581,126 @@ impl System34Schema {
         track.read_exact(&mut data).unwrap();
 
         //log::debug!("Buffer: {:02X?}", data);
-        let recorded = u16::from_be_bytes([data[bytes_requested], data[bytes_requested + 1]]);
-        let calculated = crc_ibm_3740(&data[0..bytes_requested], None);
-
-        (recorded, calculated)
+        check_sector_crc(&data, &SectorCrcOptions::default())
     }
 
     pub(crate) fn crc16_bytes(data: &[u8]) -> (u16, u16) {
         //log::debug!("Buffer: {:02X?}", data);
-        let recorded = u16::from_be_bytes([data[data.len().saturating_sub(2)], data[data.len().saturating_sub(1)]]);
-        let calculated = crc_ibm_3740(&data[..data.len().saturating_sub(2)], None);
+        check_sector_crc(data, &SectorCrcOptions::default())
+    }
 
-        (recorded, calculated)
+    /// Synthesize the elements a track formatted with the standard layout would contain, for
+    /// tracks that store only sector data. Element offsets are in bitcells, as if `sectors` had
+    /// been written in order with standard gaps and syncs, the specified GAP3, and GAP4B filling
+    /// the rest of the track up to `bitcell_ct`.
+    ///
+    /// Sectors without a data address mark are given a sector header only.
+    pub(crate) fn synthesize_elements(
+        standard: System34Standard,
+        encoding: TrackDataEncoding,
+        sectors: &[SectorMapEntry],
+        gap3: usize,
+        bitcell_ct: usize,
+    ) -> Vec<TrackElementInstance> {
+        let mut elements = Vec::new();
+        if sectors.is_empty() {
+            return elements;
+        }
+
+        let sync_len = Self::sync_len(encoding);
+
+        // Push an element of `len` bytes at the byte offset `cursor`, and advance past it.
+        fn span(
+            elements: &mut Vec<TrackElementInstance>,
+            cursor: &mut usize,
+            element: System34Element,
+            len: usize,
+            chsn: Option<DiskChsn>,
+        ) {
+            elements.push(TrackElementInstance {
+                element: TrackElement::System34(element),
+                start: *cursor * MFM_BYTE_LEN,
+                end: (*cursor + len) * MFM_BYTE_LEN,
+                chsn,
+            });
+            *cursor += len;
+        }
+
+        let mut cursor = 0;
+        if matches!(standard, System34Standard::Ibm | System34Standard::Perpendicular) {
+            span(
+                &mut elements,
+                &mut cursor,
+                System34Element::Gap4a,
+                Self::gap4a(encoding),
+                None,
+            );
+            span(&mut elements, &mut cursor, System34Element::Sync, sync_len, None);
+            let iam = System34Element::Marker(System34Marker::Iam, None);
+            span(&mut elements, &mut cursor, iam, IAM_MARKER_BYTES.len(), None);
+        }
+        let gap1 = standard.encoded_gap1(encoding);
+        span(&mut elements, &mut cursor, System34Element::Gap1, gap1, None);
+
+        for entry in sectors {
+            let (chsn, attributes) = (entry.chsn, &entry.attributes);
+            span(&mut elements, &mut cursor, System34Element::Sync, sync_len, None);
+
+            // As when scanning a bitstream track, the sector header spans from the IDAM to the
+            // DAM, or to the end of GAP2 if there is no DAM.
+            let header_start = cursor;
+            let idam = System34Element::Marker(System34Marker::Idam, None);
+            span(&mut elements, &mut cursor, idam, IDAM_MARKER_BYTES.len(), Some(chsn));
+            // Sector ID and CRC
+            cursor += 6;
+            let gap2 = standard.encoded_gap2(encoding);
+            span(&mut elements, &mut cursor, System34Element::Gap2, gap2, None);
+            if !attributes.no_dam {
+                span(&mut elements, &mut cursor, System34Element::Sync, sync_len, None);
+            }
+            elements.push(TrackElementInstance {
+                element: TrackElement::System34(System34Element::SectorHeader {
+                    chsn,
+                    address_error: attributes.address_error,
+                    data_missing: attributes.no_dam,
+                }),
+                start: header_start * MFM_BYTE_LEN,
+                end: cursor * MFM_BYTE_LEN,
+                chsn: None,
+            });
+
+            if attributes.no_dam {
+                continue;
+            }
+
+            let marker = match attributes.deleted_mark {
+                true => System34Marker::Ddam,
+                false => System34Marker::Dam,
+            };
+            elements.push(TrackElementInstance {
+                element: TrackElement::System34(System34Element::Marker(marker, None)),
+                start: cursor * MFM_BYTE_LEN,
+                end: (cursor + DAM_MARKER_BYTES.len()) * MFM_BYTE_LEN,
+                chsn: Some(chsn),
+            });
+            let data = System34Element::SectorData {
+                chsn,
+                address_error: attributes.address_error,
+                data_error: attributes.data_error,
+                deleted: attributes.deleted_mark,
+            };
+            span(&mut elements, &mut cursor, data, data.size(), Some(chsn));
+            span(&mut elements, &mut cursor, System34Element::Gap3, gap3, None);
+        }
+
+        let remaining = bitcell_ct.saturating_sub(cursor * MFM_BYTE_LEN) / MFM_BYTE_LEN;
+        if remaining > 0 {
+            span(&mut elements, &mut cursor, System34Element::Gap4b, remaining, None);
+        }
+
+        // Sort elements by start offset.
+        elements.sort_by_key(|e| e.start);
+        elements
     }
 
     pub(crate) fn build_element_map(elements: &[TrackElementInstance]) -> SourceMap {
@@ -1191,3 +1762,298 @@ impl System34Schema {
         element_map
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bitstream_codec::{
+            fm::{FmCodec, FM_ADDRESS_MARK_CLOCK},
+            EncodingVariant,
+        },
+        prelude::*,
+        test_util::formatted_disk,
+        types::DiskChsnQuery,
+    };
+
+    #[test]
+    fn test_fm_track() {
+        // A mixed disk: a standard MFM disk with an extra FM track, as written by some duplicators.
+        let mut disk = formatted_disk();
+
+        let ch = DiskCh::new(40, 0);
+        let track_idx = disk
+            .add_empty_track(
+                ch,
+                TrackDataEncoding::Fm,
+                Some(TrackDataResolution::BitStream),
+                TrackDataRate::Rate250Kbps(1.0),
+                50_000,
+                Some(false),
+            )
+            .unwrap();
+        let sectors: Vec<DiskChsn> = (1..=16).map(|s| DiskChsn::new(40, 0, s, 0)).collect();
+
+        let track = disk.track_by_idx_mut(track_idx).unwrap();
+        track
+            .format(System34Standard::Ibm, sectors.clone(), &[0xE5], 27)
+            .unwrap();
+
+        let found: Vec<DiskChsn> = track.sector_list().iter().map(|s| s.chsn).collect();
+        assert_eq!(found, sectors);
+        let analysis = track.analysis().unwrap();
+        assert!(!analysis.address_error && !analysis.data_error);
+
+        // Address marks are identified by their missing clock bits.
+        let elements = track.metadata().unwrap().elements();
+        let iam = elements
+            .iter()
+            .find(|e| {
+                matches!(
+                    e.element,
+                    TrackElement::System34(System34Element::Marker(System34Marker::Iam, _))
+                )
+            })
+            .unwrap();
+        let idam = elements
+            .iter()
+            .find(|e| {
+                matches!(
+                    e.element,
+                    TrackElement::System34(System34Element::Marker(System34Marker::Idam, _))
+                )
+            })
+            .unwrap();
+        let stream = track.stream().unwrap();
+        let mut raw = [0; 8];
+        stream.read_raw_buf(&mut raw, iam.start);
+        assert_eq!(u64::from_be_bytes(raw), IAM_MARKER_FM);
+        stream.read_raw_buf(&mut raw, idam.start);
+        assert_eq!(u64::from_be_bytes(raw), IDAM_MARKER_FM);
+
+        // A sequential marker scan finds the IAM despite its distinct clock pattern.
+        assert!(matches!(
+            System34Schema::find_next_marker(stream, 0),
+            Some((TrackMarker::System34(System34Marker::Iam), start)) if start == iam.start
+        ));
+        assert!(matches!(
+            System34Schema::find_next_marker(stream, iam.start + 1),
+            Some((TrackMarker::System34(System34Marker::Idam), start)) if start == idam.start
+        ));
+
+        let data = disk
+            .read_sector_basic(ch, DiskChsnQuery::from(sectors[2]), None)
+            .unwrap();
+        assert_eq!(data, vec![0xE5; 128]);
+
+        // MFM tracks are unaffected, and the FM track is found as a duplication mark.
+        let data = disk
+            .read_sector_basic(DiskCh::new(0, 0), DiskChsn::new(0, 0, 1, 2).into(), None)
+            .unwrap();
+        assert_eq!(data.len(), 512);
+        assert_eq!(disk.find_duplication_mark(), Some((ch, sectors[0])));
+    }
+
+    #[test]
+    fn test_fm_markers() {
+        for marker in [
+            System34Marker::Iam,
+            System34Marker::Idam,
+            System34Marker::Dam,
+            System34Marker::Ddam,
+        ] {
+            // Sync bytes have every clock bit set; the mark byte uses its own clock pattern.
+            let clock = match marker {
+                System34Marker::Iam => 0xD7,
+                _ => FM_ADDRESS_MARK_CLOCK,
+            };
+            let mut bits = FmCodec::encode(&[0; 3], false, EncodingVariant::Data);
+            let mark = marker.bytes(TrackDataEncoding::Fm)[3];
+            for i in (0..8).rev() {
+                bits.push(clock & (1 << i) != 0);
+                bits.push(mark & (1 << i) != 0);
+            }
+            let bytes: [u8; 8] = bits.to_bytes().try_into().unwrap();
+            assert_eq!(
+                u64::from_be_bytes(bytes),
+                marker.encoded(TrackDataEncoding::Fm),
+                "{:?}",
+                marker
+            );
+        }
+        assert_eq!(
+            FmCodec::encode(&[0xFE], false, EncodingVariant::AddressMark).to_bytes(),
+            [0xF5, 0x7E]
+        );
+    }
+
+    fn recovery_disk() -> DiskImage {
+        formatted_disk()
+    }
+
+    /// Return the start of the sector header element of the specified sector on track 0/0.
+    fn header_offset(disk: &DiskImage, s: u8) -> usize {
+        disk.track(DiskCh::new(0, 0))
+            .unwrap()
+            .metadata()
+            .unwrap()
+            .elements()
+            .iter()
+            .find(|e| {
+                matches!(e.element, TrackElement::System34(System34Element::SectorHeader { chsn, .. }) if chsn.s() == s)
+            })
+            .unwrap()
+            .start
+    }
+
+    /// Insert a spurious bitcell into track 0/0, dropping the last bitcell to keep the track length.
+    fn insert_bitcell(disk: &mut DiskImage, index: usize) {
+        let stream = disk.track_mut(DiskCh::new(0, 0)).unwrap().stream_mut().unwrap();
+        let bits = stream.data().clone();
+        let new_bits: BitVec = bits
+            .iter()
+            .take(index)
+            .chain(std::iter::once(false))
+            .chain(bits.iter().skip(index))
+            .take(bits.len())
+            .collect();
+        stream.replace(new_bits);
+    }
+
+    #[test]
+    fn test_recovery_resync() {
+        let mut disk = recovery_disk();
+        let ch = DiskCh::new(0, 0);
+        let original = disk
+            .read_sector_basic(ch, DiskChsn::new(0, 0, 3, 2).into(), None)
+            .unwrap();
+
+        // Slip a bitcell into the sector ID of sector 5 and the data of sector 3.
+        // Insert the last slip first, so the offset of the other is unaffected.
+        let header_slip = header_offset(&disk, 5) + mfm_offset!(6);
+        let data_slip = disk.track(ch).unwrap().sector_list()[2].data_offset.unwrap() + mfm_offset!(100);
+        insert_bitcell(&mut disk, header_slip.max(data_slip));
+        insert_bitcell(&mut disk, header_slip.min(data_slip));
+        disk.set_recovery_options(RecoveryOptions::default());
+
+        let track = disk.track(ch).unwrap();
+        assert!(track.sector_list()[2].attributes.data_error);
+        assert!(!track
+            .sector_list()
+            .iter()
+            .any(|s| s.chsn.s() == 5 && !s.attributes.address_error));
+        assert!(track.metadata().unwrap().recoveries().is_empty());
+
+        disk.set_recovery_options(RecoveryOptions {
+            resync: true,
+            ..Default::default()
+        });
+        let track = disk.track(ch).unwrap();
+        let sectors: Vec<DiskChsn> = track.sector_list().iter().map(|s| s.chsn).collect();
+        assert_eq!(sectors, (1..=9).map(|s| DiskChsn::new(0, 0, s, 2)).collect::<Vec<_>>());
+        let analysis = track.analysis().unwrap();
+        assert!(!analysis.address_error && !analysis.data_error);
+
+        let mut recoveries = track.metadata().unwrap().recoveries().to_vec();
+        recoveries.sort_by_key(|r| r.chsn.s());
+        assert_eq!(recoveries.len(), 2);
+        assert_eq!(recoveries[0].chsn, DiskChsn::new(0, 0, 3, 2));
+        assert_eq!(recoveries[0].start, track.sector_list()[2].data_offset.unwrap());
+        assert!(matches!(recoveries[0].action, RecoveryAction::Resync { .. }));
+        assert_eq!(recoveries[1].chsn, DiskChsn::new(0, 0, 5, 2));
+        assert_eq!(recoveries[1].start, header_offset(&disk, 5));
+        assert!(matches!(recoveries[1].action, RecoveryAction::Resync { .. }));
+
+        let data = disk
+            .read_sector_basic(ch, DiskChsn::new(0, 0, 3, 2).into(), None)
+            .unwrap();
+        assert_eq!(data, original);
+    }
+
+    #[test]
+    fn test_recovery_orphan_dams() {
+        let mut disk = recovery_disk();
+        let ch = DiskCh::new(0, 0);
+
+        // Overwrite the IDAMs of sectors 1 and 5.
+        for s in [1, 5] {
+            let header_start = header_offset(&disk, s);
+            let stream = disk.track_mut(ch).unwrap().stream_mut().unwrap();
+            stream.write_raw_buf(&[0; 8], header_start);
+        }
+        disk.set_recovery_options(RecoveryOptions::default());
+        let track = disk.track(ch).unwrap();
+        assert_eq!(track.sector_list().len(), 7);
+        assert_eq!(track.metadata().unwrap().orphan_data_list().len(), 2);
+
+        disk.set_recovery_options(RecoveryOptions {
+            orphan_dams: true,
+            ..Default::default()
+        });
+        let track = disk.track(ch).unwrap();
+        let metadata = track.metadata().unwrap();
+        let sectors = track.sector_list();
+        assert_eq!(
+            sectors.iter().map(|s| s.chsn).collect::<Vec<_>>(),
+            (1..=9).map(|s| DiskChsn::new(0, 0, s, 2)).collect::<Vec<_>>()
+        );
+        assert!(metadata.orphan_data_list().is_empty());
+        assert_eq!(
+            metadata.recoveries(),
+            &[
+                TrackRecovery {
+                    chsn:   DiskChsn::new(0, 0, 1, 2),
+                    start:  sectors[0].data_offset.unwrap(),
+                    action: RecoveryAction::InferredId,
+                },
+                TrackRecovery {
+                    chsn:   DiskChsn::new(0, 0, 5, 2),
+                    start:  sectors[4].data_offset.unwrap(),
+                    action: RecoveryAction::InferredId,
+                },
+            ]
+        );
+        let data = disk
+            .read_sector_basic(ch, DiskChsn::new(0, 0, 5, 2).into(), None)
+            .unwrap();
+        assert_eq!(data.len(), 512);
+    }
+
+    #[test]
+    fn test_recovery_distant_dam() {
+        let mut disk = recovery_disk();
+        let ch = DiskCh::new(1, 0);
+
+        // Write a track where the data of sector 2 follows its sector header after a long GAP2.
+        let mut data = vec![0x4E; 80];
+        for (s, gap2) in [(1, 22), (2, 60)] {
+            data.extend([0x00; 12]);
+            data.extend([0xF5, 0xF5, 0xF5, 0xFE, 1, 0, s, 2, 0xF7]);
+            data.extend(vec![0x4E; gap2]);
+            data.extend([0x00; 12]);
+            data.extend([0xF5, 0xF5, 0xF5, 0xFB]);
+            data.extend([0xE5; 512]);
+            data.extend([0xF7]);
+            data.extend([0x4E; 54]);
+        }
+        data.resize(6250, 0x4E);
+        disk.write_track(ch, &data).unwrap();
+
+        let track = disk.track(ch).unwrap();
+        assert_eq!(track.sector_list().len(), 2);
+        assert_eq!(
+            track.metadata().unwrap().recoveries(),
+            &[TrackRecovery {
+                chsn:   DiskChsn::new(1, 0, 2, 2),
+                start:  track.sector_list()[1].data_offset.unwrap(),
+                action: RecoveryAction::DistantDam { distance: 75 },
+            }]
+        );
+
+        disk.set_recovery_options(RecoveryOptions::strict());
+        let track = disk.track(ch).unwrap();
+        assert_eq!(track.sector_list().len(), 1);
+        assert!(track.analysis().unwrap().no_dam);
+        assert!(track.metadata().unwrap().recoveries().is_empty());
+    }
+}
@@ -28,7 +28,7 @@
 //! The `chs` module defines several structures for working with Cylinder-Head-Sector (CHS)
 //! addressing and sector IDs.
 
-use crate::{types::sector_layout::SectorLayout, MAXIMUM_SECTOR_SIZE};
+use crate::{types::sector_layout::SectorLayout, MAXIMUM_SECTOR_SIZE_CODE};
 use std::{cmp::Ordering, fmt::Display};
 
 /// A structure representing a query against the four components of sector header:
@@ -85,10 +85,9 @@ impl DiskChsnQuery {
     }
     /// Return the size of the 'n' parameter in bytes, or None if n is not set.
     /// The formula for calculating size from n is (128 * 2^n)
-    /// We enforce a maximum size of 8192 bytes for a single sector.
+    /// We enforce a maximum size of 16384 bytes (N=7) for a single sector.
     pub fn n_size(&self) -> Option<usize> {
-        self.n
-            .map(|n| std::cmp::min(MAXIMUM_SECTOR_SIZE, 128usize.overflowing_shl(n as u32).0))
+        self.n.map(DiskChsn::n_to_bytes)
     }
     /// Return a boolean indicating whether the specified `DiskChsn` matches the query.
     pub fn matches(&self, id_chsn: &DiskChsn) -> bool {
@@ -224,16 +223,16 @@ impl DiskChsn {
     }
     /// Return the size of the 'n' parameter in bytes.
     /// The formula for calculating size from n is (128 * 2^n)
-    /// We enforce a maximum size of 8192 bytes for a single sector.
+    /// We enforce a maximum size of 16384 bytes (N=7) for a single sector.
     #[inline]
     pub fn n_size(&self) -> usize {
-        std::cmp::min(MAXIMUM_SECTOR_SIZE, 128usize.overflowing_shl(self.n as u32).0)
+        DiskChsn::n_to_bytes(self.n)
     }
 
     /// Convert the value of the sector size field (n) into bytes.
     #[inline]
     pub fn n_to_bytes(n: u8) -> usize {
-        std::cmp::min(MAXIMUM_SECTOR_SIZE, 128usize.overflowing_shl(n as u32).0)
+        128usize << std::cmp::min(n, MAXIMUM_SECTOR_SIZE_CODE)
     }
 
     /// Convert a size in bytes into a valid sector size field value (n)
@@ -270,6 +269,11 @@ impl DiskChsn {
     pub fn set_s(&mut self, s: u8) {
         self.chs.set_s(s)
     }
+    /// Set the sector size component of a sector ID.
+    #[inline]
+    pub fn set_n(&mut self, n: u8) {
+        self.n = n;
+    }
     /// Set the CHS components of a sector ID.
     pub fn set_chs(&mut self, chs: DiskChs) {
         self.chs = chs;
@@ -295,6 +299,22 @@ impl DiskChsn {
         self.chs.to_lba(geom)
     }
 
+    /// Convert an LBA sector address into a `DiskChsn` struct, with the sector size of the
+    /// specified geometry.
+    /// Only valid for standard disk formats.
+    /// # Returns:
+    /// * `Some(DiskChsn)` representing the resulting CHSN address.
+    /// * `None` if the LBA address is invalid for the specified geometry.
+    pub fn from_lba(lba: usize, geom: &SectorLayout) -> Option<DiskChsn> {
+        DiskChs::from_lba(lba, geom).map(|chs| DiskChsn::from((chs, geom.n())))
+    }
+
+    /// Return the number of sectors between this `DiskChsn` and `other`, according to the
+    /// specified geometry. See [DiskChs::sector_count_to].
+    pub fn sector_count_to(&self, geom: &SectorLayout, other: impl Into<DiskChs>) -> Option<usize> {
+        self.chs.sector_count_to(geom, other)
+    }
+
     /// Return a new `DiskChsn` that is the next sector on the disk, according to the specified
     /// geometry.
     /// Returns None if the current `DiskChsn` represents the last sector of the specified geometry.
@@ -317,7 +337,22 @@ impl DiskChsn {
     }
 
     pub fn iter(&self, geom: SectorLayout) -> DiskChsnIterator {
-        DiskChsnIterator { geom, chs: None }
+        DiskChsnIterator {
+            geom,
+            n: geom.n(),
+            next: Some(DiskChs::new(0, 0, geom.s_off)),
+        }
+    }
+
+    /// Return a `DiskChsnIterator` that will iterate through the sectors of the specified
+    /// geometry in order, starting at this `DiskChsn`. All sectors returned have this `DiskChsn`'s
+    /// sector size. Nothing is returned if this `DiskChsn` is outside the geometry.
+    pub fn iter_from(&self, geom: SectorLayout) -> DiskChsnIterator {
+        DiskChsnIterator {
+            geom,
+            n: self.n,
+            next: self.chs.within(&geom).then_some(self.chs),
+        }
     }
 }
 
@@ -494,6 +529,27 @@ impl DiskChs {
         Some(DiskChs::from((c as u16, h as u8, s as u8)))
     }
 
+    /// Return the number of sectors between this `DiskChs` and `other`, according to the specified
+    /// geometry. This is the number of times [DiskChs::next_sector] must be called to reach
+    /// `other` from this sector.
+    /// Only valid for standard disk formats.
+    /// # Returns:
+    /// * `Some(usize)` representing the number of sectors from this sector to `other`.
+    /// * `None` if either address is outside the geometry, or `other` precedes this sector.
+    pub fn sector_count_to(&self, geom: &SectorLayout, other: impl Into<DiskChs>) -> Option<usize> {
+        let other = other.into();
+        if !self.within(geom) || !other.within(geom) {
+            return None;
+        }
+        other.to_lba(geom).checked_sub(self.to_lba(geom))
+    }
+
+    /// Return a boolean indicating whether this `DiskChs` is a valid sector address for the
+    /// specified geometry.
+    fn within(&self, geom: &SectorLayout) -> bool {
+        self.c < geom.c() && self.h < geom.h() && self.s >= geom.s_off && self.s - geom.s_off < geom.s()
+    }
+
     /// Convert a raw byte offset into a `DiskChs` struct and byte offset into the resulting sector.
     /// A reference standard disk geometry is required to calculate the address.
     /// Only valid for standard disk formats. This function is intended to assist seeking within a raw sector view.
@@ -571,7 +627,20 @@ impl DiskChs {
     /// Return a `DiskChsIterator` that will iterate through all sectors in order, interpreting the `DiskChs` as a standard disk geometry.
     /// This should only be used for standard disk formats. It will skip non-standard sectors, and may access sectors out of physical order.
     pub fn iter(&self, geom: SectorLayout) -> DiskChsIterator {
-        DiskChsIterator { geom, chs: None }
+        DiskChsIterator {
+            geom,
+            next: Some(DiskChs::new(0, 0, geom.s_off)),
+        }
+    }
+
+    /// Return a `DiskChsIterator` that will iterate through the sectors of the specified geometry
+    /// in order, starting at this `DiskChs`. Nothing is returned if this `DiskChs` is outside the
+    /// geometry.
+    pub fn iter_from(&self, geom: SectorLayout) -> DiskChsIterator {
+        DiskChsIterator {
+            geom,
+            next: self.within(&geom).then_some(*self),
+        }
     }
 }
 
@@ -748,39 +817,32 @@ impl Iterator for DiskChIterator {
 
 pub struct DiskChsIterator {
     geom: SectorLayout,
-    chs:  Option<DiskChs>,
+    next: Option<DiskChs>,
 }
 
 impl Iterator for DiskChsIterator {
     type Item = DiskChs;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(chs) = &mut self.chs {
-            *chs = chs.next_sector(&self.geom)?;
-        }
-        else {
-            self.chs = Some(DiskChs::new(0, 0, self.geom.s_off));
-        }
-        self.chs
+        let chs = self.next?;
+        self.next = chs.next_sector(&self.geom);
+        Some(chs)
     }
 }
 
 pub struct DiskChsnIterator {
     geom: SectorLayout,
-    chs:  Option<DiskChs>,
+    n:    u8,
+    next: Option<DiskChs>,
 }
 
 impl Iterator for DiskChsnIterator {
     type Item = DiskChsn;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(chs) = &mut self.chs {
-            *chs = chs.next_sector(&self.geom)?;
-        }
-        else {
-            self.chs = Some(DiskChs::new(0, 0, self.geom.s_off));
-        }
-        Some(DiskChsn::from((self.chs.unwrap(), self.geom.n())))
+        let chs = self.next?;
+        self.next = chs.next_sector(&self.geom);
+        Some(DiskChsn::from((chs, self.n)))
     }
 }
 
@@ -807,7 +869,21 @@ mod tests {
     #[test]
     fn diskchsn_n_size_enforces_maximum_size() {
         let chsn = DiskChsn::new(0, 0, 0, 7);
-        assert_eq!(chsn.n_size(), 8192);
+        assert_eq!(chsn.n_size(), 16384);
+        for n in [8, 57, 64, 255] {
+            let chsn = DiskChsn::new(0, 0, 0, n);
+            assert_eq!(chsn.n_size(), 16384);
+            assert_eq!(DiskChsnQuery::new(0, 0, 0, n).n_size(), Some(16384));
+        }
+    }
+
+    #[test]
+    fn diskchsn_n_to_bytes_covers_all_sizes() {
+        for n in 0..=7 {
+            let size = DiskChsn::n_to_bytes(n);
+            assert_eq!(size, 128 << n);
+            assert_eq!(DiskChsn::bytes_to_n(size), n);
+        }
     }
 
     #[test]
@@ -917,4 +993,50 @@ mod tests {
         let iter_ct = geom.chs_iter().count();
         assert_eq!(iter_ct, total_sectors);
     }
+
+    #[test]
+    fn diskchsn_from_lba_round_trips() {
+        let geom = StandardFormat::PcFloppy360.layout();
+        for (lba, chsn) in geom.chsn_iter().enumerate() {
+            assert_eq!(chsn.to_lba(&geom), lba);
+            assert_eq!(DiskChsn::from_lba(lba, &geom), Some(chsn));
+        }
+        assert_eq!(DiskChsn::from_lba(geom.total_sectors(), &geom), None);
+    }
+
+    #[test]
+    fn diskchs_sector_count_to_works() {
+        let geom = SectorLayout::new(40, 2, 9, 1, 512);
+        let start = DiskChs::new(0, 1, 8);
+        let end = DiskChs::new(2, 1, 5);
+
+        assert_eq!(start.sector_count_to(&geom, end), Some(49 - 16));
+        assert_eq!(start.sector_count_to(&geom, start), Some(0));
+        assert_eq!(end.sector_count_to(&geom, start), None);
+        assert_eq!(start.sector_count_to(&geom, DiskChs::new(0, 0, 0)), None);
+        assert_eq!(start.sector_count_to(&geom, DiskChs::new(40, 0, 1)), None);
+        assert_eq!(
+            DiskChsn::new(0, 1, 8, 2).sector_count_to(&geom, DiskChsn::new(2, 1, 5, 2)),
+            Some(33)
+        );
+
+        // Advancing by the sector count reaches the other sector.
+        let mut chs = start;
+        assert_eq!(chs.offset_sectors(33, &geom), Some(end));
+    }
+
+    #[test]
+    fn diskchs_iter_from_works() {
+        let geom = StandardFormat::PcFloppy360.layout();
+        let start = DiskChs::new(39, 1, 7);
+
+        let sectors: Vec<DiskChs> = start.iter_from(geom).collect();
+        assert_eq!(sectors, vec![start, DiskChs::new(39, 1, 8), DiskChs::new(39, 1, 9)]);
+        assert_eq!(DiskChs::new(40, 0, 1).iter_from(geom).count(), 0);
+
+        let chsn = DiskChsn::new(1, 0, 1, 3);
+        let sectors: Vec<DiskChsn> = chsn.iter_from(geom).take(2).collect();
+        assert_eq!(sectors, vec![chsn, DiskChsn::new(1, 0, 2, 3)]);
+        assert_eq!(chsn.iter_from(geom).count(), geom.total_sectors() - chsn.to_lba(&geom));
+    }
 }
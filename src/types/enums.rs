@@ -60,6 +60,40 @@ pub enum TrackDataResolution {
     FluxStream = 2,
 }
 
+/// Whether a track in a disk image has been formatted.
+///
+/// A track with no sectors may be unformatted, or may be a formatted track whose sectors could not
+/// be decoded. [TrackFormatting] distinguishes the two: a track is only considered unformatted if
+/// no address marks or sector headers of any kind were found on it.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrackFormatting {
+    #[default]
+    /// The track contains address marks or sectors.
+    Formatted,
+    /// The track contains no address marks or sectors. If `noise` is true, the track contains
+    /// random flux transitions, as read from a blank or degaussed disk surface. Otherwise the
+    /// track contains no flux transitions, or only a regular clock pattern.
+    Unformatted { noise: bool },
+}
+
+impl TrackFormatting {
+    /// Return true if the track is unformatted.
+    pub fn is_unformatted(&self) -> bool {
+        matches!(self, TrackFormatting::Unformatted { .. })
+    }
+}
+
+impl Display for TrackFormatting {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TrackFormatting::Formatted => write!(f, "Formatted"),
+            TrackFormatting::Unformatted { noise: true } => write!(f, "Unformatted (noise)"),
+            TrackFormatting::Unformatted { noise: false } => write!(f, "Unformatted"),
+        }
+    }
+}
+
 /// The type of data encoding used by a track in a disk image.
 /// Note that some disk images may contain tracks with different encodings.
 /// fluxfox supports two types of data encodings:
@@ -398,6 +432,34 @@ impl DiskImageFileFormat {
         }
     }
 
+    /// Return the name of the parser responsible for reading and writing the disk image format.
+    pub fn parser_name(self) -> &'static str {
+        use DiskImageFileFormat::*;
+        match self {
+            RawSectorImage => "raw",
+            ImageDisk => "imd",
+            PceSectorImage => "psi",
+            PceBitstreamImage => "pri",
+            PceFluxImage => "pfi",
+            MfmBitstreamImage => "mfm",
+            #[cfg(feature = "td0")]
+            TeleDisk => "td0",
+            KryofluxStream => "kryoflux",
+            HfeImage => "hfe",
+            F86Image => "86f",
+            TransCopyImage => "tc",
+            SuperCardPro => "scp",
+            #[cfg(feature = "mfi")]
+            MameFloppyImage => "mfi",
+            #[cfg(feature = "ipf")]
+            IpfImage => "ipf",
+            #[cfg(feature = "moof")]
+            MoofImage => "moof",
+            #[cfg(feature = "woz")]
+            WozImage => "woz",
+        }
+    }
+
     pub fn resolution(self) -> TrackDataResolution {
         use DiskImageFileFormat::*;
         match self {
@@ -485,6 +547,124 @@ pub enum RwScope {
     CrcOnly,
 }
 
+/// Defines how a weak or hole bit mask whose length differs from the length of its sector data is
+/// handled when adding a sector.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MaskLengthPolicy {
+    /// Reject the sector with [DiskImageError::MaskLengthMismatch](crate::DiskImageError::MaskLengthMismatch).
+    #[default]
+    Error,
+    /// Extend a short mask with zeros (unmasked bits). Longer masks are rejected.
+    Pad,
+    /// Discard the tail of a long mask. Shorter masks are rejected.
+    Truncate,
+    /// Pad or truncate the mask as needed to match the data length.
+    Fit,
+}
+
+/// Defines how weak and hole bits are resolved when reading sector data from `MetaSector`
+/// resolution tracks. Set with [DiskImage::set_weak_bit_policy](crate::DiskImage::set_weak_bit_policy).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum WeakBitPolicy {
+    /// Resolve weak bits with the system random number generator. Reads are not reproducible.
+    #[default]
+    Random,
+    /// Resolve weak bits with a pseudo-random number generator initialized with the given seed.
+    /// The same sequence of reads will return the same data each time the policy is set.
+    Seeded(u64),
+    /// Weak bits always read as 1.
+    Ones,
+    /// Weak bits always read as 0.
+    Zeros,
+    /// Weak bits read as 0 on the first revolution read after the policy is set, and alternate
+    /// between 1 and 0 on each subsequent revolution. Reads of sectors that follow the last sector
+    /// read on the same track are on the same revolution; any other read starts a new one.
+    Alternating,
+}
+
+/// Defines how a sector read is handled when the caller requests a sector size (N) that differs
+/// from the size recorded in the sector's ID. Set with
+/// [DiskImage::set_size_mismatch_policy](crate::DiskImage::set_size_mismatch_policy).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SizeMismatchPolicy {
+    /// The requested N only sets the size of the read buffer. Bitstream tracks fill the buffer
+    /// from the track data, while `MetaSector` tracks return the stored sector data. The data range
+    /// and CRC status describe the stored sector.
+    #[default]
+    Unchecked,
+    /// Report the sector as not found.
+    NotFound,
+    /// Read only as much sector data as both sizes allow - the smaller of the requested and stored
+    /// sizes - and signal a data CRC error.
+    Truncate,
+    /// Emulate a floppy disk controller, which reads exactly the requested number of bytes and
+    /// checks the CRC at the end of them. Short reads return the start of the sector data. Long
+    /// reads continue past the sector data - into the following track data on bitstream tracks, or
+    /// into the data CRC followed by gap bytes on `MetaSector` tracks. The data CRC status is that
+    /// of the CRC check at the requested length, which almost always fails.
+    Controller,
+}
+
+/// Defines when the flux data of `FluxStream` resolution tracks is decoded. Set with
+/// [ParserReadOptions::with_decode_policy](crate::file_parsers::ParserReadOptions::with_decode_policy)
+/// when loading an image with [DiskImage::load_with_options](crate::DiskImage::load_with_options).
+///
+/// Decoding flux is by far the most expensive part of loading a flux image. A lazy policy loads
+/// only the raw flux of most tracks, and decodes each track the first time it is accessed. This
+/// shortens the time to the first sector read and saves the memory of the decoded revolutions of
+/// tracks that are never read. The first track is always decoded at load, as it is needed to
+/// detect the format of the disk.
+///
+/// Lazy decoding is only supported by flux formats that add their tracks in a batch, currently
+/// SCP and KryoFlux; other formats always decode eagerly. Until every track is decoded, the
+/// disk analysis covers only the decoded tracks. Use
+/// [DiskImage::decode_all_tracks](crate::DiskImage::decode_all_tracks) to decode the remaining
+/// tracks and update the analysis.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DecodePolicy {
+    /// Decode every track when the image is loaded.
+    #[default]
+    Eager,
+    /// Decode each track when it is first accessed.
+    Lazy,
+    /// Decode each track when it is first accessed, and decode the given number of following
+    /// tracks ahead of access. The following tracks are decoded in the background if the `rayon`
+    /// feature is enabled (except on wasm32). The first track and the tracks following it are
+    /// decoded at load.
+    LazyPrefetch(usize),
+}
+
+impl DecodePolicy {
+    /// Return the number of tracks decoded when an image is loaded.
+    pub(crate) fn load_ct(&self) -> usize {
+        match self {
+            DecodePolicy::Eager => usize::MAX,
+            DecodePolicy::Lazy => 1,
+            DecodePolicy::LazyPrefetch(n) => n.saturating_add(1),
+        }
+    }
+
+    /// Return the number of tracks following an accessed track to decode ahead of access.
+    pub(crate) fn prefetch_ct(&self) -> usize {
+        match self {
+            DecodePolicy::LazyPrefetch(n) => *n,
+            _ => 0,
+        }
+    }
+}
+
+/// Defines which representations of track data are kept when a disk image is compacted with
+/// [DiskImage::compact](crate::DiskImage::compact).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RetentionPolicy {
+    /// Keep the flux of every revolution of `FluxStream` resolution tracks, but drop the
+    /// bitstreams decoded from every revolution other than the best one.
+    BestRevolution,
+    /// Replace each `FluxStream` resolution track with the `BitStream` resolution track decoded
+    /// from its best revolution, discarding the flux. Tracks with no decoded revolution are kept.
+    BitStream,
+}
+
 /// An enum that encompasses data integrity verification strategies.
 /// Some track schemas may use a CRC to verify the integrity of the data on a track, others may
 /// use a checksum.  Other types can be added here as needed as support for new track schemas is
@@ -520,4 +700,20 @@ impl IntegrityCheck {
     pub fn is_error(&self) -> bool {
         !self.is_valid()
     }
+
+    /// Return the CRC or checksum value recorded in the data, if present.
+    pub fn recorded(&self) -> Option<u16> {
+        use IntegrityCheck::*;
+        match self {
+            Crc16(result) | Checksum16(result) => result.recorded,
+        }
+    }
+
+    /// Return the CRC or checksum value calculated over the data.
+    pub fn calculated(&self) -> u16 {
+        use IntegrityCheck::*;
+        match self {
+            Crc16(result) | Checksum16(result) => result.calculated,
+        }
+    }
 }
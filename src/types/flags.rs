@@ -45,3 +45,29 @@ bitflags! {
         const PROLOK        = 0b0000_0000_0000_0100;
     }
 }
+
+bitflags! {
+    /// Bit flags describing irregularities in a source image file that a parser worked around
+    /// while loading it. See [SourceFormatInfo](crate::types::SourceFormatInfo).
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[rustfmt::skip]
+    pub struct SourceQuirks: u32 {
+        #[doc = "A header or comment block CRC did not match and was ignored"]
+        const BAD_HEADER_CRC          = 0b0000_0000_0000_0001;
+        #[doc = "An out-of-range data rate was replaced with a default"]
+        const DEFAULT_DATA_RATE       = 0b0000_0000_0000_0010;
+        #[doc = "The disk type in the header was unknown or unsupported"]
+        const UNKNOWN_DISK_TYPE       = 0b0000_0000_0000_0100;
+        #[doc = "The track offset table was shorter than specified and was truncated"]
+        const TRUNCATED_TRACK_TABLE   = 0b0000_0000_0000_1000;
+        #[doc = "Track data was not a multiple of the format's block size"]
+        const UNALIGNED_TRACK_DATA    = 0b0000_0000_0001_0000;
+        #[doc = "Sector data length did not match the size given in the sector header"]
+        const SECTOR_SIZE_MISMATCH    = 0b0000_0000_0010_0000;
+        #[doc = "A sector header was repeated without intervening sector data"]
+        const DUPLICATE_SECTOR_HEADER = 0b0000_0000_0100_0000;
+        #[doc = "Chunks of unknown type were skipped"]
+        const UNKNOWN_CHUNKS          = 0b0000_0000_1000_0000;
+    }
+}
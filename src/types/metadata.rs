@@ -0,0 +1,186 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+
+//! Descriptive metadata for disk images.
+//!
+//! A [DiskImage](crate::DiskImage) stores metadata as a key/value store. The keys defined in this
+//! module hold descriptive metadata about the image - its title, a free-form comment, and who
+//! dumped it, when and with what - that may be edited as a whole as a [DiskImageMetadata].
+//!
+//! Most image formats can store a single text comment at most. [DiskImageMetadata::to_comment]
+//! folds all fields into one comment for these formats, and [DiskImageMetadata::from_comment]
+//! recovers them again when the image is loaded.
+
+/// The metadata key for the title of the disk image.
+pub const METADATA_KEY_TITLE: &str = "title";
+/// The metadata key for a free-form comment describing the disk image.
+pub const METADATA_KEY_COMMENT: &str = "comment";
+/// The metadata key for the date the disk image was dumped.
+pub const METADATA_KEY_DUMP_DATE: &str = "dump_date";
+/// The metadata key for the person or group that dumped the disk image.
+pub const METADATA_KEY_DUMPER: &str = "dumper";
+/// The metadata key for the hardware used to dump the disk image.
+pub const METADATA_KEY_SOURCE_HARDWARE: &str = "source_hardware";
+
+// Labels for the fields folded into a comment, in the order they are written.
+const COMMENT_LABELS: [(&str, &str); 4] = [
+    ("Title", METADATA_KEY_TITLE),
+    ("Dump Date", METADATA_KEY_DUMP_DATE),
+    ("Dumper", METADATA_KEY_DUMPER),
+    ("Source Hardware", METADATA_KEY_SOURCE_HARDWARE),
+];
+
+/// A [DiskImageMetadata] structure holds the descriptive metadata of a disk image.
+/// Retrieve it with [DiskImage::image_metadata](crate::DiskImage::image_metadata) and apply it
+/// with [DiskImage::set_image_metadata](crate::DiskImage::set_image_metadata).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiskImageMetadata {
+    /// The title of the disk image, typically the name of the software it contains.
+    pub title: Option<String>,
+    /// A free-form comment describing the disk image. May span multiple lines.
+    pub comment: Option<String>,
+    /// The date the disk image was dumped. No particular format is enforced.
+    pub dump_date: Option<String>,
+    /// The person or group that dumped the disk image.
+    pub dumper: Option<String>,
+    /// The hardware used to dump the disk image, such as the drive and controller.
+    pub source_hardware: Option<String>,
+}
+
+impl DiskImageMetadata {
+    /// Return the value of the field stored under the specified metadata key, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        match key {
+            METADATA_KEY_TITLE => self.title.as_deref(),
+            METADATA_KEY_COMMENT => self.comment.as_deref(),
+            METADATA_KEY_DUMP_DATE => self.dump_date.as_deref(),
+            METADATA_KEY_DUMPER => self.dumper.as_deref(),
+            METADATA_KEY_SOURCE_HARDWARE => self.source_hardware.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Set the field stored under the specified metadata key. Empty values clear the field.
+    /// Keys that do not name a field are ignored.
+    pub fn set(&mut self, key: &str, value: Option<&str>) {
+        let field = match key {
+            METADATA_KEY_TITLE => &mut self.title,
+            METADATA_KEY_COMMENT => &mut self.comment,
+            METADATA_KEY_DUMP_DATE => &mut self.dump_date,
+            METADATA_KEY_DUMPER => &mut self.dumper,
+            METADATA_KEY_SOURCE_HARDWARE => &mut self.source_hardware,
+            _ => return,
+        };
+        *field = value.filter(|v| !v.is_empty()).map(str::to_string);
+    }
+
+    /// Return the metadata keys of all fields, in the order they are displayed.
+    pub fn keys() -> [&'static str; 5] {
+        [
+            METADATA_KEY_TITLE,
+            METADATA_KEY_DUMP_DATE,
+            METADATA_KEY_DUMPER,
+            METADATA_KEY_SOURCE_HARDWARE,
+            METADATA_KEY_COMMENT,
+        ]
+    }
+
+    /// Return true if no fields are set.
+    pub fn is_empty(&self) -> bool {
+        Self::keys().iter().all(|key| self.get(key).is_none())
+    }
+
+    /// Fold all fields into a single comment, for image formats that can store only one text
+    /// comment. Each field other than the comment is written as a `Label: value` line, followed
+    /// by the comment itself. Returns None if no fields are set.
+    pub fn to_comment(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut lines: Vec<String> = COMMENT_LABELS
+            .iter()
+            .filter_map(|(label, key)| self.get(key).map(|value| format!("{}: {}", label, value)))
+            .collect();
+        if let Some(comment) = &self.comment {
+            lines.push(comment.clone());
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Recover the fields of a comment produced by [DiskImageMetadata::to_comment]. Leading
+    /// `Label: value` lines with a recognized label set the corresponding field; the remainder of
+    /// the text becomes the comment. A comment from any other source is kept as-is.
+    pub fn from_comment(text: &str) -> Self {
+        let mut metadata = Self::default();
+        let mut rest = text;
+        loop {
+            let (line, remainder) = rest.split_once('\n').unwrap_or((rest, ""));
+            let field = line.split_once(": ").and_then(|(label, value)| {
+                COMMENT_LABELS
+                    .iter()
+                    .find(|(l, key)| *l == label && metadata.get(key).is_none())
+                    .map(|(_, key)| (*key, value))
+            });
+            let Some((key, value)) = field
+            else {
+                break;
+            };
+            metadata.set(key, Some(value.trim_end()));
+            rest = remainder;
+        }
+        metadata.set(METADATA_KEY_COMMENT, Some(rest.trim_end_matches(['\n', '\0'])));
+        metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_round_trip() {
+        let metadata = DiskImageMetadata {
+            title: Some("Demo Disk".to_string()),
+            comment: Some("Original master.\nWrite protected.".to_string()),
+            dump_date: Some("2024-05-01".to_string()),
+            dumper: None,
+            source_hardware: Some("Greaseweazle F7".to_string()),
+        };
+        let comment = metadata.to_comment().unwrap();
+        assert!(comment.starts_with("Title: Demo Disk\nDump Date: 2024-05-01\n"));
+        assert_eq!(DiskImageMetadata::from_comment(&comment), metadata);
+
+        // Comments without labels are kept whole.
+        let plain = DiskImageMetadata::from_comment("Dumped from: my collection\n");
+        assert_eq!(plain.comment.as_deref(), Some("Dumped from: my collection"));
+        assert_eq!(plain.title, None);
+
+        assert_eq!(DiskImageMetadata::default().to_comment(), None);
+        assert!(DiskImageMetadata::from_comment("").is_empty());
+    }
+}
@@ -28,8 +28,10 @@
 pub mod chs;
 pub mod enums;
 pub mod flags;
+pub mod metadata;
 pub mod rpm;
 pub mod sector_layout;
+pub mod source_format;
 pub mod standard_format;
 pub mod structs;
 
@@ -37,6 +39,8 @@ pub mod structs;
 pub use chs::*;
 pub use enums::*;
 pub use flags::*;
+pub use metadata::*;
 pub use rpm::*;
+pub use source_format::*;
 pub use standard_format::*;
 pub use structs::*;
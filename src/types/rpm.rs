@@ -141,6 +141,15 @@ impl DiskRpm {
         }
     }
 
+    /// Return the rotation rate in RPM for the track at `ch`, taking the zone of the track into
+    /// account for a Zoned rotation rate.
+    pub fn rpm_at(&self, ch: DiskCh) -> f64 {
+        match self {
+            DiskRpm::Zoned(map, f) => map.calculate(ch) as f64 * f,
+            _ => f64::from(*self),
+        }
+    }
+
     /// Convert a [DiskRpm] to an index time in milliseconds.
     pub fn index_time_ms(&self) -> f64 {
         60.0 / f64::from(*self)
@@ -0,0 +1,129 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+
+//! Provenance information describing the image file a disk image was loaded from.
+//!
+//! When a parser loads a disk image, it records a [SourceFormatInfo] on the
+//! [DiskImage](crate::DiskImage) describing the file format, the version or variant of that
+//! format, and any [SourceQuirks] it had to work around to load the file. This is intended for
+//! display in user interfaces and for inclusion in reports.
+
+use crate::types::{flags::SourceQuirks, DiskImageFileFormat};
+use std::fmt::{self, Display, Formatter};
+
+// Descriptions of each quirk flag, in flag order.
+const QUIRK_DESCRIPTIONS: [(SourceQuirks, &str); 8] = [
+    (SourceQuirks::BAD_HEADER_CRC, "header or comment CRC mismatch"),
+    (
+        SourceQuirks::DEFAULT_DATA_RATE,
+        "invalid data rate replaced with a default",
+    ),
+    (SourceQuirks::UNKNOWN_DISK_TYPE, "unknown or unsupported disk type"),
+    (SourceQuirks::TRUNCATED_TRACK_TABLE, "track table truncated"),
+    (
+        SourceQuirks::UNALIGNED_TRACK_DATA,
+        "track data not aligned to block size",
+    ),
+    (
+        SourceQuirks::SECTOR_SIZE_MISMATCH,
+        "sector data size differs from sector header",
+    ),
+    (SourceQuirks::DUPLICATE_SECTOR_HEADER, "duplicate sector headers"),
+    (SourceQuirks::UNKNOWN_CHUNKS, "unknown chunks ignored"),
+];
+
+/// A [SourceFormatInfo] structure describes the image file a disk image was loaded from.
+/// Retrieve it with [DiskImage::source_format_info](crate::DiskImage::source_format_info).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceFormatInfo {
+    /// The file format of the source image.
+    pub format:  DiskImageFileFormat,
+    /// The version of the file format, if the format is versioned and the version is known.
+    pub version: Option<String>,
+    /// A description of the variant of the file format, or notable header flags, if any.
+    pub variant: Option<String>,
+    /// Irregularities in the source file that the parser worked around during loading.
+    pub quirks:  SourceQuirks,
+}
+
+impl SourceFormatInfo {
+    /// Create a new [SourceFormatInfo] for the specified format, with no version, variant or quirks.
+    pub fn new(format: DiskImageFileFormat) -> Self {
+        Self {
+            format,
+            version: None,
+            variant: None,
+            quirks: SourceQuirks::empty(),
+        }
+    }
+
+    /// Return the name of the parser that loaded the source image.
+    pub fn parser_name(&self) -> &'static str {
+        self.format.parser_name()
+    }
+
+    /// Return an iterator over descriptions of each quirk applied when loading the source image.
+    pub fn quirk_descriptions(&self) -> impl Iterator<Item = &'static str> + '_ {
+        QUIRK_DESCRIPTIONS
+            .iter()
+            .filter_map(|&(quirk, desc)| self.quirks.contains(quirk).then_some(desc))
+    }
+}
+
+impl Display for SourceFormatInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format)?;
+        if let Some(version) = &self.version {
+            write!(f, " v{}", version)?;
+        }
+        if let Some(variant) = &self.variant {
+            write!(f, " ({})", variant)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_format_info_display() {
+        let mut info = SourceFormatInfo::new(DiskImageFileFormat::SuperCardPro);
+        assert_eq!(info.to_string(), "SuperCard Pro Flux");
+        assert_eq!(info.parser_name(), "scp");
+
+        info.version = Some("2.4".to_string());
+        info.variant = Some("index aligned".to_string());
+        info.quirks = SourceQuirks::TRUNCATED_TRACK_TABLE | SourceQuirks::UNKNOWN_DISK_TYPE;
+        assert_eq!(info.to_string(), "SuperCard Pro Flux v2.4 (index aligned)");
+
+        let quirks: Vec<&str> = info.quirk_descriptions().collect();
+        assert_eq!(quirks, ["unknown or unsupported disk type", "track table truncated"]);
+    }
+}
@@ -32,11 +32,23 @@ use crate::{
     file_parsers::FormatCaps,
     platform::Platform,
     prelude::{DiskCh, DiskChsn},
+    random::WeakBitGenerator,
     track::TrackAnalysis,
     track_schema::TrackSchema,
-    types::{DiskRpm, IntegrityCheck, TrackDataEncoding, TrackDataRate, TrackDensity},
+    types::{
+        DecodePolicy,
+        DiskRpm,
+        IntegrityCheck,
+        MaskLengthPolicy,
+        SizeMismatchPolicy,
+        TrackDataEncoding,
+        TrackDataRate,
+        TrackDensity,
+    },
+    DiskImageError,
 };
 use std::{
+    borrow::Cow,
     fmt,
     fmt::{Display, Formatter},
     ops::Range,
@@ -44,6 +56,7 @@ use std::{
 
 /// A structure that defines several flags that can apply to a sector.
 #[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SectorAttributes {
     pub address_error: bool,
     pub data_error: bool,
@@ -62,6 +75,128 @@ pub struct AddSectorParams<'a> {
     pub attributes: SectorAttributes,
     pub alternate: bool,
     pub bit_index: Option<usize>,
+    /// How to handle a `weak_mask` or `hole_mask` whose length differs from `data`.
+    pub mask_policy: MaskLengthPolicy,
+}
+
+impl AddSectorParams<'_> {
+    /// Validate the sector descriptor against the provided [SectorLimits].
+    /// # Returns
+    /// - `Ok(())` if the descriptor is sane.
+    /// - `Err(DiskImageError::InvalidSectorSize)` if the sector size code exceeds the limit.
+    /// - `Err(DiskImageError::EmptySectorData)` if no data was supplied, but the sector is not
+    ///   marked as having no data address mark.
+    /// - `Err(DiskImageError::MaskLengthMismatch)` if a weak or hole mask was supplied whose length
+    ///   differs from the length of the sector data, and `mask_policy` does not permit fitting it.
+    pub fn validate(&self, limits: &SectorLimits) -> Result<(), DiskImageError> {
+        if self.id_chsn.n() > limits.max_size_code {
+            return Err(DiskImageError::InvalidSectorSize(self.id_chsn.n()));
+        }
+        if self.data.is_empty() && !self.attributes.no_dam {
+            return Err(DiskImageError::EmptySectorData(self.id_chsn));
+        }
+        for mask in [self.weak_mask, self.hole_mask].into_iter().flatten() {
+            self.check_mask(mask)?;
+        }
+        Ok(())
+    }
+
+    /// Return a copy of `mask` adjusted to the length of the sector data according to
+    /// `mask_policy`. Padding is done with zeros, which leave the corresponding data bits unmasked.
+    /// # Returns
+    /// - `Err(DiskImageError::MaskLengthMismatch)` if the policy does not permit the adjustment.
+    pub fn fit_mask(&self, mask: &[u8]) -> Result<Vec<u8>, DiskImageError> {
+        self.check_mask(mask)?;
+        let mut fitted = mask[..mask.len().min(self.data.len())].to_vec();
+        fitted.resize(self.data.len(), 0);
+        Ok(fitted)
+    }
+
+    fn check_mask(&self, mask: &[u8]) -> Result<(), DiskImageError> {
+        let (data_len, mask_len) = (self.data.len(), mask.len());
+        match self.mask_policy {
+            _ if mask_len == data_len => Ok(()),
+            MaskLengthPolicy::Pad if mask_len < data_len => Ok(()),
+            MaskLengthPolicy::Truncate if mask_len > data_len => Ok(()),
+            MaskLengthPolicy::Fit => Ok(()),
+            _ => Err(DiskImageError::MaskLengthMismatch { data_len, mask_len }),
+        }
+    }
+}
+
+/// Limits applied when adding sectors to a `MetaSector` resolution track, to reject absurd sector
+/// descriptors produced by corrupt images. The defaults are [MAXIMUM_SECTOR_SIZE_CODE] and
+/// [MAXIMUM_SECTORS_PER_TRACK].
+///
+/// [MAXIMUM_SECTOR_SIZE_CODE]: crate::MAXIMUM_SECTOR_SIZE_CODE
+/// [MAXIMUM_SECTORS_PER_TRACK]: crate::MAXIMUM_SECTORS_PER_TRACK
+#[derive(Copy, Clone, Debug)]
+pub struct SectorLimits {
+    /// The largest sector size code (N) that will be accepted.
+    pub max_size_code: u8,
+    /// The largest number of sectors that will be accepted on a single track.
+    pub max_sectors:   usize,
+}
+
+impl Default for SectorLimits {
+    fn default() -> Self {
+        Self {
+            max_size_code: crate::MAXIMUM_SECTOR_SIZE_CODE,
+            max_sectors:   crate::MAXIMUM_SECTORS_PER_TRACK,
+        }
+    }
+}
+
+/// Recovery heuristics applied by the System34 track schema when scanning `BitStream` resolution
+/// tracks for sectors, so that partially damaged flux dumps yield more sectors. Any recovery
+/// performed is recorded in the track's metadata, see [TrackMetadata::recoveries].
+///
+/// The default enables only `distant_dams`, which matches the behavior of the parser before these
+/// options were introduced.
+///
+/// [TrackMetadata::recoveries]: crate::track_schema::TrackMetadata::recoveries
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryOptions {
+    /// Resynchronize sector headers and data that fail their CRC by skipping a single spurious
+    /// bitcell, such as one left by a PLL slip, if doing so makes the CRC valid.
+    pub resync: bool,
+    /// Pair a data address mark found further from the preceding sector header than a floppy disk
+    /// controller would search with that header. If disabled, such a header is reported as
+    /// missing its data.
+    pub distant_dams: bool,
+    /// Recover sector data whose sector header is missing or overwritten, inferring the sector ID
+    /// from the neighboring sector headers and the sector size from the data CRC.
+    pub orphan_dams: bool,
+}
+
+impl Default for RecoveryOptions {
+    fn default() -> Self {
+        Self {
+            resync: false,
+            distant_dams: true,
+            orphan_dams: false,
+        }
+    }
+}
+
+impl RecoveryOptions {
+    /// Return a `RecoveryOptions` with all recovery heuristics disabled.
+    pub fn strict() -> Self {
+        Self {
+            resync: false,
+            distant_dams: false,
+            orphan_dams: false,
+        }
+    }
+
+    /// Return a `RecoveryOptions` with all recovery heuristics enabled.
+    pub fn all() -> Self {
+        Self {
+            resync: true,
+            distant_dams: true,
+            orphan_dams: true,
+        }
+    }
 }
 
 /// A structure to uniquely identify a specific sector on a track.
@@ -78,10 +213,89 @@ pub struct SectorCursor {
     pub data_offset: Option<usize>,
 }
 
+/// An entry in the list of sectors on a track, as returned by [Track::sector_list()].
+///
+/// [Track::sector_list()]: crate::track::Track::sector_list
 #[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct SectorMapEntry {
     pub chsn: DiskChsn,
     pub attributes: SectorAttributes,
+    /// The index of this sector among the sectors on the track with the same ID, in track order.
+    pub duplicate_idx: usize,
+    /// The number of sectors on the track with the same ID, including this one.
+    pub duplicate_ct: usize,
+    /// The bit offset of the start of the sector data element, if the track has a bitstream.
+    pub data_offset: Option<usize>,
+    /// Whether this entry is an orphaned data field: a data address mark with no preceding sector
+    /// header. An orphaned data field has no ID and cannot be read, so `chsn` is not meaningful.
+    pub orphan: bool,
+}
+
+impl SectorMapEntry {
+    /// Returns `true` if other sectors on the track have the same ID as this sector.
+    pub fn is_duplicate(&self) -> bool {
+        self.duplicate_ct > 1
+    }
+
+    /// Returns `true` if this is the sector instance returned when reading this sector's ID from
+    /// the start of the track. This is the first sector with the ID, in track order.
+    pub fn is_read_target(&self) -> bool {
+        !self.orphan && self.duplicate_idx == 0
+    }
+
+    /// Set the duplicate index and count of each entry in `entries`, which must be in track
+    /// order. Orphaned data fields are ignored.
+    pub(crate) fn mark_duplicates(entries: &mut [SectorMapEntry]) {
+        for i in 0..entries.len() {
+            if entries[i].orphan {
+                continue;
+            }
+            let chsn = entries[i].chsn;
+            let same_id = |e: &&SectorMapEntry| !e.orphan && e.chsn == chsn;
+            entries[i].duplicate_idx = entries[..i].iter().filter(same_id).count();
+            entries[i].duplicate_ct = entries.iter().filter(same_id).count();
+        }
+    }
+}
+
+/// An ID address mark on a track, as returned by [TrackMetadata::id_marks()].
+///
+/// [TrackMetadata::id_marks()]: crate::track_schema::TrackMetadata::id_marks
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IdMark {
+    /// The sector ID recorded in the sector header.
+    pub chsn: DiskChsn,
+    /// Whether the sector header has a bad CRC.
+    pub address_error: bool,
+    /// The bit offset of the start of the sector header element from the index.
+    pub bit_offset: usize,
+}
+
+/// The next ID address mark to pass under the head from a given rotational position, as returned
+/// by [DiskImage::next_id_timed()].
+///
+/// Times are estimated from the bitcell offsets and the track's RPM, assuming bitcells of uniform
+/// length. For MetaSector tracks, the bitcell offsets are themselves estimated from a standard
+/// track layout.
+///
+/// [DiskImage::next_id_timed()]: crate::DiskImage::next_id_timed
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NextIdResult {
+    /// The sector ID recorded in the sector header.
+    pub chsn: DiskChsn,
+    /// Whether the sector header has a bad CRC.
+    pub address_error: bool,
+    /// The bit offset of the start of the sector header element from the index.
+    pub bit_offset: usize,
+    /// The number of bitcells from the supplied position to the address mark, wrapping past the
+    /// index if necessary.
+    pub bit_distance: usize,
+    /// The estimated time from the index to the address mark, in seconds.
+    pub time_from_index: f64,
+    /// The estimated time from the supplied position to the address mark, in seconds.
+    pub latency: f64,
 }
 
 /// A DiskConsistency structure maintains information about the consistency of a disk image.
@@ -106,6 +320,8 @@ pub struct DiskAnalysis {
     pub consistent_sector_size: Option<u8>,
     /// The track length in sectors if the disk image has consistent track lengths, otherwise None.
     pub consistent_track_length: Option<u32>,
+    /// The number of unformatted tracks in the disk image.
+    pub unformatted_tracks: usize,
 }
 
 impl DiskAnalysis {
@@ -288,6 +504,9 @@ pub struct ReadSectorResult {
     /// Whether the specified sector ID was not matched, but a sector ID with a different head
     /// specifier was found.
     pub wrong_head: bool,
+    /// Whether the sector data overlaps a hole (a physically damaged region of the disk).
+    /// Bytes within a hole read back as random data, similar to weak bits.
+    pub hole: bool,
     /// The index of the start of sector data within `read_buf`.
     pub data_range: Range<usize>,
     /// The data read for the sector, potentially including address mark and CRC bytes.
@@ -309,6 +528,7 @@ impl Default for ReadSectorResult {
             wrong_cylinder: false,
             bad_cylinder: false,
             wrong_head: false,
+            hole: false,
             data_range: 0..0,
             read_buf: Vec::new(),
         }
@@ -321,6 +541,73 @@ impl ReadSectorResult {
     }
 }
 
+/// A `ReadSectorRef` structure contains the results of a read sector operation that borrows the
+/// sector data from the track where possible, avoiding a copy of the data for each read.
+/// Returned by [Track::read_sector_ref](crate::track::Track::read_sector_ref).
+///
+/// The flags have the same meaning as those of a [ReadSectorResult].
+#[derive(Clone, Debug, Default)]
+pub struct ReadSectorRef<'a> {
+    /// The matching Sector ID as `DiskChsn`, or `None`.
+    pub id_chsn: Option<DiskChsn>,
+    /// Whether the specified Sector ID was found.
+    pub not_found: bool,
+    /// Whether the specified Sector ID was found, but no corresponding sector data was found.
+    pub no_dam: bool,
+    /// Whether the specific sector was marked deleted.
+    pub deleted_mark: bool,
+    /// Whether the specified sector had a CRC error with the sector header.
+    pub address_crc_error: bool,
+    /// Whether the specified sector had a CRC error with the sector data.
+    pub data_crc_error: bool,
+    /// Whether the specified sector ID was not matched, but a sector ID with a different cylinder
+    /// specifier was found.
+    pub wrong_cylinder: bool,
+    /// Whether the specified sector ID was not matched, but a sector ID with a bad cylinder
+    /// specifier was found.
+    pub bad_cylinder: bool,
+    /// Whether the specified sector ID was not matched, but a sector ID with a different head
+    /// specifier was found.
+    pub wrong_head: bool,
+    /// Whether the sector data overlaps a hole (a physically damaged region of the disk).
+    pub hole: bool,
+    /// The sector data, without address mark or CRC bytes. Borrowed from the track if the data
+    /// could be read without modification, otherwise owned.
+    pub data: Cow<'a, [u8]>,
+}
+
+impl ReadSectorRef<'_> {
+    /// Return true if the sector data is borrowed from the track.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.data, Cow::Borrowed(_))
+    }
+}
+
+impl From<ReadSectorResult> for ReadSectorRef<'static> {
+    /// Convert a [ReadSectorResult] into an owned `ReadSectorRef`, keeping only the sector data
+    /// within `read_buf`. The buffer is reused if it contains only sector data.
+    fn from(rsr: ReadSectorResult) -> Self {
+        let mut read_buf = rsr.read_buf;
+        if rsr.data_range.start > 0 {
+            read_buf.drain(..rsr.data_range.start.min(read_buf.len()));
+        }
+        read_buf.truncate(rsr.data_range.len());
+        ReadSectorRef {
+            id_chsn: rsr.id_chsn,
+            not_found: rsr.not_found,
+            no_dam: rsr.no_dam,
+            deleted_mark: rsr.deleted_mark,
+            address_crc_error: rsr.address_crc_error,
+            data_crc_error: rsr.data_crc_error,
+            wrong_cylinder: rsr.wrong_cylinder,
+            bad_cylinder: rsr.bad_cylinder,
+            wrong_head: rsr.wrong_head,
+            hole: rsr.hole,
+            data: Cow::Owned(read_buf),
+        }
+    }
+}
+
 /// A `ReadTrackResult` structure contains the results of a read track operation.
 #[derive(Clone)]
 pub struct ReadTrackResult {
@@ -430,4 +717,16 @@ pub(crate) struct SharedDiskContext {
     /// The number of write operations (WriteData or FormatTrack) operations performed on the disk image.
     /// This can be used to determine if the disk image has been modified since the last save.
     pub(crate) writes: u64,
+    /// Limits applied to sectors added to `MetaSector` resolution tracks.
+    pub(crate) sector_limits: SectorLimits,
+    /// Resolves weak bits when reading sectors from `MetaSector` resolution tracks.
+    pub(crate) weak_bits: WeakBitGenerator,
+    /// How reads requesting a sector size other than the stored size are handled.
+    pub(crate) size_mismatch: SizeMismatchPolicy,
+    /// Recovery heuristics applied when scanning `BitStream` resolution tracks for sectors.
+    pub(crate) recovery: RecoveryOptions,
+    /// When the flux data of `FluxStream` resolution tracks is decoded.
+    pub(crate) decode_policy: DecodePolicy,
+    /// Whether skewed double-density flux bands are normalized before decoding.
+    pub(crate) normalize_dd_bands: bool,
 }
@@ -123,6 +123,84 @@ pub fn crc_ibm_3740_byte(byte: u8, crc: u16) -> u16 {
     crc
 }
 
+/// The address mark preceding a CRC-protected sector header or data field. The address mark is
+/// covered by the CRC, but is often not part of the buffer being checked.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CrcPremark {
+    /// Nothing is prepended. The data either begins with its address mark or has none.
+    #[default]
+    None,
+    /// An MFM address mark: three 0xA1 sync bytes followed by the specified mark byte.
+    Mfm(u8),
+    /// An FM address mark: the specified mark byte alone.
+    Fm(u8),
+}
+
+/// Options for [sector_crc] and [check_sector_crc].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SectorCrcOptions {
+    /// The initial CRC value. Defaults to [CRC_CCITT_INITIAL].
+    pub initial: u16,
+    /// The address mark to include in the CRC before the data.
+    pub premark: CrcPremark,
+}
+
+impl Default for SectorCrcOptions {
+    fn default() -> Self {
+        Self {
+            initial: CRC_CCITT_INITIAL,
+            premark: CrcPremark::None,
+        }
+    }
+}
+
+impl SectorCrcOptions {
+    /// Options for a field preceded by an MFM address mark with the specified mark byte.
+    pub fn mfm(mark: u8) -> Self {
+        Self::default().with_premark(CrcPremark::Mfm(mark))
+    }
+
+    /// Options for a field preceded by an FM address mark with the specified mark byte.
+    pub fn fm(mark: u8) -> Self {
+        Self::default().with_premark(CrcPremark::Fm(mark))
+    }
+
+    pub fn with_initial(mut self, initial: u16) -> Self {
+        self.initial = initial;
+        self
+    }
+
+    pub fn with_premark(mut self, premark: CrcPremark) -> Self {
+        self.premark = premark;
+        self
+    }
+}
+
+/// Calculate the IBM System 34 CRC of a sector header or data field, as recorded after the field
+/// on disk.
+pub fn sector_crc(data: &[u8], options: &SectorCrcOptions) -> u16 {
+    let crc = match options.premark {
+        CrcPremark::None => options.initial,
+        CrcPremark::Mfm(mark) => crc_ibm_3740(&[0xA1, 0xA1, 0xA1, mark], Some(options.initial)),
+        CrcPremark::Fm(mark) => crc_ibm_3740_byte(mark, options.initial),
+    };
+    crc_ibm_3740(data, Some(crc))
+}
+
+/// Check the IBM System 34 CRC of a sector header or data field. `field` must end with the two
+/// byte, big-endian CRC recorded on disk.
+/// # Returns
+/// A tuple containing the recorded CRC and the calculated CRC. A `field` shorter than two bytes
+/// has a recorded CRC of 0.
+pub fn check_sector_crc(field: &[u8], options: &SectorCrcOptions) -> (u16, u16) {
+    let (data, recorded) = field.split_at(field.len().saturating_sub(2));
+    let recorded = match recorded {
+        [hi, lo] => u16::from_be_bytes([*hi, *lo]),
+        _ => 0,
+    };
+    (recorded, sector_crc(data, options))
+}
+
 pub fn dump_slice<W: crate::io::Write>(
     data_slice: &[u8],
     start_address: usize,
@@ -246,6 +324,37 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    #[test]
+    fn test_sector_crc() {
+        // The sector header of the first sector of a standard PC track.
+        let header = [0x00, 0x00, 0x01, 0x02];
+        let mfm = SectorCrcOptions::mfm(0xFE);
+        assert_eq!(sector_crc(&header, &mfm), 0xCA6F);
+        assert_eq!(
+            sector_crc(
+                &[0xA1, 0xA1, 0xA1, 0xFE, 0x00, 0x00, 0x01, 0x02],
+                &SectorCrcOptions::default()
+            ),
+            0xCA6F
+        );
+
+        // An FM premark starting from the CRC of the sync bytes is equivalent to an MFM premark.
+        let preset = crc_ibm_3740(&[0xA1; 3], None);
+        assert_eq!(
+            sector_crc(&header, &SectorCrcOptions::fm(0xFE).with_initial(preset)),
+            0xCA6F
+        );
+        assert_ne!(sector_crc(&header, &SectorCrcOptions::fm(0xFE)), 0xCA6F);
+
+        let mut field = header.to_vec();
+        field.extend_from_slice(&0xCA6Fu16.to_be_bytes());
+        assert_eq!(check_sector_crc(&field, &mfm), (0xCA6F, 0xCA6F));
+        field[0] = 0x01;
+        let (recorded, calculated) = check_sector_crc(&field, &mfm);
+        assert_ne!(recorded, calculated);
+        assert_eq!(check_sector_crc(&[0x12], &mfm).0, 0);
+    }
+
     #[test]
     fn test_natural_sort() {
         let mut paths = vec![
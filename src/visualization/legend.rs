@@ -0,0 +1,277 @@
+/*
+    FluxFox
+    https://github.com/dbalsom/fluxfox
+
+    Copyright 2024-2025 Daniel Balsom
+
+    Permission is hereby granted, free of charge, to any person obtaining a
+    copy of this software and associated documentation files (the “Software”),
+    to deal in the Software without restriction, including without limitation
+    the rights to use, copy, modify, merge, publish, distribute, sublicense,
+    and/or sell copies of the Software, and to permit persons to whom the
+    Software is furnished to do so, subject to the following conditions:
+
+    The above copyright notice and this permission notice shall be included in
+    all copies or substantial portions of the Software.
+
+    THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+    IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+    FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+    AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+    LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+    FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+    DEALINGS IN THE SOFTWARE.
+
+    --------------------------------------------------------------------------
+*/
+
+//! Legends and scale bars for disk visualizations.
+//!
+//! A legend is produced as a list of [VizLegendElement]s - filled rectangles, lines and text
+//! labels - positioned in the same coordinate space as the visualization they annotate. A legend
+//! does not depend on how the disk itself was rendered, so a rendering backend can compose it onto
+//! any visualization output, so that exported images are self-explanatory.
+//!
+//! A legend may include:
+//! - A color key for the track element types in a palette.
+//! - A radial scale bar along the index angle, labeling every nth cylinder ring.
+//! - An annotation of the data encoding, data rate and rotation rate of the disk.
+
+use crate::{
+    track_schema::GenericTrackElement,
+    visualization::{
+        types::{
+            color::VizColor,
+            shapes::{VizLine, VizPoint2d, VizRect},
+        },
+        CommonVizParams,
+    },
+    DiskImage,
+    DiskVisualizationError,
+    FoxHashMap,
+};
+
+/// The order in which element types are listed in a legend's color key.
+pub const LEGEND_ELEMENT_ORDER: [GenericTrackElement; 7] = [
+    GenericTrackElement::SectorData,
+    GenericTrackElement::SectorBadData,
+    GenericTrackElement::SectorDeletedData,
+    GenericTrackElement::SectorBadDeletedData,
+    GenericTrackElement::SectorHeader,
+    GenericTrackElement::SectorBadHeader,
+    GenericTrackElement::Marker,
+];
+
+/// The horizontal alignment of a [VizText] label relative to its position.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum VizTextAnchor {
+    #[default]
+    Start,
+    Middle,
+    End,
+}
+
+/// A [VizText] represents a single line of text. `pos` is the position of the text baseline.
+#[derive(Clone, Debug)]
+pub struct VizText {
+    pub pos:    VizPoint2d<f32>,
+    pub text:   String,
+    pub size:   f32,
+    pub anchor: VizTextAnchor,
+    pub color:  VizColor,
+}
+
+/// A [VizLegendElement] represents a single primitive of a legend.
+#[derive(Clone, Debug)]
+pub enum VizLegendElement {
+    /// A filled rectangle, such as a color key swatch.
+    Rect(VizRect<f32>, VizColor),
+    /// A line with the specified stroke width, such as a scale bar or tick mark.
+    Line(VizLine<f32>, f32, VizColor),
+    /// A text label.
+    Text(VizText),
+}
+
+/// Parameter struct for use with [render_legend].
+#[derive(Clone)]
+pub struct RenderLegendParams {
+    /// Which side of the disk the legend annotates.
+    pub side: u8,
+    /// The palette of element colors to list in the color key, in [LEGEND_ELEMENT_ORDER]. Element
+    /// types not in the palette are omitted. If None, no color key will be produced.
+    pub palette: Option<FoxHashMap<GenericTrackElement, VizColor>>,
+    /// Label every nth cylinder ring along a radial scale bar at the index angle. If None, no scale
+    /// bar will be produced.
+    pub track_label_interval: Option<u16>,
+    /// Annotate the data encoding, data rate and rotation rate of the disk.
+    pub data_rate: bool,
+    /// The top-left position of the color key and data rate annotation, typically in a margin
+    /// beside the disk.
+    pub key_origin: VizPoint2d<f32>,
+    /// The size of text labels. Color key swatches and line spacing are scaled to match.
+    pub font_size: f32,
+    /// The color of text labels and the scale bar.
+    pub text_color: VizColor,
+}
+
+impl Default for RenderLegendParams {
+    fn default() -> Self {
+        Self {
+            side: 0,
+            palette: None,
+            track_label_interval: Some(10),
+            data_rate: true,
+            key_origin: VizPoint2d::new(0.0, 0.0),
+            font_size: 12.0,
+            text_color: VizColor::BLACK,
+        }
+    }
+}
+
+/// Render a legend for a visualization of the specified side of a disk image.
+/// # Arguments:
+/// - `disk`: The [DiskImage] the visualization was rendered from.
+/// - `p`: A reference to the [CommonVizParams] the visualization was rendered with.
+/// - `r`: A reference to a [RenderLegendParams] object specifying the contents of the legend.
+/// # Returns:
+/// A vector of [VizLegendElement]s to be drawn on top of the visualization.
+pub fn render_legend(
+    disk: &DiskImage,
+    p: &CommonVizParams,
+    r: &RenderLegendParams,
+) -> Result<Vec<VizLegendElement>, DiskVisualizationError> {
+    let mut elements = Vec::new();
+    let line_height = r.font_size * 1.5;
+    let mut cursor = r.key_origin;
+
+    if let Some(palette) = &r.palette {
+        for element in LEGEND_ELEMENT_ORDER {
+            let Some(color) = palette.get(&element)
+            else {
+                continue;
+            };
+            let swatch = VizRect::new(cursor, VizPoint2d::new(cursor.x + r.font_size, cursor.y + r.font_size));
+            elements.push(VizLegendElement::Rect(swatch, *color));
+            elements.push(VizLegendElement::Text(VizText {
+                pos:    VizPoint2d::new(cursor.x + r.font_size * 1.5, cursor.y + r.font_size * 0.85),
+                text:   element.to_string(),
+                size:   r.font_size,
+                anchor: VizTextAnchor::Start,
+                color:  r.text_color,
+            }));
+            cursor.y += line_height;
+        }
+    }
+
+    if r.data_rate {
+        let descriptor = disk.image_format();
+        let mut annotation = format!("{} {}", descriptor.data_encoding, descriptor.data_rate);
+        if let Some(rpm) = descriptor.rpm {
+            annotation.push_str(&format!(", {}", rpm));
+        }
+        annotation.push_str(&format!(", side {}: {} tracks", r.side, disk.track_ct(r.side as usize)));
+        elements.push(VizLegendElement::Text(VizText {
+            pos:    VizPoint2d::new(cursor.x, cursor.y + r.font_size * 0.85),
+            text:   annotation,
+            size:   r.font_size,
+            anchor: VizTextAnchor::Start,
+            color:  r.text_color,
+        }));
+    }
+
+    if let Some(interval) = r.track_label_interval {
+        elements.extend(render_scale_bar(disk, p, r, interval.max(1))?);
+    }
+
+    Ok(elements)
+}
+
+/// Render a radial scale bar from the first to the last track along the index angle, with a tick
+/// mark and label at the center of every `interval`th cylinder ring.
+fn render_scale_bar(
+    disk: &DiskImage,
+    p: &CommonVizParams,
+    r: &RenderLegendParams,
+    interval: u16,
+) -> Result<Vec<VizLegendElement>, DiskVisualizationError> {
+    let tp = p.track_params(disk.track_ct(r.side as usize))?;
+    let offset = p.pos_offset.unwrap_or_default();
+    let center = VizPoint2d::new(tp.center.x + offset.x, tp.center.y + offset.y);
+    let (sin, cos) = p.index_angle.sin_cos();
+    let point = |radius: f32| VizPoint2d::new(center.x + cos * radius, center.y + sin * radius);
+    let stroke = r.font_size / 12.0;
+    let tick_len = r.font_size * 0.5;
+
+    let (first_outer, _, _) = tp.radii(0, true);
+    let (_, _, last_inner) = tp.radii(tp.num_tracks - 1, true);
+    let mut elements = vec![VizLegendElement::Line(
+        VizLine::new(point(first_outer), point(last_inner)),
+        stroke,
+        r.text_color,
+    )];
+
+    for c in (0..tp.num_tracks).step_by(interval as usize) {
+        let (_, middle, _) = tp.radii(c, true);
+        let tick = point(middle);
+        // Ticks are drawn perpendicular to the scale bar.
+        let tick_end = VizPoint2d::new(tick.x - sin * tick_len, tick.y + cos * tick_len);
+        elements.push(VizLegendElement::Line(
+            VizLine::new(tick, tick_end),
+            stroke,
+            r.text_color,
+        ));
+        elements.push(VizLegendElement::Text(VizText {
+            pos:    VizPoint2d::new(tick_end.x - sin * r.font_size, tick_end.y + cos * r.font_size),
+            text:   c.to_string(),
+            size:   r.font_size,
+            anchor: VizTextAnchor::Middle,
+            color:  r.text_color,
+        }));
+    }
+
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::formatted_disk;
+
+    #[test]
+    fn test_legend_key_and_scale_bar() {
+        let disk = formatted_disk();
+
+        let mut palette = FoxHashMap::new();
+        palette.insert(GenericTrackElement::SectorData, VizColor::WHITE);
+        palette.insert(GenericTrackElement::SectorHeader, VizColor::BLACK);
+        palette.insert(GenericTrackElement::NullElement, VizColor::BLACK);
+
+        let params = RenderLegendParams {
+            palette: Some(palette),
+            ..Default::default()
+        };
+        let elements = render_legend(&disk, &CommonVizParams::default(), &params).unwrap();
+
+        // Element types outside the legend order are not listed.
+        let swatches = elements
+            .iter()
+            .filter(|e| matches!(e, VizLegendElement::Rect(..)))
+            .count();
+        assert_eq!(swatches, 2);
+
+        // Cylinders 0, 10, 20 and 30 of a 40-track disk are labeled.
+        let labels: Vec<&str> = elements
+            .iter()
+            .filter_map(|e| match e {
+                VizLegendElement::Text(t) if t.anchor == VizTextAnchor::Middle => Some(t.text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(labels, ["0", "10", "20", "30"]);
+
+        // The data rate annotation describes the side and its track count.
+        assert!(elements
+            .iter()
+            .any(|e| matches!(e, VizLegendElement::Text(t) if t.text.ends_with("side 0: 40 tracks"))));
+    }
+}
@@ -51,6 +51,9 @@
 //! Layers are typically rendered in order with some sort of blend operation, with mask layers on
 //! top.
 //!
+//! A legend explaining the element colors, cylinder numbering and data rate of a visualization
+//! may be generated separately with [legend::render_legend] and drawn on top of any layers.
+//!
 //! ## Visualization types
 //!
 //! Two primary rendering modes are supported, `rasterization` and `vectorization`.
@@ -79,6 +82,7 @@
 //!
 
 pub mod data_segmenter;
+pub mod legend;
 #[cfg(feature = "tiny_skia")]
 pub mod pixmap_to_disk;
 pub mod prelude;
@@ -548,11 +552,91 @@ impl Default for RenderDiskHitTestParams {
 }
 
 #[derive(Default)]
+#[non_exhaustive]
 pub struct DiskHitTestResult {
     pub display_list: Option<VizElementDisplayList>,
     pub angle: f32,
     pub bit_index: usize,
     pub track: u16,
+    /// The position on the disk surface that was hit, or `None` if the point was outside the
+    /// data area.
+    pub position: Option<DiskSurfacePosition>,
+}
+
+/// A position on the surface of a visualized disk, as returned by [disk_position_at].
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct DiskSurfacePosition {
+    /// The physical cylinder and head of the track at the position.
+    pub ch: DiskCh,
+    /// The angle of the position from the index, in radians, adjusted for the turning direction.
+    pub angle: f32,
+    /// The bit offset into the track at the position.
+    pub bit_index: usize,
+}
+
+/// Map a point on a disk visualization back to the track and bit offset it depicts. This is the
+/// inverse of the mapping performed by the vectorization and rasterization functions, and is the
+/// basis of hit-testing.
+/// # Arguments:
+/// - `disk`: The [DiskImage] that was rendered.
+/// - `p`: A reference to the [CommonVizParams] object the visualization was rendered with.
+/// - `side`: The side of the disk that was rendered.
+/// - `point`: The point to map, in the coordinate space of the visualization.
+/// - `rotation`: An optional [VizRotation] to apply to `point` before mapping it, such as the
+///     inverse of a rotation applied to the displayed visualization.
+/// # Returns:
+/// `None` if the point is outside the data area of the disk, or the track at the point has no
+/// bitstream to map the angle to.
+pub fn disk_position_at(
+    disk: &DiskImage,
+    p: &CommonVizParams,
+    side: u8,
+    point: VizPoint2d<f32>,
+    rotation: Option<&VizRotation>,
+) -> Result<Option<DiskSurfacePosition>, DiskVisualizationError> {
+    let tp = p.track_params(disk.track_ct(side as usize))?;
+    let point = match rotation {
+        Some(rotation) => point.rotate(rotation),
+        None => point,
+    };
+    let (dx, dy) = (point.x - tp.center.x, point.y - tp.center.y);
+    let distance = (dx.powi(2) + dy.powi(2)).sqrt();
+    let angle = (dy.atan2(dx) + TAU) % TAU;
+
+    // Allow for a small increment to the maximum radius to make it easier to select elements
+    // on the outer edge of the disk. I call this 'coyote radius' as a reference to 'coyote time'
+    // in platformer games - the amount of time you can spend in midair after running off a cliff.
+    let coyote_radius = tp.render_track_width * 0.5;
+    if distance > (tp.max_radius + coyote_radius) || distance < tp.min_radius {
+        // Point is outside of data area.
+        return Ok(None);
+    }
+
+    // Find the track the same way it was rendered, counting whole track widths in from the
+    // outer radius - ignore track gap. A point in the coyote radius maps to cylinder 0.
+    let track_offset = (tp.total_radius - distance) / tp.total_track_width;
+    let cylinder = (track_offset.max(0.0).floor() as usize).min(tp.num_tracks - 1);
+
+    let ch = DiskCh::new(cylinder as u16, side);
+    let Some(track_len) = disk
+        .track(ch)
+        .and_then(|track| track.stream().map(|stream| stream.len()))
+        .filter(|&len| len > 0)
+    else {
+        return Ok(None);
+    };
+
+    // Undo the turning direction and index angle applied when the track was rendered, then
+    // calculate the bit index from the angle and track length.
+    let normalized_angle = p.direction.adjust_angle(angle);
+    let track_angle = (normalized_angle - p.index_angle).rem_euclid(TAU);
+    let bit_index = (((track_angle / TAU) * track_len as f32) as usize).min(track_len - 1);
+
+    Ok(Some(DiskSurfacePosition {
+        ch,
+        angle: normalized_angle,
+        bit_index,
+    }))
 }
 
 /// Determines the direction that the linear track data is mapped to the disk surface during
@@ -669,9 +753,59 @@ fn collect_error_maps(head: u8, disk_image: &DiskImage) -> Vec<&BitVec> {
         .collect()
 }
 
-fn collect_metadata(head: u8, disk_image: &DiskImage) -> Vec<&TrackMetadata> {
+/// Collect the streams of the tracks on `head` along with the metadata of the same tracks, so that
+/// both lists are indexed alike. Tracks without a stream, such as `MetaSector` tracks, are skipped.
+fn collect_streams_and_metadata(head: u8, disk_image: &DiskImage) -> (Vec<&TrackDataStream>, Vec<&TrackMetadata>) {
     disk_image.track_map[head as usize]
         .iter()
-        .filter_map(|track_i| disk_image.track_pool[*track_i].metadata())
-        .collect()
+        .filter_map(|track_i| {
+            let track = &disk_image.track_pool[*track_i];
+            Some((track.stream()?, track.metadata()?))
+        })
+        .unzip()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::formatted_disk;
+
+    #[test]
+    fn test_disk_position_round_trip() {
+        let disk = formatted_disk();
+        let track_len = disk.track(DiskCh::new(0, 0)).unwrap().stream().unwrap().len();
+
+        for direction in [TurningDirection::Clockwise, TurningDirection::CounterClockwise] {
+            let p = CommonVizParams {
+                index_angle: 1.0,
+                direction,
+                ..Default::default()
+            };
+            let tp = p.track_params(disk.track_ct(0)).unwrap();
+
+            for cylinder in [0, 17, 39] {
+                for bit_index in [10, track_len / 3, track_len - 10] {
+                    // Place the point where the renderer draws the bit: at the middle of the
+                    // track, offset from the index angle in the turning direction.
+                    let angle = (bit_index as f32 / track_len as f32) * TAU + p.index_angle;
+                    let angle = direction.adjust_angle(angle);
+                    let (_, mid_radius, _) = tp.radii(cylinder, true);
+                    let point = VizPoint2d::new(
+                        tp.center.x + mid_radius * angle.cos(),
+                        tp.center.y + mid_radius * angle.sin(),
+                    );
+
+                    let position = disk_position_at(&disk, &p, 0, point, None).unwrap().unwrap();
+                    assert_eq!(position.ch, DiskCh::new(cylinder as u16, 0));
+                    assert!(
+                        position.bit_index.abs_diff(bit_index) <= 1,
+                        "{:?}: bit {} mapped to {}",
+                        direction,
+                        bit_index,
+                        position.bit_index
+                    );
+                }
+            }
+        }
+    }
 }
@@ -29,6 +29,7 @@
 //! types and functions for visualization.
 
 pub use super::{
+    legend::*,
     types::{blend::VizBlendMode, color::VizColor, shapes::*},
     vectorize_disk::*,
     TurningDirection,
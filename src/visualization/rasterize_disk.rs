@@ -31,8 +31,7 @@ use crate::{
     track_schema::{GenericTrackElement, TrackElement},
     visualization::{
         collect_error_maps,
-        collect_metadata,
-        collect_streams,
+        collect_streams_and_metadata,
         collect_weak_masks,
         metadata,
         stream,
@@ -106,8 +105,7 @@ pub fn rasterize_track_data(
     let total_radius = width.min(height) as f32 / 2.0;
     let mut min_radius = p.min_radius_ratio * total_radius; // Scale min_radius to pixel value
 
-    let r_tracks = collect_streams(r.side, disk_image);
-    let r_metadata = collect_metadata(r.side, disk_image);
+    let (r_tracks, r_metadata) = collect_streams_and_metadata(r.side, disk_image);
 
     let track_limit = p.track_limit.unwrap_or(MAX_CYLINDER);
     let num_tracks = min(r_tracks.len(), track_limit);
@@ -372,8 +370,7 @@ pub fn rasterize_track_metadata_quadrant(
     r: &RenderTrackMetadataParams,
     rr: &RenderRasterizationParams,
 ) -> Result<(), DiskVisualizationError> {
-    let r_tracks = collect_streams(r.side, disk_image);
-    let r_metadata = collect_metadata(r.side, disk_image);
+    let (r_tracks, r_metadata) = collect_streams_and_metadata(r.side, disk_image);
 
     if r_tracks.len() != r_metadata.len() {
         return Err(DiskVisualizationError::InvalidImage);
@@ -679,7 +676,7 @@ pub fn rasterize_disk_selection(
     let r_metadata = metadata(r.ch, disk_image);
 
     let track_limit = p.track_limit.unwrap_or(MAX_CYLINDER);
-    let num_tracks = min(disk_image.tracks(r.ch.h()) as usize, track_limit);
+    let num_tracks = min(disk_image.track_ct(r.ch.h() as usize), track_limit);
     if r.ch.c() >= num_tracks as u16 {
         return Err(DiskVisualizationError::NoTracks);
     }
@@ -29,12 +29,13 @@
 //! track.
 
 use crate::{
-    track_schema::GenericTrackElement,
+    track_schema::{GenericTrackElement, TrackElementInstance},
     types::DiskCh,
     visualization::{
-        collect_metadata,
         collect_streams,
+        collect_streams_and_metadata,
         data_segmenter::DataSegmenter,
+        disk_position_at,
         metadata,
         stream,
         types::{
@@ -54,6 +55,7 @@ use crate::{
         },
         CommonVizParams,
         DiskHitTestResult,
+        InternalTrackParams,
         RenderDiskHitTestParams,
         RenderDiskSelectionParams,
         RenderGeometry,
@@ -101,8 +103,7 @@ pub fn vectorize_disk_elements(
     p: &CommonVizParams,
     r: &RenderTrackMetadataParams,
 ) -> Result<VizElementDisplayList, DiskVisualizationError> {
-    let r_tracks = collect_streams(r.side, disk_image);
-    let r_metadata = collect_metadata(r.side, disk_image);
+    let (r_tracks, r_metadata) = collect_streams_and_metadata(r.side, disk_image);
     let num_tracks = min(r_tracks.len(), p.track_limit.unwrap_or(MAX_CYLINDER));
 
     if num_tracks == 0 {
@@ -395,8 +396,7 @@ pub fn vectorize_disk_elements_by_quadrants(
     let overlap_max = (1024 + 6) * 16;
 
     // Collect streams.
-    let r_tracks = collect_streams(r.side, disk);
-    let r_metadata = collect_metadata(r.side, disk);
+    let (r_tracks, r_metadata) = collect_streams_and_metadata(r.side, disk);
 
     if r_tracks.len() != r_metadata.len() {
         return Err(DiskVisualizationError::InvalidParameter(
@@ -699,7 +699,7 @@ pub fn vectorize_disk_selection(
     let r_metadata = metadata(r.ch, disk_image);
 
     let track_limit = p.track_limit.unwrap_or(MAX_CYLINDER);
-    let num_tracks = min(disk_image.tracks(r.ch.h()) as usize, track_limit);
+    let num_tracks = min(disk_image.track_ct(r.ch.h() as usize), track_limit);
 
     if num_tracks == 0 {
         return Err(DiskVisualizationError::NoTracks);
@@ -852,120 +852,35 @@ pub fn vectorize_disk_hit_test(
     flags: VizElementFlags,
 ) -> Result<DiskHitTestResult, DiskVisualizationError> {
     let tp = p.track_params(disk.track_ct(r.side as usize))?;
-    let (x, y) = r.point.to_tuple();
-    let (dx, dy) = (x - tp.center.x, y - tp.center.y);
-    let distance = (dx.powi(2) + dy.powi(2)).sqrt();
-    let angle = (dy.atan2(dx) + TAU) % TAU;
-
-    // Allow for a small increment to the maximum radius to make it easier to select elements
-    // on the outer edge of the disk. I call this 'coyote radius' as a reference to 'coyote time'
-    // in platformer games - the amount of time you can spend in midair after running off a cliff.
-    let coyote_radius = tp.render_track_width * 0.5;
-    if distance > (tp.max_radius + coyote_radius) || distance < tp.min_radius {
-        // Hit test coordinate is outside of data area.
-        //log::trace!("Hit test at ({}, {}) is outside of data area", dx, dy);
-        return Ok(DiskHitTestResult::default());
-    }
-
-    // log::trace!(
-    //     "Hit test at ({}, {}) center ({}, {}) distance: {} angle: {}",
-    //     dx,
-    //     dy,
-    //     tp.center.x,
-    //     tp.center.y,
-    //     distance,
-    //     angle
-    // );
-
-    // Use the full render with for hit-testing - ignore track gap
-    let track_offset = (distance - tp.min_radius) / tp.render_track_width;
-    let cylinder = if distance > tp.max_radius {
-        // In coyote radius - return cylinder 0
-        0
-    }
+    let Some(position) = disk_position_at(disk, p, r.side, r.point, None)?
     else {
-        (tp.num_tracks - 1).saturating_sub(track_offset.floor() as usize)
+        return Ok(DiskHitTestResult::default());
     };
+    let cylinder = position.ch.c() as usize;
+    let bit_index = position.bit_index;
+    let normalized_angle = position.angle;
+
     // Get the track metadata for this track.
-    let track_opt = disk.track(DiskCh::new(cylinder as u16, r.side));
-    if track_opt.is_none() {
+    let Some(track) = disk.track(position.ch)
+    else {
         return Ok(DiskHitTestResult::default());
-    }
-    let track = track_opt.unwrap();
-    let track_len = track.stream().and_then(|s| Some(s.len())).unwrap();
+    };
+    let track_len = track.stream().map(|s| s.len()).unwrap_or_default();
 
-    let metadata_opt = track.metadata();
-    if metadata_opt.is_none() {
-        return Ok(DiskHitTestResult::default());
-    }
-    let r_metadata = metadata_opt.unwrap();
+    let Some(r_metadata) = track.metadata()
+    else {
+        return Ok(DiskHitTestResult {
+            position: Some(position),
+            ..DiskHitTestResult::default()
+        });
+    };
 
     // Selection can only be on one cylinder.
     let mut display_list = VizElementDisplayList::new(p.direction, r.side, 1);
 
-    let center = tp.center;
-
-    let (clip_start, _clip_end) = (0.0, TAU);
-
-    let normalized_angle = p.direction.adjust_angle(angle);
-
-    // Calculate the bit index from angle and track length.
-    let bit_index = ((normalized_angle / TAU) * track_len as f32) as usize;
-
     if let Some((ei, idx)) = r_metadata.hit_test(bit_index) {
         let generic_element = GenericTrackElement::from(ei.element);
-
-        let mut start_angle = ((ei.start as f32 / track_len as f32) * TAU) + p.index_angle;
-        let mut end_angle = ((ei.end as f32 / track_len as f32) * TAU) + p.index_angle;
-
-        // Set a flag if the element is larger than the track. This will switch to circle rendering.
-        let wrapping_element = (end_angle - start_angle) > TAU;
-
-        // Invert the angles for clockwise rotation
-        (start_angle, end_angle) = match p.direction {
-            TurningDirection::Clockwise => (start_angle, end_angle),
-            TurningDirection::CounterClockwise => (TAU - start_angle, TAU - end_angle),
-        };
-
-        // Exchange start and end if reversed
-        if start_angle > end_angle {
-            std::mem::swap(&mut start_angle, &mut end_angle);
-        }
-
-        let start_angle = start_angle.max(clip_start);
-
-        let (outer_radius, mid_radius, inner_radius) = tp.radii(cylinder, true);
-
-        // Start and end angles are now in the range 0..2π, but we can't emit cubic arcs longer
-        // than 90 degrees. We need to break up the arc into multiple sectors if it exceeds 90 degrees
-        // here.
-
-        let shape = match r.geometry {
-            RenderGeometry::Sector => VizShape::Sector(VizSector::from_angles(
-                &VizPoint2d::new(center.x, center.y),
-                RenderWinding::Clockwise,
-                start_angle,
-                end_angle,
-                inner_radius,
-                outer_radius,
-            )),
-            RenderGeometry::Arc => {
-                if wrapping_element {
-                    // If the element wraps around the track, render a full circle.
-                    // A circle is stroked on the outside, by default, so give the inner radius.
-                    VizShape::Circle(
-                        VizCircle::new(&VizPoint2d::new(center.x, center.y), inner_radius),
-                        outer_radius - inner_radius,
-                    )
-                }
-                else {
-                    VizShape::CubicArc(
-                        VizArc::from_angles(&VizPoint2d::new(center.x, center.y), mid_radius, start_angle, end_angle),
-                        outer_radius - inner_radius,
-                    )
-                }
-            }
-        };
+        let shape = element_shape(&tp, p, cylinder, ei, track_len, r.geometry);
 
         let info = VizElementInfo {
             element_type: generic_element,
@@ -985,6 +900,7 @@ pub fn vectorize_disk_hit_test(
             angle: normalized_angle,
             bit_index,
             track: cylinder as u16,
+            position: Some(position),
         });
     }
 
@@ -995,9 +911,134 @@ pub fn vectorize_disk_hit_test(
         bit_index,
         angle: normalized_angle,
         track: cylinder as u16,
+        position: Some(position),
     })
 }
 
+/// Return a [VizElementDisplayList] containing the single track element at `element_idx` in the
+/// metadata of the track at `ch`, such as an element listed by a track metadata inspector.
+/// The element is rendered with the same geometry as a hit-test selection, so that the result
+/// may be used interchangeably with [vectorize_disk_hit_test].
+/// # Arguments:
+/// - `disk`: The [DiskImage] to render.
+/// - `p`: A reference to a [CommonVizParams] object containing the parameters common to all
+///     visualization functions.
+/// - `ch`: The physical cylinder and head of the track containing the element.
+/// - `element_idx`: The index of the element within the track's metadata.
+/// - `geometry`: The type of geometry to generate for the element.
+/// - `flags`: A [VizElementFlags] object containing flags to apply to the element.
+/// # Returns:
+/// `None` if the track has no metadata or no element at `element_idx`.
+pub fn vectorize_disk_element(
+    disk: &DiskImage,
+    p: &CommonVizParams,
+    ch: DiskCh,
+    element_idx: usize,
+    geometry: RenderGeometry,
+    flags: VizElementFlags,
+) -> Result<Option<VizElementDisplayList>, DiskVisualizationError> {
+    let tp = p.track_params(disk.track_ct(ch.h() as usize))?;
+    let cylinder = ch.c() as usize;
+    if cylinder >= tp.num_tracks {
+        return Err(DiskVisualizationError::InvalidParameter(
+            "Invalid track number".to_string(),
+        ));
+    }
+
+    let Some(track) = disk.track(ch)
+    else {
+        return Ok(None);
+    };
+    let track_len = track.stream().map(|s| s.len()).unwrap_or(0);
+    let Some(ei) = track.metadata().and_then(|metadata| metadata.item(element_idx))
+    else {
+        return Ok(None);
+    };
+    if track_len == 0 {
+        return Ok(None);
+    }
+
+    let shape = element_shape(&tp, p, cylinder, ei, track_len, geometry);
+    let info = VizElementInfo {
+        element_type: GenericTrackElement::from(ei.element),
+        ch,
+        chsn: ei.chsn,
+        bit_range: Some(ei.start..ei.end),
+        element_idx: Some(element_idx),
+        sector_idx: None,
+    };
+
+    // Selection can only be on one cylinder.
+    let mut display_list = VizElementDisplayList::new(p.direction, ch.h(), 1);
+    display_list.push(0, VizElement { shape, flags, info });
+    Ok(Some(display_list))
+}
+
+/// Calculate the shape of the track element `ei` on the specified cylinder.
+fn element_shape(
+    tp: &InternalTrackParams,
+    p: &CommonVizParams,
+    cylinder: usize,
+    ei: &TrackElementInstance,
+    track_len: usize,
+    geometry: RenderGeometry,
+) -> VizShape {
+    let center = tp.center;
+    let (clip_start, _clip_end) = (0.0, TAU);
+
+    let mut start_angle = ((ei.start as f32 / track_len as f32) * TAU) + p.index_angle;
+    let mut end_angle = ((ei.end as f32 / track_len as f32) * TAU) + p.index_angle;
+
+    // Set a flag if the element is larger than the track. This will switch to circle rendering.
+    let wrapping_element = (end_angle - start_angle) > TAU;
+
+    // Invert the angles for clockwise rotation
+    (start_angle, end_angle) = match p.direction {
+        TurningDirection::Clockwise => (start_angle, end_angle),
+        TurningDirection::CounterClockwise => (TAU - start_angle, TAU - end_angle),
+    };
+
+    // Exchange start and end if reversed
+    if start_angle > end_angle {
+        std::mem::swap(&mut start_angle, &mut end_angle);
+    }
+
+    let start_angle = start_angle.max(clip_start);
+
+    let (outer_radius, mid_radius, inner_radius) = tp.radii(cylinder, true);
+
+    // Start and end angles are now in the range 0..2π, but we can't emit cubic arcs longer
+    // than 90 degrees. We need to break up the arc into multiple sectors if it exceeds 90 degrees
+    // here.
+
+    match geometry {
+        RenderGeometry::Sector => VizShape::Sector(VizSector::from_angles(
+            &VizPoint2d::new(center.x, center.y),
+            RenderWinding::Clockwise,
+            start_angle,
+            end_angle,
+            inner_radius,
+            outer_radius,
+        )),
+        RenderGeometry::Arc => {
+            if wrapping_element {
+                // If the element wraps around the track, render a full circle.
+                // A circle is stroked on the outside, by default, so give the inner radius.
+                VizShape::Circle(
+                    VizCircle::new(&VizPoint2d::new(center.x, center.y), inner_radius),
+                    outer_radius - inner_radius,
+                )
+            }
+            else {
+                VizShape::CubicArc(
+                    VizArc::from_angles(&VizPoint2d::new(center.x, center.y), mid_radius, start_angle, end_angle),
+                    outer_radius - inner_radius,
+                )
+            }
+        }
+    }
+}
+
 /// Return a [VizDataDisplayList] representing a selection on a disk image.
 /// # Arguments:
 /// - `disk_image`: The [DiskImage] to render.
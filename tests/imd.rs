@@ -32,3 +32,31 @@ fn test_imd_sector_test_360k() {
         DiskImageFileFormat::ImageDisk,
     );
 }
+
+#[test]
+fn test_imd_unavailable_sector_data() {
+    init();
+    // A single 250Kbps MFM track of two 512 byte sectors. Sector 1 is compressed, and the data of
+    // sector 2 was unavailable to the imager (data record type 0x00).
+    let mut image = b"IMD 1.18: 01/02/2024 12:34:56\r\ntest".to_vec();
+    image.push(0x1A);
+    image.extend_from_slice(&[5, 0, 0, 2, 2]);
+    image.extend_from_slice(&[1, 2]);
+    image.extend_from_slice(&[0x02, 0xE5]);
+    image.push(0x00);
+
+    let disk = DiskImage::load(&mut std::io::Cursor::new(image), None, None, None).unwrap();
+    let ch = DiskCh::new(0, 0);
+
+    let rsr = disk
+        .read_sector(ch, DiskChsnQuery::new(0, 0, 1, 2), None, None, RwScope::DataOnly, false)
+        .unwrap();
+    assert!(!rsr.no_dam);
+    assert_eq!(rsr.data(), &[0xE5; 512][..]);
+
+    let rsr = disk
+        .read_sector(ch, DiskChsnQuery::new(0, 0, 2, 2), None, None, RwScope::DataOnly, false)
+        .unwrap();
+    assert!(!rsr.not_found);
+    assert!(rsr.no_dam);
+}
@@ -1,8 +1,8 @@
 mod common;
 
 use crate::common::run_sector_test;
-use fluxfox::DiskImageFileFormat;
-use std::path::PathBuf;
+use fluxfox::{DiskImage, DiskImageFileFormat};
+use std::{io::Cursor, path::PathBuf};
 
 fn init() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -16,3 +16,14 @@ fn test_pfi_sector_test() {
         DiskImageFileFormat::PceFluxImage,
     );
 }
+
+#[test]
+fn test_pfi_metadata() {
+    init();
+    let disk_image_buf = std::fs::read(".\\tests\\images\\sector_test\\sector_test_360k.pfi").unwrap();
+    let disk = DiskImage::load(&mut Cursor::new(disk_image_buf), None, None, None).unwrap();
+
+    // The TEXT chunks of a PFI image are loaded as the image comment.
+    let comment = disk.image_metadata().comment.unwrap();
+    assert!(comment.contains("name=Greaseweazle"));
+}